@@ -0,0 +1,51 @@
+import { utils } from '@coral-xyz/anchor';
+import { PublicKey, VersionedTransactionResponse } from '@solana/web3.js';
+
+/** Prefix of Anchor's self-CPI event instructions (`EVENT_IX_TAG_LE`) */
+export const EVENT_IX_TAG = Buffer.from([0xe4, 0x45, 0xa5, 0x2e, 0x51, 0xcb, 0x9a, 0x1d]);
+
+/** A decoded event, as returned by the program's event coder */
+export interface DecodedEvent {
+    name: string;
+    data: any;
+}
+
+/**
+ * @description Decode the events a program emitted via `emit_cpi!` in a transaction
+ * @notice Only needed for programs built with the `event-cpi` feature; these events live in
+ *   inner instructions rather than logs, so they survive log truncation
+ * @param program The program (or anything with its ID and event coder)
+ * @param tx Transaction fetched with `getTransaction(..., { maxSupportedTransactionVersion: 0 })`
+ * @returns Events in emission order
+ * @example
+ * ```typescript
+ * const tx = await connection.getTransaction(signature, { maxSupportedTransactionVersion: 0 });
+ * const sent = decodeCpiEvents(program, tx!).filter((e) => e.name === 'standardMailSent');
+ * ```
+ */
+export function decodeCpiEvents(
+    program: { programId: PublicKey; coder: { events: { decode(data: string): DecodedEvent | null } } },
+    tx: VersionedTransactionResponse
+): DecodedEvent[] {
+    const keys = tx.transaction.message.getAccountKeys({
+        accountKeysFromLookups: tx.meta?.loadedAddresses,
+    });
+
+    const events: DecodedEvent[] = [];
+    for (const inner of tx.meta?.innerInstructions ?? []) {
+        for (const ix of inner.instructions) {
+            if (!keys.get(ix.programIdIndex)?.equals(program.programId)) {
+                continue;
+            }
+            const data = Buffer.from(utils.bytes.bs58.decode(ix.data));
+            if (data.length < EVENT_IX_TAG.length || !data.subarray(0, EVENT_IX_TAG.length).equals(EVENT_IX_TAG)) {
+                continue;
+            }
+            const event = program.coder.events.decode(data.subarray(EVENT_IX_TAG.length).toString('base64'));
+            if (event) {
+                events.push(event);
+            }
+        }
+    }
+    return events;
+}
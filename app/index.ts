@@ -1,3 +1,12 @@
+export * from './cpi-events';
 export * from './mail-service-client';
 export * from './mailer-client';
+export * from './message-body';
+export * from './message-id';
+export * from './message-roots';
+export * from './pow';
+export * from './prepared-batch';
+export * from './rpc-pool';
+export * from './sealed-envelope';
+export * from './solana-pay';
 export * from './types';
\ No newline at end of file
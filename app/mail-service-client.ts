@@ -1,5 +1,6 @@
 import * as anchor from '@coral-xyz/anchor';
 import { Program, AnchorProvider, BN } from '@coral-xyz/anchor';
+import { createHash } from 'crypto';
 import { 
     Connection, 
     PublicKey, 
@@ -13,7 +14,7 @@ import {
     getAssociatedTokenAddressSync
 } from '@solana/spl-token';
 import { MailService } from '../target/types/mail_service';
-import { DelegationInfo, MailServiceFees, formatUSDC } from './types';
+import { DelegationInfo, DelegationPermission, DelegatorInfo, FeeBucketInfo, FeeLedger, HandleInfo, MailServiceFees, ServiceFeeSource, formatUSDC } from './types';
 
 /**
  * @class MailServiceClient
@@ -22,7 +23,8 @@ import { DelegationInfo, MailServiceFees, formatUSDC } from './types';
  * 
  * ## Key Features:
  * - **Delegation Management**: Delegate mail handling to other addresses for 10 USDC
- * - **Delegation Rejection**: Allow delegates to reject unwanted delegations
+ * - **Delegation Consent**: Delegations activate only once the delegate accepts, and can be rejected
+ * - **Handles**: Register, renew and transfer human-readable names for 100 USDC a year
  * - **Fee Management**: Owner can update delegation fees
  * - **Fee Withdrawal**: Owner can withdraw collected fees
  * 
@@ -125,6 +127,8 @@ export class MailServiceClient {
                 .initialize(usdcMint)
                 .accounts({
                     mailService: client.mailServicePda,
+                    delegationBucket: client.getFeeBucketAddress('delegation'),
+                    handleBucket: client.getFeeBucketAddress('handle'),
                     owner: ownerKey,
                     systemProgram: SystemProgram.programId,
                 })
@@ -139,9 +143,12 @@ export class MailServiceClient {
 
     /**
      * @description Delegate mail handling to another address with USDC fee payment
+     * @notice The delegation stays pending until the delegate calls `acceptDelegation`
      * @param delegate Public key of the address to delegate to (null to clear delegation)
+     * @param expiresAt Unix timestamp after which the delegation counts as cleared (null never expires)
+     * @param permissions `DelegationPermission` flags to grant (defaults to all)
      * @returns Promise resolving to the transaction signature
-     * @throws {Error} If delegation setup fails or insufficient USDC balance
+     * @throws {Error} If delegation setup fails, expiresAt is in the past, or insufficient USDC balance
      * @example
      * ```typescript
      * // Delegate to another address (costs 10 USDC)
@@ -149,11 +156,18 @@ export class MailServiceClient {
      * const txSig = await client.delegateTo(delegateKey);
      * console.log('Delegation set, transaction:', txSig);
      * 
+     * // Give a contractor send rights for 30 days
+     * await client.delegateTo(contractorKey, Math.floor(Date.now() / 1000) + 30 * 24 * 60 * 60, DelegationPermission.SEND);
+     * 
      * // Clear delegation (no fee)
      * await client.delegateTo(null);
      * ```
      */
-    async delegateTo(delegate: PublicKey | null): Promise<string> {
+    async delegateTo(
+        delegate: PublicKey | null,
+        expiresAt: number | null = null,
+        permissions: number = DelegationPermission.ALL
+    ): Promise<string> {
         const delegator = this.provider.wallet.publicKey;
         
         // Derive delegation PDA
@@ -174,10 +188,15 @@ export class MailServiceClient {
         );
 
         return await (this.program.methods as any)
-            .delegateTo(delegate)
+            .delegateTo(delegate, expiresAt === null ? null : new BN(expiresAt), permissions)
             .accounts({
                 delegation: delegationPda,
+                delegatorRecord: this.getDelegatorRecordAddress(delegationPda),
+                previousIndex: await this.getIndexedDelegateIndex(delegationPda),
+                delegateIndex: delegate ? this.getDelegateIndexAddress(delegate) : null,
+                delegateClaim: delegate ? this.getDelegateClaimAddress(delegate) : null,
                 mailService: this.mailServicePda,
+                feeBucket: this.getFeeBucketAddress('delegation'),
                 delegator,
                 delegatorUsdcAccount: delegatorUsdc,
                 serviceUsdcAccount: serviceUsdc,
@@ -189,8 +208,35 @@ export class MailServiceClient {
     }
 
     /**
-     * @description Reject a delegation that was made to your address
+     * @description Accept a pending delegation made to your address, making you the active delegate
      * @param delegatorAddress Public key of the account that delegated to you
+     * @param perDelegate Accept your per-delegate delegation (from `addDelegate`) instead of the primary one
+     * @returns Promise resolving to the transaction signature
+     * @throws {Error} If no delegation to you is pending
+     * @example
+     * ```typescript
+     * await client.acceptDelegation(delegatorKey);
+     * ```
+     */
+    async acceptDelegation(delegatorAddress: PublicKey, perDelegate: boolean = false): Promise<string> {
+        const delegate = this.provider.wallet.publicKey;
+
+        return await (this.program.methods as any)
+            .acceptDelegation()
+            .accounts({
+                delegation: perDelegate
+                    ? this.getDelegateDelegationAddress(delegatorAddress, delegate)
+                    : this.getDelegationPDA(delegatorAddress)[0],
+                delegator: delegatorAddress,
+                delegate,
+            })
+            .rpc();
+    }
+
+    /**
+     * @description Reject a delegation that was made to your address, whether active or pending
+     * @param delegatorAddress Public key of the account that delegated to you
+     * @param perDelegate Reject your per-delegate delegation (from `addDelegate`) instead of the primary one
      * @returns Promise resolving to the transaction signature
      * @throws {Error} If you're not the current delegate or no delegation exists
      * @example
@@ -201,14 +247,13 @@ export class MailServiceClient {
      * console.log('Delegation rejected, transaction:', txSig);
      * ```
      */
-    async rejectDelegation(delegatorAddress: PublicKey): Promise<string> {
+    async rejectDelegation(delegatorAddress: PublicKey, perDelegate: boolean = false): Promise<string> {
         const rejector = this.provider.wallet.publicKey;
         
         // Derive delegation PDA for the delegator
-        const [delegationPda] = PublicKey.findProgramAddressSync(
-            [Buffer.from('delegation'), delegatorAddress.toBuffer()],
-            this.program.programId
-        );
+        const delegationPda = perDelegate
+            ? this.getDelegateDelegationAddress(delegatorAddress, rejector)
+            : this.getDelegationPDA(delegatorAddress)[0];
 
         return await (this.program.methods as any)
             .rejectDelegation()
@@ -216,10 +261,271 @@ export class MailServiceClient {
                 delegation: delegationPda,
                 delegator: delegatorAddress,
                 rejector,
+                delegatorRecord: this.getDelegatorRecordAddress(delegationPda),
+                previousIndex: await this.getIndexedDelegateIndex(delegationPda),
+            })
+            .rpc();
+    }
+
+    /**
+     * @description Close your cleared delegation account and reclaim its rent
+     * @notice Only possible once the delegation has no active or pending delegate, e.g. after `delegateTo(null)`
+     * @returns Promise resolving to the transaction signature
+     * @throws {Error} If the delegation still names a delegate
+     * @example
+     * ```typescript
+     * await client.delegateTo(null);
+     * await client.closeDelegation();
+     * ```
+     */
+    async closeDelegation(): Promise<string> {
+        const delegator = this.provider.wallet.publicKey;
+        const [delegationPda] = this.getDelegationPDA(delegator);
+        const recordPda = this.getDelegatorRecordAddress(delegationPda);
+        const recordExists = (await this.provider.connection.getAccountInfo(recordPda)) !== null;
+
+        return await (this.program.methods as any)
+            .closeDelegation()
+            .accounts({
+                delegation: delegationPda,
+                delegatorRecord: recordExists ? recordPda : null,
+                delegator,
+            })
+            .rpc();
+    }
+
+    /**
+     * @description Authorize an additional delegate alongside any others, with USDC fee payment
+     * @notice Each delegate gets its own delegation account and must accept it with `acceptDelegation(delegator, true)`
+     * @param delegate Public key of the operator to authorize
+     * @param expiresAt Unix timestamp after which the delegation counts as cleared (null never expires)
+     * @param permissions `DelegationPermission` flags to grant (defaults to all)
+     * @returns Promise resolving to the transaction signature
+     * @throws {Error} If the delegate is already added or USDC balance is insufficient
+     * @example
+     * ```typescript
+     * // Let two operators send for the organization
+     * await client.addDelegate(operatorA, null, DelegationPermission.SEND);
+     * await client.addDelegate(operatorB, null, DelegationPermission.SEND);
+     * ```
+     */
+    async addDelegate(
+        delegate: PublicKey,
+        expiresAt: number | null = null,
+        permissions: number = DelegationPermission.ALL
+    ): Promise<string> {
+        const delegator = this.provider.wallet.publicKey;
+        const delegationPda = this.getDelegateDelegationAddress(delegator, delegate);
+
+        return await (this.program.methods as any)
+            .addDelegate(delegate, expiresAt === null ? null : new BN(expiresAt), permissions)
+            .accounts({
+                delegation: delegationPda,
+                delegates: this.getDelegatesAddress(delegator),
+                delegatorRecord: this.getDelegatorRecordAddress(delegationPda),
+                delegateIndex: this.getDelegateIndexAddress(delegate),
+                delegateClaim: this.getDelegateClaimAddress(delegate),
+                mailService: this.mailServicePda,
+                feeBucket: this.getFeeBucketAddress('delegation'),
+                delegator,
+                delegatorUsdcAccount: getAssociatedTokenAddressSync(this.usdcMint, delegator),
+                serviceUsdcAccount: getAssociatedTokenAddressSync(this.usdcMint, this.mailServicePda, true),
+                tokenProgram: TOKEN_PROGRAM_ID,
+                systemProgram: SystemProgram.programId,
+            })
+            .rpc();
+    }
+
+    /**
+     * @description Revoke a delegate added with `addDelegate`, closing its delegation account
+     * @param delegate Public key of the operator to remove
+     * @returns Promise resolving to the transaction signature
+     */
+    async removeDelegate(delegate: PublicKey): Promise<string> {
+        const delegator = this.provider.wallet.publicKey;
+        const delegationPda = this.getDelegateDelegationAddress(delegator, delegate);
+
+        return await (this.program.methods as any)
+            .removeDelegate(delegate)
+            .accounts({
+                delegation: delegationPda,
+                delegates: this.getDelegatesAddress(delegator),
+                delegatorRecord: this.getDelegatorRecordAddress(delegationPda),
+                previousIndex: await this.getIndexedDelegateIndex(delegationPda),
+                delegator,
+            })
+            .rpc();
+    }
+
+    /**
+     * @description Register a handle for one year, or take over one that has expired
+     * @param name Handle of 1-32 lowercase letters, digits, '-' or '_'
+     * @returns Promise resolving to the transaction signature
+     * @throws {Error} If the handle is invalid, held by someone else, or USDC balance is insufficient
+     * @example
+     * ```typescript
+     * // Register a handle (costs 100 USDC)
+     * await client.registerHandle('alice');
+     * ```
+     */
+    async registerHandle(name: string): Promise<string> {
+        const registrant = this.provider.wallet.publicKey;
+
+        return await (this.program.methods as any)
+            .registerHandle(name)
+            .accounts({
+                handle: this.getHandleAddress(name),
+                mailService: this.mailServicePda,
+                feeBucket: this.getFeeBucketAddress('handle'),
+                registrant,
+                registrantUsdcAccount: getAssociatedTokenAddressSync(this.usdcMint, registrant),
+                serviceUsdcAccount: getAssociatedTokenAddressSync(this.usdcMint, this.mailServicePda, true),
+                tokenProgram: TOKEN_PROGRAM_ID,
+                systemProgram: SystemProgram.programId,
+            })
+            .rpc();
+    }
+
+    /**
+     * @description Extend a handle you own by one year
+     * @notice Renewing early adds the year on top of the time remaining
+     * @param name Handle to renew
+     * @returns Promise resolving to the transaction signature
+     * @throws {Error} If you don't own the handle or USDC balance is insufficient
+     */
+    async renewHandle(name: string): Promise<string> {
+        const owner = this.provider.wallet.publicKey;
+
+        return await (this.program.methods as any)
+            .renewHandle(name)
+            .accounts({
+                handle: this.getHandleAddress(name),
+                mailService: this.mailServicePda,
+                feeBucket: this.getFeeBucketAddress('handle'),
+                owner,
+                ownerUsdcAccount: getAssociatedTokenAddressSync(this.usdcMint, owner),
+                serviceUsdcAccount: getAssociatedTokenAddressSync(this.usdcMint, this.mailServicePda, true),
+                tokenProgram: TOKEN_PROGRAM_ID,
+            })
+            .rpc();
+    }
+
+    /**
+     * @description Transfer a handle you own to another wallet
+     * @param name Handle to transfer
+     * @param newOwner Wallet that will own the handle
+     * @returns Promise resolving to the transaction signature
+     * @throws {Error} If you don't own the handle or it has expired
+     */
+    async transferHandle(name: string, newOwner: PublicKey): Promise<string> {
+        return await (this.program.methods as any)
+            .transferHandle(name, newOwner)
+            .accounts({
+                handle: this.getHandleAddress(name),
+                reverseRecord: this.getReverseRecordAddress(this.provider.wallet.publicKey),
+                owner: this.provider.wallet.publicKey,
             })
             .rpc();
     }
 
+    /**
+     * @description Make a handle you own your wallet's primary handle for reverse lookups
+     * @param name Handle to point your reverse record at
+     * @returns Promise resolving to the transaction signature
+     * @throws {Error} If you don't own the handle or it has expired
+     */
+    async setPrimaryHandle(name: string): Promise<string> {
+        return await (this.program.methods as any)
+            .setPrimaryHandle(name)
+            .accounts({
+                handle: this.getHandleAddress(name),
+                reverseRecord: this.getReverseRecordAddress(this.provider.wallet.publicKey),
+                owner: this.provider.wallet.publicKey,
+                systemProgram: SystemProgram.programId,
+            })
+            .rpc();
+    }
+
+    /**
+     * @description Resolve a wallet's primary handle
+     * @notice The forward record is checked too, so a handle that lapsed or changed hands never resolves
+     * @param owner Wallet to look up
+     * @returns Promise resolving to the handle name or null if the wallet has no live primary handle
+     * @example
+     * ```typescript
+     * const name = await client.getPrimaryHandle(sender);
+     * console.log('From:', name ?? sender.toString());
+     * ```
+     */
+    async getPrimaryHandle(owner: PublicKey): Promise<string | null> {
+        let name: string;
+        try {
+            const record = await (this.program.account as any).reverseRecord.fetch(this.getReverseRecordAddress(owner));
+            name = record.name;
+        } catch {
+            return null;
+        }
+        if (!name) {
+            return null;
+        }
+
+        const handle = await this.getHandle(name);
+        return handle && !handle.isExpired && handle.owner.equals(owner) ? name : null;
+    }
+
+    /**
+     * @description Get the PDA of a wallet's reverse record
+     * @param owner Wallet the record belongs to
+     * @returns The deterministically derived reverse record address
+     */
+    getReverseRecordAddress(owner: PublicKey): PublicKey {
+        const [reversePda] = PublicKey.findProgramAddressSync(
+            [Buffer.from('reverse'), owner.toBuffer()],
+            this.program.programId
+        );
+        return reversePda;
+    }
+
+    /**
+     * @description Look up a handle's owner and expiry
+     * @param name Handle to look up
+     * @returns Promise resolving to HandleInfo or null if the handle was never registered
+     * @example
+     * ```typescript
+     * const handle = await client.getHandle('alice');
+     * if (handle && !handle.isExpired) {
+     *   console.log('alice resolves to', handle.owner.toString());
+     * }
+     * ```
+     */
+    async getHandle(name: string): Promise<HandleInfo | null> {
+        try {
+            const account = await (this.program.account as any).handle.fetch(this.getHandleAddress(name));
+            const expiresAt = account.expiresAt.toNumber();
+            return {
+                name: account.name,
+                owner: account.owner,
+                expiresAt,
+                isExpired: Date.now() / 1000 > expiresAt,
+            };
+        } catch {
+            return null;
+        }
+    }
+
+    /**
+     * @description Get the PDA of a handle, seeded by the SHA-256 of its name
+     * @param name Handle name
+     * @returns The deterministically derived handle address
+     */
+    getHandleAddress(name: string): PublicKey {
+        const [handlePda] = PublicKey.findProgramAddressSync(
+            [Buffer.from('handle'), createHash('sha256').update(name).digest()],
+            this.program.programId
+        );
+        return handlePda;
+    }
+
     /**
      * @description Update the delegation fee (owner only)
      * @param newFeeUsdc New fee amount in USDC (will be converted to 6-decimal format)
@@ -244,6 +550,185 @@ export class MailServiceClient {
             .rpc();
     }
 
+    /**
+     * @description Set the share of each delegation fee that accrues to the delegate (owner only)
+     * @param shareBps Share in basis points, 0-10000 (0 keeps the whole fee for the service)
+     * @returns Promise resolving to the transaction signature
+     * @throws {Error} If caller is not the owner or the share exceeds 10000
+     */
+    async setDelegateShare(shareBps: number): Promise<string> {
+        return await (this.program.methods as any)
+            .setDelegateShare(shareBps)
+            .accounts({
+                mailService: this.mailServicePda,
+                owner: this.provider.wallet.publicKey,
+            })
+            .rpc();
+    }
+
+    /**
+     * @description Allow or refuse program-derived addresses as delegates (owner only)
+     * @param allowed Whether PDAs, e.g. of programs sending by CPI, may be delegates
+     * @returns Promise resolving to the transaction signature
+     * @throws {Error} If caller is not the owner
+     */
+    async setAllowProgramDelegates(allowed: boolean): Promise<string> {
+        return await (this.program.methods as any)
+            .setAllowProgramDelegates(allowed)
+            .accounts({
+                mailService: this.mailServicePda,
+                owner: this.provider.wallet.publicKey,
+            })
+            .rpc();
+    }
+
+    /**
+     * @description Appoint the guardian, who can pause the service but not unpause it (owner only)
+     * @param guardian Guardian wallet, e.g. a cold key or monitoring service
+     * @returns Promise resolving to the transaction signature
+     * @throws {Error} If caller is not the owner
+     */
+    async setGuardian(guardian: PublicKey): Promise<string> {
+        return await (this.program.methods as any)
+            .setGuardian(guardian)
+            .accounts({
+                mailService: this.mailServicePda,
+                owner: this.provider.wallet.publicKey,
+            })
+            .rpc();
+    }
+
+    /**
+     * @description Pause new delegations (owner or guardian)
+     * @returns Promise resolving to the transaction signature
+     * @throws {Error} If caller is neither the owner nor the guardian
+     */
+    async pause(): Promise<string> {
+        return await (this.program.methods as any)
+            .pause()
+            .accounts({
+                mailService: this.mailServicePda,
+                authority: this.provider.wallet.publicKey,
+            })
+            .rpc();
+    }
+
+    /**
+     * @description Resume a paused service (owner only)
+     * @returns Promise resolving to the transaction signature
+     * @throws {Error} If caller is not the owner
+     */
+    async unpause(): Promise<string> {
+        return await (this.program.methods as any)
+            .unpause()
+            .accounts({
+                mailService: this.mailServicePda,
+                owner: this.provider.wallet.publicKey,
+            })
+            .rpc();
+    }
+
+    /**
+     * @description Check whether the service is paused and who its guardian is
+     * @returns Promise resolving to the pause flag and guardian
+     */
+    async getPauseState(): Promise<{ paused: boolean; guardian: PublicKey }> {
+        const serviceAccount = await (this.program.account as any).mailServiceState.fetch(this.mailServicePda);
+        return { paused: serviceAccount.paused, guardian: serviceAccount.guardian };
+    }
+
+    /**
+     * @description Withdraw your accrued share of the delegation fees paid to delegate to you
+     * @returns Promise resolving to the transaction signature
+     * @throws {Error} If nothing has accrued
+     * @example
+     * ```typescript
+     * if ((await client.getDelegateClaim(wallet.publicKey)) > 0) {
+     *   await client.claimDelegateShare();
+     * }
+     * ```
+     */
+    async claimDelegateShare(): Promise<string> {
+        const delegate = this.provider.wallet.publicKey;
+
+        return await (this.program.methods as any)
+            .claimDelegateShare()
+            .accounts({
+                delegateClaim: this.getDelegateClaimAddress(delegate),
+                mailService: this.mailServicePda,
+                delegate,
+                delegateUsdcAccount: getAssociatedTokenAddressSync(this.usdcMint, delegate),
+                serviceUsdcAccount: getAssociatedTokenAddressSync(this.usdcMint, this.mailServicePda, true),
+                tokenProgram: TOKEN_PROGRAM_ID,
+            })
+            .rpc();
+    }
+
+    /**
+     * @description Get a delegate's claimable share of delegation fees
+     * @param delegate Address of the delegate
+     * @returns Promise resolving to the claimable amount in USDC base units (0 if none)
+     */
+    async getDelegateClaim(delegate: PublicKey): Promise<number> {
+        try {
+            const account = await (this.program.account as any).delegateClaim.fetch(this.getDelegateClaimAddress(delegate));
+            return account.amount.toNumber();
+        } catch {
+            return 0;
+        }
+    }
+
+    /**
+     * @description Get the PDA holding a delegate's share of delegation fees
+     * @param delegate Address of the delegate
+     * @returns The deterministically derived claim address
+     */
+    getDelegateClaimAddress(delegate: PublicKey): PublicKey {
+        const [claimPda] = PublicKey.findProgramAddressSync(
+            [Buffer.from('delegate_claim'), delegate.toBuffer()],
+            this.program.programId
+        );
+        return claimPda;
+    }
+
+    /**
+     * @description Set the reduced fee for replacing a delegate shortly after the last paid change (owner only)
+     * @param updateFeeUsdc Reduced fee in USDC (will be converted to 6-decimal format)
+     * @param updateWindowSeconds How long after a paid delegation the reduced fee applies
+     * @returns Promise resolving to the transaction signature
+     * @throws {Error} If caller is not the owner or the window is negative
+     * @example
+     * ```typescript
+     * // Key rotations within a week cost 0.5 USDC
+     * await client.setUpdateFee(0.5, 7 * 24 * 60 * 60);
+     * ```
+     */
+    async setUpdateFee(updateFeeUsdc: number, updateWindowSeconds: number): Promise<string> {
+        return await (this.program.methods as any)
+            .setUpdateFee(new BN(updateFeeUsdc * 1_000_000), new BN(updateWindowSeconds))
+            .accounts({
+                mailService: this.mailServicePda,
+                owner: this.provider.wallet.publicKey,
+            })
+            .rpc();
+    }
+
+    /**
+     * @description Update the handle registration and renewal fee (owner only)
+     * @param newFeeUsdc New fee amount in USDC (will be converted to 6-decimal format)
+     * @returns Promise resolving to the transaction signature
+     * @throws {Error} If caller is not the owner
+     */
+    async setRegistrationFee(newFeeUsdc: number): Promise<string> {
+        return await (this.program.methods as any)
+            .setRegistrationFee(new BN(newFeeUsdc * 1_000_000))
+            .accounts({
+                mailService: this.mailServicePda,
+                owner: this.provider.wallet.publicKey,
+            })
+            .rpc();
+    }
+
     /**
      * @description Withdraw collected fees from the service to owner's account (owner only)
      * @param amountUsdc Amount to withdraw in USDC
@@ -274,6 +759,34 @@ export class MailServiceClient {
             .withdrawFees(amount)
             .accounts({
                 mailService: this.mailServicePda,
+                feeBucket: this.getFeeBucketAddress('delegation'),
+                owner,
+                serviceUsdcAccount: serviceUsdc,
+                ownerUsdcAccount: ownerUsdc,
+                tokenProgram: TOKEN_PROGRAM_ID,
+            })
+            .rpc();
+    }
+
+    /**
+     * @description Withdraw everything accrued in the delegation bucket to the owner's account (owner only)
+     * @returns Promise resolving to the transaction signature
+     * @throws {Error} If caller is not the owner or nothing has accrued
+     * @example
+     * ```typescript
+     * await client.withdrawAllFees();
+     * ```
+     */
+    async withdrawAllFees(): Promise<string> {
+        const owner = this.provider.wallet.publicKey;
+        const serviceUsdc = getAssociatedTokenAddressSync(this.usdcMint, this.mailServicePda, true);
+        const ownerUsdc = getAssociatedTokenAddressSync(this.usdcMint, owner);
+
+        return await (this.program.methods as any)
+            .withdrawAllFees()
+            .accounts({
+                mailService: this.mailServicePda,
+                feeBucket: this.getFeeBucketAddress('delegation'),
                 owner,
                 serviceUsdcAccount: serviceUsdc,
                 ownerUsdcAccount: ownerUsdc,
@@ -282,6 +795,88 @@ export class MailServiceClient {
             .rpc();
     }
 
+    /**
+     * @description Route a fee bucket to a new destination wallet (owner only)
+     * @param source Fee source whose bucket is routed
+     * @param destination Wallet that receives withdrawals from the bucket
+     * @returns Promise resolving to the transaction signature
+     * @throws {Error} If caller is not the owner
+     * @example
+     * ```typescript
+     * await client.setBucketDestination('delegation', delegationTreasury);
+     * ```
+     */
+    async setBucketDestination(source: ServiceFeeSource, destination: PublicKey): Promise<string> {
+        return await (this.program.methods as any)
+            .setBucketDestination({ [source]: {} }, destination)
+            .accounts({
+                feeBucket: this.getFeeBucketAddress(source),
+                mailService: this.mailServicePda,
+                owner: this.provider.wallet.publicKey,
+                systemProgram: SystemProgram.programId,
+            })
+            .rpc();
+    }
+
+    /**
+     * @description Withdraw everything accrued in a fee bucket to its destination (owner only)
+     * @param source Fee source whose bucket is withdrawn
+     * @returns Promise resolving to the transaction signature
+     * @throws {Error} If caller is not the owner or the bucket is empty
+     * @example
+     * ```typescript
+     * await client.withdrawBucket('delegation');
+     * ```
+     */
+    async withdrawBucket(source: ServiceFeeSource): Promise<string> {
+        const bucket = await this.getFeeBucket(source);
+        if (!bucket) {
+            throw new Error(`No ${source} fee bucket`);
+        }
+
+        return await (this.program.methods as any)
+            .withdrawBucket()
+            .accounts({
+                feeBucket: this.getFeeBucketAddress(source),
+                mailService: this.mailServicePda,
+                owner: this.provider.wallet.publicKey,
+                serviceUsdcAccount: getAssociatedTokenAddressSync(this.usdcMint, this.mailServicePda, true),
+                destinationUsdcAccount: getAssociatedTokenAddressSync(this.usdcMint, bucket.destination),
+                tokenProgram: TOKEN_PROGRAM_ID,
+            })
+            .rpc();
+    }
+
+    /**
+     * @description Get a fee bucket's destination and accrued balance
+     * @param source Fee source of the bucket
+     * @returns Promise resolving to FeeBucketInfo or null if the bucket does not exist
+     */
+    async getFeeBucket(source: ServiceFeeSource): Promise<FeeBucketInfo | null> {
+        try {
+            const account = await (this.program.account as any).feeBucket.fetch(this.getFeeBucketAddress(source));
+            return {
+                destination: account.destination,
+                claimable: account.claimable.toNumber(),
+            };
+        } catch {
+            return null;
+        }
+    }
+
+    /**
+     * @description Get the PDA of a fee bucket
+     * @param source Fee source of the bucket
+     * @returns The deterministically derived bucket address
+     */
+    getFeeBucketAddress(source: ServiceFeeSource): PublicKey {
+        const [bucketPda] = PublicKey.findProgramAddressSync(
+            [Buffer.from('bucket'), Buffer.from(source)],
+            this.program.programId
+        );
+        return bucketPda;
+    }
+
     /**
      * @description Get current delegation for a delegator address
      * @param delegatorAddress Address to check delegation for
@@ -296,17 +891,142 @@ export class MailServiceClient {
      * ```
      */
     async getDelegation(delegatorAddress: PublicKey): Promise<DelegationInfo | null> {
+        return this.fetchDelegation(this.getDelegationPDA(delegatorAddress)[0]);
+    }
+
+    /**
+     * @description Get the per-delegate delegation a delegator made to one delegate with `addDelegate`
+     * @param delegatorAddress Address of the delegator
+     * @param delegate Address of the delegate
+     * @returns Promise resolving to DelegationInfo or null if no such delegation exists
+     */
+    async getDelegateDelegation(delegatorAddress: PublicKey, delegate: PublicKey): Promise<DelegationInfo | null> {
+        return this.fetchDelegation(this.getDelegateDelegationAddress(delegatorAddress, delegate));
+    }
+
+    /**
+     * @description Get how many per-delegate delegations a delegator has open
+     * @param delegatorAddress Address of the delegator
+     * @returns Promise resolving to the count (0 if the delegator never added one)
+     */
+    async getDelegateCount(delegatorAddress: PublicKey): Promise<number> {
+        try {
+            const account = await (this.program.account as any).delegates.fetch(this.getDelegatesAddress(delegatorAddress));
+            return account.count;
+        } catch {
+            return 0;
+        }
+    }
+
+    /**
+     * @description Get the PDA of a per-delegate delegation
+     * @param delegatorAddress Address of the delegator
+     * @param delegate Address of the delegate
+     * @returns The deterministically derived delegation address
+     */
+    getDelegateDelegationAddress(delegatorAddress: PublicKey, delegate: PublicKey): PublicKey {
+        const [delegationPda] = PublicKey.findProgramAddressSync(
+            [Buffer.from('delegation'), delegatorAddress.toBuffer(), delegate.toBuffer()],
+            this.program.programId
+        );
+        return delegationPda;
+    }
+
+    /**
+     * @description Get the PDA counting a delegator's per-delegate delegations
+     * @param delegatorAddress Address of the delegator
+     * @returns The deterministically derived counter address
+     */
+    getDelegatesAddress(delegatorAddress: PublicKey): PublicKey {
+        const [delegatesPda] = PublicKey.findProgramAddressSync(
+            [Buffer.from('delegates'), delegatorAddress.toBuffer()],
+            this.program.programId
+        );
+        return delegatesPda;
+    }
+
+    /**
+     * @description List every delegation naming an address as its requested or active delegate
+     * @notice Uses getProgramAccounts on the delegator records, so the RPC node must allow it
+     * @param delegate Address to find delegators for
+     * @returns Promise resolving to the delegators and the delegation accounts that name the delegate
+     * @example
+     * ```typescript
+     * for (const { delegator } of await client.getDelegators(wallet.publicKey)) {
+     *   await client.acceptDelegation(delegator);
+     * }
+     * ```
+     */
+    async getDelegators(delegate: PublicKey): Promise<DelegatorInfo[]> {
+        const records = await (this.program.account as any).delegatorRecord.all([
+            { memcmp: { offset: 8, bytes: delegate.toBase58() } },
+        ]);
+        return records.map((record: any) => ({
+            delegator: record.account.delegator,
+            delegation: record.account.delegation,
+        }));
+    }
+
+    /**
+     * @description Get how many delegations currently name an address as their delegate
+     * @param delegate Address of the delegate
+     * @returns Promise resolving to the count (0 if the address was never named)
+     */
+    async getDelegatorCount(delegate: PublicKey): Promise<number> {
+        try {
+            const account = await (this.program.account as any).delegateIndex.fetch(this.getDelegateIndexAddress(delegate));
+            return account.count;
+        } catch {
+            return 0;
+        }
+    }
+
+    /**
+     * @description Get the PDA counting the delegations that name a delegate
+     * @param delegate Address of the delegate
+     * @returns The deterministically derived index address
+     */
+    getDelegateIndexAddress(delegate: PublicKey): PublicKey {
+        const [indexPda] = PublicKey.findProgramAddressSync(
+            [Buffer.from('delegate_index'), delegate.toBuffer()],
+            this.program.programId
+        );
+        return indexPda;
+    }
+
+    /**
+     * @description Get the PDA of the record linking a delegation to its delegate
+     * @param delegation Address of the delegation account
+     * @returns The deterministically derived record address
+     */
+    getDelegatorRecordAddress(delegation: PublicKey): PublicKey {
+        const [recordPda] = PublicKey.findProgramAddressSync(
+            [Buffer.from('delegator_record'), delegation.toBuffer()],
+            this.program.programId
+        );
+        return recordPda;
+    }
+
+    /** Index of the delegate a delegation's record currently points at, or null if none */
+    private async getIndexedDelegateIndex(delegation: PublicKey): Promise<PublicKey | null> {
         try {
-            const [delegationPda] = PublicKey.findProgramAddressSync(
-                [Buffer.from('delegation'), delegatorAddress.toBuffer()],
-                this.program.programId
-            );
+            const record = await (this.program.account as any).delegatorRecord.fetch(this.getDelegatorRecordAddress(delegation));
+            return record.delegate.equals(PublicKey.default) ? null : this.getDelegateIndexAddress(record.delegate);
+        } catch {
+            return null;
+        }
+    }
 
-            const delegationAccount = await (this.program.account as any).delegation.fetch(delegationPda);
+    private async fetchDelegation(address: PublicKey): Promise<DelegationInfo | null> {
+        try {
+            const delegationAccount = await (this.program.account as any).delegation.fetch(address);
             
             return {
                 delegator: delegationAccount.delegator,
                 delegate: delegationAccount.delegate,
+                pendingDelegate: delegationAccount.pendingDelegate,
+                expiresAt: delegationAccount.expiresAt?.toNumber() ?? null,
+                permissions: delegationAccount.permissions,
                 bump: delegationAccount.bump
             };
         } catch (error) {
@@ -328,8 +1048,29 @@ export class MailServiceClient {
         const serviceAccount = await (this.program.account as any).mailServiceState.fetch(this.mailServicePda);
         
         return {
-            registrationFee: serviceAccount.registrationFee?.toNumber() || 100_000_000,
-            delegationFee: serviceAccount.delegationFee.toNumber()
+            registrationFee: serviceAccount.registrationFee.toNumber(),
+            delegationFee: serviceAccount.delegationFee.toNumber(),
+            updateFee: serviceAccount.updateFee.toNumber(),
+            updateWindow: serviceAccount.updateWindow.toNumber(),
+            delegateShareBps: serviceAccount.delegateShareBps
+        };
+    }
+
+    /**
+     * @description Get the service's running totals of collected and withdrawn fees
+     * @returns Promise resolving to the fee ledger, in USDC base units
+     * @example
+     * ```typescript
+     * const ledger = await client.getFeeLedger();
+     * console.log('Outstanding:', formatUSDC(ledger.feesCollected - ledger.feesWithdrawn), 'USDC');
+     * ```
+     */
+    async getFeeLedger(): Promise<FeeLedger> {
+        const serviceAccount = await (this.program.account as any).mailServiceState.fetch(this.mailServicePda);
+
+        return {
+            feesCollected: serviceAccount.feesCollected.toNumber(),
+            feesWithdrawn: serviceAccount.feesWithdrawn.toNumber()
         };
     }
 
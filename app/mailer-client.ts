@@ -1,17 +1,29 @@
 import * as anchor from '@coral-xyz/anchor';
+import { createHash, randomBytes } from 'crypto';
 import { Program, AnchorProvider, BN } from '@coral-xyz/anchor';
 import { 
     Connection, 
+    Ed25519Program,
+    Keypair,
     PublicKey, 
-    SystemProgram
+    SystemProgram,
+    SYSVAR_INSTRUCTIONS_PUBKEY,
+    Transaction
 } from '@solana/web3.js';
 import { 
     TOKEN_PROGRAM_ID, 
     ASSOCIATED_TOKEN_PROGRAM_ID,
+    NATIVE_MINT,
+    createApproveInstruction,
+    createCloseAccountInstruction,
+    createRevokeInstruction,
+    getAccount,
     getAssociatedTokenAddressSync
 } from '@solana/spl-token';
 import { Mailer } from '../target/types/mailer';
-import { ClaimableInfo, MailerFees, formatUSDC, CLAIM_PERIOD_DAYS } from './types';
+import { encodePreparedBatch } from './prepared-batch';
+import { solvePow } from './pow';
+import { ClaimableInfo, ExpiryIndexInfo, FeeBucketInfo, MailerFeeSource, MailerClaimMode, MailerFees, MailerStats, MailerTip, MessageLimits, CLIENT_REF_LEN, ExternalAddress, MailerConfigChange, ConfigChangeReport, FeeQuote, FeeBreakdown, PaymentEscrowInfo, TokenConfigInfo, TokenDisplayInfo, ChannelInfo, ChannelSubscriptionInfo, GroupInfo, CampaignInfo, LoyaltyPointsInfo, InboxInfo, SpamDepositInfo, READ_STATE_BITS, TOKEN_METADATA_PROGRAM_ID, BUBBLEGUM_PROGRAM_ID, SPL_NOOP_PROGRAM_ID, SPL_ACCOUNT_COMPRESSION_PROGRAM_ID, MessageLogInfo, MessageRootInfo, ScheduledMessageInfo, SessionInfo, DelegatorBillingInfo, SubscriptionPlanInfo, PlanSubscriptionInfo, StakeTierInfo, StakeInfo, messageLogTreeSize, formatUSDC, CLAIM_PERIOD_DAYS, VESTING_CLIFF_DAYS, EXPIRY_BUCKET_DAYS, ESCROW_RECLAIM_DAYS } from './types';
 
 /**
  * @class MailerClient
@@ -49,14 +61,28 @@ export class MailerClient {
     private program: Program<Mailer>;
     private provider: AnchorProvider;
     private mailerPda: PublicKey;
+    private statsPda: PublicKey;
     private usdcMint: PublicKey;
+    private tokenProgramId: PublicKey;
+    private priceUpdate: PublicKey | null = null;
+    private feeExempt = false;
+    private referral: { code: PublicKey; referrer: PublicKey } | null = null;
+    private campaign: PublicKey | null = null;
+    private earnPoints = false;
+    private prepaidCredit = false;
+    private planSubscription = false;
+    private nftMint: PublicKey | null = null;
+    private staked = false;
+    private receiptTree: PublicKey | null = null;
+    private messageLogTree: PublicKey | null = null;
 
     /**
      * @description Creates a new MailerClient instance for interacting with a deployed Mailer program
      * @param connection Solana RPC connection for blockchain interactions
      * @param wallet Anchor wallet containing keypair for signing transactions
      * @param programId Public key of the deployed Mailer program
-     * @param usdcMint Public key of the USDC token mint (6 decimal places)
+     * @param usdcMint Public key of the mint fees are paid in (USDC, or any mint added with `addToken`)
+     * @param tokenProgramId Token program owning the mint: SPL Token (default) or Token-2022
      * @example
      * ```typescript
      * const connection = new Connection('https://api.devnet.solana.com');
@@ -70,7 +96,8 @@ export class MailerClient {
         connection: Connection,
         wallet: anchor.Wallet,
         programId: PublicKey,
-        usdcMint: PublicKey
+        usdcMint: PublicKey,
+        tokenProgramId: PublicKey = TOKEN_PROGRAM_ID
     ) {
         this.provider = new AnchorProvider(connection, wallet, {});
         this.program = new Program(
@@ -78,13 +105,19 @@ export class MailerClient {
             this.provider
         ) as Program<Mailer>;
         this.usdcMint = usdcMint;
+        this.tokenProgramId = tokenProgramId;
         
-        // Derive PDA
+        // Derive PDAs
         const [mailerPda] = PublicKey.findProgramAddressSync(
             [Buffer.from('mailer')],
             this.program.programId
         );
         this.mailerPda = mailerPda;
+        const [statsPda] = PublicKey.findProgramAddressSync(
+            [Buffer.from('mailer_stats')],
+            this.program.programId
+        );
+        this.statsPda = statsPda;
     }
 
     /**
@@ -122,9 +155,11 @@ export class MailerClient {
 
     private async initializeProgram(owner: PublicKey): Promise<void> {
         await (this.program.methods as any)
-            .initialize(this.usdcMint)
+            .initialize()
             .accounts({
                 mailer: this.mailerPda,
+                stats: this.statsPda,
+                usdcMint: this.usdcMint,
                 owner: owner,
                 systemProgram: SystemProgram.programId,
             })
@@ -136,6 +171,9 @@ export class MailerClient {
      * @notice Sender pays 0.1 USDC, receives 90% back as claimable revenue within 60 days
      * @param subject Message subject line (plain text)
      * @param body Message content (plain text)
+     * @param tip Optional tip paid on top of the fee to the owner or the recipient's claim
+     * @param toExternal Optional recipient address on another chain, carried in the event for the relay
+     * @param clientRef Optional 16-byte caller reference echoed in the send event, e.g. an idempotency key
      * @returns Promise resolving to transaction signature
      * @throws {Error} If insufficient USDC balance or transaction fails
      * @example
@@ -151,33 +189,142 @@ export class MailerClient {
      * const claimable = await client.getRecipientClaimable(wallet.publicKey);
      * ```
      */
-    async sendPriority(subject: string, body: string): Promise<string> {
+    async sendPriority(
+        subject: string,
+        body: string,
+        tip?: MailerTip,
+        toExternal?: ExternalAddress,
+        clientRef?: Uint8Array
+    ): Promise<string> {
         const sender = this.provider.wallet.publicKey;
         const [recipientClaimPda] = PublicKey.findProgramAddressSync(
             [Buffer.from('claim'), sender.toBuffer()],
             this.program.programId
         );
 
-        const senderUsdcAccount = getAssociatedTokenAddressSync(
-            this.usdcMint,
-            sender
-        );
+        const senderUsdcAccount = getAssociatedTokenAddressSync(this.usdcMint, sender, false, this.tokenProgramId);
+
+        const mailerUsdcAccount = getAssociatedTokenAddressSync(this.usdcMint, this.mailerPda, true, this.tokenProgramId);
+
+        return await (this.program.methods as any)
+            .sendPriority(
+                subject,
+                body,
+                this.encodeTip(tip),
+                this.encodeExternalAddress(toExternal),
+                this.encodeClientRef(clientRef)
+            )
+            .accounts({
+                recipientClaim: recipientClaimPda,
+                mailer: this.mailerPda,
+                stats: this.statsPda,
+                tokenConfig: this.getTokenConfigAddress(this.usdcMint),
+                priceUpdate: this.priceUpdate,
+                ownerBucket: this.getOwnerBucketAddress(this.usdcMint, 'send'),
+                mint: this.usdcMint,
+                sender: sender,
+                payer: sender,
+                senderUsdcAccount,
+                mailerUsdcAccount,
+                recipientPreferences: this.getPreferencesAddress(sender),
+                callerPolicy: this.getCallerPolicyAddress(),
+                instructions: SYSVAR_INSTRUCTIONS_PUBKEY,
+                expiryIndex: this.getExpiryIndexAddress(Math.floor(Date.now() / 1000)),
+                rateLimit: this.getRateLimitAddress(sender),
+                denylist: this.getDenylistAddress(sender),
+                feeExempt: this.feeExempt ? this.getFeeExemptAddress(sender) : null,
+                referral: this.referral?.code ?? null,
+                referrerClaim: this.referral ? this.getClaimAddress(this.referral.referrer) : null,
+                campaign: this.campaign,
+                senderStats: this.earnPoints ? this.getSenderStatsAddress(sender) : null,
+                nftToken: this.nftMint ? getAssociatedTokenAddressSync(this.nftMint, sender) : null,
+                nftMetadata: this.nftMint ? this.getNftMetadataAddress(this.nftMint) : null,
+                stake: this.staked ? this.getStakeAddress(sender) : null,
+                receiptConfig: this.receiptTree ? this.getReceiptConfigAddress() : null,
+                receiptTreeConfig: this.receiptTree ? this.getReceiptTreeConfigAddress(this.receiptTree) : null,
+                receiptTree: this.receiptTree,
+                bubblegumProgram: this.receiptTree ? BUBBLEGUM_PROGRAM_ID : null,
+                logWrapper: this.receiptTree || this.messageLogTree ? SPL_NOOP_PROGRAM_ID : null,
+                compressionProgram: this.receiptTree || this.messageLogTree ? SPL_ACCOUNT_COMPRESSION_PROGRAM_ID : null,
+                messageLog: this.messageLogTree ? this.getMessageLogAddress() : null,
+                messageLogTree: this.messageLogTree,
+                inbox: this.getInboxAddress(sender),
+                credit: this.prepaidCredit ? this.getCreditAddress(sender) : null,
+                planSubscription: this.planSubscription ? this.getPlanSubscriptionAddress(sender) : null,
+                tokenProgram: this.tokenProgramId,
+                associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+                systemProgram: SystemProgram.programId,
+            })
+            .rpc();
+    }
 
-        const mailerUsdcAccount = getAssociatedTokenAddressSync(
-            this.usdcMint,
-            this.mailerPda,
-            true
+    /**
+     * @description Send a priority message, trading a 30-day cliff on your share for a 95% share
+     * @notice Sender pays 0.1 USDC and receives 95% back, locked for 30 days from this send and claimable
+     *         until the claim period ends. Each vested send restarts the cliff for shares still locked.
+     *         Requires a lump claim whose period has at least 30 days left; campaign boosts do not apply.
+     * @param subject Message subject line (plain text)
+     * @param body Message content (plain text)
+     * @param clientRef Optional 16-byte caller reference echoed in the send event, e.g. an idempotency key
+     * @returns Promise resolving to transaction signature
+     * @throws {Error} If the claim streams, its period ends within 30 days, or transaction fails
+     * @example
+     * ```typescript
+     * await client.sendPriorityVested('Subject', 'Body');
+     * const { vested, vestingEndsAt } = (await client.getRecipientClaimable(wallet.publicKey))!;
+     * ```
+     */
+    async sendPriorityVested(subject: string, body: string, clientRef?: Uint8Array): Promise<string> {
+        const sender = this.provider.wallet.publicKey;
+        const [recipientClaimPda] = PublicKey.findProgramAddressSync(
+            [Buffer.from('claim'), sender.toBuffer()],
+            this.program.programId
         );
 
+        const senderUsdcAccount = getAssociatedTokenAddressSync(this.usdcMint, sender, false, this.tokenProgramId);
+
+        const mailerUsdcAccount = getAssociatedTokenAddressSync(this.usdcMint, this.mailerPda, true, this.tokenProgramId);
+
         return await (this.program.methods as any)
-            .sendPriority(subject, body)
+            .sendPriorityVested(subject, body, this.encodeClientRef(clientRef))
             .accounts({
                 recipientClaim: recipientClaimPda,
                 mailer: this.mailerPda,
+                stats: this.statsPda,
+                tokenConfig: this.getTokenConfigAddress(this.usdcMint),
+                priceUpdate: this.priceUpdate,
+                ownerBucket: this.getOwnerBucketAddress(this.usdcMint, 'send'),
+                mint: this.usdcMint,
                 sender: sender,
+                payer: sender,
                 senderUsdcAccount,
                 mailerUsdcAccount,
-                tokenProgram: TOKEN_PROGRAM_ID,
+                recipientPreferences: this.getPreferencesAddress(sender),
+                callerPolicy: this.getCallerPolicyAddress(),
+                instructions: SYSVAR_INSTRUCTIONS_PUBKEY,
+                expiryIndex: this.getExpiryIndexAddress(Math.floor(Date.now() / 1000)),
+                rateLimit: this.getRateLimitAddress(sender),
+                denylist: this.getDenylistAddress(sender),
+                feeExempt: this.feeExempt ? this.getFeeExemptAddress(sender) : null,
+                referral: this.referral?.code ?? null,
+                referrerClaim: this.referral ? this.getClaimAddress(this.referral.referrer) : null,
+                campaign: null,
+                senderStats: this.earnPoints ? this.getSenderStatsAddress(sender) : null,
+                nftToken: this.nftMint ? getAssociatedTokenAddressSync(this.nftMint, sender) : null,
+                nftMetadata: this.nftMint ? this.getNftMetadataAddress(this.nftMint) : null,
+                stake: this.staked ? this.getStakeAddress(sender) : null,
+                receiptConfig: this.receiptTree ? this.getReceiptConfigAddress() : null,
+                receiptTreeConfig: this.receiptTree ? this.getReceiptTreeConfigAddress(this.receiptTree) : null,
+                receiptTree: this.receiptTree,
+                bubblegumProgram: this.receiptTree ? BUBBLEGUM_PROGRAM_ID : null,
+                logWrapper: this.receiptTree || this.messageLogTree ? SPL_NOOP_PROGRAM_ID : null,
+                compressionProgram: this.receiptTree || this.messageLogTree ? SPL_ACCOUNT_COMPRESSION_PROGRAM_ID : null,
+                messageLog: this.messageLogTree ? this.getMessageLogAddress() : null,
+                messageLogTree: this.messageLogTree,
+                inbox: this.getInboxAddress(sender),
+                credit: this.prepaidCredit ? this.getCreditAddress(sender) : null,
+                planSubscription: this.planSubscription ? this.getPlanSubscriptionAddress(sender) : null,
+                tokenProgram: this.tokenProgramId,
                 associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
                 systemProgram: SystemProgram.programId,
             })
@@ -188,8 +335,12 @@ export class MailerClient {
      * @description Send a priority message using a pre-prepared mail identifier
      * @notice Sender pays 0.1 USDC, receives 90% back as claimable revenue within 60 days
      * @param mailId Pre-prepared message identifier (e.g., IPFS hash or UUID)
+     * @param tip Optional tip paid on top of the fee to the owner or the recipient's claim
+     * @param toExternal Optional recipient address on another chain, carried in the event for the relay
+     * @param rejectDuplicate Record the mail ID on-chain so this and any later send of it fail if it was already sent
+     * @param clientRef Optional 16-byte caller reference echoed in the send event, e.g. an idempotency key
      * @returns Promise resolving to transaction signature
-     * @throws {Error} If insufficient USDC balance or transaction fails
+     * @throws {Error} If insufficient USDC balance, the mail ID was already recorded, or transaction fails
      * @example
      * ```typescript
      * // For messages stored off-chain (IPFS, database, etc.)
@@ -198,33 +349,69 @@ export class MailerClient {
      * console.log('Prepared message sent:', tx);
      * ```
      */
-    async sendPriorityPrepared(mailId: string): Promise<string> {
+    async sendPriorityPrepared(
+        mailId: string,
+        tip?: MailerTip,
+        toExternal?: ExternalAddress,
+        rejectDuplicate = false,
+        clientRef?: Uint8Array
+    ): Promise<string> {
         const sender = this.provider.wallet.publicKey;
         const [recipientClaimPda] = PublicKey.findProgramAddressSync(
             [Buffer.from('claim'), sender.toBuffer()],
             this.program.programId
         );
 
-        const senderUsdcAccount = getAssociatedTokenAddressSync(
-            this.usdcMint,
-            sender
-        );
+        const senderUsdcAccount = getAssociatedTokenAddressSync(this.usdcMint, sender, false, this.tokenProgramId);
 
-        const mailerUsdcAccount = getAssociatedTokenAddressSync(
-            this.usdcMint,
-            this.mailerPda,
-            true
-        );
+        const mailerUsdcAccount = getAssociatedTokenAddressSync(this.usdcMint, this.mailerPda, true, this.tokenProgramId);
 
         return await (this.program.methods as any)
-            .sendPriorityPrepared(mailId)
+            .sendPriorityPrepared(
+                mailId,
+                this.encodeTip(tip),
+                this.encodeExternalAddress(toExternal),
+                this.encodeClientRef(clientRef)
+            )
             .accounts({
                 recipientClaim: recipientClaimPda,
                 mailer: this.mailerPda,
+                stats: this.statsPda,
+                tokenConfig: this.getTokenConfigAddress(this.usdcMint),
+                priceUpdate: this.priceUpdate,
+                ownerBucket: this.getOwnerBucketAddress(this.usdcMint, 'send'),
+                mint: this.usdcMint,
                 sender: sender,
+                payer: sender,
                 senderUsdcAccount,
                 mailerUsdcAccount,
-                tokenProgram: TOKEN_PROGRAM_ID,
+                recipientPreferences: this.getPreferencesAddress(sender),
+                callerPolicy: this.getCallerPolicyAddress(),
+                instructions: SYSVAR_INSTRUCTIONS_PUBKEY,
+                expiryIndex: this.getExpiryIndexAddress(Math.floor(Date.now() / 1000)),
+                rateLimit: this.getRateLimitAddress(sender),
+                denylist: this.getDenylistAddress(sender),
+                feeExempt: this.feeExempt ? this.getFeeExemptAddress(sender) : null,
+                referral: this.referral?.code ?? null,
+                referrerClaim: this.referral ? this.getClaimAddress(this.referral.referrer) : null,
+                campaign: this.campaign,
+                senderStats: this.earnPoints ? this.getSenderStatsAddress(sender) : null,
+                nftToken: this.nftMint ? getAssociatedTokenAddressSync(this.nftMint, sender) : null,
+                nftMetadata: this.nftMint ? this.getNftMetadataAddress(this.nftMint) : null,
+                stake: this.staked ? this.getStakeAddress(sender) : null,
+                receiptConfig: this.receiptTree ? this.getReceiptConfigAddress() : null,
+                receiptTreeConfig: this.receiptTree ? this.getReceiptTreeConfigAddress(this.receiptTree) : null,
+                receiptTree: this.receiptTree,
+                bubblegumProgram: this.receiptTree ? BUBBLEGUM_PROGRAM_ID : null,
+                logWrapper: this.receiptTree || this.messageLogTree ? SPL_NOOP_PROGRAM_ID : null,
+                compressionProgram: this.receiptTree || this.messageLogTree ? SPL_ACCOUNT_COMPRESSION_PROGRAM_ID : null,
+                messageLog: this.messageLogTree ? this.getMessageLogAddress() : null,
+                messageLogTree: this.messageLogTree,
+                inbox: this.getInboxAddress(sender),
+                credit: this.prepaidCredit ? this.getCreditAddress(sender) : null,
+                planSubscription: this.planSubscription ? this.getPlanSubscriptionAddress(sender) : null,
+                mailIdRecord: rejectDuplicate ? this.getMailIdRecordAddress(sender, mailId) : null,
+                tokenProgram: this.tokenProgramId,
                 associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
                 systemProgram: SystemProgram.programId,
             })
@@ -236,6 +423,9 @@ export class MailerClient {
      * @notice Sender pays 0.01 USDC with no revenue share returned
      * @param subject Message subject line (plain text)
      * @param body Message content (plain text)
+     * @param tip Optional tip paid on top of the fee to the owner or the recipient's claim
+     * @param toExternal Optional recipient address on another chain, carried in the event for the relay
+     * @param clientRef Optional 16-byte caller reference echoed in the send event, e.g. an idempotency key
      * @returns Promise resolving to transaction signature
      * @throws {Error} If insufficient USDC balance or transaction fails
      * @example
@@ -248,45 +438,254 @@ export class MailerClient {
      * console.log('Standard message sent:', tx);
      * ```
      */
-    async send(subject: string, body: string): Promise<string> {
+    async send(
+        subject: string,
+        body: string,
+        tip?: MailerTip,
+        toExternal?: ExternalAddress,
+        clientRef?: Uint8Array
+    ): Promise<string> {
         const sender = this.provider.wallet.publicKey;
         const [recipientClaimPda] = PublicKey.findProgramAddressSync(
             [Buffer.from('claim'), sender.toBuffer()],
             this.program.programId
         );
 
-        const senderUsdcAccount = getAssociatedTokenAddressSync(
-            this.usdcMint,
-            sender
-        );
+        const senderUsdcAccount = getAssociatedTokenAddressSync(this.usdcMint, sender, false, this.tokenProgramId);
 
-        const mailerUsdcAccount = getAssociatedTokenAddressSync(
-            this.usdcMint,
-            this.mailerPda,
-            true
-        );
+        const mailerUsdcAccount = getAssociatedTokenAddressSync(this.usdcMint, this.mailerPda, true, this.tokenProgramId);
 
         return await (this.program.methods as any)
-            .send(subject, body)
+            .send(
+                subject,
+                body,
+                this.encodeTip(tip),
+                this.encodeExternalAddress(toExternal),
+                this.encodeClientRef(clientRef)
+            )
             .accounts({
                 recipientClaim: recipientClaimPda,
                 mailer: this.mailerPda,
+                stats: this.statsPda,
+                tokenConfig: this.getTokenConfigAddress(this.usdcMint),
+                priceUpdate: this.priceUpdate,
+                ownerBucket: this.getOwnerBucketAddress(this.usdcMint, 'send'),
+                mint: this.usdcMint,
                 sender: sender,
+                payer: sender,
                 senderUsdcAccount,
                 mailerUsdcAccount,
-                tokenProgram: TOKEN_PROGRAM_ID,
+                recipientPreferences: this.getPreferencesAddress(sender),
+                callerPolicy: this.getCallerPolicyAddress(),
+                instructions: SYSVAR_INSTRUCTIONS_PUBKEY,
+                expiryIndex: this.getExpiryIndexAddress(Math.floor(Date.now() / 1000)),
+                rateLimit: this.getRateLimitAddress(sender),
+                denylist: this.getDenylistAddress(sender),
+                feeExempt: this.feeExempt ? this.getFeeExemptAddress(sender) : null,
+                referral: this.referral?.code ?? null,
+                referrerClaim: this.referral ? this.getClaimAddress(this.referral.referrer) : null,
+                campaign: this.campaign,
+                senderStats: this.earnPoints ? this.getSenderStatsAddress(sender) : null,
+                nftToken: this.nftMint ? getAssociatedTokenAddressSync(this.nftMint, sender) : null,
+                nftMetadata: this.nftMint ? this.getNftMetadataAddress(this.nftMint) : null,
+                stake: this.staked ? this.getStakeAddress(sender) : null,
+                receiptConfig: this.receiptTree ? this.getReceiptConfigAddress() : null,
+                receiptTreeConfig: this.receiptTree ? this.getReceiptTreeConfigAddress(this.receiptTree) : null,
+                receiptTree: this.receiptTree,
+                bubblegumProgram: this.receiptTree ? BUBBLEGUM_PROGRAM_ID : null,
+                logWrapper: this.receiptTree || this.messageLogTree ? SPL_NOOP_PROGRAM_ID : null,
+                compressionProgram: this.receiptTree || this.messageLogTree ? SPL_ACCOUNT_COMPRESSION_PROGRAM_ID : null,
+                messageLog: this.messageLogTree ? this.getMessageLogAddress() : null,
+                messageLogTree: this.messageLogTree,
+                inbox: this.getInboxAddress(sender),
+                credit: this.prepaidCredit ? this.getCreditAddress(sender) : null,
+                planSubscription: this.planSubscription ? this.getPlanSubscriptionAddress(sender) : null,
+                tokenProgram: this.tokenProgramId,
                 associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
                 systemProgram: SystemProgram.programId,
             })
             .rpc();
     }
 
+    /**
+     * @description Send a prepared message without revealing the true sender on-chain
+     * @notice The connected wallet should be a relayer or a one-off ephemeral key holding
+     *         the fee; it is the only sender in the event. Pays the standard fee
+     * @param to Recipient of the message
+     * @param mailId Pre-prepared message identifier
+     * @param envelope True sender sealed to the recipient, from `sealEnvelope`
+     * @param rejectDuplicate Create a replay record so the mail ID can never be sent again
+     * @returns Promise resolving to transaction signature
+     * @notice When the recipient requires deposits and the signer is not their contact, also locks the deposit
+     * @throws {Error} If the envelope is empty or larger than 256 bytes, or the recipient blocked the signer
+     * @example
+     * ```typescript
+     * const envelope = sealEnvelope(senderKeypair, recipient, mailId, recipientEnvelopeKey);
+     * const relayClient = new MailerClient(connection, new Wallet(relayerKeypair), programId, usdcMint);
+     * await relayClient.sendSealed(recipient, mailId, envelope);
+     * ```
+     */
+    async sendSealed(to: PublicKey, mailId: string, envelope: Buffer, rejectDuplicate = false): Promise<string> {
+        const sender = this.provider.wallet.publicKey;
+        const depositRequired = await this.isDepositRequired(to, sender);
+
+        return await (this.program.methods as any)
+            .sendSealed(to, mailId, envelope)
+            .accounts({
+                send: {
+                    recipientClaim: this.getClaimAddress(sender),
+                    mailer: this.mailerPda,
+                    stats: this.statsPda,
+                    tokenConfig: this.getTokenConfigAddress(this.usdcMint),
+                    priceUpdate: this.priceUpdate,
+                    ownerBucket: this.getOwnerBucketAddress(this.usdcMint, 'send'),
+                    mint: this.usdcMint,
+                    sender,
+                    payer: sender,
+                    senderUsdcAccount: getAssociatedTokenAddressSync(this.usdcMint, sender, false, this.tokenProgramId),
+                    mailerUsdcAccount: getAssociatedTokenAddressSync(this.usdcMint, this.mailerPda, true, this.tokenProgramId),
+                    recipientPreferences: this.getPreferencesAddress(sender),
+                    callerPolicy: this.getCallerPolicyAddress(),
+                    instructions: SYSVAR_INSTRUCTIONS_PUBKEY,
+                    expiryIndex: this.getExpiryIndexAddress(Math.floor(Date.now() / 1000)),
+                    rateLimit: this.getRateLimitAddress(sender),
+                    denylist: this.getDenylistAddress(sender),
+                    feeExempt: this.feeExempt ? this.getFeeExemptAddress(sender) : null,
+                    referral: this.referral?.code ?? null,
+                    referrerClaim: this.referral ? this.getClaimAddress(this.referral.referrer) : null,
+                    campaign: this.campaign,
+                    senderStats: this.earnPoints ? this.getSenderStatsAddress(sender) : null,
+                    nftToken: this.nftMint ? getAssociatedTokenAddressSync(this.nftMint, sender) : null,
+                    nftMetadata: this.nftMint ? this.getNftMetadataAddress(this.nftMint) : null,
+                    stake: this.staked ? this.getStakeAddress(sender) : null,
+                    receiptConfig: this.receiptTree ? this.getReceiptConfigAddress() : null,
+                    receiptTreeConfig: this.receiptTree ? this.getReceiptTreeConfigAddress(this.receiptTree) : null,
+                    receiptTree: this.receiptTree,
+                    bubblegumProgram: this.receiptTree ? BUBBLEGUM_PROGRAM_ID : null,
+                    logWrapper: this.receiptTree || this.messageLogTree ? SPL_NOOP_PROGRAM_ID : null,
+                    compressionProgram: this.receiptTree || this.messageLogTree ? SPL_ACCOUNT_COMPRESSION_PROGRAM_ID : null,
+                    messageLog: this.messageLogTree ? this.getMessageLogAddress() : null,
+                    messageLogTree: this.messageLogTree,
+                    inbox: this.getInboxAddress(to),
+                    credit: this.prepaidCredit ? this.getCreditAddress(sender) : null,
+                    planSubscription: this.planSubscription ? this.getPlanSubscriptionAddress(sender) : null,
+                    mailIdRecord: rejectDuplicate ? this.getMailIdRecordAddress(sender, mailId) : null,
+                    tokenProgram: this.tokenProgramId,
+                    associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+                    systemProgram: SystemProgram.programId,
+                },
+                recipientPreferences: this.getPreferencesAddress(to),
+                block: this.getBlockAddress(to, sender),
+                recipientDenylist: this.getDenylistAddress(to),
+                spamPolicy: this.getSpamPolicyAddress(to),
+                contact: this.getContactAddress(to, sender),
+                deposit: depositRequired ? this.getSpamDepositAddress(to, sender) : null,
+            })
+            .rpc();
+    }
+
+    /**
+     * @description Acknowledge a received message with a `MailRead` event
+     * @param messageId ID of the message being acknowledged (32 bytes)
+     * @param messageSeq Sequence number of the message; sets its bit in the read-state bitmap
+     * @param trackState Also record the read in the wallet's read-state bitmap, creating it on first use
+     * @returns Promise resolving to transaction signature
+     * @example
+     * ```typescript
+     * await client.markRead(event.messageId, event.messageSeq.toNumber());
+     * ```
+     */
+    async markRead(messageId: Buffer | number[], messageSeq: number, trackState = true): Promise<string> {
+        const reader = this.provider.wallet.publicKey;
+
+        return await (this.program.methods as any)
+            .markRead(Array.from(messageId), new BN(messageSeq))
+            .accounts({
+                reader,
+                readState: trackState ? this.getReadStateAddress(reader) : null,
+                systemProgram: SystemProgram.programId,
+            })
+            .rpc();
+    }
+
+    /**
+     * @description Check a reader's read-state bitmap for a message
+     * @notice Sequences `READ_STATE_BITS` apart share a bit, so a true result may come from an older message
+     * @param reader Reader wallet address
+     * @param messageSeq Sequence number of the message
+     * @returns Promise resolving to whether the message's slot is marked read
+     */
+    async isRead(reader: PublicKey, messageSeq: number): Promise<boolean> {
+        const account = await (this.program.account as any).readState.fetchNullable(this.getReadStateAddress(reader));
+        if (!account) {
+            return false;
+        }
+        const bit = messageSeq % READ_STATE_BITS;
+        return (account.bits[Math.floor(bit / 8)] & (1 << bit % 8)) !== 0;
+    }
+
+    /**
+     * @description Get the read-state bitmap PDA for a reader
+     * @param reader Reader wallet address
+     * @returns The deterministically derived read-state account address
+     */
+    getReadStateAddress(reader: PublicKey): PublicKey {
+        const [readStatePda] = PublicKey.findProgramAddressSync(
+            [Buffer.from('read'), reader.toBuffer()],
+            this.program.programId
+        );
+        return readStatePda;
+    }
+
+    /**
+     * @description Get the delivery counters of an address, for polling instead of subscribing to logs
+     * @notice Updated by sends that pass the recipient's inbox, as this client's sends do;
+     *         channel posts and group sends are not counted
+     * @param owner Recipient wallet address
+     * @returns Promise resolving to the inbox, or null if nothing was delivered yet
+     * @example
+     * ```typescript
+     * const inbox = await client.getInbox(wallet.publicKey);
+     * if (inbox && inbox.receivedCount > seen) {
+     *     // fetch the new messages' events
+     * }
+     * ```
+     */
+    async getInbox(owner: PublicKey): Promise<InboxInfo | null> {
+        const account = await (this.program.account as any).inbox.fetchNullable(this.getInboxAddress(owner));
+        if (!account) {
+            return null;
+        }
+        return {
+            receivedCount: account.receivedCount.toNumber(),
+            lastMessageSeq: account.lastMessageSeq.toNumber(),
+            lastSender: account.lastSender,
+        };
+    }
+
+    /**
+     * @description Get the inbox counters PDA for a recipient
+     * @param owner Recipient wallet address
+     * @returns The deterministically derived inbox account address
+     */
+    getInboxAddress(owner: PublicKey): PublicKey {
+        const [inboxPda] = PublicKey.findProgramAddressSync(
+            [Buffer.from('inbox'), owner.toBuffer()],
+            this.program.programId
+        );
+        return inboxPda;
+    }
+
     /**
      * @description Send a standard message using a pre-prepared mail identifier
      * @notice Sender pays 0.01 USDC with no revenue share returned
      * @param mailId Pre-prepared message identifier (e.g., IPFS hash or UUID)
+     * @param tip Optional tip paid on top of the fee to the owner or the recipient's claim
+     * @param toExternal Optional recipient address on another chain, carried in the event for the relay
+     * @param rejectDuplicate Record the mail ID on-chain so this and any later send of it fail if it was already sent
+     * @param clientRef Optional 16-byte caller reference echoed in the send event, e.g. an idempotency key
      * @returns Promise resolving to transaction signature
-     * @throws {Error} If insufficient USDC balance or transaction fails
+     * @throws {Error} If insufficient USDC balance, the mail ID was already recorded, or transaction fails
      * @example
      * ```typescript
      * // For cost-effective messaging with external content
@@ -295,33 +694,69 @@ export class MailerClient {
      * console.log('Standard prepared message sent:', tx);
      * ```
      */
-    async sendPrepared(mailId: string): Promise<string> {
+    async sendPrepared(
+        mailId: string,
+        tip?: MailerTip,
+        toExternal?: ExternalAddress,
+        rejectDuplicate = false,
+        clientRef?: Uint8Array
+    ): Promise<string> {
         const sender = this.provider.wallet.publicKey;
         const [recipientClaimPda] = PublicKey.findProgramAddressSync(
             [Buffer.from('claim'), sender.toBuffer()],
             this.program.programId
         );
 
-        const senderUsdcAccount = getAssociatedTokenAddressSync(
-            this.usdcMint,
-            sender
-        );
+        const senderUsdcAccount = getAssociatedTokenAddressSync(this.usdcMint, sender, false, this.tokenProgramId);
 
-        const mailerUsdcAccount = getAssociatedTokenAddressSync(
-            this.usdcMint,
-            this.mailerPda,
-            true
-        );
+        const mailerUsdcAccount = getAssociatedTokenAddressSync(this.usdcMint, this.mailerPda, true, this.tokenProgramId);
 
         return await (this.program.methods as any)
-            .sendPrepared(mailId)
+            .sendPrepared(
+                mailId,
+                this.encodeTip(tip),
+                this.encodeExternalAddress(toExternal),
+                this.encodeClientRef(clientRef)
+            )
             .accounts({
                 recipientClaim: recipientClaimPda,
                 mailer: this.mailerPda,
+                stats: this.statsPda,
+                tokenConfig: this.getTokenConfigAddress(this.usdcMint),
+                priceUpdate: this.priceUpdate,
+                ownerBucket: this.getOwnerBucketAddress(this.usdcMint, 'send'),
+                mint: this.usdcMint,
                 sender: sender,
+                payer: sender,
                 senderUsdcAccount,
                 mailerUsdcAccount,
-                tokenProgram: TOKEN_PROGRAM_ID,
+                recipientPreferences: this.getPreferencesAddress(sender),
+                callerPolicy: this.getCallerPolicyAddress(),
+                instructions: SYSVAR_INSTRUCTIONS_PUBKEY,
+                expiryIndex: this.getExpiryIndexAddress(Math.floor(Date.now() / 1000)),
+                rateLimit: this.getRateLimitAddress(sender),
+                denylist: this.getDenylistAddress(sender),
+                feeExempt: this.feeExempt ? this.getFeeExemptAddress(sender) : null,
+                referral: this.referral?.code ?? null,
+                referrerClaim: this.referral ? this.getClaimAddress(this.referral.referrer) : null,
+                campaign: this.campaign,
+                senderStats: this.earnPoints ? this.getSenderStatsAddress(sender) : null,
+                nftToken: this.nftMint ? getAssociatedTokenAddressSync(this.nftMint, sender) : null,
+                nftMetadata: this.nftMint ? this.getNftMetadataAddress(this.nftMint) : null,
+                stake: this.staked ? this.getStakeAddress(sender) : null,
+                receiptConfig: this.receiptTree ? this.getReceiptConfigAddress() : null,
+                receiptTreeConfig: this.receiptTree ? this.getReceiptTreeConfigAddress(this.receiptTree) : null,
+                receiptTree: this.receiptTree,
+                bubblegumProgram: this.receiptTree ? BUBBLEGUM_PROGRAM_ID : null,
+                logWrapper: this.receiptTree || this.messageLogTree ? SPL_NOOP_PROGRAM_ID : null,
+                compressionProgram: this.receiptTree || this.messageLogTree ? SPL_ACCOUNT_COMPRESSION_PROGRAM_ID : null,
+                messageLog: this.messageLogTree ? this.getMessageLogAddress() : null,
+                messageLogTree: this.messageLogTree,
+                inbox: this.getInboxAddress(sender),
+                credit: this.prepaidCredit ? this.getCreditAddress(sender) : null,
+                planSubscription: this.planSubscription ? this.getPlanSubscriptionAddress(sender) : null,
+                mailIdRecord: rejectDuplicate ? this.getMailIdRecordAddress(sender, mailId) : null,
+                tokenProgram: this.tokenProgramId,
                 associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
                 systemProgram: SystemProgram.programId,
             })
@@ -329,237 +764,2706 @@ export class MailerClient {
     }
 
     /**
-     * @description Claim your accumulated revenue share from priority messages
-     * @notice Must be called within 60 days of earning shares, or they expire
+     * @description Send many pre-prepared messages in a single transaction
+     * @notice Mail IDs are zstd-compressed so far more fit per transaction; each ID is
+     *         charged the standard fee, or the full fee with revenue share if `priority`
+     * @param mailIds Pre-prepared message identifiers (max 512, no newlines)
+     * @param priority Charge the priority fee and record revenue shares (default false)
+     * @param clientRef Optional 16-byte caller reference echoed in the send event, e.g. an idempotency key
      * @returns Promise resolving to transaction signature
-     * @throws {Error} If no claimable amount, claim period expired, or transfer fails
+     * @throws {Error} If the compressed batch is too large, or insufficient USDC balance
      * @example
      * ```typescript
-     * // Check claimable amount first
-     * const info = await client.getRecipientClaimable(wallet.publicKey);
-     * if (info && info.amount > 0 && !info.isExpired) {
-     *     const tx = await client.claimRecipientShare();
-     *     console.log(`Claimed ${formatUSDC(info.amount)} USDC:`, tx);
-     * }
+     * const tx = await client.sendPreparedBatch(['QmA...', 'QmB...', 'QmC...']);
+     * console.log('Batch sent:', tx);
      * ```
      */
-    async claimRecipientShare(): Promise<string> {
-        const recipient = this.provider.wallet.publicKey;
+    async sendPreparedBatch(mailIds: string[], priority: boolean = false, clientRef?: Uint8Array): Promise<string> {
+        const sender = this.provider.wallet.publicKey;
         const [recipientClaimPda] = PublicKey.findProgramAddressSync(
-            [Buffer.from('claim'), recipient.toBuffer()],
+            [Buffer.from('claim'), sender.toBuffer()],
             this.program.programId
         );
 
-        const recipientUsdcAccount = getAssociatedTokenAddressSync(
-            this.usdcMint,
-            recipient
-        );
+        const senderUsdcAccount = getAssociatedTokenAddressSync(this.usdcMint, sender, false, this.tokenProgramId);
 
-        const mailerUsdcAccount = getAssociatedTokenAddressSync(
-            this.usdcMint,
-            this.mailerPda,
-            true
-        );
+        const mailerUsdcAccount = getAssociatedTokenAddressSync(this.usdcMint, this.mailerPda, true, this.tokenProgramId);
+
+        const batch = await encodePreparedBatch(mailIds);
 
         return await (this.program.methods as any)
-            .claimRecipientShare()
+            .sendPreparedBatch(
+                batch.count,
+                Array.from(batch.idsHash),
+                batch.payload,
+                priority,
+                this.encodeClientRef(clientRef)
+            )
             .accounts({
                 recipientClaim: recipientClaimPda,
                 mailer: this.mailerPda,
-                recipient: recipient,
-                recipientUsdcAccount,
+                stats: this.statsPda,
+                tokenConfig: this.getTokenConfigAddress(this.usdcMint),
+                priceUpdate: this.priceUpdate,
+                ownerBucket: this.getOwnerBucketAddress(this.usdcMint, 'send'),
+                mint: this.usdcMint,
+                sender: sender,
+                payer: sender,
+                senderUsdcAccount,
                 mailerUsdcAccount,
-                tokenProgram: TOKEN_PROGRAM_ID,
+                recipientPreferences: this.getPreferencesAddress(sender),
+                callerPolicy: this.getCallerPolicyAddress(),
+                instructions: SYSVAR_INSTRUCTIONS_PUBKEY,
+                expiryIndex: this.getExpiryIndexAddress(Math.floor(Date.now() / 1000)),
+                rateLimit: this.getRateLimitAddress(sender),
+                denylist: this.getDenylistAddress(sender),
+                feeExempt: this.feeExempt ? this.getFeeExemptAddress(sender) : null,
+                referral: this.referral?.code ?? null,
+                referrerClaim: this.referral ? this.getClaimAddress(this.referral.referrer) : null,
+                campaign: this.campaign,
+                senderStats: this.earnPoints ? this.getSenderStatsAddress(sender) : null,
+                nftToken: this.nftMint ? getAssociatedTokenAddressSync(this.nftMint, sender) : null,
+                nftMetadata: this.nftMint ? this.getNftMetadataAddress(this.nftMint) : null,
+                stake: this.staked ? this.getStakeAddress(sender) : null,
+                receiptConfig: this.receiptTree ? this.getReceiptConfigAddress() : null,
+                receiptTreeConfig: this.receiptTree ? this.getReceiptTreeConfigAddress(this.receiptTree) : null,
+                receiptTree: this.receiptTree,
+                bubblegumProgram: this.receiptTree ? BUBBLEGUM_PROGRAM_ID : null,
+                logWrapper: this.receiptTree || this.messageLogTree ? SPL_NOOP_PROGRAM_ID : null,
+                compressionProgram: this.receiptTree || this.messageLogTree ? SPL_ACCOUNT_COMPRESSION_PROGRAM_ID : null,
+                messageLog: this.messageLogTree ? this.getMessageLogAddress() : null,
+                messageLogTree: this.messageLogTree,
+                inbox: this.getInboxAddress(sender),
+                credit: this.prepaidCredit ? this.getCreditAddress(sender) : null,
+                planSubscription: this.planSubscription ? this.getPlanSubscriptionAddress(sender) : null,
+                tokenProgram: this.tokenProgramId,
+                associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+                systemProgram: SystemProgram.programId,
             })
             .rpc();
     }
 
     /**
-     * @description Claim accumulated owner fees (owner only)
-     * @notice Only the program owner can call this function
+     * @description Send a message paying the fee from your claimable revenue share first
+     * @notice Only the part of the fee not covered by your unexpired claim is transferred from your wallet
+     * @param subject Message subject line (plain text)
+     * @param body Message content (plain text)
+     * @param priority Whether to send as a priority message (full fee with revenue share)
+     * @param clientRef Optional 16-byte caller reference echoed in the send event, e.g. an idempotency key
      * @returns Promise resolving to transaction signature
-     * @throws {Error} If caller is not owner, no claimable amount, or transfer fails
+     * @throws {Error} If the uncovered remainder exceeds your USDC balance or transaction fails
      * @example
      * ```typescript
-     * // Only works if wallet is the program owner
-     * const ownerFees = await client.getOwnerClaimable();
-     * if (ownerFees > 0) {
-     *     const tx = await client.claimOwnerShare();
-     *     console.log(`Owner claimed ${formatUSDC(ownerFees)} USDC:`, tx);
-     * }
+     * // Fee is drawn from the 0.09 USDC earned by an earlier priority send
+     * const tx = await client.sendUsingClaim('Subject', 'Body', true);
      * ```
      */
-    async claimOwnerShare(): Promise<string> {
-        const owner = this.provider.wallet.publicKey;
-        const ownerUsdcAccount = getAssociatedTokenAddressSync(
-            this.usdcMint,
-            owner
+    async sendUsingClaim(subject: string, body: string, priority: boolean, clientRef?: Uint8Array): Promise<string> {
+        const sender = this.provider.wallet.publicKey;
+        const [recipientClaimPda] = PublicKey.findProgramAddressSync(
+            [Buffer.from('claim'), sender.toBuffer()],
+            this.program.programId
         );
 
-        const mailerUsdcAccount = getAssociatedTokenAddressSync(
-            this.usdcMint,
-            this.mailerPda,
-            true
-        );
+        const senderUsdcAccount = getAssociatedTokenAddressSync(this.usdcMint, sender, false, this.tokenProgramId);
+
+        const mailerUsdcAccount = getAssociatedTokenAddressSync(this.usdcMint, this.mailerPda, true, this.tokenProgramId);
 
         return await (this.program.methods as any)
-            .claimOwnerShare()
+            .sendUsingClaim(subject, body, priority, this.encodeClientRef(clientRef))
             .accounts({
+                recipientClaim: recipientClaimPda,
                 mailer: this.mailerPda,
-                owner: owner,
-                ownerUsdcAccount,
+                stats: this.statsPda,
+                tokenConfig: this.getTokenConfigAddress(this.usdcMint),
+                priceUpdate: this.priceUpdate,
+                ownerBucket: this.getOwnerBucketAddress(this.usdcMint, 'send'),
+                mint: this.usdcMint,
+                sender: sender,
+                payer: sender,
+                senderUsdcAccount,
                 mailerUsdcAccount,
-                tokenProgram: TOKEN_PROGRAM_ID,
+                recipientPreferences: this.getPreferencesAddress(sender),
+                callerPolicy: this.getCallerPolicyAddress(),
+                instructions: SYSVAR_INSTRUCTIONS_PUBKEY,
+                expiryIndex: this.getExpiryIndexAddress(Math.floor(Date.now() / 1000)),
+                rateLimit: this.getRateLimitAddress(sender),
+                denylist: this.getDenylistAddress(sender),
+                feeExempt: this.feeExempt ? this.getFeeExemptAddress(sender) : null,
+                referral: this.referral?.code ?? null,
+                referrerClaim: this.referral ? this.getClaimAddress(this.referral.referrer) : null,
+                campaign: this.campaign,
+                senderStats: this.earnPoints ? this.getSenderStatsAddress(sender) : null,
+                nftToken: this.nftMint ? getAssociatedTokenAddressSync(this.nftMint, sender) : null,
+                nftMetadata: this.nftMint ? this.getNftMetadataAddress(this.nftMint) : null,
+                stake: this.staked ? this.getStakeAddress(sender) : null,
+                receiptConfig: this.receiptTree ? this.getReceiptConfigAddress() : null,
+                receiptTreeConfig: this.receiptTree ? this.getReceiptTreeConfigAddress(this.receiptTree) : null,
+                receiptTree: this.receiptTree,
+                bubblegumProgram: this.receiptTree ? BUBBLEGUM_PROGRAM_ID : null,
+                logWrapper: this.receiptTree || this.messageLogTree ? SPL_NOOP_PROGRAM_ID : null,
+                compressionProgram: this.receiptTree || this.messageLogTree ? SPL_ACCOUNT_COMPRESSION_PROGRAM_ID : null,
+                messageLog: this.messageLogTree ? this.getMessageLogAddress() : null,
+                messageLogTree: this.messageLogTree,
+                inbox: this.getInboxAddress(sender),
+                credit: this.prepaidCredit ? this.getCreditAddress(sender) : null,
+                planSubscription: this.planSubscription ? this.getPlanSubscriptionAddress(sender) : null,
+                tokenProgram: this.tokenProgramId,
+                associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+                systemProgram: SystemProgram.programId,
             })
             .rpc();
     }
 
     /**
-     * @description Reclaim expired revenue shares to owner account (owner only)
-     * @notice Only callable after 60-day claim period has expired
-     * @param recipient Address whose expired shares should be reclaimed
+     * @description Send a message on behalf of an account that delegated to you in the mail service
+     * @notice You pay the fee and earn any priority revenue share; the message is emitted from and to the delegator
+     * @param delegator Account whose mail service delegation names your wallet as the delegate
+     * @param mailServiceProgramId Public key of the deployed MailService program holding the delegation
+     * @param subject Message subject line (plain text)
+     * @param body Message content (plain text)
+     * @param priority Whether to send as a priority message (full fee with revenue share)
+     * @param clientRef Optional 16-byte caller reference echoed in the send event, e.g. an idempotency key
+     * @param perDelegate Authorize with your own `[delegation, delegator, you]` delegation instead of the delegator's primary one
+     * @param billDelegator Bill the standard fee to the delegator's billing allowance for this delegation (see openDelegatorBilling) instead of paying it
      * @returns Promise resolving to transaction signature
-     * @throws {Error} If caller is not owner, no expired shares, or period not expired
+     * @throws {Error} If you are not the delegator's current delegate or the transaction fails
      * @example
      * ```typescript
-     * // Check if user's shares have expired
-     * const info = await client.getRecipientClaimable(recipientAddress);
-     * if (info && info.amount > 0 && info.isExpired) {
-     *     const tx = await client.claimExpiredShares(recipientAddress);
-     *     console.log(`Reclaimed ${formatUSDC(info.amount)} USDC:`, tx);
-     * }
+     * // Post to the team inbox that delegated to this wallet
+     * const tx = await client.sendAsDelegator(teamInbox, mailServiceProgramId, 'Weekly update', 'Body');
      * ```
      */
-    async claimExpiredShares(recipient: PublicKey): Promise<string> {
+    async sendAsDelegator(
+        delegator: PublicKey,
+        mailServiceProgramId: PublicKey,
+        subject: string,
+        body: string,
+        priority: boolean = false,
+        clientRef?: Uint8Array,
+        perDelegate: boolean = false,
+        billDelegator: boolean = false
+    ): Promise<string> {
+        const sender = this.provider.wallet.publicKey;
         const [recipientClaimPda] = PublicKey.findProgramAddressSync(
-            [Buffer.from('claim'), recipient.toBuffer()],
+            [Buffer.from('claim'), sender.toBuffer()],
             this.program.programId
         );
+        const delegationPda = this.getDelegationAddress(mailServiceProgramId, delegator, perDelegate ? sender : undefined);
+
+        const senderUsdcAccount = getAssociatedTokenAddressSync(this.usdcMint, sender, false, this.tokenProgramId);
+
+        const mailerUsdcAccount = getAssociatedTokenAddressSync(this.usdcMint, this.mailerPda, true, this.tokenProgramId);
 
         return await (this.program.methods as any)
-            .claimExpiredShares()
+            .sendAsDelegator(subject, body, priority, this.encodeClientRef(clientRef))
             .accounts({
-                recipientClaim: recipientClaimPda,
-                mailer: this.mailerPda,
-                owner: this.provider.wallet.publicKey,
+                send: {
+                    recipientClaim: recipientClaimPda,
+                    mailer: this.mailerPda,
+                    stats: this.statsPda,
+                    tokenConfig: this.getTokenConfigAddress(this.usdcMint),
+                    priceUpdate: this.priceUpdate,
+                    ownerBucket: this.getOwnerBucketAddress(this.usdcMint, 'send'),
+                    mint: this.usdcMint,
+                    sender: sender,
+                    payer: sender,
+                    senderUsdcAccount,
+                    mailerUsdcAccount,
+                    recipientPreferences: this.getPreferencesAddress(sender),
+                    callerPolicy: this.getCallerPolicyAddress(),
+                    instructions: SYSVAR_INSTRUCTIONS_PUBKEY,
+                    expiryIndex: this.getExpiryIndexAddress(Math.floor(Date.now() / 1000)),
+                    rateLimit: this.getRateLimitAddress(sender),
+                    denylist: this.getDenylistAddress(sender),
+                    feeExempt: this.feeExempt ? this.getFeeExemptAddress(sender) : null,
+                    referral: this.referral?.code ?? null,
+                    referrerClaim: this.referral ? this.getClaimAddress(this.referral.referrer) : null,
+                    campaign: this.campaign,
+                    senderStats: this.earnPoints ? this.getSenderStatsAddress(sender) : null,
+                    nftToken: this.nftMint ? getAssociatedTokenAddressSync(this.nftMint, sender) : null,
+                    nftMetadata: this.nftMint ? this.getNftMetadataAddress(this.nftMint) : null,
+                    stake: this.staked ? this.getStakeAddress(sender) : null,
+                    receiptConfig: this.receiptTree ? this.getReceiptConfigAddress() : null,
+                    receiptTreeConfig: this.receiptTree ? this.getReceiptTreeConfigAddress(this.receiptTree) : null,
+                    receiptTree: this.receiptTree,
+                    bubblegumProgram: this.receiptTree ? BUBBLEGUM_PROGRAM_ID : null,
+                    logWrapper: this.receiptTree || this.messageLogTree ? SPL_NOOP_PROGRAM_ID : null,
+                    compressionProgram: this.receiptTree || this.messageLogTree ? SPL_ACCOUNT_COMPRESSION_PROGRAM_ID : null,
+                    messageLog: this.messageLogTree ? this.getMessageLogAddress() : null,
+                    messageLogTree: this.messageLogTree,
+                    inbox: this.getInboxAddress(delegator),
+                    credit: this.prepaidCredit ? this.getCreditAddress(sender) : null,
+                    planSubscription: this.planSubscription ? this.getPlanSubscriptionAddress(sender) : null,
+                    mailIdRecord: null,
+                    tokenProgram: this.tokenProgramId,
+                    associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+                    systemProgram: SystemProgram.programId,
+                },
+                delegation: delegationPda,
+                delegatorPreferences: this.getPreferencesAddress(delegator),
+                delegatorUsdcAccount: billDelegator
+                    ? getAssociatedTokenAddressSync(this.usdcMint, delegator, false, this.tokenProgramId)
+                    : null,
+                delegatorBilling: billDelegator ? this.getDelegatorBillingAddress(delegationPda) : null,
             })
             .rpc();
     }
 
     /**
-     * @description Update the base sending fee (owner only)
-     * @notice This affects both priority and standard message pricing
-     * @param newFee New fee amount in USDC (with 6 decimals)
+     * @description Let a delegate of yours bill standard sends to you, up to a limit
+     * @notice Approves the mailer PDA to draw up to limit more in fees from your USDC account.
+     * Each delegation needs its own allowance; close and reopen it to change the limit.
+     * @param mailServiceProgramId Public key of the deployed MailService program holding the delegation
+     * @param limit Most the delegate may bill in total, in USDC base units
+     * @param delegate Delegate of a per-delegate delegation; omit for your primary delegation
      * @returns Promise resolving to transaction signature
-     * @throws {Error} If caller is not owner or transaction fails
+     * @throws {Error} If the delegation does not exist or already has a billing allowance
      * @example
      * ```typescript
-     * // Set fee to 0.2 USDC (200,000 with 6 decimals)
-     * const tx = await client.setFee(200000);
-     * console.log('Fee updated:', tx);
-     * 
-     * // Priority messages now cost 0.2 USDC
-     * // Standard messages now cost 0.02 USDC (10% of 0.2)
+     * // Let the backend delegate send 100 standard messages on your account
+     * await client.openDelegatorBilling(mailServiceProgramId, 100 * 10_000, backendKey);
      * ```
      */
-    async setFee(newFee: number): Promise<string> {
+    async openDelegatorBilling(mailServiceProgramId: PublicKey, limit: number, delegate?: PublicKey): Promise<string> {
+        const delegator = this.provider.wallet.publicKey;
+        const delegation = this.getDelegationAddress(mailServiceProgramId, delegator, delegate);
         return await (this.program.methods as any)
-            .setFee(new BN(newFee))
+            .openDelegatorBilling(new BN(limit))
             .accounts({
+                billing: this.getDelegatorBillingAddress(delegation),
+                delegation,
                 mailer: this.mailerPda,
-                owner: this.provider.wallet.publicKey,
+                mint: this.usdcMint,
+                delegator,
+                delegatorUsdcAccount: getAssociatedTokenAddressSync(this.usdcMint, delegator, false, this.tokenProgramId),
+                tokenProgram: this.tokenProgramId,
+                systemProgram: SystemProgram.programId,
             })
             .rpc();
     }
 
     /**
-     * @description Get detailed information about claimable revenue shares
-     * @param recipient Address to check claimable information for
-     * @returns Promise resolving to ClaimableInfo or null if no claims exist
-     * @example
-     * ```typescript
-     * const info = await client.getRecipientClaimable(userAddress);
-     * if (info) {
-     *     console.log(`Claimable: ${formatUSDC(info.amount)} USDC`);
-     *     console.log(`Expires: ${new Date(info.expiresAt * 1000).toISOString()}`);
-     *     console.log(`Is Expired: ${info.isExpired}`);
-     * } else {
-     *     console.log('No claimable amounts found');
-     * }
-     * ```
+     * @description Withdraw a delegate's billing allowance, handing back the approval it did not spend
+     * @param mailServiceProgramId Public key of the deployed MailService program holding the delegation
+     * @param delegate Delegate of a per-delegate delegation; omit for your primary delegation
+     * @returns Promise resolving to transaction signature
+     * @throws {Error} If the delegation has no billing allowance
      */
-    async getRecipientClaimable(recipient: PublicKey): Promise<ClaimableInfo | null> {
-        try {
-            const [recipientClaimPda] = PublicKey.findProgramAddressSync(
-                [Buffer.from('claim'), recipient.toBuffer()],
-                this.program.programId
-            );
+    async closeDelegatorBilling(mailServiceProgramId: PublicKey, delegate?: PublicKey): Promise<string> {
+        const delegator = this.provider.wallet.publicKey;
+        const delegation = this.getDelegationAddress(mailServiceProgramId, delegator, delegate);
+        const billing = await this.getDelegatorBilling(delegation);
+        if (!billing) {
+            throw new Error('Billing allowance does not exist');
+        }
+        return await (this.program.methods as any)
+            .closeDelegatorBilling()
+            .accounts({
+                billing: this.getDelegatorBillingAddress(delegation),
+                mailer: this.mailerPda,
+                mint: billing.mint,
+                delegator,
+                delegatorUsdcAccount: getAssociatedTokenAddressSync(billing.mint, delegator, false, this.tokenProgramId),
+                tokenProgram: this.tokenProgramId,
+            })
+            .rpc();
+    }
 
-            const account = await (this.program.account as any).recipientClaim.fetch(recipientClaimPda);
-            const amount = account.amount.toNumber();
-            const timestamp = account.timestamp.toNumber();
-            const claimPeriodSeconds = CLAIM_PERIOD_DAYS * 24 * 60 * 60;
-            const expiresAt = timestamp + claimPeriodSeconds;
-            const currentTime = Math.floor(Date.now() / 1000);
-            
-            return {
-                amount,
-                expiresAt,
-                isExpired: amount > 0 && currentTime > expiresAt,
-            };
-        } catch {
+    /**
+     * @description Get a delegation's billing allowance
+     * @param delegation Mail service delegation account
+     * @returns Promise resolving to the allowance, or null if the delegator has not opened one
+     */
+    async getDelegatorBilling(delegation: PublicKey): Promise<DelegatorBillingInfo | null> {
+        const account = await (this.program.account as any).delegatorBilling.fetchNullable(
+            this.getDelegatorBillingAddress(delegation)
+        );
+        if (!account) {
             return null;
         }
+        return {
+            delegator: account.delegator,
+            delegation: account.delegation,
+            mint: account.mint,
+            limit: account.limit.toNumber(),
+            spent: account.spent.toNumber(),
+        };
     }
 
     /**
-     * @description Get the total USDC amount claimable by the program owner
-     * @returns Promise resolving to claimable amount in USDC (with 6 decimals)
-     * @example
-     * ```typescript
-     * const ownerClaimable = await client.getOwnerClaimable();
-     * console.log(`Owner can claim: ${formatUSDC(ownerClaimable)} USDC`);
-     * ```
+     * @description Get the PDA of a delegation's billing allowance
+     * @param delegation Mail service delegation account
+     * @returns The billing allowance address
      */
-    async getOwnerClaimable(): Promise<number> {
-        const account = await (this.program.account as any).mailerState.fetch(this.mailerPda);
-        return account.ownerClaimable.toNumber();
+    getDelegatorBillingAddress(delegation: PublicKey): PublicKey {
+        const [billingPda] = PublicKey.findProgramAddressSync(
+            [Buffer.from('billing'), delegation.toBuffer()],
+            this.program.programId
+        );
+        return billingPda;
     }
 
     /**
-     * @description Get current fee structure information
-     * @returns Promise resolving to MailerFees with fee details
-     * @example
-     * ```typescript
-     * const fees = await client.getFees();
-     * console.log(`Send Fee: ${formatUSDC(fees.sendFee)} USDC`);
-     * 
-     * // Calculate actual costs
-     * const priorityCost = fees.sendFee; // Full fee
-     * const standardCost = Math.floor(fees.sendFee * 0.1); // 10% fee
-     * ```
+     * @description Get the PDA of a mail service delegation
+     * @param mailServiceProgramId Public key of the deployed MailService program holding the delegation
+     * @param delegator Account that delegated
+     * @param delegate Delegate of a per-delegate delegation; omit for the delegator's primary delegation
+     * @returns The delegation address
      */
-    async getFees(): Promise<MailerFees> {
-        const account = await (this.program.account as any).mailerState.fetch(this.mailerPda);
-        return {
-            sendFee: account.sendFee.toNumber(),
-        };
+    getDelegationAddress(mailServiceProgramId: PublicKey, delegator: PublicKey, delegate?: PublicKey): PublicKey {
+        const [delegationPda] = PublicKey.findProgramAddressSync(
+            delegate
+                ? [Buffer.from('delegation'), delegator.toBuffer(), delegate.toBuffer()]
+                : [Buffer.from('delegation'), delegator.toBuffer()],
+            mailServiceProgramId
+        );
+        return delegationPda;
     }
 
     /**
-     * @description Get current fees formatted as human-readable strings
-     * @returns Promise resolving to formatted fee strings
+     * @description Approve the mailer PDA to pull up to an amount of USDC fees from your account
+     * @notice Enables signed sends. Replaces any existing approval on the account, including
+     * allowance held by open sessions and delegate billing allowances.
+     * @param amount Allowance in USDC base units
+     * @returns Promise resolving to transaction signature
      * @example
      * ```typescript
-     * const formatted = await client.getFeesFormatted();
-     * console.log(`Current send fee: ${formatted.sendFee}`);
+     * // Pre-authorize 100 standard sends
+     * await client.approveFeeAllowance(100 * 10_000);
      * ```
      */
-    async getFeesFormatted(): Promise<{ sendFee: string }> {
-        const fees = await this.getFees();
-        return {
-            sendFee: formatUSDC(fees.sendFee) + ' USDC',
-        };
+    async approveFeeAllowance(amount: number): Promise<string> {
+        const owner = this.provider.wallet.publicKey;
+        const usdcAccount = getAssociatedTokenAddressSync(this.usdcMint, owner, false, this.tokenProgramId);
+        return await this.provider.sendAndConfirm(
+            new Transaction().add(
+                createApproveInstruction(usdcAccount, this.mailerPda, owner, BigInt(amount), [], this.tokenProgramId)
+            )
+        );
+    }
+
+    /**
+     * @description Withdraw the mailer PDA's fee allowance on your USDC account
+     * @returns Promise resolving to transaction signature
+     */
+    async revokeFeeAllowance(): Promise<string> {
+        const owner = this.provider.wallet.publicKey;
+        const usdcAccount = getAssociatedTokenAddressSync(this.usdcMint, owner, false, this.tokenProgramId);
+        return await this.provider.sendAndConfirm(
+            new Transaction().add(createRevokeInstruction(usdcAccount, owner, [], this.tokenProgramId))
+        );
+    }
+
+    /**
+     * @description Get how much USDC the mailer PDA may still pull from an account for fees
+     * @param owner Wallet whose USDC account to check
+     * @returns Remaining allowance in USDC base units, 0 if the mailer is not the account's delegate
+     */
+    async getFeeAllowance(owner: PublicKey): Promise<number> {
+        const usdcAccount = getAssociatedTokenAddressSync(this.usdcMint, owner, false, this.tokenProgramId);
+        const account = await getAccount(this.provider.connection, usdcAccount, undefined, this.tokenProgramId);
+        return account.delegate?.equals(this.mailerPda) ? Number(account.delegatedAmount) : 0;
+    }
+
+    /**
+     * @description Send a standard message paid for with proof-of-work instead of USDC
+     * @notice Solves the challenge locally before sending; only available while the owner has set
+     *         a non-zero difficulty. The wallet needs SOL for the transaction fee and, on its first
+     *         proof-of-work send, the rent of its counter account
+     * @param subject Message subject line
+     * @param body Message content
+     * @param clientRef Optional 16-byte caller reference echoed in the send event, e.g. an idempotency key
+     * @returns Promise resolving to transaction signature
+     * @throws {Error} If proof-of-work sends are disabled or transaction fails
+     * @example
+     * ```typescript
+     * if ((await client.getPowDifficulty()) > 0) {
+     *     await client.sendWithPow('Hello', 'Sent without USDC');
+     * }
+     * ```
+     */
+    async sendWithPow(subject: string, body: string, clientRef?: Uint8Array): Promise<string> {
+        const sender = this.provider.wallet.publicKey;
+        const difficulty = await this.getPowDifficulty();
+        if (difficulty === 0) {
+            throw new Error('Proof-of-work sends are disabled');
+        }
+
+        const powState = this.getPowStateAddress(sender);
+        const state = await (this.program.account as any).powState.fetchNullable(powState);
+        const counter = state ? BigInt(state.counter.toString()) : 0n;
+        const nonce = solvePow(sender, counter, subject, body, difficulty);
+
+        return await (this.program.methods as any)
+            .sendWithPow(subject, body, new BN(nonce.toString()), this.encodeClientRef(clientRef))
+            .accounts({
+                mailer: this.mailerPda,
+                stats: this.statsPda,
+                powState,
+                sender,
+                payer: sender,
+                recipientPreferences: this.getPreferencesAddress(sender),
+                callerPolicy: this.getCallerPolicyAddress(),
+                instructions: SYSVAR_INSTRUCTIONS_PUBKEY,
+                rateLimit: this.getRateLimitAddress(sender),
+                denylist: this.getDenylistAddress(sender),
+                inbox: this.getInboxAddress(sender),
+                systemProgram: SystemProgram.programId,
+            })
+            .rpc();
+    }
+
+    /**
+     * @description Set the proof-of-work difficulty for fee-less standard sends (owner only)
+     * @param difficulty Required leading zero bits (max 32); 0 disables proof-of-work sends
+     * @returns Promise resolving to transaction signature
+     * @throws {Error} If caller is not owner or difficulty exceeds the cap
+     * @example
+     * ```typescript
+     * // About a million hashes per message
+     * await client.setPowDifficulty(20);
+     * ```
+     */
+    async setPowDifficulty(difficulty: number): Promise<string> {
+        return await (this.program.methods as any)
+            .setPowDifficulty(difficulty)
+            .accounts({
+                mailer: this.mailerPda,
+                owner: this.provider.wallet.publicKey,
+            })
+            .rpc();
+    }
+
+    /**
+     * @description Get the proof-of-work difficulty for fee-less standard sends
+     * @returns Promise resolving to the required leading zero bits, or 0 when disabled
+     */
+    async getPowDifficulty(): Promise<number> {
+        const account = await (this.program.account as any).mailerState.fetch(this.mailerPda);
+        return account.powDifficulty;
+    }
+
+    /**
+     * @description Set the longest subject and body a send accepts (owner only)
+     * @notice Lengths are in UTF-8 bytes; longer sends fail with `MessageTooLarge`
+     * @param maxSubjectLen Longest accepted subject
+     * @param maxBodyLen Longest accepted body
+     * @returns Promise resolving to transaction signature
+     * @throws {Error} If caller is not owner or either limit is zero
+     * @example
+     * ```typescript
+     * await client.setMessageLimits(120, 600);
+     * ```
+     */
+    async setMessageLimits(maxSubjectLen: number, maxBodyLen: number): Promise<string> {
+        return await (this.program.methods as any)
+            .setMessageLimits(maxSubjectLen, maxBodyLen)
+            .accounts({
+                mailer: this.mailerPda,
+                owner: this.provider.wallet.publicKey,
+            })
+            .rpc();
+    }
+
+    /**
+     * @description Get the longest subject and body a send accepts, and the daily send cap
+     * @returns Promise resolving to the limits in UTF-8 bytes and messages per day
+     */
+    async getMessageLimits(): Promise<MessageLimits> {
+        const account = await (this.program.account as any).mailerState.fetch(this.mailerPda);
+        return {
+            maxSubjectLen: account.maxSubjectLen,
+            maxBodyLen: account.maxBodyLen,
+            maxMessagesPerDay: account.maxMessagesPerDay,
+        };
+    }
+
+    /**
+     * @description Cap how many messages one sender may send per day (owner only)
+     * @notice Every send path counts, a batch counting each of its mail IDs; sends past the cap
+     *         fail with `RateLimitExceeded` until the sender's one-day window ends
+     * @param maxMessagesPerDay Messages allowed per sender per day, or 0 for no limit
+     * @returns Promise resolving to transaction signature
+     * @throws {Error} If caller is not owner
+     * @example
+     * ```typescript
+     * await client.setRateLimit(500);
+     * ```
+     */
+    async setRateLimit(maxMessagesPerDay: number): Promise<string> {
+        return await (this.program.methods as any)
+            .setRateLimit(maxMessagesPerDay)
+            .accounts({
+                mailer: this.mailerPda,
+                owner: this.provider.wallet.publicKey,
+            })
+            .rpc();
+    }
+
+    /**
+     * @description Control which programs may send messages via CPI (owner only)
+     * @notice Applies to all recipients before their own origin policy; wallet sends are always accepted.
+     *         Until this is first called any program may send via CPI
+     * @param programs Program IDs allowed to send via CPI (max 32)
+     * @param allowAll If true CPI sends from any program are accepted and `programs` is ignored
+     * @returns Promise resolving to transaction signature
+     * @throws {Error} If caller is not owner, more than 32 programs are listed or transaction fails
+     * @example
+     * ```typescript
+     * // Only the DAO program may post mail programmatically
+     * await client.setCallerPolicy([daoProgramId], false);
+     * ```
+     */
+    async setCallerPolicy(programs: PublicKey[], allowAll: boolean): Promise<string> {
+        return await (this.program.methods as any)
+            .setCallerPolicy(programs, allowAll)
+            .accounts({
+                mailer: this.mailerPda,
+                callerPolicy: this.getCallerPolicyAddress(),
+                owner: this.provider.wallet.publicKey,
+                systemProgram: SystemProgram.programId,
+            })
+            .rpc();
+    }
+
+    /**
+     * @description Control which programs may deliver messages to you via CPI
+     * @notice Messages sent directly from a wallet are always accepted
+     * @param originPrograms Program IDs to allow or deny (max 16)
+     * @param allowlist If true only listed programs are accepted; if false listed programs are rejected
+     * @returns Promise resolving to transaction signature
+     * @throws {Error} If more than 16 programs are listed or transaction fails
+     * @example
+     * ```typescript
+     * // Block notifications generated by a noisy protocol
+     * await client.setOriginPolicy([noisyProgramId], false);
+     * ```
+     */
+    async setOriginPolicy(originPrograms: PublicKey[], allowlist: boolean): Promise<string> {
+        const recipient = this.provider.wallet.publicKey;
+        return await (this.program.methods as any)
+            .setOriginPolicy(originPrograms, allowlist)
+            .accounts({
+                recipientPreferences: this.getPreferencesAddress(recipient),
+                recipient: recipient,
+                systemProgram: SystemProgram.programId,
+            })
+            .rpc();
+    }
+
+    /**
+     * @description Choose how your revenue shares are paid out
+     * @notice 'lump' (default) pays the whole claim until it expires after 60 days; 'streaming'
+     *         unlocks it linearly over 60 days and never expires, each deposit restarting the
+     *         60 days for whatever is still locked. Only while the claim is empty
+     * @param mode Payout mode
+     * @returns Promise resolving to transaction signature
+     * @throws {Error} If the claim still holds an unclaimed amount
+     * @example
+     * ```typescript
+     * await client.setClaimMode('streaming');
+     * ```
+     */
+    async setClaimMode(mode: MailerClaimMode): Promise<string> {
+        const recipient = this.provider.wallet.publicKey;
+        const [recipientClaimPda] = PublicKey.findProgramAddressSync(
+            [Buffer.from('claim'), recipient.toBuffer()],
+            this.program.programId
+        );
+
+        return await (this.program.methods as any)
+            .setClaimMode({ [mode]: {} })
+            .accounts({
+                recipientClaim: recipientClaimPda,
+                recipient,
+                systemProgram: SystemProgram.programId,
+            })
+            .rpc();
+    }
+
+    /**
+     * @description Claim your accumulated revenue share from priority messages
+     * @notice Must be called within 60 days of earning shares, or they expire. Streaming
+     *         claims pay out only the part unlocked so far
+     * @returns Promise resolving to transaction signature
+     * @throws {Error} If no claimable amount, claim period expired, or transfer fails
+     * @example
+     * ```typescript
+     * // Check claimable amount first
+     * const info = await client.getRecipientClaimable(wallet.publicKey);
+     * if (info && info.amount > 0 && !info.isExpired) {
+     *     const tx = await client.claimRecipientShare();
+     *     console.log(`Claimed ${formatUSDC(info.amount)} USDC:`, tx);
+     * }
+     * ```
+     */
+    async claimRecipientShare(): Promise<string> {
+        const recipient = this.provider.wallet.publicKey;
+        const [recipientClaimPda] = PublicKey.findProgramAddressSync(
+            [Buffer.from('claim'), recipient.toBuffer()],
+            this.program.programId
+        );
+
+        const recipientUsdcAccount = getAssociatedTokenAddressSync(this.usdcMint, recipient, false, this.tokenProgramId);
+
+        const mailerUsdcAccount = getAssociatedTokenAddressSync(this.usdcMint, this.mailerPda, true, this.tokenProgramId);
+
+        const claim = await (this.program.account as any).recipientClaim.fetch(recipientClaimPda);
+
+        return await (this.program.methods as any)
+            .claimRecipientShare()
+            .accounts({
+                recipientClaim: recipientClaimPda,
+                mailer: this.mailerPda,
+                mint: this.usdcMint,
+                recipient: recipient,
+                recipientUsdcAccount,
+                mailerUsdcAccount,
+                expiryIndex: this.getExpiryIndexAddress(claim.timestamp.toNumber()),
+                denylist: this.getDenylistAddress(recipient),
+                tokenProgram: this.tokenProgramId,
+            })
+            .rpc();
+    }
+
+    /**
+     * @description Register the automation thread allowed to auto-claim (owner only)
+     * @param autoClaimer Thread signer, e.g. a Clockwork thread PDA, or null to disable auto-claiming
+     * @returns Promise resolving to transaction signature
+     * @throws {Error} If caller is not owner
+     */
+    async setAutoClaimer(autoClaimer: PublicKey | null): Promise<string> {
+        return await (this.program.methods as any)
+            .setAutoClaimer(autoClaimer ?? PublicKey.default)
+            .accounts({
+                mailer: this.mailerPda,
+                owner: this.provider.wallet.publicKey,
+            })
+            .rpc();
+    }
+
+    /**
+     * @description Opt in to (or out of) having the registered auto-claimer pay out your claim
+     * @notice The auto-claimer can only pay a lump claim to your own token account, during the
+     *         last 3 days before it expires
+     * @param enabled Whether to allow auto-claiming
+     * @returns Promise resolving to transaction signature
+     * @example
+     * ```typescript
+     * await client.enableAutoClaim(true);
+     * ```
+     */
+    async enableAutoClaim(enabled: boolean): Promise<string> {
+        const recipient = this.provider.wallet.publicKey;
+        return await (this.program.methods as any)
+            .enableAutoClaim(enabled)
+            .accounts({
+                recipientClaim: this.getClaimAddress(recipient),
+                recipient,
+                systemProgram: SystemProgram.programId,
+            })
+            .rpc();
+    }
+
+    /**
+     * @description Pay an opted-in recipient's expiring claim out to their token account
+     * @notice The client's wallet must be the registered auto-claimer; automation threads build
+     *         the same instruction with the thread as signer
+     * @param recipient Recipient whose claim to pay out
+     * @returns Promise resolving to transaction signature
+     * @throws {Error} If the signer is not the auto-claimer, the recipient has not opted in,
+     *         or the claim is not within 3 days of expiry
+     */
+    async autoClaim(recipient: PublicKey): Promise<string> {
+        const recipientClaimPda = this.getClaimAddress(recipient);
+        const claim = await (this.program.account as any).recipientClaim.fetch(recipientClaimPda);
+
+        return await (this.program.methods as any)
+            .autoClaim()
+            .accounts({
+                recipientClaim: recipientClaimPda,
+                mailer: this.mailerPda,
+                autoClaimer: this.provider.wallet.publicKey,
+                recipient,
+                mint: this.usdcMint,
+                recipientUsdcAccount: getAssociatedTokenAddressSync(this.usdcMint, recipient, false, this.tokenProgramId),
+                mailerUsdcAccount: getAssociatedTokenAddressSync(this.usdcMint, this.mailerPda, true, this.tokenProgramId),
+                expiryIndex: this.getExpiryIndexAddress(claim.timestamp.toNumber()),
+                denylist: this.getDenylistAddress(recipient),
+                tokenProgram: this.tokenProgramId,
+            })
+            .rpc();
+    }
+
+    /**
+     * @description Build an unsigned send transaction for another wallet to sign
+     * @notice For Solana Pay transaction requests: the wallet scanning the code is the sender
+     *         and fee payer. Serve the result with `createTransactionRequestResponse`
+     * @param sender Wallet account from the transaction request
+     * @param subject Message subject line
+     * @param body Message content
+     * @param priority Send as priority (full fee, revenue share) instead of standard
+     * @param sponsor Optional account paying the transaction fee and rent instead of the sender, so
+     *        senders holding only fee tokens need no SOL; it must also sign the transaction
+     * @returns Promise resolving to a transaction with fee payer and recent blockhash set
+     * @example
+     * ```typescript
+     * const account = parseTransactionRequestAccount(req.body);
+     * const tx = await client.buildSendTransaction(account, 'Hello', 'From my phone', true);
+     * res.json(createTransactionRequestResponse(tx, 'Send a priority message'));
+     * ```
+     */
+    async buildSendTransaction(
+        sender: PublicKey,
+        subject: string,
+        body: string,
+        priority: boolean = false,
+        sponsor?: PublicKey
+    ): Promise<Transaction> {
+        const [recipientClaimPda] = PublicKey.findProgramAddressSync(
+            [Buffer.from('claim'), sender.toBuffer()],
+            this.program.programId
+        );
+
+        const senderUsdcAccount = getAssociatedTokenAddressSync(this.usdcMint, sender, false, this.tokenProgramId);
+
+        const mailerUsdcAccount = getAssociatedTokenAddressSync(this.usdcMint, this.mailerPda, true, this.tokenProgramId);
+
+        const ix = await (this.program.methods as any)[priority ? 'sendPriority' : 'send'](subject, body, null, null, null)
+            .accounts({
+                recipientClaim: recipientClaimPda,
+                mailer: this.mailerPda,
+                tokenConfig: this.getTokenConfigAddress(this.usdcMint),
+                priceUpdate: this.priceUpdate,
+                ownerBucket: this.getOwnerBucketAddress(this.usdcMint, 'send'),
+                mint: this.usdcMint,
+                sender: sender,
+                payer: sponsor ?? sender,
+                senderUsdcAccount,
+                mailerUsdcAccount,
+                recipientPreferences: this.getPreferencesAddress(sender),
+                callerPolicy: this.getCallerPolicyAddress(),
+                instructions: SYSVAR_INSTRUCTIONS_PUBKEY,
+                expiryIndex: this.getExpiryIndexAddress(Math.floor(Date.now() / 1000)),
+                rateLimit: this.getRateLimitAddress(sender),
+                denylist: this.getDenylistAddress(sender),
+                feeExempt: this.feeExempt ? this.getFeeExemptAddress(sender) : null,
+                referral: this.referral?.code ?? null,
+                referrerClaim: this.referral ? this.getClaimAddress(this.referral.referrer) : null,
+                campaign: this.campaign,
+                senderStats: this.earnPoints ? this.getSenderStatsAddress(sender) : null,
+                nftToken: this.nftMint ? getAssociatedTokenAddressSync(this.nftMint, sender) : null,
+                nftMetadata: this.nftMint ? this.getNftMetadataAddress(this.nftMint) : null,
+                stake: this.staked ? this.getStakeAddress(sender) : null,
+                receiptConfig: this.receiptTree ? this.getReceiptConfigAddress() : null,
+                receiptTreeConfig: this.receiptTree ? this.getReceiptTreeConfigAddress(this.receiptTree) : null,
+                receiptTree: this.receiptTree,
+                bubblegumProgram: this.receiptTree ? BUBBLEGUM_PROGRAM_ID : null,
+                logWrapper: this.receiptTree || this.messageLogTree ? SPL_NOOP_PROGRAM_ID : null,
+                compressionProgram: this.receiptTree || this.messageLogTree ? SPL_ACCOUNT_COMPRESSION_PROGRAM_ID : null,
+                messageLog: this.messageLogTree ? this.getMessageLogAddress() : null,
+                messageLogTree: this.messageLogTree,
+                inbox: this.getInboxAddress(sender),
+                credit: this.prepaidCredit ? this.getCreditAddress(sender) : null,
+                planSubscription: this.planSubscription ? this.getPlanSubscriptionAddress(sender) : null,
+                tokenProgram: this.tokenProgramId,
+                associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+                systemProgram: SystemProgram.programId,
+            })
+            .instruction();
+
+        return await this.toUnsignedTransaction(sponsor ?? sender, ix);
+    }
+
+    /**
+     * @description Build an unsigned revenue share claim transaction for another wallet to sign
+     * @notice For Solana Pay transaction requests: the wallet scanning the code is the recipient
+     * @param recipient Wallet account from the transaction request
+     * @returns Promise resolving to a transaction with fee payer and recent blockhash set
+     * @throws {Error} If the recipient has no claim account
+     */
+    async buildClaimTransaction(recipient: PublicKey): Promise<Transaction> {
+        const [recipientClaimPda] = PublicKey.findProgramAddressSync(
+            [Buffer.from('claim'), recipient.toBuffer()],
+            this.program.programId
+        );
+
+        const recipientUsdcAccount = getAssociatedTokenAddressSync(this.usdcMint, recipient, false, this.tokenProgramId);
+
+        const mailerUsdcAccount = getAssociatedTokenAddressSync(this.usdcMint, this.mailerPda, true, this.tokenProgramId);
+
+        const claim = await (this.program.account as any).recipientClaim.fetch(recipientClaimPda);
+
+        const ix = await (this.program.methods as any)
+            .claimRecipientShare()
+            .accounts({
+                recipientClaim: recipientClaimPda,
+                mailer: this.mailerPda,
+                mint: this.usdcMint,
+                recipient: recipient,
+                recipientUsdcAccount,
+                mailerUsdcAccount,
+                expiryIndex: this.getExpiryIndexAddress(claim.timestamp.toNumber()),
+                denylist: this.getDenylistAddress(recipient),
+                tokenProgram: this.tokenProgramId,
+            })
+            .instruction();
+
+        return await this.toUnsignedTransaction(recipient, ix);
+    }
+
+    private async toUnsignedTransaction(feePayer: PublicKey, ix: anchor.web3.TransactionInstruction): Promise<Transaction> {
+        const { blockhash, lastValidBlockHeight } = await this.provider.connection.getLatestBlockhash();
+        return new Transaction({ feePayer, blockhash, lastValidBlockHeight }).add(ix);
+    }
+
+    /**
+     * @description Close your fully claimed revenue share account and refund its rent
+     * @notice Only succeeds once the claimable amount is zero; rent goes back to whoever funded the account
+     *         and the account is recreated on the next priority send
+     * @returns Promise resolving to transaction signature
+     * @throws {Error} If the claim still holds an unclaimed amount or does not exist
+     * @example
+     * ```typescript
+     * await client.claimRecipientShare();
+     * const tx = await client.closeClaim();
+     * console.log('Claim account closed:', tx);
+     * ```
+     */
+    async closeClaim(): Promise<string> {
+        const recipient = this.provider.wallet.publicKey;
+        const [recipientClaimPda] = PublicKey.findProgramAddressSync(
+            [Buffer.from('claim'), recipient.toBuffer()],
+            this.program.programId
+        );
+
+        // Rent is refunded to whoever funded the claim (sender or relayer)
+        const claim = await (this.program.account as any).recipientClaim.fetch(recipientClaimPda);
+
+        return await (this.program.methods as any)
+            .closeClaim()
+            .accounts({
+                recipientClaim: recipientClaimPda,
+                recipient: recipient,
+                rentPayer: claim.rentPayer,
+            })
+            .rpc();
+    }
+
+    /**
+     * @description Grow a recipient claim created by an earlier program version to the current layout
+     * @notice Permissionless; the connected wallet pays the extra rent. Current claims are left unchanged
+     * @param recipient Recipient whose claim to migrate (defaults to the connected wallet)
+     * @returns Promise resolving to transaction signature
+     * @example
+     * ```typescript
+     * const tx = await client.migrateClaim(recipient);
+     * ```
+     */
+    async migrateClaim(recipient: PublicKey = this.provider.wallet.publicKey): Promise<string> {
+        const [recipientClaimPda] = PublicKey.findProgramAddressSync(
+            [Buffer.from('claim'), recipient.toBuffer()],
+            this.program.programId
+        );
+
+        return await (this.program.methods as any)
+            .migrateClaim(recipient)
+            .accounts({
+                recipientClaim: recipientClaimPda,
+                payer: this.provider.wallet.publicKey,
+                systemProgram: SystemProgram.programId,
+            })
+            .rpc();
+    }
+
+    /**
+     * @description Withdraw an owner share bucket to its destination (owner only)
+     * @notice Only the program owner can call this function; funds go to the bucket's destination
+     * @param source Fee source bucket to withdraw (defaults to send fees)
+     * @returns Promise resolving to transaction signature
+     * @throws {Error} If caller is not owner, no claimable amount, or transfer fails
+     * @example
+     * ```typescript
+     * // Only works if wallet is the program owner
+     * const ownerFees = await client.getOwnerClaimable();
+     * if (ownerFees > 0) {
+     *     const tx = await client.claimOwnerShare();
+     *     console.log(`Owner claimed ${formatUSDC(ownerFees)} USDC:`, tx);
+     * }
+     * // Expired shares are withdrawn separately
+     * await client.claimOwnerShare('expired');
+     * ```
+     */
+    async claimOwnerShare(source: MailerFeeSource = 'send'): Promise<string> {
+        const owner = this.provider.wallet.publicKey;
+        const bucket = await this.getOwnerBucket(this.usdcMint, source);
+        if (!bucket) {
+            throw new Error(`No ${source} owner bucket for mint ${this.usdcMint.toString()}`);
+        }
+        const destinationUsdcAccount = getAssociatedTokenAddressSync(this.usdcMint, bucket.destination, false, this.tokenProgramId);
+
+        const mailerUsdcAccount = getAssociatedTokenAddressSync(this.usdcMint, this.mailerPda, true, this.tokenProgramId);
+
+        return await (this.program.methods as any)
+            .claimOwnerShare()
+            .accounts({
+                mailer: this.mailerPda,
+                ownerBucket: this.getOwnerBucketAddress(this.usdcMint, source),
+                mint: this.usdcMint,
+                owner: owner,
+                destinationUsdcAccount,
+                mailerUsdcAccount,
+                tokenProgram: this.tokenProgramId,
+            })
+            .rpc();
+    }
+
+    /**
+     * @description Sweep expired revenue shares to the owner account (permissionless crank)
+     * @notice Callable by anyone after the 60-day claim period; the caller earns a small USDC bounty
+     * @param recipient Address whose expired shares should be swept
+     * @returns Promise resolving to transaction signature
+     * @throws {Error} If no expired shares or period not expired
+     * @example
+     * ```typescript
+     * // Check if user's shares have expired
+     * const info = await client.getRecipientClaimable(recipientAddress);
+     * if (info && info.amount > 0 && info.isExpired) {
+     *     const tx = await client.claimExpiredShares(recipientAddress);
+     *     console.log(`Swept ${formatUSDC(info.amount)} USDC:`, tx);
+     * }
+     * ```
+     */
+    async claimExpiredShares(recipient: PublicKey): Promise<string> {
+        const cranker = this.provider.wallet.publicKey;
+        const [recipientClaimPda] = PublicKey.findProgramAddressSync(
+            [Buffer.from('claim'), recipient.toBuffer()],
+            this.program.programId
+        );
+
+        const crankerUsdcAccount = getAssociatedTokenAddressSync(this.usdcMint, cranker, false, this.tokenProgramId);
+
+        const mailerUsdcAccount = getAssociatedTokenAddressSync(this.usdcMint, this.mailerPda, true, this.tokenProgramId);
+
+        const claim = await (this.program.account as any).recipientClaim.fetch(recipientClaimPda);
+
+        return await (this.program.methods as any)
+            .claimExpiredShares()
+            .accounts({
+                recipientClaim: recipientClaimPda,
+                mailer: this.mailerPda,
+                ownerBucket: this.getOwnerBucketAddress(this.usdcMint, 'expired'),
+                mint: this.usdcMint,
+                cranker: cranker,
+                crankerUsdcAccount,
+                mailerUsdcAccount,
+                expiryIndex: this.getExpiryIndexAddress(claim.timestamp.toNumber()),
+                tokenProgram: this.tokenProgramId,
+            })
+            .rpc();
+    }
+
+    /**
+     * @description Sweep expired revenue shares for many recipients in one transaction
+     * @notice Unexpired or empty claims in the list are skipped; the bounty is paid once for the batch.
+     * Fetches each claim to pass the expiry bucket it is listed in
+     * @param recipients Addresses whose expired shares should be swept
+     * @returns Promise resolving to transaction signature
+     * @throws {Error} If none of the claims could be swept
+     * @example
+     * ```typescript
+     * const tx = await client.claimExpiredSharesBatch([alice, bob, carol]);
+     * console.log('Batch sweep:', tx);
+     * ```
+     */
+    async claimExpiredSharesBatch(recipients: PublicKey[]): Promise<string> {
+        const cranker = this.provider.wallet.publicKey;
+        const crankerUsdcAccount = getAssociatedTokenAddressSync(this.usdcMint, cranker, false, this.tokenProgramId);
+
+        const mailerUsdcAccount = getAssociatedTokenAddressSync(this.usdcMint, this.mailerPda, true, this.tokenProgramId);
+
+        // Each claim is followed by the expiry bucket it is listed in
+        const claimPdas = recipients.map((recipient) => this.getClaimAddress(recipient));
+        const claims = await (this.program.account as any).recipientClaim.fetchMultiple(claimPdas);
+        const remainingAccounts = claimPdas.flatMap((pubkey, i) => [
+            { pubkey, isSigner: false, isWritable: true },
+            {
+                pubkey: this.getExpiryIndexAddress(claims[i] ? claims[i].timestamp.toNumber() : 0),
+                isSigner: false,
+                isWritable: true,
+            },
+        ]);
+
+        return await (this.program.methods as any)
+            .claimExpiredSharesBatch()
+            .accounts({
+                mailer: this.mailerPda,
+                ownerBucket: this.getOwnerBucketAddress(this.usdcMint, 'expired'),
+                mint: this.usdcMint,
+                cranker: cranker,
+                crankerUsdcAccount,
+                mailerUsdcAccount,
+                tokenProgram: this.tokenProgramId,
+            })
+            .remainingAccounts(remainingAccounts)
+            .rpc();
+    }
+
+    /**
+     * @description Emit `ClaimExpiringSoon` events for recipients' claims nearing expiry
+     * @notice Permissionless; empty, streaming, expired or distant claims in the list are skipped
+     * @param recipients Addresses whose claims should be checked
+     * @param withinDays Notice window before expiry, 1 to 60 days
+     * @returns Promise resolving to transaction signature
+     * @example
+     * ```typescript
+     * const tx = await client.pingClaims([alice, bob, carol], 7);
+     * ```
+     */
+    async pingClaims(recipients: PublicKey[], withinDays: number): Promise<string> {
+        return await (this.program.methods as any)
+            .pingClaims(withinDays)
+            .accounts({})
+            .remainingAccounts(
+                recipients.map((recipient) => ({
+                    pubkey: this.getClaimAddress(recipient),
+                    isSigner: false,
+                    isWritable: false,
+                }))
+            )
+            .rpc();
+    }
+
+    /**
+     * @description Price a mint's sends from a Pyth feed instead of its fixed fee (owner only)
+     * @notice Sends in the mint are then charged the token equivalent of the USD fee and
+     *         must include a fresh price update (see setPriceUpdateAccount)
+     * @param mint Token mint to price
+     * @param priceFeedId Pyth feed ID as hex (with or without 0x), or null to revert to the fixed fee
+     * @returns Promise resolving to transaction signature
+     * @throws {Error} If caller is not owner or the feed ID is not 32 bytes
+     * @example
+     * ```typescript
+     * // SOL/USD
+     * await client.setPriceFeed(wsolMint, '0xef0d8b6fda2ceba41da15d4095d1da392a0d2f8ed0c6c7bc0f4cfac8c280b56d');
+     * ```
+     */
+    async setPriceFeed(mint: PublicKey, priceFeedId: string | null): Promise<string> {
+        const feedId = priceFeedId === null
+            ? Buffer.alloc(32)
+            : Buffer.from(priceFeedId.replace(/^0x/, ''), 'hex');
+        if (feedId.length !== 32) {
+            throw new Error('Pyth feed ID must be 32 bytes');
+        }
+        return await (this.program.methods as any)
+            .setPriceFeed(Array.from(feedId))
+            .accounts({
+                tokenConfig: this.getTokenConfigAddress(mint),
+                mailer: this.mailerPda,
+                owner: this.provider.wallet.publicKey,
+            })
+            .rpc();
+    }
+
+    /**
+     * @description Set the priority send fee for oracle-priced mints, in US cents (owner only)
+     * @param usdFeeCents Fee in cents (10 = $0.10); standard sends are charged 10% of it
+     * @returns Promise resolving to transaction signature
+     * @throws {Error} If caller is not owner, the fee is zero (`InvalidFee`), or transaction fails
+     * @example
+     * ```typescript
+     * await client.setUsdFee(25); // $0.25 per priority message
+     * ```
+     */
+    async setUsdFee(usdFeeCents: number): Promise<string> {
+        return await (this.program.methods as any)
+            .setUsdFee(new BN(usdFeeCents))
+            .accounts({
+                mailer: this.mailerPda,
+                owner: this.provider.wallet.publicKey,
+            })
+            .rpc();
+    }
+
+    /**
+     * @description Get the priority send fee for oracle-priced mints
+     * @returns Promise resolving to the fee in US cents
+     */
+    async getUsdFee(): Promise<number> {
+        const account = await (this.program.account as any).mailerState.fetch(this.mailerPda);
+        return account.usdFeeCents.toNumber();
+    }
+
+    /**
+     * @description Use a Pyth price update account for subsequent sends
+     * @notice Required when the client's mint has a price feed. Post a fresh update with the
+     *         Pyth receiver (e.g. `@pythnetwork/pyth-solana-receiver`) before sending;
+     *         updates older than 60 seconds are rejected
+     * @param priceUpdate PriceUpdateV2 account for the mint's feed, or null for fixed-fee mints
+     * @example
+     * ```typescript
+     * client.setPriceUpdateAccount(priceUpdateAccount);
+     * await client.sendPriority('Subject', 'Body');
+     * ```
+     */
+    setPriceUpdateAccount(priceUpdate: PublicKey | null): void {
+        this.priceUpdate = priceUpdate;
+    }
+
+    /**
+     * @description Pass the connected wallet's fee exemption with subsequent sends
+     * @notice Only takes effect once the owner has exempted the wallet with `setFeeExempt`;
+     *         until then sends fail because the exemption account does not exist
+     * @param enabled Send fee-free instead of paying the fee
+     * @example
+     * ```typescript
+     * supportBotClient.useFeeExemption(true);
+     * await supportBotClient.send('Ticket #42', 'We are on it');
+     * ```
+     */
+    useFeeExemption(enabled: boolean): void {
+        this.feeExempt = enabled;
+    }
+
+    /**
+     * @description Let an address send without paying fees, or make it pay again (owner only)
+     * @notice Exempt sends record no fee or revenue share and emit `FeeExemptSend` for accounting;
+     *         tips and spam deposits are still paid
+     * @param address Sender to exempt, e.g. a support bot or partner integration
+     * @param exempt Grant the exemption instead of revoking it
+     * @returns Promise resolving to transaction signature
+     * @throws {Error} If caller is not owner or the address is already in the requested state
+     */
+    async setFeeExempt(address: PublicKey, exempt: boolean): Promise<string> {
+        const feeExempt = this.getFeeExemptAddress(address);
+        const owner = this.provider.wallet.publicKey;
+
+        if (exempt) {
+            return await (this.program.methods as any)
+                .grantFeeExemption(address)
+                .accounts({ feeExempt, mailer: this.mailerPda, owner, systemProgram: SystemProgram.programId })
+                .rpc();
+        }
+        return await (this.program.methods as any)
+            .revokeFeeExemption()
+            .accounts({ feeExempt, mailer: this.mailerPda, owner })
+            .rpc();
+    }
+
+    /**
+     * @description Check whether an address sends without paying fees
+     * @param address Wallet address
+     * @returns Promise resolving to whether the owner has exempted the address
+     */
+    async isFeeExempt(address: PublicKey): Promise<boolean> {
+        return (await this.provider.connection.getAccountInfo(this.getFeeExemptAddress(address))) !== null;
+    }
+
+    /**
+     * @description Set the slice of the owner's share paid to referrers (owner only)
+     * @param referralBps Referral share in basis points of the owner share; 0 pays nothing
+     * @returns Promise resolving to transaction signature
+     * @throws {Error} If caller is not owner or the share exceeds 100%
+     * @example
+     * ```typescript
+     * // A fifth of the owner's share goes to the referrer
+     * await client.setReferralShare(2000);
+     * ```
+     */
+    async setReferralShare(referralBps: number): Promise<string> {
+        return await (this.program.methods as any)
+            .setReferralShare(referralBps)
+            .accounts({
+                mailer: this.mailerPda,
+                owner: this.provider.wallet.publicKey,
+            })
+            .rpc();
+    }
+
+    /**
+     * @description Register a referral code naming the connected wallet as its referrer
+     * @notice Also creates the wallet's claim, where referral shares accrue until claimed
+     * @param code Referral code, 1 to 32 bytes
+     * @returns Promise resolving to transaction signature
+     * @throws {Error} If the code is empty, too long, or already taken
+     */
+    async registerReferralCode(code: string): Promise<string> {
+        const referrer = this.provider.wallet.publicKey;
+        return await (this.program.methods as any)
+            .registerReferralCode(code)
+            .accounts({
+                referral: this.getReferralCodeAddress(code),
+                referrerClaim: this.getClaimAddress(referrer),
+                referrer,
+                systemProgram: SystemProgram.programId,
+            })
+            .rpc();
+    }
+
+    /**
+     * @description Send subsequent messages through a referral code, or stop doing so
+     * @notice Channel posts and group sends never pass the referral
+     * @param code Referral code, or null to send without a referrer
+     * @throws {Error} If the code is not registered
+     * @example
+     * ```typescript
+     * await client.useReferralCode('wallet-x');
+     * await client.sendPriority('Hello', 'Referred send');
+     * ```
+     */
+    async useReferralCode(code: string | null): Promise<void> {
+        if (code === null) {
+            this.referral = null;
+            return;
+        }
+        const referrer = await this.getReferralCodeReferrer(code);
+        if (!referrer) {
+            throw new Error(`Referral code "${code}" is not registered`);
+        }
+        this.referral = { code: this.getReferralCodeAddress(code), referrer };
+    }
+
+    /**
+     * @description Look up who a referral code pays
+     * @param code Referral code
+     * @returns Promise resolving to the referrer, or null if the code is not registered
+     */
+    async getReferralCodeReferrer(code: string): Promise<PublicKey | null> {
+        const account = await (this.program.account as any).referralCode.fetchNullable(this.getReferralCodeAddress(code));
+        return account ? account.referrer : null;
+    }
+
+    /**
+     * @description Create a promotional campaign boosting the recipient share of priority sends (owner only)
+     * @notice The boost is paid out of the owner's share in the mailer's USDC mint until the budget is spent
+     * @param id Campaign ID
+     * @param recipientShare Boosted recipient share in percent, above the standard 90 and at most 100
+     * @param startTime Unix timestamp (seconds) the boost starts at
+     * @param endTime Unix timestamp (seconds) the boost ends at
+     * @param budget Most the boost may pay out, in USDC base units
+     * @returns Promise resolving to transaction signature
+     * @throws {Error} If caller is not owner, the terms are invalid, or the ID is taken
+     * @example
+     * ```typescript
+     * const now = Math.floor(Date.now() / 1000);
+     * // 95% back on priority sends for a week, up to 500 USDC of boosts
+     * await client.createCampaign(1, 95, now, now + 7 * 86400, 500_000_000);
+     * ```
+     */
+    async createCampaign(id: number, recipientShare: number, startTime: number, endTime: number, budget: number): Promise<string> {
+        return await (this.program.methods as any)
+            .createCampaign(new BN(id), recipientShare, new BN(startTime), new BN(endTime), new BN(budget))
+            .accounts({
+                campaign: this.getCampaignAddress(id),
+                mailer: this.mailerPda,
+                owner: this.provider.wallet.publicKey,
+                systemProgram: SystemProgram.programId,
+            })
+            .rpc();
+    }
+
+    /**
+     * @description Close a campaign, ending its boost and returning its rent (owner only)
+     * @param id Campaign ID
+     * @returns Promise resolving to transaction signature
+     */
+    async closeCampaign(id: number): Promise<string> {
+        return await (this.program.methods as any)
+            .closeCampaign()
+            .accounts({
+                campaign: this.getCampaignAddress(id),
+                mailer: this.mailerPda,
+                owner: this.provider.wallet.publicKey,
+            })
+            .rpc();
+    }
+
+    /**
+     * @description Pass a campaign with subsequent sends, or stop doing so
+     * @notice Only priority sends are boosted, and only while the campaign runs and has budget left;
+     *         channel posts and group sends never pass the campaign
+     * @param id Campaign ID, or null to send outside campaigns
+     */
+    useCampaign(id: number | null): void {
+        this.campaign = id === null ? null : this.getCampaignAddress(id);
+    }
+
+    /**
+     * @description Configure loyalty point accrual and redemption (owner only)
+     * @param priorityPoints Points earned per paid priority message
+     * @param standardPoints Points earned per paid standard message
+     * @param pointValue Send credit per redeemed point, in USDC base units; 0 disables redemption
+     * @returns Promise resolving to transaction signature
+     * @throws {Error} If caller is not owner
+     * @example
+     * ```typescript
+     * // 10 points per priority send, 1 per standard send; 1,000 points buy one 0.1 USDC fee
+     * await client.setPointsRates(10, 1, 100);
+     * ```
+     */
+    async setPointsRates(priorityPoints: number, standardPoints: number, pointValue: number): Promise<string> {
+        return await (this.program.methods as any)
+            .setPointsRates(priorityPoints, standardPoints, new BN(pointValue))
+            .accounts({
+                mailer: this.mailerPda,
+                owner: this.provider.wallet.publicKey,
+            })
+            .rpc();
+    }
+
+    /**
+     * @description Earn loyalty points on subsequent paid sends, or stop doing so
+     * @notice The first send with points enabled creates the wallet's stats account, funded by the payer
+     * @param enabled Pass the wallet's stats account with sends
+     */
+    useLoyaltyPoints(enabled: boolean): void {
+        this.earnPoints = enabled;
+    }
+
+    /**
+     * @description Pay subsequent sends from prepaid credit before transferring tokens, or stop doing so
+     * @notice Only takes effect once the wallet has deposited with `depositCredit`;
+     *         until then sends fail because the credit account does not exist
+     * @param enabled Pass the wallet's credit account with sends
+     */
+    usePrepaidCredit(enabled: boolean): void {
+        this.prepaidCredit = enabled;
+    }
+
+    /**
+     * @description Deposit USDC as prepaid credit that sends draw on instead of transferring tokens
+     * @param amount Amount to deposit in USDC base units
+     * @returns Promise resolving to transaction signature
+     * @example
+     * ```typescript
+     * await client.depositCredit(5_000_000);
+     * client.usePrepaidCredit(true);
+     * await client.send('Subject', 'Paid from credit');
+     * ```
+     */
+    async depositCredit(amount: number): Promise<string> {
+        const owner = this.provider.wallet.publicKey;
+        return await (this.program.methods as any)
+            .depositCredit(new BN(amount))
+            .accounts({
+                credit: this.getCreditAddress(owner),
+                mailer: this.mailerPda,
+                tokenConfig: this.getTokenConfigAddress(this.usdcMint),
+                mint: this.usdcMint,
+                owner,
+                ownerUsdcAccount: getAssociatedTokenAddressSync(this.usdcMint, owner, false, this.tokenProgramId),
+                mailerUsdcAccount: getAssociatedTokenAddressSync(this.usdcMint, this.mailerPda, true, this.tokenProgramId),
+                tokenProgram: this.tokenProgramId,
+                systemProgram: SystemProgram.programId,
+            })
+            .rpc();
+    }
+
+    /**
+     * @description Withdraw unspent prepaid credit back to your USDC account
+     * @param amount Amount to withdraw in USDC base units
+     * @returns Promise resolving to transaction signature
+     * @throws {Error} If the amount exceeds the credit balance
+     */
+    async withdrawCredit(amount: number): Promise<string> {
+        const owner = this.provider.wallet.publicKey;
+        return await (this.program.methods as any)
+            .withdrawCredit(new BN(amount))
+            .accounts({
+                credit: this.getCreditAddress(owner),
+                mailer: this.mailerPda,
+                mint: this.usdcMint,
+                owner,
+                ownerUsdcAccount: getAssociatedTokenAddressSync(this.usdcMint, owner, false, this.tokenProgramId),
+                mailerUsdcAccount: getAssociatedTokenAddressSync(this.usdcMint, this.mailerPda, true, this.tokenProgramId),
+                tokenProgram: this.tokenProgramId,
+            })
+            .rpc();
+    }
+
+    /**
+     * @description Get a wallet's prepaid credit balance in the client's mint
+     * @param owner Wallet to look up
+     * @returns Balance in base units, 0 if the wallet never deposited
+     */
+    async getCreditBalance(owner: PublicKey): Promise<number> {
+        const account = await (this.program.account as any).credit.fetchNullable(this.getCreditAddress(owner));
+        return account ? account.balance.toNumber() : 0;
+    }
+
+    /**
+     * @description Get the PDA of a wallet's prepaid credit
+     * @param owner Wallet holding the credit
+     * @param mint Mint the credit is denominated in; defaults to the client's mint
+     * @returns The credit address
+     */
+    getCreditAddress(owner: PublicKey, mint: PublicKey = this.usdcMint): PublicKey {
+        const [creditPda] = PublicKey.findProgramAddressSync(
+            [Buffer.from('credit'), owner.toBuffer(), mint.toBuffer()],
+            this.program.programId
+        );
+        return creditPda;
+    }
+
+    /**
+     * @description Create a subscription plan (owner only)
+     * @param id Plan ID
+     * @param price Price of one period in USDC base units
+     * @param duration Length of one period in seconds
+     * @param messageCap Standard messages one period covers; 0 for unlimited
+     * @returns Promise resolving to transaction signature
+     * @throws {Error} If caller is not owner
+     * @example
+     * ```typescript
+     * // 5 USDC for 30 days of up to 1,000 standard sends
+     * await client.createSubscriptionPlan(1, 5_000_000, 30 * 86400, 1_000);
+     * ```
+     */
+    async createSubscriptionPlan(id: number, price: number, duration: number, messageCap: number): Promise<string> {
+        return await (this.program.methods as any)
+            .createSubscriptionPlan(new BN(id), new BN(price), new BN(duration), new BN(messageCap))
+            .accounts({
+                plan: this.getSubscriptionPlanAddress(id),
+                mailer: this.mailerPda,
+                owner: this.provider.wallet.publicKey,
+                systemProgram: SystemProgram.programId,
+            })
+            .rpc();
+    }
+
+    /**
+     * @description Withdraw a subscription plan from sale and return its rent (owner only)
+     * @notice Existing subscriptions to the plan keep running until they expire
+     * @param id Plan ID
+     * @returns Promise resolving to transaction signature
+     */
+    async closeSubscriptionPlan(id: number): Promise<string> {
+        return await (this.program.methods as any)
+            .closeSubscriptionPlan()
+            .accounts({
+                plan: this.getSubscriptionPlanAddress(id),
+                mailer: this.mailerPda,
+                owner: this.provider.wallet.publicKey,
+            })
+            .rpc();
+    }
+
+    /**
+     * @description Buy a period of a subscription plan
+     * @notice Buying the plan you are subscribed to extends the current period;
+     *         any other purchase starts a fresh period now
+     * @param id Plan ID
+     * @returns Promise resolving to transaction signature
+     * @example
+     * ```typescript
+     * await client.purchaseSubscription(1);
+     * client.useSubscription(true);
+     * await client.send('Subject', 'No per-message fee');
+     * ```
+     */
+    async purchaseSubscription(id: number): Promise<string> {
+        const subscriber = this.provider.wallet.publicKey;
+        return await (this.program.methods as any)
+            .purchaseSubscription(new BN(id))
+            .accounts({
+                subscription: this.getPlanSubscriptionAddress(subscriber),
+                plan: this.getSubscriptionPlanAddress(id),
+                mailer: this.mailerPda,
+                ownerBucket: this.getOwnerBucketAddress(this.usdcMint, 'send'),
+                mint: this.usdcMint,
+                subscriber,
+                subscriberUsdcAccount: getAssociatedTokenAddressSync(this.usdcMint, subscriber, false, this.tokenProgramId),
+                mailerUsdcAccount: getAssociatedTokenAddressSync(this.usdcMint, this.mailerPda, true, this.tokenProgramId),
+                tokenProgram: this.tokenProgramId,
+                systemProgram: SystemProgram.programId,
+            })
+            .rpc();
+    }
+
+    /**
+     * @description Pass the wallet's plan subscription with subsequent sends, or stop doing so
+     * @notice Only standard sends are covered, and only while the subscription is active and has cap left;
+     *         until the wallet has purchased a plan, sends fail because the subscription account does not exist
+     * @param enabled Pass the wallet's subscription with sends
+     */
+    useSubscription(enabled: boolean): void {
+        this.planSubscription = enabled;
+    }
+
+    /**
+     * @description Get a subscription plan by ID
+     * @param id Plan ID
+     * @returns Promise resolving to the plan, or null if it does not exist
+     */
+    async getSubscriptionPlan(id: number): Promise<SubscriptionPlanInfo | null> {
+        const account = await (this.program.account as any).subscriptionPlan.fetchNullable(this.getSubscriptionPlanAddress(id));
+        if (!account) {
+            return null;
+        }
+        return {
+            id,
+            mint: account.mint,
+            price: account.price.toNumber(),
+            duration: account.duration.toNumber(),
+            messageCap: account.messageCap.toNumber(),
+        };
+    }
+
+    /**
+     * @description Get a wallet's plan subscription
+     * @param subscriber Wallet to look up
+     * @returns Promise resolving to the subscription, or null if the wallet never subscribed
+     */
+    async getPlanSubscription(subscriber: PublicKey): Promise<PlanSubscriptionInfo | null> {
+        const account = await (this.program.account as any).planSubscription.fetchNullable(this.getPlanSubscriptionAddress(subscriber));
+        if (!account) {
+            return null;
+        }
+        return {
+            subscriber: account.subscriber,
+            planId: account.planId.toNumber(),
+            expiresAt: account.expiresAt.toNumber(),
+            messageCap: account.messageCap.toNumber(),
+            messagesUsed: account.messagesUsed.toNumber(),
+        };
+    }
+
+    /**
+     * @description Get the PDA of a subscription plan
+     * @param id Plan ID
+     * @returns The plan address
+     */
+    getSubscriptionPlanAddress(id: number): PublicKey {
+        const [planPda] = PublicKey.findProgramAddressSync(
+            [Buffer.from('plan'), new BN(id).toArrayLike(Buffer, 'le', 8)],
+            this.program.programId
+        );
+        return planPda;
+    }
+
+    /**
+     * @description Get the PDA of a wallet's plan subscription
+     * @param subscriber Subscribed wallet
+     * @returns The subscription address
+     */
+    getPlanSubscriptionAddress(subscriber: PublicKey): PublicKey {
+        const [subscriptionPda] = PublicKey.findProgramAddressSync(
+            [Buffer.from('plan_subscription'), subscriber.toBuffer()],
+            this.program.programId
+        );
+        return subscriptionPda;
+    }
+
+    /**
+     * @description Redeem loyalty points for send credit, which later sends use before charging tokens
+     * @param points Points to redeem
+     * @returns Promise resolving to transaction signature
+     * @throws {Error} If redemption is disabled, the wallet holds too few points, or the owner's bucket cannot fund it
+     */
+    async redeemPoints(points: number): Promise<string> {
+        const sender = this.provider.wallet.publicKey;
+        const usdcMint = await this.getMailerUsdcMint();
+        return await (this.program.methods as any)
+            .redeemPoints(new BN(points))
+            .accounts({
+                senderStats: this.getSenderStatsAddress(sender),
+                recipientClaim: this.getClaimAddress(sender),
+                ownerBucket: this.getOwnerBucketAddress(usdcMint, 'send'),
+                mailer: this.mailerPda,
+                sender,
+                systemProgram: SystemProgram.programId,
+            })
+            .rpc();
+    }
+
+    /**
+     * @description Get a sender's loyalty points
+     * @param sender Wallet address
+     * @returns Promise resolving to the points, or null if the sender never earned any
+     */
+    async getLoyaltyPoints(sender: PublicKey): Promise<LoyaltyPointsInfo | null> {
+        const account = await (this.program.account as any).senderStats.fetchNullable(this.getSenderStatsAddress(sender));
+        if (!account) {
+            return null;
+        }
+        return {
+            points: account.points.toNumber(),
+            lifetimePoints: account.lifetimePoints.toNumber(),
+        };
+    }
+
+    /**
+     * @description Discount sends for holders of an NFT collection (owner only)
+     * @param collection Metaplex collection mint, or null to disable the perk
+     * @param discountBps Fee discount in basis points; 10000 waives the fee
+     * @returns Promise resolving to transaction signature
+     * @throws {Error} If caller is not owner or the discount exceeds 100%
+     * @example
+     * ```typescript
+     * // Holders of the community collection send at half price
+     * await client.setNftPerk(collectionMint, 5000);
+     * ```
+     */
+    async setNftPerk(collection: PublicKey | null, discountBps: number): Promise<string> {
+        return await (this.program.methods as any)
+            .setNftPerk(collection ?? PublicKey.default, discountBps)
+            .accounts({
+                mailer: this.mailerPda,
+                owner: this.provider.wallet.publicKey,
+            })
+            .rpc();
+    }
+
+    /**
+     * @description Prove ownership of an NFT from the perk collection with subsequent sends, or stop doing so
+     * @notice The NFT must sit in the wallet's associated token account and carry the collection as
+     *         verified in its Metaplex metadata, or sends fail with `InvalidNftProof`
+     * @param nftMint Mint of the held NFT, or null to pay the full fee
+     */
+    useNftPerk(nftMint: PublicKey | null): void {
+        this.nftMint = nftMint;
+    }
+
+    /**
+     * @description Configure the fee discount tiers for stakers (owner only)
+     * @notice Stakers get the discount of the highest tier they reach, or their NFT holder discount if larger
+     * @param tiers Up to 4 tiers rising strictly in minimum stake (USDC base units) and discount (basis points);
+     *        an empty list disables stake discounts
+     * @returns Promise resolving to transaction signature
+     * @throws {Error} If caller is not owner or the tiers are invalid
+     * @example
+     * ```typescript
+     * // 10% off from 100 USDC staked, 25% off from 1,000 USDC
+     * await client.setStakeTiers([
+     *     { minStake: 100_000_000, discountBps: 1_000 },
+     *     { minStake: 1_000_000_000, discountBps: 2_500 },
+     * ]);
+     * ```
+     */
+    async setStakeTiers(tiers: StakeTierInfo[]): Promise<string> {
+        return await (this.program.methods as any)
+            .setStakeTiers(tiers.map(tier => ({ minStake: new BN(tier.minStake), discountBps: tier.discountBps })))
+            .accounts({
+                mailer: this.mailerPda,
+                owner: this.provider.wallet.publicKey,
+            })
+            .rpc();
+    }
+
+    /**
+     * @description Lock USDC in your stake to earn fee discounts
+     * @notice Each deposit locks the whole stake for another 7 days
+     * @param amount Amount to stake in USDC base units
+     * @returns Promise resolving to transaction signature
+     * @example
+     * ```typescript
+     * await client.stake(100_000_000);
+     * client.useStake(true);
+     * await client.sendPriority('Subject', 'Discounted');
+     * ```
+     */
+    async stake(amount: number): Promise<string> {
+        const owner = this.provider.wallet.publicKey;
+        return await (this.program.methods as any)
+            .stake(new BN(amount))
+            .accounts({
+                stake: this.getStakeAddress(owner),
+                mailer: this.mailerPda,
+                mint: this.usdcMint,
+                owner,
+                ownerUsdcAccount: getAssociatedTokenAddressSync(this.usdcMint, owner, false, this.tokenProgramId),
+                mailerUsdcAccount: getAssociatedTokenAddressSync(this.usdcMint, this.mailerPda, true, this.tokenProgramId),
+                tokenProgram: this.tokenProgramId,
+                systemProgram: SystemProgram.programId,
+            })
+            .rpc();
+    }
+
+    /**
+     * @description Withdraw your whole stake and close the stake account
+     * @returns Promise resolving to transaction signature
+     * @throws {Error} If 7 days have not passed since the last deposit
+     */
+    async unstake(): Promise<string> {
+        const owner = this.provider.wallet.publicKey;
+        const stake = await (this.program.account as any).stake.fetch(this.getStakeAddress(owner));
+        return await (this.program.methods as any)
+            .unstake()
+            .accounts({
+                stake: this.getStakeAddress(owner),
+                mailer: this.mailerPda,
+                mint: stake.mint,
+                owner,
+                ownerUsdcAccount: getAssociatedTokenAddressSync(stake.mint, owner, false, this.tokenProgramId),
+                mailerUsdcAccount: getAssociatedTokenAddressSync(stake.mint, this.mailerPda, true, this.tokenProgramId),
+                tokenProgram: this.tokenProgramId,
+            })
+            .rpc();
+    }
+
+    /**
+     * @description Pass the wallet's stake with subsequent sends to be charged its tier discount, or stop doing so
+     * @notice Sends fail while enabled if the wallet has no stake
+     * @param enabled Pass the wallet's stake with sends
+     */
+    useStake(enabled: boolean): void {
+        this.staked = enabled;
+    }
+
+    /**
+     * @description Get a wallet's stake
+     * @param owner Wallet to look up
+     * @returns Promise resolving to the stake, or null if the wallet has none
+     */
+    async getStake(owner: PublicKey): Promise<StakeInfo | null> {
+        const account = await (this.program.account as any).stake.fetchNullable(this.getStakeAddress(owner));
+        if (!account) {
+            return null;
+        }
+        return {
+            owner: account.owner,
+            mint: account.mint,
+            amount: account.amount.toNumber(),
+            lockedUntil: account.lockedUntil.toNumber(),
+        };
+    }
+
+    /**
+     * @description Get the configured stake discount tiers
+     * @returns Promise resolving to the tiers in ascending order, without unused slots
+     */
+    async getStakeTiers(): Promise<StakeTierInfo[]> {
+        const mailer = await (this.program.account as any).mailerState.fetch(this.mailerPda);
+        return mailer.stakeTiers
+            .filter((tier: any) => !tier.minStake.isZero())
+            .map((tier: any) => ({ minStake: tier.minStake.toNumber(), discountBps: tier.discountBps }));
+    }
+
+    /**
+     * @description Get the PDA of a wallet's stake
+     * @param owner Staking wallet
+     * @returns The stake address
+     */
+    getStakeAddress(owner: PublicKey): PublicKey {
+        const [stakePda] = PublicKey.findProgramAddressSync(
+            [Buffer.from('stake'), owner.toBuffer()],
+            this.program.programId
+        );
+        return stakePda;
+    }
+
+    /**
+     * @description Point cNFT receipts at a Bubblegum tree (owner only)
+     * @notice The mailer PDA must be the tree's creator or delegate, or minting receipts fails
+     * @param merkleTree Bubblegum merkle tree, or null to disable receipts
+     * @param baseUri Prefix of receipt URIs, followed by the hex message ID and `?t=<timestamp>`
+     * @returns Promise resolving to transaction signature
+     * @throws {Error} If caller is not owner or the base URI exceeds 110 bytes
+     * @example
+     * ```typescript
+     * await client.setReceiptTree(tree, 'https://mail.example/receipts/');
+     * ```
+     */
+    async setReceiptTree(merkleTree: PublicKey | null, baseUri: string): Promise<string> {
+        return await (this.program.methods as any)
+            .setReceiptTree(merkleTree ?? PublicKey.default, baseUri)
+            .accounts({
+                receiptConfig: this.getReceiptConfigAddress(),
+                mailer: this.mailerPda,
+                owner: this.provider.wallet.publicKey,
+                systemProgram: SystemProgram.programId,
+            })
+            .rpc();
+    }
+
+    /**
+     * @description Mint a cNFT receipt of each subsequent priority message, or stop doing so
+     * @notice Only single priority sends mint receipts; batch, delegated, channel and group sends do not
+     * @param merkleTree The tree configured with `setReceiptTree`, or null to mint no receipts
+     */
+    useReceipts(merkleTree: PublicKey | null): void {
+        this.receiptTree = merkleTree;
+    }
+
+    /**
+     * @description Start the compressed message log on a new tree (owner only)
+     * @notice Allocates the tree account, paid by the owner, and initializes it with the mailer PDA
+     *         as authority; calling it again moves the log to a fresh tree
+     * @param maxDepth Tree depth; the tree holds 2^maxDepth messages
+     * @param maxBufferSize Concurrent changes the tree tolerates per slot (e.g. 64)
+     * @returns Promise resolving to the transaction signature and the new tree's address
+     * @throws {Error} If caller is not owner or the compression program rejects the tree size
+     * @example
+     * ```typescript
+     * const { merkleTree } = await client.initMessageLog(20, 64);
+     * client.useMessageLog(merkleTree);
+     * ```
+     */
+    async initMessageLog(maxDepth: number, maxBufferSize: number): Promise<{ signature: string; merkleTree: PublicKey }> {
+        const tree = Keypair.generate();
+        const space = messageLogTreeSize(maxDepth, maxBufferSize);
+        const lamports = await this.provider.connection.getMinimumBalanceForRentExemption(space);
+        const signature = await (this.program.methods as any)
+            .initMessageLog(maxDepth, maxBufferSize)
+            .accounts({
+                messageLog: this.getMessageLogAddress(),
+                merkleTree: tree.publicKey,
+                mailer: this.mailerPda,
+                owner: this.provider.wallet.publicKey,
+                logWrapper: SPL_NOOP_PROGRAM_ID,
+                compressionProgram: SPL_ACCOUNT_COMPRESSION_PROGRAM_ID,
+                systemProgram: SystemProgram.programId,
+            })
+            .preInstructions([
+                SystemProgram.createAccount({
+                    fromPubkey: this.provider.wallet.publicKey,
+                    newAccountPubkey: tree.publicKey,
+                    lamports,
+                    space,
+                    programId: SPL_ACCOUNT_COMPRESSION_PROGRAM_ID,
+                }),
+            ])
+            .signers([tree])
+            .rpc();
+        return { signature, merkleTree: tree.publicKey };
+    }
+
+    /**
+     * @description Append the leaf of each subsequent single send to the message log, or stop doing so
+     * @notice Batch sends, channel posts and group sends are not logged
+     * @param merkleTree The log's current tree (see `getMessageLog`), or null to stop logging
+     */
+    useMessageLog(merkleTree: PublicKey | null): void {
+        this.messageLogTree = merkleTree;
+    }
+
+    /**
+     * @description Prove a message is in the message log
+     * @param messageId Message ID as emitted with the message
+     * @param from Sender as emitted with the message
+     * @param to Recipient as emitted with the message
+     * @param contentHash Hash of the message content the leaf was built from
+     * @param root Tree root the proof was built against
+     * @param index Leaf index from the `MessageLogged` event
+     * @param proof Proof nodes from the leaf up, e.g. from an indexer replaying `MessageLogged`
+     * @returns Promise resolving to transaction signature
+     * @throws {Error} If the tree is not the log's current tree or the proof is invalid
+     */
+    async verifyMessage(
+        messageId: Uint8Array,
+        from: PublicKey,
+        to: PublicKey,
+        contentHash: Uint8Array,
+        root: Uint8Array,
+        index: number,
+        proof: PublicKey[]
+    ): Promise<string> {
+        const log = await this.getMessageLog();
+        if (!log) {
+            throw new Error('Message log is not initialized');
+        }
+        return await (this.program.methods as any)
+            .verifyMessage(
+                Array.from(messageId),
+                from,
+                to,
+                Array.from(contentHash),
+                Array.from(root),
+                index
+            )
+            .accounts({
+                messageLog: this.getMessageLogAddress(),
+                merkleTree: log.merkleTree,
+                compressionProgram: SPL_ACCOUNT_COMPRESSION_PROGRAM_ID,
+            })
+            .remainingAccounts(proof.map((node) => ({ pubkey: node, isSigner: false, isWritable: false })))
+            .rpc();
+    }
+
+    /**
+     * @description Get the message log's current tree and leaf count
+     * @returns The message log, or null if it was never initialized
+     */
+    async getMessageLog(): Promise<MessageLogInfo | null> {
+        const account = await (this.program.account as any).messageLog.fetchNullable(this.getMessageLogAddress());
+        if (!account) {
+            return null;
+        }
+        return {
+            merkleTree: account.merkleTree,
+            leaves: account.leaves.toNumber(),
+        };
+    }
+
+    /**
+     * @description Appoint a key besides the owner to publish daily message roots (owner only)
+     * @param publisher Root publisher, or null for none
+     * @returns Promise resolving to transaction signature
+     * @throws {Error} If caller is not owner
+     */
+    async setRootPublisher(publisher: PublicKey | null): Promise<string> {
+        return await (this.program.methods as any)
+            .setRootPublisher(publisher ?? PublicKey.default)
+            .accounts({
+                mailer: this.mailerPda,
+                owner: this.provider.wallet.publicKey,
+            })
+            .rpc();
+    }
+
+    /**
+     * @description Anchor the Merkle root of a finished day's messages (owner or root publisher)
+     * @notice Roots are write-once; build them with `computeMessageRoot`
+     * @param day Day number, see `messageRootDay`
+     * @param root Root over the day's message leaves
+     * @param messageCount Messages the root covers
+     * @returns Promise resolving to transaction signature
+     * @throws {Error} If caller may not publish roots, the day has not ended or its root exists
+     * @example
+     * ```typescript
+     * const leaves = events.map((e) => computeMessageLeaf(e.messageId, e.from, e.to, contentHash(e)));
+     * await client.publishMessageRoot(day, computeMessageRoot(leaves), leaves.length);
+     * ```
+     */
+    async publishMessageRoot(day: number, root: Uint8Array, messageCount: number): Promise<string> {
+        return await (this.program.methods as any)
+            .publishMessageRoot(new BN(day), Array.from(root), new BN(messageCount))
+            .accounts({
+                messageRoot: this.getMessageRootAddress(day),
+                mailer: this.mailerPda,
+                publisher: this.provider.wallet.publicKey,
+                systemProgram: SystemProgram.programId,
+            })
+            .rpc();
+    }
+
+    /**
+     * @description Check on chain that a message is included in a published daily root
+     * @param day Day the message was sent on
+     * @param messageHash The message's leaf
+     * @param proof Sibling hashes from `messageRootProof`
+     * @returns Promise resolving to transaction signature
+     * @throws {Error} If the day has no root or the proof does not lead to it
+     */
+    async verifyInclusion(day: number, messageHash: Uint8Array, proof: Uint8Array[]): Promise<string> {
+        return await (this.program.methods as any)
+            .verifyInclusion(Array.from(messageHash), proof.map((node) => Array.from(node)))
+            .accounts({
+                messageRoot: this.getMessageRootAddress(day),
+            })
+            .rpc();
+    }
+
+    /**
+     * @description Get the published root of a day
+     * @param day Day number
+     * @returns The day's root, or null if none is published
+     */
+    async getMessageRoot(day: number): Promise<MessageRootInfo | null> {
+        const account = await (this.program.account as any).messageRoot.fetchNullable(this.getMessageRootAddress(day));
+        if (!account) {
+            return null;
+        }
+        return {
+            day: account.day.toNumber(),
+            root: Uint8Array.from(account.root),
+            messageCount: account.messageCount.toNumber(),
+            publishedAt: account.publishedAt.toNumber(),
+        };
+    }
+
+    /**
+     * @description Get a campaign by ID
+     * @param id Campaign ID
+     * @returns Promise resolving to the campaign, or null if it does not exist
+     */
+    async getCampaign(id: number): Promise<CampaignInfo | null> {
+        const account = await (this.program.account as any).campaign.fetchNullable(this.getCampaignAddress(id));
+        if (!account) {
+            return null;
+        }
+        return {
+            id,
+            mint: account.mint,
+            recipientShare: account.recipientShare,
+            startTime: account.startTime.toNumber(),
+            endTime: account.endTime.toNumber(),
+            budget: account.budget.toNumber(),
+            spent: account.spent.toNumber(),
+        };
+    }
+
+    /**
+     * @description Set the bounty paid to crankers of expired claims (owner only)
+     * @param bountyBps Bounty in basis points of the swept amount (max 1000 = 10%)
+     * @returns Promise resolving to transaction signature
+     * @throws {Error} If caller is not owner or bounty exceeds the cap
+     * @example
+     * ```typescript
+     * // Pay crankers 2% of each swept claim
+     * await client.setCrankBounty(200);
+     * ```
+     */
+    async setCrankBounty(bountyBps: number): Promise<string> {
+        return await (this.program.methods as any)
+            .setCrankBounty(bountyBps)
+            .accounts({
+                mailer: this.mailerPda,
+                owner: this.provider.wallet.publicKey,
+            })
+            .rpc();
+    }
+
+    /**
+     * @description Set the portion of expired shares rolled into recipients' send credit (owner only)
+     * @notice Send credit cannot be withdrawn; it is spent first by future sends. 0 disables rollover
+     * @param rolloverBps Rollover in basis points of the swept amount after the crank bounty
+     * @returns Promise resolving to transaction signature
+     * @throws {Error} If caller is not owner or rollover exceeds 10000
+     * @example
+     * ```typescript
+     * // Roll half of every expired share into send credit
+     * await client.setRollover(5000);
+     * ```
+     */
+    async setRollover(rolloverBps: number): Promise<string> {
+        return await (this.program.methods as any)
+            .setRollover(rolloverBps)
+            .accounts({
+                mailer: this.mailerPda,
+                owner: this.provider.wallet.publicKey,
+            })
+            .rpc();
+    }
+
+    /**
+     * @description Extend a recipient's claim window as a grace period (owner only)
+     * @notice Capped at 30 days per call and never beyond a fresh 60-day window from now
+     * @param recipient Address whose claim should be extended
+     * @param extraSeconds Seconds to add to the claim window
+     * @returns Promise resolving to transaction signature
+     * @throws {Error} If caller is not owner, claim is empty, or extension is out of range
+     * @example
+     * ```typescript
+     * // Give a user one extra week to claim
+     * await client.extendClaim(userAddress, 7 * 24 * 60 * 60);
+     * ```
+     */
+    async extendClaim(recipient: PublicKey, extraSeconds: number): Promise<string> {
+        const [recipientClaimPda] = PublicKey.findProgramAddressSync(
+            [Buffer.from('claim'), recipient.toBuffer()],
+            this.program.programId
+        );
+
+        // The claim moves from its current expiry bucket to the extended week's
+        const claim = await (this.program.account as any).recipientClaim.fetch(recipientClaimPda);
+        const timestamp = claim.timestamp.toNumber();
+
+        return await (this.program.methods as any)
+            .extendClaim(recipient, new BN(extraSeconds))
+            .accounts({
+                recipientClaim: recipientClaimPda,
+                mailer: this.mailerPda,
+                owner: this.provider.wallet.publicKey,
+                oldExpiryIndex: this.getExpiryIndexAddress(timestamp),
+                newExpiryIndex: this.getExpiryIndexAddress(timestamp + extraSeconds),
+                systemProgram: SystemProgram.programId,
+            })
+            .rpc();
+    }
+
+    /**
+     * @description Update the base sending fee (owner only)
+     * @notice This affects both priority and standard message pricing
+     * @param newFee New fee amount in USDC (with 6 decimals)
+     * @returns Promise resolving to transaction signature
+     * @throws {Error} If caller is not owner, the fee is below 10 base units or too large
+     *         for a full prepared batch (`InvalidFee`), or transaction fails
+     * @example
+     * ```typescript
+     * // Set fee to 0.2 USDC (200,000 with 6 decimals)
+     * const tx = await client.setFee(200000);
+     * console.log('Fee updated:', tx);
+     * 
+     * // Priority messages now cost 0.2 USDC
+     * // Standard messages now cost 0.02 USDC (10% of 0.2)
+     * ```
+     */
+    async setFee(newFee: number): Promise<string> {
+        return await (this.program.methods as any)
+            .setFee(new BN(newFee))
+            .accounts({
+                mailer: this.mailerPda,
+                owner: this.provider.wallet.publicKey,
+            })
+            .rpc();
+    }
+
+    /**
+     * @description Dry-run a fee or parameter change without applying it
+     * @notice Read-only and permissionless: runs the setter's validation against the
+     *         current state, so a proposed change can be reviewed before the owner signs it
+     * @param change Parameter and proposed value
+     * @returns Report with the old and new values and the error the setter would fail with
+     * @example
+     * ```typescript
+     * const report = await client.simulateConfigChange({ kind: 'rollover', value: 12_000 });
+     * if (!report.valid) {
+     *     console.log(`Rejected with error ${report.errorCode}`);
+     * }
+     * ```
+     */
+    async simulateConfigChange(change: MailerConfigChange): Promise<ConfigChangeReport> {
+        const report = await (this.program.methods as any)
+            .simulateConfigChange(this.encodeConfigChange(change))
+            .accounts({
+                mailer: this.mailerPda,
+            })
+            .view();
+
+        return {
+            change,
+            oldValue: report.oldValue.toNumber(),
+            newValue: report.newValue.toNumber(),
+            valid: report.errorCode === 0,
+            errorCode: report.errorCode,
+        };
+    }
+
+    private encodeConfigChange(change: MailerConfigChange): object {
+        switch (change.kind) {
+            case 'sendFee':
+                return { sendFee: { newFee: new BN(change.value) } };
+            case 'usdFee':
+                return { usdFee: { newUsdFeeCents: new BN(change.value) } };
+            case 'crankBounty':
+                return { crankBounty: { newBountyBps: change.value } };
+            case 'rollover':
+                return { rollover: { newRolloverBps: change.value } };
+            case 'powDifficulty':
+                return { powDifficulty: { newDifficulty: change.value } };
+            case 'referralShare':
+                return { referralShare: { newReferralBps: change.value } };
+            case 'nftDiscount':
+                return { nftDiscount: { discountBps: change.value } };
+        }
+    }
+
+    /**
+     * @description Replace a misconfigured USDC mint (owner only)
+     * @notice Only succeeds while the current mint was never added with `addToken` and the
+     *         owner balance is empty. The client switches to the new mint on success
+     * @param newUsdcMint Replacement mint; must have 6 decimals
+     * @returns Promise resolving to transaction signature
+     * @throws {Error} If caller is not owner, the mint is invalid, or the current mint is in use
+     * @example
+     * ```typescript
+     * const usdc = new PublicKey('EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v');
+     * await client.updateUsdcMint(usdc);
+     * ```
+     */
+    async updateUsdcMint(newUsdcMint: PublicKey): Promise<string> {
+        const currentMint = await this.getMailerUsdcMint();
+        const tx = await (this.program.methods as any)
+            .updateUsdcMint()
+            .accounts({
+                mailer: this.mailerPda,
+                oldTokenConfig: this.getTokenConfigAddress(currentMint),
+                newUsdcMint: newUsdcMint,
+                owner: this.provider.wallet.publicKey,
+            })
+            .rpc();
+        this.usdcMint = newUsdcMint;
+        return tx;
+    }
+
+    /**
+     * @description Get detailed information about claimable revenue shares
+     * @param recipient Address to check claimable information for
+     * @returns Promise resolving to ClaimableInfo or null if no claims exist
+     * @example
+     * ```typescript
+     * const info = await client.getRecipientClaimable(userAddress);
+     * if (info) {
+     *     console.log(`Claimable: ${formatUSDC(info.amount)} USDC`);
+     *     console.log(`Expires: ${new Date(info.expiresAt * 1000).toISOString()}`);
+     *     console.log(`Is Expired: ${info.isExpired}`);
+     * } else {
+     *     console.log('No claimable amounts found');
+     * }
+     * ```
+     */
+    async getRecipientClaimable(recipient: PublicKey): Promise<ClaimableInfo | null> {
+        try {
+            const [recipientClaimPda] = PublicKey.findProgramAddressSync(
+                [Buffer.from('claim'), recipient.toBuffer()],
+                this.program.programId
+            );
+
+            const account = await (this.program.account as any).recipientClaim.fetch(recipientClaimPda);
+            const amount = account.amount.toNumber();
+            const timestamp = account.timestamp.toNumber();
+            const claimPeriodSeconds = CLAIM_PERIOD_DAYS * 24 * 60 * 60;
+            const expiresAt = timestamp + claimPeriodSeconds;
+            const currentTime = Math.floor(Date.now() / 1000);
+            const mode: MailerClaimMode = account.mode.streaming ? 'streaming' : 'lump';
+            const isExpired = mode === 'lump' && amount > 0 && currentTime > expiresAt;
+
+            // Mirrors the program: what earlier streams released is available, and the
+            // current stream's balance plus what was withdrawn from it unlocks linearly
+            // Shares behind a vesting cliff are held back until it passes
+            const vestingEndsAt = account.vestingStart.toNumber() + VESTING_CLIFF_DAYS * 24 * 60 * 60;
+            const vested = currentTime < vestingEndsAt ? Math.min(account.vested.toNumber(), amount) : 0;
+            let available = isExpired ? 0 : amount - vested;
+            if (mode === 'streaming') {
+                const streamed = account.streamed.toNumber();
+                const released = account.released.toNumber();
+                const stream = amount - released;
+                const elapsed = Math.min(Math.max(currentTime - timestamp, 0), claimPeriodSeconds);
+                const unlocked = Math.floor(((stream + streamed) * elapsed) / claimPeriodSeconds);
+                available = released + Math.min(Math.max(unlocked - streamed, 0), stream);
+            }
+            
+            return {
+                amount,
+                expiresAt,
+                isExpired,
+                mode,
+                available,
+                vested,
+                vestingEndsAt: vested > 0 ? vestingEndsAt : 0,
+                sendCredit: account.sendCredit.toNumber(),
+                autoClaim: account.autoClaim,
+                feeSnapshot: {
+                    sendFee: account.feeSnapshot.sendFee.toNumber(),
+                    ownerShare: account.feeSnapshot.ownerShare,
+                    recipientShare: account.feeSnapshot.recipientShare,
+                },
+                // Claims recorded before multi-token support are in USDC
+                mint: account.mint.equals(PublicKey.default)
+                    ? (await this.getMailerUsdcMint())
+                    : account.mint,
+            };
+        } catch {
+            return null;
+        }
+    }
+
+    /**
+     * @description Get the total amount of a mint claimable by the program owner
+     * @param mint Token mint to check (defaults to the client's mint)
+     * @returns Promise resolving to claimable amount in the mint's base units
+     * @example
+     * ```typescript
+     * const ownerClaimable = await client.getOwnerClaimable();
+     * console.log(`Owner can claim: ${formatUSDC(ownerClaimable)} USDC`);
+     * ```
+     */
+    async getOwnerClaimable(mint: PublicKey = this.usdcMint, source: MailerFeeSource = 'send'): Promise<number> {
+        const account = await (this.program.account as any).mailerState.fetch(this.mailerPda);
+        const bucket = await this.getOwnerBucket(mint, source);
+        // Send fees accrued before multi-token support are held on the mailer state in USDC
+        const legacy = source === 'send' && account.usdcMint.equals(mint) ? account.ownerClaimable.toNumber() : 0;
+        return legacy + (bucket?.claimable ?? 0);
+    }
+
+    /**
+     * @description Route an owner share bucket to a new destination wallet (owner only)
+     * @param mint Mint of the bucket
+     * @param source Fee source whose bucket is routed
+     * @param destination Wallet that receives withdrawals from the bucket
+     * @returns Promise resolving to transaction signature
+     * @throws {Error} If caller is not owner or transaction fails
+     * @example
+     * ```typescript
+     * // Budget expired-share revenue separately from send fees
+     * await client.setBucketDestination(usdcMint, 'expired', growthTreasury);
+     * ```
+     */
+    async setBucketDestination(mint: PublicKey, source: MailerFeeSource, destination: PublicKey): Promise<string> {
+        return await (this.program.methods as any)
+            .setBucketDestination({ [source]: {} }, destination)
+            .accounts({
+                ownerBucket: this.getOwnerBucketAddress(mint, source),
+                mailer: this.mailerPda,
+                mint,
+                owner: this.provider.wallet.publicKey,
+                systemProgram: SystemProgram.programId,
+            })
+            .rpc();
+    }
+
+    /**
+     * @description Get an owner share bucket's destination and accrued balance
+     * @param mint Mint of the bucket
+     * @param source Fee source of the bucket
+     * @returns Promise resolving to FeeBucketInfo or null if the bucket does not exist
+     */
+    async getOwnerBucket(mint: PublicKey, source: MailerFeeSource): Promise<FeeBucketInfo | null> {
+        try {
+            const account = await (this.program.account as any).ownerBucket.fetch(this.getOwnerBucketAddress(mint, source));
+            return {
+                destination: account.destination,
+                claimable: account.claimable.toNumber(),
+            };
+        } catch {
+            return null;
+        }
+    }
+
+    /**
+     * @description Accept a mint for fees or update its send fee (owner only)
+     * @param mint Token mint to accept (e.g. USDC, USDT, PYUSD)
+     * @param sendFee Priority send fee in the mint's base units
+     * @returns Promise resolving to transaction signature
+     * @throws {Error} If caller is not owner or transaction fails
+     * @example
+     * ```typescript
+     * // Accept PYUSD (6 decimals) at 0.1 PYUSD per priority message
+     * await client.addToken(pyusdMint, 100000);
+     * ```
+     */
+    async addToken(mint: PublicKey, sendFee: number): Promise<string> {
+        return await (this.program.methods as any)
+            .addToken(new BN(sendFee))
+            .accounts({
+                tokenConfig: this.getTokenConfigAddress(mint),
+                sendBucket: this.getOwnerBucketAddress(mint, 'send'),
+                expiredBucket: this.getOwnerBucketAddress(mint, 'expired'),
+                mailer: this.mailerPda,
+                mint,
+                owner: this.provider.wallet.publicKey,
+                systemProgram: SystemProgram.programId,
+            })
+            .rpc();
+    }
+
+    /**
+     * @description Stop accepting a mint for new sends (owner only)
+     * @notice Outstanding claims and owner share in the mint remain claimable
+     * @param mint Token mint to disable
+     * @returns Promise resolving to transaction signature
+     * @throws {Error} If caller is not owner or the mint is already disabled
+     */
+    async removeToken(mint: PublicKey): Promise<string> {
+        return await (this.program.methods as any)
+            .removeToken()
+            .accounts({
+                tokenConfig: this.getTokenConfigAddress(mint),
+                mailer: this.mailerPda,
+                owner: this.provider.wallet.publicKey,
+            })
+            .rpc();
+    }
+
+    /**
+     * @description Get the fee configuration of a mint
+     * @param mint Token mint to look up
+     * @returns Promise resolving to TokenConfigInfo or null if the mint was never added
+     */
+    async getTokenConfig(mint: PublicKey): Promise<TokenConfigInfo | null> {
+        try {
+            const account = await (this.program.account as any).tokenConfig.fetch(this.getTokenConfigAddress(mint));
+            return {
+                mint: account.mint,
+                enabled: account.enabled,
+                sendFee: account.sendFee.toNumber(),
+                decimals: account.decimals,
+                priceFeedId: Buffer.from(account.priceFeedId).every((b) => b === 0)
+                    ? null
+                    : Buffer.from(account.priceFeedId).toString('hex'),
+            };
+        } catch {
+            return null;
+        }
+    }
+
+    /**
+     * @description Set how clients should display a fee token (owner only)
+     * @notice The mint must have been added with `addToken`; its decimals are read from the mint
+     * @param mint Token mint to describe
+     * @param symbol Ticker shown to users (1-10 bytes)
+     * @param displayHint Formatting pattern understood by `formatTokenAmount` (max 32 bytes)
+     * @returns Promise resolving to transaction signature
+     * @throws {Error} If caller is not owner or the metadata is out of bounds
+     * @example
+     * ```typescript
+     * await client.setTokenDisplay(usdcMint, 'USDC', '$0.00');
+     * await client.setTokenDisplay(pyusdMint, 'PYUSD', '0.00 PYUSD');
+     * ```
+     */
+    async setTokenDisplay(mint: PublicKey, symbol: string, displayHint: string): Promise<string> {
+        return await (this.program.methods as any)
+            .setTokenDisplay(symbol, displayHint)
+            .accounts({
+                tokenDisplay: this.getTokenDisplayAddress(mint),
+                tokenConfig: this.getTokenConfigAddress(mint),
+                mailer: this.mailerPda,
+                mint,
+                owner: this.provider.wallet.publicKey,
+                systemProgram: SystemProgram.programId,
+            })
+            .rpc();
+    }
+
+    /**
+     * @description Get the display metadata of a fee token
+     * @param mint Token mint to look up
+     * @returns Promise resolving to TokenDisplayInfo or null if none was set
+     */
+    async getTokenDisplay(mint: PublicKey): Promise<TokenDisplayInfo | null> {
+        try {
+            const account = await (this.program.account as any).tokenDisplay.fetch(this.getTokenDisplayAddress(mint));
+            return {
+                mint: account.mint,
+                symbol: account.symbol,
+                decimals: account.decimals,
+                displayHint: account.displayHint,
+            };
+        } catch {
+            return null;
+        }
+    }
+
+    /**
+     * @description Quote the fee for one send by simulating the `quote_fee` instruction
+     * @notice Includes the mint's display metadata, so the result can be passed to
+     *         `formatTokenAmount`. Oracle-priced mints use the account set with `setPriceUpdateAccount`
+     * @param priority Quote the priority fee instead of the standard fee
+     * @param mint Fee token to quote (defaults to the client's mint)
+     * @returns Promise resolving to the fee quote
+     * @throws {Error} If the mint is not accepted or its price update is missing, stale or has
+     *         a confidence interval wider than 2% of the price
+     * @example
+     * ```typescript
+     * const quote = await client.quoteFee(true);
+     * console.log(`Priority message: ${formatTokenAmount(quote.amount, quote)}`);
+     * ```
+     */
+    async quoteFee(priority: boolean, mint: PublicKey = this.usdcMint): Promise<FeeQuote> {
+        const displayAddress = this.getTokenDisplayAddress(mint);
+        const hasDisplay = (await this.provider.connection.getAccountInfo(displayAddress)) !== null;
+
+        const quote = await (this.program.methods as any)
+            .quoteFee(priority)
+            .accounts({
+                mailer: this.mailerPda,
+                tokenConfig: this.getTokenConfigAddress(mint),
+                tokenDisplay: hasDisplay ? displayAddress : null,
+                priceUpdate: this.priceUpdate,
+            })
+            .view();
+
+        return {
+            mint: quote.mint,
+            amount: quote.amount.toNumber(),
+            decimals: quote.decimals,
+            symbol: quote.symbol,
+            displayHint: quote.displayHint,
+        };
+    }
+
+    /**
+     * @description Get the fee split in a mint by simulating the `get_fee_breakdown` instruction
+     * @notice Oracle-priced mints use the account set with `setPriceUpdateAccount`
+     * @param mint Fee token to break down (defaults to the client's mint)
+     * @returns Promise resolving to the priority and standard fees and their shares
+     * @throws {Error} If the mint is not accepted or its price update is missing, stale or has
+     *         a confidence interval wider than 2% of the price
+     * @example
+     * ```typescript
+     * const { recipientShare } = await client.getFeeBreakdown();
+     * console.log(`Priority senders get back ${formatUSDC(recipientShare)} USDC`);
+     * ```
+     */
+    async getFeeBreakdown(mint: PublicKey = this.usdcMint): Promise<FeeBreakdown> {
+        const breakdown = await (this.program.methods as any)
+            .getFeeBreakdown()
+            .accounts({
+                mailer: this.mailerPda,
+                tokenConfig: this.getTokenConfigAddress(mint),
+                tokenDisplay: null,
+                priceUpdate: this.priceUpdate,
+            })
+            .view();
+
+        return {
+            mint: breakdown.mint,
+            priorityFee: breakdown.priorityFee.toNumber(),
+            standardFee: breakdown.standardFee.toNumber(),
+            recipientShare: breakdown.recipientShare.toNumber(),
+            ownerShare: breakdown.ownerShare.toNumber(),
+            claimPeriod: breakdown.claimPeriod.toNumber(),
+            crankBountyBps: breakdown.crankBountyBps,
+            rolloverBps: breakdown.rolloverBps,
+        };
+    }
+
+    /**
+     * @description Get a recipient's claim as the program computes it, by simulating `get_claimable`
+     * @notice Unlike `getRecipientClaimable`, availability and expiry use the cluster clock and never
+     *         return null: recipients without a claim get an empty one
+     * @param recipient Recipient wallet address
+     * @returns Promise resolving to the claimable information
+     * @example
+     * ```typescript
+     * const info = await client.getClaimableView(userAddress);
+     * console.log(`Withdrawable now: ${formatUSDC(info.available!)} USDC`);
+     * ```
+     */
+    async getClaimableView(recipient: PublicKey): Promise<ClaimableInfo> {
+        const view = await (this.program.methods as any)
+            .getClaimable(recipient)
+            .accounts({
+                mailer: this.mailerPda,
+                recipientClaim: PublicKey.findProgramAddressSync(
+                    [Buffer.from('claim'), recipient.toBuffer()],
+                    this.program.programId
+                )[0],
+            })
+            .view();
+
+        return {
+            amount: view.amount.toNumber(),
+            expiresAt: view.expiresAt.toNumber(),
+            isExpired: view.isExpired,
+            mode: view.mode.streaming ? 'streaming' : 'lump',
+            available: view.available.toNumber(),
+            sendCredit: view.sendCredit.toNumber(),
+            mint: view.mint,
+        };
+    }
+
+    /**
+     * @description List the claims expiring in the same week as a given time
+     * @notice Entries are hints: claims netted to zero or swept in a batch stay listed
+     *         until their week passes, so confirm each with getRecipientClaimable
+     * @param expiresAt Unix timestamp (seconds) within the week of interest
+     * @returns Promise resolving to ExpiryIndexInfo, or null if no claim expires that week
+     * @example
+     * ```typescript
+     * // Keeper: find claims expiring this week
+     * const index = await client.getExpiringClaims(Math.floor(Date.now() / 1000));
+     * for (const recipient of index?.recipients ?? []) {
+     *     const info = await client.getRecipientClaimable(recipient);
+     *     if (info && info.isExpired) await client.claimExpiredShares(recipient);
+     * }
+     * ```
+     */
+    async getExpiringClaims(expiresAt: number): Promise<ExpiryIndexInfo | null> {
+        const bucketSeconds = EXPIRY_BUCKET_DAYS * 24 * 60 * 60;
+        const week = Math.floor(expiresAt / bucketSeconds);
+        try {
+            const account = await (this.program.account as any).expiryIndex.fetch(this.getExpiryIndexForWeek(week));
+            return {
+                week,
+                startsAt: week * bucketSeconds,
+                endsAt: (week + 1) * bucketSeconds,
+                recipients: account.recipients,
+                overflow: account.overflow,
+            };
+        } catch {
+            return null;
+        }
+    }
+
+    private async getMailerUsdcMint(): Promise<PublicKey> {
+        const account = await (this.program.account as any).mailerState.fetch(this.mailerPda);
+        return account.usdcMint;
+    }
+
+    /**
+     * @description Get current fee structure information
+     * @returns Promise resolving to MailerFees with fee details
+     * @example
+     * ```typescript
+     * const fees = await client.getFees();
+     * console.log(`Send Fee: ${formatUSDC(fees.sendFee)} USDC`);
+     * 
+     * // Calculate actual costs
+     * const priorityCost = fees.sendFee; // Full fee
+     * const standardCost = Math.floor(fees.sendFee * 0.1); // 10% fee
+     * ```
+     */
+    async getFees(): Promise<MailerFees> {
+        const account = await (this.program.account as any).mailerState.fetch(this.mailerPda);
+        return {
+            sendFee: account.sendFee.toNumber(),
+        };
+    }
+
+    /**
+     * @description Get aggregate usage counters without replaying event history
+     * @param mint Mint whose collected fees to report; defaults to the client's mint
+     * @returns Promise resolving to total messages, priority messages and fees collected in `mint`
+     * @example
+     * ```typescript
+     * const stats = await client.getStats();
+     * console.log(`${stats.totalPriorityMessages} of ${stats.totalMessagesSent} messages were priority`);
+     * ```
+     */
+    async getStats(mint: PublicKey = this.usdcMint): Promise<MailerStats> {
+        const account = await (this.program.account as any).mailerStats.fetch(this.statsPda);
+        const bucket = await (this.program.account as any).ownerBucket.fetchNullable(
+            this.getOwnerBucketAddress(mint, 'send')
+        );
+        return {
+            totalMessagesSent: account.totalMessagesSent.toNumber(),
+            totalPriorityMessages: account.totalPriorityMessages.toNumber(),
+            totalFeesCollected: bucket ? bucket.feesCollected.toNumber() : 0,
+            nextMessageSeq: account.messageSeq.toNumber(),
+        };
+    }
+
+    /**
+     * @description Create the usage counters of a deployment initialized before they existed
+     * @notice Owner only; sends fail until this has run once on such a deployment
+     * @returns Promise resolving to transaction signature
+     */
+    async initStats(): Promise<string> {
+        return await (this.program.methods as any)
+            .initStats()
+            .accounts({
+                stats: this.statsPda,
+                mailer: this.mailerPda,
+                owner: this.provider.wallet.publicKey,
+                systemProgram: SystemProgram.programId,
+            })
+            .rpc();
+    }
+
+    /**
+     * @description Grow a mailer state created by an earlier program version to the current layout (owner only)
+     * @notice Run once after upgrading such a deployment; sends cannot decode the old state until then
+     * @returns Promise resolving to transaction signature
+     * @throws {Error} If caller is not the owner
+     */
+    async migrateMailer(): Promise<string> {
+        return await (this.program.methods as any)
+            .migrateMailer()
+            .accounts({
+                mailer: this.mailerPda,
+                owner: this.provider.wallet.publicKey,
+                systemProgram: SystemProgram.programId,
+            })
+            .rpc();
+    }
+
+    /**
+     * @description Get current fees formatted as human-readable strings
+     * @returns Promise resolving to formatted fee strings
+     * @example
+     * ```typescript
+     * const formatted = await client.getFeesFormatted();
+     * console.log(`Current send fee: ${formatted.sendFee}`);
+     * ```
+     */
+    async getFeesFormatted(): Promise<{ sendFee: string }> {
+        const fees = await this.getFees();
+        return {
+            sendFee: formatUSDC(fees.sendFee) + ' USDC',
+        };
     }
 
     /**
@@ -567,15 +3471,1239 @@ export class MailerClient {
      * @returns The deterministically derived address of the Mailer program account
      * @example
      * ```typescript
-     * const mailerAddress = client.getMailerAddress();
-     * console.log('Mailer PDA:', mailerAddress.toString());
-     * 
-     * // This address stores the program state and USDC balance
-     * const usdcAccount = getAssociatedTokenAddressSync(usdcMint, mailerAddress, true);
+     * const mailerAddress = client.getMailerAddress();
+     * console.log('Mailer PDA:', mailerAddress.toString());
+     * 
+     * // This address stores the program state and USDC balance
+     * const usdcAccount = getAssociatedTokenAddressSync(usdcMint, mailerAddress, true);
+     * ```
+     */
+    getMailerAddress(): PublicKey {
+        return this.mailerPda;
+    }
+
+    /**
+     * @description Refuse or accept again mail from a sender addressed to the connected wallet
+     * @notice Blocking creates a small marker account funded by the wallet; unblocking closes it and refunds the rent
+     * @param sender Address to block or unblock
+     * @param blocked Block the sender instead of unblocking them
+     * @returns Promise resolving to transaction signature
+     * @throws {Error} If the sender is already in the requested state
+     * @example
+     * ```typescript
+     * await client.setSenderBlocked(spammer, true);
+     * ```
+     */
+    async setSenderBlocked(sender: PublicKey, blocked: boolean): Promise<string> {
+        const recipient = this.provider.wallet.publicKey;
+        const block = this.getBlockAddress(recipient, sender);
+
+        if (blocked) {
+            return await (this.program.methods as any)
+                .blockSender(sender)
+                .accounts({ block, recipient, systemProgram: SystemProgram.programId })
+                .rpc();
+        }
+        return await (this.program.methods as any)
+            .unblockSender()
+            .accounts({ block, recipient })
+            .rpc();
+    }
+
+    /**
+     * @description Check whether a recipient has blocked a sender
+     * @param recipient Recipient wallet address
+     * @param sender Sender wallet address
+     * @returns Promise resolving to whether mail from the sender to the recipient is refused
+     */
+    async isSenderBlocked(recipient: PublicKey, sender: PublicKey): Promise<boolean> {
+        return (await this.provider.connection.getAccountInfo(this.getBlockAddress(recipient, sender))) !== null;
+    }
+
+    /**
+     * @description Add an address to or remove it from the compliance denylist (owner only)
+     * @notice A denied address cannot sign sends, receive addressed sends or claim its revenue share;
+     *         the marker account is funded by the owner and closed again on removal
+     * @param address Address to deny or allow again
+     * @param denied Deny the address instead of removing it from the list
+     * @returns Promise resolving to transaction signature
+     * @throws {Error} If caller is not owner or the address is already in the requested state
+     * @example
+     * ```typescript
+     * await client.setAddressDenied(sanctionedWallet, true);
+     * ```
+     */
+    async setAddressDenied(address: PublicKey, denied: boolean): Promise<string> {
+        const denylist = this.getDenylistAddress(address);
+        const owner = this.provider.wallet.publicKey;
+
+        if (denied) {
+            return await (this.program.methods as any)
+                .denyAddress(address)
+                .accounts({ denylist, mailer: this.mailerPda, owner, systemProgram: SystemProgram.programId })
+                .rpc();
+        }
+        return await (this.program.methods as any)
+            .undenyAddress()
+            .accounts({ denylist, mailer: this.mailerPda, owner })
+            .rpc();
+    }
+
+    /**
+     * @description Check whether an address is on the compliance denylist
+     * @param address Wallet address
+     * @returns Promise resolving to whether the address is denied
+     */
+    async isAddressDenied(address: PublicKey): Promise<boolean> {
+        return (await this.provider.connection.getAccountInfo(this.getDenylistAddress(address))) !== null;
+    }
+
+    /**
+     * @description Require senders who are not contacts to lock a deposit with each addressed message
+     * @param depositFees Deposit per message in send fees (max 100); 0 disables deposits
+     * @returns Promise resolving to transaction signature
+     * @throws {Error} If more than 100 send fees are required
+     * @example
+     * ```typescript
+     * // Strangers lock 1 USDC per message at the default 0.1 USDC fee
+     * await client.setSpamDeposit(10);
+     * ```
+     */
+    async setSpamDeposit(depositFees: number): Promise<string> {
+        const recipient = this.provider.wallet.publicKey;
+
+        return await (this.program.methods as any)
+            .setSpamDeposit(depositFees)
+            .accounts({
+                spamPolicy: this.getSpamPolicyAddress(recipient),
+                recipient,
+                systemProgram: SystemProgram.programId,
+            })
+            .rpc();
+    }
+
+    /**
+     * @description Make a sender a contact of the connected wallet, so their mail needs no deposit
+     * @param sender Address to vouch for
+     * @returns Promise resolving to transaction signature
+     * @throws {Error} If the sender is already a contact
+     */
+    async approveSender(sender: PublicKey): Promise<string> {
+        const recipient = this.provider.wallet.publicKey;
+
+        return await (this.program.methods as any)
+            .approveSender(sender)
+            .accounts({
+                contact: this.getContactAddress(recipient, sender),
+                recipient,
+                systemProgram: SystemProgram.programId,
+            })
+            .rpc();
+    }
+
+    /**
+     * @description Return a sender's deposit as legitimate mail, making them a contact
+     * @param sender Sender whose deposit to refund
+     * @returns Promise resolving to transaction signature
+     * @throws {Error} If the sender has no outstanding deposit with the connected wallet
+     */
+    async refundSpamDeposit(sender: PublicKey): Promise<string> {
+        const recipient = this.provider.wallet.publicKey;
+        const deposit = await (this.program.account as any).spamDeposit.fetch(this.getSpamDepositAddress(recipient, sender));
+
+        return await (this.program.methods as any)
+            .refundSpamDeposit()
+            .accounts({
+                deposit: this.getSpamDepositAddress(recipient, sender),
+                contact: this.getContactAddress(recipient, sender),
+                mailer: this.mailerPda,
+                sender,
+                rentPayer: deposit.rentPayer,
+                mint: deposit.mint,
+                senderUsdcAccount: getAssociatedTokenAddressSync(deposit.mint, sender, false, this.tokenProgramId),
+                mailerUsdcAccount: getAssociatedTokenAddressSync(deposit.mint, this.mailerPda, true, this.tokenProgramId),
+                recipient,
+                tokenProgram: this.tokenProgramId,
+                systemProgram: SystemProgram.programId,
+            })
+            .rpc();
+    }
+
+    /**
+     * @description Keep a spammer's deposit, crediting it to the connected wallet's claim
+     * @param sender Sender whose deposit to confiscate
+     * @returns Promise resolving to transaction signature
+     * @throws {Error} If there is no outstanding deposit, or the claim holds another mint
+     */
+    async confiscateSpamDeposit(sender: PublicKey): Promise<string> {
+        const recipient = this.provider.wallet.publicKey;
+        const deposit = await (this.program.account as any).spamDeposit.fetch(this.getSpamDepositAddress(recipient, sender));
+
+        return await (this.program.methods as any)
+            .confiscateSpamDeposit()
+            .accounts({
+                deposit: this.getSpamDepositAddress(recipient, sender),
+                recipientClaim: this.getClaimAddress(recipient),
+                mailer: this.mailerPda,
+                rentPayer: deposit.rentPayer,
+                expiryIndex: this.getExpiryIndexAddress(Math.floor(Date.now() / 1000)),
+                recipient,
+                systemProgram: SystemProgram.programId,
+            })
+            .rpc();
+    }
+
+    /**
+     * @description Get the deposit a sender has outstanding with a recipient
+     * @param recipient Recipient wallet address
+     * @param sender Sender wallet address
+     * @returns Promise resolving to the deposit, or null once refunded or confiscated
+     */
+    async getSpamDeposit(recipient: PublicKey, sender: PublicKey): Promise<SpamDepositInfo | null> {
+        const account = await (this.program.account as any).spamDeposit.fetchNullable(
+            this.getSpamDepositAddress(recipient, sender)
+        );
+        if (!account) {
+            return null;
+        }
+        return {
+            recipient: account.recipient,
+            sender: account.sender,
+            mint: account.mint,
+            amount: account.amount.toNumber(),
+        };
+    }
+
+    /**
+     * @description Check whether mail from a sender to a recipient must lock a deposit
+     * @param recipient Recipient wallet address
+     * @param sender Sender wallet address
+     * @returns Promise resolving to true when the recipient requires deposits and the sender is not a contact
+     */
+    async isDepositRequired(recipient: PublicKey, sender: PublicKey): Promise<boolean> {
+        if (recipient.equals(sender)) {
+            return false;
+        }
+        const policy = await (this.program.account as any).spamPolicy.fetchNullable(this.getSpamPolicyAddress(recipient));
+        if (!policy || policy.depositFees === 0) {
+            return false;
+        }
+        return (await this.provider.connection.getAccountInfo(this.getContactAddress(recipient, sender))) === null;
+    }
+
+    /**
+     * @description Get the PDA holding a recipient's spam deposit terms
+     * @param recipient Recipient wallet address
+     * @returns The deterministically derived spam policy address
+     */
+    getSpamPolicyAddress(recipient: PublicKey): PublicKey {
+        const [policyPda] = PublicKey.findProgramAddressSync(
+            [Buffer.from('spam'), recipient.toBuffer()],
+            this.program.programId
+        );
+        return policyPda;
+    }
+
+    /**
+     * @description Get the PDA marking a sender as a recipient's contact
+     * @param recipient Recipient wallet address
+     * @param sender Sender wallet address
+     * @returns The deterministically derived contact marker address
+     */
+    getContactAddress(recipient: PublicKey, sender: PublicKey): PublicKey {
+        const [contactPda] = PublicKey.findProgramAddressSync(
+            [Buffer.from('contact'), recipient.toBuffer(), sender.toBuffer()],
+            this.program.programId
+        );
+        return contactPda;
+    }
+
+    /**
+     * @description Get the PDA of a sender's outstanding deposit with a recipient
+     * @param recipient Recipient wallet address
+     * @param sender Sender wallet address
+     * @returns The deterministically derived deposit address
+     */
+    getSpamDepositAddress(recipient: PublicKey, sender: PublicKey): PublicKey {
+        const [depositPda] = PublicKey.findProgramAddressSync(
+            [Buffer.from('deposit'), recipient.toBuffer(), sender.toBuffer()],
+            this.program.programId
+        );
+        return depositPda;
+    }
+
+    /**
+     * @description Get the PDA marking that a recipient blocked a sender
+     * @param recipient Recipient wallet address
+     * @param sender Blocked sender address
+     * @returns The deterministically derived block marker address
+     */
+    getBlockAddress(recipient: PublicKey, sender: PublicKey): PublicKey {
+        const [blockPda] = PublicKey.findProgramAddressSync(
+            [Buffer.from('block'), recipient.toBuffer(), sender.toBuffer()],
+            this.program.programId
+        );
+        return blockPda;
+    }
+
+    /**
+     * @description Get the compliance denylist marker PDA of an address
+     * @param address Wallet address
+     * @returns The deterministically derived denylist marker address
+     */
+    getDenylistAddress(address: PublicKey): PublicKey {
+        const [denylistPda] = PublicKey.findProgramAddressSync(
+            [Buffer.from('deny'), address.toBuffer()],
+            this.program.programId
+        );
+        return denylistPda;
+    }
+
+    /**
+     * @description Get the fee exemption marker PDA of an address
+     * @param address Wallet address
+     * @returns The deterministically derived fee exemption address
+     */
+    getFeeExemptAddress(address: PublicKey): PublicKey {
+        const [feeExemptPda] = PublicKey.findProgramAddressSync(
+            [Buffer.from('exempt'), address.toBuffer()],
+            this.program.programId
+        );
+        return feeExemptPda;
+    }
+
+    /**
+     * @description Get the PDA of a referral code
+     * @param code Referral code
+     * @returns The deterministically derived referral code address
+     */
+    getReferralCodeAddress(code: string): PublicKey {
+        const codeHash = createHash('sha256').update(code, 'utf8').digest();
+        const [referralPda] = PublicKey.findProgramAddressSync(
+            [Buffer.from('referral'), codeHash],
+            this.program.programId
+        );
+        return referralPda;
+    }
+
+    /**
+     * @description Get the PDA of a campaign
+     * @param id Campaign ID
+     * @returns The deterministically derived campaign address
+     */
+    getCampaignAddress(id: number): PublicKey {
+        const [campaignPda] = PublicKey.findProgramAddressSync(
+            [Buffer.from('campaign'), new BN(id).toArrayLike(Buffer, 'le', 8)],
+            this.program.programId
+        );
+        return campaignPda;
+    }
+
+    /**
+     * @description Get the Metaplex metadata PDA of an NFT mint
+     * @param mint NFT mint
+     * @returns The metadata address owned by the Token Metadata program
+     */
+    getNftMetadataAddress(mint: PublicKey): PublicKey {
+        const [metadataPda] = PublicKey.findProgramAddressSync(
+            [Buffer.from('metadata'), TOKEN_METADATA_PROGRAM_ID.toBuffer(), mint.toBuffer()],
+            TOKEN_METADATA_PROGRAM_ID
+        );
+        return metadataPda;
+    }
+
+    /**
+     * @description Get the receipt configuration PDA
+     * @returns The receipt config address
+     */
+    getReceiptConfigAddress(): PublicKey {
+        const [configPda] = PublicKey.findProgramAddressSync(
+            [Buffer.from('receipts')],
+            this.program.programId
+        );
+        return configPda;
+    }
+
+    /**
+     * @description Get the Bubblegum tree config PDA of a merkle tree
+     * @param merkleTree Receipt merkle tree
+     * @returns The tree config address owned by the Bubblegum program
+     */
+    getReceiptTreeConfigAddress(merkleTree: PublicKey): PublicKey {
+        const [treeConfigPda] = PublicKey.findProgramAddressSync(
+            [merkleTree.toBuffer()],
+            BUBBLEGUM_PROGRAM_ID
+        );
+        return treeConfigPda;
+    }
+
+    /**
+     * @description Get the message log PDA
+     * @returns The message log address
+     */
+    getMessageLogAddress(): PublicKey {
+        const [logPda] = PublicKey.findProgramAddressSync(
+            [Buffer.from('log')],
+            this.program.programId
+        );
+        return logPda;
+    }
+
+    /**
+     * @description Get the PDA of a day's message root
+     * @param day Day number
+     * @returns The message root address
+     */
+    getMessageRootAddress(day: number): PublicKey {
+        const [rootPda] = PublicKey.findProgramAddressSync(
+            [Buffer.from('root'), new BN(day).toArrayLike(Buffer, 'le', 8)],
+            this.program.programId
+        );
+        return rootPda;
+    }
+
+    /**
+     * @description Get the loyalty stats PDA of a sender
+     * @param sender Wallet address
+     * @returns The deterministically derived sender stats address
+     */
+    getSenderStatsAddress(sender: PublicKey): PublicKey {
+        const [statsPda] = PublicKey.findProgramAddressSync(
+            [Buffer.from('stats'), sender.toBuffer()],
+            this.program.programId
+        );
+        return statsPda;
+    }
+
+    /**
+     * @description Get the preferences PDA for a recipient
+     * @param recipient Recipient wallet address
+     * @returns The deterministically derived preferences account address
+     * @example
+     * ```typescript
+     * const prefs = client.getPreferencesAddress(wallet.publicKey);
+     * ```
+     */
+    getPreferencesAddress(recipient: PublicKey): PublicKey {
+        const [preferencesPda] = PublicKey.findProgramAddressSync(
+            [Buffer.from('prefs'), recipient.toBuffer()],
+            this.program.programId
+        );
+        return preferencesPda;
+    }
+
+    /**
+     * @description Get the PDA holding the owner's CPI caller policy
+     * @returns The deterministically derived caller policy account address
+     */
+    getCallerPolicyAddress(): PublicKey {
+        const [policyPda] = PublicKey.findProgramAddressSync(
+            [Buffer.from('callers')],
+            this.program.programId
+        );
+        return policyPda;
+    }
+
+    /**
+     * @description Get the replay-protection record PDA of a sender's prepared mail ID
+     * @param sender Sender of the prepared message
+     * @param mailId Pre-prepared message identifier
+     * @returns The deterministically derived mail ID record address
+     */
+    getMailIdRecordAddress(sender: PublicKey, mailId: string): PublicKey {
+        const idHash = createHash('sha256').update(mailId, 'utf8').digest();
+        const [recordPda] = PublicKey.findProgramAddressSync(
+            [Buffer.from('mailid'), sender.toBuffer(), idHash],
+            this.program.programId
+        );
+        return recordPda;
+    }
+
+    /**
+     * @description Check whether a sender sent a prepared mail ID with replay protection
+     * @param sender Sender of the prepared message
+     * @param mailId Pre-prepared message identifier
+     * @returns Promise resolving to true if a mail ID record exists
+     */
+    async isMailIdRecorded(sender: PublicKey, mailId: string): Promise<boolean> {
+        return (await this.provider.connection.getAccountInfo(this.getMailIdRecordAddress(sender, mailId))) !== null;
+    }
+
+    /**
+     * @description Send a message paying the fee in SOL from a wallet without wSOL
+     * @notice Wraps exactly the fee into the sender's wSOL account, sends, and closes the
+     *         wSOL account in one transaction, so no separate wrapping step is needed.
+     *         The client must be constructed with `NATIVE_MINT` as its mint, and wrapped
+     *         SOL must have been added with `addToken`
+     * @param subject Message subject line
+     * @param body Message content
+     * @param priority Send as priority (full fee, revenue share) instead of standard
+     * @param clientRef Optional 16-byte caller reference echoed in the send event, e.g. an idempotency key
+     * @returns Promise resolving to transaction signature
+     * @throws {Error} If the client mint is not wrapped SOL, or the wallet lacks SOL
+     * @example
+     * ```typescript
+     * const solClient = new MailerClient(connection, wallet, programId, NATIVE_MINT);
+     * await solClient.sendWithSol('Hello', 'Paid in SOL');
+     * ```
+     */
+    async sendWithSol(subject: string, body: string, priority: boolean = false, clientRef?: Uint8Array): Promise<string> {
+        if (!this.usdcMint.equals(NATIVE_MINT)) {
+            throw new Error('sendWithSol requires a client constructed with NATIVE_MINT');
+        }
+        const sender = this.provider.wallet.publicKey;
+        const [recipientClaimPda] = PublicKey.findProgramAddressSync(
+            [Buffer.from('claim'), sender.toBuffer()],
+            this.program.programId
+        );
+
+        const senderUsdcAccount = getAssociatedTokenAddressSync(this.usdcMint, sender, false, this.tokenProgramId);
+
+        const mailerUsdcAccount = getAssociatedTokenAddressSync(this.usdcMint, this.mailerPda, true, this.tokenProgramId);
+
+        const wrapIx = await (this.program.methods as any)
+            .wrapSolFee(priority)
+            .accounts({
+                mailer: this.mailerPda,
+                tokenConfig: this.getTokenConfigAddress(this.usdcMint),
+                priceUpdate: this.priceUpdate,
+                mint: this.usdcMint,
+                sender,
+                senderWsolAccount: senderUsdcAccount,
+                tokenProgram: this.tokenProgramId,
+                associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+                systemProgram: SystemProgram.programId,
+            })
+            .instruction();
+
+        // Unwrap whatever the send did not spend back to SOL
+        const closeIx = createCloseAccountInstruction(senderUsdcAccount, sender, sender, [], this.tokenProgramId);
+
+        return await (this.program.methods as any)[priority ? 'sendPriority' : 'send'](
+            subject,
+            body,
+            null,
+            null,
+            this.encodeClientRef(clientRef)
+        )
+            .accounts({
+                recipientClaim: recipientClaimPda,
+                mailer: this.mailerPda,
+                stats: this.statsPda,
+                tokenConfig: this.getTokenConfigAddress(this.usdcMint),
+                priceUpdate: this.priceUpdate,
+                ownerBucket: this.getOwnerBucketAddress(this.usdcMint, 'send'),
+                mint: this.usdcMint,
+                sender: sender,
+                payer: sender,
+                senderUsdcAccount,
+                mailerUsdcAccount,
+                recipientPreferences: this.getPreferencesAddress(sender),
+                callerPolicy: this.getCallerPolicyAddress(),
+                instructions: SYSVAR_INSTRUCTIONS_PUBKEY,
+                expiryIndex: this.getExpiryIndexAddress(Math.floor(Date.now() / 1000)),
+                rateLimit: this.getRateLimitAddress(sender),
+                denylist: this.getDenylistAddress(sender),
+                feeExempt: this.feeExempt ? this.getFeeExemptAddress(sender) : null,
+                referral: this.referral?.code ?? null,
+                referrerClaim: this.referral ? this.getClaimAddress(this.referral.referrer) : null,
+                campaign: this.campaign,
+                senderStats: this.earnPoints ? this.getSenderStatsAddress(sender) : null,
+                nftToken: this.nftMint ? getAssociatedTokenAddressSync(this.nftMint, sender) : null,
+                nftMetadata: this.nftMint ? this.getNftMetadataAddress(this.nftMint) : null,
+                stake: this.staked ? this.getStakeAddress(sender) : null,
+                receiptConfig: this.receiptTree ? this.getReceiptConfigAddress() : null,
+                receiptTreeConfig: this.receiptTree ? this.getReceiptTreeConfigAddress(this.receiptTree) : null,
+                receiptTree: this.receiptTree,
+                bubblegumProgram: this.receiptTree ? BUBBLEGUM_PROGRAM_ID : null,
+                logWrapper: this.receiptTree || this.messageLogTree ? SPL_NOOP_PROGRAM_ID : null,
+                compressionProgram: this.receiptTree || this.messageLogTree ? SPL_ACCOUNT_COMPRESSION_PROGRAM_ID : null,
+                messageLog: this.messageLogTree ? this.getMessageLogAddress() : null,
+                messageLogTree: this.messageLogTree,
+                inbox: this.getInboxAddress(sender),
+                credit: this.prepaidCredit ? this.getCreditAddress(sender) : null,
+                planSubscription: this.planSubscription ? this.getPlanSubscriptionAddress(sender) : null,
+                tokenProgram: this.tokenProgramId,
+                associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+                systemProgram: SystemProgram.programId,
+            })
+            .preInstructions([wrapIx])
+            .postInstructions([closeIx])
+            .rpc();
+    }
+
+    /**
+     * @description Send a message with a payment escrowed for the recipient
+     * @notice The recipient collects the payment with acknowledgePayment. With an
+     *         acknowledgement deadline, anyone can refund it to the sender once it passes
+     * @param recipient Address that may acknowledge and collect the payment
+     * @param amount Payment in the client mint's base units
+     * @param mailId Pre-prepared message identifier
+     * @param ackDeadline Optional unix time (seconds) after which the payment is refundable
+     * @param recipientExternal Optional recipient on another chain, stored on the escrow for the relay
+     * @param clientRef Optional 16-byte caller reference echoed in the send event, e.g. an idempotency key
+     * @returns Promise resolving to the transaction signature and the generated payment ID that
+     *          keys the escrow; the message ID is derived on-chain and emitted in PaymentMailSent
+     * @throws {Error} If the amount is zero, the deadline is not in the future, the recipient blocked the sender, or transfer fails
+     * @example
+     * ```typescript
+     * // Pay 5 USDC for a reply; refundable if unread after a week
+     * const { paymentId } = await client.sendWithPayment(
+     *     recipient, 5_000_000, 'QmX7Y8Z9...', Math.floor(Date.now() / 1000) + 7 * 24 * 60 * 60
+     * );
+     * ```
+     */
+    async sendWithPayment(
+        recipient: PublicKey,
+        amount: number,
+        mailId: string,
+        ackDeadline?: number,
+        recipientExternal?: ExternalAddress,
+        clientRef?: Uint8Array
+    ): Promise<{ signature: string; paymentId: Buffer }> {
+        const sender = this.provider.wallet.publicKey;
+        const paymentId = randomBytes(32);
+
+        const signature = await (this.program.methods as any)
+            .sendWithPayment({
+                paymentId: Array.from(paymentId),
+                recipient,
+                amount: new BN(amount),
+                mailId,
+                ackDeadline: ackDeadline === undefined ? null : new BN(ackDeadline),
+                recipientExternal: this.encodeExternalAddress(recipientExternal),
+                clientRef: this.encodeClientRef(clientRef),
+            })
+            .accounts({
+                escrow: this.getEscrowAddress(paymentId),
+                mailer: this.mailerPda,
+                stats: this.statsPda,
+                tokenConfig: this.getTokenConfigAddress(this.usdcMint),
+                mint: this.usdcMint,
+                sender,
+                payer: sender,
+                senderUsdcAccount: getAssociatedTokenAddressSync(this.usdcMint, sender, false, this.tokenProgramId),
+                mailerUsdcAccount: getAssociatedTokenAddressSync(this.usdcMint, this.mailerPda, true, this.tokenProgramId),
+                rateLimit: this.getRateLimitAddress(sender),
+                denylist: this.getDenylistAddress(sender),
+                recipientDenylist: this.getDenylistAddress(recipient),
+                block: this.getBlockAddress(recipient, sender),
+                inbox: this.getInboxAddress(recipient),
+                tokenProgram: this.tokenProgramId,
+                systemProgram: SystemProgram.programId,
+            })
+            .rpc();
+
+        return { signature, paymentId };
+    }
+
+    /**
+     * @description Acknowledge a payment-bearing message and collect its payment
+     * @param paymentId Payment ID returned by sendWithPayment or seen in PaymentMailSent
+     * @returns Promise resolving to transaction signature
+     * @throws {Error} If the caller is not the recipient, the deadline has passed or the
+     *         recipient is denylisted
+     */
+    async acknowledgePayment(paymentId: Buffer): Promise<string> {
+        const recipient = this.provider.wallet.publicKey;
+        const escrow = await this.fetchEscrow(paymentId);
+
+        return await (this.program.methods as any)
+            .acknowledgePayment()
+            .accounts({
+                escrow: this.getEscrowAddress(paymentId),
+                mailer: this.mailerPda,
+                recipient,
+                rentPayer: escrow.rentPayer,
+                mint: escrow.mint,
+                recipientUsdcAccount: getAssociatedTokenAddressSync(escrow.mint, recipient, false, this.tokenProgramId),
+                mailerUsdcAccount: getAssociatedTokenAddressSync(escrow.mint, this.mailerPda, true, this.tokenProgramId),
+                denylist: this.getDenylistAddress(recipient),
+                tokenProgram: this.tokenProgramId,
+            })
+            .rpc();
+    }
+
+    /**
+     * @description Refund an unacknowledged payment to its sender after its deadline
+     * @notice Permissionless crank; emits MailExpiredUnread
+     * @param paymentId Payment ID of the expired payment
+     * @returns Promise resolving to transaction signature
+     * @throws {Error} If the payment has no deadline or the deadline has not passed
+     */
+    async refundUnread(paymentId: Buffer): Promise<string> {
+        const escrow = await this.fetchEscrow(paymentId);
+
+        return await (this.program.methods as any)
+            .refundUnread()
+            .accounts({
+                escrow: this.getEscrowAddress(paymentId),
+                mailer: this.mailerPda,
+                sender: escrow.sender,
+                rentPayer: escrow.rentPayer,
+                mint: escrow.mint,
+                senderUsdcAccount: getAssociatedTokenAddressSync(escrow.mint, escrow.sender, false, this.tokenProgramId),
+                mailerUsdcAccount: getAssociatedTokenAddressSync(escrow.mint, this.mailerPda, true, this.tokenProgramId),
+                cranker: this.provider.wallet.publicKey,
+                tokenProgram: this.tokenProgramId,
+            })
+            .rpc();
+    }
+
+    /**
+     * @description Reclaim one of your unacknowledged payments after the 30-day reclaim timeout
+     * @notice Only for payments sent without an acknowledgement deadline; those with one are
+     *         refunded by refundUnread. Emits PaymentReclaimed
+     * @param paymentId Payment ID of the payment
+     * @returns Promise resolving to transaction signature
+     * @throws {Error} If the wallet is not the sender, the payment has a deadline or the timeout
+     *         has not passed
+     * @example
+     * ```typescript
+     * const escrow = await client.getPaymentEscrow(paymentId);
+     * if (escrow && escrow.reclaimableAt !== null && Date.now() / 1000 > escrow.reclaimableAt) {
+     *     await client.reclaimPayment(paymentId);
+     * }
+     * ```
+     */
+    async reclaimPayment(paymentId: Buffer): Promise<string> {
+        const sender = this.provider.wallet.publicKey;
+        const escrow = await this.fetchEscrow(paymentId);
+
+        return await (this.program.methods as any)
+            .reclaimPayment()
+            .accounts({
+                escrow: this.getEscrowAddress(paymentId),
+                mailer: this.mailerPda,
+                sender,
+                rentPayer: escrow.rentPayer,
+                mint: escrow.mint,
+                senderUsdcAccount: getAssociatedTokenAddressSync(escrow.mint, sender, false, this.tokenProgramId),
+                mailerUsdcAccount: getAssociatedTokenAddressSync(escrow.mint, this.mailerPda, true, this.tokenProgramId),
+                tokenProgram: this.tokenProgramId,
+            })
+            .rpc();
+    }
+
+    /**
+     * @description Get an outstanding payment escrow
+     * @param paymentId Payment ID of the payment
+     * @returns Promise resolving to PaymentEscrowInfo, or null once acknowledged or refunded
+     */
+    async getPaymentEscrow(paymentId: Buffer): Promise<PaymentEscrowInfo | null> {
+        try {
+            const account = await this.fetchEscrow(paymentId);
+            const ackDeadline = account.ackDeadline.toNumber();
+            const createdAt = account.createdAt.toNumber();
+            return {
+                paymentId: Buffer.from(account.paymentId).toString('hex'),
+                messageId: Buffer.from(account.messageId).toString('hex'),
+                sender: account.sender,
+                recipient: account.recipient,
+                mint: account.mint,
+                amount: account.amount.toNumber(),
+                ackDeadline: ackDeadline === 0 ? null : ackDeadline,
+                createdAt,
+                reclaimableAt: ackDeadline === 0 ? createdAt + ESCROW_RECLAIM_DAYS * 24 * 60 * 60 : null,
+                recipientExternal: account.recipientExternal
+                    ? {
+                          chainId: account.recipientExternal.chainId.toNumber(),
+                          address: Uint8Array.from(account.recipientExternal.address),
+                      }
+                    : null,
+            };
+        } catch {
+            return null;
+        }
+    }
+
+    private async fetchEscrow(paymentId: Buffer): Promise<any> {
+        return await (this.program.account as any).paymentEscrow.fetch(this.getEscrowAddress(paymentId));
+    }
+
+    /**
+     * @description Get the escrow PDA of a payment
+     * @param paymentId 32-byte payment ID chosen at send time
+     * @returns The deterministically derived escrow address
+     */
+    getEscrowAddress(paymentId: Buffer): PublicKey {
+        const [escrowPda] = PublicKey.findProgramAddressSync(
+            [Buffer.from('escrow'), paymentId],
+            this.program.programId
+        );
+        return escrowPda;
+    }
+
+    /**
+     * @description Schedule a prepared message to be sent once a given time has passed
+     * @notice The standard fee is escrowed now; anyone may execute the send when it is due
+     * @param mailId Pre-prepared message identifier, 1 to 64 bytes
+     * @param executeAfter Unix time from which the send may be executed
+     * @returns Promise resolving to transaction signature
+     * @throws {Error} If the time is not in the future, the mail ID is already scheduled or USDC is short
+     * @example
+     * ```typescript
+     * // Birthday mail at midnight UTC
+     * await client.scheduleSend('birthday-2026', Date.UTC(2026, 4, 1) / 1000);
+     * ```
+     */
+    async scheduleSend(mailId: string, executeAfter: number): Promise<string> {
+        const sender = this.provider.wallet.publicKey;
+        return await (this.program.methods as any)
+            .scheduleSend(mailId, new BN(executeAfter))
+            .accounts({
+                scheduled: this.getScheduledMessageAddress(sender, mailId),
+                mailer: this.mailerPda,
+                tokenConfig: this.getTokenConfigAddress(this.usdcMint),
+                priceUpdate: this.priceUpdate,
+                mint: this.usdcMint,
+                sender,
+                payer: sender,
+                senderUsdcAccount: getAssociatedTokenAddressSync(this.usdcMint, sender, false, this.tokenProgramId),
+                mailerUsdcAccount: getAssociatedTokenAddressSync(this.usdcMint, this.mailerPda, true, this.tokenProgramId),
+                rateLimit: this.getRateLimitAddress(sender),
+                denylist: this.getDenylistAddress(sender),
+                tokenProgram: this.tokenProgramId,
+                systemProgram: SystemProgram.programId,
+            })
+            .rpc();
+    }
+
+    /**
+     * @description Send a due scheduled message
+     * @notice Permissionless crank; emits StandardPreparedMailSent from the scheduling sender
+     * @param sender Wallet that scheduled the message
+     * @param mailId The scheduled mail ID
+     * @returns Promise resolving to transaction signature
+     * @throws {Error} If the message is not scheduled or not due yet
+     */
+    async executeScheduled(sender: PublicKey, mailId: string): Promise<string> {
+        const scheduled = await this.getScheduledMessage(sender, mailId);
+        if (!scheduled) {
+            throw new Error('Message is not scheduled');
+        }
+        return await (this.program.methods as any)
+            .executeScheduled()
+            .accounts({
+                scheduled: this.getScheduledMessageAddress(sender, mailId),
+                mailer: this.mailerPda,
+                stats: this.statsPda,
+                ownerBucket: this.getOwnerBucketAddress(scheduled.mint, 'send'),
+                rentPayer: scheduled.rentPayer,
+                cranker: this.provider.wallet.publicKey,
+            })
+            .rpc();
+    }
+
+    /**
+     * @description Cancel one of your scheduled messages before it is executed, refunding its fee
+     * @param mailId The scheduled mail ID
+     * @returns Promise resolving to transaction signature
+     * @throws {Error} If the message is not scheduled or was already executed
+     */
+    async cancelScheduled(mailId: string): Promise<string> {
+        const sender = this.provider.wallet.publicKey;
+        const scheduled = await this.getScheduledMessage(sender, mailId);
+        if (!scheduled) {
+            throw new Error('Message is not scheduled');
+        }
+        return await (this.program.methods as any)
+            .cancelScheduled()
+            .accounts({
+                scheduled: this.getScheduledMessageAddress(sender, mailId),
+                mailer: this.mailerPda,
+                sender,
+                rentPayer: scheduled.rentPayer,
+                mint: scheduled.mint,
+                senderUsdcAccount: getAssociatedTokenAddressSync(scheduled.mint, sender, false, this.tokenProgramId),
+                mailerUsdcAccount: getAssociatedTokenAddressSync(scheduled.mint, this.mailerPda, true, this.tokenProgramId),
+                tokenProgram: this.tokenProgramId,
+            })
+            .rpc();
+    }
+
+    /**
+     * @description Get a pending scheduled message
+     * @param sender Wallet that scheduled the message
+     * @param mailId The scheduled mail ID
+     * @returns The scheduled message, or null once executed or cancelled
+     */
+    async getScheduledMessage(sender: PublicKey, mailId: string): Promise<ScheduledMessageInfo | null> {
+        const account = await (this.program.account as any).scheduledMessage.fetchNullable(
+            this.getScheduledMessageAddress(sender, mailId)
+        );
+        if (!account) {
+            return null;
+        }
+        return {
+            sender: account.sender,
+            mint: account.mint,
+            amount: account.amount.toNumber(),
+            executeAfter: account.executeAfter.toNumber(),
+            mailId: account.mailId,
+            rentPayer: account.rentPayer,
+        };
+    }
+
+    /**
+     * @description Get the PDA of a scheduled message
+     * @param sender Wallet that scheduled the message
+     * @param mailId The scheduled mail ID
+     * @returns The scheduled message address
+     */
+    getScheduledMessageAddress(sender: PublicKey, mailId: string): PublicKey {
+        const idHash = createHash('sha256').update(mailId, 'utf8').digest();
+        const [scheduledPda] = PublicKey.findProgramAddressSync(
+            [Buffer.from('scheduled'), sender.toBuffer(), idHash],
+            this.program.programId
+        );
+        return scheduledPda;
+    }
+
+    /**
+     * @description Open a session letting another key send messages on your behalf
+     * @notice Approves the mailer PDA to draw up to maxSpend more in fees from your USDC account
+     * @param sessionKey Key allowed to send, e.g. a keypair kept in the browser
+     * @param expiresAt Unix time the session ends, at most 30 days away
+     * @param maxSpend Most the session may spend on fees, in USDC base units
+     * @returns Promise resolving to transaction signature
+     * @throws {Error} If the expiry is not within the next 30 days or the key already has a session
+     * @example
+     * ```typescript
+     * const sessionKey = Keypair.generate();
+     * // One day of sending, up to 1 USDC in fees
+     * await client.createSession(sessionKey.publicKey, Math.floor(Date.now() / 1000) + 86400, 1_000_000);
      * ```
      */
-    getMailerAddress(): PublicKey {
-        return this.mailerPda;
+    async createSession(sessionKey: PublicKey, expiresAt: number, maxSpend: number): Promise<string> {
+        const owner = this.provider.wallet.publicKey;
+        return await (this.program.methods as any)
+            .createSession(sessionKey, new BN(expiresAt), new BN(maxSpend))
+            .accounts({
+                session: this.getSessionAddress(owner, sessionKey),
+                mailer: this.mailerPda,
+                tokenConfig: this.getTokenConfigAddress(this.usdcMint),
+                mint: this.usdcMint,
+                owner,
+                ownerUsdcAccount: getAssociatedTokenAddressSync(this.usdcMint, owner, false, this.tokenProgramId),
+                tokenProgram: this.tokenProgramId,
+                systemProgram: SystemProgram.programId,
+            })
+            .rpc();
+    }
+
+    /**
+     * @description Close one of your sessions, handing back the allowance it did not spend
+     * @param sessionKey The session's key
+     * @returns Promise resolving to transaction signature
+     * @throws {Error} If there is no such session
+     */
+    async revokeSession(sessionKey: PublicKey): Promise<string> {
+        const owner = this.provider.wallet.publicKey;
+        const session = await this.getSession(owner, sessionKey);
+        if (!session) {
+            throw new Error('Session does not exist');
+        }
+        return await (this.program.methods as any)
+            .revokeSession()
+            .accounts({
+                session: this.getSessionAddress(owner, sessionKey),
+                mailer: this.mailerPda,
+                mint: session.mint,
+                owner,
+                ownerUsdcAccount: getAssociatedTokenAddressSync(session.mint, owner, false, this.tokenProgramId),
+                tokenProgram: this.tokenProgramId,
+            })
+            .rpc();
+    }
+
+    /**
+     * @description Send a message to self on behalf of a session's owner
+     * @notice The client's wallet must be the session key; it also pays any rent.
+     * The fee is drawn from the owner's USDC account and the message is sent from the owner.
+     * @param owner Wallet that opened the session
+     * @param subject Message subject
+     * @param body Message body
+     * @param priority Whether to send as a priority (full fee) message
+     * @param clientRef Optional 16-byte caller reference echoed in the event
+     * @returns Promise resolving to transaction signature
+     * @throws {Error} If the session has expired or the fee would exceed its spend limit
+     */
+    async sendWithSession(
+        owner: PublicKey,
+        subject: string,
+        body: string,
+        priority: boolean,
+        clientRef?: Uint8Array
+    ): Promise<string> {
+        const sessionKey = this.provider.wallet.publicKey;
+        return await (this.program.methods as any)
+            .sendWithSession(subject, body, priority, this.encodeClientRef(clientRef))
+            .accounts({
+                session: this.getSessionAddress(owner, sessionKey),
+                recipientClaim: this.getClaimAddress(owner),
+                mailer: this.mailerPda,
+                stats: this.statsPda,
+                tokenConfig: this.getTokenConfigAddress(this.usdcMint),
+                priceUpdate: this.priceUpdate,
+                ownerBucket: this.getOwnerBucketAddress(this.usdcMint, 'send'),
+                mint: this.usdcMint,
+                owner,
+                sessionKey,
+                payer: sessionKey,
+                ownerUsdcAccount: getAssociatedTokenAddressSync(this.usdcMint, owner, false, this.tokenProgramId),
+                mailerUsdcAccount: getAssociatedTokenAddressSync(this.usdcMint, this.mailerPda, true, this.tokenProgramId),
+                recipientPreferences: this.getPreferencesAddress(owner),
+                callerPolicy: this.getCallerPolicyAddress(),
+                instructions: SYSVAR_INSTRUCTIONS_PUBKEY,
+                expiryIndex: this.getExpiryIndexAddress(Math.floor(Date.now() / 1000)),
+                rateLimit: this.getRateLimitAddress(owner),
+                denylist: this.getDenylistAddress(owner),
+                tokenProgram: this.tokenProgramId,
+                systemProgram: SystemProgram.programId,
+            })
+            .rpc();
+    }
+
+    /**
+     * @description Get an open session
+     * @param owner Wallet that opened the session
+     * @param sessionKey The session's key
+     * @returns The session, or null if it was never opened or has been revoked
+     */
+    async getSession(owner: PublicKey, sessionKey: PublicKey): Promise<SessionInfo | null> {
+        const account = await (this.program.account as any).session.fetchNullable(
+            this.getSessionAddress(owner, sessionKey)
+        );
+        if (!account) {
+            return null;
+        }
+        return {
+            owner: account.owner,
+            sessionKey: account.sessionKey,
+            mint: account.mint,
+            expiresAt: account.expiresAt.toNumber(),
+            maxSpend: account.maxSpend.toNumber(),
+            spent: account.spent.toNumber(),
+        };
+    }
+
+    /**
+     * @description Get the PDA of a session
+     * @param owner Wallet that opened the session
+     * @param sessionKey The session's key
+     * @returns The session address
+     */
+    getSessionAddress(owner: PublicKey, sessionKey: PublicKey): PublicKey {
+        const [sessionPda] = PublicKey.findProgramAddressSync(
+            [Buffer.from('session'), owner.toBuffer(), sessionKey.toBuffer()],
+            this.program.programId
+        );
+        return sessionPda;
+    }
+
+    /**
+     * @description Get the bytes a sender signs to let a relayer submit a prepared send for them
+     * @param sender Wallet the message is sent from and billed to
+     * @param mailId Pre-prepared message identifier, 1 to 64 bytes
+     * @param nonce The sender's next nonce, see getNextSignedSendNonce
+     * @param expiry Unix time after which the payload may no longer be relayed
+     * @returns The payload to sign, e.g. with a wallet's signMessage
+     */
+    getSignedSendPayload(sender: PublicKey, mailId: string, nonce: number, expiry: number): Buffer {
+        return Buffer.concat([
+            Buffer.from('mailer-signed-send'),
+            this.program.programId.toBuffer(),
+            sender.toBuffer(),
+            new BN(nonce).toArrayLike(Buffer, 'le', 8),
+            new BN(expiry).toTwos(64).toArrayLike(Buffer, 'le', 8),
+            Buffer.from(mailId, 'utf8'),
+        ]);
+    }
+
+    /**
+     * @description Get the nonce a sender's next signed send must carry
+     * @param sender Wallet signing the send
+     * @returns The next nonce, 0 before the sender's first signed send
+     */
+    async getNextSignedSendNonce(sender: PublicKey): Promise<number> {
+        const account = await (this.program.account as any).nonceRegistry.fetchNullable(
+            this.getNonceRegistryAddress(sender)
+        );
+        return account ? account.nextNonce.toNumber() : 0;
+    }
+
+    /**
+     * @description Relay a prepared send signed off chain by its sender
+     * @notice The client's wallet pays the SOL; the standard fee is pulled from the sender's
+     * USDC account, so the sender must have approved the mailer PDA as delegate
+     * @param sender Wallet that signed the payload
+     * @param mailId The signed mail ID
+     * @param nonce The signed nonce
+     * @param expiry The signed expiry
+     * @param signature Sender's 64-byte Ed25519 signature over getSignedSendPayload
+     * @returns Promise resolving to transaction signature
+     * @throws {Error} If the signature, nonce or expiry is invalid, or the allowance is short
+     */
+    async relaySignedSend(
+        sender: PublicKey,
+        mailId: string,
+        nonce: number,
+        expiry: number,
+        signature: Uint8Array
+    ): Promise<string> {
+        const relayer = this.provider.wallet.publicKey;
+        return await (this.program.methods as any)
+            .sendWithSignature(mailId, new BN(nonce), new BN(expiry))
+            .accounts({
+                nonceRegistry: this.getNonceRegistryAddress(sender),
+                mailer: this.mailerPda,
+                stats: this.statsPda,
+                tokenConfig: this.getTokenConfigAddress(this.usdcMint),
+                priceUpdate: this.priceUpdate,
+                ownerBucket: this.getOwnerBucketAddress(this.usdcMint, 'send'),
+                mint: this.usdcMint,
+                sender,
+                relayer,
+                senderUsdcAccount: getAssociatedTokenAddressSync(this.usdcMint, sender, false, this.tokenProgramId),
+                mailerUsdcAccount: getAssociatedTokenAddressSync(this.usdcMint, this.mailerPda, true, this.tokenProgramId),
+                recipientPreferences: this.getPreferencesAddress(sender),
+                callerPolicy: this.getCallerPolicyAddress(),
+                instructions: SYSVAR_INSTRUCTIONS_PUBKEY,
+                rateLimit: this.getRateLimitAddress(sender),
+                denylist: this.getDenylistAddress(sender),
+                tokenProgram: this.tokenProgramId,
+                systemProgram: SystemProgram.programId,
+            })
+            .preInstructions([
+                Ed25519Program.createInstructionWithPublicKey({
+                    publicKey: sender.toBytes(),
+                    message: this.getSignedSendPayload(sender, mailId, nonce, expiry),
+                    signature,
+                }),
+            ])
+            .rpc();
+    }
+
+    /**
+     * @description Get the PDA holding a sender's next signed send nonce
+     * @param sender Wallet signing sends
+     * @returns The nonce registry address
+     */
+    getNonceRegistryAddress(sender: PublicKey): PublicKey {
+        const [noncePda] = PublicKey.findProgramAddressSync(
+            [Buffer.from('nonce'), sender.toBuffer()],
+            this.program.programId
+        );
+        return noncePda;
+    }
+
+    /**
+     * @description Get the expiry index PDA a claim recorded at a given time is listed in
+     * @param claimTimestamp Claim's recorded timestamp (unix seconds)
+     * @returns The deterministically derived expiry index address
+     */
+    getExpiryIndexAddress(claimTimestamp: number): PublicKey {
+        const expiresAt = claimTimestamp + CLAIM_PERIOD_DAYS * 24 * 60 * 60;
+        return this.getExpiryIndexForWeek(Math.floor(expiresAt / (EXPIRY_BUCKET_DAYS * 24 * 60 * 60)));
+    }
+
+    private getExpiryIndexForWeek(week: number): PublicKey {
+        const [expiryIndexPda] = PublicKey.findProgramAddressSync(
+            [Buffer.from('expiry'), new BN(week).toArrayLike(Buffer, 'le', 8)],
+            this.program.programId
+        );
+        return expiryIndexPda;
+    }
+
+    /**
+     * @description Get the fee token display metadata PDA for a mint
+     * @param mint Token mint
+     * @returns The deterministically derived token display address
+     */
+    getTokenDisplayAddress(mint: PublicKey): PublicKey {
+        const [tokenDisplayPda] = PublicKey.findProgramAddressSync(
+            [Buffer.from('display'), mint.toBuffer()],
+            this.program.programId
+        );
+        return tokenDisplayPda;
+    }
+
+    private encodeTip(tip?: MailerTip): { amount: BN; target: object } | null {
+        if (!tip) {
+            return null;
+        }
+        return { amount: new BN(tip.amount), target: { [tip.target]: {} } };
+    }
+
+    private encodeClientRef(clientRef?: Uint8Array): number[] | null {
+        if (!clientRef) {
+            return null;
+        }
+        if (clientRef.length !== CLIENT_REF_LEN) {
+            throw new Error(`Client reference must be ${CLIENT_REF_LEN} bytes`);
+        }
+        return Array.from(clientRef);
+    }
+
+    private encodeExternalAddress(address?: ExternalAddress): { chainId: BN; address: Buffer } | null {
+        if (!address) {
+            return null;
+        }
+        return { chainId: new BN(address.chainId), address: Buffer.from(address.address) };
+    }
+
+    /**
+     * @description Get a sender's daily message counter PDA
+     * @param sender Sending wallet
+     * @returns The deterministically derived rate limit address
+     */
+    getRateLimitAddress(sender: PublicKey): PublicKey {
+        const [rateLimitPda] = PublicKey.findProgramAddressSync(
+            [Buffer.from('rate'), sender.toBuffer()],
+            this.program.programId
+        );
+        return rateLimitPda;
+    }
+
+    /**
+     * @description Get a sender's proof-of-work counter PDA
+     * @param sender Sending wallet
+     * @returns The deterministically derived proof-of-work state address
+     */
+    getPowStateAddress(sender: PublicKey): PublicKey {
+        const [powStatePda] = PublicKey.findProgramAddressSync(
+            [Buffer.from('pow'), sender.toBuffer()],
+            this.program.programId
+        );
+        return powStatePda;
+    }
+
+    /**
+     * @description Get the fee token configuration PDA for a mint
+     * @param mint Token mint
+     * @returns The deterministically derived token config address
+     */
+    getTokenConfigAddress(mint: PublicKey): PublicKey {
+        const [tokenConfigPda] = PublicKey.findProgramAddressSync(
+            [Buffer.from('token'), mint.toBuffer()],
+            this.program.programId
+        );
+        return tokenConfigPda;
+    }
+
+    /**
+     * @description Get the owner share bucket PDA for a mint and fee source
+     * @param mint Token mint
+     * @param source Fee source
+     * @returns The deterministically derived bucket address
+     */
+    getOwnerBucketAddress(mint: PublicKey, source: MailerFeeSource): PublicKey {
+        const [bucketPda] = PublicKey.findProgramAddressSync(
+            [Buffer.from('bucket'), mint.toBuffer(), Buffer.from(source)],
+            this.program.programId
+        );
+        return bucketPda;
     }
 
     /**
@@ -604,6 +4732,564 @@ export class MailerClient {
         return this.program.programId;
     }
 
+    /**
+     * @description Create a broadcast channel owned by the connected wallet
+     * @param name Channel name, 1 to 32 bytes; each name can be created once
+     * @returns Promise resolving to transaction signature
+     * @throws {Error} If the name is invalid or already taken
+     */
+    async createChannel(name: string): Promise<string> {
+        return await (this.program.methods as any)
+            .createChannel(name)
+            .accounts({
+                channel: this.getChannelAddress(name),
+                creator: this.provider.wallet.publicKey,
+                systemProgram: SystemProgram.programId,
+            })
+            .rpc();
+    }
+
+    /**
+     * @description Subscribe the connected wallet to a channel
+     * @param name Channel name
+     * @returns Promise resolving to transaction signature
+     * @throws {Error} If the wallet is already subscribed
+     */
+    async subscribeToChannel(name: string): Promise<string> {
+        const channel = this.getChannelAddress(name);
+        const subscriber = this.provider.wallet.publicKey;
+
+        return await (this.program.methods as any)
+            .subscribe()
+            .accounts({
+                channel,
+                subscription: this.getSubscriptionAddress(channel, subscriber),
+                subscriber,
+                systemProgram: SystemProgram.programId,
+            })
+            .rpc();
+    }
+
+    /**
+     * @description Post a prepared message to a channel
+     * @notice Pays the priority fee; the 90% recipient share is credited to the channel owner's claim.
+     *         Readers-only channels require the poster to hold an active subscription
+     * @param name Channel name
+     * @param mailId Pre-prepared message identifier
+     * @param rejectDuplicate Create a replay record so the mail ID can never be sent again
+     * @returns Promise resolving to transaction signature
+     * @throws {Error} If the channel does not exist or the wallet has insufficient USDC
+     */
+    async postToChannel(name: string, mailId: string, rejectDuplicate = false): Promise<string> {
+        const sender = this.provider.wallet.publicKey;
+        const channel = this.getChannelAddress(name);
+        const { owner, readersOnly } = await (this.program.account as any).channel.fetch(channel);
+        const [recipientClaimPda] = PublicKey.findProgramAddressSync(
+            [Buffer.from('claim'), sender.toBuffer()],
+            this.program.programId
+        );
+        const [ownerClaimPda] = PublicKey.findProgramAddressSync(
+            [Buffer.from('claim'), owner.toBuffer()],
+            this.program.programId
+        );
+
+        return await (this.program.methods as any)
+            .postToChannel(mailId)
+            .accounts({
+                send: {
+                    recipientClaim: recipientClaimPda,
+                    mailer: this.mailerPda,
+                    stats: this.statsPda,
+                    tokenConfig: this.getTokenConfigAddress(this.usdcMint),
+                    priceUpdate: this.priceUpdate,
+                    ownerBucket: this.getOwnerBucketAddress(this.usdcMint, 'send'),
+                    mint: this.usdcMint,
+                    sender,
+                    payer: sender,
+                    senderUsdcAccount: getAssociatedTokenAddressSync(this.usdcMint, sender, false, this.tokenProgramId),
+                    mailerUsdcAccount: getAssociatedTokenAddressSync(this.usdcMint, this.mailerPda, true, this.tokenProgramId),
+                    recipientPreferences: this.getPreferencesAddress(sender),
+                    callerPolicy: this.getCallerPolicyAddress(),
+                    instructions: SYSVAR_INSTRUCTIONS_PUBKEY,
+                    expiryIndex: this.getExpiryIndexAddress(Math.floor(Date.now() / 1000)),
+                    rateLimit: this.getRateLimitAddress(sender),
+                    denylist: this.getDenylistAddress(sender),
+                    feeExempt: this.feeExempt ? this.getFeeExemptAddress(sender) : null,
+                    referral: null,
+                    referrerClaim: null,
+                    campaign: null,
+                    senderStats: this.earnPoints ? this.getSenderStatsAddress(sender) : null,
+                    nftToken: this.nftMint ? getAssociatedTokenAddressSync(this.nftMint, sender) : null,
+                    nftMetadata: this.nftMint ? this.getNftMetadataAddress(this.nftMint) : null,
+                    stake: this.staked ? this.getStakeAddress(sender) : null,
+                    receiptConfig: null,
+                    receiptTreeConfig: null,
+                    receiptTree: null,
+                    bubblegumProgram: null,
+                    logWrapper: null,
+                    compressionProgram: null,
+                    messageLog: null,
+                    messageLogTree: null,
+                    mailIdRecord: rejectDuplicate ? this.getMailIdRecordAddress(sender, mailId) : null,
+                    tokenProgram: this.tokenProgramId,
+                    associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+                    systemProgram: SystemProgram.programId,
+                },
+                channel,
+                // The owner posting to their own channel is credited through `send.recipientClaim`
+                ownerClaim: owner.equals(sender) ? null : ownerClaimPda,
+                subscription: readersOnly && !owner.equals(sender) ? this.getSubscriptionAddress(channel, sender) : null,
+                mute: this.getMuteAddress(channel, sender),
+            })
+            .rpc();
+    }
+
+    /**
+     * @description Get a channel by name
+     * @param name Channel name
+     * @returns Promise resolving to the channel, or null if it does not exist
+     */
+    async getChannel(name: string): Promise<ChannelInfo | null> {
+        const address = this.getChannelAddress(name);
+        const account = await (this.program.account as any).channel.fetchNullable(address);
+        if (!account) {
+            return null;
+        }
+        return {
+            address,
+            owner: account.owner,
+            name: account.name,
+            subscriptionMint: account.subscriptionMint,
+            subscriptionFee: account.subscriptionFee.toNumber(),
+            readersOnly: account.readersOnly,
+            claimable: account.claimable.toNumber(),
+            moderators: account.moderators,
+            pinnedMessage: account.pinnedMessage ? Buffer.from(account.pinnedMessage).toString('hex') : null,
+            subscriberCount: account.subscriberCount.toNumber(),
+            postCount: account.postCount.toNumber(),
+            createdAt: account.createdAt.toNumber(),
+        };
+    }
+
+    /**
+     * @description Set a channel's subscription fee and posting policy (channel owner only)
+     * @param name Channel name
+     * @param subscriptionFee Fee per 30-day epoch in the client mint's base units; 0 for a free channel
+     * @param readersOnly Only accept posts from the owner and active subscribers
+     * @returns Promise resolving to transaction signature
+     * @throws {Error} If the wallet does not own the channel, or uncollected fees are in another mint
+     */
+    async setChannelTerms(name: string, subscriptionFee: number, readersOnly: boolean): Promise<string> {
+        return await (this.program.methods as any)
+            .setChannelTerms(new BN(subscriptionFee), readersOnly)
+            .accounts({
+                channel: this.getChannelAddress(name),
+                owner: this.provider.wallet.publicKey,
+                tokenConfig: this.getTokenConfigAddress(this.usdcMint),
+                mint: this.usdcMint,
+            })
+            .rpc();
+    }
+
+    /**
+     * @description Pay a channel's subscription fee, extending the wallet's subscription
+     * @notice The wallet must already be subscribed via subscribeToChannel
+     * @param name Channel name
+     * @param epochs Number of 30-day epochs to pay for
+     * @returns Promise resolving to transaction signature
+     * @throws {Error} If the channel is free or the wallet has insufficient funds
+     */
+    async paySubscription(name: string, epochs: number): Promise<string> {
+        const channel = this.getChannelAddress(name);
+        const subscriber = this.provider.wallet.publicKey;
+        const { subscriptionMint } = await (this.program.account as any).channel.fetch(channel);
+
+        return await (this.program.methods as any)
+            .paySubscription(epochs)
+            .accounts({
+                channel,
+                subscription: this.getSubscriptionAddress(channel, subscriber),
+                mailer: this.mailerPda,
+                subscriber,
+                mint: subscriptionMint,
+                subscriberUsdcAccount: getAssociatedTokenAddressSync(subscriptionMint, subscriber, false, this.tokenProgramId),
+                mailerUsdcAccount: getAssociatedTokenAddressSync(subscriptionMint, this.mailerPda, true, this.tokenProgramId),
+                tokenProgram: this.tokenProgramId,
+            })
+            .rpc();
+    }
+
+    /**
+     * @description Withdraw a channel's collected subscription fees (channel owner only)
+     * @param name Channel name
+     * @returns Promise resolving to transaction signature
+     * @throws {Error} If the wallet does not own the channel, nothing was collected or the
+     *         owner is denylisted
+     */
+    async withdrawChannelFees(name: string): Promise<string> {
+        const channel = this.getChannelAddress(name);
+        const owner = this.provider.wallet.publicKey;
+        const { subscriptionMint } = await (this.program.account as any).channel.fetch(channel);
+
+        return await (this.program.methods as any)
+            .withdrawChannelFees()
+            .accounts({
+                channel,
+                mailer: this.mailerPda,
+                owner,
+                mint: subscriptionMint,
+                ownerUsdcAccount: getAssociatedTokenAddressSync(subscriptionMint, owner, false, this.tokenProgramId),
+                mailerUsdcAccount: getAssociatedTokenAddressSync(subscriptionMint, this.mailerPda, true, this.tokenProgramId),
+                denylist: this.getDenylistAddress(owner),
+                tokenProgram: this.tokenProgramId,
+            })
+            .rpc();
+    }
+
+    /**
+     * @description Get a wallet's subscription to a channel
+     * @param name Channel name
+     * @param subscriber Subscribing wallet
+     * @returns Promise resolving to the subscription, or null if the wallet is not subscribed
+     */
+    async getSubscription(name: string, subscriber: PublicKey): Promise<ChannelSubscriptionInfo | null> {
+        const channel = this.getChannelAddress(name);
+        const account = await (this.program.account as any).subscription.fetchNullable(
+            this.getSubscriptionAddress(channel, subscriber)
+        );
+        if (!account) {
+            return null;
+        }
+        const { subscriptionFee } = await (this.program.account as any).channel.fetch(channel);
+        const paidUntil = account.paidUntil.toNumber();
+        return {
+            subscribedAt: account.subscribedAt.toNumber(),
+            paidUntil,
+            active: subscriptionFee.isZero() || paidUntil > Math.floor(Date.now() / 1000),
+        };
+    }
+
+    /**
+     * @description Appoint or remove a channel moderator (channel owner only)
+     * @param name Channel name
+     * @param moderator Wallet to appoint or remove
+     * @param grant Appoint the moderator instead of removing them
+     * @returns Promise resolving to transaction signature
+     * @throws {Error} If the wallet is not the owner, or the channel already has 4 moderators
+     */
+    async setChannelModerator(name: string, moderator: PublicKey, grant: boolean): Promise<string> {
+        const methods = this.program.methods as any;
+        return await (grant ? methods.grantChannelModerator(moderator) : methods.revokeChannelModerator(moderator))
+            .accounts({
+                channel: this.getChannelAddress(name),
+                owner: this.provider.wallet.publicKey,
+            })
+            .rpc();
+    }
+
+    /**
+     * @description Remove a subscriber from a channel (channel owner or moderator)
+     * @notice Any paid time is forfeited; the subscription's rent returns to the subscriber
+     * @param name Channel name
+     * @param subscriber Wallet to remove
+     * @returns Promise resolving to transaction signature
+     */
+    async removeSubscriber(name: string, subscriber: PublicKey): Promise<string> {
+        const channel = this.getChannelAddress(name);
+        return await (this.program.methods as any)
+            .removeSubscriber()
+            .accounts({
+                channel,
+                subscription: this.getSubscriptionAddress(channel, subscriber),
+                subscriber,
+                moderator: this.provider.wallet.publicKey,
+            })
+            .rpc();
+    }
+
+    /**
+     * @description Pin a post in a channel, replacing any earlier pin (channel owner or moderator)
+     * @param name Channel name
+     * @param messageId 32-byte message ID from the `ChannelPosted` event
+     * @returns Promise resolving to transaction signature
+     */
+    async pinPost(name: string, messageId: Buffer): Promise<string> {
+        return await (this.program.methods as any)
+            .pinPost(Array.from(messageId))
+            .accounts({
+                channel: this.getChannelAddress(name),
+                moderator: this.provider.wallet.publicKey,
+            })
+            .rpc();
+    }
+
+    /**
+     * @description Mute or unmute a sender in a channel (channel owner or moderator)
+     * @notice Muting creates a record paid for by the moderator; unmuting refunds it
+     * @param name Channel name
+     * @param sender Wallet to mute or unmute
+     * @param mute Mute the sender instead of unmuting them
+     * @returns Promise resolving to transaction signature
+     * @throws {Error} If the wallet is not a moderator, or the sender is the owner or a moderator
+     */
+    async setSenderMuted(name: string, sender: PublicKey, mute: boolean): Promise<string> {
+        const channel = this.getChannelAddress(name);
+        const moderator = this.provider.wallet.publicKey;
+        const muteAddress = this.getMuteAddress(channel, sender);
+
+        if (mute) {
+            return await (this.program.methods as any)
+                .muteSender(sender)
+                .accounts({ channel, mute: muteAddress, moderator, systemProgram: SystemProgram.programId })
+                .rpc();
+        }
+        const { mutedBy } = await (this.program.account as any).channelMute.fetch(muteAddress);
+        return await (this.program.methods as any)
+            .unmuteSender()
+            .accounts({ channel, mute: muteAddress, mutedBy, moderator })
+            .rpc();
+    }
+
+    /**
+     * @description Check whether a wallet is subscribed to a channel
+     * @param name Channel name
+     * @param subscriber Wallet to check
+     * @returns Promise resolving to true if a subscription exists
+     */
+    async isSubscribed(name: string, subscriber: PublicKey): Promise<boolean> {
+        const subscription = this.getSubscriptionAddress(this.getChannelAddress(name), subscriber);
+        return (await this.provider.connection.getAccountInfo(subscription)) !== null;
+    }
+
+    /**
+     * @description Get the PDA of a channel
+     * @param name Channel name
+     * @returns The channel address, derived from the SHA-256 of its name
+     */
+    getChannelAddress(name: string): PublicKey {
+        const nameHash = createHash('sha256').update(name, 'utf8').digest();
+        const [channelPda] = PublicKey.findProgramAddressSync(
+            [Buffer.from('channel'), nameHash],
+            this.program.programId
+        );
+        return channelPda;
+    }
+
+    /**
+     * @description Get the subscription PDA of a subscriber to a channel
+     * @param channel Channel address
+     * @param subscriber Subscribing wallet
+     * @returns The deterministically derived subscription address
+     */
+    getSubscriptionAddress(channel: PublicKey, subscriber: PublicKey): PublicKey {
+        const [subscriptionPda] = PublicKey.findProgramAddressSync(
+            [Buffer.from('subscription'), channel.toBuffer(), subscriber.toBuffer()],
+            this.program.programId
+        );
+        return subscriptionPda;
+    }
+
+    /**
+     * @description Get the mute record PDA of a sender in a channel
+     * @param channel Channel address
+     * @param sender Sending wallet
+     * @returns The deterministically derived mute address; it exists only while the sender is muted
+     */
+    getMuteAddress(channel: PublicKey, sender: PublicKey): PublicKey {
+        const [mutePda] = PublicKey.findProgramAddressSync(
+            [Buffer.from('mute'), channel.toBuffer(), sender.toBuffer()],
+            this.program.programId
+        );
+        return mutePda;
+    }
+
+    /**
+     * @description Create a group administered by the connected wallet
+     * @param name Group name, 1 to 32 bytes; each name can be created once
+     * @returns Promise resolving to transaction signature
+     * @throws {Error} If the name is invalid or already taken
+     */
+    async createGroup(name: string): Promise<string> {
+        return await (this.program.methods as any)
+            .createGroup(name)
+            .accounts({
+                group: this.getGroupAddress(name),
+                admin: this.provider.wallet.publicKey,
+                systemProgram: SystemProgram.programId,
+            })
+            .rpc();
+    }
+
+    /**
+     * @description Add a member to a group (group admin only)
+     * @notice Creates the member's claim, paid for by the admin, if it does not exist yet
+     * @param name Group name
+     * @param member Wallet to add
+     * @returns Promise resolving to transaction signature
+     * @throws {Error} If the wallet is not the admin, the member exists, or the group is full
+     */
+    async addGroupMember(name: string, member: PublicKey): Promise<string> {
+        return await (this.program.methods as any)
+            .addMember(member)
+            .accounts({
+                group: this.getGroupAddress(name),
+                memberClaim: this.getClaimAddress(member),
+                admin: this.provider.wallet.publicKey,
+                systemProgram: SystemProgram.programId,
+            })
+            .rpc();
+    }
+
+    /**
+     * @description Remove a member from a group (group admin or moderator)
+     * @param name Group name
+     * @param member Wallet to remove
+     * @returns Promise resolving to transaction signature
+     * @throws {Error} If the wallet is neither admin nor moderator, or the address is not a member
+     */
+    async removeGroupMember(name: string, member: PublicKey): Promise<string> {
+        return await (this.program.methods as any)
+            .removeMember(member)
+            .accounts({
+                group: this.getGroupAddress(name),
+                moderator: this.provider.wallet.publicKey,
+            })
+            .rpc();
+    }
+
+    /**
+     * @description Appoint or remove a group moderator (group admin only)
+     * @param name Group name
+     * @param moderator Wallet to appoint or remove
+     * @param grant Appoint the moderator instead of removing them
+     * @returns Promise resolving to transaction signature
+     * @throws {Error} If the wallet is not the admin, or the group already has 4 moderators
+     */
+    async setGroupModerator(name: string, moderator: PublicKey, grant: boolean): Promise<string> {
+        const methods = this.program.methods as any;
+        return await (grant ? methods.grantGroupModerator(moderator) : methods.revokeGroupModerator(moderator))
+            .accounts({
+                group: this.getGroupAddress(name),
+                admin: this.provider.wallet.publicKey,
+            })
+            .rpc();
+    }
+
+    /**
+     * @description Send a prepared message to every member of a group
+     * @notice Pays the priority fee; the 90% recipient share is split equally across the members' claims
+     * @param name Group name
+     * @param mailId Pre-prepared message identifier
+     * @param rejectDuplicate Create a replay record so the mail ID can never be sent again
+     * @returns Promise resolving to transaction signature
+     * @throws {Error} If the wallet is not a member or admin, or has insufficient USDC
+     */
+    async sendToGroup(name: string, mailId: string, rejectDuplicate = false): Promise<string> {
+        const sender = this.provider.wallet.publicKey;
+        const group = this.getGroupAddress(name);
+        const { members } = await (this.program.account as any).group.fetch(group);
+
+        return await (this.program.methods as any)
+            .sendToGroup(mailId)
+            .accounts({
+                send: {
+                    recipientClaim: this.getClaimAddress(sender),
+                    mailer: this.mailerPda,
+                    stats: this.statsPda,
+                    tokenConfig: this.getTokenConfigAddress(this.usdcMint),
+                    priceUpdate: this.priceUpdate,
+                    ownerBucket: this.getOwnerBucketAddress(this.usdcMint, 'send'),
+                    mint: this.usdcMint,
+                    sender,
+                    payer: sender,
+                    senderUsdcAccount: getAssociatedTokenAddressSync(this.usdcMint, sender, false, this.tokenProgramId),
+                    mailerUsdcAccount: getAssociatedTokenAddressSync(this.usdcMint, this.mailerPda, true, this.tokenProgramId),
+                    recipientPreferences: this.getPreferencesAddress(sender),
+                    callerPolicy: this.getCallerPolicyAddress(),
+                    instructions: SYSVAR_INSTRUCTIONS_PUBKEY,
+                    expiryIndex: this.getExpiryIndexAddress(Math.floor(Date.now() / 1000)),
+                    rateLimit: this.getRateLimitAddress(sender),
+                    denylist: this.getDenylistAddress(sender),
+                    feeExempt: this.feeExempt ? this.getFeeExemptAddress(sender) : null,
+                    referral: null,
+                    referrerClaim: null,
+                    campaign: null,
+                    senderStats: this.earnPoints ? this.getSenderStatsAddress(sender) : null,
+                    nftToken: this.nftMint ? getAssociatedTokenAddressSync(this.nftMint, sender) : null,
+                    nftMetadata: this.nftMint ? this.getNftMetadataAddress(this.nftMint) : null,
+                    stake: this.staked ? this.getStakeAddress(sender) : null,
+                    receiptConfig: null,
+                    receiptTreeConfig: null,
+                    receiptTree: null,
+                    bubblegumProgram: null,
+                    logWrapper: null,
+                    compressionProgram: null,
+                    messageLog: null,
+                    messageLogTree: null,
+                    mailIdRecord: rejectDuplicate ? this.getMailIdRecordAddress(sender, mailId) : null,
+                    tokenProgram: this.tokenProgramId,
+                    associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+                    systemProgram: SystemProgram.programId,
+                },
+                group,
+            })
+            .remainingAccounts(
+                (members as PublicKey[]).map((member) => ({
+                    pubkey: this.getClaimAddress(member),
+                    isWritable: true,
+                    isSigner: false,
+                }))
+            )
+            .rpc();
+    }
+
+    /**
+     * @description Get a group by name
+     * @param name Group name
+     * @returns Promise resolving to the group, or null if it does not exist
+     */
+    async getGroup(name: string): Promise<GroupInfo | null> {
+        const address = this.getGroupAddress(name);
+        const account = await (this.program.account as any).group.fetchNullable(address);
+        if (!account) {
+            return null;
+        }
+        return {
+            address,
+            admin: account.admin,
+            name: account.name,
+            members: account.members,
+            moderators: account.moderators,
+        };
+    }
+
+    /**
+     * @description Get the PDA of a group
+     * @param name Group name
+     * @returns The group address, derived from the SHA-256 of its name
+     */
+    getGroupAddress(name: string): PublicKey {
+        const nameHash = createHash('sha256').update(name, 'utf8').digest();
+        const [groupPda] = PublicKey.findProgramAddressSync(
+            [Buffer.from('group'), nameHash],
+            this.program.programId
+        );
+        return groupPda;
+    }
+
+    /**
+     * @description Get a recipient's claim PDA
+     * @param recipient Recipient wallet address
+     * @returns The deterministically derived claim address
+     */
+    getClaimAddress(recipient: PublicKey): PublicKey {
+        const [claimPda] = PublicKey.findProgramAddressSync(
+            [Buffer.from('claim'), recipient.toBuffer()],
+            this.program.programId
+        );
+        return claimPda;
+    }
+
     /**
      * @description Helper method to calculate fee splits for priority vs standard messages
      * @param sendFee The base sending fee amount
@@ -0,0 +1,34 @@
+import { createHash } from 'crypto';
+
+/** Bodies longer than this many bytes are emitted as a hash only (mirrors the program) */
+export const BODY_HASH_THRESHOLD = 512;
+
+/**
+ * @description Recover or verify the body of a send event
+ * @notice Events of bodies over `BODY_HASH_THRESHOLD` bytes carry only `bodyHash` and `bodyLen`;
+ *   pass the body from the transaction's instruction data to check it against the event
+ * @param event Decoded `priorityMailSent`, `standardMailSent` or `powMailSent` event
+ * @param fullBody Body taken from the instruction data, needed when the event omits it
+ * @returns The message body, or null if it was omitted and `fullBody` is missing or does not match
+ * @example
+ * ```typescript
+ * const body = resolveEventBody(event, decodedInstruction.data.body);
+ * ```
+ */
+export function resolveEventBody(
+    event: { body: string; bodyHash: number[] | Uint8Array | null; bodyLen: number },
+    fullBody?: string
+): string | null {
+    if (event.bodyHash === null) {
+        return event.body;
+    }
+    if (fullBody === undefined) {
+        return null;
+    }
+
+    const bytes = Buffer.from(fullBody, 'utf8');
+    const matches =
+        bytes.length === event.bodyLen &&
+        createHash('sha256').update(bytes).digest().equals(Buffer.from(event.bodyHash));
+    return matches ? fullBody : null;
+}
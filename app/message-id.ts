@@ -0,0 +1,66 @@
+import { createHash } from 'crypto';
+import { PublicKey } from '@solana/web3.js';
+
+const MESSAGE_ID_DOMAIN = Buffer.from('mailer-message');
+
+function u64(value: bigint): Buffer {
+    const buf = Buffer.alloc(8);
+    buf.writeBigUInt64LE(value);
+    return buf;
+}
+
+/**
+ * @description Compute the deterministic ID the program assigns to a message
+ * @notice Matches the `messageId` field of send events and the return data of send instructions
+ * @param sender Sending wallet
+ * @param messageSeq The message's `messageSeq` event field
+ * @param slot Slot the send transaction landed in
+ * @returns SHA-256 message ID
+ * @example
+ * ```typescript
+ * // Message i of a prepared batch
+ * const id = computeMessageId(event.from, BigInt(event.messageSeq.toString()) + BigInt(i), BigInt(event.slot.toString()));
+ * ```
+ */
+export function computeMessageId(sender: PublicKey, messageSeq: bigint, slot: bigint): Buffer {
+    return createHash('sha256')
+        .update(MESSAGE_ID_DOMAIN)
+        .update(sender.toBuffer())
+        .update(u64(messageSeq))
+        .update(u64(slot))
+        .digest();
+}
+
+const MESSAGE_LEAF_DOMAIN = Buffer.from('mailer-log');
+
+/**
+ * @description Compute the content hash of an inline message, as committed to by its message log leaf
+ * @notice Prepared mail commits to the SHA-256 of its mail ID and sealed mail to that of its envelope
+ * @param subject Message subject
+ * @param body Full message body
+ * @returns SHA-256 over the length-prefixed subject and the body
+ */
+export function computeMessageContentHash(subject: string, body: string): Buffer {
+    const subjectBytes = Buffer.from(subject, 'utf8');
+    const len = Buffer.alloc(4);
+    len.writeUInt32LE(subjectBytes.length);
+    return createHash('sha256').update(len).update(subjectBytes).update(Buffer.from(body, 'utf8')).digest();
+}
+
+/**
+ * @description Compute the leaf a logged message was appended to the message log as
+ * @param messageId The message's ID
+ * @param from The message's `from` event field
+ * @param to The message's `to` event field
+ * @param contentHash The message's content hash
+ * @returns SHA-256 leaf
+ */
+export function computeMessageLeaf(messageId: Uint8Array, from: PublicKey, to: PublicKey, contentHash: Uint8Array): Buffer {
+    return createHash('sha256')
+        .update(MESSAGE_LEAF_DOMAIN)
+        .update(messageId)
+        .update(from.toBuffer())
+        .update(to.toBuffer())
+        .update(contentHash)
+        .digest();
+}
@@ -0,0 +1,74 @@
+import { createHash } from 'crypto';
+
+/** Seconds in a root's day; days start at UTC midnight */
+export const ROOT_DAY_SECONDS = 24 * 60 * 60;
+
+const ROOT_LEAF_PREFIX = Buffer.from([0]);
+const ROOT_NODE_PREFIX = Buffer.from([1]);
+
+function rootLeaf(messageHash: Uint8Array): Buffer {
+    return createHash('sha256').update(ROOT_LEAF_PREFIX).update(messageHash).digest();
+}
+
+function rootNode(a: Buffer, b: Buffer): Buffer {
+    const [left, right] = Buffer.compare(a, b) <= 0 ? [a, b] : [b, a];
+    return createHash('sha256').update(ROOT_NODE_PREFIX).update(left).update(right).digest();
+}
+
+function levels(messageHashes: Uint8Array[]): Buffer[][] {
+    if (messageHashes.length === 0) {
+        throw new Error('A root needs at least one message');
+    }
+    const tree = [messageHashes.map(rootLeaf)];
+    while (tree[tree.length - 1].length > 1) {
+        const level = tree[tree.length - 1];
+        const next: Buffer[] = [];
+        for (let i = 0; i < level.length; i += 2) {
+            // An unpaired node is carried up as is
+            next.push(i + 1 < level.length ? rootNode(level[i], level[i + 1]) : level[i]);
+        }
+        tree.push(next);
+    }
+    return tree;
+}
+
+/**
+ * @description Day number a timestamp falls in, as used by `publishMessageRoot`
+ * @param unixTimestamp Seconds since the Unix epoch
+ */
+export function messageRootDay(unixTimestamp: number): number {
+    return Math.floor(unixTimestamp / ROOT_DAY_SECONDS);
+}
+
+/**
+ * @description Compute the daily root over a day's messages, as checked by `verify_inclusion`
+ * @param messageHashes Each message's leaf (see `computeMessageLeaf`), in send order
+ * @returns The Merkle root
+ * @throws {Error} If there are no messages
+ */
+export function computeMessageRoot(messageHashes: Uint8Array[]): Buffer {
+    const tree = levels(messageHashes);
+    return tree[tree.length - 1][0];
+}
+
+/**
+ * @description Build the inclusion proof of one of a day's messages
+ * @param messageHashes Each message's leaf, in send order
+ * @param index Position of the message to prove
+ * @returns Sibling hashes from the leaf up to the root
+ * @throws {Error} If there are no messages or the index is out of range
+ */
+export function messageRootProof(messageHashes: Uint8Array[], index: number): Buffer[] {
+    if (index < 0 || index >= messageHashes.length) {
+        throw new Error('Message index out of range');
+    }
+    const proof: Buffer[] = [];
+    for (const level of levels(messageHashes).slice(0, -1)) {
+        const sibling = index ^ 1;
+        if (sibling < level.length) {
+            proof.push(level[sibling]);
+        }
+        index = Math.floor(index / 2);
+    }
+    return proof;
+}
@@ -0,0 +1,94 @@
+import { createHash } from 'crypto';
+import { PublicKey } from '@solana/web3.js';
+
+/** Upper bound on the proof-of-work difficulty (mirrors the program) */
+export const MAX_POW_DIFFICULTY = 32;
+
+const POW_DOMAIN = Buffer.from('mailer-pow');
+
+function u64(value: bigint): Buffer {
+    const buf = Buffer.alloc(8);
+    buf.writeBigUInt64LE(value);
+    return buf;
+}
+
+/**
+ * @description Compute the challenge hash checked by `send_with_pow`
+ * @param sender Sending wallet
+ * @param counter Sender's proof-of-work counter (`PowState.counter`, 0 before the first send)
+ * @param subject Message subject line
+ * @param body Message content
+ * @param nonce Candidate solution
+ * @returns SHA-256 challenge hash
+ */
+export function powChallengeHash(
+    sender: PublicKey,
+    counter: bigint,
+    subject: string,
+    body: string,
+    nonce: bigint
+): Buffer {
+    const subjectBytes = Buffer.from(subject, 'utf8');
+    const subjectLen = Buffer.alloc(4);
+    subjectLen.writeUInt32LE(subjectBytes.length);
+
+    return createHash('sha256')
+        .update(POW_DOMAIN)
+        .update(sender.toBuffer())
+        .update(u64(counter))
+        .update(subjectLen)
+        .update(subjectBytes)
+        .update(Buffer.from(body, 'utf8'))
+        .update(u64(nonce))
+        .digest();
+}
+
+/**
+ * @description Count the leading zero bits of a hash
+ * @param hash Hash bytes
+ * @returns Number of leading zero bits
+ */
+export function leadingZeroBits(hash: Buffer): number {
+    let bits = 0;
+    for (const byte of hash) {
+        if (byte === 0) {
+            bits += 8;
+            continue;
+        }
+        return bits + Math.clz32(byte) - 24;
+    }
+    return bits;
+}
+
+/**
+ * @description Find a nonce that pays for one proof-of-work message
+ * @notice Expected work doubles with each difficulty bit; at 20 bits expect about a million hashes
+ * @param sender Sending wallet
+ * @param counter Sender's proof-of-work counter
+ * @param subject Message subject line
+ * @param body Message content
+ * @param difficulty Required leading zero bits
+ * @returns The first nonce meeting the difficulty
+ * @throws {Error} If the difficulty is outside 1-32
+ * @example
+ * ```typescript
+ * const nonce = solvePow(wallet.publicKey, 0n, 'Hello', 'No USDC needed', 16);
+ * ```
+ */
+export function solvePow(
+    sender: PublicKey,
+    counter: bigint,
+    subject: string,
+    body: string,
+    difficulty: number
+): bigint {
+    if (difficulty < 1 || difficulty > MAX_POW_DIFFICULTY) {
+        throw new Error(`Proof-of-work difficulty must be 1-${MAX_POW_DIFFICULTY}`);
+    }
+
+    for (let nonce = 0n; ; nonce++) {
+        if (leadingZeroBits(powChallengeHash(sender, counter, subject, body, nonce)) >= difficulty) {
+            return nonce;
+        }
+    }
+}
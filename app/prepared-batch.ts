@@ -0,0 +1,90 @@
+import { createHash } from 'crypto';
+import { compress, decompress } from '@mongodb-js/zstd';
+
+/** Maximum mail IDs a single prepared batch may pay for (mirrors the program) */
+export const MAX_PREPARED_BATCH = 512;
+
+/** Maximum compressed payload size accepted by the program */
+export const MAX_BATCH_PAYLOAD = 900;
+
+/**
+ * @interface PreparedBatch
+ * @description Compressed mail IDs ready for `send_prepared_batch`
+ */
+export interface PreparedBatch {
+    /** Number of mail IDs in the payload */
+    count: number;
+    /** SHA-256 of the uncompressed, newline-separated mail IDs */
+    idsHash: Buffer;
+    /** zstd frame of the newline-separated mail IDs */
+    payload: Buffer;
+}
+
+function serializeMailIds(mailIds: string[]): Buffer {
+    return Buffer.from(mailIds.join('\n'), 'utf8');
+}
+
+/**
+ * @description Compress mail IDs into a prepared batch payload
+ * @param mailIds Pre-prepared message identifiers; must not contain newlines
+ * @param level zstd compression level (default 19; batches are small and sent once)
+ * @returns Promise resolving to the batch's count, hash and compressed payload
+ * @throws {Error} If the batch is empty, too large, or an ID contains a newline
+ * @example
+ * ```typescript
+ * const batch = await encodePreparedBatch(mailIds);
+ * console.log(`${batch.count} IDs in ${batch.payload.length} bytes`);
+ * ```
+ */
+export async function encodePreparedBatch(mailIds: string[], level = 19): Promise<PreparedBatch> {
+    if (mailIds.length === 0 || mailIds.length > MAX_PREPARED_BATCH) {
+        throw new Error(`Prepared batch must hold 1-${MAX_PREPARED_BATCH} mail IDs`);
+    }
+    if (mailIds.some((id) => id.length === 0 || id.includes('\n'))) {
+        throw new Error('Mail IDs must be non-empty and must not contain newlines');
+    }
+
+    const raw = serializeMailIds(mailIds);
+    const payload = await compress(raw, level);
+    if (payload.length > MAX_BATCH_PAYLOAD) {
+        throw new Error(
+            `Compressed batch is ${payload.length} bytes; split it to stay under ${MAX_BATCH_PAYLOAD}`
+        );
+    }
+
+    return {
+        count: mailIds.length,
+        idsHash: createHash('sha256').update(raw).digest(),
+        payload,
+    };
+}
+
+/**
+ * @description Decompress and verify a prepared batch emitted in `PreparedBatchSent`
+ * @notice The program does not decompress payloads; indexers must call this and
+ *         drop batches that fail verification
+ * @param batch Count, hash and payload from the event
+ * @returns Promise resolving to the mail IDs in send order
+ * @throws {Error} If the payload does not decompress to `count` IDs matching `idsHash`
+ * @example
+ * ```typescript
+ * const mailIds = await decodePreparedBatch({
+ *     count: event.count,
+ *     idsHash: Buffer.from(event.idsHash),
+ *     payload: Buffer.from(event.payload),
+ * });
+ * ```
+ */
+export async function decodePreparedBatch(batch: PreparedBatch): Promise<string[]> {
+    const raw = await decompress(batch.payload);
+    const hash = createHash('sha256').update(raw).digest();
+    if (!hash.equals(Buffer.from(batch.idsHash))) {
+        throw new Error('Prepared batch payload does not match its hash');
+    }
+
+    const mailIds = raw.toString('utf8').split('\n');
+    if (mailIds.length !== batch.count) {
+        throw new Error(`Prepared batch declares ${batch.count} mail IDs but holds ${mailIds.length}`);
+    }
+    return mailIds;
+}
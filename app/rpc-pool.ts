@@ -0,0 +1,218 @@
+import { Commitment, Connection, ConnectionConfig } from '@solana/web3.js';
+
+/**
+ * @interface RpcPoolOptions
+ * @description Tuning knobs for endpoint health scoring and failover
+ */
+export interface RpcPoolOptions {
+    /** Commitment or config used when constructing connections from URLs */
+    commitment?: Commitment | ConnectionConfig;
+    /** Consecutive failures after which an endpoint is benched (default 3) */
+    maxConsecutiveFailures?: number;
+    /** How long a benched endpoint is skipped, in milliseconds (default 30s) */
+    cooldownMs?: number;
+    /** Decides whether an error should fail over to the next endpoint */
+    isRetryable?: (error: unknown) => boolean;
+    /** Clock override, mainly for tests */
+    now?: () => number;
+}
+
+/**
+ * @interface RpcEndpointHealth
+ * @description Health snapshot of a single endpoint in the pool
+ */
+export interface RpcEndpointHealth {
+    endpoint: string;
+    /** Health score in [0, 1]; 1 is perfectly healthy */
+    score: number;
+    consecutiveFailures: number;
+    /** Exponentially weighted average latency in milliseconds */
+    latencyMs: number;
+    /** Timestamp (ms) until which the endpoint is benched, or 0 */
+    benchedUntil: number;
+}
+
+interface PoolEntry extends RpcEndpointHealth {
+    connection: Connection;
+    priority: number;
+}
+
+const TRANSIENT_ERROR_PATTERN =
+    /fetch failed|failed to fetch|network|timeout|timed out|ECONNRESET|ECONNREFUSED|ETIMEDOUT|ENOTFOUND|EAI_AGAIN|socket hang up|429|502|503|504|too many requests|service unavailable|bad gateway|node is behind|blockhash not found/i;
+
+/**
+ * @description Default failover policy: only transport and provider errors fail over
+ * @notice Program errors and failed simulations are deterministic and would fail on
+ * every endpoint, so they are surfaced immediately instead of being retried.
+ * @param error Error thrown by a Connection call
+ * @returns True if the call should be retried on another endpoint
+ */
+export function isTransientRpcError(error: unknown): boolean {
+    if (!error) return false;
+    if ((error as any).logs) return false;
+    const message = (error as any).message ?? String(error);
+    return TRANSIENT_ERROR_PATTERN.test(message);
+}
+
+/**
+ * @class RpcPool
+ * @description Ordered set of RPC endpoints with health scoring and automatic failover
+ * @notice Endpoints are tried in configured order, demoted as they fail and benched
+ * for a cooldown after repeated failures. Sends are safe to fail over because a
+ * signed transaction has the same signature on every endpoint.
+ *
+ * ## Usage Examples:
+ * ```typescript
+ * const pool = new RpcPool([
+ *     'https://primary.example.com',
+ *     'https://backup.example.com',
+ *     'https://api.mainnet-beta.solana.com'
+ * ]);
+ * // Drop-in Connection for MailerClient / MailServiceClient
+ * const client = new MailerClient(pool.connection(), wallet, programId, usdcMint);
+ * ```
+ */
+export class RpcPool {
+    private entries: PoolEntry[];
+    private maxConsecutiveFailures: number;
+    private cooldownMs: number;
+    private isRetryable: (error: unknown) => boolean;
+    private now: () => number;
+    private proxy?: Connection;
+
+    /**
+     * @description Creates a pool from endpoint URLs or existing connections, in priority order
+     * @param endpoints RPC URLs or Connection instances, most preferred first
+     * @param options Optional health scoring and failover settings
+     * @throws {Error} If no endpoints are provided
+     */
+    constructor(endpoints: Array<string | Connection>, options: RpcPoolOptions = {}) {
+        if (endpoints.length === 0) {
+            throw new Error('RpcPool requires at least one endpoint');
+        }
+        this.maxConsecutiveFailures = options.maxConsecutiveFailures ?? 3;
+        this.cooldownMs = options.cooldownMs ?? 30_000;
+        this.isRetryable = options.isRetryable ?? isTransientRpcError;
+        this.now = options.now ?? Date.now;
+        this.entries = endpoints.map((endpoint, priority) => {
+            const connection = typeof endpoint === 'string'
+                ? new Connection(endpoint, options.commitment)
+                : endpoint;
+            return {
+                endpoint: typeof endpoint === 'string' ? endpoint : connection.rpcEndpoint,
+                connection,
+                priority,
+                score: 1,
+                consecutiveFailures: 0,
+                latencyMs: 0,
+                benchedUntil: 0,
+            };
+        });
+    }
+
+    /**
+     * @description Endpoints in the order they will be tried for the next call
+     * @notice Available endpoints come first by score, then configured priority;
+     * benched endpoints are kept last so a fully degraded pool still makes attempts.
+     */
+    private ranked(): PoolEntry[] {
+        const now = this.now();
+        return [...this.entries].sort((a, b) => {
+            const aBenched = a.benchedUntil > now ? 1 : 0;
+            const bBenched = b.benchedUntil > now ? 1 : 0;
+            if (aBenched !== bBenched) return aBenched - bBenched;
+            if (a.score !== b.score) return b.score - a.score;
+            return a.priority - b.priority;
+        });
+    }
+
+    private recordSuccess(entry: PoolEntry, latencyMs: number) {
+        entry.consecutiveFailures = 0;
+        entry.benchedUntil = 0;
+        entry.score = Math.min(1, entry.score * 0.8 + 0.2);
+        entry.latencyMs = entry.latencyMs === 0 ? latencyMs : entry.latencyMs * 0.8 + latencyMs * 0.2;
+    }
+
+    private recordFailure(entry: PoolEntry) {
+        entry.consecutiveFailures += 1;
+        entry.score = entry.score * 0.5;
+        if (entry.consecutiveFailures >= this.maxConsecutiveFailures) {
+            entry.benchedUntil = this.now() + this.cooldownMs;
+        }
+    }
+
+    /**
+     * @description Run an RPC operation, failing over across endpoints on transient errors
+     * @param operation Callback receiving a Connection for the endpoint being tried
+     * @returns Promise resolving to the first successful result
+     * @throws {Error} The first non-retryable error, or the last error once every endpoint failed
+     * @example
+     * ```typescript
+     * const balance = await pool.execute((c) => c.getBalance(address));
+     * ```
+     */
+    async execute<T>(operation: (connection: Connection) => Promise<T>): Promise<T> {
+        let lastError: unknown;
+        for (const entry of this.ranked()) {
+            const started = this.now();
+            try {
+                const result = await operation(entry.connection);
+                this.recordSuccess(entry, this.now() - started);
+                return result;
+            } catch (error) {
+                if (!this.isRetryable(error)) {
+                    throw error;
+                }
+                this.recordFailure(entry);
+                lastError = error;
+            }
+        }
+        throw lastError;
+    }
+
+    /**
+     * @description Connection that transparently routes every async call through the pool
+     * @notice Synchronous members (subscriptions, `rpcEndpoint`, ...) are served by the
+     * currently healthiest endpoint without failover.
+     * @returns A Connection usable anywhere the SDK expects one
+     */
+    connection(): Connection {
+        if (this.proxy) return this.proxy;
+        const pool = this;
+        this.proxy = new Proxy(this.entries[0].connection, {
+            get(_target, prop) {
+                const primary = pool.ranked()[0].connection as any;
+                const value = primary[prop];
+                if (typeof value !== 'function') return value;
+                return (...args: any[]) => {
+                    const result = value.apply(primary, args);
+                    if (!result || typeof result.then !== 'function') return result;
+                    // The first attempt already ran on the healthiest endpoint; reuse it
+                    let first = true;
+                    return pool.execute((connection: any) => {
+                        if (first) {
+                            first = false;
+                            return result;
+                        }
+                        return connection[prop].apply(connection, args);
+                    });
+                };
+            },
+        });
+        return this.proxy;
+    }
+
+    /**
+     * @description Current health of every endpoint, in configured order
+     * @returns Array of health snapshots
+     */
+    health(): RpcEndpointHealth[] {
+        return this.entries.map(({ endpoint, score, consecutiveFailures, latencyMs, benchedUntil }) => ({
+            endpoint,
+            score,
+            consecutiveFailures,
+            latencyMs,
+            benchedUntil,
+        }));
+    }
+}
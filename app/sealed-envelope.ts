@@ -0,0 +1,137 @@
+import {
+    KeyObject,
+    createCipheriv,
+    createDecipheriv,
+    createHash,
+    createPrivateKey,
+    createPublicKey,
+    diffieHellman,
+    generateKeyPairSync,
+    randomBytes,
+    sign,
+    verify,
+} from 'crypto';
+import { Keypair, PublicKey } from '@solana/web3.js';
+
+/** Upper bound on an envelope accepted by `send_sealed` (mirrors the program) */
+export const MAX_SEALED_ENVELOPE_LEN = 256;
+
+const ENVELOPE_DOMAIN = Buffer.from('mailer-sealed');
+const KEY_LEN = 32;
+const IV_LEN = 12;
+const TAG_LEN = 16;
+const SIGNATURE_LEN = 64;
+
+/** X25519 key pair a recipient publishes so senders can seal envelopes to it */
+export interface EnvelopeKeyPair {
+    /** Raw 32-byte X25519 public key */
+    publicKey: Buffer;
+    privateKey: KeyObject;
+}
+
+/**
+ * @description Generate an X25519 key pair for receiving sealed-sender envelopes
+ * @returns Raw public key to publish and the private key to open envelopes with
+ */
+export function generateEnvelopeKeyPair(): EnvelopeKeyPair {
+    const { publicKey, privateKey } = generateKeyPairSync('x25519');
+    return { publicKey: rawX25519(publicKey), privateKey };
+}
+
+/**
+ * @description Seal the true sender of a message for its recipient
+ * @notice The envelope holds the sender and their signature over the recipient and
+ *         mail ID, encrypted to the recipient's X25519 key under a fresh ephemeral key,
+ *         so only the recipient learns who sent it and the envelope cannot be replayed
+ *         onto another message
+ * @param sender True sender; signs the envelope
+ * @param to Recipient of the message
+ * @param mailId Pre-prepared message identifier passed to `send_sealed`
+ * @param recipientKey Recipient's raw X25519 public key
+ * @returns Envelope bytes for `send_sealed`
+ * @example
+ * ```typescript
+ * const envelope = sealEnvelope(wallet, recipient, mailId, recipientEnvelopeKey);
+ * await ephemeralClient.sendSealed(recipient, mailId, envelope);
+ * ```
+ */
+export function sealEnvelope(sender: Keypair, to: PublicKey, mailId: string, recipientKey: Buffer): Buffer {
+    const signingKey = createPrivateKey({
+        key: {
+            kty: 'OKP',
+            crv: 'Ed25519',
+            d: Buffer.from(sender.secretKey.subarray(0, 32)).toString('base64url'),
+            x: sender.publicKey.toBuffer().toString('base64url'),
+        },
+        format: 'jwk',
+    });
+    const signature = sign(null, envelopeMessage(to, mailId), signingKey);
+    const plaintext = Buffer.concat([sender.publicKey.toBuffer(), signature]);
+
+    const ephemeral = generateKeyPairSync('x25519');
+    const ephemeralKey = rawX25519(ephemeral.publicKey);
+    const key = envelopeKey(
+        diffieHellman({ privateKey: ephemeral.privateKey, publicKey: x25519PublicKey(recipientKey) }),
+        ephemeralKey,
+        recipientKey
+    );
+
+    const iv = randomBytes(IV_LEN);
+    const cipher = createCipheriv('aes-256-gcm', key, iv);
+    const ciphertext = Buffer.concat([cipher.update(plaintext), cipher.final()]);
+    return Buffer.concat([ephemeralKey, iv, cipher.getAuthTag(), ciphertext]);
+}
+
+/**
+ * @description Recover the true sender of a sealed message
+ * @param envelope `envelope` field of the `SealedMailSent` event
+ * @param to Recipient of the message (the `to` field of the event)
+ * @param mailId `mailId` field of the event
+ * @param recipientKeys Recipient's envelope key pair
+ * @returns The true sender
+ * @throws {Error} If the envelope was not sealed to this key or its signature does not match the message
+ */
+export function openEnvelope(envelope: Buffer, to: PublicKey, mailId: string, recipientKeys: EnvelopeKeyPair): PublicKey {
+    if (envelope.length !== KEY_LEN + IV_LEN + TAG_LEN + KEY_LEN + SIGNATURE_LEN) {
+        throw new Error('Malformed sealed envelope');
+    }
+    const ephemeralKey = envelope.subarray(0, KEY_LEN);
+    const iv = envelope.subarray(KEY_LEN, KEY_LEN + IV_LEN);
+    const tag = envelope.subarray(KEY_LEN + IV_LEN, KEY_LEN + IV_LEN + TAG_LEN);
+    const ciphertext = envelope.subarray(KEY_LEN + IV_LEN + TAG_LEN);
+
+    const key = envelopeKey(
+        diffieHellman({ privateKey: recipientKeys.privateKey, publicKey: x25519PublicKey(ephemeralKey) }),
+        ephemeralKey,
+        recipientKeys.publicKey
+    );
+    const decipher = createDecipheriv('aes-256-gcm', key, iv);
+    decipher.setAuthTag(tag);
+    const plaintext = Buffer.concat([decipher.update(ciphertext), decipher.final()]);
+
+    const sender = new PublicKey(plaintext.subarray(0, KEY_LEN));
+    const verifyingKey = createPublicKey({
+        key: { kty: 'OKP', crv: 'Ed25519', x: sender.toBuffer().toString('base64url') },
+        format: 'jwk',
+    });
+    if (!verify(null, envelopeMessage(to, mailId), verifyingKey, plaintext.subarray(KEY_LEN))) {
+        throw new Error('Sealed envelope signature does not match the message');
+    }
+    return sender;
+}
+
+function envelopeMessage(to: PublicKey, mailId: string): Buffer {
+    return createHash('sha256').update(ENVELOPE_DOMAIN).update(to.toBuffer()).update(mailId, 'utf8').digest();
+}
+
+function envelopeKey(sharedSecret: Buffer, ephemeralKey: Buffer, recipientKey: Buffer): Buffer {
+    return createHash('sha256').update(ENVELOPE_DOMAIN).update(sharedSecret).update(ephemeralKey).update(recipientKey).digest();
+}
+
+function rawX25519(key: KeyObject): Buffer {
+    return Buffer.from(key.export({ format: 'jwk' }).x as string, 'base64url');
+}
+
+function x25519PublicKey(raw: Buffer): KeyObject {
+    return createPublicKey({ key: { kty: 'OKP', crv: 'X25519', x: raw.toString('base64url') }, format: 'jwk' });
+}
@@ -0,0 +1,121 @@
+import { PublicKey, Transaction } from '@solana/web3.js';
+import QRCode from 'qrcode';
+
+/** URL scheme wallets register for Solana Pay requests */
+export const SOLANA_PAY_PROTOCOL = 'solana:';
+
+/**
+ * @interface TransactionRequestLabel
+ * @description Body of a transaction request endpoint's GET response, shown by the wallet before signing
+ */
+export interface TransactionRequestLabel {
+    /** Name of the requesting app, e.g. "MailBox" */
+    label: string;
+    /** Absolute URL of an SVG, PNG or WebP icon */
+    icon: string;
+}
+
+/**
+ * @interface TransactionRequestResponse
+ * @description Body of a transaction request endpoint's POST response
+ */
+export interface TransactionRequestResponse {
+    /** Base64 wire transaction for the wallet to sign and submit */
+    transaction: string;
+    /** Optional description of the action shown by the wallet */
+    message?: string;
+}
+
+/**
+ * @description Encode a Solana Pay transaction request URL for a mailer action endpoint
+ * @notice The wallet POSTs `{ account }` to `link` and signs the returned transaction;
+ *         serve it with a MailerClient `build*Transaction` method and `createTransactionRequestResponse`
+ * @param link HTTPS endpoint that builds the transaction
+ * @returns `solana:` URL suitable for deep links and QR codes
+ * @throws {Error} If `link` is not an HTTPS URL
+ * @example
+ * ```typescript
+ * const url = encodeTransactionRequestUrl('https://mail.example.com/api/pay/claim');
+ * // solana:https://mail.example.com/api/pay/claim
+ * ```
+ */
+export function encodeTransactionRequestUrl(link: string | URL): string {
+    const url = new URL(link.toString());
+    if (url.protocol !== 'https:') {
+        throw new Error('Transaction request links must use HTTPS');
+    }
+
+    // Links with query parameters must be URL-encoded so wallets do not read them as request fields
+    const encoded = url.search ? encodeURIComponent(url.toString()) : url.toString();
+    return `${SOLANA_PAY_PROTOCOL}${encoded}`;
+}
+
+/**
+ * @description Extract the endpoint from a Solana Pay transaction request URL
+ * @param url `solana:` URL produced by `encodeTransactionRequestUrl`
+ * @returns The HTTPS endpoint the wallet should call
+ * @throws {Error} If the URL is not a transaction request
+ */
+export function parseTransactionRequestUrl(url: string): URL {
+    if (!url.startsWith(SOLANA_PAY_PROTOCOL)) {
+        throw new Error(`Not a Solana Pay URL: ${url}`);
+    }
+
+    const link = new URL(decodeURIComponent(url.slice(SOLANA_PAY_PROTOCOL.length)));
+    if (link.protocol !== 'https:') {
+        throw new Error('Not a transaction request URL');
+    }
+    return link;
+}
+
+/**
+ * @description Read the wallet account from a transaction request POST body
+ * @param body Parsed JSON body sent by the wallet
+ * @returns The account that will sign and pay for the transaction
+ * @throws {Error} If the body has no valid `account`
+ */
+export function parseTransactionRequestAccount(body: unknown): PublicKey {
+    const account = (body as { account?: unknown } | null)?.account;
+    if (typeof account !== 'string') {
+        throw new Error('Transaction request body must contain an account');
+    }
+    return new PublicKey(account);
+}
+
+/**
+ * @description Serialize an unsigned mailer transaction as a transaction request POST response
+ * @param transaction Transaction with fee payer and recent blockhash set, e.g. from `buildSendTransaction`
+ * @param message Optional description shown by the wallet
+ * @returns Response body to return as JSON
+ * @example
+ * ```typescript
+ * const account = parseTransactionRequestAccount(req.body);
+ * const tx = await client.buildClaimTransaction(account);
+ * res.json(createTransactionRequestResponse(tx, 'Claim your MailBox revenue share'));
+ * ```
+ */
+export function createTransactionRequestResponse(
+    transaction: Transaction,
+    message?: string
+): TransactionRequestResponse {
+    const serialized = transaction.serialize({ requireAllSignatures: false, verifySignatures: false });
+    return {
+        transaction: serialized.toString('base64'),
+        ...(message ? { message } : {}),
+    };
+}
+
+/**
+ * @description Render a Solana Pay URL as an SVG QR code for mobile wallets to scan
+ * @param url `solana:` URL produced by `encodeTransactionRequestUrl`
+ * @param size Width and height in pixels (default 512)
+ * @returns Promise resolving to the SVG markup
+ * @example
+ * ```typescript
+ * const svg = await createTransactionRequestQR(url);
+ * document.getElementById('qr')!.innerHTML = svg;
+ * ```
+ */
+export async function createTransactionRequestQR(url: string, size = 512): Promise<string> {
+    return await QRCode.toString(url, { type: 'svg', width: size, errorCorrectionLevel: 'H' });
+}
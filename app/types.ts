@@ -1,17 +1,390 @@
 import { PublicKey } from '@solana/web3.js';
 
+/** How a recipient's revenue shares are paid out */
+export type MailerClaimMode = 'lump' | 'streaming';
+
 export interface ClaimableInfo {
     amount: number;
+    /** For streaming claims, when the balance is fully unlocked; they never expire */
     expiresAt: number;
     isExpired: boolean;
+    mode?: MailerClaimMode;
+    /** Portion of `amount` that can be withdrawn right now */
+    available?: number;
+    /** Portion of `amount` still behind a vesting cliff */
+    vested?: number;
+    /** Unix time the vesting cliff passes; 0 when nothing is vesting */
+    vestingEndsAt?: number;
+    /** Non-withdrawable credit rolled over from expired shares or redeemed from loyalty points, spent first by future sends */
+    sendCredit: number;
+    /** Whether the registered auto-claimer may pay the claim out shortly before it expires */
+    autoClaim?: boolean;
+    /** Fee terms in effect for the most recent deposit */
+    feeSnapshot?: FeeSnapshot;
+    /** Mint the claim is denominated in */
+    mint?: PublicKey;
+}
+
+export interface FeeSnapshot {
+    sendFee: number;
+    ownerShare: number;
+    recipientShare: number;
+}
+
+/** Who a tip attached to a send is credited to */
+export type MailerTipTarget = 'owner' | 'recipient';
+
+/** Optional tip paid on top of a send fee */
+export interface MailerTip {
+    /** Tip in the fee mint's base units */
+    amount: number;
+    target: MailerTipTarget;
+}
+
+/** Maximum length of a recipient address on another chain (mirrors the program) */
+export const MAX_EXTERNAL_ADDRESS_LEN = 64;
+
+/** Length of the optional caller reference carried by send events */
+export const CLIENT_REF_LEN = 16;
+
+/** Message sequence slots in a read-state bitmap; sequences this far apart share a bit */
+export const READ_STATE_BITS = 1024;
+
+/** Subject and body caps a new deployment starts with, in bytes (mirrors the program) */
+export const DEFAULT_MAX_SUBJECT_LEN = 200;
+export const DEFAULT_MAX_BODY_LEN = 800;
+
+/**
+ * Recipient address on another chain, e.g. an EVM account reached through the relay.
+ * `chainId` is the chain's numeric ID (EIP-155 for EVM chains) and `address` its raw bytes.
+ */
+export interface ExternalAddress {
+    chainId: number;
+    address: Uint8Array;
+}
+
+/** Mailer revenue streams with separately withdrawable owner share buckets */
+export type MailerFeeSource = 'send' | 'expired';
+
+/** MailService revenue streams with separately withdrawable fee buckets */
+export type ServiceFeeSource = 'delegation' | 'handle';
+
+export interface FeeBucketInfo {
+    destination: PublicKey;
+    claimable: number;
+}
+
+export interface TokenConfigInfo {
+    mint: PublicKey;
+    enabled: boolean;
+    /** Priority send fee in the mint's base units */
+    sendFee: number;
+    decimals: number;
+    /** Pyth feed ID (hex) pricing the mint in USD, or null when `sendFee` applies */
+    priceFeedId?: string | null;
+}
+
+export interface TokenDisplayInfo {
+    mint: PublicKey;
+    /** Ticker shown to users, e.g. "USDC" */
+    symbol: string;
+    decimals: number;
+    /** Formatting pattern, e.g. "$0.00" or "0.00 PYUSD"; empty for the default format */
+    displayHint: string;
+}
+
+/** A proposed change to one mailer parameter, for `simulateConfigChange` */
+export type MailerConfigChange =
+    | { kind: 'sendFee'; value: number }
+    | { kind: 'usdFee'; value: number }
+    | { kind: 'crankBounty'; value: number }
+    | { kind: 'rollover'; value: number }
+    | { kind: 'powDifficulty'; value: number }
+    | { kind: 'referralShare'; value: number }
+    | { kind: 'nftDiscount'; value: number };
+
+/** Outcome of a config change dry run, as returned by `simulate_config_change` */
+export interface ConfigChangeReport {
+    change: MailerConfigChange;
+    /** Current value of the parameter */
+    oldValue: number;
+    /** Proposed value of the parameter */
+    newValue: number;
+    /** Whether the matching setter would accept the change */
+    valid: boolean;
+    /** Program error code the setter would fail with; 0 when valid */
+    errorCode: number;
+}
+
+/** Fee for one send, as returned by the `quote_fee` instruction */
+/** Split of the send fee in one mint, returned by `get_fee_breakdown` */
+export interface FeeBreakdown {
+    mint: PublicKey;
+    /** Fee of a priority send, in the mint's base units */
+    priorityFee: number;
+    /** Fee of a standard send: the owner share only */
+    standardFee: number;
+    /** Part of a priority fee credited back to the sender's claim */
+    recipientShare: number;
+    /** Part of a priority fee credited to the owner */
+    ownerShare: number;
+    /** Seconds a lump-sum share stays claimable */
+    claimPeriod: number;
+    crankBountyBps: number;
+    rolloverBps: number;
+}
+
+export interface FeeQuote {
+    mint: PublicKey;
+    /** Fee in the mint's base units */
+    amount: number;
+    decimals: number;
+    /** Display symbol; empty when the mint has no display metadata */
+    symbol: string;
+    displayHint: string;
+}
+
+export interface ExpiryIndexInfo {
+    /** Week index: expiry time (seconds) divided by 7 days */
+    week: number;
+    /** Unix timestamp at which the week starts */
+    startsAt: number;
+    /** Unix timestamp at which the week ends */
+    endsAt: number;
+    /** Recipients whose claims were listed as expiring this week; confirm against each claim */
+    recipients: PublicKey[];
+    /** Claims expiring this week that did not fit in the index */
+    overflow: number;
+}
+
+export interface PaymentEscrowInfo {
+    /** 32-byte payment ID (hex) seeding the escrow PDA */
+    paymentId: string;
+    /** 32-byte ID (hex) of the message carrying the payment, as emitted in PaymentMailSent */
+    messageId: string;
+    sender: PublicKey;
+    recipient: PublicKey;
+    mint: PublicKey;
+    /** Escrowed amount in the mint's base units */
+    amount: number;
+    /** Unix time after which the payment is refundable, or null for no deadline */
+    ackDeadline: number | null;
+    createdAt: number;
+    /** Unix time after which the sender may reclaim the payment, or null when it has a deadline */
+    reclaimableAt: number | null;
+    /** Recipient on another chain the payment is relayed to, if any */
+    recipientExternal: ExternalAddress | null;
+}
+
+export interface ScheduledMessageInfo {
+    sender: PublicKey;
+    /** Mint the fee is escrowed in */
+    mint: PublicKey;
+    /** Escrowed standard fee in the mint's base units */
+    amount: number;
+    /** Unix time from which the send may be executed */
+    executeAfter: number;
+    mailId: string;
+    /** Funded the scheduled message's rent; refunded on execution or cancellation */
+    rentPayer: PublicKey;
+}
+
+export interface SessionInfo {
+    owner: PublicKey;
+    /** Key allowed to send on the owner's behalf */
+    sessionKey: PublicKey;
+    /** Mint the session's fees are paid in */
+    mint: PublicKey;
+    /** Unix time from which the session can no longer send */
+    expiresAt: number;
+    /** Most the session may spend on fees, in the mint's base units */
+    maxSpend: number;
+    /** Fees spent so far */
+    spent: number;
+}
+
+export interface DelegatorBillingInfo {
+    delegator: PublicKey;
+    /** Mail service delegation whose delegate may bill the delegator */
+    delegation: PublicKey;
+    /** Mint the fees are pulled in */
+    mint: PublicKey;
+    /** Most the delegate may bill in total, in the mint's base units */
+    limit: number;
+    /** Fees billed so far */
+    spent: number;
+}
+
+export interface SubscriptionPlanInfo {
+    id: number;
+    /** Mint the price is paid in */
+    mint: PublicKey;
+    /** Price of one period, in the mint's base units */
+    price: number;
+    /** Length of one period, in seconds */
+    duration: number;
+    /** Standard messages one period covers; 0 means unlimited */
+    messageCap: number;
+}
+
+export interface PlanSubscriptionInfo {
+    subscriber: PublicKey;
+    planId: number;
+    /** Unix time from which the subscription no longer covers sends */
+    expiresAt: number;
+    /** Standard messages the current period covers; 0 means unlimited */
+    messageCap: number;
+    /** Standard messages covered so far this period */
+    messagesUsed: number;
+}
+
+export interface StakeTierInfo {
+    /** Stake required, in USDC base units */
+    minStake: number;
+    /** Fee discount in basis points; 10000 waives the fee */
+    discountBps: number;
 }
 
+export interface StakeInfo {
+    owner: PublicKey;
+    /** Mint the stake is held in */
+    mint: PublicKey;
+    /** Staked, in the mint's base units */
+    amount: number;
+    /** Unix time from which the stake can be withdrawn */
+    lockedUntil: number;
+}
+
+export interface SpamDepositInfo {
+    recipient: PublicKey;
+    sender: PublicKey;
+    mint: PublicKey;
+    /** Outstanding deposit in the mint's base units */
+    amount: number;
+}
+
+export interface ChannelInfo {
+    address: PublicKey;
+    /** Creator of the channel, credited with the recipient share of every post */
+    owner: PublicKey;
+    name: string;
+    /** Mint subscription fees are paid in; the default key until terms are set */
+    subscriptionMint: PublicKey;
+    /** Fee per 30-day epoch in the mint's base units; 0 for a free channel */
+    subscriptionFee: number;
+    /** Only the owner and active subscribers may post */
+    readersOnly: boolean;
+    /** Subscription fees collected and not yet withdrawn */
+    claimable: number;
+    /** Appointed by the owner; may remove subscribers, pin and mute */
+    moderators: PublicKey[];
+    /** Message ID (hex) of the pinned post, or null */
+    pinnedMessage: string | null;
+    subscriberCount: number;
+    postCount: number;
+    createdAt: number;
+}
+
+export interface GroupInfo {
+    address: PublicKey;
+    /** Manages the member list and may send to the group */
+    admin: PublicKey;
+    name: string;
+    members: PublicKey[];
+    /** Appointed by the admin; may remove members */
+    moderators: PublicKey[];
+}
+
+export interface CampaignInfo {
+    id: number;
+    /** Mint the budget is denominated in */
+    mint: PublicKey;
+    /** Recipient share of priority fees while the campaign runs, in percent */
+    recipientShare: number;
+    /** Unix seconds */
+    startTime: number;
+    /** Unix seconds; the boost no longer applies from this second */
+    endTime: number;
+    budget: number;
+    spent: number;
+}
+
+export interface LoyaltyPointsInfo {
+    /** Points available to redeem */
+    points: number;
+    /** Points earned since the stats account was created, including redeemed ones */
+    lifetimePoints: number;
+}
+
+export interface MessageLogInfo {
+    /** Tree single sends are appended to */
+    merkleTree: PublicKey;
+    /** Messages appended to the tree so far; the next message's leaf index */
+    leaves: number;
+}
+
+export interface MessageRootInfo {
+    /** Day number, `unixTimestamp / 86400` */
+    day: number;
+    root: Uint8Array;
+    /** Messages the root covers */
+    messageCount: number;
+    /** Unix timestamp the root was published at */
+    publishedAt: number;
+}
+
+export interface InboxInfo {
+    /** Messages delivered to the address so far */
+    receivedCount: number;
+    /** Global sequence of the latest delivered message */
+    lastMessageSeq: number;
+    lastSender: PublicKey;
+}
+
+export interface ChannelSubscriptionInfo {
+    subscribedAt: number;
+    /** End of the last paid epoch (unix seconds) */
+    paidUntil: number;
+    /** Whether the subscription currently counts; always true on free channels */
+    active: boolean;
+}
+
+/** Delegation permission flags (mirror the program's `PERMISSION_*` constants) */
+export const DelegationPermission = {
+    SEND: 1 << 0,
+    CLAIM: 1 << 1,
+    MANAGE_HANDLES: 1 << 2,
+    ALL: (1 << 0) | (1 << 1) | (1 << 2),
+} as const;
+
 export interface DelegationInfo {
     delegator: PublicKey;
+    /** Active delegate; set only once the requested delegate accepts */
     delegate: PublicKey | null;
+    /** Delegate requested by `delegateTo` and awaiting `acceptDelegation` */
+    pendingDelegate?: PublicKey | null;
+    /** Unix timestamp after which the delegation counts as cleared, or null if it never expires */
+    expiresAt?: number | null;
+    /** `DelegationPermission` flags granted to the delegate */
+    permissions?: number;
     bump?: number;
 }
 
+export interface HandleInfo {
+    name: string;
+    owner: PublicKey;
+    /** Unix timestamp after which the handle stops resolving and can be registered by anyone */
+    expiresAt: number;
+    isExpired: boolean;
+}
+
+/** A delegation that names a given address as its requested or active delegate */
+export interface DelegatorInfo {
+    delegator: PublicKey;
+    /** The delegator's primary delegation or its per-delegate one */
+    delegation: PublicKey;
+}
+
 export interface DeploymentConfig {
     network: string;
     cluster: string;
@@ -23,14 +396,60 @@ export interface DeploymentConfig {
 export interface MailServiceFees {
     registrationFee: number;
     delegationFee: number;
+    /** Reduced fee for replacing a delegate within `updateWindow` seconds of the last paid change */
+    updateFee: number;
+    updateWindow: number;
+    /** Share of each delegation fee, in basis points, that accrues to the delegate */
+    delegateShareBps: number;
+}
+
+/** Running totals kept by the service; collected fees are net of delegate shares */
+export interface FeeLedger {
+    feesCollected: number;
+    feesWithdrawn: number;
 }
 
 export interface MailerFees {
     sendFee: number;
 }
 
+/** Aggregate usage counters kept on `MailerStats`, with fees from one mint's send bucket */
+export interface MailerStats {
+    /** Messages sent through every send path, counting each message of a batch */
+    totalMessagesSent: number;
+    /** Of `totalMessagesSent`, those that paid the full fee with revenue sharing */
+    totalPriorityMessages: number;
+    /** Send fees received in the requested mint, in its base units */
+    totalFeesCollected: number;
+    /** Sequence number the next message will carry in its `messageSeq` event field */
+    nextMessageSeq: number;
+}
+
+/** Owner-set caps on message size, in UTF-8 bytes, and on sends per sender */
+export interface MessageLimits {
+    maxSubjectLen: number;
+    maxBodyLen: number;
+    /** Messages one sender may send per day; 0 means unlimited */
+    maxMessagesPerDay: number;
+}
+
 export const USDC_DECIMALS = 6;
 export const CLAIM_PERIOD_DAYS = 60;
+export const VESTING_CLIFF_DAYS = 30;
+export const EXPIRY_BUCKET_DAYS = 7;
+export const ESCROW_RECLAIM_DAYS = 30;
+
+/** Metaplex Token Metadata program, which owns the NFT metadata checked by holder perks */
+export const TOKEN_METADATA_PROGRAM_ID = new PublicKey('metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s');
+
+/** Metaplex Bubblegum program, which mints cNFT receipts */
+export const BUBBLEGUM_PROGRAM_ID = new PublicKey('BGUMAp9Gq7iTEuizy4pqaxsTyUCBK68MDfK752saRPUY');
+
+/** SPL Noop program Bubblegum logs minted leaves through */
+export const SPL_NOOP_PROGRAM_ID = new PublicKey('noopb9bkMVfRPU8AsbpTUg8AQkHtKwMYZiFUjNRtMmV');
+
+/** SPL Account Compression program, which owns Bubblegum trees */
+export const SPL_ACCOUNT_COMPRESSION_PROGRAM_ID = new PublicKey('cmtDvXumGCrqC1Age74AVPhSRVXJMd8PJS91L8KbNCK');
 
 // Network configurations
 export const NETWORK_CONFIGS: Record<string, { usdcMint: PublicKey }> = {
@@ -50,6 +469,66 @@ export function formatUSDC(amount: number): string {
     return (amount / Math.pow(10, USDC_DECIMALS)).toFixed(2);
 }
 
+/**
+ * @description Format a token amount using a fee token's display metadata
+ * @notice The hint's run of zeros is replaced by the amount, with as many fraction digits as
+ *         zeros after its decimal point; text around it is kept. Without a hint the amount is
+ *         shown with all of the mint's decimals, followed by the symbol
+ * @param amount Amount in the mint's base units
+ * @param display Decimals, symbol and hint, e.g. from `quoteFee`
+ * @returns Display string, e.g. "$0.10" or "0.10 PYUSD"
+ */
+export function formatTokenAmount(
+    amount: number,
+    display: { decimals: number; symbol: string; displayHint: string }
+): string {
+    const value = amount / Math.pow(10, display.decimals);
+    const pattern = /0+(?:\.(0+))?/.exec(display.displayHint);
+    if (!pattern) {
+        const formatted = value.toFixed(display.decimals);
+        return display.symbol ? `${formatted} ${display.symbol}` : formatted;
+    }
+
+    const fractionDigits = pattern[1]?.length ?? 0;
+    return display.displayHint.slice(0, pattern.index)
+        + value.toFixed(fractionDigits)
+        + display.displayHint.slice(pattern.index + pattern[0].length);
+}
+
+/**
+ * @description Build an external address from a hex string such as an EVM address
+ * @param chainId Numeric chain ID (e.g. 1 for Ethereum mainnet)
+ * @param hex Address bytes as hex, with or without a 0x prefix
+ * @returns External address for the given chain
+ * @throws {Error} If the hex is malformed or longer than 64 bytes
+ * @example
+ * ```typescript
+ * const to = externalAddressFromHex(1, '0x742d35Cc6634C0532925a3b844Bc454e4438f44e');
+ * ```
+ */
+export function externalAddressFromHex(chainId: number, hex: string): ExternalAddress {
+    const digits = hex.startsWith('0x') ? hex.slice(2) : hex;
+    if (digits.length === 0 || digits.length % 2 !== 0 || !/^[0-9a-fA-F]+$/.test(digits)) {
+        throw new Error('Invalid address hex');
+    }
+    if (digits.length / 2 > MAX_EXTERNAL_ADDRESS_LEN) {
+        throw new Error(`Address exceeds ${MAX_EXTERNAL_ADDRESS_LEN} bytes`);
+    }
+    return { chainId, address: Uint8Array.from(Buffer.from(digits, 'hex')) };
+}
+
 export function parseUSDC(amount: string): number {
     return Math.floor(parseFloat(amount) * Math.pow(10, USDC_DECIMALS));
-}
\ No newline at end of file
+}
+
+/**
+ * @description Size of an SPL Account Compression tree account without a canopy
+ * @param maxDepth Tree depth
+ * @param maxBufferSize Changelog buffer size
+ * @returns Account size in bytes: header, tree counters, changelog buffer and rightmost path
+ */
+export function messageLogTreeSize(maxDepth: number, maxBufferSize: number): number {
+    const header = 2 + 54;
+    const path = 32 * maxDepth + 32 + 4 + 4;
+    return header + 3 * 8 + maxBufferSize * path + path;
+}
@@ -0,0 +1,50 @@
+//! Constants and helpers shared by the Mailer, MailService and MailBoxFactory programs.
+//!
+//! Anything a second program or an off-chain client has to agree on lives here,
+//! so a seed or fee rule only has one definition.
+
+/// PDA seeds of each program's singleton state account
+pub mod seeds {
+    /// Mailer state: `[MAILER]` under the mailer program
+    pub const MAILER: &[u8] = b"mailer";
+    /// MailService state: `[MAIL_SERVICE]` under the mail service program
+    pub const MAIL_SERVICE: &[u8] = b"mail_service";
+    /// Factory state: `[FACTORY]` under the factory program
+    pub const FACTORY: &[u8] = b"factory";
+}
+
+/// Fee denominations and basis-point math
+pub mod fees {
+    /// Decimals every fee amount is denominated in
+    pub const USDC_DECIMALS: u8 = 6;
+
+    /// Basis points in a whole
+    pub const BPS_DENOMINATOR: u16 = 10_000;
+
+    /// Split `total` into its `bps` share, rounded down, and the remainder
+    ///
+    /// Returns `None` if `bps` exceeds `BPS_DENOMINATOR`. The two parts always
+    /// add back up to `total`.
+    pub fn split_fee(total: u64, bps: u16) -> Option<(u64, u64)> {
+        if bps > BPS_DENOMINATOR {
+            return None;
+        }
+        // Cannot overflow: bps <= 10_000, so share <= total
+        let share = (total as u128 * bps as u128 / BPS_DENOMINATOR as u128) as u64;
+        Some((share, total - share))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::fees::*;
+
+    #[test]
+    fn split_fee_rounds_the_share_down_and_conserves_the_total() {
+        assert_eq!(split_fee(1_000, 2_500), Some((250, 750)));
+        assert_eq!(split_fee(999, 1), Some((0, 999)));
+        assert_eq!(split_fee(u64::MAX, BPS_DENOMINATOR), Some((u64::MAX, 0)));
+        assert_eq!(split_fee(u64::MAX, 0), Some((0, u64::MAX)));
+        assert_eq!(split_fee(1, BPS_DENOMINATOR + 1), None);
+    }
+}
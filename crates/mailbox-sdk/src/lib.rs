@@ -0,0 +1,119 @@
+//! Rust client SDK for the MailBox programs.
+//!
+//! Each program is re-exported behind its own feature so services only build
+//! what they use: `mailer`, `service` (MailService) and `factory`
+//! (MailBoxFactory), all on by default. Every module exposes the program's
+//! `ID`, typed accounts, the generated `accounts` and `instruction` modules for
+//! building instructions with [`instruction`], its events for [`decode_event`],
+//! and helpers for its well-known PDAs.
+//!
+//! ```rust,ignore
+//! use mailbox_sdk::{instruction, service};
+//!
+//! let ix = instruction(
+//!     service::ID,
+//!     service::accounts::AcceptDelegation { delegation, delegator, delegate },
+//!     service::instruction::AcceptDelegation {},
+//! );
+//! ```
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::{Discriminator, Event, InstructionData};
+
+pub use mailbox_common::{fees, seeds};
+
+#[cfg(feature = "mailer")]
+pub mod mailer {
+    //! Mailer program: messaging, revenue share claims and fee tokens
+    pub use ::mailer::*;
+    use anchor_lang::prelude::Pubkey;
+
+    /// The `MailerState` account
+    pub fn state_address() -> Pubkey {
+        Pubkey::find_program_address(&[super::seeds::MAILER], &ID).0
+    }
+
+    /// A recipient's revenue share claim
+    pub fn claim_address(recipient: &Pubkey) -> Pubkey {
+        Pubkey::find_program_address(&[b"claim", recipient.as_ref()], &ID).0
+    }
+}
+
+#[cfg(feature = "service")]
+pub mod service {
+    //! MailService program: delegations and handles
+    pub use ::mail_service::*;
+    use anchor_lang::prelude::Pubkey;
+
+    /// The `MailServiceState` account
+    pub fn state_address() -> Pubkey {
+        Pubkey::find_program_address(&[super::seeds::MAIL_SERVICE], &ID).0
+    }
+
+    /// A delegator's own delegation, as set by `delegate_to`
+    pub fn delegation_address(delegator: &Pubkey) -> Pubkey {
+        Pubkey::find_program_address(&[b"delegation", delegator.as_ref()], &ID).0
+    }
+
+    /// A handle's registration record
+    pub fn handle_address(name: &str) -> Pubkey {
+        Pubkey::find_program_address(&[b"handle", handle_hash(name).as_ref()], &ID).0
+    }
+}
+
+#[cfg(feature = "factory")]
+pub mod factory {
+    //! MailBoxFactory program: deployment registry and batch initialization
+    pub use ::mail_box_factory::*;
+    use anchor_lang::prelude::Pubkey;
+
+    /// The `FactoryState` account
+    pub fn state_address() -> Pubkey {
+        Pubkey::find_program_address(&[super::seeds::FACTORY], &ID).0
+    }
+
+    /// The curated deployment registered for `program_id`
+    pub fn deployment_by_program_address(program_id: &Pubkey) -> Pubkey {
+        Pubkey::find_program_address(&[b"deployment_by_program", program_id.as_ref()], &ID).0
+    }
+}
+
+/// Build an instruction from a program's generated `accounts` and `instruction` structs
+pub fn instruction(
+    program_id: Pubkey,
+    accounts: impl ToAccountMetas,
+    data: impl InstructionData,
+) -> Instruction {
+    Instruction {
+        program_id,
+        accounts: accounts.to_account_metas(None),
+        data: data.data(),
+    }
+}
+
+/// Decode an event from the base64-decoded payload of a `Program data:` log line
+///
+/// Returns `None` if the payload is a different event or is malformed.
+pub fn decode_event<E: Event + Discriminator>(data: &[u8]) -> Option<E> {
+    let payload = data.strip_prefix(E::DISCRIMINATOR.as_ref())?;
+    E::try_from_slice(payload).ok()
+}
+
+#[cfg(all(test, feature = "service"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_event_matches_on_discriminator() {
+        let event = service::DelegationAccepted {
+            delegator: Pubkey::new_unique(),
+            delegate: Pubkey::new_unique(),
+        };
+        let data = event.data();
+
+        let decoded = decode_event::<service::DelegationAccepted>(&data).unwrap();
+        assert_eq!(decoded.delegator, event.delegator);
+        assert!(decode_event::<service::DelegationClosed>(&data).is_none());
+    }
+}
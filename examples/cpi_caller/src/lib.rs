@@ -0,0 +1,147 @@
+//! # CPI Caller Example
+//!
+//! Minimal program that posts mail through the mailer via CPI, e.g. a DAO
+//! announcing proposals. A `[b"board"]` PDA owned by this program is the
+//! sender: it holds the fee tokens and signs the send with its seeds, while
+//! the transaction signer pays rent for any PDAs the send creates.
+//!
+//! The mailer validates every account it is handed, so this program only
+//! derives the board PDA and forwards the rest unchecked.
+
+use anchor_lang::prelude::*;
+use mailer::cpi::accounts::SendMessage;
+use mailer::program::Mailer;
+
+declare_id!("Hj5tVKGE4xtFBiYTnUqY95wR8KdmayhyCGMM96KzEify");
+
+#[program]
+pub mod cpi_caller {
+    use super::*;
+
+    /// Post a standard message from the board, returning its message ID
+    pub fn post(ctx: Context<Post>, subject: String, body: String) -> Result<[u8; 32]> {
+        let bump = [ctx.bumps.board];
+        let board_seeds: &[&[u8]] = &[b"board", &bump];
+        let signer_seeds = &[board_seeds];
+
+        let accounts = SendMessage {
+            recipient_claim: ctx.accounts.recipient_claim.to_account_info(),
+            mailer: ctx.accounts.mailer.to_account_info(),
+            stats: ctx.accounts.stats.to_account_info(),
+            token_config: ctx.accounts.token_config.to_account_info(),
+            price_update: None,
+            owner_bucket: ctx.accounts.owner_bucket.to_account_info(),
+            mint: ctx.accounts.mint.to_account_info(),
+            sender: ctx.accounts.board.to_account_info(),
+            payer: ctx.accounts.payer.to_account_info(),
+            sender_usdc_account: ctx.accounts.board_usdc_account.to_account_info(),
+            mailer_usdc_account: ctx.accounts.mailer_usdc_account.to_account_info(),
+            recipient_preferences: ctx.accounts.board_preferences.to_account_info(),
+            caller_policy: ctx.accounts.caller_policy.to_account_info(),
+            instructions: ctx.accounts.instructions.to_account_info(),
+            expiry_index: ctx.accounts.expiry_index.to_account_info(),
+            mail_id_record: None,
+            rate_limit: ctx.accounts.rate_limit.to_account_info(),
+            denylist: ctx.accounts.denylist.to_account_info(),
+            fee_exempt: None,
+            referral: None,
+            referrer_claim: None,
+            campaign: None,
+            sender_stats: None,
+            nft_token: None,
+            nft_metadata: None,
+            stake: None,
+            receipt_config: None,
+            receipt_tree_config: None,
+            receipt_tree: None,
+            bubblegum_program: None,
+            log_wrapper: None,
+            compression_program: None,
+            message_log: None,
+            message_log_tree: None,
+            inbox: None,
+            credit: None,
+            plan_subscription: None,
+            token_program: ctx.accounts.token_program.to_account_info(),
+            associated_token_program: ctx.accounts.associated_token_program.to_account_info(),
+            system_program: ctx.accounts.system_program.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(
+            ctx.accounts.mailer_program.to_account_info(),
+            accounts,
+            signer_seeds,
+        );
+
+        let message_id = mailer::cpi::send(cpi_ctx, subject, body, None, None, None)?.get();
+        Ok(message_id)
+    }
+}
+
+#[derive(Accounts)]
+pub struct Post<'info> {
+    /// CHECK: Sender of the mail; only signs via seeds and never holds data
+    #[account(seeds = [b"board"], bump)]
+    pub board: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// CHECK: Board's claim PDA, validated by the mailer
+    #[account(mut)]
+    pub recipient_claim: UncheckedAccount<'info>,
+
+    /// CHECK: Validated by the mailer
+    pub mailer: UncheckedAccount<'info>,
+
+    /// CHECK: Validated by the mailer
+    #[account(mut)]
+    pub stats: UncheckedAccount<'info>,
+
+    /// CHECK: Validated by the mailer
+    pub token_config: UncheckedAccount<'info>,
+
+    /// CHECK: Validated by the mailer
+    #[account(mut)]
+    pub owner_bucket: UncheckedAccount<'info>,
+
+    /// CHECK: Validated by the mailer
+    pub mint: UncheckedAccount<'info>,
+
+    /// CHECK: Board's associated token account for `mint`, validated by the mailer
+    #[account(mut)]
+    pub board_usdc_account: UncheckedAccount<'info>,
+
+    /// CHECK: Validated by the mailer
+    #[account(mut)]
+    pub mailer_usdc_account: UncheckedAccount<'info>,
+
+    /// CHECK: Board's preferences PDA, validated by the mailer
+    pub board_preferences: UncheckedAccount<'info>,
+
+    /// CHECK: Mailer's CPI caller policy PDA, validated by the mailer; the
+    /// owner must allow this program unless the policy allows all callers
+    pub caller_policy: UncheckedAccount<'info>,
+
+    /// CHECK: Instructions sysvar, validated by the mailer
+    pub instructions: UncheckedAccount<'info>,
+
+    /// CHECK: Validated by the mailer when the send starts a claim period
+    #[account(mut)]
+    pub expiry_index: UncheckedAccount<'info>,
+
+    /// CHECK: Board's rate limit PDA, validated by the mailer
+    #[account(mut)]
+    pub rate_limit: UncheckedAccount<'info>,
+
+    /// CHECK: Board's denylist marker, validated by the mailer
+    pub denylist: UncheckedAccount<'info>,
+
+    /// CHECK: Token program for `mint`, validated by the mailer
+    pub token_program: UncheckedAccount<'info>,
+
+    /// CHECK: Validated by the mailer
+    pub associated_token_program: UncheckedAccount<'info>,
+
+    pub mailer_program: Program<'info, Mailer>,
+    pub system_program: Program<'info, System>,
+}
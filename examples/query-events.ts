@@ -15,14 +15,14 @@ async function queryExamples() {
             console.log(`  - ${event.eventName} at slot ${event.slot}`);
         });
 
-        console.log('\n2. Get MailSent events only:');
+        console.log('\n2. Get mail sent events only:');
         const mailSentEvents = await database.getEvents({
-            eventNames: ['MailSent'],
+            eventNames: ['PriorityMailSent', 'StandardMailSent'],
             limit: 3
         });
-        console.log(`Found ${mailSentEvents.length} MailSent events`);
+        console.log(`Found ${mailSentEvents.length} mail sent events`);
         mailSentEvents.forEach(event => {
-            if (event.eventName === 'MailSent') {
+            if (event.eventName === 'PriorityMailSent' || event.eventName === 'StandardMailSent') {
                 console.log(`  - From: ${event.data.from.toString()}`);
                 console.log(`    To: ${event.data.to.toString()}`);
                 console.log(`    Subject: ${event.data.subject}`);
@@ -81,7 +81,7 @@ async function restApiExamples() {
     const examples = [
         'GET /health',
         'GET /events?limit=10',
-        'GET /events?eventNames=MailSent&limit=5',
+        'GET /events?eventNames=PriorityMailSent,StandardMailSent&limit=5',
         'GET /events?startSlot=100000&endSlot=200000',
         'GET /events/address/So11111111111111111111111111111111111111112',
         'GET /events/program/Your_Mailer_Program_ID',
@@ -95,7 +95,7 @@ async function restApiExamples() {
     });
 
     console.log('\nExample with curl:');
-    console.log(`curl "${baseUrl}/events?eventNames=MailSent&limit=3" | jq`);
+    console.log(`curl "${baseUrl}/events?eventNames=PriorityMailSent,StandardMailSent&limit=3" | jq`);
 }
 
 async function webSocketExample() {
@@ -110,7 +110,7 @@ async function webSocketExample() {
         ws.send(JSON.stringify({
             type: 'subscribe',
             filter: {
-                eventNames: ['MailSent', 'DomainRegistered']
+                eventNames: ['PriorityMailSent', 'StandardMailSent', 'DomainRegistered']
             }
         }));
 
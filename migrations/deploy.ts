@@ -117,6 +117,10 @@ export class MailBoxDeployer {
             .initialize(usdcMint)
             .accounts({
                 mailService: mailServicePda,
+                delegationBucket: PublicKey.findProgramAddressSync(
+                    [Buffer.from('bucket'), Buffer.from('delegation')],
+                    mailServiceProgram.programId
+                )[0],
                 owner: owner,
                 systemProgram: SystemProgram.programId,
             })
@@ -146,6 +150,31 @@ export class MailBoxDeployer {
             .signers(owner.equals(deployer) ? [] : [])
             .rpc();
             
+        // Accept USDC for fees at the default send fee
+        const [usdcTokenConfig] = PublicKey.findProgramAddressSync(
+            [Buffer.from('token'), usdcMint.toBuffer()],
+            mailerProgram.programId
+        );
+        const mailerState = await (mailerProgram.account as any).mailerState.fetch(mailerPda);
+        await (mailerProgram.methods as any)
+            .addToken(mailerState.sendFee)
+            .accounts({
+                tokenConfig: usdcTokenConfig,
+                sendBucket: PublicKey.findProgramAddressSync(
+                    [Buffer.from('bucket'), usdcMint.toBuffer(), Buffer.from('send')],
+                    mailerProgram.programId
+                )[0],
+                expiredBucket: PublicKey.findProgramAddressSync(
+                    [Buffer.from('bucket'), usdcMint.toBuffer(), Buffer.from('expired')],
+                    mailerProgram.programId
+                )[0],
+                mailer: mailerPda,
+                mint: usdcMint,
+                owner: owner,
+                systemProgram: SystemProgram.programId,
+            })
+            .rpc();
+
         console.log('✅ Mailer deployed:', mailerPda.toString());
         
         // Create associated token account for Mailer
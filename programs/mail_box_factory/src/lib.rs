@@ -1,17 +1,86 @@
 use anchor_lang::prelude::*;
+use anchor_lang::{system_program, Discriminator};
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::token::{self, Mint, Token, TokenAccount, Transfer};
+use mailbox_common::seeds;
+use mail_service::program::MailService;
+use mailer::program::Mailer;
 
 declare_id!("7KxLzPMHGHLYqHYkX8YYtNjSGRD9mT4rE5hQ6pZvGbPz");
 
+/// Longest reason `deactivate_deployment` stores, in bytes
+pub const MAX_REASON_LEN: usize = 64;
+
+/// Most admins the factory can hold at once
+pub const MAX_ADMINS: usize = 8;
+
+/// Longest `metadata_uri` a deployment can carry, in bytes
+pub const MAX_METADATA_URI_LEN: usize = 128;
+
+/// Most tags a deployment can carry, and the longest tag in bytes
+pub const MAX_TAGS: usize = 4;
+pub const MAX_TAG_LEN: usize = 16;
+
 #[program]
 pub mod mail_box_factory {
     use super::*;
 
     pub fn initialize(ctx: Context<Initialize>, version: String) -> Result<()> {
         let factory = &mut ctx.accounts.factory;
-        factory.owner = ctx.accounts.owner.key();
+        factory.owner = ctx.accounts.admin.key();
         factory.version = version;
         factory.deployment_count = 0;
         factory.bump = ctx.bumps.factory;
+        factory.admins = vec![ctx.accounts.admin.key()];
+        Ok(())
+    }
+
+    /// Grow a factory initialized before the admin set existed to the current
+    /// layout, seeding the set with its owner (owner only)
+    ///
+    /// Fields are only ever appended, so the old data keeps its meaning and the
+    /// new fields start zeroed. The owner pays the extra rent; a factory
+    /// already at the current size is left unchanged.
+    pub fn migrate_factory(ctx: Context<MigrateFactory>) -> Result<()> {
+        let factory_info = ctx.accounts.factory.to_account_info();
+        require!(
+            factory_info.owner == &crate::ID
+                && factory_info
+                    .try_borrow_data()?
+                    .starts_with(&FactoryState::DISCRIMINATOR),
+            FactoryError::InvalidMigrationAccount
+        );
+        let space = 8 + FactoryState::INIT_SPACE;
+        if factory_info.data_len() >= space {
+            return Ok(());
+        }
+
+        let shortfall = Rent::get()?
+            .minimum_balance(space)
+            .saturating_sub(factory_info.lamports());
+        if shortfall > 0 {
+            system_program::transfer(
+                CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    system_program::Transfer {
+                        from: ctx.accounts.owner.to_account_info(),
+                        to: factory_info.clone(),
+                    },
+                ),
+                shortfall,
+            )?;
+        }
+        factory_info.realloc(space, true)?;
+
+        let mut data = factory_info.try_borrow_mut_data()?;
+        let mut factory = FactoryState::try_deserialize(&mut &data[..])?;
+        require_keys_eq!(factory.owner, ctx.accounts.owner.key(), FactoryError::OnlyAdmin);
+        if factory.admins.is_empty() {
+            factory.admins = vec![factory.owner];
+        }
+        let mut writer: &mut [u8] = &mut data[..];
+        factory.try_serialize(&mut writer)?;
+
         Ok(())
     }
 
@@ -20,30 +89,232 @@ pub mod mail_box_factory {
         deployment_type: String,
         program_id: Pubkey,
         network: String,
+        program_hash: [u8; 32],
+        idl_hash: [u8; 32],
+        metadata: DeploymentMetadata,
     ) -> Result<()> {
+        metadata.validate()?;
         let factory = &mut ctx.accounts.factory;
         let deployment = &mut ctx.accounts.deployment;
         
         deployment.deployment_type = deployment_type;
         deployment.program_id = program_id;
-        deployment.network = network.clone();
-        deployment.deployer = ctx.accounts.owner.key();
+        deployment.network = network;
+        deployment.deployer = ctx.accounts.admin.key();
         deployment.timestamp = Clock::get()?.unix_timestamp;
         deployment.bump = ctx.bumps.deployment;
-        
-        factory.deployment_count += 1;
+        deployment.active = true;
+        deployment.program_hash = program_hash;
+        deployment.idl_hash = idl_hash;
+        deployment.metadata_uri = metadata.metadata_uri;
+        deployment.tags = metadata.tags;
+        deployment.curated = true;
 
-        emit!(DeploymentRegistered {
-            deployment_type: deployment.deployment_type.clone(),
-            program_id,
-            network,
-            deployer: deployment.deployer,
-            timestamp: deployment.timestamp,
+        // Both lookups point at the most recent registration
+        let by_program = &mut ctx.accounts.deployment_by_program;
+        by_program.deployment = deployment.key();
+        by_program.bump = ctx.bumps.deployment_by_program;
+        let by_network = &mut ctx.accounts.deployment_by_network;
+        by_network.deployment = deployment.key();
+        by_network.bump = ctx.bumps.deployment_by_network;
+
+        record_registration(factory, deployment)
+    }
+
+    /// Register a deployment as a third party by paying the registration fee
+    ///
+    /// Community registrations are listed but not curated: they never move the
+    /// `deployment_by_program` or `deployment_by_network` lookups.
+    pub fn register_deployment_paid(
+        ctx: Context<RegisterDeploymentPaid>,
+        deployment_type: String,
+        program_id: Pubkey,
+        network: String,
+        program_hash: [u8; 32],
+        idl_hash: [u8; 32],
+        metadata: DeploymentMetadata,
+    ) -> Result<()> {
+        metadata.validate()?;
+        let fee = ctx.accounts.factory.registration_fee;
+        if fee > 0 {
+            token::transfer(
+                CpiContext::new(
+                    ctx.accounts.token_program.to_account_info(),
+                    Transfer {
+                        from: ctx.accounts.registrant_usdc_account.to_account_info(),
+                        to: ctx.accounts.factory_usdc_account.to_account_info(),
+                        authority: ctx.accounts.registrant.to_account_info(),
+                    },
+                ),
+                fee,
+            )?;
+        }
+
+        let deployment = &mut ctx.accounts.deployment;
+        deployment.deployment_type = deployment_type;
+        deployment.program_id = program_id;
+        deployment.network = network;
+        deployment.deployer = ctx.accounts.registrant.key();
+        deployment.timestamp = Clock::get()?.unix_timestamp;
+        deployment.bump = ctx.bumps.deployment;
+        deployment.active = true;
+        deployment.program_hash = program_hash;
+        deployment.idl_hash = idl_hash;
+        deployment.metadata_uri = metadata.metadata_uri;
+        deployment.tags = metadata.tags;
+
+        record_registration(&mut ctx.accounts.factory, deployment)
+    }
+
+    /// Open third-party registration for `fee` in `usdc_mint`; withdraw collected fees before changing mints
+    pub fn set_registration_fee(ctx: Context<SetRegistrationFee>, usdc_mint: Pubkey, fee: u64) -> Result<()> {
+        let factory = &mut ctx.accounts.factory;
+        factory.usdc_mint = usdc_mint;
+        factory.registration_fee = fee;
+
+        emit!(RegistrationFeeUpdated { usdc_mint, fee });
+
+        Ok(())
+    }
+
+    /// Sweep collected registration fees to the calling admin
+    pub fn withdraw_registration_fees(ctx: Context<WithdrawRegistrationFees>) -> Result<()> {
+        let amount = ctx.accounts.factory_usdc_account.amount;
+        require!(amount > 0, FactoryError::NothingToWithdraw);
+
+        let bump = ctx.accounts.factory.bump;
+        let seeds = &[seeds::FACTORY, &[bump]];
+        let signer_seeds = &[&seeds[..]];
+
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.factory_usdc_account.to_account_info(),
+                    to: ctx.accounts.admin_usdc_account.to_account_info(),
+                    authority: ctx.accounts.factory.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            amount,
+        )?;
+
+        emit!(RegistrationFeesWithdrawn {
+            admin: ctx.accounts.admin.key(),
+            amount,
         });
 
         Ok(())
     }
 
+    /// Record an admin's attestation that the deployed binary hashes to the
+    /// `program_hash` recorded at registration
+    ///
+    /// The program does not read the binary itself: the admin hashes the program's
+    /// ProgramData off-chain and passes the result, which must match the registered
+    /// hash. Integrators trust `attested` as far as they trust the factory admins.
+    pub fn attest_deployment(ctx: Context<ManageDeployment>, program_hash: [u8; 32]) -> Result<()> {
+        let deployment = &mut ctx.accounts.deployment;
+        require!(program_hash == deployment.program_hash, FactoryError::HashMismatch);
+        deployment.attested = true;
+
+        emit!(DeploymentAttested {
+            deployment: deployment.key(),
+            program_id: deployment.program_id,
+            program_hash,
+        });
+
+        Ok(())
+    }
+
+    /// Close a deployment, refunding its rent to the deployer and tombstoning any lookup
+    /// that still points at it
+    ///
+    /// Curated deployments must pass both lookups, so none can be left pointing at a
+    /// closed account; community listings never own one and may omit them.
+    pub fn deregister_deployment(ctx: Context<DeregisterDeployment>) -> Result<()> {
+        let deployment = &ctx.accounts.deployment;
+        let key = deployment.key();
+        if deployment.curated {
+            require!(
+                ctx.accounts.deployment_by_program.is_some()
+                    && ctx.accounts.deployment_by_network.is_some(),
+                FactoryError::MissingDeploymentIndex
+            );
+        }
+        for index in [
+            &mut ctx.accounts.deployment_by_program,
+            &mut ctx.accounts.deployment_by_network,
+        ]
+        .into_iter()
+        .flatten()
+        {
+            if index.deployment == key {
+                index.deployment = Pubkey::default();
+            }
+        }
+
+        emit!(DeploymentDeregistered {
+            deployment: key,
+            program_id: deployment.program_id,
+            deregistered_by: ctx.accounts.authority.key(),
+        });
+
+        Ok(())
+    }
+
+    pub fn update_deployment_metadata(
+        ctx: Context<ManageDeployment>,
+        metadata: DeploymentMetadata,
+    ) -> Result<()> {
+        metadata.validate()?;
+        let deployment = &mut ctx.accounts.deployment;
+        deployment.metadata_uri = metadata.metadata_uri.clone();
+        deployment.tags = metadata.tags.clone();
+
+        emit!(DeploymentMetadataUpdated {
+            deployment: deployment.key(),
+            metadata_uri: metadata.metadata_uri,
+            tags: metadata.tags,
+        });
+
+        Ok(())
+    }
+
+    /// Mark a deployment as superseded so clients resolving through the factory move off it
+    pub fn deactivate_deployment(ctx: Context<ManageDeployment>, reason: String) -> Result<()> {
+        require!(reason.len() <= MAX_REASON_LEN, FactoryError::ReasonTooLong);
+        let deployment = &mut ctx.accounts.deployment;
+        deployment.active = false;
+        deployment.deprecated_reason = reason.clone();
+
+        emit!(DeploymentDeactivated {
+            deployment: deployment.key(),
+            program_id: deployment.program_id,
+            reason,
+        });
+
+        Ok(())
+    }
+
+    pub fn reactivate_deployment(ctx: Context<ManageDeployment>) -> Result<()> {
+        let deployment = &mut ctx.accounts.deployment;
+        deployment.active = true;
+        deployment.deprecated_reason.clear();
+
+        emit!(DeploymentReactivated {
+            deployment: deployment.key(),
+            program_id: deployment.program_id,
+        });
+
+        Ok(())
+    }
+
+    /// Resolve a `deployment_by_program` or `deployment_by_network` index to its deployment
+    pub fn lookup_deployment(ctx: Context<LookupDeployment>) -> Result<DeploymentInfo> {
+        Ok((*ctx.accounts.deployment).clone())
+    }
+
     pub fn predict_addresses(
         ctx: Context<PredictAddresses>,
         project_name: String,
@@ -53,13 +324,13 @@ pub mod mail_box_factory {
         let mailer_seeds = [
             project_name.as_bytes(),
             version.as_bytes(),
-            b"mailer"
+            seeds::MAILER
         ];
         
         let mail_service_seeds = [
             project_name.as_bytes(),
             version.as_bytes(),
-            b"mail_service"
+            seeds::MAIL_SERVICE
         ];
 
         let (mailer_pda, mailer_bump) = Pubkey::find_program_address(
@@ -89,22 +360,96 @@ pub mod mail_box_factory {
         Ok(predicted)
     }
 
+    /// Set the canonical USDC mint and fee defaults batch initialization uses on `network`
+    pub fn set_network_config(
+        ctx: Context<SetNetworkConfig>,
+        network: String,
+        usdc_mint: Pubkey,
+        send_fee: u64,
+        delegation_fee: u64,
+    ) -> Result<()> {
+        let config = &mut ctx.accounts.network_config;
+        config.network = network.clone();
+        config.usdc_mint = usdc_mint;
+        config.send_fee = send_fee;
+        config.delegation_fee = delegation_fee;
+        config.bump = ctx.bumps.network_config;
+
+        emit!(NetworkConfigUpdated {
+            network,
+            usdc_mint,
+            send_fee,
+            delegation_fee,
+        });
+
+        Ok(())
+    }
+
+    /// Initialize the Mailer and MailService in one transaction, both owned by the calling admin
+    /// and configured from `network`'s `NetworkConfig`
     pub fn batch_initialize_programs(
         ctx: Context<BatchInitialize>,
         project_name: String,
         version: String,
-        usdc_mint: Pubkey,
+        _network: String,
     ) -> Result<()> {
-        // This would coordinate initialization of both Mailer and MailService programs
-        // In practice, this would invoke CPIs to initialize both programs
-        
+        let usdc_mint = ctx.accounts.usdc_mint.key();
+        let config = &ctx.accounts.network_config;
+
+        mailer::cpi::initialize(CpiContext::new(
+            ctx.accounts.mailer_program.to_account_info(),
+            mailer::cpi::accounts::Initialize {
+                mailer: ctx.accounts.mailer.to_account_info(),
+                stats: ctx.accounts.mailer_stats.to_account_info(),
+                usdc_mint: ctx.accounts.usdc_mint.to_account_info(),
+                owner: ctx.accounts.admin.to_account_info(),
+                system_program: ctx.accounts.system_program.to_account_info(),
+            },
+        ))?;
+
+        mail_service::cpi::initialize(
+            CpiContext::new(
+                ctx.accounts.mail_service_program.to_account_info(),
+                mail_service::cpi::accounts::Initialize {
+                    mail_service: ctx.accounts.mail_service.to_account_info(),
+                    delegation_bucket: ctx.accounts.delegation_bucket.to_account_info(),
+                    handle_bucket: ctx.accounts.handle_bucket.to_account_info(),
+                    owner: ctx.accounts.admin.to_account_info(),
+                    system_program: ctx.accounts.system_program.to_account_info(),
+                },
+            ),
+            usdc_mint,
+        )?;
+
+        mailer::cpi::set_fee(
+            CpiContext::new(
+                ctx.accounts.mailer_program.to_account_info(),
+                mailer::cpi::accounts::SetFee {
+                    mailer: ctx.accounts.mailer.to_account_info(),
+                    owner: ctx.accounts.admin.to_account_info(),
+                },
+            ),
+            config.send_fee,
+        )?;
+
+        mail_service::cpi::set_delegation_fee(
+            CpiContext::new(
+                ctx.accounts.mail_service_program.to_account_info(),
+                mail_service::cpi::accounts::SetFee {
+                    mail_service: ctx.accounts.mail_service.to_account_info(),
+                    owner: ctx.accounts.admin.to_account_info(),
+                },
+            ),
+            config.delegation_fee,
+        )?;
+
         emit!(BatchInitialized {
             project_name,
             version,
             usdc_mint,
             mailer_program: ctx.accounts.mailer_program.key(),
             mail_service_program: ctx.accounts.mail_service_program.key(),
-            coordinator: ctx.accounts.owner.key(),
+            coordinator: ctx.accounts.admin.key(),
         });
 
         Ok(())
@@ -123,42 +468,84 @@ pub mod mail_box_factory {
         Ok(())
     }
 
-    pub fn set_owner(ctx: Context<SetOwner>, new_owner: Pubkey) -> Result<()> {
+    pub fn add_admin(ctx: Context<ManageAdmins>, admin: Pubkey) -> Result<()> {
+        let factory = &mut ctx.accounts.factory;
+        require!(!factory.is_admin(&admin), FactoryError::AlreadyAdmin);
+        require!(factory.admins.len() < MAX_ADMINS, FactoryError::TooManyAdmins);
+        factory.admins.push(admin);
+
+        emit!(AdminAdded {
+            admin,
+            added_by: ctx.accounts.admin.key(),
+        });
+
+        Ok(())
+    }
+
+    /// Any admin may remove any other, but the factory always keeps at least one
+    pub fn remove_admin(ctx: Context<ManageAdmins>, admin: Pubkey) -> Result<()> {
         let factory = &mut ctx.accounts.factory;
-        let old_owner = factory.owner;
-        factory.owner = new_owner;
+        let index = factory
+            .admins
+            .iter()
+            .position(|key| *key == admin)
+            .ok_or(FactoryError::NotAdmin)?;
+        require!(factory.admins.len() > 1, FactoryError::LastAdmin);
+        factory.admins.swap_remove(index);
 
-        emit!(OwnerUpdated {
-            old_owner,
-            new_owner,
+        emit!(AdminRemoved {
+            admin,
+            removed_by: ctx.accounts.admin.key(),
         });
 
         Ok(())
     }
 }
 
+/// Count a newly filled-in `deployment` and announce it
+fn record_registration(
+    factory: &mut Account<FactoryState>,
+    deployment: &Account<DeploymentInfo>,
+) -> Result<()> {
+    factory.deployment_count = factory
+        .deployment_count
+        .checked_add(1)
+        .ok_or(FactoryError::MathOverflow)?;
+
+    emit!(DeploymentRegistered {
+        deployment_type: deployment.deployment_type.clone(),
+        program_id: deployment.program_id,
+        network: deployment.network.clone(),
+        deployer: deployment.deployer,
+        timestamp: deployment.timestamp,
+    });
+
+    Ok(())
+}
+
 #[derive(Accounts)]
 pub struct Initialize<'info> {
     #[account(
         init,
-        payer = owner,
+        payer = admin,
         space = 8 + FactoryState::INIT_SPACE,
-        seeds = [b"factory"],
+        seeds = [seeds::FACTORY],
         bump
     )]
     pub factory: Account<'info, FactoryState>,
     
     #[account(mut)]
-    pub owner: Signer<'info>,
+    pub admin: Signer<'info>,
     
     pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
+#[instruction(deployment_type: String, program_id: Pubkey, network: String)]
 pub struct RegisterDeployment<'info> {
     #[account(
         init,
-        payer = owner,
+        payer = admin,
         space = 8 + DeploymentInfo::INIT_SPACE,
         seeds = [
             b"deployment",
@@ -167,20 +554,188 @@ pub struct RegisterDeployment<'info> {
         bump
     )]
     pub deployment: Account<'info, DeploymentInfo>,
+
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = 8 + DeploymentIndex::INIT_SPACE,
+        seeds = [b"deployment_by_program", program_id.as_ref()],
+        bump
+    )]
+    pub deployment_by_program: Account<'info, DeploymentIndex>,
+
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = 8 + DeploymentIndex::INIT_SPACE,
+        seeds = [b"deployment_by_network", network.as_bytes(), deployment_type.as_bytes()],
+        bump
+    )]
+    pub deployment_by_network: Account<'info, DeploymentIndex>,
     
     #[account(
         mut,
-        seeds = [b"factory"],
+        seeds = [seeds::FACTORY],
         bump = factory.bump,
-        has_one = owner @ FactoryError::OnlyOwner
+        constraint = factory.is_admin(&admin.key()) @ FactoryError::OnlyAdmin
     )]
     pub factory: Account<'info, FactoryState>,
     
     #[account(mut)]
-    pub owner: Signer<'info>,
+    pub admin: Signer<'info>,
     pub system_program: Program<'info, System>,
 }
 
+#[derive(Accounts)]
+pub struct RegisterDeploymentPaid<'info> {
+    #[account(
+        init,
+        payer = registrant,
+        space = 8 + DeploymentInfo::INIT_SPACE,
+        seeds = [
+            b"deployment",
+            factory.deployment_count.to_le_bytes().as_ref()
+        ],
+        bump
+    )]
+    pub deployment: Account<'info, DeploymentInfo>,
+
+    #[account(
+        mut,
+        seeds = [seeds::FACTORY],
+        bump = factory.bump,
+        constraint = factory.usdc_mint != Pubkey::default() @ FactoryError::RegistrationClosed
+    )]
+    pub factory: Account<'info, FactoryState>,
+
+    #[account(mut)]
+    pub registrant: Signer<'info>,
+
+    #[account(address = factory.usdc_mint)]
+    pub usdc_mint: Account<'info, Mint>,
+
+    #[account(
+        mut,
+        associated_token::mint = usdc_mint,
+        associated_token::authority = registrant
+    )]
+    pub registrant_usdc_account: Account<'info, TokenAccount>,
+
+    #[account(
+        init_if_needed,
+        payer = registrant,
+        associated_token::mint = usdc_mint,
+        associated_token::authority = factory
+    )]
+    pub factory_usdc_account: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetRegistrationFee<'info> {
+    #[account(
+        mut,
+        seeds = [seeds::FACTORY],
+        bump = factory.bump,
+        constraint = factory.is_admin(&admin.key()) @ FactoryError::OnlyAdmin
+    )]
+    pub factory: Account<'info, FactoryState>,
+
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawRegistrationFees<'info> {
+    #[account(
+        seeds = [seeds::FACTORY],
+        bump = factory.bump,
+        constraint = factory.is_admin(&admin.key()) @ FactoryError::OnlyAdmin
+    )]
+    pub factory: Account<'info, FactoryState>,
+
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        associated_token::mint = factory.usdc_mint,
+        associated_token::authority = factory
+    )]
+    pub factory_usdc_account: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        associated_token::mint = factory.usdc_mint,
+        associated_token::authority = admin
+    )]
+    pub admin_usdc_account: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct DeregisterDeployment<'info> {
+    #[account(mut, close = deployer, has_one = deployer)]
+    pub deployment: Account<'info, DeploymentInfo>,
+
+    #[account(
+        mut,
+        seeds = [b"deployment_by_program", deployment.program_id.as_ref()],
+        bump = deployment_by_program.bump
+    )]
+    pub deployment_by_program: Option<Account<'info, DeploymentIndex>>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"deployment_by_network",
+            deployment.network.as_bytes(),
+            deployment.deployment_type.as_bytes()
+        ],
+        bump = deployment_by_network.bump
+    )]
+    pub deployment_by_network: Option<Account<'info, DeploymentIndex>>,
+
+    #[account(seeds = [seeds::FACTORY], bump = factory.bump)]
+    pub factory: Account<'info, FactoryState>,
+
+    /// CHECK: Receives the rent; must be the deployment's recorded deployer
+    #[account(mut)]
+    pub deployer: UncheckedAccount<'info>,
+
+    /// An admin, or the deployer of its own listing
+    #[account(
+        constraint = factory.is_admin(&authority.key())
+            || authority.key() == deployment.deployer @ FactoryError::OnlyAdmin
+    )]
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ManageDeployment<'info> {
+    #[account(mut)]
+    pub deployment: Account<'info, DeploymentInfo>,
+
+    #[account(
+        seeds = [seeds::FACTORY],
+        bump = factory.bump,
+        constraint = factory.is_admin(&admin.key()) @ FactoryError::OnlyAdmin
+    )]
+    pub factory: Account<'info, FactoryState>,
+
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct LookupDeployment<'info> {
+    pub index: Account<'info, DeploymentIndex>,
+
+    #[account(address = index.deployment @ FactoryError::DeploymentMismatch)]
+    pub deployment: Account<'info, DeploymentInfo>,
+}
+
 #[derive(Accounts)]
 pub struct PredictAddresses<'info> {
     /// CHECK: This is the mailer program ID for PDA calculation
@@ -191,57 +746,137 @@ pub struct PredictAddresses<'info> {
 }
 
 #[derive(Accounts)]
+#[instruction(network: String)]
+pub struct SetNetworkConfig<'info> {
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = 8 + NetworkConfig::INIT_SPACE,
+        seeds = [b"network", network.as_bytes()],
+        bump
+    )]
+    pub network_config: Account<'info, NetworkConfig>,
+
+    #[account(
+        seeds = [seeds::FACTORY],
+        bump = factory.bump,
+        constraint = factory.is_admin(&admin.key()) @ FactoryError::OnlyAdmin
+    )]
+    pub factory: Account<'info, FactoryState>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(project_name: String, version: String, network: String)]
 pub struct BatchInitialize<'info> {
     #[account(
-        seeds = [b"factory"],
+        seeds = [seeds::FACTORY],
         bump = factory.bump,
-        has_one = owner @ FactoryError::OnlyOwner
+        constraint = factory.is_admin(&admin.key()) @ FactoryError::OnlyAdmin
     )]
     pub factory: Account<'info, FactoryState>,
     
-    pub owner: Signer<'info>,
-    
-    /// CHECK: This is the mailer program to initialize
-    pub mailer_program: UncheckedAccount<'info>,
-    
-    /// CHECK: This is the mail service program to initialize
-    pub mail_service_program: UncheckedAccount<'info>,
+    /// Pays for and owns both initialized programs
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(seeds = [b"network", network.as_bytes()], bump = network_config.bump)]
+    pub network_config: Account<'info, NetworkConfig>,
+
+    /// CHECK: The network's canonical mint; validated as a 6-decimal mint by the mailer's `initialize`
+    #[account(address = network_config.usdc_mint @ FactoryError::UsdcMintMismatch)]
+    pub usdc_mint: UncheckedAccount<'info>,
+
+    /// CHECK: Mailer state PDA, created by the mailer's `initialize`
+    #[account(mut)]
+    pub mailer: UncheckedAccount<'info>,
+
+    /// CHECK: Mailer stats PDA (`[b"mailer_stats"]`), created by the mailer's `initialize`
+    #[account(mut)]
+    pub mailer_stats: UncheckedAccount<'info>,
+
+    /// CHECK: MailService state PDA, created by the mail service's `initialize`
+    #[account(mut)]
+    pub mail_service: UncheckedAccount<'info>,
+
+    /// CHECK: Delegation fee bucket PDA, created by the mail service's `initialize`
+    #[account(mut)]
+    pub delegation_bucket: UncheckedAccount<'info>,
+
+    /// CHECK: Handle fee bucket PDA, created by the mail service's `initialize`
+    #[account(mut)]
+    pub handle_bucket: UncheckedAccount<'info>,
+
+    pub mailer_program: Program<'info, Mailer>,
+
+    pub mail_service_program: Program<'info, MailService>,
+
+    pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
 pub struct UpdateVersion<'info> {
     #[account(
         mut,
-        seeds = [b"factory"],
+        seeds = [seeds::FACTORY],
         bump = factory.bump,
-        has_one = owner @ FactoryError::OnlyOwner
+        constraint = factory.is_admin(&admin.key()) @ FactoryError::OnlyAdmin
     )]
     pub factory: Account<'info, FactoryState>,
     
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateFactory<'info> {
+    /// CHECK: Factory PDA, possibly in an older layout; checked in `migrate_factory`
+    #[account(mut, seeds = [seeds::FACTORY], bump)]
+    pub factory: UncheckedAccount<'info>,
+
+    #[account(mut)]
     pub owner: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct SetOwner<'info> {
+pub struct ManageAdmins<'info> {
     #[account(
         mut,
-        seeds = [b"factory"],
+        seeds = [seeds::FACTORY],
         bump = factory.bump,
-        has_one = owner @ FactoryError::OnlyOwner
+        constraint = factory.is_admin(&admin.key()) @ FactoryError::OnlyAdmin
     )]
     pub factory: Account<'info, FactoryState>,
     
-    pub owner: Signer<'info>,
+    pub admin: Signer<'info>,
 }
 
 #[account]
 #[derive(InitSpace)]
 pub struct FactoryState {
+    /// Wallet that initialized the factory; access is decided by `admins` alone
     pub owner: Pubkey,
     #[max_len(32)]
     pub version: String,
     pub deployment_count: u64,
     pub bump: u8,
+    /// Mint third-party registration fees are paid in; unset keeps registration admin-only
+    pub usdc_mint: Pubkey,
+    pub registration_fee: u64,
+    /// Wallets that may manage the factory; any one of them suffices
+    #[max_len(MAX_ADMINS)]
+    pub admins: Vec<Pubkey>,
+}
+
+impl FactoryState {
+    pub fn is_admin(&self, key: &Pubkey) -> bool {
+        self.admins.contains(key)
+    }
 }
 
 #[account]
@@ -255,6 +890,63 @@ pub struct DeploymentInfo {
     pub deployer: Pubkey,
     pub timestamp: i64,
     pub bump: u8,
+    /// Cleared by `deactivate_deployment` once the deployment is superseded
+    pub active: bool,
+    #[max_len(64)]
+    pub deprecated_reason: String,
+    /// SHA-256 of the program binary, recorded at registration
+    pub program_hash: [u8; 32],
+    /// SHA-256 of the IDL, recorded at registration
+    pub idl_hash: [u8; 32],
+    /// Set once an admin attests `program_hash` with `attest_deployment`; not checked on-chain
+    pub attested: bool,
+    /// Registered by an admin rather than as a paid community listing
+    pub curated: bool,
+    /// Off-chain JSON manifest, e.g. on Arweave or IPFS
+    #[max_len(MAX_METADATA_URI_LEN)]
+    pub metadata_uri: String,
+    #[max_len(MAX_TAGS, MAX_TAG_LEN)]
+    pub tags: Vec<String>,
+}
+
+/// Explorer-facing details set at registration and by `update_deployment_metadata`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct DeploymentMetadata {
+    pub metadata_uri: String,
+    pub tags: Vec<String>,
+}
+
+impl DeploymentMetadata {
+    fn validate(&self) -> Result<()> {
+        require!(
+            self.metadata_uri.len() <= MAX_METADATA_URI_LEN
+                && self.tags.len() <= MAX_TAGS
+                && self.tags.iter().all(|tag| tag.len() <= MAX_TAG_LEN),
+            FactoryError::InvalidMetadata
+        );
+        Ok(())
+    }
+}
+
+/// Per-cluster defaults at `[b"network", network]`
+#[account]
+#[derive(InitSpace)]
+pub struct NetworkConfig {
+    #[max_len(32)]
+    pub network: String,
+    pub usdc_mint: Pubkey,
+    pub send_fee: u64,
+    pub delegation_fee: u64,
+    pub bump: u8,
+}
+
+/// Secondary index at `[b"deployment_by_program", program_id]` or
+/// `[b"deployment_by_network", network, deployment_type]`
+#[account]
+#[derive(InitSpace)]
+pub struct DeploymentIndex {
+    pub deployment: Pubkey,
+    pub bump: u8,
 }
 
 #[derive(AnchorSerialize, AnchorDeserialize)]
@@ -274,6 +966,60 @@ pub struct DeploymentRegistered {
     pub timestamp: i64,
 }
 
+#[event]
+pub struct NetworkConfigUpdated {
+    pub network: String,
+    pub usdc_mint: Pubkey,
+    pub send_fee: u64,
+    pub delegation_fee: u64,
+}
+
+#[event]
+pub struct RegistrationFeeUpdated {
+    pub usdc_mint: Pubkey,
+    pub fee: u64,
+}
+
+#[event]
+pub struct RegistrationFeesWithdrawn {
+    pub admin: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct DeploymentAttested {
+    pub deployment: Pubkey,
+    pub program_id: Pubkey,
+    pub program_hash: [u8; 32],
+}
+
+#[event]
+pub struct DeploymentDeregistered {
+    pub deployment: Pubkey,
+    pub program_id: Pubkey,
+    pub deregistered_by: Pubkey,
+}
+
+#[event]
+pub struct DeploymentMetadataUpdated {
+    pub deployment: Pubkey,
+    pub metadata_uri: String,
+    pub tags: Vec<String>,
+}
+
+#[event]
+pub struct DeploymentDeactivated {
+    pub deployment: Pubkey,
+    pub program_id: Pubkey,
+    pub reason: String,
+}
+
+#[event]
+pub struct DeploymentReactivated {
+    pub deployment: Pubkey,
+    pub program_id: Pubkey,
+}
+
 #[event]
 pub struct AddressesPredicted {
     pub project_name: String,
@@ -298,6 +1044,20 @@ pub struct VersionUpdated {
     pub new_version: String,
 }
 
+#[event]
+pub struct AdminAdded {
+    pub admin: Pubkey,
+    pub added_by: Pubkey,
+}
+
+#[event]
+pub struct AdminRemoved {
+    pub admin: Pubkey,
+    pub removed_by: Pubkey,
+}
+
+/// Emitted by ownership transfers before the owner was replaced by the admin
+/// set; no longer emitted, kept so indexers can decode earlier history
 #[event]
 pub struct OwnerUpdated {
     pub old_owner: Pubkey,
@@ -306,12 +1066,40 @@ pub struct OwnerUpdated {
 
 #[error_code]
 pub enum FactoryError {
-    #[msg("Only the owner can perform this action")]
-    OnlyOwner,
+    #[msg("Only an admin can perform this action")]
+    OnlyAdmin,
     #[msg("Invalid program ID")]
     InvalidProgramId,
     #[msg("Network not supported")]
     NetworkNotSupported,
     #[msg("Version string too long")]
     VersionTooLong,
+    #[msg("Arithmetic overflow")]
+    MathOverflow,
+    #[msg("Deployment does not match the index")]
+    DeploymentMismatch,
+    #[msg("Deprecation reason too long")]
+    ReasonTooLong,
+    #[msg("USDC mint does not match the network configuration")]
+    UsdcMintMismatch,
+    #[msg("Program hash does not match the registered hash")]
+    HashMismatch,
+    #[msg("Wallet is already an admin")]
+    AlreadyAdmin,
+    #[msg("Wallet is not an admin")]
+    NotAdmin,
+    #[msg("Admin set is full")]
+    TooManyAdmins,
+    #[msg("Cannot remove the last admin")]
+    LastAdmin,
+    #[msg("Third-party registration is not open")]
+    RegistrationClosed,
+    #[msg("No registration fees to withdraw")]
+    NothingToWithdraw,
+    #[msg("Metadata URI or tags too long, or too many tags")]
+    InvalidMetadata,
+    #[msg("Curated deployments must pass both lookup indexes")]
+    MissingDeploymentIndex,
+    #[msg("Account is not the factory state")]
+    InvalidMigrationAccount,
 }
\ No newline at end of file
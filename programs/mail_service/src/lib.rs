@@ -1,10 +1,110 @@
 use anchor_lang::prelude::*;
+use anchor_lang::solana_program::hash::hash;
+use anchor_lang::system_program::{self, Allocate, Assign, CreateAccount};
 use anchor_spl::token::{self, Token, TokenAccount, Transfer};
 use anchor_spl::associated_token::AssociatedToken;
+use mailbox_common::fees::{split_fee, BPS_DENOMINATOR};
+use mailbox_common::seeds;
 
 declare_id!("8EKjCLZjz6LKRxZcQ6LwwF5V8P3TCEgM2CdQg4pZxXHE");
 
 const DELEGATION_FEE: u64 = 10_000_000;    // 10 USDC (6 decimals)
+const REGISTRATION_FEE: u64 = 100_000_000; // 100 USDC (6 decimals)
+const UPDATE_FEE: u64 = 1_000_000;         // 1 USDC (6 decimals)
+const UPDATE_WINDOW: i64 = 30 * 24 * 60 * 60; // 30 days
+
+/// How long a handle registration or renewal lasts: 365 days in seconds
+pub const HANDLE_PERIOD: i64 = 365 * 24 * 60 * 60;
+
+/// Maximum handle length in bytes
+pub const MAX_HANDLE_LEN: usize = 32;
+
+/// Seed of a handle's PDA: the SHA-256 of its name
+pub fn handle_hash(name: &str) -> [u8; 32] {
+    hash(name.as_bytes()).to_bytes()
+}
+
+/// Handles are 1-32 characters of lowercase letters, digits, `-` and `_`
+pub fn validate_handle(name: &str) -> Result<()> {
+    require!(
+        !name.is_empty()
+            && name.len() <= MAX_HANDLE_LEN
+            && name
+                .bytes()
+                .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'-' || b == b'_'),
+        MailServiceError::InvalidHandle
+    );
+    Ok(())
+}
+
+/// Delegate may send mail on the delegator's behalf
+pub const PERMISSION_SEND: u32 = 1 << 0;
+/// Delegate may claim the delegator's revenue share
+pub const PERMISSION_CLAIM: u32 = 1 << 1;
+/// Delegate may register, renew and transfer the delegator's handles
+pub const PERMISSION_MANAGE_HANDLES: u32 = 1 << 2;
+/// Every permission a delegation can grant
+pub const ALL_PERMISSIONS: u32 = PERMISSION_SEND | PERMISSION_CLAIM | PERMISSION_MANAGE_HANDLES;
+
+/// Fee for pointing `delegation` at a new delegate, before `delegation_charge`
+///
+/// Replacing a delegate within `update_window` of the last paid change costs the
+/// reduced `update_fee`; first-time and lapsed delegations pay the full fee.
+pub fn delegation_base_fee(delegation: &Delegation, service: &MailServiceState, now: i64) -> u64 {
+    let existing = delegation.delegate.or(delegation.pending_delegate).is_some();
+    let within_window = delegation
+        .updated_at
+        .checked_add(service.update_window)
+        .is_none_or(|window_end| now <= window_end);
+    if existing && within_window {
+        service.update_fee
+    } else {
+        service.delegation_fee
+    }
+}
+
+/// Maximum `delegate_share_bps`: the whole delegation fee
+pub const MAX_DELEGATE_SHARE_BPS: u16 = BPS_DENOMINATOR;
+
+/// Portion of a delegation fee that accrues to the delegate at `share_bps`
+pub fn delegate_share(fee: u64, share_bps: u16) -> Result<u64> {
+    let (share, _) = split_fee(fee, share_bps).ok_or(MailServiceError::InvalidDelegateShare)?;
+    Ok(share)
+}
+
+/// Fee `delegate_to` charges for setting `delegate`; clearing a delegation is free
+pub fn delegation_charge(delegate: Option<Pubkey>, delegation_fee: u64) -> u64 {
+    match delegate {
+        Some(key) if key != Pubkey::default() => delegation_fee,
+        _ => 0,
+    }
+}
+
+/// A delegate must be another wallet; program-derived addresses are refused
+/// unless the owner allows program delegates
+pub fn validate_delegate(delegator: &Pubkey, delegate: &Pubkey, allow_program_delegates: bool) -> Result<()> {
+    require_keys_neq!(*delegator, *delegate, MailServiceError::SelfDelegation);
+    require!(
+        allow_program_delegates || delegate.is_on_curve(),
+        MailServiceError::ProgramDelegate
+    );
+    Ok(())
+}
+
+/// Permissions must be known `PERMISSION_*` flags and an expiry must lie in the future
+fn validate_delegation_terms(expires_at: Option<i64>, permissions: u32) -> Result<()> {
+    require!(
+        permissions & !ALL_PERMISSIONS == 0,
+        MailServiceError::InvalidPermissions
+    );
+    if let Some(expires_at) = expires_at {
+        require!(
+            expires_at > Clock::get()?.unix_timestamp,
+            MailServiceError::InvalidDelegationExpiry
+        );
+    }
+    Ok(())
+}
 
 #[program]
 pub mod mail_service {
@@ -16,37 +116,209 @@ pub mod mail_service {
         service.usdc_mint = usdc_mint;
         service.delegation_fee = DELEGATION_FEE;
         service.bump = ctx.bumps.mail_service;
+        service.registration_fee = REGISTRATION_FEE;
+        service.update_fee = UPDATE_FEE;
+        service.update_window = UPDATE_WINDOW;
+        service.guardian = service.owner;
+
+        let bucket = &mut ctx.accounts.delegation_bucket;
+        bucket.source = FeeSource::Delegation;
+        bucket.destination = service.owner;
+        bucket.bump = ctx.bumps.delegation_bucket;
+
+        let bucket = &mut ctx.accounts.handle_bucket;
+        bucket.source = FeeSource::Handle;
+        bucket.destination = service.owner;
+        bucket.bump = ctx.bumps.handle_bucket;
         Ok(())
     }
 
-    pub fn delegate_to(ctx: Context<DelegateTo>, delegate: Option<Pubkey>) -> Result<()> {
+    pub fn delegate_to(
+        ctx: Context<DelegateTo>,
+        delegate: Option<Pubkey>,
+        expires_at: Option<i64>,
+        permissions: u32,
+    ) -> Result<()> {
         let delegation = &mut ctx.accounts.delegation;
         let delegator = ctx.accounts.delegator.key();
+        validate_delegation_terms(expires_at, permissions)?;
+        if let Some(delegate) = delegate.filter(|key| *key != Pubkey::default()) {
+            // Clearing stays possible while paused so delegators can still revoke access
+            require!(!ctx.accounts.mail_service.paused, MailServiceError::Paused);
+            validate_delegate(
+                &delegator,
+                &delegate,
+                ctx.accounts.mail_service.allow_program_delegates,
+            )?;
+        }
+        let now = Clock::get()?.unix_timestamp;
         
         // If setting delegation (not clearing), charge fee
-        if let Some(delegate_key) = delegate {
-            if delegate_key != Pubkey::default() {
-                // Transfer delegation fee from delegator to service
-                let transfer_ctx = CpiContext::new(
-                    ctx.accounts.token_program.to_account_info(),
-                    Transfer {
-                        from: ctx.accounts.delegator_usdc_account.to_account_info(),
-                        to: ctx.accounts.service_usdc_account.to_account_info(),
-                        authority: ctx.accounts.delegator.to_account_info(),
-                    },
-                );
-                token::transfer(transfer_ctx, ctx.accounts.mail_service.delegation_fee)?;
-            }
+        let fee = delegation_charge(
+            delegate,
+            delegation_base_fee(delegation, &ctx.accounts.mail_service, now),
+        );
+        charge_fee(
+            &ctx.accounts.token_program,
+            &ctx.accounts.delegator_usdc_account,
+            &ctx.accounts.service_usdc_account,
+            &ctx.accounts.delegator,
+            &mut ctx.accounts.mail_service,
+            &mut ctx.accounts.fee_bucket,
+            fee,
+        )?;
+        if fee > 0 {
+            delegation.updated_at = now;
+            share_with_delegate(
+                &mut ctx.accounts.mail_service,
+                &mut ctx.accounts.fee_bucket,
+                &ctx.accounts.delegate_claim,
+                delegate.unwrap_or_default(),
+                fee,
+                &ctx.accounts.delegator,
+                &ctx.accounts.system_program,
+            )?;
         }
 
-        // Update delegation
+        // A new delegate only becomes active once they accept; clearing takes effect immediately
         delegation.delegator = delegator;
-        delegation.delegate = delegate;
+        delegation.delegate = None;
+        delegation.pending_delegate = delegate;
+        delegation.expires_at = expires_at;
+        delegation.permissions = permissions;
         delegation.bump = ctx.bumps.delegation;
 
+        let accounts = &ctx.accounts;
+        unindex_delegation(
+            &accounts.delegator_record,
+            &accounts.previous_index,
+            accounts.delegation.key(),
+        )?;
+        if let Some(delegate) = delegate.filter(|key| *key != Pubkey::default()) {
+            index_delegation(
+                &accounts.delegator_record,
+                &accounts.delegate_index,
+                accounts.delegation.key(),
+                delegator,
+                delegate,
+                &accounts.delegator,
+                &accounts.system_program,
+            )?;
+        }
+
         emit!(DelegationSet {
             delegator,
             delegate,
+            expires_at,
+            permissions,
+        });
+
+        Ok(())
+    }
+
+    pub fn add_delegate(
+        ctx: Context<AddDelegate>,
+        delegate: Pubkey,
+        expires_at: Option<i64>,
+        permissions: u32,
+    ) -> Result<()> {
+        require!(!ctx.accounts.mail_service.paused, MailServiceError::Paused);
+        let delegator = ctx.accounts.delegator.key();
+        validate_delegation_terms(expires_at, permissions)?;
+        validate_delegate(
+            &delegator,
+            &delegate,
+            ctx.accounts.mail_service.allow_program_delegates,
+        )?;
+
+        let fee = delegation_charge(Some(delegate), ctx.accounts.mail_service.delegation_fee);
+        charge_fee(
+            &ctx.accounts.token_program,
+            &ctx.accounts.delegator_usdc_account,
+            &ctx.accounts.service_usdc_account,
+            &ctx.accounts.delegator,
+            &mut ctx.accounts.mail_service,
+            &mut ctx.accounts.fee_bucket,
+            fee,
+        )?;
+        share_with_delegate(
+            &mut ctx.accounts.mail_service,
+            &mut ctx.accounts.fee_bucket,
+            &ctx.accounts.delegate_claim,
+            delegate,
+            fee,
+            &ctx.accounts.delegator,
+            &ctx.accounts.system_program,
+        )?;
+
+        // Like delegate_to, the delegate must accept before the delegation is active
+        let delegation = &mut ctx.accounts.delegation;
+        delegation.delegator = delegator;
+        delegation.delegate = None;
+        delegation.pending_delegate = Some(delegate);
+        delegation.expires_at = expires_at;
+        delegation.permissions = permissions;
+        delegation.bump = ctx.bumps.delegation;
+
+        let delegates = &mut ctx.accounts.delegates;
+        delegates.delegator = delegator;
+        delegates.count = delegates.count.checked_add(1).ok_or(MailServiceError::MathOverflow)?;
+        delegates.bump = ctx.bumps.delegates;
+
+        let accounts = &ctx.accounts;
+        index_delegation(
+            &accounts.delegator_record,
+            &accounts.delegate_index,
+            accounts.delegation.key(),
+            delegator,
+            delegate,
+            &accounts.delegator,
+            &accounts.system_program,
+        )?;
+
+        emit!(DelegateAdded {
+            delegator,
+            delegate,
+            expires_at,
+            permissions,
+        });
+
+        Ok(())
+    }
+
+    pub fn remove_delegate(ctx: Context<RemoveDelegate>, delegate: Pubkey) -> Result<()> {
+        unindex_delegation(
+            &ctx.accounts.delegator_record,
+            &ctx.accounts.previous_index,
+            ctx.accounts.delegation.key(),
+        )?;
+
+        let delegates = &mut ctx.accounts.delegates;
+        delegates.count = delegates.count.checked_sub(1).ok_or(MailServiceError::MathOverflow)?;
+
+        emit!(DelegateRemoved {
+            delegator: delegates.delegator,
+            delegate,
+        });
+
+        Ok(())
+    }
+
+    pub fn accept_delegation(ctx: Context<AcceptDelegation>) -> Result<()> {
+        let delegation = &mut ctx.accounts.delegation;
+        let delegate = ctx.accounts.delegate.key();
+
+        require!(
+            delegation.pending_delegate == Some(delegate),
+            MailServiceError::NoPendingDelegation
+        );
+
+        delegation.delegate = Some(delegate);
+        delegation.pending_delegate = None;
+
+        emit!(DelegationAccepted {
+            delegator: delegation.delegator,
+            delegate,
         });
 
         Ok(())
@@ -54,150 +326,1176 @@ pub mod mail_service {
 
     pub fn reject_delegation(ctx: Context<RejectDelegation>) -> Result<()> {
         let delegation = &mut ctx.accounts.delegation;
+        let rejector = Some(ctx.accounts.rejector.key());
         
-        // Verify the rejector is the current delegate
+        // Verify the rejector is the current or requested delegate
         require!(
-            delegation.delegate == Some(ctx.accounts.rejector.key()),
+            delegation.delegate == rejector || delegation.pending_delegate == rejector,
             MailServiceError::NoDelegationToReject
         );
 
         let delegator = delegation.delegator;
         
         // Clear the delegation
-        delegation.delegate = None;
+        if delegation.delegate == rejector {
+            delegation.delegate = None;
+        }
+        if delegation.pending_delegate == rejector {
+            delegation.pending_delegate = None;
+        }
+        unindex_delegation(
+            &ctx.accounts.delegator_record,
+            &ctx.accounts.previous_index,
+            ctx.accounts.delegation.key(),
+        )?;
 
         emit!(DelegationSet {
             delegator,
             delegate: None,
+            expires_at: None,
+            permissions: 0,
+        });
+
+        Ok(())
+    }
+
+    pub fn close_delegation(ctx: Context<CloseDelegation>) -> Result<()> {
+        emit!(DelegationClosed {
+            delegator: ctx.accounts.delegator.key(),
+        });
+
+        Ok(())
+    }
+
+    pub fn set_guardian(ctx: Context<SetFee>, guardian: Pubkey) -> Result<()> {
+        let service = &mut ctx.accounts.mail_service;
+        let old_guardian = service.guardian;
+        service.guardian = guardian;
+
+        emit!(GuardianUpdated {
+            old_guardian,
+            new_guardian: guardian,
+        });
+
+        Ok(())
+    }
+
+    pub fn pause(ctx: Context<Pause>) -> Result<()> {
+        ctx.accounts.mail_service.paused = true;
+
+        emit!(ServicePaused {
+            by: ctx.accounts.authority.key(),
+        });
+
+        Ok(())
+    }
+
+    /// Only the owner can resume; a guardian can stop the service but never restart it
+    pub fn unpause(ctx: Context<SetFee>) -> Result<()> {
+        ctx.accounts.mail_service.paused = false;
+
+        emit!(ServiceUnpaused {
+            by: ctx.accounts.owner.key(),
+        });
+
+        Ok(())
+    }
+
+    pub fn set_delegation_fee(ctx: Context<SetFee>, new_fee: u64) -> Result<()> {
+        let service = &mut ctx.accounts.mail_service;
+        let old_fee = service.delegation_fee;
+        service.delegation_fee = new_fee;
+
+        emit!(DelegationFeeUpdated {
+            old_fee,
+            new_fee,
+        });
+
+        Ok(())
+    }
+
+    pub fn register_handle(ctx: Context<RegisterHandle>, name: String) -> Result<()> {
+        validate_handle(&name)?;
+        let now = Clock::get()?.unix_timestamp;
+
+        // A lapsed handle is free for anyone to register again
+        let handle = &mut ctx.accounts.handle;
+        require!(
+            handle.owner == Pubkey::default() || now > handle.expires_at,
+            MailServiceError::HandleTaken
+        );
+
+        let fee = ctx.accounts.mail_service.registration_fee;
+        charge_fee(
+            &ctx.accounts.token_program,
+            &ctx.accounts.registrant_usdc_account,
+            &ctx.accounts.service_usdc_account,
+            &ctx.accounts.registrant,
+            &mut ctx.accounts.mail_service,
+            &mut ctx.accounts.fee_bucket,
+            fee,
+        )?;
+
+        handle.name = name.clone();
+        handle.owner = ctx.accounts.registrant.key();
+        handle.expires_at = now.checked_add(HANDLE_PERIOD).ok_or(MailServiceError::MathOverflow)?;
+        handle.bump = ctx.bumps.handle;
+
+        emit!(HandleRegistered {
+            name,
+            owner: handle.owner,
+            expires_at: handle.expires_at,
+        });
+
+        Ok(())
+    }
+
+    pub fn renew_handle(ctx: Context<RenewHandle>, name: String) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+
+        let fee = ctx.accounts.mail_service.registration_fee;
+        charge_fee(
+            &ctx.accounts.token_program,
+            &ctx.accounts.owner_usdc_account,
+            &ctx.accounts.service_usdc_account,
+            &ctx.accounts.owner,
+            &mut ctx.accounts.mail_service,
+            &mut ctx.accounts.fee_bucket,
+            fee,
+        )?;
+
+        // Renewing early adds a full period on top of the time left
+        let handle = &mut ctx.accounts.handle;
+        handle.expires_at = handle
+            .expires_at
+            .max(now)
+            .checked_add(HANDLE_PERIOD)
+            .ok_or(MailServiceError::MathOverflow)?;
+
+        emit!(HandleRenewed {
+            name,
+            owner: handle.owner,
+            expires_at: handle.expires_at,
+        });
+
+        Ok(())
+    }
+
+    pub fn transfer_handle(ctx: Context<TransferHandle>, name: String, new_owner: Pubkey) -> Result<()> {
+        let handle = &mut ctx.accounts.handle;
+        require!(
+            Clock::get()?.unix_timestamp <= handle.expires_at,
+            MailServiceError::HandleExpired
+        );
+
+        let old_owner = handle.owner;
+        handle.owner = new_owner;
+        clear_reverse_record(&ctx.accounts.reverse_record, &name)?;
+
+        emit!(HandleTransferred {
+            name,
+            old_owner,
+            new_owner,
+        });
+
+        Ok(())
+    }
+
+    pub fn set_primary_handle(ctx: Context<SetPrimaryHandle>, name: String) -> Result<()> {
+        require!(
+            Clock::get()?.unix_timestamp <= ctx.accounts.handle.expires_at,
+            MailServiceError::HandleExpired
+        );
+
+        let reverse_record = &mut ctx.accounts.reverse_record;
+        reverse_record.owner = ctx.accounts.owner.key();
+        reverse_record.name = name.clone();
+        reverse_record.bump = ctx.bumps.reverse_record;
+
+        emit!(PrimaryHandleSet {
+            owner: reverse_record.owner,
+            name,
+        });
+
+        Ok(())
+    }
+
+    pub fn claim_delegate_share(ctx: Context<ClaimDelegateShare>) -> Result<()> {
+        let claim = &mut ctx.accounts.delegate_claim;
+        require!(claim.amount > 0, MailServiceError::NoClaimableAmount);
+
+        let amount = claim.amount;
+        claim.amount = 0;
+
+        // Transfer USDC from service to the delegate
+        let bump = ctx.accounts.mail_service.bump;
+        let seeds = &[seeds::MAIL_SERVICE, &[bump]];
+        let signer_seeds = &[&seeds[..]];
+
+        let transfer_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.service_usdc_account.to_account_info(),
+                to: ctx.accounts.delegate_usdc_account.to_account_info(),
+                authority: ctx.accounts.mail_service.to_account_info(),
+            },
+            signer_seeds,
+        );
+        token::transfer(transfer_ctx, amount)?;
+
+        emit!(DelegateShareClaimed {
+            delegate: ctx.accounts.delegate.key(),
+            amount,
+        });
+
+        Ok(())
+    }
+
+    pub fn set_delegate_share(ctx: Context<SetFee>, new_share_bps: u16) -> Result<()> {
+        require!(
+            new_share_bps <= MAX_DELEGATE_SHARE_BPS,
+            MailServiceError::InvalidDelegateShare
+        );
+        let service = &mut ctx.accounts.mail_service;
+        let old_share_bps = service.delegate_share_bps;
+        service.delegate_share_bps = new_share_bps;
+
+        emit!(DelegateShareUpdated {
+            old_share_bps,
+            new_share_bps,
+        });
+
+        Ok(())
+    }
+
+    pub fn set_allow_program_delegates(ctx: Context<SetFee>, allowed: bool) -> Result<()> {
+        ctx.accounts.mail_service.allow_program_delegates = allowed;
+
+        emit!(ProgramDelegatesUpdated { allowed });
+
+        Ok(())
+    }
+
+    pub fn set_update_fee(ctx: Context<SetFee>, update_fee: u64, update_window: i64) -> Result<()> {
+        require!(update_window >= 0, MailServiceError::InvalidUpdateWindow);
+        let service = &mut ctx.accounts.mail_service;
+        service.update_fee = update_fee;
+        service.update_window = update_window;
+
+        emit!(UpdateFeeUpdated {
+            update_fee,
+            update_window,
+        });
+
+        Ok(())
+    }
+
+    pub fn set_registration_fee(ctx: Context<SetFee>, new_fee: u64) -> Result<()> {
+        let service = &mut ctx.accounts.mail_service;
+        let old_fee = service.registration_fee;
+        service.registration_fee = new_fee;
+
+        emit!(RegistrationFeeUpdated {
+            old_fee,
+            new_fee,
+        });
+
+        Ok(())
+    }
+
+    pub fn set_bucket_destination(
+        ctx: Context<SetBucketDestination>,
+        source: FeeSource,
+        destination: Pubkey,
+    ) -> Result<()> {
+        let bucket = &mut ctx.accounts.fee_bucket;
+        let old_destination = bucket.destination;
+        bucket.source = source;
+        bucket.destination = destination;
+        bucket.bump = ctx.bumps.fee_bucket;
+
+        emit!(BucketDestinationUpdated {
+            source,
+            old_destination,
+            new_destination: destination,
+        });
+
+        Ok(())
+    }
+
+    pub fn withdraw_bucket(ctx: Context<WithdrawBucket>) -> Result<()> {
+        let bucket = &mut ctx.accounts.fee_bucket;
+        require!(bucket.claimable > 0, MailServiceError::NoClaimableAmount);
+
+        let amount = bucket.claimable;
+        bucket.claimable = 0;
+        record_withdrawal(&mut ctx.accounts.mail_service, amount)?;
+
+        // Transfer USDC from service to the bucket's destination
+        let bump = ctx.accounts.mail_service.bump;
+        let seeds = &[seeds::MAIL_SERVICE, &[bump]];
+        let signer_seeds = &[&seeds[..]];
+
+        let transfer_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.service_usdc_account.to_account_info(),
+                to: ctx.accounts.destination_usdc_account.to_account_info(),
+                authority: ctx.accounts.mail_service.to_account_info(),
+            },
+            signer_seeds,
+        );
+        token::transfer(transfer_ctx, amount)?;
+
+        emit!(BucketWithdrawn {
+            source: ctx.accounts.fee_bucket.source,
+            destination: ctx.accounts.fee_bucket.destination,
+            amount,
         });
 
         Ok(())
     }
 
-    pub fn set_delegation_fee(ctx: Context<SetFee>, new_fee: u64) -> Result<()> {
-        let service = &mut ctx.accounts.mail_service;
-        let old_fee = service.delegation_fee;
-        service.delegation_fee = new_fee;
+    pub fn withdraw_fees(ctx: Context<WithdrawFees>, amount: u64) -> Result<()> {
+        // Legacy withdrawal draws on delegation fees; handle fees are withdrawn with withdraw_bucket
+        require!(
+            amount <= ctx.accounts.fee_bucket.claimable,
+            MailServiceError::WithdrawalExceedsAccrued
+        );
+        pay_owner(ctx.accounts, amount)
+    }
+
+    pub fn withdraw_all_fees(ctx: Context<WithdrawFees>) -> Result<()> {
+        let amount = ctx.accounts.fee_bucket.claimable;
+        require!(amount > 0, MailServiceError::NoClaimableAmount);
+        pay_owner(ctx.accounts, amount)
+    }
+}
+
+/// Debit `amount` from the delegation bucket and transfer it to the owner
+fn pay_owner(accounts: &mut WithdrawFees, amount: u64) -> Result<()> {
+    let bucket = &mut accounts.fee_bucket;
+    bucket.claimable -= amount;
+    record_withdrawal(&mut accounts.mail_service, amount)?;
+
+    // Transfer USDC from service to owner
+    let bump = accounts.mail_service.bump;
+    let seeds = &[seeds::MAIL_SERVICE, &[bump]];
+    let signer_seeds = &[&seeds[..]];
+
+    let transfer_ctx = CpiContext::new_with_signer(
+        accounts.token_program.to_account_info(),
+        Transfer {
+            from: accounts.service_usdc_account.to_account_info(),
+            to: accounts.owner_usdc_account.to_account_info(),
+            authority: accounts.mail_service.to_account_info(),
+        },
+        signer_seeds,
+    );
+    token::transfer(transfer_ctx, amount)?;
+
+    emit!(FeesWithdrawn {
+        amount,
+        fees_withdrawn: accounts.mail_service.fees_withdrawn,
+    });
+
+    Ok(())
+}
+
+/// Add `amount` to the service's running total of withdrawn fees
+fn record_withdrawal(mail_service: &mut Account<MailServiceState>, amount: u64) -> Result<()> {
+    mail_service.fees_withdrawn = mail_service
+        .fees_withdrawn
+        .checked_add(amount)
+        .ok_or(MailServiceError::MathOverflow)?;
+    Ok(())
+}
+
+/// Move a fee from `payer` into the service account and credit `fee_bucket`
+fn charge_fee<'info>(
+    token_program: &Program<'info, Token>,
+    from: &Account<'info, TokenAccount>,
+    to: &Account<'info, TokenAccount>,
+    payer: &Signer<'info>,
+    mail_service: &mut Account<'info, MailServiceState>,
+    fee_bucket: &mut Account<'info, FeeBucket>,
+    fee: u64,
+) -> Result<()> {
+    if fee == 0 {
+        return Ok(());
+    }
+    let transfer_ctx = CpiContext::new(
+        token_program.to_account_info(),
+        Transfer {
+            from: from.to_account_info(),
+            to: to.to_account_info(),
+            authority: payer.to_account_info(),
+        },
+    );
+    token::transfer(transfer_ctx, fee)?;
+    fee_bucket.claimable = fee_bucket
+        .claimable
+        .checked_add(fee)
+        .ok_or(MailServiceError::MathOverflow)?;
+    mail_service.fees_collected = mail_service
+        .fees_collected
+        .checked_add(fee)
+        .ok_or(MailServiceError::MathOverflow)?;
+    Ok(())
+}
+
+/// Carve the delegate's share of a delegation fee out of the delegation bucket
+/// into the delegate's `DelegateClaim`, creating the claim if needed (see `create_pda`)
+fn share_with_delegate<'info>(
+    mail_service: &mut Account<'info, MailServiceState>,
+    fee_bucket: &mut Account<'info, FeeBucket>,
+    delegate_claim: &Option<UncheckedAccount<'info>>,
+    delegate: Pubkey,
+    fee: u64,
+    payer: &Signer<'info>,
+    system_program: &Program<'info, System>,
+) -> Result<()> {
+    let share = delegate_share(fee, mail_service.delegate_share_bps)?;
+    if share == 0 {
+        return Ok(());
+    }
+
+    let claim = delegate_claim
+        .as_ref()
+        .ok_or(MailServiceError::InvalidDelegateClaim)?;
+    let (expected, bump) =
+        Pubkey::find_program_address(&[b"delegate_claim", delegate.as_ref()], &crate::ID);
+    require_keys_eq!(expected, claim.key(), MailServiceError::InvalidDelegateClaim);
+    if claim.data_is_empty() {
+        create_pda(
+            claim,
+            payer,
+            system_program,
+            &[b"delegate_claim", delegate.as_ref(), &[bump]],
+            8 + DelegateClaim::INIT_SPACE,
+        )?;
+        store(claim, &DelegateClaim { delegate, amount: 0, bump })?;
+    }
+
+    let mut entry = load::<DelegateClaim>(claim)?;
+    entry.amount = entry.amount.checked_add(share).ok_or(MailServiceError::MathOverflow)?;
+    store(claim, &entry)?;
+
+    fee_bucket.claimable = fee_bucket
+        .claimable
+        .checked_sub(share)
+        .ok_or(MailServiceError::MathOverflow)?;
+    // The delegate's share is never the service's to withdraw
+    mail_service.fees_collected = mail_service
+        .fees_collected
+        .checked_sub(share)
+        .ok_or(MailServiceError::MathOverflow)?;
+    Ok(())
+}
+
+/// Blank `reverse_record` if it still points at `name`, so a transferred handle
+/// doesn't keep resolving back to its previous owner
+fn clear_reverse_record(reverse_record: &UncheckedAccount, name: &str) -> Result<()> {
+    if reverse_record.owner != &crate::ID || reverse_record.data_is_empty() {
+        return Ok(());
+    }
+    let mut record = ReverseRecord::try_deserialize(&mut &reverse_record.try_borrow_data()?[..])?;
+    if record.name != name {
+        return Ok(());
+    }
+    record.name.clear();
+    record.try_serialize(&mut &mut reverse_record.try_borrow_mut_data()?[..])?;
+    Ok(())
+}
+
+/// Create the program-owned PDA `account` at `seeds`, funded by `payer`
+///
+/// Anyone can send lamports to a PDA before it exists, which would make a
+/// plain `create_account` fail for good; an address that already holds
+/// lamports is topped up to rent exemption, allocated and assigned instead.
+fn create_pda<'info>(
+    account: &UncheckedAccount<'info>,
+    payer: &Signer<'info>,
+    system_program: &Program<'info, System>,
+    seeds: &[&[u8]],
+    space: usize,
+) -> Result<()> {
+    let rent = Rent::get()?.minimum_balance(space);
+    let funded = account.lamports();
+    if funded == 0 {
+        return system_program::create_account(
+            CpiContext::new_with_signer(
+                system_program.to_account_info(),
+                CreateAccount {
+                    from: payer.to_account_info(),
+                    to: account.to_account_info(),
+                },
+                &[seeds],
+            ),
+            rent,
+            space as u64,
+            &crate::ID,
+        );
+    }
+
+    let shortfall = rent.saturating_sub(funded);
+    if shortfall > 0 {
+        system_program::transfer(
+            CpiContext::new(
+                system_program.to_account_info(),
+                system_program::Transfer {
+                    from: payer.to_account_info(),
+                    to: account.to_account_info(),
+                },
+            ),
+            shortfall,
+        )?;
+    }
+    system_program::allocate(
+        CpiContext::new_with_signer(
+            system_program.to_account_info(),
+            Allocate {
+                account_to_allocate: account.to_account_info(),
+            },
+            &[seeds],
+        ),
+        space as u64,
+    )?;
+    system_program::assign(
+        CpiContext::new_with_signer(
+            system_program.to_account_info(),
+            Assign {
+                account_to_assign: account.to_account_info(),
+            },
+            &[seeds],
+        ),
+        &crate::ID,
+    )
+}
+
+fn load<T: AccountDeserialize>(account: &UncheckedAccount) -> Result<T> {
+    let data = account.try_borrow_data()?;
+    T::try_deserialize(&mut &data[..])
+}
+
+fn store<T: AccountSerialize>(account: &UncheckedAccount, value: &T) -> Result<()> {
+    let mut data = account.try_borrow_mut_data()?;
+    let mut writer: &mut [u8] = &mut data[..];
+    value.try_serialize(&mut writer)
+}
+
+/// Verify `index` is `[b"delegate_index", delegate]`, returning its bump
+fn check_delegate_index(index: &UncheckedAccount, delegate: &Pubkey) -> Result<u8> {
+    let (expected, bump) =
+        Pubkey::find_program_address(&[b"delegate_index", delegate.as_ref()], &crate::ID);
+    require_keys_eq!(expected, index.key(), MailServiceError::InvalidDelegateIndex);
+    Ok(bump)
+}
+
+/// Verify `record` is `[b"delegator_record", delegation]`, returning its bump
+fn check_delegator_record(record: &UncheckedAccount, delegation: &Pubkey) -> Result<u8> {
+    let (expected, bump) =
+        Pubkey::find_program_address(&[b"delegator_record", delegation.as_ref()], &crate::ID);
+    require_keys_eq!(expected, record.key(), MailServiceError::InvalidDelegateIndex);
+    Ok(bump)
+}
+
+/// Point `delegation`'s `DelegatorRecord` at `delegate` and count it in the delegate's index
+fn index_delegation<'info>(
+    record: &UncheckedAccount<'info>,
+    delegate_index: &Option<UncheckedAccount<'info>>,
+    delegation: Pubkey,
+    delegator: Pubkey,
+    delegate: Pubkey,
+    payer: &Signer<'info>,
+    system_program: &Program<'info, System>,
+) -> Result<()> {
+    let index = delegate_index
+        .as_ref()
+        .ok_or(MailServiceError::InvalidDelegateIndex)?;
+    let index_bump = check_delegate_index(index, &delegate)?;
+    if index.data_is_empty() {
+        create_pda(
+            index,
+            payer,
+            system_program,
+            &[b"delegate_index", delegate.as_ref(), &[index_bump]],
+            8 + DelegateIndex::INIT_SPACE,
+        )?;
+        store(index, &DelegateIndex { delegate, count: 0, bump: index_bump })?;
+    }
+    let mut entry = load::<DelegateIndex>(index)?;
+    entry.count = entry.count.checked_add(1).ok_or(MailServiceError::MathOverflow)?;
+    store(index, &entry)?;
+
+    let record_bump = check_delegator_record(record, &delegation)?;
+    if record.data_is_empty() {
+        create_pda(
+            record,
+            payer,
+            system_program,
+            &[b"delegator_record", delegation.as_ref(), &[record_bump]],
+            8 + DelegatorRecord::INIT_SPACE,
+        )?;
+    }
+    store(
+        record,
+        &DelegatorRecord {
+            delegate,
+            delegator,
+            delegation,
+            bump: record_bump,
+        },
+    )
+}
+
+/// Blank `record` and uncount it from its delegate's index, if it points at a delegate
+///
+/// Delegations set before the index existed have no record and are skipped.
+fn unindex_delegation(
+    record: &UncheckedAccount,
+    previous_index: &Option<UncheckedAccount>,
+    delegation: Pubkey,
+) -> Result<()> {
+    check_delegator_record(record, &delegation)?;
+    if record.owner != &crate::ID || record.data_is_empty() {
+        return Ok(());
+    }
+    let mut entry = load::<DelegatorRecord>(record)?;
+    if entry.delegate == Pubkey::default() {
+        return Ok(());
+    }
+
+    let index = previous_index
+        .as_ref()
+        .ok_or(MailServiceError::InvalidDelegateIndex)?;
+    check_delegate_index(index, &entry.delegate)?;
+    let mut counted = load::<DelegateIndex>(index)?;
+    counted.count = counted.count.saturating_sub(1);
+    store(index, &counted)?;
+
+    entry.delegate = Pubkey::default();
+    store(record, &entry)
+}
+
+#[derive(Accounts)]
+pub struct Initialize<'info> {
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + MailServiceState::INIT_SPACE,
+        seeds = [seeds::MAIL_SERVICE],
+        bump
+    )]
+    pub mail_service: Account<'info, MailServiceState>,
+
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + FeeBucket::INIT_SPACE,
+        seeds = [b"bucket", FeeSource::Delegation.seed()],
+        bump
+    )]
+    pub delegation_bucket: Account<'info, FeeBucket>,
+
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + FeeBucket::INIT_SPACE,
+        seeds = [b"bucket", FeeSource::Handle.seed()],
+        bump
+    )]
+    pub handle_bucket: Account<'info, FeeBucket>,
+    
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct DelegateTo<'info> {
+    #[account(
+        init_if_needed,
+        payer = delegator,
+        space = 8 + Delegation::INIT_SPACE,
+        seeds = [b"delegation", delegator.key().as_ref()],
+        bump
+    )]
+    pub delegation: Account<'info, Delegation>,
+
+    /// CHECK: `[b"delegator_record", delegation]`; verified and written by the handler
+    #[account(mut)]
+    pub delegator_record: UncheckedAccount<'info>,
+
+    /// CHECK: `DelegateIndex` of the delegate the record currently points at, verified by the
+    /// handler. Omit when the delegation has no indexed delegate
+    #[account(mut)]
+    pub previous_index: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: `DelegateIndex` of the new delegate, created if needed and verified by the
+    /// handler. Omit when clearing the delegation
+    #[account(mut)]
+    pub delegate_index: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: `[b"delegate_claim", delegate]`, credited with the delegate's share of the fee;
+    /// created if needed and verified by the handler. Omit while the delegate share is zero
+    #[account(mut)]
+    pub delegate_claim: Option<UncheckedAccount<'info>>,
+    
+    #[account(mut, seeds = [seeds::MAIL_SERVICE], bump = mail_service.bump)]
+    pub mail_service: Account<'info, MailServiceState>,
+
+    #[account(
+        mut,
+        seeds = [b"bucket", FeeSource::Delegation.seed()],
+        bump = fee_bucket.bump
+    )]
+    pub fee_bucket: Account<'info, FeeBucket>,
+    
+    #[account(mut)]
+    pub delegator: Signer<'info>,
+    
+    #[account(
+        mut,
+        associated_token::mint = mail_service.usdc_mint,
+        associated_token::authority = delegator
+    )]
+    pub delegator_usdc_account: Account<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        associated_token::mint = mail_service.usdc_mint,
+        associated_token::authority = mail_service
+    )]
+    pub service_usdc_account: Account<'info, TokenAccount>,
+    
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(delegate: Pubkey)]
+pub struct AddDelegate<'info> {
+    #[account(
+        init,
+        payer = delegator,
+        space = 8 + Delegation::INIT_SPACE,
+        seeds = [b"delegation", delegator.key().as_ref(), delegate.as_ref()],
+        bump
+    )]
+    pub delegation: Account<'info, Delegation>,
+
+    #[account(
+        init_if_needed,
+        payer = delegator,
+        space = 8 + Delegates::INIT_SPACE,
+        seeds = [b"delegates", delegator.key().as_ref()],
+        bump
+    )]
+    pub delegates: Account<'info, Delegates>,
+
+    /// CHECK: `[b"delegator_record", delegation]`; created and written by the handler
+    #[account(mut)]
+    pub delegator_record: UncheckedAccount<'info>,
+
+    /// CHECK: `DelegateIndex` of the delegate, created if needed and verified by the handler
+    #[account(mut)]
+    pub delegate_index: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: `[b"delegate_claim", delegate]`, credited with the delegate's share of the fee;
+    /// created if needed and verified by the handler. Omit while the delegate share is zero
+    #[account(mut)]
+    pub delegate_claim: Option<UncheckedAccount<'info>>,
+
+    #[account(mut, seeds = [seeds::MAIL_SERVICE], bump = mail_service.bump)]
+    pub mail_service: Account<'info, MailServiceState>,
+
+    #[account(
+        mut,
+        seeds = [b"bucket", FeeSource::Delegation.seed()],
+        bump = fee_bucket.bump
+    )]
+    pub fee_bucket: Account<'info, FeeBucket>,
+
+    #[account(mut)]
+    pub delegator: Signer<'info>,
+
+    #[account(
+        mut,
+        associated_token::mint = mail_service.usdc_mint,
+        associated_token::authority = delegator
+    )]
+    pub delegator_usdc_account: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        associated_token::mint = mail_service.usdc_mint,
+        associated_token::authority = mail_service
+    )]
+    pub service_usdc_account: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(delegate: Pubkey)]
+pub struct RemoveDelegate<'info> {
+    #[account(
+        mut,
+        close = delegator,
+        seeds = [b"delegation", delegator.key().as_ref(), delegate.as_ref()],
+        bump = delegation.bump
+    )]
+    pub delegation: Account<'info, Delegation>,
+
+    #[account(
+        mut,
+        seeds = [b"delegates", delegator.key().as_ref()],
+        bump = delegates.bump
+    )]
+    pub delegates: Account<'info, Delegates>,
+
+    /// CHECK: `[b"delegator_record", delegation]`; verified and written by the handler
+    #[account(mut)]
+    pub delegator_record: UncheckedAccount<'info>,
+
+    /// CHECK: `DelegateIndex` of the delegate the record currently points at, verified by the
+    /// handler. Omit when the delegation has no indexed delegate
+    #[account(mut)]
+    pub previous_index: Option<UncheckedAccount<'info>>,
+
+    #[account(mut)]
+    pub delegator: Signer<'info>,
+}
+
+/// Accepting and rejecting work on both the delegator's own delegation and its
+/// per-delegate ones, so the account is identified by type and owner alone
+#[derive(Accounts)]
+pub struct AcceptDelegation<'info> {
+    #[account(
+        mut,
+        has_one = delegator @ MailServiceError::InvalidDelegator
+    )]
+    pub delegation: Account<'info, Delegation>,
+
+    /// CHECK: This is the original delegator, validated by the delegation account
+    pub delegator: UncheckedAccount<'info>,
+
+    pub delegate: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RejectDelegation<'info> {
+    #[account(
+        mut,
+        has_one = delegator @ MailServiceError::InvalidDelegator
+    )]
+    pub delegation: Account<'info, Delegation>,
+    
+    /// CHECK: This is the original delegator, validated by the delegation account
+    pub delegator: UncheckedAccount<'info>,
+    
+    pub rejector: Signer<'info>,
+
+    /// CHECK: `[b"delegator_record", delegation]`; verified and written by the handler
+    #[account(mut)]
+    pub delegator_record: UncheckedAccount<'info>,
+
+    /// CHECK: `DelegateIndex` of the delegate the record currently points at, verified by the
+    /// handler. Omit when the delegation has no indexed delegate
+    #[account(mut)]
+    pub previous_index: Option<UncheckedAccount<'info>>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimDelegateShare<'info> {
+    #[account(
+        mut,
+        seeds = [b"delegate_claim", delegate.key().as_ref()],
+        bump = delegate_claim.bump
+    )]
+    pub delegate_claim: Account<'info, DelegateClaim>,
+
+    #[account(seeds = [seeds::MAIL_SERVICE], bump = mail_service.bump)]
+    pub mail_service: Account<'info, MailServiceState>,
+
+    pub delegate: Signer<'info>,
+
+    #[account(
+        mut,
+        associated_token::mint = mail_service.usdc_mint,
+        associated_token::authority = delegate
+    )]
+    pub delegate_usdc_account: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        associated_token::mint = mail_service.usdc_mint,
+        associated_token::authority = mail_service
+    )]
+    pub service_usdc_account: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct CloseDelegation<'info> {
+    /// Only a cleared delegation, with no active or pending delegate, can be closed
+    #[account(
+        mut,
+        close = delegator,
+        seeds = [b"delegation", delegator.key().as_ref()],
+        bump = delegation.bump,
+        constraint = delegation.delegate.is_none() && delegation.pending_delegate.is_none()
+            @ MailServiceError::DelegationNotCleared
+    )]
+    pub delegation: Account<'info, Delegation>,
+
+    /// The delegation's blanked reverse-index record, closed along with it if it exists
+    #[account(
+        mut,
+        close = delegator,
+        seeds = [b"delegator_record", delegation.key().as_ref()],
+        bump = delegator_record.bump
+    )]
+    pub delegator_record: Option<Account<'info, DelegatorRecord>>,
+
+    #[account(mut)]
+    pub delegator: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(name: String)]
+pub struct RegisterHandle<'info> {
+    #[account(
+        init_if_needed,
+        payer = registrant,
+        space = 8 + Handle::INIT_SPACE,
+        seeds = [b"handle", handle_hash(&name).as_ref()],
+        bump
+    )]
+    pub handle: Account<'info, Handle>,
+
+    #[account(mut, seeds = [seeds::MAIL_SERVICE], bump = mail_service.bump)]
+    pub mail_service: Account<'info, MailServiceState>,
+
+    #[account(
+        mut,
+        seeds = [b"bucket", FeeSource::Handle.seed()],
+        bump = fee_bucket.bump
+    )]
+    pub fee_bucket: Account<'info, FeeBucket>,
+
+    #[account(mut)]
+    pub registrant: Signer<'info>,
+
+    #[account(
+        mut,
+        associated_token::mint = mail_service.usdc_mint,
+        associated_token::authority = registrant
+    )]
+    pub registrant_usdc_account: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        associated_token::mint = mail_service.usdc_mint,
+        associated_token::authority = mail_service
+    )]
+    pub service_usdc_account: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(name: String)]
+pub struct RenewHandle<'info> {
+    #[account(
+        mut,
+        seeds = [b"handle", handle_hash(&name).as_ref()],
+        bump = handle.bump,
+        has_one = owner @ MailServiceError::NotHandleOwner
+    )]
+    pub handle: Account<'info, Handle>,
+
+    #[account(mut, seeds = [seeds::MAIL_SERVICE], bump = mail_service.bump)]
+    pub mail_service: Account<'info, MailServiceState>,
+
+    #[account(
+        mut,
+        seeds = [b"bucket", FeeSource::Handle.seed()],
+        bump = fee_bucket.bump
+    )]
+    pub fee_bucket: Account<'info, FeeBucket>,
+
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        associated_token::mint = mail_service.usdc_mint,
+        associated_token::authority = owner
+    )]
+    pub owner_usdc_account: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        associated_token::mint = mail_service.usdc_mint,
+        associated_token::authority = mail_service
+    )]
+    pub service_usdc_account: Account<'info, TokenAccount>,
 
-        emit!(DelegationFeeUpdated {
-            old_fee,
-            new_fee,
-        });
+    pub token_program: Program<'info, Token>,
+}
 
-        Ok(())
-    }
+#[derive(Accounts)]
+#[instruction(name: String)]
+pub struct TransferHandle<'info> {
+    #[account(
+        mut,
+        seeds = [b"handle", handle_hash(&name).as_ref()],
+        bump = handle.bump,
+        has_one = owner @ MailServiceError::NotHandleOwner
+    )]
+    pub handle: Account<'info, Handle>,
 
-    pub fn withdraw_fees(ctx: Context<WithdrawFees>, amount: u64) -> Result<()> {
-        // Transfer USDC from service to owner
-        let bump = ctx.accounts.mail_service.bump;
-        let seeds = &[b"mail_service".as_ref(), &[bump]];
-        let signer_seeds = &[&seeds[..]];
-        
-        let transfer_ctx = CpiContext::new_with_signer(
-            ctx.accounts.token_program.to_account_info(),
-            Transfer {
-                from: ctx.accounts.service_usdc_account.to_account_info(),
-                to: ctx.accounts.owner_usdc_account.to_account_info(),
-                authority: ctx.accounts.mail_service.to_account_info(),
-            },
-            signer_seeds,
-        );
-        token::transfer(transfer_ctx, amount)?;
+    /// CHECK: the owner's reverse record, which may not exist; parsed and cleared by the handler
+    #[account(mut, seeds = [b"reverse", owner.key().as_ref()], bump)]
+    pub reverse_record: UncheckedAccount<'info>,
 
-        Ok(())
-    }
+    pub owner: Signer<'info>,
 }
 
 #[derive(Accounts)]
-pub struct Initialize<'info> {
+#[instruction(name: String)]
+pub struct SetPrimaryHandle<'info> {
     #[account(
-        init,
+        seeds = [b"handle", handle_hash(&name).as_ref()],
+        bump = handle.bump,
+        has_one = owner @ MailServiceError::NotHandleOwner
+    )]
+    pub handle: Account<'info, Handle>,
+
+    #[account(
+        init_if_needed,
         payer = owner,
-        space = 8 + MailServiceState::INIT_SPACE,
-        seeds = [b"mail_service"],
+        space = 8 + ReverseRecord::INIT_SPACE,
+        seeds = [b"reverse", owner.key().as_ref()],
         bump
     )]
-    pub mail_service: Account<'info, MailServiceState>,
-    
+    pub reverse_record: Account<'info, ReverseRecord>,
+
     #[account(mut)]
     pub owner: Signer<'info>,
-    
     pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct DelegateTo<'info> {
+pub struct SetFee<'info> {
     #[account(
-        init_if_needed,
-        payer = delegator,
-        space = 8 + Delegation::INIT_SPACE,
-        seeds = [b"delegation", delegator.key().as_ref()],
-        bump
+        mut,
+        seeds = [seeds::MAIL_SERVICE],
+        bump = mail_service.bump,
+        has_one = owner @ MailServiceError::OnlyOwner
     )]
-    pub delegation: Account<'info, Delegation>,
-    
-    #[account(seeds = [b"mail_service"], bump = mail_service.bump)]
     pub mail_service: Account<'info, MailServiceState>,
     
-    #[account(mut)]
-    pub delegator: Signer<'info>,
-    
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(source: FeeSource)]
+pub struct SetBucketDestination<'info> {
     #[account(
-        mut,
-        associated_token::mint = mail_service.usdc_mint,
-        associated_token::authority = delegator
+        init_if_needed,
+        payer = owner,
+        space = 8 + FeeBucket::INIT_SPACE,
+        seeds = [b"bucket", source.seed()],
+        bump
     )]
-    pub delegator_usdc_account: Account<'info, TokenAccount>,
-    
+    pub fee_bucket: Account<'info, FeeBucket>,
+
     #[account(
-        mut,
-        associated_token::mint = mail_service.usdc_mint,
-        associated_token::authority = mail_service
+        seeds = [seeds::MAIL_SERVICE],
+        bump = mail_service.bump,
+        has_one = owner @ MailServiceError::OnlyOwner
     )]
-    pub service_usdc_account: Account<'info, TokenAccount>,
-    
-    pub token_program: Program<'info, Token>,
-    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub mail_service: Account<'info, MailServiceState>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
     pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct RejectDelegation<'info> {
+pub struct Pause<'info> {
     #[account(
         mut,
-        seeds = [b"delegation", delegation.delegator.as_ref()],
-        bump = delegation.bump,
-        has_one = delegator @ MailServiceError::InvalidDelegator
+        seeds = [seeds::MAIL_SERVICE],
+        bump = mail_service.bump,
+        constraint = authority.key() == mail_service.owner
+            || authority.key() == mail_service.guardian @ MailServiceError::OnlyGuardian
     )]
-    pub delegation: Account<'info, Delegation>,
-    
-    /// CHECK: This is the original delegator, validated by the delegation account
-    pub delegator: UncheckedAccount<'info>,
-    
-    pub rejector: Signer<'info>,
+    pub mail_service: Account<'info, MailServiceState>,
+
+    pub authority: Signer<'info>,
 }
 
 #[derive(Accounts)]
-pub struct SetFee<'info> {
+pub struct WithdrawBucket<'info> {
+    #[account(
+        mut,
+        seeds = [b"bucket", fee_bucket.source.seed()],
+        bump = fee_bucket.bump
+    )]
+    pub fee_bucket: Account<'info, FeeBucket>,
+
     #[account(
         mut,
-        seeds = [b"mail_service"],
+        seeds = [seeds::MAIL_SERVICE],
         bump = mail_service.bump,
         has_one = owner @ MailServiceError::OnlyOwner
     )]
     pub mail_service: Account<'info, MailServiceState>,
-    
+
     pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        associated_token::mint = mail_service.usdc_mint,
+        associated_token::authority = mail_service
+    )]
+    pub service_usdc_account: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        associated_token::mint = mail_service.usdc_mint,
+        associated_token::authority = fee_bucket.destination
+    )]
+    pub destination_usdc_account: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
 }
 
 #[derive(Accounts)]
 pub struct WithdrawFees<'info> {
     #[account(
-        seeds = [b"mail_service"],
+        mut,
+        seeds = [seeds::MAIL_SERVICE],
         bump = mail_service.bump,
         has_one = owner @ MailServiceError::OnlyOwner
     )]
     pub mail_service: Account<'info, MailServiceState>,
+
+    #[account(
+        mut,
+        seeds = [b"bucket", FeeSource::Delegation.seed()],
+        bump = fee_bucket.bump
+    )]
+    pub fee_bucket: Account<'info, FeeBucket>,
     
     #[account(mut)]
     pub owner: Signer<'info>,
@@ -226,20 +1524,186 @@ pub struct MailServiceState {
     pub usdc_mint: Pubkey,
     pub delegation_fee: u64,
     pub bump: u8,
+    /// Fee to register or renew a handle for one `HANDLE_PERIOD`
+    pub registration_fee: u64,
+    /// Reduced fee for replacing a delegate within `update_window` seconds of the last paid change
+    pub update_fee: u64,
+    pub update_window: i64,
+    /// Share of each delegation fee, in basis points, that accrues to the delegate
+    pub delegate_share_bps: u16,
+    /// Whether program-derived addresses may be delegates, e.g. programs sending by CPI
+    pub allow_program_delegates: bool,
+    /// Running total of fees credited to the service, net of delegate shares
+    pub fees_collected: u64,
+    /// Running total of fees paid out by `withdraw_fees`, `withdraw_all_fees` and `withdraw_bucket`
+    pub fees_withdrawn: u64,
+    /// Key that can pause the service alongside the owner, but not unpause or withdraw
+    pub guardian: Pubkey,
+    /// While set, no new delegations can be made
+    pub paused: bool,
 }
 
+/// A delegator's delegation, either its own at `[b"delegation", delegator]` or
+/// one of several per-delegate ones at `[b"delegation", delegator, delegate]`
 #[account]
 #[derive(InitSpace)]
 pub struct Delegation {
     pub delegator: Pubkey,
+    /// Active delegate, set once the requested delegate accepts
     pub delegate: Option<Pubkey>,
     pub bump: u8,
+    /// Delegate requested by `delegate_to` and awaiting `accept_delegation`
+    pub pending_delegate: Option<Pubkey>,
+    /// After this time the delegation counts as cleared; `None` never expires
+    pub expires_at: Option<i64>,
+    /// `PERMISSION_*` flags granted to the delegate
+    pub permissions: u32,
+    /// When a delegate was last set for a fee; starts the reduced-fee update window
+    pub updated_at: i64,
+}
+
+impl Delegation {
+    /// The accepted delegate, or `None` if there is none or the delegation has expired
+    pub fn active_delegate(&self, now: i64) -> Option<Pubkey> {
+        match self.expires_at {
+            Some(expires_at) if now > expires_at => None,
+            _ => self.delegate,
+        }
+    }
+
+    /// Whether `delegate` is the active delegate and was granted every flag in `permission`
+    pub fn allows(&self, delegate: Pubkey, permission: u32, now: i64) -> bool {
+        self.active_delegate(now) == Some(delegate) && self.permissions & permission == permission
+    }
+}
+
+/// Number of per-delegate delegations a delegator has open, at `[b"delegates", delegator]`
+#[account]
+#[derive(InitSpace)]
+pub struct Delegates {
+    pub delegator: Pubkey,
+    pub count: u32,
+    pub bump: u8,
+}
+
+/// Delegation fee revenue owed to a delegate, at `[b"delegate_claim", delegate]`
+#[account]
+#[derive(InitSpace)]
+pub struct DelegateClaim {
+    pub delegate: Pubkey,
+    pub amount: u64,
+    pub bump: u8,
+}
+
+/// How many delegations currently name `delegate`, at `[b"delegate_index", delegate]`
+#[account]
+#[derive(InitSpace)]
+pub struct DelegateIndex {
+    pub delegate: Pubkey,
+    pub count: u32,
+    pub bump: u8,
+}
+
+/// Marker linking a delegation to its requested or active delegate, at
+/// `[b"delegator_record", delegation]`
+///
+/// `delegate` sits at a fixed offset (8) so a delegate can list its delegators
+/// with a `getProgramAccounts` memcmp filter. Blanked to the default key when the
+/// delegation is cleared, rejected or removed.
+#[account]
+#[derive(InitSpace)]
+pub struct DelegatorRecord {
+    pub delegate: Pubkey,
+    pub delegator: Pubkey,
+    pub delegation: Pubkey,
+    pub bump: u8,
 }
 
+/// A human-readable name mapped to a wallet, at `[b"handle", handle_hash(name)]`
+#[account]
+#[derive(InitSpace)]
+pub struct Handle {
+    #[max_len(32)]
+    pub name: String,
+    pub owner: Pubkey,
+    /// After this time the handle stops resolving and anyone may register it
+    pub expires_at: i64,
+    pub bump: u8,
+}
+
+/// A wallet's primary handle, at `[b"reverse", owner]`
+///
+/// Resolvers must confirm the named handle is still owned by `owner` and unexpired:
+/// the record is only cleared on transfer, not when a lapsed handle is re-registered.
+#[account]
+#[derive(InitSpace)]
+pub struct ReverseRecord {
+    pub owner: Pubkey,
+    /// Empty once the handle has been transferred away
+    #[max_len(32)]
+    pub name: String,
+    pub bump: u8,
+}
+
+/// Revenue stream a service fee was collected from
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq, InitSpace)]
+pub enum FeeSource {
+    Delegation,
+    Handle,
+}
+
+impl FeeSource {
+    pub fn seed(&self) -> &'static [u8] {
+        match self {
+            FeeSource::Delegation => b"delegation",
+            FeeSource::Handle => b"handle",
+        }
+    }
+}
+
+/// Fees accrued from one source, withdrawn independently to `destination`
+#[account]
+#[derive(InitSpace)]
+pub struct FeeBucket {
+    pub source: FeeSource,
+    pub destination: Pubkey,
+    pub claimable: u64,
+    pub bump: u8,
+}
+
+/// Emitted when a delegation is requested, cleared or rejected; a requested
+/// `delegate` stays pending until `DelegationAccepted`
 #[event]
 pub struct DelegationSet {
     pub delegator: Pubkey,
     pub delegate: Option<Pubkey>,
+    pub expires_at: Option<i64>,
+    pub permissions: u32,
+}
+
+#[event]
+pub struct DelegateAdded {
+    pub delegator: Pubkey,
+    pub delegate: Pubkey,
+    pub expires_at: Option<i64>,
+    pub permissions: u32,
+}
+
+#[event]
+pub struct DelegateRemoved {
+    pub delegator: Pubkey,
+    pub delegate: Pubkey,
+}
+
+#[event]
+pub struct DelegationClosed {
+    pub delegator: Pubkey,
+}
+
+#[event]
+pub struct DelegationAccepted {
+    pub delegator: Pubkey,
+    pub delegate: Pubkey,
 }
 
 #[event]
@@ -248,6 +1712,99 @@ pub struct DelegationFeeUpdated {
     pub new_fee: u64,
 }
 
+#[event]
+pub struct DelegateShareUpdated {
+    pub old_share_bps: u16,
+    pub new_share_bps: u16,
+}
+
+#[event]
+pub struct DelegateShareClaimed {
+    pub delegate: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct ProgramDelegatesUpdated {
+    pub allowed: bool,
+}
+
+#[event]
+pub struct UpdateFeeUpdated {
+    pub update_fee: u64,
+    pub update_window: i64,
+}
+
+#[event]
+pub struct GuardianUpdated {
+    pub old_guardian: Pubkey,
+    pub new_guardian: Pubkey,
+}
+
+#[event]
+pub struct ServicePaused {
+    pub by: Pubkey,
+}
+
+#[event]
+pub struct ServiceUnpaused {
+    pub by: Pubkey,
+}
+
+#[event]
+pub struct RegistrationFeeUpdated {
+    pub old_fee: u64,
+    pub new_fee: u64,
+}
+
+#[event]
+pub struct HandleRegistered {
+    pub name: String,
+    pub owner: Pubkey,
+    pub expires_at: i64,
+}
+
+#[event]
+pub struct HandleRenewed {
+    pub name: String,
+    pub owner: Pubkey,
+    pub expires_at: i64,
+}
+
+#[event]
+pub struct HandleTransferred {
+    pub name: String,
+    pub old_owner: Pubkey,
+    pub new_owner: Pubkey,
+}
+
+#[event]
+pub struct PrimaryHandleSet {
+    pub owner: Pubkey,
+    pub name: String,
+}
+
+#[event]
+pub struct BucketDestinationUpdated {
+    pub source: FeeSource,
+    pub old_destination: Pubkey,
+    pub new_destination: Pubkey,
+}
+
+#[event]
+pub struct BucketWithdrawn {
+    pub source: FeeSource,
+    pub destination: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct FeesWithdrawn {
+    pub amount: u64,
+    /// Service's running total of withdrawn fees after this withdrawal
+    pub fees_withdrawn: u64,
+}
+
 #[error_code]
 pub enum MailServiceError {
     #[msg("Only the owner can perform this action")]
@@ -256,4 +1813,63 @@ pub enum MailServiceError {
     NoDelegationToReject,
     #[msg("Invalid delegator")]
     InvalidDelegator,
-}
\ No newline at end of file
+    #[msg("No claimable amount available")]
+    NoClaimableAmount,
+    #[msg("Arithmetic overflow")]
+    MathOverflow,
+    #[msg("Handles must be 1-32 lowercase letters, digits, '-' or '_'")]
+    InvalidHandle,
+    #[msg("Handle is registered and has not expired")]
+    HandleTaken,
+    #[msg("Handle has expired")]
+    HandleExpired,
+    #[msg("Only the handle owner can perform this action")]
+    NotHandleOwner,
+    #[msg("No pending delegation to accept")]
+    NoPendingDelegation,
+    #[msg("Delegation expiry must be in the future")]
+    InvalidDelegationExpiry,
+    #[msg("Unknown delegation permission flags")]
+    InvalidPermissions,
+    #[msg("Delegate index or delegator record account does not match")]
+    InvalidDelegateIndex,
+    #[msg("Delegation still has an active or pending delegate")]
+    DelegationNotCleared,
+    #[msg("Update window cannot be negative")]
+    InvalidUpdateWindow,
+    #[msg("Delegate share cannot exceed 10000 basis points")]
+    InvalidDelegateShare,
+    #[msg("Delegate claim account does not match the delegate")]
+    InvalidDelegateClaim,
+    #[msg("Cannot delegate to yourself")]
+    SelfDelegation,
+    #[msg("Delegate must be a wallet, not a program-derived address")]
+    ProgramDelegate,
+    #[msg("Withdrawal exceeds the fees accrued to the service")]
+    WithdrawalExceedsAccrued,
+    #[msg("Mail service is paused")]
+    Paused,
+    #[msg("Only the owner or guardian can pause the service")]
+    OnlyGuardian,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn delegate_must_be_another_wallet() {
+        let delegator = Pubkey::new_unique();
+        // Ed25519 base point: a valid wallet public key
+        let mut base_point = [0x66; 32];
+        base_point[0] = 0x58;
+        let wallet = Pubkey::new_from_array(base_point);
+        let (pda, _) = Pubkey::find_program_address(&[b"board"], &crate::ID);
+
+        assert!(validate_delegate(&delegator, &delegator, true).is_err());
+        assert!(validate_delegate(&delegator, &pda, false).is_err());
+        assert!(validate_delegate(&delegator, &pda, true).is_ok());
+        assert!(wallet.is_on_curve());
+        assert!(validate_delegate(&delegator, &wallet, false).is_ok());
+    }
+}
@@ -0,0 +1,400 @@
+//! Program initialization, the shared `MailerState` configuration account and
+//! the `MailerStats` usage counters.
+//!
+//! Sends only read `MailerState`; the counters they update live in
+//! `MailerStats` and, for fees, in each mint's send bucket.
+
+use anchor_lang::prelude::*;
+use anchor_lang::system_program::{self, Transfer};
+use anchor_lang::Discriminator;
+use anchor_spl::token_interface::Mint;
+use mailbox_common::seeds;
+
+use crate::fees::{FeeError, DEFAULT_CRANK_BOUNTY_BPS, DEFAULT_USD_FEE_CENTS, SEND_FEE};
+use crate::messaging::{DEFAULT_MAX_BODY_LEN, DEFAULT_MAX_SUBJECT_LEN};
+use crate::staking::{StakeTier, MAX_STAKE_TIERS};
+
+/// Decimals the base fee token must have; `SEND_FEE` is denominated in them
+pub use mailbox_common::fees::USDC_DECIMALS;
+
+pub fn initialize(ctx: Context<Initialize>) -> Result<()> {
+    let mailer = &mut ctx.accounts.mailer;
+    mailer.owner = ctx.accounts.owner.key();
+    mailer.usdc_mint = ctx.accounts.usdc_mint.key();
+    mailer.send_fee = SEND_FEE;
+    mailer.owner_claimable = 0;
+    mailer.bump = ctx.bumps.mailer;
+    mailer.crank_bounty_bps = DEFAULT_CRANK_BOUNTY_BPS;
+    mailer.rollover_bps = 0;
+    mailer.usd_fee_cents = DEFAULT_USD_FEE_CENTS;
+    mailer.pow_difficulty = 0;
+    mailer.max_subject_len = DEFAULT_MAX_SUBJECT_LEN;
+    mailer.max_body_len = DEFAULT_MAX_BODY_LEN;
+    mailer.max_messages_per_day = 0;
+    mailer.referral_bps = 0;
+    mailer.priority_points = 0;
+    mailer.standard_points = 0;
+    mailer.point_value = 0;
+    mailer.nft_collection = Pubkey::default();
+    mailer.nft_discount_bps = 0;
+    mailer.root_publisher = Pubkey::default();
+    mailer.auto_claimer = Pubkey::default();
+    mailer.stake_tiers = [StakeTier::default(); MAX_STAKE_TIERS];
+
+    ctx.accounts.stats.bump = ctx.bumps.stats;
+    Ok(())
+}
+
+pub fn init_stats(ctx: Context<InitStats>) -> Result<()> {
+    ctx.accounts.stats.bump = ctx.bumps.stats;
+    Ok(())
+}
+
+pub fn migrate_mailer(ctx: Context<MigrateMailer>) -> Result<()> {
+    let mailer_info = ctx.accounts.mailer.to_account_info();
+    let grown = grow_account::<MailerState>(
+        &mailer_info,
+        8 + MailerState::INIT_SPACE,
+        &ctx.accounts.owner.to_account_info(),
+        &ctx.accounts.system_program.to_account_info(),
+    )?;
+
+    let mut data = mailer_info.try_borrow_mut_data()?;
+    let mut mailer = MailerState::try_deserialize(&mut &data[..])?;
+    require_keys_eq!(
+        mailer.owner,
+        ctx.accounts.owner.key(),
+        MailerError::OnlyOwner
+    );
+    // Appended fields start zeroed; give those `initialize` sets otherwise their defaults
+    if grown {
+        mailer.crank_bounty_bps = DEFAULT_CRANK_BOUNTY_BPS;
+        mailer.usd_fee_cents = DEFAULT_USD_FEE_CENTS;
+        mailer.max_subject_len = DEFAULT_MAX_SUBJECT_LEN;
+        mailer.max_body_len = DEFAULT_MAX_BODY_LEN;
+    }
+    let mut writer: &mut [u8] = &mut data[..];
+    mailer.try_serialize(&mut writer)?;
+
+    Ok(())
+}
+
+pub fn update_usdc_mint(ctx: Context<UpdateUsdcMint>) -> Result<()> {
+    let mailer = &mut ctx.accounts.mailer;
+
+    // Legacy claims and the legacy owner balance are denominated in the current mint
+    require!(mailer.owner_claimable == 0, MailerError::UsdcMintInUse);
+    let old_token_config = &ctx.accounts.old_token_config;
+    require!(
+        old_token_config.data_is_empty() && old_token_config.owner != &crate::ID,
+        MailerError::UsdcMintInUse
+    );
+
+    let old_mint = mailer.usdc_mint;
+    mailer.usdc_mint = ctx.accounts.new_usdc_mint.key();
+
+    emit!(UsdcMintUpdated {
+        old_mint,
+        new_mint: mailer.usdc_mint,
+    });
+
+    Ok(())
+}
+
+/// Grow `account`, a `T` created under an older, shorter layout, to `space`
+/// bytes, topping its rent up from `payer`
+///
+/// Layouts only ever append fields, so the old data stays valid and the
+/// appended fields start zeroed. Returns whether the account grew; accounts
+/// already `space` bytes long are left as they are.
+pub(crate) fn grow_account<'info, T: Discriminator>(
+    account: &AccountInfo<'info>,
+    space: usize,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+) -> Result<bool> {
+    require!(
+        account.owner == &crate::ID && account.try_borrow_data()?.starts_with(&T::DISCRIMINATOR),
+        MailerError::InvalidMigrationAccount
+    );
+    if account.data_len() >= space {
+        return Ok(false);
+    }
+
+    let shortfall = Rent::get()?
+        .minimum_balance(space)
+        .saturating_sub(account.lamports());
+    if shortfall > 0 {
+        system_program::transfer(
+            CpiContext::new(
+                system_program.clone(),
+                Transfer {
+                    from: payer.clone(),
+                    to: account.clone(),
+                },
+            ),
+            shortfall,
+        )?;
+    }
+    account.realloc(space, true)?;
+
+    Ok(true)
+}
+
+#[derive(Accounts)]
+pub struct Initialize<'info> {
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + MailerState::INIT_SPACE,
+        seeds = [seeds::MAILER],
+        bump
+    )]
+    pub mailer: Account<'info, MailerState>,
+
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + MailerStats::INIT_SPACE,
+        seeds = [b"mailer_stats"],
+        bump
+    )]
+    pub stats: Account<'info, MailerStats>,
+
+    #[account(constraint = usdc_mint.decimals == USDC_DECIMALS @ MailerError::InvalidUsdcMint)]
+    pub usdc_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Creates the usage counters of a deployment initialized before they existed
+#[derive(Accounts)]
+pub struct InitStats<'info> {
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + MailerStats::INIT_SPACE,
+        seeds = [b"mailer_stats"],
+        bump
+    )]
+    pub stats: Account<'info, MailerStats>,
+
+    #[account(
+        seeds = [seeds::MAILER],
+        bump = mailer.bump,
+        has_one = owner @ MailerError::OnlyOwner
+    )]
+    pub mailer: Account<'info, MailerState>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Grows a `MailerState` created by an earlier program version; the owner is
+/// checked in `migrate_mailer` once the account decodes
+#[derive(Accounts)]
+pub struct MigrateMailer<'info> {
+    /// CHECK: Mailer state PDA, possibly in an older layout; checked in `grow_account`
+    #[account(mut, seeds = [seeds::MAILER], bump)]
+    pub mailer: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateUsdcMint<'info> {
+    #[account(
+        mut,
+        seeds = [seeds::MAILER],
+        bump = mailer.bump,
+        has_one = owner @ MailerError::OnlyOwner
+    )]
+    pub mailer: Account<'info, MailerState>,
+
+    /// CHECK: Fee token config PDA of the current mint; must never have been created
+    #[account(seeds = [b"token", mailer.usdc_mint.as_ref()], bump)]
+    pub old_token_config: UncheckedAccount<'info>,
+
+    #[account(constraint = new_usdc_mint.decimals == USDC_DECIMALS @ MailerError::InvalidUsdcMint)]
+    pub new_usdc_mint: InterfaceAccount<'info, Mint>,
+
+    pub owner: Signer<'info>,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct MailerState {
+    pub owner: Pubkey,
+    pub usdc_mint: Pubkey,
+    pub send_fee: u64,
+    pub owner_claimable: u64,
+    pub bump: u8,
+    /// Portion of an expired share paid to whoever sweeps it, in basis points
+    pub crank_bounty_bps: u16,
+    /// Portion of an expired share rolled into the recipient's send credit, in basis points
+    pub rollover_bps: u16,
+    /// Priority send fee in US cents for mints priced by an oracle
+    pub usd_fee_cents: u64,
+    /// Leading zero bits required by `send_with_pow`; 0 disables proof-of-work sends
+    pub pow_difficulty: u8,
+    /// Longest subject a send accepts, in bytes
+    pub max_subject_len: u32,
+    /// Longest body a send accepts, in bytes
+    pub max_body_len: u32,
+    /// Messages one sender may send per `RATE_LIMIT_WINDOW`; 0 disables the limit
+    pub max_messages_per_day: u32,
+    /// Portion of the owner's share paid to a send's referrer, in basis points
+    pub referral_bps: u16,
+    /// Loyalty points earned per paid priority message
+    pub priority_points: u32,
+    /// Loyalty points earned per paid standard message
+    pub standard_points: u32,
+    /// Send credit granted per redeemed point, in USDC base units; 0 disables redemption
+    pub point_value: u64,
+    /// Metaplex collection whose holders get `nft_discount_bps` off fees; default disables the perk
+    pub nft_collection: Pubkey,
+    /// Fee discount for holders of an `nft_collection` NFT, in basis points; 10000 waives the fee
+    pub nft_discount_bps: u16,
+    /// Key besides the owner allowed to publish daily message roots; default for none
+    pub root_publisher: Pubkey,
+    /// Automation thread allowed to auto-claim opted-in claims; default disables auto-claiming
+    pub auto_claimer: Pubkey,
+    /// Fee discounts by staked amount, in ascending order; unused slots have a zero `min_stake`
+    pub stake_tiers: [StakeTier; MAX_STAKE_TIERS],
+}
+
+impl MailerState {
+    /// Whether `key` may publish daily message roots
+    pub fn can_publish_roots(&self, key: &Pubkey) -> bool {
+        *key == self.owner || (*key == self.root_publisher && *key != Pubkey::default())
+    }
+
+    /// Whether `key` is the registered auto-claimer
+    pub fn is_auto_claimer(&self, key: &Pubkey) -> bool {
+        *key == self.auto_claimer && *key != Pubkey::default()
+    }
+}
+
+/// Usage counters updated by every send, so `MailerState` stays read-only on send paths
+#[account]
+#[derive(InitSpace)]
+pub struct MailerStats {
+    /// Messages sent through every send path, counting each message of a batch
+    pub total_messages_sent: u64,
+    /// Of `total_messages_sent`, those that paid the full fee with revenue sharing
+    pub total_priority_messages: u64,
+    /// Sequence number the next message is assigned; events carry it as an ordering key
+    pub message_seq: u64,
+    pub bump: u8,
+}
+
+impl MailerStats {
+    /// Add `messages` sends to the counters
+    ///
+    /// Returns the sequence number assigned to the first of the messages; the
+    /// rest are numbered consecutively after it. Fees are counted per mint by
+    /// `OwnerBucket::record_fees`.
+    pub(crate) fn record_sends(&mut self, messages: u64, priority: bool) -> Result<u64> {
+        let first_seq = self.message_seq;
+        self.message_seq = self
+            .message_seq
+            .checked_add(messages)
+            .ok_or(FeeError::MathOverflow)?;
+        self.total_messages_sent = self
+            .total_messages_sent
+            .checked_add(messages)
+            .ok_or(FeeError::MathOverflow)?;
+        if priority {
+            self.total_priority_messages = self
+                .total_priority_messages
+                .checked_add(messages)
+                .ok_or(FeeError::MathOverflow)?;
+        }
+        Ok(first_seq)
+    }
+}
+
+#[event]
+pub struct UsdcMintUpdated {
+    pub old_mint: Pubkey,
+    pub new_mint: Pubkey,
+}
+
+/// Errors of the original program keep their deployed codes; new admin errors follow them
+#[error_code]
+pub enum MailerError {
+    #[msg("Only the owner can perform this action")]
+    OnlyOwner,
+    #[msg("No claimable amount available")]
+    NoClaimableAmount,
+    #[msg("Claim period has expired")]
+    ClaimPeriodExpired,
+    #[msg("Claim period has not expired yet")]
+    ClaimPeriodNotExpired,
+    #[msg("Invalid recipient")]
+    InvalidRecipient,
+    #[msg("USDC mint must be an SPL mint with 6 decimals")]
+    InvalidUsdcMint,
+    #[msg("USDC mint already holds fees or claims and cannot be replaced")]
+    UsdcMintInUse,
+    #[msg("Account is not a mailer account of the type being migrated")]
+    InvalidMigrationAccount,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::claims::ClaimError;
+    use crate::escrow::EscrowError;
+    use crate::fees::FeeError;
+    use crate::messaging::MessagingError;
+    use crate::pow::PowError;
+    use crate::tokens::TokenError;
+
+    #[test]
+    fn original_error_codes_are_unchanged() {
+        assert_eq!(u32::from(MailerError::OnlyOwner), 6000);
+        assert_eq!(u32::from(MailerError::NoClaimableAmount), 6001);
+        assert_eq!(u32::from(MailerError::ClaimPeriodExpired), 6002);
+        assert_eq!(u32::from(MailerError::ClaimPeriodNotExpired), 6003);
+        assert_eq!(u32::from(MailerError::InvalidRecipient), 6004);
+    }
+
+    #[test]
+    fn mailer_state_extends_the_original_layout() {
+        let mut state = crate::tokens::tests::mailer_with_usdc(Pubkey::new_unique());
+        state.send_fee = 100_000;
+        state.owner_claimable = 42;
+        state.bump = 254;
+        let original = (
+            state.owner,
+            state.usdc_mint,
+            state.send_fee,
+            state.owner_claimable,
+            state.bump,
+        );
+        assert!(state
+            .try_to_vec()
+            .unwrap()
+            .starts_with(&original.try_to_vec().unwrap()));
+    }
+
+    #[test]
+    fn error_namespaces_do_not_overlap() {
+        assert_eq!(u32::from(MailerError::OnlyOwner), 6000);
+        assert_eq!(u32::from(ClaimError::ClaimNotEmpty), 6100);
+        assert_eq!(u32::from(MessagingError::TooManyOriginPrograms), 6200);
+        assert_eq!(u32::from(FeeError::InvalidCrankBounty), 6300);
+        assert_eq!(u32::from(TokenError::TokenDisabled), 6400);
+        assert_eq!(u32::from(EscrowError::InvalidPaymentAmount), 6500);
+        assert_eq!(u32::from(PowError::PowDisabled), 6600);
+    }
+}
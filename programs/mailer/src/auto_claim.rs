@@ -0,0 +1,216 @@
+//! Automated claims.
+//!
+//! The owner registers an automation thread (e.g. a Clockwork or Tuk Tuk
+//! thread PDA) as the `auto_claimer`. Recipients who opt in with
+//! `enable_auto_claim` let that thread run `auto_claim`, which pays their
+//! lump-sum revenue share out to their own token account during the last
+//! `AUTO_CLAIM_WINDOW` before it expires. The thread can only move a claim to
+//! its recipient, so opting in grants it no custody.
+
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+use mailbox_common::seeds;
+
+use crate::admin::{MailerError, MailerState};
+use crate::claims::{is_expiring_within, ClaimMode, RecipientClaim, RecipientClaimed};
+use crate::compliance::ComplianceError;
+use crate::escrow::release;
+use crate::expiry;
+use crate::fees::SetFee;
+use crate::tokens::{claim_mint, TokenError};
+
+/// How long before expiry an opted-in claim may be auto-claimed: 3 days in seconds
+pub const AUTO_CLAIM_WINDOW: i64 = 3 * 24 * 60 * 60;
+
+pub fn set_auto_claimer(ctx: Context<SetFee>, auto_claimer: Pubkey) -> Result<()> {
+    ctx.accounts.mailer.auto_claimer = auto_claimer;
+
+    emit!(AutoClaimerUpdated { auto_claimer });
+
+    Ok(())
+}
+
+pub fn enable_auto_claim(ctx: Context<EnableAutoClaim>, enabled: bool) -> Result<()> {
+    let claim = &mut ctx.accounts.recipient_claim;
+    let recipient = ctx.accounts.recipient.key();
+
+    if claim.recipient == Pubkey::default() {
+        claim.rent_payer = recipient;
+    }
+    claim.recipient = recipient;
+    claim.bump = ctx.bumps.recipient_claim;
+    claim.auto_claim = enabled;
+
+    emit!(AutoClaimUpdated { recipient, enabled });
+
+    Ok(())
+}
+
+pub fn auto_claim(ctx: Context<AutoClaim>) -> Result<()> {
+    let claim = &mut ctx.accounts.recipient_claim;
+    let recipient = ctx.accounts.recipient.key();
+
+    require!(claim.auto_claim, AutoClaimError::AutoClaimDisabled);
+    require!(claim.amount > 0, MailerError::NoClaimableAmount);
+
+    // Streaming claims never expire, so they are never due; nor is a claim still vesting
+    let current_time = Clock::get()?.unix_timestamp;
+    require!(
+        claim.mode == ClaimMode::Lump
+            && is_auto_claim_due(claim.timestamp, current_time)
+            && claim.locked(current_time) == 0,
+        AutoClaimError::AutoClaimNotDue
+    );
+
+    expiry::untrack(&ctx.accounts.expiry_index, recipient, claim.timestamp)?;
+    let amount = claim.amount;
+    claim.withdraw(amount)?;
+
+    release(
+        &ctx.accounts.mailer,
+        &ctx.accounts.mint,
+        &ctx.accounts.mailer_usdc_account,
+        &ctx.accounts.recipient_usdc_account,
+        &ctx.accounts.token_program,
+        amount,
+    )?;
+
+    emit!(AutoClaimed {
+        recipient,
+        auto_claimer: ctx.accounts.auto_claimer.key(),
+    });
+    emit!(RecipientClaimed { recipient, amount });
+
+    Ok(())
+}
+
+/// Whether a lump claim recorded at `timestamp` is in its auto-claim window at
+/// `current_time`: within `AUTO_CLAIM_WINDOW` of expiry and not yet expired
+pub fn is_auto_claim_due(timestamp: i64, current_time: i64) -> bool {
+    is_expiring_within(timestamp, AUTO_CLAIM_WINDOW, current_time)
+}
+
+#[derive(Accounts)]
+pub struct EnableAutoClaim<'info> {
+    #[account(
+        init_if_needed,
+        payer = recipient,
+        space = 8 + RecipientClaim::INIT_SPACE,
+        seeds = [b"claim", recipient.key().as_ref()],
+        bump
+    )]
+    pub recipient_claim: Account<'info, RecipientClaim>,
+
+    #[account(mut)]
+    pub recipient: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct AutoClaim<'info> {
+    #[account(
+        mut,
+        seeds = [b"claim", recipient.key().as_ref()],
+        bump,
+        has_one = recipient @ MailerError::InvalidRecipient
+    )]
+    pub recipient_claim: Account<'info, RecipientClaim>,
+
+    #[account(
+        seeds = [seeds::MAILER],
+        bump = mailer.bump,
+        constraint = mailer.is_auto_claimer(&auto_claimer.key()) @ AutoClaimError::NotAutoClaimer
+    )]
+    pub mailer: Account<'info, MailerState>,
+
+    /// Registered automation thread
+    pub auto_claimer: Signer<'info>,
+
+    /// CHECK: Claim's recipient, validated by `has_one`; only receives the payout
+    pub recipient: UncheckedAccount<'info>,
+
+    #[account(
+        constraint = claim_mint(&recipient_claim, &mailer) == mint.key() @ TokenError::ClaimMintMismatch
+    )]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = recipient,
+        associated_token::token_program = token_program
+    )]
+    pub recipient_usdc_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = mailer,
+        associated_token::token_program = token_program
+    )]
+    pub mailer_usdc_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// CHECK: Expiry bucket for the claim's current week, verified in `expiry::untrack`
+    #[account(mut)]
+    pub expiry_index: UncheckedAccount<'info>,
+
+    /// CHECK: Recipient's compliance denylist marker; must not exist
+    #[account(
+        seeds = [b"deny", recipient.key().as_ref()],
+        bump,
+        constraint = denylist.data_is_empty() @ ComplianceError::AddressDenied
+    )]
+    pub denylist: UncheckedAccount<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[event]
+pub struct AutoClaimerUpdated {
+    /// Automation thread allowed to auto-claim; default disables auto-claiming
+    pub auto_claimer: Pubkey,
+}
+
+#[event]
+pub struct AutoClaimUpdated {
+    pub recipient: Pubkey,
+    pub enabled: bool,
+}
+
+/// Emitted by `auto_claim` alongside the `RecipientClaimed` of the payout
+#[event]
+pub struct AutoClaimed {
+    pub recipient: Pubkey,
+    pub auto_claimer: Pubkey,
+}
+
+#[error_code(offset = 7900)]
+pub enum AutoClaimError {
+    #[msg("Signer is not the registered auto-claimer")]
+    NotAutoClaimer,
+    #[msg("Recipient has not enabled auto-claim")]
+    AutoClaimDisabled,
+    #[msg("Claim is not within its auto-claim window")]
+    AutoClaimNotDue,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::claims::CLAIM_PERIOD;
+
+    #[test]
+    fn auto_claim_is_due_only_in_the_window_before_expiry() {
+        let start = 1_000;
+        let expires_at = start + CLAIM_PERIOD;
+        assert!(!is_auto_claim_due(start, start));
+        assert!(!is_auto_claim_due(
+            start,
+            expires_at - AUTO_CLAIM_WINDOW - 1
+        ));
+        assert!(is_auto_claim_due(start, expires_at - AUTO_CLAIM_WINDOW));
+        assert!(is_auto_claim_due(start, expires_at));
+        assert!(!is_auto_claim_due(start, expires_at + 1));
+    }
+}
@@ -0,0 +1,295 @@
+//! Promotional campaigns.
+//!
+//! The owner creates a `Campaign` PDA at `[b"campaign", id]` with a boosted
+//! recipient share, a start and end time and a budget in the mailer's USDC
+//! mint. Priority sends that pass a running campaign credit the recipient the
+//! boosted share instead of `RECIPIENT_SHARE`; the difference comes out of
+//! the owner's share (after any referral) and is charged to the budget until
+//! it runs out. Sends passing a campaign that has not started, has ended or
+//! is spent fall back to the normal split. Channel posts and group sends do
+//! not accept campaigns.
+
+use anchor_lang::prelude::*;
+use mailbox_common::seeds;
+
+use crate::admin::{MailerError, MailerState};
+use crate::fees::{standard_fee, FeeError, RECIPIENT_SHARE};
+use crate::messaging::SendMessage;
+
+pub fn create_campaign(
+    ctx: Context<CreateCampaign>,
+    id: u64,
+    recipient_share: u8,
+    start_time: i64,
+    end_time: i64,
+    budget: u64,
+) -> Result<()> {
+    validate_campaign(recipient_share, start_time, end_time, budget)?;
+
+    let campaign = &mut ctx.accounts.campaign;
+    campaign.id = id;
+    campaign.mint = ctx.accounts.mailer.usdc_mint;
+    campaign.recipient_share = recipient_share;
+    campaign.start_time = start_time;
+    campaign.end_time = end_time;
+    campaign.budget = budget;
+    campaign.spent = 0;
+    campaign.bump = ctx.bumps.campaign;
+
+    emit!(CampaignCreated {
+        id,
+        mint: campaign.mint,
+        recipient_share,
+        start_time,
+        end_time,
+        budget,
+    });
+
+    Ok(())
+}
+
+pub fn close_campaign(ctx: Context<CloseCampaign>) -> Result<()> {
+    let campaign = &ctx.accounts.campaign;
+    emit!(CampaignClosed {
+        id: campaign.id,
+        spent: campaign.spent,
+    });
+
+    Ok(())
+}
+
+pub fn validate_campaign(
+    recipient_share: u8,
+    start_time: i64,
+    end_time: i64,
+    budget: u64,
+) -> Result<()> {
+    require!(
+        recipient_share as u64 > RECIPIENT_SHARE && recipient_share <= 100,
+        CampaignError::InvalidCampaignShare
+    );
+    require!(start_time < end_time, CampaignError::InvalidCampaignWindow);
+    require!(budget > 0, CampaignError::InvalidCampaignBudget);
+    Ok(())
+}
+
+/// Credit the recipient of a priority send the campaign's boost, when the send
+/// passes a running campaign in the paid mint
+///
+/// `net_fee` is the priority fee the mailer kept and `referred` the part of
+/// its owner share already paid to a referrer; the boost never exceeds what
+/// is left of the owner share.
+pub(crate) fn boost_shares(accounts: &mut SendMessage, net_fee: u64, referred: u64) -> Result<()> {
+    let mint = accounts.owner_bucket.mint;
+    let Some(campaign) = accounts.campaign.as_mut() else {
+        return Ok(());
+    };
+    if campaign.mint != mint || !campaign.is_active(Clock::get()?.unix_timestamp) {
+        return Ok(());
+    }
+
+    let owner_left = standard_fee(net_fee)?
+        .checked_sub(referred)
+        .ok_or(FeeError::MathOverflow)?;
+    let amount = campaign.boost(net_fee, owner_left)?;
+    if amount == 0 {
+        return Ok(());
+    }
+    campaign.spent = campaign
+        .spent
+        .checked_add(amount)
+        .ok_or(FeeError::MathOverflow)?;
+    accounts.owner_bucket.claimable = accounts
+        .owner_bucket
+        .claimable
+        .checked_sub(amount)
+        .ok_or(FeeError::MathOverflow)?;
+    accounts.recipient_claim.amount = accounts
+        .recipient_claim
+        .amount
+        .checked_add(amount)
+        .ok_or(FeeError::MathOverflow)?;
+
+    emit!(CampaignBoostPaid {
+        id: campaign.id,
+        recipient: accounts.recipient_claim.recipient,
+        amount,
+        remaining_budget: campaign.budget - campaign.spent,
+    });
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(id: u64)]
+pub struct CreateCampaign<'info> {
+    /// Fails if a campaign with this ID already exists
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + Campaign::INIT_SPACE,
+        seeds = [b"campaign", id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub campaign: Account<'info, Campaign>,
+
+    #[account(
+        seeds = [seeds::MAILER],
+        bump = mailer.bump,
+        has_one = owner @ MailerError::OnlyOwner
+    )]
+    pub mailer: Account<'info, MailerState>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CloseCampaign<'info> {
+    /// Closed when the campaign is wound up; rent returns to the owner
+    #[account(
+        mut,
+        close = owner,
+        seeds = [b"campaign", campaign.id.to_le_bytes().as_ref()],
+        bump = campaign.bump
+    )]
+    pub campaign: Account<'info, Campaign>,
+
+    #[account(
+        seeds = [seeds::MAILER],
+        bump = mailer.bump,
+        has_one = owner @ MailerError::OnlyOwner
+    )]
+    pub mailer: Account<'info, MailerState>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Campaign {
+    pub id: u64,
+    /// Mint the budget is denominated in; sends paying another mint get no boost
+    pub mint: Pubkey,
+    /// Recipient share of priority fees while the campaign runs, in percent
+    pub recipient_share: u8,
+    pub start_time: i64,
+    /// First second the campaign no longer applies
+    pub end_time: i64,
+    /// Most the boost may pay out over the campaign, in `mint` base units
+    pub budget: u64,
+    pub spent: u64,
+    pub bump: u8,
+}
+
+impl Campaign {
+    pub fn is_active(&self, now: i64) -> bool {
+        self.start_time <= now && now < self.end_time && self.spent < self.budget
+    }
+
+    /// Extra recipient share of a priority fee of `net_fee` over the normal
+    /// split, capped by `owner_left` and the remaining budget
+    pub fn boost(&self, net_fee: u64, owner_left: u64) -> Result<u64> {
+        let boosted = net_fee
+            .checked_mul(self.recipient_share as u64)
+            .ok_or(FeeError::MathOverflow)?
+            / 100;
+        let normal = net_fee
+            .checked_sub(standard_fee(net_fee)?)
+            .ok_or(FeeError::MathOverflow)?;
+        let remaining = self.budget.saturating_sub(self.spent);
+        Ok(boosted
+            .saturating_sub(normal)
+            .min(owner_left)
+            .min(remaining))
+    }
+}
+
+#[event]
+pub struct CampaignCreated {
+    pub id: u64,
+    pub mint: Pubkey,
+    pub recipient_share: u8,
+    pub start_time: i64,
+    pub end_time: i64,
+    pub budget: u64,
+}
+
+#[event]
+pub struct CampaignBoostPaid {
+    pub id: u64,
+    pub recipient: Pubkey,
+    pub amount: u64,
+    pub remaining_budget: u64,
+}
+
+#[event]
+pub struct CampaignClosed {
+    pub id: u64,
+    pub spent: u64,
+}
+
+#[error_code(offset = 7200)]
+pub enum CampaignError {
+    #[msg("Campaign recipient share must exceed the standard share and be at most 100%")]
+    InvalidCampaignShare,
+    #[msg("Campaign must end after it starts")]
+    InvalidCampaignWindow,
+    #[msg("Campaign budget must be positive")]
+    InvalidCampaignBudget,
+    #[msg("Channel posts and group sends do not accept campaigns")]
+    CampaignNotAccepted,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn campaign(budget: u64, spent: u64) -> Campaign {
+        Campaign {
+            id: 1,
+            mint: Pubkey::new_unique(),
+            recipient_share: 95,
+            start_time: 100,
+            end_time: 200,
+            budget,
+            spent,
+            bump: 0,
+        }
+    }
+
+    #[test]
+    fn boost_is_capped_by_owner_share_and_budget() {
+        // 95% instead of 90% of 100_000
+        assert_eq!(
+            campaign(1_000_000, 0).boost(100_000, 10_000).unwrap(),
+            5_000
+        );
+        assert_eq!(campaign(1_000_000, 0).boost(100_000, 3_000).unwrap(), 3_000);
+        assert_eq!(
+            campaign(1_000_000, 998_000).boost(100_000, 10_000).unwrap(),
+            2_000
+        );
+    }
+
+    #[test]
+    fn campaigns_run_inside_their_window_until_spent() {
+        assert!(!campaign(10, 0).is_active(99));
+        assert!(campaign(10, 0).is_active(100));
+        assert!(!campaign(10, 0).is_active(200));
+        assert!(!campaign(10, 10).is_active(150));
+    }
+
+    #[test]
+    fn campaign_terms_are_validated() {
+        assert!(validate_campaign(95, 0, 1, 1).is_ok());
+        assert!(validate_campaign(100, 0, 1, 1).is_ok());
+        assert!(validate_campaign(90, 0, 1, 1).is_err());
+        assert!(validate_campaign(101, 0, 1, 1).is_err());
+        assert!(validate_campaign(95, 1, 1, 1).is_err());
+        assert!(validate_campaign(95, 0, 1, 0).is_err());
+    }
+}
@@ -0,0 +1,911 @@
+//! Broadcast channels.
+//!
+//! A channel is a named PDA owned by its creator. Readers subscribe by creating
+//! a subscription PDA, and anyone may post a prepared message to the channel.
+//! Posts pay the priority send fee, and the recipient share is credited to the
+//! channel owner's claim instead of back to the poster, so channels earn
+//! through the same claim and expiry machinery as priority sends.
+//!
+//! Owners may also charge a subscription fee per `CHANNEL_EPOCH`. Fees are held
+//! in the mailer's token account against the channel's `claimable` balance until
+//! the owner withdraws them, and readers-only channels accept posts only from
+//! the owner, moderators and subscribers with a paid-up subscription.
+//!
+//! The owner may appoint up to `MAX_MODERATORS` moderators, who share the
+//! owner's power to remove subscribers, pin a post and mute senders.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::hash::hash;
+use anchor_spl::token_interface::{self, Mint, TokenAccount, TokenInterface, TransferChecked};
+use mailbox_common::seeds;
+
+use crate::admin::MailerState;
+use crate::campaigns::CampaignError;
+use crate::claims::{ClaimMode, RecipientClaim};
+use crate::compliance::ComplianceError;
+use crate::escrow::release;
+use crate::expiry;
+use crate::fees::{charge_sender, record_shares, FeeError};
+use crate::messaging::{
+    __client_accounts_send_message, __cpi_client_accounts_send_message, check_message_origin,
+    count_sends, emit_send_event, message_id, record_mail_id, record_sends, SendMessage,
+    SendMessageBumps,
+};
+use crate::referrals::ReferralError;
+use crate::tokens::{TokenConfig, TokenError};
+
+/// Maximum length of a channel name, in bytes
+pub const MAX_CHANNEL_NAME_LEN: usize = 32;
+
+/// Maximum number of moderators on a channel or group
+pub const MAX_MODERATORS: usize = 4;
+
+/// Length of one paid subscription period, in seconds
+pub const CHANNEL_EPOCH: i64 = 30 * 24 * 60 * 60; // 30 days
+
+pub fn create_channel(ctx: Context<CreateChannel>, name: String) -> Result<()> {
+    validate_channel_name(&name)?;
+
+    let channel = &mut ctx.accounts.channel;
+    channel.owner = ctx.accounts.creator.key();
+    channel.name_hash = channel_name_hash(&name);
+    channel.name = name.clone();
+    channel.subscription_mint = Pubkey::default();
+    channel.subscription_fee = 0;
+    channel.readers_only = false;
+    channel.claimable = 0;
+    channel.moderators = Vec::new();
+    channel.pinned_message = None;
+    channel.subscriber_count = 0;
+    channel.post_count = 0;
+    channel.created_at = Clock::get()?.unix_timestamp;
+    channel.bump = ctx.bumps.channel;
+
+    emit!(ChannelCreated {
+        channel: channel.key(),
+        owner: channel.owner,
+        name,
+    });
+
+    Ok(())
+}
+
+pub fn subscribe(ctx: Context<Subscribe>) -> Result<()> {
+    let channel = &mut ctx.accounts.channel;
+    channel.subscriber_count = channel
+        .subscriber_count
+        .checked_add(1)
+        .ok_or(FeeError::MathOverflow)?;
+
+    let subscription = &mut ctx.accounts.subscription;
+    subscription.channel = channel.key();
+    subscription.subscriber = ctx.accounts.subscriber.key();
+    subscription.subscribed_at = Clock::get()?.unix_timestamp;
+    subscription.paid_until = 0;
+    subscription.bump = ctx.bumps.subscription;
+
+    emit!(ChannelSubscribed {
+        channel: channel.key(),
+        subscriber: subscription.subscriber,
+        subscriber_count: channel.subscriber_count,
+    });
+
+    Ok(())
+}
+
+pub fn set_channel_terms(
+    ctx: Context<SetChannelTerms>,
+    subscription_fee: u64,
+    readers_only: bool,
+) -> Result<()> {
+    let channel = &mut ctx.accounts.channel;
+    let mint = ctx.accounts.mint.key();
+    // Collected fees are tracked in a single mint, so they must be withdrawn before switching
+    require!(
+        channel.claimable == 0 || channel.subscription_mint == mint,
+        ChannelError::ChannelFeesOutstanding
+    );
+    channel.subscription_mint = mint;
+    channel.subscription_fee = subscription_fee;
+    channel.readers_only = readers_only;
+
+    emit!(ChannelTermsUpdated {
+        channel: channel.key(),
+        mint,
+        subscription_fee,
+        readers_only,
+    });
+
+    Ok(())
+}
+
+pub fn pay_subscription(ctx: Context<PaySubscription>, epochs: u32) -> Result<()> {
+    require!(epochs > 0, ChannelError::InvalidEpochs);
+    let fee = ctx.accounts.channel.subscription_fee;
+    require!(fee > 0, ChannelError::FreeChannel);
+    let amount = fee
+        .checked_mul(u64::from(epochs))
+        .ok_or(FeeError::MathOverflow)?;
+
+    let balance_before = ctx.accounts.mailer_usdc_account.amount;
+    let transfer_ctx = CpiContext::new(
+        ctx.accounts.token_program.to_account_info(),
+        TransferChecked {
+            from: ctx.accounts.subscriber_usdc_account.to_account_info(),
+            mint: ctx.accounts.mint.to_account_info(),
+            to: ctx.accounts.mailer_usdc_account.to_account_info(),
+            authority: ctx.accounts.subscriber.to_account_info(),
+        },
+    );
+    token_interface::transfer_checked(transfer_ctx, amount, ctx.accounts.mint.decimals)?;
+
+    // Credit what actually arrived, net of any Token-2022 transfer fee
+    ctx.accounts.mailer_usdc_account.reload()?;
+    let received = ctx
+        .accounts
+        .mailer_usdc_account
+        .amount
+        .checked_sub(balance_before)
+        .ok_or(FeeError::MathOverflow)?;
+
+    let channel = &mut ctx.accounts.channel;
+    channel.claimable = channel
+        .claimable
+        .checked_add(received)
+        .ok_or(FeeError::MathOverflow)?;
+
+    // Renewals extend a subscription that is still running; lapsed ones restart now
+    let subscription = &mut ctx.accounts.subscription;
+    let now = Clock::get()?.unix_timestamp;
+    subscription.paid_until = subscription
+        .paid_until
+        .max(now)
+        .checked_add(
+            CHANNEL_EPOCH
+                .checked_mul(i64::from(epochs))
+                .ok_or(FeeError::MathOverflow)?,
+        )
+        .ok_or(FeeError::MathOverflow)?;
+
+    emit!(SubscriptionPaid {
+        channel: channel.key(),
+        subscriber: subscription.subscriber,
+        amount: received,
+        epochs,
+        paid_until: subscription.paid_until,
+    });
+
+    Ok(())
+}
+
+pub fn withdraw_channel_fees(ctx: Context<WithdrawChannelFees>) -> Result<()> {
+    let amount = ctx.accounts.channel.claimable;
+    require!(amount > 0, ChannelError::NothingToWithdraw);
+    ctx.accounts.channel.claimable = 0;
+
+    release(
+        &ctx.accounts.mailer,
+        &ctx.accounts.mint,
+        &ctx.accounts.mailer_usdc_account,
+        &ctx.accounts.owner_usdc_account,
+        &ctx.accounts.token_program,
+        amount,
+    )?;
+
+    emit!(ChannelFeesWithdrawn {
+        channel: ctx.accounts.channel.key(),
+        owner: ctx.accounts.owner.key(),
+        amount,
+    });
+
+    Ok(())
+}
+
+pub fn grant_channel_moderator(ctx: Context<ManageChannel>, moderator: Pubkey) -> Result<()> {
+    let channel = &mut ctx.accounts.channel;
+    require!(
+        moderator != channel.owner && !channel.moderators.contains(&moderator),
+        ChannelError::AlreadyModerator
+    );
+    require!(
+        channel.moderators.len() < MAX_MODERATORS,
+        ChannelError::TooManyModerators
+    );
+    channel.moderators.push(moderator);
+
+    emit!(ModeratorGranted {
+        target: channel.key(),
+        moderator,
+    });
+
+    Ok(())
+}
+
+pub fn revoke_channel_moderator(ctx: Context<ManageChannel>, moderator: Pubkey) -> Result<()> {
+    let channel = &mut ctx.accounts.channel;
+    let count = channel.moderators.len();
+    channel.moderators.retain(|key| *key != moderator);
+    require!(channel.moderators.len() < count, ChannelError::UnknownModerator);
+
+    emit!(ModeratorRevoked {
+        target: channel.key(),
+        moderator,
+    });
+
+    Ok(())
+}
+
+pub fn remove_subscriber(ctx: Context<RemoveSubscriber>) -> Result<()> {
+    let channel = &mut ctx.accounts.channel;
+    channel.subscriber_count = channel
+        .subscriber_count
+        .checked_sub(1)
+        .ok_or(FeeError::MathOverflow)?;
+
+    emit!(SubscriberRemoved {
+        channel: channel.key(),
+        subscriber: ctx.accounts.subscriber.key(),
+        removed_by: ctx.accounts.moderator.key(),
+        subscriber_count: channel.subscriber_count,
+    });
+
+    Ok(())
+}
+
+pub fn pin_post(ctx: Context<ModerateChannel>, message_id: [u8; 32]) -> Result<()> {
+    let channel = &mut ctx.accounts.channel;
+    channel.pinned_message = Some(message_id);
+
+    emit!(PostPinned {
+        channel: channel.key(),
+        message_id,
+        pinned_by: ctx.accounts.moderator.key(),
+    });
+
+    Ok(())
+}
+
+pub fn mute_sender(ctx: Context<MuteSender>, sender: Pubkey) -> Result<()> {
+    require!(
+        !ctx.accounts.channel.can_moderate(&sender),
+        ChannelError::CannotMuteModerator
+    );
+
+    let mute = &mut ctx.accounts.mute;
+    mute.channel = ctx.accounts.channel.key();
+    mute.sender = sender;
+    mute.muted_by = ctx.accounts.moderator.key();
+    mute.bump = ctx.bumps.mute;
+
+    emit!(SenderMuted {
+        channel: mute.channel,
+        sender,
+        muted_by: mute.muted_by,
+    });
+
+    Ok(())
+}
+
+pub fn unmute_sender(ctx: Context<UnmuteSender>) -> Result<()> {
+    emit!(SenderUnmuted {
+        channel: ctx.accounts.channel.key(),
+        sender: ctx.accounts.mute.sender,
+        unmuted_by: ctx.accounts.moderator.key(),
+    });
+
+    Ok(())
+}
+
+pub fn post_to_channel(ctx: Context<PostToChannel>, mail_id: String) -> Result<[u8; 32]> {
+    let poster = ctx.accounts.send.sender.key();
+    let channel = &ctx.accounts.channel;
+    require!(ctx.accounts.mute.data_is_empty(), ChannelError::SenderMuted);
+    if channel.readers_only && !channel.can_moderate(&poster) {
+        let now = Clock::get()?.unix_timestamp;
+        let subscribed = ctx
+            .accounts
+            .subscription
+            .as_ref()
+            .is_some_and(|subscription| subscription.is_active(channel, now));
+        require!(subscribed, ChannelError::SubscriptionRequired);
+    }
+    check_message_origin(
+        &ctx.accounts.send.caller_policy,
+        &ctx.accounts.send.recipient_preferences,
+        &ctx.accounts.send.instructions,
+    )?;
+    count_sends(&mut ctx.accounts.send, 1, true)?;
+    require!(
+        ctx.accounts.send.referral.is_none(),
+        ReferralError::ReferralNotAccepted
+    );
+    require!(
+        ctx.accounts.send.campaign.is_none(),
+        CampaignError::CampaignNotAccepted
+    );
+
+    // Posts always pay the priority fee, drawing down the poster's send credit first
+    let send_fee = ctx.accounts.send.send_fee()?;
+    let charge = charge_sender(&mut ctx.accounts.send, send_fee)?;
+
+    // The recipient share goes to the channel owner rather than back to the poster.
+    // An owner posting to their own channel is credited through the send's claim,
+    // so the same claim is never loaded twice and overwritten with stale data.
+    let accounts = &mut *ctx.accounts;
+    let owner = accounts.channel.owner;
+    let owner_claim = match accounts.owner_claim.as_mut() {
+        Some(claim) if owner != poster => claim,
+        None if owner == poster => &mut accounts.send.recipient_claim,
+        _ => return err!(ChannelError::InvalidOwnerClaim),
+    };
+    let started = record_shares(
+        owner_claim,
+        &mut accounts.send.owner_bucket,
+        &accounts.send.mailer,
+        owner,
+        accounts.send.payer.key(),
+        charge.net_of(send_fee)?,
+    )?;
+    if started && owner_claim.mode != ClaimMode::Streaming {
+        expiry::track(
+            &accounts.send.expiry_index,
+            &accounts.send.payer.to_account_info(),
+            &accounts.send.system_program.to_account_info(),
+            owner,
+            owner_claim.timestamp,
+        )?;
+    }
+
+    let message_seq = record_sends(&mut accounts.send, 1, true, charge.net_of(send_fee)?)?;
+    let clock = Clock::get()?;
+    let message_id = message_id(&poster, message_seq, clock.slot);
+    record_mail_id(&accounts.send, &mail_id, message_id)?;
+
+    let channel = &mut accounts.channel;
+    channel.post_count = channel.post_count.checked_add(1).ok_or(FeeError::MathOverflow)?;
+
+    let event = ChannelPosted {
+        channel: channel.key(),
+        from: poster,
+        owner,
+        message_seq,
+        message_id,
+        timestamp: clock.unix_timestamp,
+        mail_id,
+    };
+    emit_send_event!(ctx, event);
+
+    Ok(message_id)
+}
+
+pub fn validate_channel_name(name: &str) -> Result<()> {
+    require!(
+        !name.is_empty() && name.len() <= MAX_CHANNEL_NAME_LEN,
+        ChannelError::InvalidChannelName
+    );
+    Ok(())
+}
+
+/// Key a channel's PDA is derived from
+pub fn channel_name_hash(name: &str) -> [u8; 32] {
+    hash(name.as_bytes()).to_bytes()
+}
+
+#[derive(Accounts)]
+#[instruction(name: String)]
+pub struct CreateChannel<'info> {
+    #[account(
+        init,
+        payer = creator,
+        space = 8 + Channel::INIT_SPACE,
+        seeds = [b"channel", channel_name_hash(&name).as_ref()],
+        bump
+    )]
+    pub channel: Account<'info, Channel>,
+
+    #[account(mut)]
+    pub creator: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Subscribe<'info> {
+    #[account(mut, seeds = [b"channel", channel.name_hash.as_ref()], bump = channel.bump)]
+    pub channel: Account<'info, Channel>,
+
+    /// Fails if the subscriber is already subscribed
+    #[account(
+        init,
+        payer = subscriber,
+        space = 8 + Subscription::INIT_SPACE,
+        seeds = [b"subscription", channel.key().as_ref(), subscriber.key().as_ref()],
+        bump
+    )]
+    pub subscription: Account<'info, Subscription>,
+
+    #[account(mut)]
+    pub subscriber: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+pub struct PostToChannel<'info> {
+    /// Send accounts for the poster, who pays the fee
+    pub send: SendMessage<'info>,
+
+    #[account(mut, seeds = [b"channel", channel.name_hash.as_ref()], bump = channel.bump)]
+    pub channel: Account<'info, Channel>,
+
+    /// Channel owner's claim, credited with the recipient share of the post fee;
+    /// omitted when the owner posts, whose claim is then `send.recipient_claim`
+    #[account(
+        init_if_needed,
+        payer = send.payer,
+        space = 8 + RecipientClaim::INIT_SPACE,
+        seeds = [b"claim", channel.owner.as_ref()],
+        bump
+    )]
+    pub owner_claim: Option<Account<'info, RecipientClaim>>,
+
+    /// Poster's subscription; required to post to a readers-only channel
+    #[account(
+        seeds = [b"subscription", channel.key().as_ref(), send.sender.key().as_ref()],
+        bump = subscription.bump
+    )]
+    pub subscription: Option<Account<'info, Subscription>>,
+
+    /// CHECK: Poster's mute record; must not exist, checked in `post_to_channel`
+    #[account(seeds = [b"mute", channel.key().as_ref(), send.sender.key().as_ref()], bump)]
+    pub mute: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ManageChannel<'info> {
+    #[account(
+        mut,
+        seeds = [b"channel", channel.name_hash.as_ref()],
+        bump = channel.bump,
+        has_one = owner @ ChannelError::NotChannelOwner
+    )]
+    pub channel: Account<'info, Channel>,
+
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ModerateChannel<'info> {
+    #[account(
+        mut,
+        seeds = [b"channel", channel.name_hash.as_ref()],
+        bump = channel.bump,
+        constraint = channel.can_moderate(&moderator.key()) @ ChannelError::NotModerator
+    )]
+    pub channel: Account<'info, Channel>,
+
+    pub moderator: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RemoveSubscriber<'info> {
+    #[account(
+        mut,
+        seeds = [b"channel", channel.name_hash.as_ref()],
+        bump = channel.bump,
+        constraint = channel.can_moderate(&moderator.key()) @ ChannelError::NotModerator
+    )]
+    pub channel: Account<'info, Channel>,
+
+    /// Closed on removal; any paid time is forfeited and the rent returns to the subscriber
+    #[account(
+        mut,
+        close = subscriber,
+        seeds = [b"subscription", channel.key().as_ref(), subscriber.key().as_ref()],
+        bump = subscription.bump,
+        has_one = subscriber
+    )]
+    pub subscription: Account<'info, Subscription>,
+
+    /// CHECK: Subscription's owner, validated by `has_one`; receives the rent refund
+    #[account(mut)]
+    pub subscriber: UncheckedAccount<'info>,
+
+    pub moderator: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(sender: Pubkey)]
+pub struct MuteSender<'info> {
+    #[account(
+        seeds = [b"channel", channel.name_hash.as_ref()],
+        bump = channel.bump,
+        constraint = channel.can_moderate(&moderator.key()) @ ChannelError::NotModerator
+    )]
+    pub channel: Account<'info, Channel>,
+
+    /// Fails if the sender is already muted
+    #[account(
+        init,
+        payer = moderator,
+        space = 8 + ChannelMute::INIT_SPACE,
+        seeds = [b"mute", channel.key().as_ref(), sender.as_ref()],
+        bump
+    )]
+    pub mute: Account<'info, ChannelMute>,
+
+    #[account(mut)]
+    pub moderator: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UnmuteSender<'info> {
+    #[account(
+        seeds = [b"channel", channel.name_hash.as_ref()],
+        bump = channel.bump,
+        constraint = channel.can_moderate(&moderator.key()) @ ChannelError::NotModerator
+    )]
+    pub channel: Account<'info, Channel>,
+
+    /// Closed on unmute; rent returns to the moderator who muted
+    #[account(
+        mut,
+        close = muted_by,
+        seeds = [b"mute", channel.key().as_ref(), mute.sender.as_ref()],
+        bump = mute.bump,
+        has_one = muted_by
+    )]
+    pub mute: Account<'info, ChannelMute>,
+
+    /// CHECK: Moderator who created the mute, validated by `has_one`; receives the rent refund
+    #[account(mut)]
+    pub muted_by: UncheckedAccount<'info>,
+
+    pub moderator: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetChannelTerms<'info> {
+    #[account(
+        mut,
+        seeds = [b"channel", channel.name_hash.as_ref()],
+        bump = channel.bump,
+        has_one = owner @ ChannelError::NotChannelOwner
+    )]
+    pub channel: Account<'info, Channel>,
+
+    pub owner: Signer<'info>,
+
+    /// Subscriptions are paid in an enabled fee token, which the mailer holds an account for
+    #[account(
+        seeds = [b"token", mint.key().as_ref()],
+        bump = token_config.bump,
+        constraint = token_config.enabled @ TokenError::TokenDisabled
+    )]
+    pub token_config: Account<'info, TokenConfig>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+}
+
+#[derive(Accounts)]
+pub struct PaySubscription<'info> {
+    #[account(
+        mut,
+        seeds = [b"channel", channel.name_hash.as_ref()],
+        bump = channel.bump,
+        constraint = channel.subscription_mint == mint.key() @ TokenError::ClaimMintMismatch
+    )]
+    pub channel: Account<'info, Channel>,
+
+    #[account(
+        mut,
+        seeds = [b"subscription", channel.key().as_ref(), subscriber.key().as_ref()],
+        bump = subscription.bump
+    )]
+    pub subscription: Account<'info, Subscription>,
+
+    #[account(seeds = [seeds::MAILER], bump = mailer.bump)]
+    pub mailer: Account<'info, MailerState>,
+
+    pub subscriber: Signer<'info>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = subscriber,
+        associated_token::token_program = token_program
+    )]
+    pub subscriber_usdc_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = mailer,
+        associated_token::token_program = token_program
+    )]
+    pub mailer_usdc_account: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawChannelFees<'info> {
+    #[account(
+        mut,
+        seeds = [b"channel", channel.name_hash.as_ref()],
+        bump = channel.bump,
+        has_one = owner @ ChannelError::NotChannelOwner,
+        constraint = channel.subscription_mint == mint.key() @ TokenError::ClaimMintMismatch
+    )]
+    pub channel: Account<'info, Channel>,
+
+    #[account(seeds = [seeds::MAILER], bump = mailer.bump)]
+    pub mailer: Account<'info, MailerState>,
+
+    pub owner: Signer<'info>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = owner,
+        associated_token::token_program = token_program
+    )]
+    pub owner_usdc_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = mailer,
+        associated_token::token_program = token_program
+    )]
+    pub mailer_usdc_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// CHECK: Owner's compliance denylist marker; must not exist
+    #[account(
+        seeds = [b"deny", owner.key().as_ref()],
+        bump,
+        constraint = denylist.data_is_empty() @ ComplianceError::AddressDenied
+    )]
+    pub denylist: UncheckedAccount<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Channel {
+    /// Creator of the channel; receives the recipient share of every post
+    pub owner: Pubkey,
+    /// `channel_name_hash(name)`; seeds the channel PDA
+    pub name_hash: [u8; 32],
+    #[max_len(MAX_CHANNEL_NAME_LEN)]
+    pub name: String,
+    /// Mint subscription fees are paid in; default until the owner sets terms
+    pub subscription_mint: Pubkey,
+    /// Fee per `CHANNEL_EPOCH`; zero makes every subscription active
+    pub subscription_fee: u64,
+    /// Only the owner and active subscribers may post
+    pub readers_only: bool,
+    /// Subscription fees collected and not yet withdrawn by the owner
+    pub claimable: u64,
+    /// Share the owner's moderation powers; appointed by the owner
+    #[max_len(MAX_MODERATORS)]
+    pub moderators: Vec<Pubkey>,
+    /// Message ID of the post pinned by a moderator, if any
+    pub pinned_message: Option<[u8; 32]>,
+    pub subscriber_count: u64,
+    pub post_count: u64,
+    pub created_at: i64,
+    pub bump: u8,
+}
+
+impl Channel {
+    /// Whether `key` is the owner or one of the channel's moderators
+    pub fn can_moderate(&self, key: &Pubkey) -> bool {
+        self.owner == *key || self.moderators.contains(key)
+    }
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Subscription {
+    pub channel: Pubkey,
+    pub subscriber: Pubkey,
+    pub subscribed_at: i64,
+    /// End of the last paid epoch
+    pub paid_until: i64,
+    pub bump: u8,
+}
+
+impl Subscription {
+    /// Whether the subscription currently counts for `channel`
+    pub fn is_active(&self, channel: &Channel, now: i64) -> bool {
+        channel.subscription_fee == 0 || self.paid_until > now
+    }
+}
+
+/// Marks a sender as muted in one channel; closed to unmute
+#[account]
+#[derive(InitSpace)]
+pub struct ChannelMute {
+    pub channel: Pubkey,
+    pub sender: Pubkey,
+    pub muted_by: Pubkey,
+    pub bump: u8,
+}
+
+#[event]
+pub struct ChannelCreated {
+    pub channel: Pubkey,
+    pub owner: Pubkey,
+    pub name: String,
+}
+
+#[event]
+pub struct ChannelSubscribed {
+    pub channel: Pubkey,
+    pub subscriber: Pubkey,
+    pub subscriber_count: u64,
+}
+
+#[event]
+pub struct ChannelTermsUpdated {
+    pub channel: Pubkey,
+    pub mint: Pubkey,
+    pub subscription_fee: u64,
+    pub readers_only: bool,
+}
+
+#[event]
+pub struct SubscriptionPaid {
+    pub channel: Pubkey,
+    pub subscriber: Pubkey,
+    /// Amount credited to the channel, net of any transfer fee
+    pub amount: u64,
+    pub epochs: u32,
+    pub paid_until: i64,
+}
+
+#[event]
+pub struct ChannelFeesWithdrawn {
+    pub channel: Pubkey,
+    pub owner: Pubkey,
+    pub amount: u64,
+}
+
+/// Emitted when a moderator is appointed to a channel or group (`target`)
+#[event]
+pub struct ModeratorGranted {
+    pub target: Pubkey,
+    pub moderator: Pubkey,
+}
+
+/// Emitted when a moderator is removed from a channel or group (`target`)
+#[event]
+pub struct ModeratorRevoked {
+    pub target: Pubkey,
+    pub moderator: Pubkey,
+}
+
+#[event]
+pub struct SubscriberRemoved {
+    pub channel: Pubkey,
+    pub subscriber: Pubkey,
+    pub removed_by: Pubkey,
+    pub subscriber_count: u64,
+}
+
+#[event]
+pub struct PostPinned {
+    pub channel: Pubkey,
+    pub message_id: [u8; 32],
+    pub pinned_by: Pubkey,
+}
+
+#[event]
+pub struct SenderMuted {
+    pub channel: Pubkey,
+    pub sender: Pubkey,
+    pub muted_by: Pubkey,
+}
+
+#[event]
+pub struct SenderUnmuted {
+    pub channel: Pubkey,
+    pub sender: Pubkey,
+    pub unmuted_by: Pubkey,
+}
+
+/// Emitted by `post_to_channel`; subscribers follow a channel by filtering on `channel`
+#[event]
+pub struct ChannelPosted {
+    pub channel: Pubkey,
+    pub from: Pubkey,
+    /// Channel owner credited with the recipient share
+    pub owner: Pubkey,
+    /// Global order of the message across all send paths
+    pub message_seq: u64,
+    /// `message_id(from, message_seq, slot)`
+    pub message_id: [u8; 32],
+    pub timestamp: i64,
+    pub mail_id: String,
+}
+
+#[error_code(offset = 6700)]
+pub enum ChannelError {
+    #[msg("Channel name is empty or too long")]
+    InvalidChannelName,
+    #[msg("Owner claim must be passed exactly when the poster is not the channel owner")]
+    InvalidOwnerClaim,
+    #[msg("Only the channel owner can perform this action")]
+    NotChannelOwner,
+    #[msg("Subscription must be paid for at least one epoch")]
+    InvalidEpochs,
+    #[msg("Channel does not charge a subscription fee")]
+    FreeChannel,
+    #[msg("Readers-only channel requires an active subscription to post")]
+    SubscriptionRequired,
+    #[msg("Withdraw collected subscription fees before changing the mint")]
+    ChannelFeesOutstanding,
+    #[msg("No subscription fees to withdraw")]
+    NothingToWithdraw,
+    #[msg("Only the channel owner or a moderator can perform this action")]
+    NotModerator,
+    #[msg("Address is already a moderator")]
+    AlreadyModerator,
+    #[msg("Address is not a moderator")]
+    UnknownModerator,
+    #[msg("Channel has reached the maximum number of moderators")]
+    TooManyModerators,
+    #[msg("The owner and moderators cannot be muted")]
+    CannotMuteModerator,
+    #[msg("Sender is muted in this channel")]
+    SenderMuted,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn channel(subscription_fee: u64) -> Channel {
+        Channel {
+            owner: Pubkey::new_unique(),
+            name_hash: channel_name_hash("news"),
+            name: "news".to_string(),
+            subscription_mint: Pubkey::new_unique(),
+            subscription_fee,
+            readers_only: true,
+            claimable: 0,
+            moderators: Vec::new(),
+            pinned_message: None,
+            subscriber_count: 1,
+            post_count: 0,
+            created_at: 0,
+            bump: 255,
+        }
+    }
+
+    #[test]
+    fn subscription_lapses_at_the_end_of_its_paid_epochs() {
+        let subscription = Subscription {
+            channel: Pubkey::new_unique(),
+            subscriber: Pubkey::new_unique(),
+            subscribed_at: 0,
+            paid_until: CHANNEL_EPOCH,
+            bump: 255,
+        };
+        assert!(subscription.is_active(&channel(100_000), CHANNEL_EPOCH - 1));
+        assert!(!subscription.is_active(&channel(100_000), CHANNEL_EPOCH));
+        // Free channels never lapse
+        assert!(subscription.is_active(&channel(0), i64::MAX));
+    }
+}
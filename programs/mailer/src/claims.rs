@@ -0,0 +1,1121 @@
+//! Revenue share claims: recipient and owner payouts, expiry sweeping and
+//! claim account lifecycle.
+//!
+//! A claim is paid out either as a lump sum that expires `CLAIM_PERIOD` after
+//! its first deposit, or, if the recipient opts into `ClaimMode::Streaming`,
+//! unlocked linearly over `CLAIM_PERIOD` with no expiry cliff. Each streaming
+//! deposit restarts the stream for everything still locked, so a deposit never
+//! unlocks faster than over a full period.
+
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{self, Mint, TokenAccount, TokenInterface, TransferChecked};
+use mailbox_common::seeds;
+
+use crate::admin::{grow_account, MailerError, MailerState};
+use crate::compliance::ComplianceError;
+use crate::expiry;
+use crate::fees::{split_expired, FeeError, FeeSnapshot, FeeSource, OwnerBucket};
+use crate::tokens::{claim_mint, TokenError};
+use crate::vesting::VESTING_CLIFF;
+
+/// Claim period for revenue shares: 60 days in seconds
+pub const CLAIM_PERIOD: i64 = 60 * 24 * 60 * 60;
+
+/// Length of a day in seconds, the unit of `ping_claims` windows
+pub const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
+
+/// Maximum a single grace-period extension may push out a claim: 30 days in seconds
+pub const MAX_CLAIM_EXTENSION: i64 = 30 * 24 * 60 * 60;
+const _: () = assert!(MAX_CLAIM_EXTENSION < CLAIM_PERIOD);
+
+/// Whether a claim recorded at `timestamp` can no longer be claimed at `current_time`
+pub fn is_claim_expired(timestamp: i64, current_time: i64) -> bool {
+    current_time > timestamp.saturating_add(CLAIM_PERIOD)
+}
+
+/// Whether a claim recorded at `timestamp` expires within `window` seconds of
+/// `current_time` and has not expired yet
+pub fn is_expiring_within(timestamp: i64, window: i64, current_time: i64) -> bool {
+    let expires_at = timestamp.saturating_add(CLAIM_PERIOD);
+    current_time >= expires_at.saturating_sub(window) && !is_claim_expired(timestamp, current_time)
+}
+
+/// Portion of a streaming claim unlocked at `current_time`
+///
+/// The stream's balance plus what was already withdrawn from it unlocks
+/// linearly over `CLAIM_PERIOD` from `timestamp`. Deposits restart the stream
+/// (see `RecipientClaim::restream`) rather than join it.
+pub fn unlocked_amount(
+    amount: u64,
+    streamed: u64,
+    timestamp: i64,
+    current_time: i64,
+) -> Result<u64> {
+    let total = amount.checked_add(streamed).ok_or(FeeError::MathOverflow)?;
+    let elapsed = current_time.saturating_sub(timestamp).clamp(0, CLAIM_PERIOD);
+    let unlocked = (total as u128)
+        .checked_mul(elapsed as u128)
+        .ok_or(FeeError::MathOverflow)?
+        / CLAIM_PERIOD as u128;
+    // `unlocked <= total`, so the cast is lossless
+    Ok((unlocked as u64).saturating_sub(streamed).min(amount))
+}
+
+pub fn set_claim_mode(ctx: Context<SetClaimMode>, mode: ClaimMode) -> Result<()> {
+    let claim = &mut ctx.accounts.recipient_claim;
+    let recipient = ctx.accounts.recipient.key();
+
+    // Switching mid-period would change the terms of shares already deposited
+    require!(claim.amount == 0, ClaimError::ClaimNotEmpty);
+
+    if claim.recipient == Pubkey::default() {
+        claim.rent_payer = recipient;
+    }
+    claim.recipient = recipient;
+    claim.bump = ctx.bumps.recipient_claim;
+    claim.mode = mode;
+
+    emit!(ClaimModeUpdated { recipient, mode });
+
+    Ok(())
+}
+
+pub fn claim_recipient_share(ctx: Context<ClaimRecipientShare>) -> Result<()> {
+    let claim = &mut ctx.accounts.recipient_claim;
+    let recipient = ctx.accounts.recipient.key();
+
+    require!(claim.amount > 0, MailerError::NoClaimableAmount);
+
+    let current_time = Clock::get()?.unix_timestamp;
+    let amount = match claim.mode {
+        ClaimMode::Lump => {
+            // Check if claim period has expired
+            require!(
+                !is_claim_expired(claim.timestamp, current_time),
+                MailerError::ClaimPeriodExpired
+            );
+            // Shares still vesting stay behind, keeping the claim open and tracked
+            let unlocked = claim.available(current_time)?;
+            require!(unlocked > 0, MailerError::NoClaimableAmount);
+            if unlocked == claim.amount {
+                expiry::untrack(&ctx.accounts.expiry_index, recipient, claim.timestamp)?;
+            }
+            unlocked
+        }
+        ClaimMode::Streaming => {
+            let unlocked = claim.available(current_time)?;
+            require!(unlocked > 0, MailerError::NoClaimableAmount);
+            unlocked
+        }
+    };
+    claim.withdraw(amount)?;
+
+    // Transfer the claim's mint from mailer to recipient
+    let bump = ctx.accounts.mailer.bump;
+    let seeds = &[seeds::MAILER, &[bump]];
+    let signer_seeds = &[&seeds[..]];
+
+    let transfer_ctx = CpiContext::new_with_signer(
+        ctx.accounts.token_program.to_account_info(),
+        TransferChecked {
+            from: ctx.accounts.mailer_usdc_account.to_account_info(),
+            mint: ctx.accounts.mint.to_account_info(),
+            to: ctx.accounts.recipient_usdc_account.to_account_info(),
+            authority: ctx.accounts.mailer.to_account_info(),
+        },
+        signer_seeds,
+    );
+    token_interface::transfer_checked(transfer_ctx, amount, ctx.accounts.mint.decimals)?;
+
+    emit!(RecipientClaimed { recipient, amount });
+
+    Ok(())
+}
+
+pub fn close_claim(ctx: Context<CloseClaim>) -> Result<()> {
+    let claim = &ctx.accounts.recipient_claim;
+    require!(claim.amount == 0, ClaimError::ClaimNotEmpty);
+    // Send credit is only spendable through the claim, so closing would strand it
+    require!(claim.send_credit == 0, ClaimError::ClaimHasSendCredit);
+
+    emit!(ClaimClosed {
+        recipient: claim.recipient,
+    });
+
+    Ok(())
+}
+
+pub fn migrate_claim(ctx: Context<MigrateClaim>, recipient: Pubkey) -> Result<()> {
+    let claim_info = ctx.accounts.recipient_claim.to_account_info();
+    grow_account::<RecipientClaim>(
+        &claim_info,
+        8 + RecipientClaim::INIT_SPACE,
+        &ctx.accounts.payer.to_account_info(),
+        &ctx.accounts.system_program.to_account_info(),
+    )?;
+
+    let mut data = claim_info.try_borrow_mut_data()?;
+    let mut claim = RecipientClaim::try_deserialize(&mut &data[..])?;
+    // Claims from before the rent payer was recorded refund their rent to the recipient
+    if claim.rent_payer == Pubkey::default() {
+        claim.rent_payer = recipient;
+    }
+    let mut writer: &mut [u8] = &mut data[..];
+    claim.try_serialize(&mut writer)?;
+
+    Ok(())
+}
+
+pub fn claim_owner_share(ctx: Context<ClaimOwnerShare>) -> Result<()> {
+    let mailer = &mut ctx.accounts.mailer;
+    let owner_bucket = &mut ctx.accounts.owner_bucket;
+
+    // Send fees accrued before multi-token support are held on MailerState in USDC
+    let legacy_amount =
+        if owner_bucket.source == FeeSource::Send && owner_bucket.mint == mailer.usdc_mint {
+            mailer.owner_claimable
+        } else {
+            0
+        };
+    let amount = owner_bucket
+        .claimable
+        .checked_add(legacy_amount)
+        .ok_or(FeeError::MathOverflow)?;
+    require!(amount > 0, MailerError::NoClaimableAmount);
+
+    owner_bucket.claimable = 0;
+    mailer.owner_claimable = mailer
+        .owner_claimable
+        .checked_sub(legacy_amount)
+        .ok_or(FeeError::MathOverflow)?;
+
+    // Transfer the bucket's mint from mailer to the bucket's destination
+    let bump = mailer.bump;
+    let seeds = &[seeds::MAILER, &[bump]];
+    let signer_seeds = &[&seeds[..]];
+
+    let transfer_ctx = CpiContext::new_with_signer(
+        ctx.accounts.token_program.to_account_info(),
+        TransferChecked {
+            from: ctx.accounts.mailer_usdc_account.to_account_info(),
+            mint: ctx.accounts.mint.to_account_info(),
+            to: ctx.accounts.destination_usdc_account.to_account_info(),
+            authority: ctx.accounts.mailer.to_account_info(),
+        },
+        signer_seeds,
+    );
+    token_interface::transfer_checked(transfer_ctx, amount, ctx.accounts.mint.decimals)?;
+
+    emit!(OwnerClaimed {
+        amount,
+        mint: ctx.accounts.owner_bucket.mint,
+        source: ctx.accounts.owner_bucket.source,
+        destination: ctx.accounts.owner_bucket.destination,
+    });
+
+    Ok(())
+}
+
+pub fn claim_expired_shares(ctx: Context<ClaimExpiredShares>) -> Result<()> {
+    let recipient_key = ctx.accounts.recipient_claim.recipient;
+    let claim = &mut ctx.accounts.recipient_claim;
+
+    require!(claim.amount > 0, MailerError::NoClaimableAmount);
+
+    // Check if claim period has expired; streaming claims never do
+    let current_time = Clock::get()?.unix_timestamp;
+    require!(
+        claim.mode == ClaimMode::Lump && is_claim_expired(claim.timestamp, current_time),
+        MailerError::ClaimPeriodNotExpired
+    );
+
+    expiry::untrack(&ctx.accounts.expiry_index, recipient_key, claim.timestamp)?;
+
+    let amount = claim.amount;
+    let (bounty, rolled_over, owner_amount) = split_expired(
+        amount,
+        ctx.accounts.mailer.crank_bounty_bps,
+        ctx.accounts.mailer.rollover_bps,
+    )?;
+    claim.amount = 0;
+    claim.timestamp = 0;
+    claim.send_credit = claim
+        .send_credit
+        .checked_add(rolled_over)
+        .ok_or(FeeError::MathOverflow)?;
+
+    // Add expired amount, less the bounty and rollover, to owner claimable
+    ctx.accounts.owner_bucket.credit(owner_amount)?;
+
+    if bounty > 0 {
+        let bump = ctx.accounts.mailer.bump;
+        let seeds = &[seeds::MAILER, &[bump]];
+        let signer_seeds = &[&seeds[..]];
+
+        let transfer_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            TransferChecked {
+                from: ctx.accounts.mailer_usdc_account.to_account_info(),
+                mint: ctx.accounts.mint.to_account_info(),
+                to: ctx.accounts.cranker_usdc_account.to_account_info(),
+                authority: ctx.accounts.mailer.to_account_info(),
+            },
+            signer_seeds,
+        );
+        token_interface::transfer_checked(transfer_ctx, bounty, ctx.accounts.mint.decimals)?;
+    }
+
+    emit!(ExpiredSharesClaimed {
+        recipient: recipient_key,
+        amount,
+        cranker: ctx.accounts.cranker.key(),
+        bounty,
+        rolled_over,
+    });
+
+    Ok(())
+}
+
+pub fn claim_expired_shares_batch<'info>(
+    ctx: Context<'_, '_, 'info, 'info, ClaimExpiredSharesBatch<'info>>,
+) -> Result<()> {
+    let cranker = ctx.accounts.cranker.key();
+    let bounty_bps = ctx.accounts.mailer.crank_bounty_bps;
+    let rollover_bps = ctx.accounts.mailer.rollover_bps;
+    let mint = ctx.accounts.owner_bucket.mint;
+    let current_time = Clock::get()?.unix_timestamp;
+
+    let mut swept_any = false;
+    let mut total_owner_amount: u64 = 0;
+    let mut total_bounty: u64 = 0;
+
+    // Each claim is followed by its expiry bucket
+    require!(
+        ctx.remaining_accounts.len().is_multiple_of(2),
+        ClaimError::InvalidClaimAccount
+    );
+    for pair in ctx.remaining_accounts.chunks_exact(2) {
+        let (account_info, expiry_index) = (&pair[0], &pair[1]);
+        require!(account_info.is_writable, ClaimError::InvalidClaimAccount);
+
+        let mut claim: Account<RecipientClaim> = Account::try_from(account_info)?;
+        let (expected_pda, _) = Pubkey::find_program_address(
+            &[b"claim", claim.recipient.as_ref()],
+            ctx.program_id,
+        );
+        require_keys_eq!(expected_pda, account_info.key(), ClaimError::InvalidClaimAccount);
+
+        // Claims in other mints belong to a batch for that mint
+        if claim.amount == 0
+            || claim.mode == ClaimMode::Streaming
+            || !is_claim_expired(claim.timestamp, current_time)
+            || claim_mint(&claim, &ctx.accounts.mailer) != mint
+        {
+            continue;
+        }
+
+        expiry::untrack(expiry_index, claim.recipient, claim.timestamp)?;
+
+        let amount = claim.amount;
+        let (bounty, rolled_over, owner_amount) = split_expired(amount, bounty_bps, rollover_bps)?;
+        claim.amount = 0;
+        claim.timestamp = 0;
+        claim.send_credit = claim
+            .send_credit
+            .checked_add(rolled_over)
+            .ok_or(FeeError::MathOverflow)?;
+        claim.exit(ctx.program_id)?;
+
+        swept_any = true;
+        total_owner_amount = total_owner_amount
+            .checked_add(owner_amount)
+            .ok_or(FeeError::MathOverflow)?;
+        total_bounty = total_bounty.checked_add(bounty).ok_or(FeeError::MathOverflow)?;
+
+        emit!(ExpiredSharesClaimed {
+            recipient: claim.recipient,
+            amount,
+            cranker,
+            bounty,
+            rolled_over,
+        });
+    }
+
+    require!(swept_any, MailerError::NoClaimableAmount);
+
+    ctx.accounts.owner_bucket.credit(total_owner_amount)?;
+
+    if total_bounty > 0 {
+        let bump = ctx.accounts.mailer.bump;
+        let seeds = &[seeds::MAILER, &[bump]];
+        let signer_seeds = &[&seeds[..]];
+
+        let transfer_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            TransferChecked {
+                from: ctx.accounts.mailer_usdc_account.to_account_info(),
+                mint: ctx.accounts.mint.to_account_info(),
+                to: ctx.accounts.cranker_usdc_account.to_account_info(),
+                authority: ctx.accounts.mailer.to_account_info(),
+            },
+            signer_seeds,
+        );
+        token_interface::transfer_checked(transfer_ctx, total_bounty, ctx.accounts.mint.decimals)?;
+    }
+
+    Ok(())
+}
+
+pub fn ping_claims<'info>(
+    ctx: Context<'_, '_, 'info, 'info, PingClaims<'info>>,
+    within_days: u16,
+) -> Result<()> {
+    let window = i64::from(within_days) * SECONDS_PER_DAY;
+    require!(
+        within_days > 0 && window <= CLAIM_PERIOD,
+        ClaimError::InvalidPingWindow
+    );
+    let current_time = Clock::get()?.unix_timestamp;
+
+    for account_info in ctx.remaining_accounts.iter() {
+        let claim: Account<RecipientClaim> = Account::try_from(account_info)?;
+        let (expected_pda, _) = Pubkey::find_program_address(
+            &[b"claim", claim.recipient.as_ref()],
+            ctx.program_id,
+        );
+        require_keys_eq!(expected_pda, account_info.key(), ClaimError::InvalidClaimAccount);
+
+        // Streaming claims never expire
+        if claim.amount == 0
+            || claim.mode == ClaimMode::Streaming
+            || !is_expiring_within(claim.timestamp, window, current_time)
+        {
+            continue;
+        }
+
+        emit!(ClaimExpiringSoon {
+            recipient: claim.recipient,
+            amount: claim.amount,
+            expires_at: claim.timestamp.saturating_add(CLAIM_PERIOD),
+        });
+    }
+
+    Ok(())
+}
+
+pub fn extend_claim(ctx: Context<ExtendClaim>, recipient: Pubkey, extra_seconds: i64) -> Result<()> {
+    let claim = &mut ctx.accounts.recipient_claim;
+    require!(claim.amount > 0, MailerError::NoClaimableAmount);
+    // Streaming claims have no expiry to extend
+    require!(
+        claim.mode == ClaimMode::Lump
+            && extra_seconds > 0
+            && extra_seconds <= MAX_CLAIM_EXTENSION,
+        ClaimError::InvalidExtension
+    );
+
+    let current_time = Clock::get()?.unix_timestamp;
+    let new_timestamp = claim
+        .timestamp
+        .checked_add(extra_seconds)
+        .ok_or(FeeError::MathOverflow)?;
+    require!(new_timestamp <= current_time, ClaimError::InvalidExtension);
+
+    // Move the claim to the bucket for its new expiry week
+    expiry::untrack(&ctx.accounts.old_expiry_index, recipient, claim.timestamp)?;
+    expiry::track(
+        &ctx.accounts.new_expiry_index,
+        &ctx.accounts.owner.to_account_info(),
+        &ctx.accounts.system_program.to_account_info(),
+        recipient,
+        new_timestamp,
+    )?;
+
+    // Both timestamps are at most `current_time`, so adding the claim period cannot overflow
+    let old_expires_at = claim.timestamp + CLAIM_PERIOD;
+    claim.timestamp = new_timestamp;
+
+    emit!(ClaimExtended {
+        recipient,
+        old_expires_at,
+        new_expires_at: new_timestamp + CLAIM_PERIOD,
+    });
+
+    Ok(())
+}
+
+pub fn get_claimable(ctx: Context<GetClaimable>, recipient: Pubkey) -> Result<ClaimableView> {
+    let mailer = &ctx.accounts.mailer;
+    let account = &ctx.accounts.recipient_claim;
+    let claim = if account.owner == &crate::ID && !account.data_is_empty() {
+        let data = account.try_borrow_data()?;
+        RecipientClaim::try_deserialize(&mut &data[..])?
+    } else {
+        // Never received a share: report an empty claim in the default mint
+        RecipientClaim {
+            recipient,
+            amount: 0,
+            timestamp: 0,
+            bump: 0,
+            rent_payer: Pubkey::default(),
+            send_credit: 0,
+            fee_snapshot: FeeSnapshot::default(),
+            mint: Pubkey::default(),
+            mode: ClaimMode::Lump,
+            streamed: 0,
+            auto_claim: false,
+            vested: 0,
+            vesting_start: 0,
+            released: 0,
+        }
+    };
+    claimable_view(&claim, mailer, Clock::get()?.unix_timestamp)
+}
+
+/// What `claim` holds for its recipient at `current_time`
+pub fn claimable_view(claim: &RecipientClaim, mailer: &MailerState, current_time: i64) -> Result<ClaimableView> {
+    let active = claim.amount > 0;
+    Ok(ClaimableView {
+        recipient: claim.recipient,
+        mint: claim_mint(claim, mailer),
+        amount: claim.amount,
+        available: claim.available(current_time)?,
+        send_credit: claim.send_credit,
+        mode: claim.mode,
+        expires_at: if active {
+            claim.timestamp.saturating_add(CLAIM_PERIOD)
+        } else {
+            0
+        },
+        is_expired: active
+            && claim.mode == ClaimMode::Lump
+            && is_claim_expired(claim.timestamp, current_time),
+    })
+}
+
+#[derive(Accounts)]
+pub struct SetClaimMode<'info> {
+    #[account(
+        init_if_needed,
+        payer = recipient,
+        space = 8 + RecipientClaim::INIT_SPACE,
+        seeds = [b"claim", recipient.key().as_ref()],
+        bump
+    )]
+    pub recipient_claim: Account<'info, RecipientClaim>,
+
+    #[account(mut)]
+    pub recipient: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimRecipientShare<'info> {
+    #[account(
+        mut,
+        seeds = [b"claim", recipient.key().as_ref()],
+        bump,
+        has_one = recipient @ MailerError::InvalidRecipient
+    )]
+    pub recipient_claim: Account<'info, RecipientClaim>,
+
+    #[account(seeds = [seeds::MAILER], bump = mailer.bump)]
+    pub mailer: Account<'info, MailerState>,
+
+    pub recipient: Signer<'info>,
+
+    #[account(
+        constraint = claim_mint(&recipient_claim, &mailer) == mint.key() @ TokenError::ClaimMintMismatch
+    )]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = recipient,
+        associated_token::token_program = token_program
+    )]
+    pub recipient_usdc_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = mailer,
+        associated_token::token_program = token_program
+    )]
+    pub mailer_usdc_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// CHECK: Expiry bucket for the claim's current week, verified in `expiry::untrack`
+    #[account(mut)]
+    pub expiry_index: UncheckedAccount<'info>,
+
+    /// CHECK: Recipient's compliance denylist marker; must not exist
+    #[account(
+        seeds = [b"deny", recipient.key().as_ref()],
+        bump,
+        constraint = denylist.data_is_empty() @ ComplianceError::AddressDenied
+    )]
+    pub denylist: UncheckedAccount<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+/// Read-only: anyone may query any recipient's claim
+#[derive(Accounts)]
+#[instruction(recipient: Pubkey)]
+pub struct GetClaimable<'info> {
+    #[account(seeds = [seeds::MAILER], bump = mailer.bump)]
+    pub mailer: Account<'info, MailerState>,
+
+    /// CHECK: Recipient's claim PDA; may be uninitialized, parsed in `get_claimable`
+    #[account(seeds = [b"claim", recipient.as_ref()], bump)]
+    pub recipient_claim: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CloseClaim<'info> {
+    #[account(
+        mut,
+        close = rent_payer,
+        seeds = [b"claim", recipient.key().as_ref()],
+        bump,
+        has_one = recipient @ MailerError::InvalidRecipient,
+        has_one = rent_payer @ ClaimError::InvalidRentPayer
+    )]
+    pub recipient_claim: Account<'info, RecipientClaim>,
+
+    pub recipient: Signer<'info>,
+
+    /// CHECK: Original rent payer recorded on the claim, validated by `has_one`
+    #[account(mut)]
+    pub rent_payer: UncheckedAccount<'info>,
+}
+
+/// Permissionless: growing a claim only appends zeroed fields, paid for by `payer`
+#[derive(Accounts)]
+#[instruction(recipient: Pubkey)]
+pub struct MigrateClaim<'info> {
+    /// CHECK: Recipient's claim PDA, possibly in an older layout; checked in `admin::grow_account`
+    #[account(mut, seeds = [b"claim", recipient.as_ref()], bump)]
+    pub recipient_claim: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimOwnerShare<'info> {
+    #[account(
+        mut,
+        seeds = [seeds::MAILER],
+        bump = mailer.bump,
+        has_one = owner @ MailerError::OnlyOwner
+    )]
+    pub mailer: Account<'info, MailerState>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    /// Bucket being withdrawn; any fee source may be withdrawn independently
+    #[account(
+        mut,
+        seeds = [b"bucket", owner_bucket.mint.as_ref(), owner_bucket.source.seed()],
+        bump = owner_bucket.bump
+    )]
+    pub owner_bucket: Account<'info, OwnerBucket>,
+
+    #[account(address = owner_bucket.mint)]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = owner_bucket.destination,
+        associated_token::token_program = token_program
+    )]
+    pub destination_usdc_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = mailer,
+        associated_token::token_program = token_program
+    )]
+    pub mailer_usdc_account: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimExpiredShares<'info> {
+    #[account(
+        mut,
+        seeds = [b"claim", recipient_claim.recipient.as_ref()],
+        bump
+    )]
+    pub recipient_claim: Account<'info, RecipientClaim>,
+
+    #[account(seeds = [seeds::MAILER], bump = mailer.bump)]
+    pub mailer: Account<'info, MailerState>,
+
+    /// Owner share bucket for expired shares in this mint
+    #[account(
+        mut,
+        seeds = [b"bucket", mint.key().as_ref(), FeeSource::Expired.seed()],
+        bump = owner_bucket.bump
+    )]
+    pub owner_bucket: Account<'info, OwnerBucket>,
+
+    #[account(
+        constraint = claim_mint(&recipient_claim, &mailer) == mint.key() @ TokenError::ClaimMintMismatch
+    )]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    pub cranker: Signer<'info>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = cranker,
+        associated_token::token_program = token_program
+    )]
+    pub cranker_usdc_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = mailer,
+        associated_token::token_program = token_program
+    )]
+    pub mailer_usdc_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// CHECK: Expiry bucket for the claim's current week, verified in `expiry::untrack`
+    #[account(mut)]
+    pub expiry_index: UncheckedAccount<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimExpiredSharesBatch<'info> {
+    #[account(seeds = [seeds::MAILER], bump = mailer.bump)]
+    pub mailer: Account<'info, MailerState>,
+
+    /// Owner share bucket for expired shares in this mint
+    #[account(
+        mut,
+        seeds = [b"bucket", mint.key().as_ref(), FeeSource::Expired.seed()],
+        bump = owner_bucket.bump
+    )]
+    pub owner_bucket: Account<'info, OwnerBucket>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    pub cranker: Signer<'info>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = cranker,
+        associated_token::token_program = token_program
+    )]
+    pub cranker_usdc_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = mailer,
+        associated_token::token_program = token_program
+    )]
+    pub mailer_usdc_account: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+/// Permissionless: the claims to check are passed as remaining accounts
+#[derive(Accounts)]
+pub struct PingClaims<'info> {
+    #[account(seeds = [seeds::MAILER], bump = mailer.bump)]
+    pub mailer: Account<'info, MailerState>,
+}
+
+#[derive(Accounts)]
+#[instruction(recipient: Pubkey)]
+pub struct ExtendClaim<'info> {
+    #[account(
+        mut,
+        seeds = [b"claim", recipient.as_ref()],
+        bump
+    )]
+    pub recipient_claim: Account<'info, RecipientClaim>,
+
+    #[account(
+        seeds = [seeds::MAILER],
+        bump = mailer.bump,
+        has_one = owner @ MailerError::OnlyOwner
+    )]
+    pub mailer: Account<'info, MailerState>,
+
+    /// Pays for the new expiry bucket if this is its first claim
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    /// CHECK: Expiry bucket for the claim's current week, verified in `expiry::untrack`
+    #[account(mut)]
+    pub old_expiry_index: UncheckedAccount<'info>,
+
+    /// CHECK: Expiry bucket for the extended week, verified in `expiry::track`
+    #[account(mut)]
+    pub new_expiry_index: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct RecipientClaim {
+    pub recipient: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+    pub bump: u8,
+    /// Account that funded the claim PDA and gets its rent back on close
+    pub rent_payer: Pubkey,
+    /// Expired shares rolled over into credit only spendable on sends
+    pub send_credit: u64,
+    /// Fee terms of the most recent deposit
+    pub fee_snapshot: FeeSnapshot,
+    /// Mint `amount` and `send_credit` are denominated in; default means `usdc_mint`
+    pub mint: Pubkey,
+    pub mode: ClaimMode,
+    /// Withdrawn so far from the current stream; only used by `ClaimMode::Streaming`
+    pub streamed: u64,
+    /// Whether the registered auto-claimer may pay the claim out shortly before it expires
+    pub auto_claim: bool,
+    /// Shares locked by the most recent `send_priority_vested`, including earlier ones still locked then
+    pub vested: u64,
+    /// Start of the current vesting cliff; `vested` unlocks `VESTING_CLIFF` after it
+    pub vesting_start: i64,
+    /// Unlocked by earlier streams and not yet withdrawn; only used by `ClaimMode::Streaming`
+    pub released: u64,
+}
+
+impl RecipientClaim {
+    /// Amount the recipient may withdraw or spend at `current_time`
+    pub fn available(&self, current_time: i64) -> Result<u64> {
+        match self.mode {
+            ClaimMode::Lump if is_claim_expired(self.timestamp, current_time) => Ok(0),
+            ClaimMode::Lump => Ok(self.amount - self.locked(current_time)),
+            ClaimMode::Streaming => {
+                let stream = self
+                    .amount
+                    .checked_sub(self.released)
+                    .ok_or(FeeError::MathOverflow)?;
+                let unlocked =
+                    unlocked_amount(stream, self.streamed, self.timestamp, current_time)?;
+                // `unlocked <= stream`, so the sum is at most `amount`
+                Ok(self.released + unlocked)
+            }
+        }
+    }
+
+    /// Set aside what a streaming claim has unlocked by `current_time` and
+    /// restart the stream there for the rest, ahead of a deposit
+    pub fn restream(&mut self, current_time: i64) -> Result<()> {
+        self.released = self.available(current_time)?;
+        self.timestamp = current_time;
+        self.streamed = 0;
+        Ok(())
+    }
+
+    /// Portion of `amount` still behind the vesting cliff at `current_time`
+    pub fn locked(&self, current_time: i64) -> u64 {
+        if current_time < self.vesting_start.saturating_add(VESTING_CLIFF) {
+            self.vested.min(self.amount)
+        } else {
+            0
+        }
+    }
+
+    /// Take `amount` out of the claim, ending the period once it is empty
+    pub fn withdraw(&mut self, amount: u64) -> Result<()> {
+        self.amount = self.amount.checked_sub(amount).ok_or(FeeError::MathOverflow)?;
+        if self.mode == ClaimMode::Streaming {
+            // Earlier streams' releases are paid out before the current stream
+            let from_released = amount.min(self.released);
+            self.released -= from_released;
+            self.streamed = self
+                .streamed
+                .checked_add(amount - from_released)
+                .ok_or(FeeError::MathOverflow)?;
+        }
+        if self.amount == 0 {
+            self.timestamp = 0;
+            self.streamed = 0;
+            self.released = 0;
+            self.vested = 0;
+            self.vesting_start = 0;
+        }
+        Ok(())
+    }
+}
+
+/// A recipient's claim at the time of the query, returned by `get_claimable`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ClaimableView {
+    pub recipient: Pubkey,
+    /// Mint `amount` and `send_credit` are denominated in
+    pub mint: Pubkey,
+    pub amount: u64,
+    /// Portion of `amount` that can be withdrawn right now
+    pub available: u64,
+    pub send_credit: u64,
+    pub mode: ClaimMode,
+    /// End of the claim period (for streaming claims, when fully unlocked); 0 when empty
+    pub expires_at: i64,
+    pub is_expired: bool,
+}
+
+/// How a recipient's revenue shares are paid out
+#[derive(
+    AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq, InitSpace,
+)]
+pub enum ClaimMode {
+    /// Fully claimable at once until the claim period expires
+    #[default]
+    Lump,
+    /// Unlocks linearly over the claim period and never expires; each deposit
+    /// restarts the period for whatever is still locked
+    Streaming,
+}
+
+#[event]
+pub struct RecipientClaimed {
+    pub recipient: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct ClaimModeUpdated {
+    pub recipient: Pubkey,
+    pub mode: ClaimMode,
+}
+
+#[event]
+pub struct ClaimClosed {
+    pub recipient: Pubkey,
+}
+
+#[event]
+pub struct OwnerClaimed {
+    pub amount: u64,
+    pub mint: Pubkey,
+    pub source: FeeSource,
+    pub destination: Pubkey,
+}
+
+#[event]
+pub struct ExpiredSharesClaimed {
+    pub recipient: Pubkey,
+    pub amount: u64,
+    pub cranker: Pubkey,
+    pub bounty: u64,
+    pub rolled_over: u64,
+}
+
+/// Emitted by `ping_claims` for each lump claim nearing expiry, for notification services
+#[event]
+pub struct ClaimExpiringSoon {
+    pub recipient: Pubkey,
+    pub amount: u64,
+    pub expires_at: i64,
+}
+
+#[event]
+pub struct ClaimExtended {
+    pub recipient: Pubkey,
+    pub old_expires_at: i64,
+    pub new_expires_at: i64,
+}
+
+#[error_code(offset = 6100)]
+pub enum ClaimError {
+    #[msg("Claim account still holds an unclaimed amount")]
+    ClaimNotEmpty,
+    #[msg("Rent payer does not match the claim account")]
+    InvalidRentPayer,
+    #[msg("Account is not a writable recipient claim PDA")]
+    InvalidClaimAccount,
+    #[msg("Claim extension is out of range")]
+    InvalidExtension,
+    #[msg("Expiry index does not match the claim's expiry week")]
+    InvalidExpiryIndex,
+    #[msg("Ping window must be 1 to 60 days")]
+    InvalidPingWindow,
+    #[msg("Claim account still holds send credit")]
+    ClaimHasSendCredit,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn claimable_view_reports_expiry_of_lump_claims_only() {
+        let usdc = Pubkey::new_unique();
+        let mailer = crate::tokens::tests::mailer_with_usdc(usdc);
+        let mut claim = RecipientClaim {
+            recipient: Pubkey::new_unique(),
+            amount: 90_000,
+            timestamp: 1_000,
+            bump: 0,
+            rent_payer: Pubkey::default(),
+            send_credit: 0,
+            fee_snapshot: FeeSnapshot::default(),
+            mint: Pubkey::default(),
+            mode: ClaimMode::Lump,
+            streamed: 0,
+            auto_claim: false,
+            vested: 0,
+            vesting_start: 0,
+            released: 0,
+        };
+
+        let view = claimable_view(&claim, &mailer, 1_000 + CLAIM_PERIOD + 1).unwrap();
+        assert_eq!(view.mint, usdc);
+        assert_eq!(view.expires_at, 1_000 + CLAIM_PERIOD);
+        assert!(view.is_expired);
+        assert_eq!(view.available, 0);
+
+        claim.mode = ClaimMode::Streaming;
+        let view = claimable_view(&claim, &mailer, 1_000 + CLAIM_PERIOD + 1).unwrap();
+        assert!(!view.is_expired);
+        assert_eq!(view.available, 90_000);
+    }
+
+    #[test]
+    fn claim_extends_the_original_layout() {
+        let claim = RecipientClaim {
+            recipient: Pubkey::new_unique(),
+            amount: 90_000,
+            timestamp: 1_000,
+            bump: 254,
+            rent_payer: Pubkey::new_unique(),
+            send_credit: 5,
+            fee_snapshot: FeeSnapshot::default(),
+            mint: Pubkey::default(),
+            mode: ClaimMode::Lump,
+            streamed: 0,
+            auto_claim: false,
+            vested: 0,
+            vesting_start: 0,
+            released: 0,
+        };
+        let original = (claim.recipient, claim.amount, claim.timestamp, claim.bump);
+        assert!(claim
+            .try_to_vec()
+            .unwrap()
+            .starts_with(&original.try_to_vec().unwrap()));
+    }
+
+    #[test]
+    fn vested_shares_are_unavailable_until_the_cliff() {
+        let mut claim = RecipientClaim {
+            recipient: Pubkey::new_unique(),
+            amount: 95_000,
+            timestamp: 1_000,
+            bump: 0,
+            rent_payer: Pubkey::default(),
+            send_credit: 0,
+            fee_snapshot: FeeSnapshot::default(),
+            mint: Pubkey::default(),
+            mode: ClaimMode::Lump,
+            streamed: 0,
+            auto_claim: false,
+            vested: 60_000,
+            vesting_start: 2_000,
+            released: 0,
+        };
+
+        assert_eq!(claim.available(2_000).unwrap(), 35_000);
+        assert_eq!(claim.available(2_000 + VESTING_CLIFF - 1).unwrap(), 35_000);
+        assert_eq!(claim.available(2_000 + VESTING_CLIFF).unwrap(), 95_000);
+
+        claim.withdraw(35_000).unwrap();
+        assert_eq!(claim.available(2_000).unwrap(), 0);
+        claim.withdraw(60_000).unwrap();
+        assert_eq!((claim.vested, claim.vesting_start), (0, 0));
+    }
+
+    #[test]
+    fn claim_is_claimable_through_last_second_of_period() {
+        assert!(!is_claim_expired(1_000, 1_000 + CLAIM_PERIOD));
+        assert!(is_claim_expired(1_000, 1_000 + CLAIM_PERIOD + 1));
+    }
+
+    #[test]
+    fn streaming_claim_unlocks_linearly_over_claim_period() {
+        let start = 1_000;
+        assert_eq!(unlocked_amount(60_000, 0, start, start).unwrap(), 0);
+        assert_eq!(unlocked_amount(60_000, 0, start, start + CLAIM_PERIOD / 4).unwrap(), 15_000);
+        assert_eq!(unlocked_amount(60_000, 0, start, start + CLAIM_PERIOD).unwrap(), 60_000);
+        assert_eq!(unlocked_amount(60_000, 0, start, start + 2 * CLAIM_PERIOD).unwrap(), 60_000);
+    }
+
+    #[test]
+    fn streaming_claim_excludes_what_was_already_withdrawn() {
+        let start = 1_000;
+        let halfway = start + CLAIM_PERIOD / 2;
+        // Half of 60_000 unlocked and withdrawn; nothing more until time passes
+        assert_eq!(unlocked_amount(30_000, 30_000, start, halfway).unwrap(), 0);
+        assert_eq!(unlocked_amount(30_000, 30_000, start, start + CLAIM_PERIOD).unwrap(), 30_000);
+        assert_eq!(unlocked_amount(u64::MAX, 0, start, start + CLAIM_PERIOD).unwrap(), u64::MAX);
+        assert!(unlocked_amount(u64::MAX, 1, start, halfway).is_err());
+    }
+
+    #[test]
+    fn streaming_deposit_unlocks_over_its_own_period() {
+        let start = 1_000;
+        let mut claim = RecipientClaim {
+            recipient: Pubkey::new_unique(),
+            amount: 60_000,
+            timestamp: start,
+            bump: 0,
+            rent_payer: Pubkey::default(),
+            send_credit: 0,
+            fee_snapshot: FeeSnapshot::default(),
+            mint: Pubkey::default(),
+            mode: ClaimMode::Streaming,
+            streamed: 0,
+            auto_claim: false,
+            vested: 0,
+            vesting_start: 0,
+            released: 0,
+        };
+
+        // Long after the first stream ends, a new deposit still starts locked
+        let later = start + 2 * CLAIM_PERIOD;
+        claim.restream(later).unwrap();
+        claim.amount += 40_000;
+        assert_eq!(claim.available(later).unwrap(), 60_000);
+        assert_eq!(claim.available(later + CLAIM_PERIOD / 2).unwrap(), 80_000);
+
+        // Withdrawals drain the released balance before the stream
+        claim.withdraw(70_000).unwrap();
+        assert_eq!((claim.released, claim.streamed), (0, 10_000));
+        assert_eq!(claim.available(later + CLAIM_PERIOD / 2).unwrap(), 10_000);
+        assert_eq!(claim.available(later + CLAIM_PERIOD).unwrap(), 30_000);
+    }
+
+    #[test]
+    fn claim_is_expiring_within_window_until_it_expires() {
+        let expires_at = 1_000 + CLAIM_PERIOD;
+        let week = 7 * SECONDS_PER_DAY;
+        assert!(!is_expiring_within(1_000, week, expires_at - week - 1));
+        assert!(is_expiring_within(1_000, week, expires_at - week));
+        assert!(is_expiring_within(1_000, week, expires_at));
+        assert!(!is_expiring_within(1_000, week, expires_at + 1));
+    }
+}
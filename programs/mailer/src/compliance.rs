@@ -0,0 +1,111 @@
+//! Owner-managed denylist of addresses barred from the mailer.
+//!
+//! The owner marks an address with a `Denylist` PDA at `[b"deny", address]`
+//! and removes the mark by closing it. Sends take the sender's marker (and,
+//! when addressed, the recipient's), and every instruction paying out to a
+//! user takes the payee's: recipient claims, escrow acknowledgements and
+//! channel fee withdrawals. Each fails with `AddressDenied` when the marker
+//! exists.
+
+use anchor_lang::prelude::*;
+use mailbox_common::seeds;
+
+use crate::admin::{MailerError, MailerState};
+
+pub fn deny_address(ctx: Context<DenyAddress>, address: Pubkey) -> Result<()> {
+    let denylist = &mut ctx.accounts.denylist;
+    denylist.address = address;
+    denylist.denied_at = Clock::get()?.unix_timestamp;
+    denylist.bump = ctx.bumps.denylist;
+
+    emit!(DenylistAdded {
+        address,
+        timestamp: denylist.denied_at,
+    });
+
+    Ok(())
+}
+
+pub fn undeny_address(ctx: Context<UndenyAddress>) -> Result<()> {
+    emit!(DenylistRemoved {
+        address: ctx.accounts.denylist.address,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(address: Pubkey)]
+pub struct DenyAddress<'info> {
+    /// Fails if the address is already denied
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + Denylist::INIT_SPACE,
+        seeds = [b"deny", address.as_ref()],
+        bump
+    )]
+    pub denylist: Account<'info, Denylist>,
+
+    #[account(
+        seeds = [seeds::MAILER],
+        bump = mailer.bump,
+        has_one = owner @ MailerError::OnlyOwner
+    )]
+    pub mailer: Account<'info, MailerState>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UndenyAddress<'info> {
+    /// Closed on removal; rent returns to the owner
+    #[account(
+        mut,
+        close = owner,
+        seeds = [b"deny", denylist.address.as_ref()],
+        bump = denylist.bump
+    )]
+    pub denylist: Account<'info, Denylist>,
+
+    #[account(
+        seeds = [seeds::MAILER],
+        bump = mailer.bump,
+        has_one = owner @ MailerError::OnlyOwner
+    )]
+    pub mailer: Account<'info, MailerState>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+}
+
+/// Marks an address barred from sending, receiving addressed mail and claiming
+#[account]
+#[derive(InitSpace)]
+pub struct Denylist {
+    pub address: Pubkey,
+    pub denied_at: i64,
+    pub bump: u8,
+}
+
+#[event]
+pub struct DenylistAdded {
+    pub address: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct DenylistRemoved {
+    pub address: Pubkey,
+    pub timestamp: i64,
+}
+
+#[error_code(offset = 7000)]
+pub enum ComplianceError {
+    #[msg("Address is on the compliance denylist")]
+    AddressDenied,
+}
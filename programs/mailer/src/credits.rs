@@ -0,0 +1,241 @@
+//! Prepaid credit.
+//!
+//! `deposit_credit` moves fee tokens into the mailer's account once and books
+//! them on the depositor's `Credit` PDA (`[b"credit", owner, mint]`). Sends
+//! that pass the PDA draw their fee from its balance, after any send credit
+//! and before transferring tokens, so a funded sender costs no token CPI per
+//! message. Unlike send credit, the balance is the depositor's own money and
+//! can be taken back at any time with `withdraw_credit`.
+
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{self, Mint, TokenAccount, TokenInterface, TransferChecked};
+use mailbox_common::seeds;
+
+use crate::admin::MailerState;
+use crate::escrow::release;
+use crate::fees::FeeError;
+use crate::tokens::{TokenConfig, TokenError};
+
+pub fn deposit_credit(ctx: Context<DepositCredit>, amount: u64) -> Result<()> {
+    require!(amount > 0, CreditError::InvalidCreditAmount);
+
+    let balance_before = ctx.accounts.mailer_usdc_account.amount;
+    let transfer_ctx = CpiContext::new(
+        ctx.accounts.token_program.to_account_info(),
+        TransferChecked {
+            from: ctx.accounts.owner_usdc_account.to_account_info(),
+            mint: ctx.accounts.mint.to_account_info(),
+            to: ctx.accounts.mailer_usdc_account.to_account_info(),
+            authority: ctx.accounts.owner.to_account_info(),
+        },
+    );
+    token_interface::transfer_checked(transfer_ctx, amount, ctx.accounts.mint.decimals)?;
+
+    // Credit what actually arrived, net of any Token-2022 transfer fee
+    ctx.accounts.mailer_usdc_account.reload()?;
+    let received = ctx
+        .accounts
+        .mailer_usdc_account
+        .amount
+        .checked_sub(balance_before)
+        .ok_or(FeeError::MathOverflow)?;
+
+    let credit = &mut ctx.accounts.credit;
+    credit.owner = ctx.accounts.owner.key();
+    credit.mint = ctx.accounts.mint.key();
+    credit.bump = ctx.bumps.credit;
+    credit.balance = credit
+        .balance
+        .checked_add(received)
+        .ok_or(FeeError::MathOverflow)?;
+
+    emit!(CreditDeposited {
+        owner: credit.owner,
+        mint: credit.mint,
+        amount: received,
+        balance: credit.balance,
+    });
+
+    Ok(())
+}
+
+pub fn withdraw_credit(ctx: Context<WithdrawCredit>, amount: u64) -> Result<()> {
+    require!(amount > 0, CreditError::InvalidCreditAmount);
+    let credit = &mut ctx.accounts.credit;
+    require!(amount <= credit.balance, CreditError::InsufficientCredit);
+    credit.balance -= amount;
+
+    release(
+        &ctx.accounts.mailer,
+        &ctx.accounts.mint,
+        &ctx.accounts.mailer_usdc_account,
+        &ctx.accounts.owner_usdc_account,
+        &ctx.accounts.token_program,
+        amount,
+    )?;
+
+    emit!(CreditWithdrawn {
+        owner: credit.owner,
+        mint: credit.mint,
+        amount,
+        balance: credit.balance,
+    });
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct DepositCredit<'info> {
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = 8 + Credit::INIT_SPACE,
+        seeds = [b"credit", owner.key().as_ref(), mint.key().as_ref()],
+        bump
+    )]
+    pub credit: Account<'info, Credit>,
+
+    #[account(seeds = [seeds::MAILER], bump = mailer.bump)]
+    pub mailer: Account<'info, MailerState>,
+
+    /// Only enabled fee tokens can be deposited
+    #[account(
+        seeds = [b"token", mint.key().as_ref()],
+        bump = token_config.bump,
+        constraint = token_config.enabled @ TokenError::TokenDisabled
+    )]
+    pub token_config: Account<'info, TokenConfig>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = owner,
+        associated_token::token_program = token_program
+    )]
+    pub owner_usdc_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = mailer,
+        associated_token::token_program = token_program
+    )]
+    pub mailer_usdc_account: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawCredit<'info> {
+    #[account(
+        mut,
+        seeds = [b"credit", owner.key().as_ref(), mint.key().as_ref()],
+        bump = credit.bump
+    )]
+    pub credit: Account<'info, Credit>,
+
+    #[account(seeds = [seeds::MAILER], bump = mailer.bump)]
+    pub mailer: Account<'info, MailerState>,
+
+    /// Withdrawals work even after the token is disabled
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = owner,
+        associated_token::token_program = token_program
+    )]
+    pub owner_usdc_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = mailer,
+        associated_token::token_program = token_program
+    )]
+    pub mailer_usdc_account: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Credit {
+    pub owner: Pubkey,
+    /// Mint the balance is denominated in
+    pub mint: Pubkey,
+    /// Deposited and not yet spent or withdrawn, in `mint` base units
+    pub balance: u64,
+    pub bump: u8,
+}
+
+impl Credit {
+    /// Take up to `fee` from the balance, returning how much was taken
+    pub fn draw(&mut self, fee: u64) -> u64 {
+        let drawn = self.balance.min(fee);
+        self.balance -= drawn;
+        drawn
+    }
+}
+
+#[event]
+pub struct CreditDeposited {
+    pub owner: Pubkey,
+    pub mint: Pubkey,
+    /// Amount credited, net of any transfer fee
+    pub amount: u64,
+    pub balance: u64,
+}
+
+#[event]
+pub struct CreditWithdrawn {
+    pub owner: Pubkey,
+    pub mint: Pubkey,
+    pub amount: u64,
+    pub balance: u64,
+}
+
+/// Emitted by sends that draw on prepaid credit
+#[event]
+pub struct PrepaidCreditUsed {
+    pub sender: Pubkey,
+    pub amount: u64,
+    pub remaining_balance: u64,
+}
+
+#[error_code(offset = 8200)]
+pub enum CreditError {
+    #[msg("Credit amount must be greater than zero")]
+    InvalidCreditAmount,
+    #[msg("Withdrawal exceeds the credit balance")]
+    InsufficientCredit,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn draws_never_exceed_the_balance() {
+        let mut credit = Credit {
+            owner: Pubkey::new_unique(),
+            mint: Pubkey::new_unique(),
+            balance: 25_000,
+            bump: 0,
+        };
+        assert_eq!(credit.draw(10_000), 10_000);
+        assert_eq!(credit.draw(10_000), 10_000);
+        assert_eq!(credit.draw(10_000), 5_000);
+        assert_eq!(credit.balance, 0);
+        assert_eq!(credit.draw(10_000), 0);
+    }
+}
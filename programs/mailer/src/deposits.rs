@@ -0,0 +1,408 @@
+//! Anti-spam deposits: recipients can require senders they have no relationship
+//! with to lock a refundable deposit alongside each addressed message.
+//!
+//! A recipient opts in by setting `deposit_fees`, a multiple of the send fee.
+//! Sends to them from a sender without a `Contact` marker top up that sender's
+//! `SpamDeposit`; the recipient then refunds it (legitimate mail, which also
+//! makes the sender a contact) or confiscates it into their own claim (spam).
+
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+use mailbox_common::seeds;
+
+use crate::admin::MailerState;
+use crate::claims::{ClaimMode, RecipientClaim};
+use crate::escrow::release;
+use crate::expiry;
+use crate::fees::{credit_claim, transfer_from_sender, FeeError};
+use crate::messaging::{load_policy, SendMessage};
+use crate::tokens::TokenError;
+
+/// Largest deposit a recipient may require, in send fees
+pub const MAX_DEPOSIT_FEES: u16 = 100;
+
+pub fn set_spam_deposit(ctx: Context<SetSpamDeposit>, deposit_fees: u16) -> Result<()> {
+    require!(
+        deposit_fees <= MAX_DEPOSIT_FEES,
+        DepositError::InvalidDepositFees
+    );
+
+    let policy = &mut ctx.accounts.spam_policy;
+    policy.recipient = ctx.accounts.recipient.key();
+    policy.deposit_fees = deposit_fees;
+    policy.bump = ctx.bumps.spam_policy;
+
+    emit!(SpamPolicyUpdated {
+        recipient: policy.recipient,
+        deposit_fees,
+    });
+
+    Ok(())
+}
+
+pub fn approve_sender(ctx: Context<ApproveSender>, sender: Pubkey) -> Result<()> {
+    let contact = &mut ctx.accounts.contact;
+    contact.recipient = ctx.accounts.recipient.key();
+    contact.sender = sender;
+    contact.bump = ctx.bumps.contact;
+
+    emit!(ContactApproved {
+        recipient: contact.recipient,
+        sender,
+    });
+
+    Ok(())
+}
+
+pub fn refund_spam_deposit(ctx: Context<RefundSpamDeposit>) -> Result<()> {
+    let deposit = &ctx.accounts.deposit;
+    release(
+        &ctx.accounts.mailer,
+        &ctx.accounts.mint,
+        &ctx.accounts.mailer_usdc_account,
+        &ctx.accounts.sender_usdc_account,
+        &ctx.accounts.token_program,
+        deposit.amount,
+    )?;
+
+    // Refunding vouches for the sender, so their later mail needs no deposit
+    let contact = &mut ctx.accounts.contact;
+    contact.recipient = deposit.recipient;
+    contact.sender = deposit.sender;
+    contact.bump = ctx.bumps.contact;
+
+    emit!(SpamDepositRefunded {
+        recipient: deposit.recipient,
+        sender: deposit.sender,
+        mint: deposit.mint,
+        amount: deposit.amount,
+    });
+
+    Ok(())
+}
+
+pub fn confiscate_spam_deposit(ctx: Context<ConfiscateSpamDeposit>) -> Result<()> {
+    let deposit = &ctx.accounts.deposit;
+    let claim = &mut ctx.accounts.recipient_claim;
+    let started = credit_claim(
+        claim,
+        &ctx.accounts.mailer,
+        deposit.mint,
+        deposit.recipient,
+        ctx.accounts.recipient.key(),
+        deposit.amount,
+    )?;
+    if started && claim.mode != ClaimMode::Streaming {
+        expiry::track(
+            &ctx.accounts.expiry_index,
+            &ctx.accounts.recipient.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            deposit.recipient,
+            claim.timestamp,
+        )?;
+    }
+    claim.bump = ctx.bumps.recipient_claim;
+
+    emit!(SpamDepositConfiscated {
+        recipient: deposit.recipient,
+        sender: deposit.sender,
+        mint: deposit.mint,
+        amount: deposit.amount,
+    });
+
+    Ok(())
+}
+
+/// Deposit `recipient`'s policy requires of a sender, or 0 when none is due
+///
+/// Nothing is due when the recipient has no policy or has made the sender a
+/// contact; otherwise the deposit is `deposit_fees` times the send fee.
+pub(crate) fn required_deposit(
+    spam_policy: &UncheckedAccount,
+    contact: &UncheckedAccount,
+    send_fee: u64,
+) -> Result<u64> {
+    let Some(policy) = load_policy::<SpamPolicy>(spam_policy)? else {
+        return Ok(0);
+    };
+    if contact.owner == &crate::ID && !contact.data_is_empty() {
+        return Ok(0);
+    }
+    deposit_amount(send_fee, policy.deposit_fees)
+}
+
+/// Deposit of `deposit_fees` send fees
+pub fn deposit_amount(send_fee: u64, deposit_fees: u16) -> Result<u64> {
+    let amount = send_fee
+        .checked_mul(u64::from(deposit_fees))
+        .ok_or(FeeError::MathOverflow)?;
+    Ok(amount)
+}
+
+/// Lock `amount` from the sender into their deposit with `recipient`
+pub(crate) fn lock(
+    deposit: &mut Account<SpamDeposit>,
+    accounts: &mut SendMessage,
+    recipient: Pubkey,
+    amount: u64,
+    bump: u8,
+) -> Result<()> {
+    let mint = accounts.mint.key();
+    if deposit.recipient == Pubkey::default() {
+        deposit.recipient = recipient;
+        deposit.sender = accounts.sender.key();
+        deposit.rent_payer = accounts.payer.key();
+        deposit.mint = mint;
+        deposit.bump = bump;
+    }
+    // Top-ups stay in one mint so a single transfer resolves the deposit
+    require_keys_eq!(deposit.mint, mint, DepositError::DepositMintMismatch);
+
+    let received = transfer_from_sender(accounts, amount)?;
+    deposit.amount = deposit
+        .amount
+        .checked_add(received)
+        .ok_or(FeeError::MathOverflow)?;
+
+    emit!(SpamDepositLocked {
+        recipient,
+        sender: deposit.sender,
+        mint,
+        amount: received,
+        total: deposit.amount,
+    });
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct SetSpamDeposit<'info> {
+    #[account(
+        init_if_needed,
+        payer = recipient,
+        space = 8 + SpamPolicy::INIT_SPACE,
+        seeds = [b"spam", recipient.key().as_ref()],
+        bump
+    )]
+    pub spam_policy: Account<'info, SpamPolicy>,
+
+    #[account(mut)]
+    pub recipient: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(sender: Pubkey)]
+pub struct ApproveSender<'info> {
+    /// Fails if the sender is already a contact
+    #[account(
+        init,
+        payer = recipient,
+        space = 8 + Contact::INIT_SPACE,
+        seeds = [b"contact", recipient.key().as_ref(), sender.as_ref()],
+        bump
+    )]
+    pub contact: Account<'info, Contact>,
+
+    #[account(mut)]
+    pub recipient: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RefundSpamDeposit<'info> {
+    /// Closed on refund; rent returns to whoever funded it
+    #[account(
+        mut,
+        close = rent_payer,
+        seeds = [b"deposit", recipient.key().as_ref(), sender.key().as_ref()],
+        bump = deposit.bump,
+        has_one = recipient,
+        has_one = sender,
+        has_one = rent_payer,
+        has_one = mint @ TokenError::ClaimMintMismatch
+    )]
+    pub deposit: Account<'info, SpamDeposit>,
+
+    #[account(
+        init_if_needed,
+        payer = recipient,
+        space = 8 + Contact::INIT_SPACE,
+        seeds = [b"contact", recipient.key().as_ref(), sender.key().as_ref()],
+        bump
+    )]
+    pub contact: Account<'info, Contact>,
+
+    #[account(seeds = [seeds::MAILER], bump = mailer.bump)]
+    pub mailer: Account<'info, MailerState>,
+
+    /// CHECK: Deposit's sender, validated by `has_one`
+    pub sender: UncheckedAccount<'info>,
+
+    /// CHECK: Funded the deposit's rent, validated by `has_one`; receives it back
+    #[account(mut)]
+    pub rent_payer: UncheckedAccount<'info>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = sender,
+        associated_token::token_program = token_program
+    )]
+    pub sender_usdc_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = mailer,
+        associated_token::token_program = token_program
+    )]
+    pub mailer_usdc_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub recipient: Signer<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ConfiscateSpamDeposit<'info> {
+    /// Closed on confiscation; rent returns to whoever funded it
+    #[account(
+        mut,
+        close = rent_payer,
+        seeds = [b"deposit", recipient.key().as_ref(), deposit.sender.as_ref()],
+        bump = deposit.bump,
+        has_one = recipient,
+        has_one = rent_payer
+    )]
+    pub deposit: Account<'info, SpamDeposit>,
+
+    #[account(
+        init_if_needed,
+        payer = recipient,
+        space = 8 + RecipientClaim::INIT_SPACE,
+        seeds = [b"claim", recipient.key().as_ref()],
+        bump
+    )]
+    pub recipient_claim: Account<'info, RecipientClaim>,
+
+    #[account(seeds = [seeds::MAILER], bump = mailer.bump)]
+    pub mailer: Account<'info, MailerState>,
+
+    /// CHECK: Funded the deposit's rent, validated by `has_one`; receives it back
+    #[account(mut)]
+    pub rent_payer: UncheckedAccount<'info>,
+
+    /// CHECK: Expiry bucket for the current week; only written, and verified in
+    /// `expiry::track`, when the confiscation starts a new claim period
+    #[account(mut)]
+    pub expiry_index: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub recipient: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// A recipient's anti-spam terms for senders who are not contacts
+#[account]
+#[derive(InitSpace)]
+pub struct SpamPolicy {
+    pub recipient: Pubkey,
+    /// Deposit required per message, in send fees; 0 disables deposits
+    pub deposit_fees: u16,
+    pub bump: u8,
+}
+
+/// Marks a sender the recipient has vouched for; their mail needs no deposit
+#[account]
+#[derive(InitSpace)]
+pub struct Contact {
+    pub recipient: Pubkey,
+    pub sender: Pubkey,
+    pub bump: u8,
+}
+
+/// Deposits a sender has locked for one recipient, pending the recipient's verdict
+#[account]
+#[derive(InitSpace)]
+pub struct SpamDeposit {
+    pub recipient: Pubkey,
+    pub sender: Pubkey,
+    pub mint: Pubkey,
+    /// Locked amount in the mint's base units, net of any transfer fee
+    pub amount: u64,
+    /// Funded this account's rent; refunded when the deposit is resolved
+    pub rent_payer: Pubkey,
+    pub bump: u8,
+}
+
+#[event]
+pub struct SpamPolicyUpdated {
+    pub recipient: Pubkey,
+    pub deposit_fees: u16,
+}
+
+#[event]
+pub struct ContactApproved {
+    pub recipient: Pubkey,
+    pub sender: Pubkey,
+}
+
+#[event]
+pub struct SpamDepositLocked {
+    pub recipient: Pubkey,
+    pub sender: Pubkey,
+    pub mint: Pubkey,
+    pub amount: u64,
+    /// Outstanding deposit after this message
+    pub total: u64,
+}
+
+#[event]
+pub struct SpamDepositRefunded {
+    pub recipient: Pubkey,
+    pub sender: Pubkey,
+    pub mint: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct SpamDepositConfiscated {
+    pub recipient: Pubkey,
+    pub sender: Pubkey,
+    pub mint: Pubkey,
+    pub amount: u64,
+}
+
+#[error_code(offset = 6900)]
+pub enum DepositError {
+    #[msg("Deposit may be at most 100 send fees")]
+    InvalidDepositFees,
+    #[msg("Recipient requires a deposit from senders who are not contacts")]
+    DepositRequired,
+    #[msg("Deposit top-ups must be paid in the deposit's mint")]
+    DepositMintMismatch,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deposit_scales_with_the_send_fee() {
+        assert_eq!(deposit_amount(100_000, 0).unwrap(), 0);
+        assert_eq!(deposit_amount(100_000, 5).unwrap(), 500_000);
+        assert_eq!(
+            deposit_amount(100_000, MAX_DEPOSIT_FEES).unwrap(),
+            10_000_000
+        );
+        assert!(deposit_amount(u64::MAX, 2).is_err());
+    }
+}
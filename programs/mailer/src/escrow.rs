@@ -0,0 +1,600 @@
+//! Payment-bearing messages: funds escrowed alongside a message until the
+//! recipient acknowledges it, or refunded once an acknowledgement deadline passes.
+//! Payments sent without a deadline can be reclaimed by the sender after
+//! `RECLAIM_TIMEOUT`, so escrowed funds are never stranded. The two paths never
+//! overlap: a payment with a deadline is only refunded by `refund_unread`, even
+//! if the deadline lies beyond the reclaim timeout.
+
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{self, Mint, TokenAccount, TokenInterface, TransferChecked};
+use mailbox_common::seeds;
+
+use crate::admin::{MailerState, MailerStats};
+use crate::compliance::ComplianceError;
+use crate::fees::FeeError;
+use crate::inbox;
+use crate::messaging::{
+    emit_send_event, message_id, validate_external_address, ExternalAddress, MessagingError,
+    RateLimit,
+};
+use crate::tokens::{TokenConfig, TokenError};
+
+/// Seconds after which the sender may reclaim a payment that was never acknowledged
+pub const RECLAIM_TIMEOUT: i64 = 30 * 24 * 60 * 60; // 30 days
+
+pub fn send_with_payment(
+    ctx: Context<SendWithPayment>,
+    args: SendWithPaymentArgs,
+) -> Result<[u8; 32]> {
+    let SendWithPaymentArgs {
+        payment_id,
+        recipient,
+        amount,
+        mail_id,
+        ack_deadline,
+        recipient_external,
+        client_ref,
+    } = args;
+    require!(amount > 0, EscrowError::InvalidPaymentAmount);
+    require!(ctx.accounts.block.data_is_empty(), MessagingError::SenderBlocked);
+    validate_external_address(&recipient_external)?;
+    let clock = Clock::get()?;
+    let current_time = clock.unix_timestamp;
+    if let Some(deadline) = ack_deadline {
+        require!(deadline > current_time, EscrowError::InvalidAckDeadline);
+    }
+    let max_per_day = ctx.accounts.mailer.max_messages_per_day;
+    ctx.accounts.rate_limit.sender = ctx.accounts.sender.key();
+    ctx.accounts.rate_limit.record(1, max_per_day, current_time)?;
+
+    let balance_before = ctx.accounts.mailer_usdc_account.amount;
+    let transfer_ctx = CpiContext::new(
+        ctx.accounts.token_program.to_account_info(),
+        TransferChecked {
+            from: ctx.accounts.sender_usdc_account.to_account_info(),
+            mint: ctx.accounts.mint.to_account_info(),
+            to: ctx.accounts.mailer_usdc_account.to_account_info(),
+            authority: ctx.accounts.sender.to_account_info(),
+        },
+    );
+    token_interface::transfer_checked(transfer_ctx, amount, ctx.accounts.mint.decimals)?;
+
+    // Escrow what actually arrived, net of any Token-2022 transfer fee
+    ctx.accounts.mailer_usdc_account.reload()?;
+    let received = ctx
+        .accounts
+        .mailer_usdc_account
+        .amount
+        .checked_sub(balance_before)
+        .ok_or(FeeError::MathOverflow)?;
+
+    // The payment is escrowed for the recipient, not a fee
+    let sender = ctx.accounts.sender.key();
+    let message_seq = ctx.accounts.stats.record_sends(1, false)?;
+    let message_id = message_id(&sender, message_seq, clock.slot);
+    if let Some(inbox) = &ctx.accounts.inbox {
+        inbox::record(
+            &inbox.to_account_info(),
+            &ctx.accounts.payer.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            recipient,
+            sender,
+            message_seq,
+            1,
+        )?;
+    }
+
+    let escrow = &mut ctx.accounts.escrow;
+    escrow.payment_id = payment_id;
+    escrow.message_id = message_id;
+    escrow.sender = sender;
+    escrow.recipient = recipient;
+    escrow.recipient_external = recipient_external;
+    escrow.mint = ctx.accounts.mint.key();
+    escrow.amount = received;
+    escrow.ack_deadline = ack_deadline.unwrap_or(0);
+    escrow.created_at = current_time;
+    escrow.bump = ctx.bumps.escrow;
+    escrow.rent_payer = ctx.accounts.payer.key();
+
+    let event = PaymentMailSent {
+        message_id,
+        from: escrow.sender,
+        to: recipient,
+        to_external: escrow.recipient_external.clone(),
+        message_seq,
+        timestamp: current_time,
+        client_ref,
+        mint: escrow.mint,
+        amount: escrow.amount,
+        mail_id,
+        ack_deadline: escrow.ack_deadline,
+        payment_id,
+    };
+    emit_send_event!(ctx, event);
+
+    Ok(message_id)
+}
+
+pub fn acknowledge_payment(ctx: Context<AcknowledgePayment>) -> Result<()> {
+    let escrow = &ctx.accounts.escrow;
+    let current_time = Clock::get()?.unix_timestamp;
+    require!(
+        !is_past_deadline(escrow.ack_deadline, current_time),
+        EscrowError::AcknowledgementExpired
+    );
+
+    release(
+        &ctx.accounts.mailer,
+        &ctx.accounts.mint,
+        &ctx.accounts.mailer_usdc_account,
+        &ctx.accounts.recipient_usdc_account,
+        &ctx.accounts.token_program,
+        escrow.amount,
+    )?;
+
+    emit!(PaymentAcknowledged {
+        message_id: escrow.message_id,
+        recipient: escrow.recipient,
+        amount: escrow.amount,
+    });
+
+    Ok(())
+}
+
+pub fn refund_unread(ctx: Context<RefundUnread>) -> Result<()> {
+    let escrow = &ctx.accounts.escrow;
+    let current_time = Clock::get()?.unix_timestamp;
+    require!(escrow.ack_deadline != 0, EscrowError::NoAckDeadline);
+    require!(
+        is_past_deadline(escrow.ack_deadline, current_time),
+        EscrowError::AckDeadlineNotReached
+    );
+
+    release(
+        &ctx.accounts.mailer,
+        &ctx.accounts.mint,
+        &ctx.accounts.mailer_usdc_account,
+        &ctx.accounts.sender_usdc_account,
+        &ctx.accounts.token_program,
+        escrow.amount,
+    )?;
+
+    emit!(MailExpiredUnread {
+        message_id: escrow.message_id,
+        sender: escrow.sender,
+        recipient: escrow.recipient,
+        amount: escrow.amount,
+        ack_deadline: escrow.ack_deadline,
+        cranker: ctx.accounts.cranker.key(),
+    });
+
+    Ok(())
+}
+
+pub fn reclaim_payment(ctx: Context<ReclaimPayment>) -> Result<()> {
+    let escrow = &ctx.accounts.escrow;
+    let current_time = Clock::get()?.unix_timestamp;
+    // A deadline is the sender's promise of the acknowledgement window; only
+    // `refund_unread` may end it
+    require!(escrow.ack_deadline == 0, EscrowError::HasAckDeadline);
+    require!(
+        is_reclaimable(escrow.created_at, current_time),
+        EscrowError::ReclaimTimeoutNotReached
+    );
+
+    release(
+        &ctx.accounts.mailer,
+        &ctx.accounts.mint,
+        &ctx.accounts.mailer_usdc_account,
+        &ctx.accounts.sender_usdc_account,
+        &ctx.accounts.token_program,
+        escrow.amount,
+    )?;
+
+    emit!(PaymentReclaimed {
+        message_id: escrow.message_id,
+        sender: escrow.sender,
+        recipient: escrow.recipient,
+        amount: escrow.amount,
+    });
+
+    Ok(())
+}
+
+/// Whether an acknowledgement deadline (0 = none) has passed at `current_time`
+pub fn is_past_deadline(ack_deadline: i64, current_time: i64) -> bool {
+    ack_deadline != 0 && current_time > ack_deadline
+}
+
+/// Whether a payment created at `created_at` may be reclaimed by its sender at `current_time`
+pub fn is_reclaimable(created_at: i64, current_time: i64) -> bool {
+    current_time > created_at.saturating_add(RECLAIM_TIMEOUT)
+}
+
+/// Transfer funds held by the mailer, such as escrowed payments, out of its token account
+pub(crate) fn release<'info>(
+    mailer: &Account<'info, MailerState>,
+    mint: &InterfaceAccount<'info, Mint>,
+    from: &InterfaceAccount<'info, TokenAccount>,
+    to: &InterfaceAccount<'info, TokenAccount>,
+    token_program: &Interface<'info, TokenInterface>,
+    amount: u64,
+) -> Result<()> {
+    let bump = mailer.bump;
+    let seeds = &[seeds::MAILER, &[bump]];
+    let signer_seeds = &[&seeds[..]];
+
+    let transfer_ctx = CpiContext::new_with_signer(
+        token_program.to_account_info(),
+        TransferChecked {
+            from: from.to_account_info(),
+            mint: mint.to_account_info(),
+            to: to.to_account_info(),
+            authority: mailer.to_account_info(),
+        },
+        signer_seeds,
+    );
+    token_interface::transfer_checked(transfer_ctx, amount, mint.decimals)
+}
+
+/// Arguments of `send_with_payment`, in their wire order
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct SendWithPaymentArgs {
+    /// Unique key chosen by the caller; seeds the escrow PDA
+    pub payment_id: [u8; 32],
+    /// Address that may acknowledge and collect the payment
+    pub recipient: Pubkey,
+    /// Payment in the mint's base units
+    pub amount: u64,
+    pub mail_id: String,
+    /// Unix time after which anyone may refund the payment to the sender
+    pub ack_deadline: Option<i64>,
+    /// Recipient on another chain, stored on the escrow for the relay
+    pub recipient_external: Option<ExternalAddress>,
+    pub client_ref: Option<[u8; 16]>,
+}
+
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+#[instruction(args: SendWithPaymentArgs)]
+pub struct SendWithPayment<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + PaymentEscrow::INIT_SPACE,
+        seeds = [b"escrow", args.payment_id.as_ref()],
+        bump
+    )]
+    pub escrow: Account<'info, PaymentEscrow>,
+
+    #[account(seeds = [seeds::MAILER], bump = mailer.bump)]
+    pub mailer: Account<'info, MailerState>,
+
+    #[account(mut, seeds = [b"mailer_stats"], bump = stats.bump)]
+    pub stats: Account<'info, MailerStats>,
+
+    /// Payments are accepted in any enabled fee token
+    #[account(
+        seeds = [b"token", mint.key().as_ref()],
+        bump = token_config.bump,
+        constraint = token_config.enabled @ TokenError::TokenDisabled
+    )]
+    pub token_config: Account<'info, TokenConfig>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    pub sender: Signer<'info>,
+
+    /// Pays rent for any PDAs created by this send. Usually the sender, but
+    /// may be a relayer sponsoring SOL for senders who only hold fee tokens.
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = sender,
+        associated_token::token_program = token_program
+    )]
+    pub sender_usdc_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = mailer,
+        associated_token::token_program = token_program
+    )]
+    pub mailer_usdc_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// Sender's daily message counter, created on first send
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + RateLimit::INIT_SPACE,
+        seeds = [b"rate", sender.key().as_ref()],
+        bump
+    )]
+    pub rate_limit: Account<'info, RateLimit>,
+
+    /// CHECK: Sender's compliance denylist marker; must not exist
+    #[account(
+        seeds = [b"deny", sender.key().as_ref()],
+        bump,
+        constraint = denylist.data_is_empty() @ ComplianceError::AddressDenied
+    )]
+    pub denylist: UncheckedAccount<'info>,
+
+    /// CHECK: Recipient's compliance denylist marker; must not exist
+    #[account(
+        seeds = [b"deny", args.recipient.as_ref()],
+        bump,
+        constraint = recipient_denylist.data_is_empty() @ ComplianceError::AddressDenied
+    )]
+    pub recipient_denylist: UncheckedAccount<'info>,
+
+    /// CHECK: Recipient's block on the sender; must not exist, checked in `send_with_payment`
+    #[account(seeds = [b"block", args.recipient.as_ref(), sender.key().as_ref()], bump)]
+    pub block: UncheckedAccount<'info>,
+
+    /// CHECK: Recipient's `[b"inbox", recipient]` counters, created on first delivery and
+    /// verified in `inbox::record`. Omit to leave them untouched
+    #[account(mut)]
+    pub inbox: Option<UncheckedAccount<'info>>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct AcknowledgePayment<'info> {
+    /// Closed on release; rent returns to whoever funded it
+    #[account(
+        mut,
+        close = rent_payer,
+        seeds = [b"escrow", escrow.payment_id.as_ref()],
+        bump = escrow.bump,
+        has_one = recipient @ EscrowError::InvalidEscrowParty,
+        has_one = rent_payer @ EscrowError::InvalidEscrowParty,
+        has_one = mint @ TokenError::ClaimMintMismatch
+    )]
+    pub escrow: Account<'info, PaymentEscrow>,
+
+    #[account(seeds = [seeds::MAILER], bump = mailer.bump)]
+    pub mailer: Account<'info, MailerState>,
+
+    pub recipient: Signer<'info>,
+
+    /// CHECK: Funded the escrow's rent, validated by `has_one`; receives it back
+    #[account(mut)]
+    pub rent_payer: UncheckedAccount<'info>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = recipient,
+        associated_token::token_program = token_program
+    )]
+    pub recipient_usdc_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = mailer,
+        associated_token::token_program = token_program
+    )]
+    pub mailer_usdc_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// CHECK: Recipient's compliance denylist marker; must not exist
+    #[account(
+        seeds = [b"deny", recipient.key().as_ref()],
+        bump,
+        constraint = denylist.data_is_empty() @ ComplianceError::AddressDenied
+    )]
+    pub denylist: UncheckedAccount<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct RefundUnread<'info> {
+    /// Closed on refund; rent returns to whoever funded it
+    #[account(
+        mut,
+        close = rent_payer,
+        seeds = [b"escrow", escrow.payment_id.as_ref()],
+        bump = escrow.bump,
+        has_one = sender @ EscrowError::InvalidEscrowParty,
+        has_one = rent_payer @ EscrowError::InvalidEscrowParty,
+        has_one = mint @ TokenError::ClaimMintMismatch
+    )]
+    pub escrow: Account<'info, PaymentEscrow>,
+
+    #[account(seeds = [seeds::MAILER], bump = mailer.bump)]
+    pub mailer: Account<'info, MailerState>,
+
+    /// CHECK: Escrow's sender, validated by `has_one`; receives the refund
+    pub sender: UncheckedAccount<'info>,
+
+    /// CHECK: Funded the escrow's rent, validated by `has_one`; receives it back
+    #[account(mut)]
+    pub rent_payer: UncheckedAccount<'info>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = sender,
+        associated_token::token_program = token_program
+    )]
+    pub sender_usdc_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = mailer,
+        associated_token::token_program = token_program
+    )]
+    pub mailer_usdc_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// Anyone may crank a refund once the deadline has passed
+    pub cranker: Signer<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct ReclaimPayment<'info> {
+    /// Closed on reclaim; rent returns to whoever funded it
+    #[account(
+        mut,
+        close = rent_payer,
+        seeds = [b"escrow", escrow.payment_id.as_ref()],
+        bump = escrow.bump,
+        has_one = sender @ EscrowError::InvalidEscrowParty,
+        has_one = rent_payer @ EscrowError::InvalidEscrowParty,
+        has_one = mint @ TokenError::ClaimMintMismatch
+    )]
+    pub escrow: Account<'info, PaymentEscrow>,
+
+    #[account(seeds = [seeds::MAILER], bump = mailer.bump)]
+    pub mailer: Account<'info, MailerState>,
+
+    pub sender: Signer<'info>,
+
+    /// CHECK: Funded the escrow's rent, validated by `has_one`; receives it back
+    #[account(mut)]
+    pub rent_payer: UncheckedAccount<'info>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = sender,
+        associated_token::token_program = token_program
+    )]
+    pub sender_usdc_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = mailer,
+        associated_token::token_program = token_program
+    )]
+    pub mailer_usdc_account: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct PaymentEscrow {
+    /// Caller-chosen key the escrow PDA is derived from
+    pub payment_id: [u8; 32],
+    pub sender: Pubkey,
+    pub recipient: Pubkey,
+    pub mint: Pubkey,
+    /// Escrowed amount in the mint's base units, net of any transfer fee
+    pub amount: u64,
+    /// Unix time after which the payment is refundable; 0 means no deadline
+    pub ack_deadline: i64,
+    pub created_at: i64,
+    pub bump: u8,
+    /// Recipient on another chain the payment is relayed to, if any
+    pub recipient_external: Option<ExternalAddress>,
+    /// Funded this account's rent; refunded when the payment is resolved
+    pub rent_payer: Pubkey,
+    /// `message_id(sender, message_seq, slot)` of the message carrying the payment
+    pub message_id: [u8; 32],
+}
+
+#[event]
+pub struct PaymentMailSent {
+    /// `message_id(from, message_seq, slot)`
+    pub message_id: [u8; 32],
+    pub from: Pubkey,
+    pub to: Pubkey,
+    pub to_external: Option<ExternalAddress>,
+    /// Global order of the message across all send paths
+    pub message_seq: u64,
+    pub timestamp: i64,
+    /// Caller-chosen reference passed through unchanged, e.g. an idempotency key
+    pub client_ref: Option<[u8; 16]>,
+    pub mint: Pubkey,
+    pub amount: u64,
+    pub mail_id: String,
+    pub ack_deadline: i64,
+    /// Key of the escrow holding the payment
+    pub payment_id: [u8; 32],
+}
+
+#[event]
+pub struct PaymentAcknowledged {
+    pub message_id: [u8; 32],
+    pub recipient: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct MailExpiredUnread {
+    pub message_id: [u8; 32],
+    pub sender: Pubkey,
+    pub recipient: Pubkey,
+    pub amount: u64,
+    pub ack_deadline: i64,
+    pub cranker: Pubkey,
+}
+
+#[event]
+pub struct PaymentReclaimed {
+    pub message_id: [u8; 32],
+    pub sender: Pubkey,
+    pub recipient: Pubkey,
+    pub amount: u64,
+}
+
+#[error_code(offset = 6500)]
+pub enum EscrowError {
+    #[msg("Payment amount must be greater than zero")]
+    InvalidPaymentAmount,
+    #[msg("Acknowledgement deadline must be in the future")]
+    InvalidAckDeadline,
+    #[msg("Acknowledgement deadline has passed")]
+    AcknowledgementExpired,
+    #[msg("Acknowledgement deadline has not passed yet")]
+    AckDeadlineNotReached,
+    #[msg("Payment has no acknowledgement deadline")]
+    NoAckDeadline,
+    #[msg("Account is not a party to this payment")]
+    InvalidEscrowParty,
+    #[msg("Payment cannot be reclaimed until the reclaim timeout has passed")]
+    ReclaimTimeoutNotReached,
+    #[msg("Payment has an acknowledgement deadline; refund it with refund_unread")]
+    HasAckDeadline,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn payment_without_deadline_never_expires() {
+        assert!(!is_past_deadline(0, i64::MAX));
+    }
+
+    #[test]
+    fn deadline_is_inclusive() {
+        assert!(!is_past_deadline(1_000, 1_000));
+        assert!(is_past_deadline(1_000, 1_001));
+    }
+
+    #[test]
+    fn sender_reclaims_only_after_timeout() {
+        assert!(!is_reclaimable(1_000, 1_000 + RECLAIM_TIMEOUT));
+        assert!(is_reclaimable(1_000, 1_001 + RECLAIM_TIMEOUT));
+    }
+}
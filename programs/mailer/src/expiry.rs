@@ -0,0 +1,193 @@
+//! Weekly expiry index over recipient claims.
+//!
+//! Each `ExpiryIndex` PDA lists the recipients whose claims expire in one
+//! week, so keepers and dashboards can find claims nearing expiry with a
+//! couple of account reads instead of scanning every `RecipientClaim`.
+//! Entries are hints: a claim drained by a path that does not touch the index
+//! (e.g. netting in `send_using_claim`, batch sweeps) stays listed until its
+//! week passes, so readers must confirm against the claim itself.
+
+use anchor_lang::prelude::*;
+use anchor_lang::system_program::{self, Allocate, Assign, CreateAccount, Transfer};
+
+use crate::claims::{ClaimError, CLAIM_PERIOD};
+use crate::fees::FeeError;
+
+/// Width of one expiry bucket: 7 days in seconds
+pub const EXPIRY_BUCKET: i64 = 7 * 24 * 60 * 60;
+
+/// Maximum recipients listed per weekly bucket
+pub const MAX_INDEXED_CLAIMS: usize = 256;
+
+/// Index of the week in which a claim recorded at `timestamp` expires
+pub fn expiry_week(timestamp: i64) -> i64 {
+    timestamp.saturating_add(CLAIM_PERIOD) / EXPIRY_BUCKET
+}
+
+/// List `recipient` in the bucket for a claim recorded at `timestamp`,
+/// creating the bucket (funded by `payer`) on first use
+pub(crate) fn track<'info>(
+    index: &AccountInfo<'info>,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    recipient: Pubkey,
+    timestamp: i64,
+) -> Result<()> {
+    let week = expiry_week(timestamp);
+    let bump = verify_index_address(index, week)?;
+
+    if index.data_is_empty() {
+        let space = 8 + ExpiryIndex::INIT_SPACE;
+        let week_bytes = week.to_le_bytes();
+        let seeds: &[&[u8]] = &[b"expiry", &week_bytes, &[bump]];
+        create_pda(index, payer, system_program, seeds, space)?;
+
+        let fresh = ExpiryIndex {
+            week,
+            recipients: Vec::new(),
+            overflow: 0,
+            bump,
+        };
+        let mut data = index.try_borrow_mut_data()?;
+        let mut writer: &mut [u8] = &mut data[..];
+        fresh.try_serialize(&mut writer)?;
+    }
+
+    let mut data = index.try_borrow_mut_data()?;
+    let mut entry = ExpiryIndex::try_deserialize(&mut &data[..])?;
+    if !entry.recipients.contains(&recipient) {
+        if entry.recipients.len() < MAX_INDEXED_CLAIMS {
+            entry.recipients.push(recipient);
+        } else {
+            // Readers fall back to scanning claims for this week
+            entry.overflow = entry.overflow.checked_add(1).ok_or(FeeError::MathOverflow)?;
+        }
+    }
+    let mut writer: &mut [u8] = &mut data[..];
+    entry.try_serialize(&mut writer)?;
+
+    Ok(())
+}
+
+/// Remove `recipient` from the bucket for a claim recorded at `timestamp`
+///
+/// Claims recorded before the index existed have no bucket; that is not an error.
+pub(crate) fn untrack(index: &AccountInfo, recipient: Pubkey, timestamp: i64) -> Result<()> {
+    verify_index_address(index, expiry_week(timestamp))?;
+    if index.owner != &crate::ID || index.data_is_empty() {
+        return Ok(());
+    }
+
+    let mut data = index.try_borrow_mut_data()?;
+    let mut entry = ExpiryIndex::try_deserialize(&mut &data[..])?;
+    entry.recipients.retain(|r| r != &recipient);
+    let mut writer: &mut [u8] = &mut data[..];
+    entry.try_serialize(&mut writer)?;
+
+    Ok(())
+}
+
+/// Create the program-owned PDA `account` at `seeds`, funded by `payer`
+///
+/// Anyone can send lamports to a PDA before it exists, which would make a
+/// plain `create_account` fail for good; an address that already holds
+/// lamports is topped up to rent exemption, allocated and assigned instead.
+pub(crate) fn create_pda<'info>(
+    account: &AccountInfo<'info>,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    seeds: &[&[u8]],
+    space: usize,
+) -> Result<()> {
+    let rent = Rent::get()?.minimum_balance(space);
+    let funded = account.lamports();
+    if funded == 0 {
+        return system_program::create_account(
+            CpiContext::new_with_signer(
+                system_program.clone(),
+                CreateAccount {
+                    from: payer.clone(),
+                    to: account.clone(),
+                },
+                &[seeds],
+            ),
+            rent,
+            space as u64,
+            &crate::ID,
+        );
+    }
+
+    let shortfall = rent.saturating_sub(funded);
+    if shortfall > 0 {
+        system_program::transfer(
+            CpiContext::new(
+                system_program.clone(),
+                Transfer {
+                    from: payer.clone(),
+                    to: account.clone(),
+                },
+            ),
+            shortfall,
+        )?;
+    }
+    system_program::allocate(
+        CpiContext::new_with_signer(
+            system_program.clone(),
+            Allocate {
+                account_to_allocate: account.clone(),
+            },
+            &[seeds],
+        ),
+        space as u64,
+    )?;
+    system_program::assign(
+        CpiContext::new_with_signer(
+            system_program.clone(),
+            Assign {
+                account_to_assign: account.clone(),
+            },
+            &[seeds],
+        ),
+        &crate::ID,
+    )
+}
+
+fn verify_index_address(index: &AccountInfo, week: i64) -> Result<u8> {
+    let (expected, bump) =
+        Pubkey::find_program_address(&[b"expiry", &week.to_le_bytes()], &crate::ID);
+    require_keys_eq!(expected, index.key(), ClaimError::InvalidExpiryIndex);
+    Ok(bump)
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct ExpiryIndex {
+    pub week: i64,
+    #[max_len(256)]
+    pub recipients: Vec<Pubkey>,
+    /// Claims that expire this week but did not fit in `recipients`
+    pub overflow: u32,
+    pub bump: u8,
+}
+
+// `max_len` takes a literal: keep it in step with `MAX_INDEXED_CLAIMS`, and the
+// index within a single CPI allocation
+const _: () = assert!(MAX_INDEXED_CLAIMS == 256 && 8 + ExpiryIndex::INIT_SPACE <= 10_240);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn claims_recorded_the_same_day_share_a_bucket() {
+        let recorded = 1_700_000_000;
+        assert_eq!(expiry_week(recorded), expiry_week(recorded + 60 * 60));
+    }
+
+    #[test]
+    fn bucket_is_the_week_of_expiry() {
+        let recorded = 1_700_000_000;
+        assert_eq!(expiry_week(recorded), (recorded + CLAIM_PERIOD) / EXPIRY_BUCKET);
+        assert!(expiry_week(recorded + EXPIRY_BUCKET) > expiry_week(recorded));
+    }
+}
@@ -0,0 +1,1312 @@
+//! Fee parameters, fee collection from senders and revenue share accounting.
+
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{
+    self, Approve, Mint, Revoke, TokenAccount, TokenInterface, TransferChecked,
+};
+use mail_service::Delegation;
+use mailbox_common::fees::BPS_DENOMINATOR;
+use mailbox_common::seeds;
+
+use crate::admin::{MailerError, MailerState};
+use crate::claims::{ClaimMode, RecipientClaim, CLAIM_PERIOD};
+use crate::credits::PrepaidCreditUsed;
+use crate::escrow::release;
+use crate::messaging::{SendMessage, MAX_PREPARED_BATCH};
+use crate::nft_perks::validate_nft_discount;
+use crate::pow::validate_pow_difficulty;
+use crate::tokens::{claim_mint, current_send_fee, QuoteFee, TokenError};
+
+/// Base sending fee in USDC (with 6 decimals): 0.1 USDC
+pub const SEND_FEE: u64 = 100_000;
+
+/// Percentage of fee that goes to message sender as revenue share: 90%
+pub const RECIPIENT_SHARE: u64 = 90;
+
+/// Percentage of fee that goes to program owner: 10%
+pub const OWNER_SHARE: u64 = 10;
+
+/// Default priority send fee for oracle-priced mints: $0.10 in US cents
+pub const DEFAULT_USD_FEE_CENTS: u64 = 10;
+
+/// Default bounty paid to whoever cranks an expired claim: 1% (basis points)
+pub const DEFAULT_CRANK_BOUNTY_BPS: u16 = 100;
+
+/// Upper bound on the crank bounty: 10% (basis points)
+pub const MAX_CRANK_BOUNTY_BPS: u16 = 1_000;
+
+pub fn set_fee(ctx: Context<SetFee>, new_fee: u64) -> Result<()> {
+    ConfigChange::SendFee { new_fee }.validate()?;
+
+    let mailer = &mut ctx.accounts.mailer;
+    let old_fee = mailer.send_fee;
+    mailer.send_fee = new_fee;
+
+    emit!(FeeUpdated { old_fee, new_fee });
+
+    Ok(())
+}
+
+pub fn set_usd_fee(ctx: Context<SetFee>, new_usd_fee_cents: u64) -> Result<()> {
+    ConfigChange::UsdFee { new_usd_fee_cents }.validate()?;
+
+    let mailer = &mut ctx.accounts.mailer;
+    let old_usd_fee_cents = mailer.usd_fee_cents;
+    mailer.usd_fee_cents = new_usd_fee_cents;
+
+    emit!(UsdFeeUpdated {
+        old_usd_fee_cents,
+        new_usd_fee_cents,
+    });
+
+    Ok(())
+}
+
+pub fn set_crank_bounty(ctx: Context<SetFee>, new_bounty_bps: u16) -> Result<()> {
+    ConfigChange::CrankBounty { new_bounty_bps }.validate()?;
+
+    let mailer = &mut ctx.accounts.mailer;
+    let old_bounty_bps = mailer.crank_bounty_bps;
+    mailer.crank_bounty_bps = new_bounty_bps;
+
+    emit!(CrankBountyUpdated {
+        old_bounty_bps,
+        new_bounty_bps,
+    });
+
+    Ok(())
+}
+
+pub fn set_rollover(ctx: Context<SetFee>, new_rollover_bps: u16) -> Result<()> {
+    ConfigChange::Rollover { new_rollover_bps }.validate()?;
+
+    let mailer = &mut ctx.accounts.mailer;
+    let old_rollover_bps = mailer.rollover_bps;
+    mailer.rollover_bps = new_rollover_bps;
+
+    emit!(RolloverUpdated {
+        old_rollover_bps,
+        new_rollover_bps,
+    });
+
+    Ok(())
+}
+
+pub fn set_referral_share(ctx: Context<SetFee>, new_referral_bps: u16) -> Result<()> {
+    ConfigChange::ReferralShare { new_referral_bps }.validate()?;
+
+    let mailer = &mut ctx.accounts.mailer;
+    let old_referral_bps = mailer.referral_bps;
+    mailer.referral_bps = new_referral_bps;
+
+    emit!(ReferralShareUpdated {
+        old_referral_bps,
+        new_referral_bps,
+    });
+
+    Ok(())
+}
+
+pub fn get_fee_breakdown(ctx: Context<QuoteFee>) -> Result<FeeBreakdown> {
+    let send_fee = current_send_fee(
+        &ctx.accounts.token_config,
+        &ctx.accounts.mailer,
+        ctx.accounts.price_update.as_deref(),
+    )?;
+    fee_breakdown(ctx.accounts.token_config.mint, send_fee, &ctx.accounts.mailer)
+}
+
+/// Split of a priority fee of `send_fee` in `mint` under the mailer's current terms
+pub fn fee_breakdown(mint: Pubkey, send_fee: u64, mailer: &MailerState) -> Result<FeeBreakdown> {
+    let (owner_share, recipient_share) = split_shares(send_fee)?;
+    Ok(FeeBreakdown {
+        mint,
+        priority_fee: send_fee,
+        standard_fee: standard_fee(send_fee)?,
+        recipient_share,
+        owner_share,
+        claim_period: CLAIM_PERIOD,
+        crank_bounty_bps: mailer.crank_bounty_bps,
+        rollover_bps: mailer.rollover_bps,
+    })
+}
+
+pub fn simulate_config_change(
+    ctx: Context<SimulateConfigChange>,
+    change: ConfigChange,
+) -> Result<ConfigChangeReport> {
+    let mailer = &ctx.accounts.mailer;
+
+    // Run the setter's own validation, but report the outcome instead of failing
+    let report = ConfigChangeReport {
+        change,
+        old_value: change.current_value(mailer),
+        new_value: change.new_value(),
+        error_code: validation_error_code(change.validate()),
+    };
+
+    emit!(ConfigChangeSimulated {
+        report: report.clone(),
+    });
+
+    Ok(report)
+}
+
+/// A send fee must leave the standard (owner share) fee non-zero and a full
+/// prepared batch chargeable without overflow
+pub fn validate_send_fee(send_fee: u64) -> Result<()> {
+    require!(
+        send_fee.checked_mul(MAX_PREPARED_BATCH as u64).is_some() && standard_fee(send_fee)? > 0,
+        FeeError::InvalidFee
+    );
+    Ok(())
+}
+
+/// Oracle-priced sends must never be free
+pub fn validate_usd_fee(usd_fee_cents: u64) -> Result<()> {
+    require!(usd_fee_cents > 0, FeeError::InvalidFee);
+    Ok(())
+}
+
+pub fn validate_crank_bounty(bounty_bps: u16) -> Result<()> {
+    require!(
+        bounty_bps <= MAX_CRANK_BOUNTY_BPS,
+        FeeError::InvalidCrankBounty
+    );
+    Ok(())
+}
+
+pub fn validate_rollover(rollover_bps: u16) -> Result<()> {
+    require!(rollover_bps <= BPS_DENOMINATOR, FeeError::InvalidRollover);
+    Ok(())
+}
+
+pub fn validate_referral_share(referral_bps: u16) -> Result<()> {
+    require!(
+        referral_bps <= BPS_DENOMINATOR,
+        FeeError::InvalidReferralShare
+    );
+    Ok(())
+}
+
+/// Error code a validation failed with, or 0 if it passed
+fn validation_error_code(result: Result<()>) -> u32 {
+    match result {
+        Ok(()) => 0,
+        Err(Error::AnchorError(error)) => error.error_code_number,
+        Err(Error::ProgramError(_)) => u32::MAX,
+    }
+}
+
+pub fn set_bucket_destination(
+    ctx: Context<SetBucketDestination>,
+    source: FeeSource,
+    destination: Pubkey,
+) -> Result<()> {
+    let bucket = &mut ctx.accounts.owner_bucket;
+    let mint = ctx.accounts.mint.key();
+    let old_destination = bucket.destination;
+    init_owner_bucket(bucket, mint, source, destination, ctx.bumps.owner_bucket);
+    bucket.destination = destination;
+
+    emit!(BucketDestinationUpdated {
+        mint,
+        source,
+        old_destination,
+        new_destination: destination,
+    });
+
+    Ok(())
+}
+
+pub fn grant_fee_exemption(ctx: Context<GrantFeeExemption>, address: Pubkey) -> Result<()> {
+    let exemption = &mut ctx.accounts.fee_exempt;
+    exemption.address = address;
+    exemption.granted_at = Clock::get()?.unix_timestamp;
+    exemption.bump = ctx.bumps.fee_exempt;
+
+    emit!(FeeExemptionGranted {
+        address,
+        timestamp: exemption.granted_at,
+    });
+
+    Ok(())
+}
+
+pub fn revoke_fee_exemption(ctx: Context<RevokeFeeExemption>) -> Result<()> {
+    emit!(FeeExemptionRevoked {
+        address: ctx.accounts.fee_exempt.address,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+pub fn open_delegator_billing(ctx: Context<OpenDelegatorBilling>, limit: u64) -> Result<()> {
+    require!(limit > 0, FeeError::InvalidBillingLimit);
+
+    reserve_allowance(
+        &ctx.accounts.mailer,
+        &ctx.accounts.delegator_usdc_account,
+        &ctx.accounts.delegator,
+        &ctx.accounts.token_program,
+        limit,
+    )?;
+
+    let billing = &mut ctx.accounts.billing;
+    billing.delegator = ctx.accounts.delegator.key();
+    billing.delegation = ctx.accounts.delegation.key();
+    billing.mint = ctx.accounts.mint.key();
+    billing.limit = limit;
+    billing.spent = 0;
+    billing.bump = ctx.bumps.billing;
+
+    emit!(DelegatorBillingOpened {
+        delegator: billing.delegator,
+        delegation: billing.delegation,
+        mint: billing.mint,
+        limit,
+    });
+
+    Ok(())
+}
+
+pub fn close_delegator_billing(ctx: Context<CloseDelegatorBilling>) -> Result<()> {
+    let billing = &ctx.accounts.billing;
+    release_allowance(
+        &ctx.accounts.mailer,
+        &ctx.accounts.delegator_usdc_account,
+        &ctx.accounts.delegator,
+        &ctx.accounts.token_program,
+        billing.limit.saturating_sub(billing.spent),
+    )?;
+
+    emit!(DelegatorBillingClosed {
+        delegator: billing.delegator,
+        delegation: billing.delegation,
+        spent: billing.spent,
+    });
+
+    Ok(())
+}
+
+/// Populate a freshly created owner bucket; existing buckets are left untouched
+pub(crate) fn init_owner_bucket(
+    bucket: &mut Account<OwnerBucket>,
+    mint: Pubkey,
+    source: FeeSource,
+    destination: Pubkey,
+    bump: u8,
+) {
+    if bucket.mint == Pubkey::default() {
+        bucket.mint = mint;
+        bucket.source = source;
+        bucket.destination = destination;
+        bucket.bump = bump;
+    }
+}
+
+/// Fee charged for a standard (non-priority) send: the owner's share only
+pub fn standard_fee(send_fee: u64) -> Result<u64> {
+    let owner_fee = send_fee
+        .checked_mul(OWNER_SHARE)
+        .ok_or(FeeError::MathOverflow)?;
+    Ok(owner_fee / 100)
+}
+
+/// Split a priority fee into the owner's share and the recipient's share
+///
+/// The owner's share is rounded down and the recipient takes the remainder,
+/// so the two always add back up to `total_amount`.
+pub fn split_shares(total_amount: u64) -> Result<(u64, u64)> {
+    let owner_amount = standard_fee(total_amount)?;
+    let recipient_amount = total_amount
+        .checked_sub(owner_amount)
+        .ok_or(FeeError::MathOverflow)?;
+    Ok((owner_amount, recipient_amount))
+}
+
+/// Outcome of charging a sender
+pub(crate) struct Charge {
+    /// Amount debited from the sender's token account
+    pub transferred: u64,
+    /// Portion of `transferred` withheld by a Token-2022 transfer-fee extension
+    pub withheld: u64,
+}
+
+impl Charge {
+    /// Portion of `fee` the mailer actually keeps once the withheld amount is removed
+    pub fn net_of(&self, fee: u64) -> Result<u64> {
+        fee.checked_sub(self.withheld).ok_or_else(|| error!(FeeError::MathOverflow))
+    }
+}
+
+/// Collect `fee` from the sender, drawing down send credit and then prepaid
+/// credit before transferring tokens
+///
+/// Send credit is only drawn when it is denominated in the mint being paid;
+/// prepaid credit always is, as its PDA is derived from the mint.
+/// The mailer's received amount is measured from its token balance, so mints
+/// with a transfer-fee extension only credit what actually arrived.
+pub(crate) fn charge_sender(accounts: &mut SendMessage, fee: u64) -> Result<Charge> {
+    let same_mint = claim_mint(&accounts.recipient_claim, &accounts.mailer) == accounts.mint.key();
+    let from_credit = if same_mint {
+        accounts.recipient_claim.send_credit.min(fee)
+    } else {
+        0
+    };
+    if from_credit > 0 {
+        accounts.recipient_claim.send_credit = accounts
+            .recipient_claim
+            .send_credit
+            .checked_sub(from_credit)
+            .ok_or(FeeError::MathOverflow)?;
+
+        emit!(SendCreditUsed {
+            sender: accounts.sender.key(),
+            amount: from_credit,
+            remaining_credit: accounts.recipient_claim.send_credit,
+        });
+    }
+
+    let mut transferred = fee.checked_sub(from_credit).ok_or(FeeError::MathOverflow)?;
+    if let Some(credit) = accounts.credit.as_mut() {
+        let from_prepaid = credit.draw(transferred);
+        if from_prepaid > 0 {
+            transferred -= from_prepaid;
+
+            emit!(PrepaidCreditUsed {
+                sender: accounts.sender.key(),
+                amount: from_prepaid,
+                remaining_balance: credit.balance,
+            });
+        }
+    }
+
+    let withheld = transferred
+        .checked_sub(transfer_from_sender(accounts, transferred)?)
+        .ok_or(FeeError::MathOverflow)?;
+
+    Ok(Charge {
+        transferred,
+        withheld,
+    })
+}
+
+/// Transfer `amount` from the sender to the mailer, returning what actually arrived
+pub(crate) fn transfer_from_sender(accounts: &mut SendMessage, amount: u64) -> Result<u64> {
+    if amount == 0 {
+        return Ok(0);
+    }
+
+    let balance_before = accounts.mailer_usdc_account.amount;
+    let transfer_ctx = CpiContext::new(
+        accounts.token_program.to_account_info(),
+        TransferChecked {
+            from: accounts.sender_usdc_account.to_account_info(),
+            mint: accounts.mint.to_account_info(),
+            to: accounts.mailer_usdc_account.to_account_info(),
+            authority: accounts.sender.to_account_info(),
+        },
+    );
+    token_interface::transfer_checked(transfer_ctx, amount, accounts.mint.decimals)?;
+
+    accounts.mailer_usdc_account.reload()?;
+    let received = accounts
+        .mailer_usdc_account
+        .amount
+        .checked_sub(balance_before)
+        .ok_or(FeeError::MathOverflow)?;
+    Ok(received)
+}
+
+/// Add `amount` to the mailer PDA's approval on `owner_account`, on top of
+/// what open sessions and billing allowances already reserved
+pub(crate) fn reserve_allowance<'info>(
+    mailer: &Account<'info, MailerState>,
+    owner_account: &InterfaceAccount<'info, TokenAccount>,
+    owner: &Signer<'info>,
+    token_program: &Interface<'info, TokenInterface>,
+    amount: u64,
+) -> Result<()> {
+    let allowance = if owner_account.delegate == Some(mailer.key()).into() {
+        owner_account
+            .delegated_amount
+            .checked_add(amount)
+            .ok_or(FeeError::MathOverflow)?
+    } else {
+        amount
+    };
+    let approve_ctx = CpiContext::new(
+        token_program.to_account_info(),
+        Approve {
+            to: owner_account.to_account_info(),
+            delegate: mailer.to_account_info(),
+            authority: owner.to_account_info(),
+        },
+    );
+    token_interface::approve(approve_ctx, allowance)
+}
+
+/// Take `unspent` back out of the mailer PDA's approval on `owner_account`
+///
+/// The approval is shared, so a reservation can already have been drawn down
+/// by signed sends or replaced by the owner re-approving; in that case there
+/// is less than `unspent` left and only what remains is withdrawn.
+pub(crate) fn release_allowance<'info>(
+    mailer: &Account<'info, MailerState>,
+    owner_account: &InterfaceAccount<'info, TokenAccount>,
+    owner: &Signer<'info>,
+    token_program: &Interface<'info, TokenInterface>,
+    unspent: u64,
+) -> Result<()> {
+    if owner_account.delegate != Some(mailer.key()).into() || unspent == 0 {
+        return Ok(());
+    }
+
+    match owner_account.delegated_amount.checked_sub(unspent) {
+        Some(allowance) if allowance > 0 => {
+            let approve_ctx = CpiContext::new(
+                token_program.to_account_info(),
+                Approve {
+                    to: owner_account.to_account_info(),
+                    delegate: mailer.to_account_info(),
+                    authority: owner.to_account_info(),
+                },
+            );
+            token_interface::approve(approve_ctx, allowance)
+        }
+        _ => {
+            let revoke_ctx = CpiContext::new(
+                token_program.to_account_info(),
+                Revoke {
+                    source: owner_account.to_account_info(),
+                    authority: owner.to_account_info(),
+                },
+            );
+            token_interface::revoke(revoke_ctx)
+        }
+    }
+}
+
+/// Collect `fee` from a token account that approved the mailer PDA as its
+/// delegate, for sends billed to a wallet that did not sign the transaction
+///
+/// Send credit is not drawn: it belongs to the signer's claim, not the payer.
+pub(crate) fn charge_allowance<'info>(
+    accounts: &mut SendMessage<'info>,
+    from: &InterfaceAccount<'info, TokenAccount>,
+    fee: u64,
+) -> Result<Charge> {
+    let received = transfer_from_allowance(
+        &accounts.mailer,
+        &accounts.mint,
+        from,
+        &mut accounts.mailer_usdc_account,
+        &accounts.token_program,
+        fee,
+    )?;
+    Ok(Charge {
+        transferred: fee,
+        withheld: fee.checked_sub(received).ok_or(FeeError::MathOverflow)?,
+    })
+}
+
+/// Pull `amount` from `from` into the mailer's token account with the mailer
+/// PDA as authority, returning what actually arrived
+///
+/// `from` must have approved the mailer PDA as delegate for at least `amount`.
+pub(crate) fn transfer_from_allowance<'info>(
+    mailer: &Account<'info, MailerState>,
+    mint: &InterfaceAccount<'info, Mint>,
+    from: &InterfaceAccount<'info, TokenAccount>,
+    mailer_usdc_account: &mut InterfaceAccount<'info, TokenAccount>,
+    token_program: &Interface<'info, TokenInterface>,
+    amount: u64,
+) -> Result<u64> {
+    if amount == 0 {
+        return Ok(0);
+    }
+    require!(
+        has_allowance(from, &mailer.key(), amount),
+        FeeError::InsufficientAllowance
+    );
+
+    let balance_before = mailer_usdc_account.amount;
+    release(mailer, mint, from, mailer_usdc_account, token_program, amount)?;
+
+    mailer_usdc_account.reload()?;
+    let received = mailer_usdc_account
+        .amount
+        .checked_sub(balance_before)
+        .ok_or(FeeError::MathOverflow)?;
+    Ok(received)
+}
+
+/// Whether `account` lets `delegate` move at least `amount` on its behalf
+pub fn has_allowance(account: &TokenAccount, delegate: &Pubkey, amount: u64) -> bool {
+    account.delegate == Some(*delegate).into() && account.delegated_amount >= amount
+}
+
+/// Split an expired amount into the cranker bounty, the send-credit rollover
+/// and the remainder that goes to the owner
+pub fn split_expired(
+    amount: u64,
+    bounty_bps: u16,
+    rollover_bps: u16,
+) -> Result<(u64, u64, u64)> {
+    let bounty = amount
+        .checked_mul(bounty_bps as u64)
+        .ok_or(FeeError::MathOverflow)?
+        / 10_000;
+    let after_bounty = amount.checked_sub(bounty).ok_or(FeeError::MathOverflow)?;
+    let rolled_over = after_bounty
+        .checked_mul(rollover_bps as u64)
+        .ok_or(FeeError::MathOverflow)?
+        / 10_000;
+    let owner_amount = after_bounty
+        .checked_sub(rolled_over)
+        .ok_or(FeeError::MathOverflow)?;
+    Ok((bounty, rolled_over, owner_amount))
+}
+
+/// Referrer's slice of the owner's share of a fee
+pub fn referral_amount(owner_amount: u64, referral_bps: u16) -> Result<u64> {
+    let amount = owner_amount
+        .checked_mul(referral_bps as u64)
+        .ok_or(FeeError::MathOverflow)?;
+    Ok(amount / BPS_DENOMINATOR as u64)
+}
+
+/// Credit a deposit to the recipient claim and owner bucket
+///
+/// Returns whether the deposit started a new claim period, in which case the
+/// claim still has to be listed in its expiry bucket.
+pub(crate) fn record_shares(
+    claim: &mut Account<RecipientClaim>,
+    owner_bucket: &mut Account<OwnerBucket>,
+    mailer: &MailerState,
+    recipient: Pubkey,
+    rent_payer: Pubkey,
+    total_amount: u64,
+) -> Result<bool> {
+    // Calculate owner amount first for precision
+    let (owner_amount, recipient_amount) = split_shares(total_amount)?;
+
+    let started = credit_claim(
+        claim,
+        mailer,
+        owner_bucket.mint,
+        recipient,
+        rent_payer,
+        recipient_amount,
+    )?;
+
+    // Remember the terms this deposit was made under
+    let fee_snapshot = FeeSnapshot::current(total_amount);
+    claim.fee_snapshot = fee_snapshot;
+
+    // Update owner's claimable amount in the paid mint's send bucket
+    owner_bucket.credit(owner_amount)?;
+
+    emit!(SharesRecorded {
+        recipient,
+        recipient_amount,
+        owner_amount,
+        fee_snapshot,
+        mint: owner_bucket.mint,
+    });
+
+    Ok(started)
+}
+
+/// Add `amount` of `mint` to a recipient's claim
+///
+/// Returns whether the deposit started a new claim period.
+pub(crate) fn credit_claim(
+    claim: &mut Account<RecipientClaim>,
+    mailer: &MailerState,
+    mint: Pubkey,
+    recipient: Pubkey,
+    rent_payer: Pubkey,
+    amount: u64,
+) -> Result<bool> {
+    // A freshly created claim records who funded its rent so it can be refunded on close
+    if claim.recipient == Pubkey::default() {
+        claim.rent_payer = rent_payer;
+    }
+
+    // A claim holds a single mint; it may only switch once fully drained
+    if claim_mint(claim, mailer) != mint {
+        require!(
+            claim.amount == 0 && claim.send_credit == 0,
+            TokenError::ClaimMintMismatch
+        );
+        claim.timestamp = 0;
+    }
+    claim.mint = mint;
+
+    // A streaming deposit unlocks over its own period instead of joining one underway
+    if claim.mode == ClaimMode::Streaming && claim.timestamp != 0 {
+        claim.restream(Clock::get()?.unix_timestamp)?;
+    }
+
+    // Update recipient's claimable amount and set timestamp only if not already set
+    claim.recipient = recipient;
+    claim.amount = claim.amount.checked_add(amount).ok_or(FeeError::MathOverflow)?;
+    let started = claim.timestamp == 0;
+    if started {
+        claim.timestamp = Clock::get()?.unix_timestamp;
+    }
+
+    Ok(started)
+}
+
+/// Optional tip attached to a send, paid on top of the fee
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Tip {
+    /// Tip in the paying mint's base units
+    pub amount: u64,
+    pub target: TipTarget,
+}
+
+/// Who a tip is credited to
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq, InitSpace)]
+pub enum TipTarget {
+    /// The owner's send bucket in the paying mint
+    Owner,
+    /// The recipient's claim, under the usual claim period
+    Recipient,
+}
+
+/// A proposed change to one `MailerState` parameter
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConfigChange {
+    SendFee { new_fee: u64 },
+    UsdFee { new_usd_fee_cents: u64 },
+    CrankBounty { new_bounty_bps: u16 },
+    Rollover { new_rollover_bps: u16 },
+    PowDifficulty { new_difficulty: u8 },
+    ReferralShare { new_referral_bps: u16 },
+    NftDiscount { discount_bps: u16 },
+}
+
+impl ConfigChange {
+    /// Check the proposed value against the parameter's invariants
+    ///
+    /// The matching setter and `simulate_config_change` both run this, so a
+    /// dry run fails exactly when the setter would.
+    pub fn validate(&self) -> Result<()> {
+        match *self {
+            ConfigChange::SendFee { new_fee } => validate_send_fee(new_fee),
+            ConfigChange::UsdFee { new_usd_fee_cents } => validate_usd_fee(new_usd_fee_cents),
+            ConfigChange::CrankBounty { new_bounty_bps } => validate_crank_bounty(new_bounty_bps),
+            ConfigChange::Rollover { new_rollover_bps } => validate_rollover(new_rollover_bps),
+            ConfigChange::PowDifficulty { new_difficulty } => {
+                validate_pow_difficulty(new_difficulty)
+            }
+            ConfigChange::ReferralShare { new_referral_bps } => {
+                validate_referral_share(new_referral_bps)
+            }
+            ConfigChange::NftDiscount { discount_bps } => validate_nft_discount(discount_bps),
+        }
+    }
+
+    /// Value of the parameter on `mailer` today
+    pub fn current_value(&self, mailer: &MailerState) -> u64 {
+        match self {
+            ConfigChange::SendFee { .. } => mailer.send_fee,
+            ConfigChange::UsdFee { .. } => mailer.usd_fee_cents,
+            ConfigChange::CrankBounty { .. } => mailer.crank_bounty_bps as u64,
+            ConfigChange::Rollover { .. } => mailer.rollover_bps as u64,
+            ConfigChange::PowDifficulty { .. } => mailer.pow_difficulty as u64,
+            ConfigChange::ReferralShare { .. } => mailer.referral_bps as u64,
+            ConfigChange::NftDiscount { .. } => mailer.nft_discount_bps as u64,
+        }
+    }
+
+    /// Proposed value of the parameter
+    pub fn new_value(&self) -> u64 {
+        match *self {
+            ConfigChange::SendFee { new_fee } => new_fee,
+            ConfigChange::UsdFee { new_usd_fee_cents } => new_usd_fee_cents,
+            ConfigChange::CrankBounty { new_bounty_bps } => new_bounty_bps as u64,
+            ConfigChange::Rollover { new_rollover_bps } => new_rollover_bps as u64,
+            ConfigChange::PowDifficulty { new_difficulty } => new_difficulty as u64,
+            ConfigChange::ReferralShare { new_referral_bps } => new_referral_bps as u64,
+            ConfigChange::NftDiscount { discount_bps } => discount_bps as u64,
+        }
+    }
+}
+
+/// How a send fee in a mint is split, returned by `get_fee_breakdown`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct FeeBreakdown {
+    pub mint: Pubkey,
+    /// Fee of a priority send, in the mint's base units
+    pub priority_fee: u64,
+    /// Fee of a standard send: the owner share only
+    pub standard_fee: u64,
+    /// Part of a priority fee credited back to the sender's claim
+    pub recipient_share: u64,
+    /// Part of a priority fee credited to the owner
+    pub owner_share: u64,
+    /// Seconds a lump-sum share stays claimable
+    pub claim_period: i64,
+    pub crank_bounty_bps: u16,
+    pub rollover_bps: u16,
+}
+
+/// Outcome of `simulate_config_change`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ConfigChangeReport {
+    pub change: ConfigChange,
+    /// Current value of the parameter
+    pub old_value: u64,
+    /// Proposed value of the parameter
+    pub new_value: u64,
+    /// Error the real setter would fail with, or 0 if the change is valid
+    pub error_code: u32,
+}
+
+/// Fee terms in effect when a revenue share was recorded
+///
+/// Stored on each claim and emitted with `SharesRecorded` so that deposits can
+/// be reconciled exactly even after the owner changes `send_fee`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq, InitSpace)]
+pub struct FeeSnapshot {
+    pub send_fee: u64,
+    pub owner_share: u8,
+    pub recipient_share: u8,
+}
+
+impl FeeSnapshot {
+    pub fn current(send_fee: u64) -> Self {
+        Self {
+            send_fee,
+            owner_share: OWNER_SHARE as u8,
+            recipient_share: RECIPIENT_SHARE as u8,
+        }
+    }
+}
+
+/// Revenue stream an owner share was collected from
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq, InitSpace)]
+pub enum FeeSource {
+    /// Owner share of priority and standard send fees
+    Send,
+    /// Expired recipient shares swept back to the owner
+    Expired,
+}
+
+impl FeeSource {
+    pub fn seed(&self) -> &'static [u8] {
+        match self {
+            FeeSource::Send => b"send",
+            FeeSource::Expired => b"expired",
+        }
+    }
+}
+
+/// Owner share accrued from one fee source in one mint
+///
+/// Each bucket is withdrawn independently to its own `destination`, so product
+/// lines can be accounted for and budgeted separately on-chain.
+#[account]
+#[derive(InitSpace)]
+pub struct OwnerBucket {
+    pub mint: Pubkey,
+    pub source: FeeSource,
+    pub destination: Pubkey,
+    pub claimable: u64,
+    pub bump: u8,
+    /// Send fees received in `mint`, net of transfer fees; only kept by send buckets
+    pub fees_collected: u64,
+}
+
+impl OwnerBucket {
+    /// Add `amount` to the bucket's claimable balance
+    pub(crate) fn credit(&mut self, amount: u64) -> Result<()> {
+        self.claimable = self
+            .claimable
+            .checked_add(amount)
+            .ok_or(FeeError::MathOverflow)?;
+        Ok(())
+    }
+
+    /// Add `fee`, a send's whole fee in `mint`, to the bucket's running total
+    pub(crate) fn record_fees(&mut self, fee: u64) -> Result<()> {
+        self.fees_collected = self
+            .fees_collected
+            .checked_add(fee)
+            .ok_or(FeeError::MathOverflow)?;
+        Ok(())
+    }
+}
+
+/// Marks an address whose sends are charged no fee; closed to revoke
+#[account]
+#[derive(InitSpace)]
+pub struct FeeExempt {
+    pub address: Pubkey,
+    pub granted_at: i64,
+    pub bump: u8,
+}
+
+/// A delegator's consent to be billed for its delegate's standard sends, up
+/// to `limit`; closed to withdraw it
+#[account]
+#[derive(InitSpace)]
+pub struct DelegatorBilling {
+    pub delegator: Pubkey,
+    /// Mail service delegation whose delegate may bill the delegator
+    pub delegation: Pubkey,
+    /// Mint the fees are pulled in
+    pub mint: Pubkey,
+    /// Most the delegate may bill in total, in `mint` base units
+    pub limit: u64,
+    pub spent: u64,
+    pub bump: u8,
+}
+
+impl DelegatorBilling {
+    /// Count `fee` against the limit
+    pub fn spend(&mut self, fee: u64) -> Result<()> {
+        let spent = self.spent.checked_add(fee).ok_or(FeeError::MathOverflow)?;
+        require!(spent <= self.limit, FeeError::BillingLimitExceeded);
+        self.spent = spent;
+        Ok(())
+    }
+}
+
+/// Read-only: anyone may simulate a change, e.g. while reviewing a governance proposal
+#[derive(Accounts)]
+pub struct SimulateConfigChange<'info> {
+    #[account(seeds = [seeds::MAILER], bump = mailer.bump)]
+    pub mailer: Account<'info, MailerState>,
+}
+
+#[derive(Accounts)]
+pub struct SetFee<'info> {
+    #[account(
+        mut,
+        seeds = [seeds::MAILER],
+        bump = mailer.bump,
+        has_one = owner @ MailerError::OnlyOwner
+    )]
+    pub mailer: Account<'info, MailerState>,
+
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(source: FeeSource)]
+pub struct SetBucketDestination<'info> {
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = 8 + OwnerBucket::INIT_SPACE,
+        seeds = [b"bucket", mint.key().as_ref(), source.seed()],
+        bump
+    )]
+    pub owner_bucket: Account<'info, OwnerBucket>,
+
+    #[account(
+        seeds = [seeds::MAILER],
+        bump = mailer.bump,
+        has_one = owner @ MailerError::OnlyOwner
+    )]
+    pub mailer: Account<'info, MailerState>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(address: Pubkey)]
+pub struct GrantFeeExemption<'info> {
+    /// Fails if the address is already exempt
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + FeeExempt::INIT_SPACE,
+        seeds = [b"exempt", address.as_ref()],
+        bump
+    )]
+    pub fee_exempt: Account<'info, FeeExempt>,
+
+    #[account(
+        seeds = [seeds::MAILER],
+        bump = mailer.bump,
+        has_one = owner @ MailerError::OnlyOwner
+    )]
+    pub mailer: Account<'info, MailerState>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RevokeFeeExemption<'info> {
+    /// Closed on revocation; rent returns to the owner
+    #[account(
+        mut,
+        close = owner,
+        seeds = [b"exempt", fee_exempt.address.as_ref()],
+        bump = fee_exempt.bump
+    )]
+    pub fee_exempt: Account<'info, FeeExempt>,
+
+    #[account(
+        seeds = [seeds::MAILER],
+        bump = mailer.bump,
+        has_one = owner @ MailerError::OnlyOwner
+    )]
+    pub mailer: Account<'info, MailerState>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct OpenDelegatorBilling<'info> {
+    /// Fails if the delegation already has a billing allowance; close it to change the limit
+    #[account(
+        init,
+        payer = delegator,
+        space = 8 + DelegatorBilling::INIT_SPACE,
+        seeds = [b"billing", delegation.key().as_ref()],
+        bump
+    )]
+    pub billing: Account<'info, DelegatorBilling>,
+
+    /// Any of the delegator's mail service delegations, its own or a per-delegate one
+    #[account(
+        owner = mail_service::ID,
+        constraint = delegation.delegator == delegator.key() @ FeeError::NotDelegator
+    )]
+    pub delegation: Account<'info, Delegation>,
+
+    #[account(seeds = [seeds::MAILER], bump = mailer.bump)]
+    pub mailer: Account<'info, MailerState>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(mut)]
+    pub delegator: Signer<'info>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = delegator,
+        associated_token::token_program = token_program
+    )]
+    pub delegator_usdc_account: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CloseDelegatorBilling<'info> {
+    /// Closed on withdrawal; rent returns to the delegator
+    #[account(
+        mut,
+        close = delegator,
+        seeds = [b"billing", billing.delegation.as_ref()],
+        bump = billing.bump,
+        has_one = delegator @ FeeError::NotDelegator,
+        has_one = mint @ TokenError::ClaimMintMismatch
+    )]
+    pub billing: Account<'info, DelegatorBilling>,
+
+    #[account(seeds = [seeds::MAILER], bump = mailer.bump)]
+    pub mailer: Account<'info, MailerState>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(mut)]
+    pub delegator: Signer<'info>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = delegator,
+        associated_token::token_program = token_program
+    )]
+    pub delegator_usdc_account: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[event]
+pub struct FeeUpdated {
+    pub old_fee: u64,
+    pub new_fee: u64,
+}
+
+#[event]
+pub struct UsdFeeUpdated {
+    pub old_usd_fee_cents: u64,
+    pub new_usd_fee_cents: u64,
+}
+
+#[event]
+pub struct SharesRecorded {
+    pub recipient: Pubkey,
+    pub recipient_amount: u64,
+    pub owner_amount: u64,
+    pub fee_snapshot: FeeSnapshot,
+    pub mint: Pubkey,
+}
+
+#[event]
+pub struct SendCreditUsed {
+    pub sender: Pubkey,
+    pub amount: u64,
+    pub remaining_credit: u64,
+}
+
+#[event]
+pub struct CrankBountyUpdated {
+    pub old_bounty_bps: u16,
+    pub new_bounty_bps: u16,
+}
+
+#[event]
+pub struct RolloverUpdated {
+    pub old_rollover_bps: u16,
+    pub new_rollover_bps: u16,
+}
+
+#[event]
+pub struct BucketDestinationUpdated {
+    pub mint: Pubkey,
+    pub source: FeeSource,
+    pub old_destination: Pubkey,
+    pub new_destination: Pubkey,
+}
+
+#[event]
+pub struct TipPaid {
+    pub from: Pubkey,
+    pub to: Pubkey,
+    pub mint: Pubkey,
+    /// Tip credited, net of any Token-2022 transfer fee
+    pub amount: u64,
+    pub target: TipTarget,
+}
+
+#[event]
+pub struct ConfigChangeSimulated {
+    pub report: ConfigChangeReport,
+}
+
+#[event]
+pub struct ReferralShareUpdated {
+    pub old_referral_bps: u16,
+    pub new_referral_bps: u16,
+}
+
+#[event]
+pub struct FeeExemptionGranted {
+    pub address: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct FeeExemptionRevoked {
+    pub address: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct DelegatorBillingOpened {
+    pub delegator: Pubkey,
+    pub delegation: Pubkey,
+    pub mint: Pubkey,
+    pub limit: u64,
+}
+
+#[event]
+pub struct DelegatorBillingClosed {
+    pub delegator: Pubkey,
+    pub delegation: Pubkey,
+    pub spent: u64,
+}
+
+/// Emitted alongside the mail-sent events of a fee-exempt sender, whose sends
+/// are recorded with zero fee
+#[event]
+pub struct FeeExemptSend {
+    pub sender: Pubkey,
+    /// Messages sent without a fee in this instruction
+    pub messages: u64,
+}
+
+#[error_code(offset = 6300)]
+pub enum FeeError {
+    #[msg("Crank bounty exceeds the maximum allowed")]
+    InvalidCrankBounty,
+    #[msg("Rollover exceeds 100%")]
+    InvalidRollover,
+    #[msg("Fee would make sends free or overflow a prepared batch")]
+    InvalidFee,
+    #[msg("Arithmetic overflow")]
+    MathOverflow,
+    #[msg("Referral share exceeds 100% of the owner share")]
+    InvalidReferralShare,
+    #[msg("Token account has not approved the mailer to pull this fee")]
+    InsufficientAllowance,
+    #[msg("Billing limit must be greater than zero")]
+    InvalidBillingLimit,
+    #[msg("Only the delegator can manage its billing allowance")]
+    NotDelegator,
+    #[msg("Delegated sends billed to the delegator need its billing allowance")]
+    BillingNotApproved,
+    #[msg("Delegator's billing allowance is spent")]
+    BillingLimitExceeded,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn standard_fee_is_owner_share() {
+        assert_eq!(standard_fee(SEND_FEE).unwrap(), 10_000);
+    }
+
+    #[test]
+    fn fee_breakdown_splits_priority_fee() {
+        let usdc = Pubkey::new_unique();
+        let breakdown = fee_breakdown(usdc, SEND_FEE, &crate::tokens::tests::mailer_with_usdc(usdc)).unwrap();
+        assert_eq!(breakdown.priority_fee, SEND_FEE);
+        assert_eq!(breakdown.standard_fee, breakdown.owner_share);
+        assert_eq!(breakdown.owner_share + breakdown.recipient_share, SEND_FEE);
+        assert_eq!(breakdown.recipient_share, 90_000);
+    }
+
+    #[test]
+    fn shares_add_up_to_whole_fee() {
+        assert_eq!(RECIPIENT_SHARE + OWNER_SHARE, 100);
+    }
+
+    #[test]
+    fn fee_snapshot_records_current_terms() {
+        let snapshot = FeeSnapshot::current(150_000);
+        assert_eq!(snapshot.send_fee, 150_000);
+        assert_eq!(snapshot.owner_share as u64, OWNER_SHARE);
+        assert_eq!(snapshot.recipient_share as u64, RECIPIENT_SHARE);
+    }
+
+    #[test]
+    fn fee_source_seeds_are_distinct() {
+        assert_ne!(FeeSource::Send.seed(), FeeSource::Expired.seed());
+    }
+
+    #[test]
+    fn split_expired_conserves_amount() {
+        let (bounty, rolled_over, owner_amount) = split_expired(90_000, 100, 5_000).unwrap();
+        assert_eq!(bounty, 900);
+        assert_eq!(rolled_over, 44_550);
+        assert_eq!(bounty + rolled_over + owner_amount, 90_000);
+    }
+
+    #[test]
+    fn referral_amount_is_a_slice_of_the_owner_share() {
+        assert_eq!(referral_amount(10_000, 2_500).unwrap(), 2_500);
+        assert_eq!(referral_amount(10_000, 0).unwrap(), 0);
+        assert_eq!(referral_amount(10_000, 10_000).unwrap(), 10_000);
+        assert!(validate_referral_share(10_001).is_err());
+    }
+
+    #[test]
+    fn split_expired_without_bounty_or_rollover_goes_to_owner() {
+        assert_eq!(split_expired(90_000, 0, 0).unwrap(), (0, 0, 90_000));
+    }
+
+    #[test]
+    fn send_fee_must_keep_standard_fee_and_fit_a_batch() {
+        assert!(validate_send_fee(SEND_FEE).is_ok());
+        assert!(validate_send_fee(10).is_ok());
+        assert!(validate_send_fee(9).is_err());
+        assert!(validate_send_fee(u64::MAX / MAX_PREPARED_BATCH as u64).is_ok());
+        assert!(validate_send_fee(u64::MAX / MAX_PREPARED_BATCH as u64 + 1).is_err());
+    }
+
+    #[test]
+    fn usd_fee_must_not_be_zero() {
+        assert!(validate_usd_fee(DEFAULT_USD_FEE_CENTS).is_ok());
+        assert!(validate_usd_fee(1).is_ok());
+        assert!(validate_usd_fee(0).is_err());
+    }
+
+    #[test]
+    fn failed_validation_reports_its_error_code() {
+        assert_eq!(validation_error_code(validate_rollover(10_000)), 0);
+        assert_eq!(
+            validation_error_code(validate_rollover(10_001)),
+            u32::from(FeeError::InvalidRollover)
+        );
+        assert_eq!(
+            validation_error_code(validate_crank_bounty(MAX_CRANK_BOUNTY_BPS + 1)),
+            u32::from(FeeError::InvalidCrankBounty)
+        );
+        assert_eq!(
+            validation_error_code(
+                ConfigChange::NftDiscount {
+                    discount_bps: BPS_DENOMINATOR + 1
+                }
+                .validate()
+            ),
+            u32::from(crate::nft_perks::NftPerkError::InvalidNftDiscount)
+        );
+    }
+
+    #[test]
+    fn standard_fee_overflows_at_the_u64_boundary() {
+        let max_fee = u64::MAX / OWNER_SHARE;
+        assert_eq!(standard_fee(max_fee).unwrap(), max_fee / 10);
+        assert!(standard_fee(max_fee + 1).is_err());
+        assert!(standard_fee(u64::MAX).is_err());
+    }
+
+    #[test]
+    fn split_expired_overflows_at_the_u64_boundary() {
+        let max_amount = u64::MAX / MAX_CRANK_BOUNTY_BPS as u64;
+        let (bounty, rolled_over, owner_amount) =
+            split_expired(max_amount, MAX_CRANK_BOUNTY_BPS, 0).unwrap();
+        assert_eq!(bounty + rolled_over + owner_amount, max_amount);
+        assert!(split_expired(max_amount + 1, MAX_CRANK_BOUNTY_BPS, 0).is_err());
+
+        // Without a bounty or rollover nothing is multiplied beyond the amount
+        assert_eq!(split_expired(u64::MAX, 0, 0).unwrap(), (0, 0, u64::MAX));
+        assert!(split_expired(u64::MAX, 0, 2).is_err());
+    }
+
+    #[test]
+    fn delegator_billing_stops_at_its_limit() {
+        let mut billing = DelegatorBilling {
+            delegator: Pubkey::new_unique(),
+            delegation: Pubkey::new_unique(),
+            mint: Pubkey::new_unique(),
+            limit: 20_000,
+            spent: 0,
+            bump: 0,
+        };
+        billing.spend(10_000).unwrap();
+        billing.spend(10_000).unwrap();
+        assert_eq!(
+            validation_error_code(billing.spend(1)),
+            u32::from(FeeError::BillingLimitExceeded)
+        );
+        assert_eq!(billing.spent, 20_000);
+    }
+}
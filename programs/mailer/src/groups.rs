@@ -0,0 +1,487 @@
+//! Group messaging with pooled fees.
+//!
+//! A group is a named PDA listing up to `MAX_GROUP_MEMBERS` members, managed by
+//! its admin. Any member can send a prepared message to the group for the
+//! priority fee; the owner share is taken as usual and the recipient share is
+//! split equally across the members' claims, with any indivisible remainder
+//! going to the owner. A member whose claim was closed gets a fresh one,
+//! paid for by the sender.
+//!
+//! The admin may appoint moderators, who can also remove members.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::hash::hash;
+
+use crate::campaigns::CampaignError;
+use crate::channels::{ModeratorGranted, ModeratorRevoked, MAX_MODERATORS};
+use crate::claims::{ClaimMode, RecipientClaim};
+use crate::expiry;
+use crate::fees::{charge_sender, credit_claim, split_shares, FeeError, FeeSnapshot};
+use crate::messaging::{
+    __client_accounts_send_message, __cpi_client_accounts_send_message, check_message_origin,
+    count_sends, emit_send_event, message_id, record_mail_id, record_sends, SendMessage,
+    SendMessageBumps,
+};
+use crate::referrals::ReferralError;
+
+/// Maximum length of a group name, in bytes
+pub const MAX_GROUP_NAME_LEN: usize = 32;
+
+/// Maximum number of members in a group
+pub const MAX_GROUP_MEMBERS: usize = 16;
+
+pub fn create_group(ctx: Context<CreateGroup>, name: String) -> Result<()> {
+    require!(
+        !name.is_empty() && name.len() <= MAX_GROUP_NAME_LEN,
+        GroupError::InvalidGroupName
+    );
+
+    let group = &mut ctx.accounts.group;
+    group.admin = ctx.accounts.admin.key();
+    group.name_hash = group_name_hash(&name);
+    group.name = name.clone();
+    group.members = Vec::new();
+    group.moderators = Vec::new();
+    group.bump = ctx.bumps.group;
+
+    emit!(GroupCreated {
+        group: group.key(),
+        admin: group.admin,
+        name,
+    });
+
+    Ok(())
+}
+
+pub fn add_member(ctx: Context<AddMember>, member: Pubkey) -> Result<()> {
+    let group = &mut ctx.accounts.group;
+    require!(!group.members.contains(&member), GroupError::AlreadyMember);
+    require!(
+        group.members.len() < MAX_GROUP_MEMBERS,
+        GroupError::GroupFull
+    );
+    group.members.push(member);
+
+    // Members receive shares into their claim, which the admin funds here if it is new
+    let claim = &mut ctx.accounts.member_claim;
+    if claim.recipient == Pubkey::default() {
+        claim.recipient = member;
+        claim.rent_payer = ctx.accounts.admin.key();
+        claim.bump = ctx.bumps.member_claim;
+    }
+
+    emit!(GroupMemberAdded {
+        group: group.key(),
+        member,
+        member_count: group.members.len() as u16,
+    });
+
+    Ok(())
+}
+
+pub fn remove_member(ctx: Context<ModerateGroup>, member: Pubkey) -> Result<()> {
+    let group = &mut ctx.accounts.group;
+    let count = group.members.len();
+    group.members.retain(|key| *key != member);
+    require!(group.members.len() < count, GroupError::NotMember);
+
+    emit!(GroupMemberRemoved {
+        group: group.key(),
+        member,
+        member_count: group.members.len() as u16,
+        removed_by: ctx.accounts.moderator.key(),
+    });
+
+    Ok(())
+}
+
+pub fn grant_group_moderator(ctx: Context<ManageGroup>, moderator: Pubkey) -> Result<()> {
+    let group = &mut ctx.accounts.group;
+    require!(
+        moderator != group.admin && !group.moderators.contains(&moderator),
+        GroupError::AlreadyModerator
+    );
+    require!(
+        group.moderators.len() < MAX_MODERATORS,
+        GroupError::TooManyModerators
+    );
+    group.moderators.push(moderator);
+
+    emit!(ModeratorGranted {
+        target: group.key(),
+        moderator,
+    });
+
+    Ok(())
+}
+
+pub fn revoke_group_moderator(ctx: Context<ManageGroup>, moderator: Pubkey) -> Result<()> {
+    let group = &mut ctx.accounts.group;
+    let count = group.moderators.len();
+    group.moderators.retain(|key| *key != moderator);
+    require!(group.moderators.len() < count, GroupError::UnknownModerator);
+
+    emit!(ModeratorRevoked {
+        target: group.key(),
+        moderator,
+    });
+
+    Ok(())
+}
+
+pub fn send_to_group<'info>(
+    ctx: Context<'_, '_, 'info, 'info, SendToGroup<'info>>,
+    mail_id: String,
+) -> Result<[u8; 32]> {
+    let sender = ctx.accounts.send.sender.key();
+    let group = &ctx.accounts.group;
+    require!(
+        group.admin == sender || group.members.contains(&sender),
+        GroupError::NotMember
+    );
+    require!(!group.members.is_empty(), GroupError::EmptyGroup);
+    require!(
+        ctx.remaining_accounts.len() == group.members.len(),
+        GroupError::InvalidMemberClaim
+    );
+    let members = group.members.clone();
+    check_message_origin(
+        &ctx.accounts.send.caller_policy,
+        &ctx.accounts.send.recipient_preferences,
+        &ctx.accounts.send.instructions,
+    )?;
+    count_sends(&mut ctx.accounts.send, 1, true)?;
+    require!(
+        ctx.accounts.send.referral.is_none(),
+        ReferralError::ReferralNotAccepted
+    );
+    require!(
+        ctx.accounts.send.campaign.is_none(),
+        CampaignError::CampaignNotAccepted
+    );
+
+    let send_fee = ctx.accounts.send.send_fee()?;
+    let charge = charge_sender(&mut ctx.accounts.send, send_fee)?;
+    let net_fee = charge.net_of(send_fee)?;
+    let (owner_amount, recipient_amount) = split_shares(net_fee)?;
+    let (member_share, remainder) = split_among(recipient_amount, members.len())?;
+
+    let accounts = &mut ctx.accounts.send;
+    accounts
+        .owner_bucket
+        .credit(owner_amount.checked_add(remainder).ok_or(FeeError::MathOverflow)?)?;
+
+    let fee_snapshot = FeeSnapshot::current(net_fee);
+    for (member, account_info) in members.iter().zip(ctx.remaining_accounts) {
+        let (expected, bump) =
+            Pubkey::find_program_address(&[b"claim", member.as_ref()], ctx.program_id);
+        require_keys_eq!(expected, account_info.key(), GroupError::InvalidMemberClaim);
+
+        if *member == sender {
+            // The sender's claim is already loaded by the send accounts; crediting a
+            // second copy would be overwritten when those accounts are written back
+            let mut claim = accounts.recipient_claim.clone();
+            credit_member(accounts, &mut claim, member_share, fee_snapshot)?;
+            accounts.recipient_claim.set_inner(claim.into_inner());
+        } else {
+            require!(account_info.is_writable, GroupError::InvalidMemberClaim);
+            if account_info.data_is_empty() {
+                // Members may close an emptied claim; reopen it for this share
+                open_member_claim(accounts, account_info, *member, bump)?;
+            }
+            let mut claim: Account<RecipientClaim> = Account::try_from(account_info)?;
+            credit_member(accounts, &mut claim, member_share, fee_snapshot)?;
+            claim.exit(ctx.program_id)?;
+        }
+    }
+
+    let message_seq = record_sends(accounts, 1, true, net_fee)?;
+    let clock = Clock::get()?;
+    let message_id = message_id(&sender, message_seq, clock.slot);
+    record_mail_id(accounts, &mail_id, message_id)?;
+
+    let event = GroupMailSent {
+        group: ctx.accounts.group.key(),
+        from: sender,
+        message_seq,
+        message_id,
+        timestamp: clock.unix_timestamp,
+        member_count: members.len() as u16,
+        member_share,
+        owner_amount,
+        mail_id,
+    };
+    emit_send_event!(ctx, event);
+
+    Ok(message_id)
+}
+
+/// Split `amount` equally among `members`, returning each member's share and the remainder
+pub fn split_among(amount: u64, members: usize) -> Result<(u64, u64)> {
+    let members = u64::try_from(members).map_err(|_| FeeError::MathOverflow)?;
+    require!(members > 0, GroupError::EmptyGroup);
+    Ok((amount / members, amount % members))
+}
+
+/// Key a group's PDA is derived from
+pub fn group_name_hash(name: &str) -> [u8; 32] {
+    hash(name.as_bytes()).to_bytes()
+}
+
+/// Create the claim PDA of a member who has none, funded by the payer
+///
+/// The recipient is left unset so `credit_claim` records the payer as the
+/// claim's rent payer, as it does for claims created by a send.
+fn open_member_claim<'info>(
+    accounts: &SendMessage<'info>,
+    claim: &AccountInfo<'info>,
+    member: Pubkey,
+    bump: u8,
+) -> Result<()> {
+    expiry::create_pda(
+        claim,
+        &accounts.payer.to_account_info(),
+        &accounts.system_program.to_account_info(),
+        &[b"claim", member.as_ref(), &[bump]],
+        8 + RecipientClaim::INIT_SPACE,
+    )?;
+
+    let fresh = RecipientClaim {
+        recipient: Pubkey::default(),
+        amount: 0,
+        timestamp: 0,
+        bump,
+        rent_payer: Pubkey::default(),
+        send_credit: 0,
+        fee_snapshot: FeeSnapshot::default(),
+        mint: Pubkey::default(),
+        mode: ClaimMode::Lump,
+        streamed: 0,
+        auto_claim: false,
+        vested: 0,
+        vesting_start: 0,
+        released: 0,
+    };
+    let mut data = claim.try_borrow_mut_data()?;
+    let mut writer: &mut [u8] = &mut data[..];
+    fresh.try_serialize(&mut writer)
+}
+
+/// Credit one member's share of a group send to their claim
+fn credit_member(
+    accounts: &SendMessage,
+    claim: &mut Account<RecipientClaim>,
+    amount: u64,
+    fee_snapshot: FeeSnapshot,
+) -> Result<()> {
+    let member = claim.recipient;
+    let started = credit_claim(
+        claim,
+        &accounts.mailer,
+        accounts.owner_bucket.mint,
+        member,
+        accounts.payer.key(),
+        amount,
+    )?;
+    claim.fee_snapshot = fee_snapshot;
+
+    if started && claim.mode != ClaimMode::Streaming {
+        expiry::track(
+            &accounts.expiry_index,
+            &accounts.payer.to_account_info(),
+            &accounts.system_program.to_account_info(),
+            member,
+            claim.timestamp,
+        )?;
+    }
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(name: String)]
+pub struct CreateGroup<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + Group::INIT_SPACE,
+        seeds = [b"group", group_name_hash(&name).as_ref()],
+        bump
+    )]
+    pub group: Account<'info, Group>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(member: Pubkey)]
+pub struct AddMember<'info> {
+    #[account(
+        mut,
+        seeds = [b"group", group.name_hash.as_ref()],
+        bump = group.bump,
+        has_one = admin @ GroupError::NotGroupAdmin
+    )]
+    pub group: Account<'info, Group>,
+
+    /// New member's claim, created if the member never received a share
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = 8 + RecipientClaim::INIT_SPACE,
+        seeds = [b"claim", member.as_ref()],
+        bump
+    )]
+    pub member_claim: Account<'info, RecipientClaim>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ManageGroup<'info> {
+    #[account(
+        mut,
+        seeds = [b"group", group.name_hash.as_ref()],
+        bump = group.bump,
+        has_one = admin @ GroupError::NotGroupAdmin
+    )]
+    pub group: Account<'info, Group>,
+
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ModerateGroup<'info> {
+    #[account(
+        mut,
+        seeds = [b"group", group.name_hash.as_ref()],
+        bump = group.bump,
+        constraint = group.can_moderate(&moderator.key()) @ GroupError::NotGroupModerator
+    )]
+    pub group: Account<'info, Group>,
+
+    pub moderator: Signer<'info>,
+}
+
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+pub struct SendToGroup<'info> {
+    /// Send accounts for the sending member, who pays the fee
+    pub send: SendMessage<'info>,
+
+    #[account(seeds = [b"group", group.name_hash.as_ref()], bump = group.bump)]
+    pub group: Account<'info, Group>,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Group {
+    /// Manages the member list; may send without being a member
+    pub admin: Pubkey,
+    /// `group_name_hash(name)`; seeds the group PDA
+    pub name_hash: [u8; 32],
+    #[max_len(MAX_GROUP_NAME_LEN)]
+    pub name: String,
+    #[max_len(MAX_GROUP_MEMBERS)]
+    pub members: Vec<Pubkey>,
+    /// May remove members; appointed by the admin
+    #[max_len(MAX_MODERATORS)]
+    pub moderators: Vec<Pubkey>,
+    pub bump: u8,
+}
+
+impl Group {
+    /// Whether `key` is the admin or one of the group's moderators
+    pub fn can_moderate(&self, key: &Pubkey) -> bool {
+        self.admin == *key || self.moderators.contains(key)
+    }
+}
+
+#[event]
+pub struct GroupCreated {
+    pub group: Pubkey,
+    pub admin: Pubkey,
+    pub name: String,
+}
+
+#[event]
+pub struct GroupMemberAdded {
+    pub group: Pubkey,
+    pub member: Pubkey,
+    pub member_count: u16,
+}
+
+#[event]
+pub struct GroupMemberRemoved {
+    pub group: Pubkey,
+    pub member: Pubkey,
+    pub member_count: u16,
+    pub removed_by: Pubkey,
+}
+
+/// Emitted by `send_to_group`; every member's claim was credited `member_share`
+#[event]
+pub struct GroupMailSent {
+    pub group: Pubkey,
+    pub from: Pubkey,
+    /// Global order of the message across all send paths
+    pub message_seq: u64,
+    /// `message_id(from, message_seq, slot)`
+    pub message_id: [u8; 32],
+    pub timestamp: i64,
+    pub member_count: u16,
+    pub member_share: u64,
+    /// Owner share, excluding the remainder of the member split
+    pub owner_amount: u64,
+    pub mail_id: String,
+}
+
+#[error_code(offset = 6800)]
+pub enum GroupError {
+    #[msg("Group name is empty or too long")]
+    InvalidGroupName,
+    #[msg("Only the group admin can perform this action")]
+    NotGroupAdmin,
+    #[msg("Address is already a member")]
+    AlreadyMember,
+    #[msg("Address is not a member")]
+    NotMember,
+    #[msg("Group has reached the maximum number of members")]
+    GroupFull,
+    #[msg("Group has no members")]
+    EmptyGroup,
+    #[msg("Remaining accounts must be the members' claims, in member order")]
+    InvalidMemberClaim,
+    #[msg("Only the group admin or a moderator can perform this action")]
+    NotGroupModerator,
+    #[msg("Address is already a moderator")]
+    AlreadyModerator,
+    #[msg("Address is not a moderator")]
+    UnknownModerator,
+    #[msg("Group has reached the maximum number of moderators")]
+    TooManyModerators,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn member_shares_and_remainder_sum_to_amount() {
+        assert_eq!(split_among(90_000, 4).unwrap(), (22_500, 0));
+        assert_eq!(split_among(90_000, 7).unwrap(), (12_857, 1));
+        assert!(split_among(90_000, 0).is_err());
+
+        for members in 1..=MAX_GROUP_MEMBERS {
+            let (share, remainder) = split_among(u64::MAX, members).unwrap();
+            assert!(remainder < members as u64);
+            assert_eq!(share * members as u64 + remainder, u64::MAX);
+        }
+    }
+}
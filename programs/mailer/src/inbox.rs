@@ -0,0 +1,103 @@
+//! Per-address inbox counters.
+//!
+//! Each `Inbox` PDA counts the messages delivered to one address and
+//! remembers the latest, so light clients can poll one small account to learn
+//! that new mail arrived instead of subscribing to logs. Sends update the
+//! recipient's inbox when it is passed, creating it (funded by the payer) on
+//! first delivery; sends that omit it, channel posts and group sends leave the
+//! counters untouched, so clients fetch the events once they see a change.
+
+use anchor_lang::prelude::*;
+
+use crate::expiry::create_pda;
+use crate::fees::FeeError;
+use crate::messaging::MessagingError;
+
+/// Count `messages` delivered to `owner` by `sender`, the last of which has
+/// sequence `last_message_seq`, creating the inbox on first use
+pub(crate) fn record<'info>(
+    inbox: &AccountInfo<'info>,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    owner: Pubkey,
+    sender: Pubkey,
+    last_message_seq: u64,
+    messages: u64,
+) -> Result<()> {
+    let (expected, bump) = Pubkey::find_program_address(&[b"inbox", owner.as_ref()], &crate::ID);
+    require_keys_eq!(expected, inbox.key(), MessagingError::InvalidInbox);
+
+    if inbox.data_is_empty() {
+        let space = 8 + Inbox::INIT_SPACE;
+        let seeds: &[&[u8]] = &[b"inbox", owner.as_ref(), &[bump]];
+        create_pda(inbox, payer, system_program, seeds, space)?;
+
+        let fresh = Inbox {
+            owner,
+            received_count: 0,
+            last_message_seq: 0,
+            last_sender: Pubkey::default(),
+            bump,
+        };
+        let mut data = inbox.try_borrow_mut_data()?;
+        let mut writer: &mut [u8] = &mut data[..];
+        fresh.try_serialize(&mut writer)?;
+    }
+
+    let mut data = inbox.try_borrow_mut_data()?;
+    let mut entry = Inbox::try_deserialize(&mut &data[..])?;
+    entry.deliver(sender, last_message_seq, messages)?;
+    let mut writer: &mut [u8] = &mut data[..];
+    entry.try_serialize(&mut writer)?;
+
+    Ok(())
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Inbox {
+    pub owner: Pubkey,
+    /// Messages delivered to `owner` since the inbox was created
+    pub received_count: u64,
+    /// Global sequence of the latest delivered message
+    pub last_message_seq: u64,
+    pub last_sender: Pubkey,
+    pub bump: u8,
+}
+
+impl Inbox {
+    pub fn deliver(&mut self, sender: Pubkey, last_message_seq: u64, messages: u64) -> Result<()> {
+        self.received_count = self
+            .received_count
+            .checked_add(messages)
+            .ok_or(FeeError::MathOverflow)?;
+        self.last_message_seq = last_message_seq;
+        self.last_sender = sender;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn delivery_counts_messages_and_keeps_the_latest() {
+        let mut inbox = Inbox {
+            owner: Pubkey::new_unique(),
+            received_count: 0,
+            last_message_seq: 0,
+            last_sender: Pubkey::default(),
+            bump: 0,
+        };
+        let (first, second) = (Pubkey::new_unique(), Pubkey::new_unique());
+        inbox.deliver(first, 4, 1).unwrap();
+        inbox.deliver(second, 12, 3).unwrap();
+        assert_eq!(inbox.received_count, 4);
+        assert_eq!(inbox.last_message_seq, 12);
+        assert_eq!(inbox.last_sender, second);
+
+        inbox.received_count = u64::MAX;
+        assert!(inbox.deliver(first, 13, 1).is_err());
+    }
+}
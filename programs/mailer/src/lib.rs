@@ -6,14 +6,98 @@
 //!
 //! - **Priority Messages**: Full fee (0.1 USDC) with 90% revenue share back to sender
 //! - **Standard Messages**: 10% fee only (0.01 USDC) with no revenue share
-//! - **Revenue Claims**: 60-day claim period for priority message revenue shares
+//! - **Revenue Claims**: 60-day claim period for priority message revenue shares,
+//!   paid as a lump sum or, by opting in, streamed linearly with no expiry
 //! - **Self-messaging**: All messages are sent to the sender's own address
+//! - **Token-2022**: Fees may be paid in Token Extensions mints; shares are
+//!   recorded on the amount received net of any transfer fee
+//! - **Oracle Pricing**: Mints with a Pyth feed are charged the token equivalent
+//!   of a USD target (`usd_fee_cents`) instead of a fixed amount
+//! - **Tips**: Sends may carry an optional tip on top of the fee for the owner
+//!   or the recipient
+//! - **Rent-on-sender**: Recipient-side PDAs are always funded by the sender or a
+//!   relayer (`payer`), so recipients never need SOL to receive or claim; every
+//!   send path takes the `payer` separately from the `sender`, so dApps can
+//!   sponsor SOL for senders who only hold fee tokens
+//! - **Channels**: Broadcast channels whose owners earn the recipient share of
+//!   posts and, optionally, a subscription fee per 30-day epoch
+//! - **Groups**: Member lists whose group sends split the recipient share
+//!   equally across the members' claims
+//! - **CPI Events**: With the `event-cpi` feature, mail-sent events are emitted
+//!   through a self-CPI so indexers can read them from inner instructions
 //!
 //! ## Program Architecture
 //!
+//! Instruction handlers, accounts, events and errors live in per-subsystem modules:
+//! - [`admin`]: initialization, `MailerState` and the original `MailerError` codes (errors `6000..`)
+//! - [`claims`]: revenue share claims and expiry sweeping (errors `6100..`)
+//! - [`messaging`]: sending and recipient preferences (errors `6200..`)
+//! - [`fees`]: fee parameters and share accounting (errors `6300..`)
+//! - [`tokens`]: accepted fee mints and per-mint configuration (errors `6400..`)
+//! - [`expiry`]: weekly index of claims by expiry, for keepers and dashboards
+//! - [`escrow`]: payment-bearing messages and acknowledgement deadlines (errors `6500..`)
+//! - [`pow`]: proof-of-work standard sends for senders without fee tokens (errors `6600..`)
+//! - [`channels`]: broadcast channels earning the recipient share of posts (errors `6700..`)
+//! - [`groups`]: member lists sharing the recipient share of group sends (errors `6800..`)
+//! - [`inbox`]: per-address delivery counters light clients poll for new mail
+//! - [`deposits`]: refundable anti-spam deposits from senders who are not contacts (errors `6900..`)
+//! - [`compliance`]: owner-managed denylist of barred addresses (errors `7000..`)
+//! - [`referrals`]: referral codes earning a slice of the owner share of sends (errors `7100..`)
+//! - [`campaigns`]: time-boxed, budgeted boosts of the priority recipient share (errors `7200..`)
+//! - [`loyalty`]: non-transferable points earned per paid message, redeemable for send credit (errors `7300..`)
+//! - [`nft_perks`]: fee discounts for holders of an owner-configured NFT collection (errors `7400..`)
+//! - [`receipts`]: compressed NFT receipts minted to senders of priority messages (errors `7500..`)
+//! - [`message_log`]: message hashes appended to a compressed Merkle tree for provable history (errors `7600..`)
+//! - [`message_roots`]: daily Merkle roots of all messages with inclusion proofs (errors `7700..`)
+//! - [`scheduled`]: prepared messages whose fee is escrowed until a crank sends them after a set time (errors `7800..`)
+//! - [`auto_claim`]: opt-in payout of expiring claims by a registered automation thread (errors `7900..`)
+//! - [`sessions`]: short-lived session keys sending on a wallet's behalf within spend and time limits (errors `8000..`)
+//! - [`signed_sends`]: prepared sends relayed on an Ed25519-signed payload and billed to its signer (errors `8100..`)
+//! - [`credits`]: prepaid, withdrawable fee balances that sends draw on before transferring tokens (errors `8200..`)
+//! - [`subscriptions`]: owner-priced plans whose subscribers send standard messages without a per-message fee (errors `8300..`)
+//! - [`staking`]: locked USDC stakes earning tiered fee discounts on sends (errors `8400..`)
+//! - [`vesting`]: priority sends trading a 30-day cliff on the sender's share for a 95% share (errors `8500..`)
+//!
 //! The program uses Program Derived Addresses (PDAs) for:
 //! - Mailer state: `[b"mailer"]`
+//! - Usage counters: `[b"mailer_stats"]`
 //! - Recipient claims: `[b"claim", recipient.key()]`
+//! - Recipient preferences: `[b"prefs", recipient.key()]`
+//! - CPI caller policy: `[b"callers"]`
+//! - Fee token configuration: `[b"token", mint.key()]`
+//! - Fee token display metadata: `[b"display", mint.key()]`
+//! - Owner share buckets: `[b"bucket", mint.key(), source]` (`b"send"` or `b"expired"`)
+//! - Expiry index: `[b"expiry", week.to_le_bytes()]` (`week` = expiry time / 7 days)
+//! - Payment escrows: `[b"escrow", payment_id]`
+//! - Proof-of-work counters: `[b"pow", sender.key()]`
+//! - Rate limits: `[b"rate", sender.key()]`
+//! - Channels: `[b"channel", sha256(name)]`
+//! - Channel subscriptions: `[b"subscription", channel.key(), subscriber.key()]`
+//! - Channel mutes: `[b"mute", channel.key(), sender.key()]`
+//! - Groups: `[b"group", sha256(name)]`
+//! - Read state: `[b"read", reader.key()]`
+//! - Inboxes: `[b"inbox", recipient.key()]`
+//! - Sender blocks: `[b"block", recipient.key(), sender.key()]`
+//! - Spam policies: `[b"spam", recipient.key()]`
+//! - Contacts: `[b"contact", recipient.key(), sender.key()]`
+//! - Spam deposits: `[b"deposit", recipient.key(), sender.key()]`
+//! - Denylist markers: `[b"deny", address]`
+//! - Fee exemptions: `[b"exempt", address]`
+//! - Referral codes: `[b"referral", sha256(code)]`
+//! - Campaigns: `[b"campaign", id.to_le_bytes()]`
+//! - Sender stats: `[b"stats", sender.key()]`
+//! - Receipt config: `[b"receipts"]`
+//! - Message log: `[b"log"]`
+//! - Daily message roots: `[b"root", day.to_le_bytes()]`
+//! - Mail ID records: `[b"mailid", sender.key(), sha256(mail_id)]`
+//! - Scheduled messages: `[b"scheduled", sender.key(), sha256(mail_id)]`
+//! - Sessions: `[b"session", owner.key(), session_key]`
+//! - Delegator billing allowances: `[b"billing", delegation.key()]`
+//! - Signed send nonces: `[b"nonce", sender.key()]`
+//! - Prepaid credit: `[b"credit", owner.key(), mint.key()]`
+//! - Subscription plans: `[b"plan", id.to_le_bytes()]`
+//! - Plan subscriptions: `[b"plan_subscription", subscriber.key()]`
+//! - Stakes: `[b"stake", owner.key()]`
 //!
 //! ## Fee Structure
 //!
@@ -26,33 +110,361 @@
 //!
 //! ```rust
 //! // Initialize the program
-//! initialize(ctx, usdc_mint_pubkey)?;
+//! initialize(ctx)?;
 //!
 //! // Send priority message (with revenue sharing)
-//! send_priority(ctx, "Subject".to_string(), "Body".to_string())?;
+//! send_priority(ctx, "Subject".to_string(), "Body".to_string(), None, None, None)?;
 //!
 //! // Claim revenue share within 60 days
 //! claim_recipient_share(ctx)?;
 //! ```
+//!
+//! ## Calling from Other Programs
+//!
+//! Depend on this crate with the `cpi` feature and invoke sends through the
+//! generated `mailer::cpi` module; `examples/cpi_caller` is a complete caller.
+//! A program-owned PDA can be the sender by signing with its seeds:
+//!
+//! ```rust,ignore
+//! let accounts = mailer::cpi::accounts::SendMessage { /* ... */ };
+//! let ctx = CpiContext::new_with_signer(mailer_program, accounts, signer_seeds);
+//! let message_id = mailer::cpi::send(ctx, subject, body, None, None, None)?.get();
+//! ```
+//!
+//! The owner can limit which programs may send via CPI with
+//! `set_caller_policy`, and recipients can further restrict programs sending
+//! to them with `set_origin_policy`. When built with `event-cpi`, the send account structs
+//! also take `event_authority` and `program`.
 
 use anchor_lang::prelude::*;
-use anchor_spl::token::{self, Token, TokenAccount, Transfer};
-use anchor_spl::associated_token::AssociatedToken;
 
-// Program ID for the Mailer program
-declare_id!("9FLkBDGpZBcR8LMsQ7MwwV6X9P4TDFgN3DeRh5qYyHJF");
+pub mod admin;
+pub mod auto_claim;
+pub mod campaigns;
+pub mod channels;
+pub mod claims;
+pub mod compliance;
+pub mod credits;
+pub mod deposits;
+pub mod escrow;
+pub mod expiry;
+pub mod fees;
+pub mod groups;
+pub mod inbox;
+pub mod loyalty;
+pub mod message_log;
+pub mod message_roots;
+pub mod messaging;
+pub mod nft_perks;
+pub mod pow;
+pub mod receipts;
+pub mod referrals;
+pub mod scheduled;
+pub mod sessions;
+pub mod signed_sends;
+pub mod staking;
+pub mod subscriptions;
+pub mod tokens;
+pub mod vesting;
 
-/// Base sending fee in USDC (with 6 decimals): 0.1 USDC
-const SEND_FEE: u64 = 100_000;
+pub use admin::{
+    InitStats, Initialize, MailerError, MailerState, MailerStats, MigrateMailer, UpdateUsdcMint,
+    UsdcMintUpdated,
+};
+pub use auto_claim::{
+    AutoClaim, AutoClaimError, AutoClaimUpdated, AutoClaimed, AutoClaimerUpdated, EnableAutoClaim,
+};
+pub use campaigns::{
+    Campaign, CampaignBoostPaid, CampaignClosed, CampaignCreated, CampaignError, CloseCampaign,
+    CreateCampaign,
+};
+pub use channels::{
+    Channel, ChannelCreated, ChannelError, ChannelFeesWithdrawn, ChannelMute, ChannelPosted,
+    ChannelSubscribed, ChannelTermsUpdated, CreateChannel, ManageChannel, ModerateChannel,
+    ModeratorGranted, ModeratorRevoked, MuteSender, PaySubscription, PostPinned, PostToChannel,
+    RemoveSubscriber, SenderMuted, SenderUnmuted, SetChannelTerms, Subscribe, SubscriberRemoved,
+    Subscription, SubscriptionPaid, UnmuteSender, WithdrawChannelFees,
+};
+pub use claims::{
+    ClaimClosed, ClaimError, ClaimExpiredShares, ClaimExpiredSharesBatch, ClaimExpiringSoon,
+    ClaimExtended, ClaimMode, ClaimModeUpdated, ClaimOwnerShare, ClaimRecipientShare,
+    ClaimableView, CloseClaim, ExpiredSharesClaimed, ExtendClaim, GetClaimable, MigrateClaim,
+    OwnerClaimed, PingClaims, RecipientClaim, RecipientClaimed, SetClaimMode,
+};
+pub use compliance::{
+    ComplianceError, DenyAddress, Denylist, DenylistAdded, DenylistRemoved, UndenyAddress,
+};
+pub use credits::{
+    Credit, CreditDeposited, CreditError, CreditWithdrawn, DepositCredit, PrepaidCreditUsed,
+    WithdrawCredit,
+};
+pub use deposits::{
+    ApproveSender, ConfiscateSpamDeposit, Contact, ContactApproved, DepositError,
+    RefundSpamDeposit, SetSpamDeposit, SpamDeposit, SpamDepositConfiscated, SpamDepositLocked,
+    SpamDepositRefunded, SpamPolicy, SpamPolicyUpdated,
+};
+pub use escrow::{
+    AcknowledgePayment, EscrowError, MailExpiredUnread, PaymentAcknowledged, PaymentEscrow,
+    PaymentMailSent, PaymentReclaimed, ReclaimPayment, RefundUnread, SendWithPayment,
+    SendWithPaymentArgs,
+};
+pub use expiry::ExpiryIndex;
+pub use fees::{
+    BucketDestinationUpdated, CloseDelegatorBilling, ConfigChange, ConfigChangeReport,
+    ConfigChangeSimulated, CrankBountyUpdated, DelegatorBilling, DelegatorBillingClosed,
+    DelegatorBillingOpened, FeeBreakdown, FeeError, FeeExempt, FeeExemptSend, FeeExemptionGranted,
+    FeeExemptionRevoked, FeeSnapshot, FeeSource, FeeUpdated, GrantFeeExemption,
+    OpenDelegatorBilling, OwnerBucket, ReferralShareUpdated, RevokeFeeExemption, RolloverUpdated,
+    SendCreditUsed, SetBucketDestination, SetFee, SharesRecorded, SimulateConfigChange, Tip,
+    TipPaid, TipTarget, UsdFeeUpdated,
+};
+pub use groups::{
+    AddMember, CreateGroup, Group, GroupCreated, GroupError, GroupMailSent, GroupMemberAdded,
+    GroupMemberRemoved, ManageGroup, ModerateGroup, SendToGroup,
+};
+pub use inbox::Inbox;
+pub use loyalty::{LoyaltyError, PointsRatesUpdated, PointsRedeemed, RedeemPoints, SenderStats};
+pub use message_log::{
+    InitMessageLog, MessageLog, MessageLogCreated, MessageLogError, MessageLogged, MessageVerified,
+    VerifyMessage,
+};
+pub use message_roots::{
+    InclusionVerified, MessageRoot, MessageRootError, MessageRootPublished, PublishMessageRoot,
+    RootPublisherUpdated, VerifyInclusion,
+};
+pub use messaging::{
+    BlockSender, CallerPolicy, CallerPolicyUpdated, ClaimNetted, ExternalAddress, MailIdRecord,
+    MailRead, MailSent, MarkRead, MessageLimitsUpdated, MessagingError, OriginPolicyUpdated,
+    PreparedBatchSent, PreparedMailSent, PriorityMailSent, PriorityPreparedMailSent, RateLimit,
+    RateLimitUpdated, ReadState, RecipientPreferences, SealedMailSent, SendAsDelegator,
+    SendMessage, SendSealed, SenderBlock, SenderBlocked, SenderUnblocked, SentAsDelegate,
+    SetCallerPolicy, SetOriginPolicy, SolWrapped, StandardMailSent, StandardPreparedMailSent,
+    UnblockSender, WrapSolFee,
+};
+pub use nft_perks::{NftPerkError, NftPerkUpdated};
+pub use pow::{PowDifficultyUpdated, PowError, PowMailSent, PowState, SendWithPow};
+pub use receipts::{
+    ReceiptConfig, ReceiptError, ReceiptMinted, ReceiptTreeUpdated, SetReceiptTree,
+};
+pub use referrals::{
+    ReferralCode, ReferralCodeRegistered, ReferralError, ReferralPaid, RegisterReferralCode,
+};
+pub use scheduled::{
+    CancelScheduled, ExecuteScheduled, ScheduleError, ScheduleSend, ScheduledMessage,
+    ScheduledSendCancelled, ScheduledSendExecuted, SendScheduled,
+};
+pub use sessions::{
+    CreateSession, RevokeSession, SendWithSession, Session, SessionCreated, SessionError,
+    SessionRevoked, SessionSend,
+};
+pub use signed_sends::{NonceRegistry, SendWithSignature, SignedSendError, SignedSendRelayed};
+pub use staking::{
+    Stake, StakeError, StakeTier, StakeTiersUpdated, StakeTokens, Staked, Unstake, Unstaked,
+};
+pub use subscriptions::{
+    CloseSubscriptionPlan, CreateSubscriptionPlan, PlanSubscription, PurchaseSubscription,
+    SubscriptionError, SubscriptionPlan, SubscriptionPlanClosed, SubscriptionPlanCreated,
+    SubscriptionPurchased, SubscriptionSendsCovered,
+};
+pub use tokens::{
+    ConfigureToken, FeeQuote, PriceFeedUpdated, QuoteFee, RemoveToken, SetPriceFeed,
+    SetTokenDisplay, TokenAdded, TokenConfig, TokenDisplay, TokenDisplayUpdated, TokenError,
+    TokenRemoved,
+};
+pub use vesting::{SharesVested, VestingError};
 
-/// Claim period for revenue shares: 60 days in seconds
-const CLAIM_PERIOD: i64 = 60 * 24 * 60 * 60;
+// Client account modules generated by `#[derive(Accounts)]`, which `#[program]`
+// resolves from the crate root
+pub(crate) use admin::{
+    __client_accounts_init_stats, __client_accounts_initialize, __client_accounts_migrate_mailer,
+    __client_accounts_update_usdc_mint,
+};
+pub(crate) use auto_claim::{__client_accounts_auto_claim, __client_accounts_enable_auto_claim};
+pub(crate) use campaigns::{__client_accounts_close_campaign, __client_accounts_create_campaign};
+pub(crate) use channels::{
+    __client_accounts_create_channel, __client_accounts_manage_channel,
+    __client_accounts_moderate_channel, __client_accounts_mute_sender,
+    __client_accounts_pay_subscription, __client_accounts_post_to_channel,
+    __client_accounts_remove_subscriber, __client_accounts_set_channel_terms,
+    __client_accounts_subscribe, __client_accounts_unmute_sender,
+    __client_accounts_withdraw_channel_fees,
+};
+pub(crate) use claims::{
+    __client_accounts_claim_expired_shares, __client_accounts_claim_expired_shares_batch,
+    __client_accounts_claim_owner_share, __client_accounts_claim_recipient_share,
+    __client_accounts_close_claim, __client_accounts_extend_claim, __client_accounts_get_claimable,
+    __client_accounts_migrate_claim, __client_accounts_ping_claims,
+    __client_accounts_set_claim_mode,
+};
+pub(crate) use compliance::{__client_accounts_deny_address, __client_accounts_undeny_address};
+pub(crate) use credits::{__client_accounts_deposit_credit, __client_accounts_withdraw_credit};
+pub(crate) use deposits::{
+    __client_accounts_approve_sender, __client_accounts_confiscate_spam_deposit,
+    __client_accounts_refund_spam_deposit, __client_accounts_set_spam_deposit,
+};
+pub(crate) use escrow::{
+    __client_accounts_acknowledge_payment, __client_accounts_reclaim_payment,
+    __client_accounts_refund_unread, __client_accounts_send_with_payment,
+};
+pub(crate) use fees::{
+    __client_accounts_close_delegator_billing, __client_accounts_grant_fee_exemption,
+    __client_accounts_open_delegator_billing, __client_accounts_revoke_fee_exemption,
+    __client_accounts_set_bucket_destination, __client_accounts_set_fee,
+    __client_accounts_simulate_config_change,
+};
+pub(crate) use groups::{
+    __client_accounts_add_member, __client_accounts_create_group, __client_accounts_manage_group,
+    __client_accounts_moderate_group, __client_accounts_send_to_group,
+};
+pub(crate) use loyalty::__client_accounts_redeem_points;
+pub(crate) use message_log::{
+    __client_accounts_init_message_log, __client_accounts_verify_message,
+};
+pub(crate) use message_roots::{
+    __client_accounts_publish_message_root, __client_accounts_verify_inclusion,
+};
+pub(crate) use messaging::{
+    __client_accounts_block_sender, __client_accounts_mark_read,
+    __client_accounts_send_as_delegator, __client_accounts_send_message,
+    __client_accounts_send_sealed, __client_accounts_set_caller_policy,
+    __client_accounts_set_origin_policy, __client_accounts_unblock_sender,
+    __client_accounts_wrap_sol_fee,
+};
+pub(crate) use pow::__client_accounts_send_with_pow;
+pub(crate) use receipts::__client_accounts_set_receipt_tree;
+pub(crate) use referrals::__client_accounts_register_referral_code;
+pub(crate) use scheduled::{
+    __client_accounts_cancel_scheduled, __client_accounts_execute_scheduled,
+    __client_accounts_schedule_send,
+};
+pub(crate) use sessions::{
+    __client_accounts_create_session, __client_accounts_revoke_session,
+    __client_accounts_send_with_session,
+};
+pub(crate) use signed_sends::__client_accounts_send_with_signature;
+pub(crate) use staking::{__client_accounts_stake_tokens, __client_accounts_unstake};
+pub(crate) use subscriptions::{
+    __client_accounts_close_subscription_plan, __client_accounts_create_subscription_plan,
+    __client_accounts_purchase_subscription,
+};
+pub(crate) use tokens::{
+    __client_accounts_configure_token, __client_accounts_quote_fee, __client_accounts_remove_token,
+    __client_accounts_set_price_feed, __client_accounts_set_token_display,
+};
 
-/// Percentage of fee that goes to message sender as revenue share: 90%
-const RECIPIENT_SHARE: u64 = 90;
+#[cfg(feature = "cpi")]
+pub(crate) use admin::{
+    __cpi_client_accounts_init_stats, __cpi_client_accounts_initialize,
+    __cpi_client_accounts_migrate_mailer, __cpi_client_accounts_update_usdc_mint,
+};
+#[cfg(feature = "cpi")]
+pub(crate) use auto_claim::{
+    __cpi_client_accounts_auto_claim, __cpi_client_accounts_enable_auto_claim,
+};
+#[cfg(feature = "cpi")]
+pub(crate) use campaigns::{
+    __cpi_client_accounts_close_campaign, __cpi_client_accounts_create_campaign,
+};
+#[cfg(feature = "cpi")]
+pub(crate) use channels::{
+    __cpi_client_accounts_create_channel, __cpi_client_accounts_manage_channel,
+    __cpi_client_accounts_moderate_channel, __cpi_client_accounts_mute_sender,
+    __cpi_client_accounts_pay_subscription, __cpi_client_accounts_post_to_channel,
+    __cpi_client_accounts_remove_subscriber, __cpi_client_accounts_set_channel_terms,
+    __cpi_client_accounts_subscribe, __cpi_client_accounts_unmute_sender,
+    __cpi_client_accounts_withdraw_channel_fees,
+};
+#[cfg(feature = "cpi")]
+pub(crate) use claims::{
+    __cpi_client_accounts_claim_expired_shares, __cpi_client_accounts_claim_expired_shares_batch,
+    __cpi_client_accounts_claim_owner_share, __cpi_client_accounts_claim_recipient_share,
+    __cpi_client_accounts_close_claim, __cpi_client_accounts_extend_claim,
+    __cpi_client_accounts_get_claimable, __cpi_client_accounts_migrate_claim,
+    __cpi_client_accounts_ping_claims, __cpi_client_accounts_set_claim_mode,
+};
+#[cfg(feature = "cpi")]
+pub(crate) use compliance::{
+    __cpi_client_accounts_deny_address, __cpi_client_accounts_undeny_address,
+};
+#[cfg(feature = "cpi")]
+pub(crate) use credits::{
+    __cpi_client_accounts_deposit_credit, __cpi_client_accounts_withdraw_credit,
+};
+#[cfg(feature = "cpi")]
+pub(crate) use deposits::{
+    __cpi_client_accounts_approve_sender, __cpi_client_accounts_confiscate_spam_deposit,
+    __cpi_client_accounts_refund_spam_deposit, __cpi_client_accounts_set_spam_deposit,
+};
+#[cfg(feature = "cpi")]
+pub(crate) use escrow::{
+    __cpi_client_accounts_acknowledge_payment, __cpi_client_accounts_reclaim_payment,
+    __cpi_client_accounts_refund_unread, __cpi_client_accounts_send_with_payment,
+};
+#[cfg(feature = "cpi")]
+pub(crate) use fees::{
+    __cpi_client_accounts_close_delegator_billing, __cpi_client_accounts_grant_fee_exemption,
+    __cpi_client_accounts_open_delegator_billing, __cpi_client_accounts_revoke_fee_exemption,
+    __cpi_client_accounts_set_bucket_destination, __cpi_client_accounts_set_fee,
+    __cpi_client_accounts_simulate_config_change,
+};
+#[cfg(feature = "cpi")]
+pub(crate) use groups::{
+    __cpi_client_accounts_add_member, __cpi_client_accounts_create_group,
+    __cpi_client_accounts_manage_group, __cpi_client_accounts_moderate_group,
+    __cpi_client_accounts_send_to_group,
+};
+#[cfg(feature = "cpi")]
+pub(crate) use loyalty::__cpi_client_accounts_redeem_points;
+#[cfg(feature = "cpi")]
+pub(crate) use message_log::{
+    __cpi_client_accounts_init_message_log, __cpi_client_accounts_verify_message,
+};
+#[cfg(feature = "cpi")]
+pub(crate) use message_roots::{
+    __cpi_client_accounts_publish_message_root, __cpi_client_accounts_verify_inclusion,
+};
+#[cfg(feature = "cpi")]
+pub(crate) use messaging::{
+    __cpi_client_accounts_block_sender, __cpi_client_accounts_mark_read,
+    __cpi_client_accounts_send_as_delegator, __cpi_client_accounts_send_message,
+    __cpi_client_accounts_send_sealed, __cpi_client_accounts_set_caller_policy,
+    __cpi_client_accounts_set_origin_policy, __cpi_client_accounts_unblock_sender,
+    __cpi_client_accounts_wrap_sol_fee,
+};
+#[cfg(feature = "cpi")]
+pub(crate) use pow::__cpi_client_accounts_send_with_pow;
+#[cfg(feature = "cpi")]
+pub(crate) use receipts::__cpi_client_accounts_set_receipt_tree;
+#[cfg(feature = "cpi")]
+pub(crate) use referrals::__cpi_client_accounts_register_referral_code;
+#[cfg(feature = "cpi")]
+pub(crate) use scheduled::{
+    __cpi_client_accounts_cancel_scheduled, __cpi_client_accounts_execute_scheduled,
+    __cpi_client_accounts_schedule_send,
+};
+#[cfg(feature = "cpi")]
+pub(crate) use sessions::{
+    __cpi_client_accounts_create_session, __cpi_client_accounts_revoke_session,
+    __cpi_client_accounts_send_with_session,
+};
+#[cfg(feature = "cpi")]
+pub(crate) use signed_sends::__cpi_client_accounts_send_with_signature;
+#[cfg(feature = "cpi")]
+pub(crate) use staking::{__cpi_client_accounts_stake_tokens, __cpi_client_accounts_unstake};
+#[cfg(feature = "cpi")]
+pub(crate) use subscriptions::{
+    __cpi_client_accounts_close_subscription_plan, __cpi_client_accounts_create_subscription_plan,
+    __cpi_client_accounts_purchase_subscription,
+};
+#[cfg(feature = "cpi")]
+pub(crate) use tokens::{
+    __cpi_client_accounts_configure_token, __cpi_client_accounts_quote_fee,
+    __cpi_client_accounts_remove_token, __cpi_client_accounts_set_price_feed,
+    __cpi_client_accounts_set_token_display,
+};
 
-/// Percentage of fee that goes to program owner: 10%
-const OWNER_SHARE: u64 = 10;
+// Program ID for the Mailer program
+declare_id!("9FLkBDGpZBcR8LMsQ7MwwV6X9P4TDFgN3DeRh5qYyHJF");
 
 #[program]
 pub mod mailer {
@@ -65,29 +477,67 @@ pub mod mailer {
     ///
     /// # Arguments
     /// * `ctx` - Anchor context with required accounts
-    /// * `usdc_mint` - Public key of the USDC token mint (must have 6 decimals)
     ///
     /// # Accounts
     /// * `mailer` - The main program state account (PDA)
+    /// * `stats` - Usage counters updated by every send (PDA)
+    /// * `usdc_mint` - The USDC token mint (must be a mint account with 6 decimals)
     /// * `owner` - Program owner with administrative privileges
     /// * `system_program` - System program for account creation
     ///
     /// # Errors
-    /// Returns an error if account initialization fails
+    /// * `InvalidUsdcMint` - If `usdc_mint` is not a mint or does not have 6 decimals
     ///
     /// # Example
     /// ```rust
-    /// let usdc_mint = Pubkey::from_str("EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v")?;
-    /// initialize(ctx, usdc_mint)?;
+    /// // `usdc_mint` is passed in the accounts, e.g. EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v
+    /// initialize(ctx)?;
     /// ```
-    pub fn initialize(ctx: Context<Initialize>, usdc_mint: Pubkey) -> Result<()> {
-        let mailer = &mut ctx.accounts.mailer;
-        mailer.owner = ctx.accounts.owner.key();
-        mailer.usdc_mint = usdc_mint;
-        mailer.send_fee = SEND_FEE;
-        mailer.owner_claimable = 0;
-        mailer.bump = ctx.bumps.mailer;
-        Ok(())
+    pub fn initialize(ctx: Context<Initialize>) -> Result<()> {
+        admin::initialize(ctx)
+    }
+
+    /// Create the usage counters for a deployment initialized before they existed (owner only)
+    ///
+    /// Sends need the `[b"mailer_stats"]` account, so this must run once after
+    /// upgrading such a deployment; new deployments get it from `initialize`.
+    ///
+    /// # Errors
+    /// * `OnlyOwner` - If caller is not the owner
+    pub fn init_stats(ctx: Context<InitStats>) -> Result<()> {
+        admin::init_stats(ctx)
+    }
+
+    /// Grow a MailerState created by an earlier program version to the current layout (owner only)
+    ///
+    /// Upgrades only ever append fields to the state, so sends fail to decode
+    /// it until this has run once after upgrading such a deployment. Appended
+    /// fields get the defaults `initialize` gives them; the owner pays the
+    /// extra rent. A state already at the current size is left unchanged.
+    ///
+    /// # Errors
+    /// * `OnlyOwner` - If caller is not the owner
+    /// * `InvalidMigrationAccount` - If `mailer` is not the mailer state account
+    pub fn migrate_mailer(ctx: Context<MigrateMailer>) -> Result<()> {
+        admin::migrate_mailer(ctx)
+    }
+
+    /// Replace a misconfigured USDC mint (owner only)
+    ///
+    /// Recovery path for deployments initialized with the wrong mint. Only
+    /// allowed while nothing is denominated in the current mint: the owner
+    /// balance is empty and the mint was never accepted with `add_token`.
+    ///
+    /// # Accounts
+    /// * `old_token_config` - Fee token config PDA of the current mint (must not exist)
+    /// * `new_usdc_mint` - Replacement mint (must have 6 decimals)
+    ///
+    /// # Errors
+    /// * `OnlyOwner` - If caller is not the owner
+    /// * `InvalidUsdcMint` - If `new_usdc_mint` does not have 6 decimals
+    /// * `UsdcMintInUse` - If fees or claims may already exist in the current mint
+    pub fn update_usdc_mint(ctx: Context<UpdateUsdcMint>) -> Result<()> {
+        admin::update_usdc_mint(ctx)
     }
 
     /// Send a priority message with full fee and 90% revenue sharing
@@ -96,64 +546,84 @@ pub mod mailer {
     /// 90% back as claimable revenue within 60 days. This creates an incentive
     /// system where frequent users can recover most of their costs.
     ///
+    /// Every send returns the message's ID, `hash(sender, message_seq, slot)`
+    /// (see [`messaging::message_id`]), as instruction return data and emits it
+    /// in its event, so clients and indexers correlate messages by the same ID.
+    /// Bodies over 512 bytes are emitted as `body_hash` (SHA-256) and
+    /// `body_len` instead of in full, keeping events within the log limit.
+    ///
     /// # Arguments
     /// * `ctx` - Anchor context with required accounts
     /// * `subject` - Message subject line (plain text)
     /// * `body` - Message content (plain text)
+    /// * `tip` - Optional extra amount paid on top of the fee, credited to the
+    ///   owner's send bucket or the recipient's claim
+    /// * `to_external` - Optional recipient address on another chain (chain ID +
+    ///   address bytes), carried in the event for the cross-chain relay
+    /// * `client_ref` - Optional caller-chosen 16-byte reference echoed in the event,
+    ///   e.g. an idempotency key for matching confirmations to backend records
     ///
     /// # Accounts
     /// * `recipient_claim` - PDA to store claimable revenue for sender
     /// * `mailer` - Main program state account
+    /// * `stats` - Usage counters; assigns the message its sequence number
+    /// * `token_config` - Fee token configuration PDA for `mint` (must be enabled)
+    /// * `price_update` - Pyth price update for `mint`; only for oracle-priced mints
+    /// * `mint` - Mint the fee is paid in
     /// * `sender` - User sending the message (signer)
-    /// * `sender_usdc_account` - Sender's USDC associated token account
-    /// * `mailer_usdc_account` - Program's USDC associated token account
-    /// * `token_program` - SPL Token program
+    /// * `sender_usdc_account` - Sender's associated token account for `mint`
+    /// * `mailer_usdc_account` - Program's associated token account for `mint`
+    /// * `expiry_index` - Expiry bucket for the current week; lists a newly started claim
+    /// * `rate_limit` - Sender's `[b"rate", sender]` daily message counter
+    /// * `denylist` - Sender's `[b"deny", sender]` compliance marker (must not exist)
+    /// * `fee_exempt` - Optional `[b"exempt", sender]` marker; when passed no fee is charged
+    /// * `referral` / `referrer_claim` - Optional referral code and its referrer's claim,
+    ///   credited with `referral_bps` of the owner's share
+    /// * `campaign` - Optional running campaign; its boost of the recipient share is
+    ///   paid out of the owner's share until the budget runs out
+    /// * `sender_stats` - Optional `[b"stats", sender]` loyalty points, created on first use
+    /// * `nft_token` / `nft_metadata` - Optional proof the sender holds an NFT of the perk
+    ///   collection, discounting the fee by `nft_discount_bps`
+    /// * `receipt_config` / `receipt_tree_config` / `receipt_tree` / `bubblegum_program` /
+    ///   `log_wrapper` / `compression_program` - Optional receipt accounts; when passed a
+    ///   cNFT receipt of the message is minted to the sender
+    /// * `message_log` / `message_log_tree` - Optional `[b"log"]` log and its tree; when
+    ///   passed with `log_wrapper` and `compression_program` the message's leaf is appended
+    /// * `token_program` - SPL Token or Token-2022 program owning `mint`
     /// * `associated_token_program` - Associated Token program
     /// * `system_program` - System program
     ///
     /// # Errors
     /// * `InsufficientFunds` - If sender doesn't have enough USDC
     /// * `TokenTransferFailed` - If USDC transfer fails
+    /// * `PriceFeedRequired` / `PriceFeedMismatch` / `StalePrice` - If an oracle-priced
+    ///   mint is sent without a fresh price for its feed
+    /// * `UncertainPrice` - If that price's confidence interval exceeds 2% of it
+    /// * `InvalidExternalAddress` - If `to_external` has no chain ID or 0 / more than 64 bytes
+    /// * `MessageTooLarge` - If the subject or body exceeds the owner's length limits
+    /// * `RateLimitExceeded` - If the sender has used up the owner's daily message limit
+    /// * `AddressDenied` - If the sender is on the compliance denylist
+    /// * `InvalidNftProof` - If the passed NFT is not the sender's verified NFT of the perk collection
+    /// * `SelfReferral` / `InvalidReferrerClaim` - If the referral code is the sender's own
+    ///   or `referrer_claim` is not its referrer's claim
+    /// * `ReceiptsDisabled` / `MissingReceiptAccounts` / `InvalidReceiptTree` - If a receipt
+    ///   is requested without a configured tree, all receipt accounts or the configured tree
+    /// * `MissingMessageLogAccounts` / `InvalidMessageLogTree` - If the message log is passed
+    ///   without its tree and programs, or with another tree
     ///
     /// # Example
     /// ```rust
-    /// send_priority(ctx, "Important Update".to_string(), "This is urgent!".to_string())?;
+    /// send_priority(ctx, "Important Update".to_string(), "This is urgent!".to_string(), None, None, None)?;
     /// ```
     pub fn send_priority(
         ctx: Context<SendMessage>,
         subject: String,
         body: String,
-    ) -> Result<()> {
-        let sender = ctx.accounts.sender.key();
-        
-        // Transfer full send fee from sender to mailer contract
-        let transfer_ctx = CpiContext::new(
-            ctx.accounts.token_program.to_account_info(),
-            Transfer {
-                from: ctx.accounts.sender_usdc_account.to_account_info(),
-                to: ctx.accounts.mailer_usdc_account.to_account_info(),
-                authority: ctx.accounts.sender.to_account_info(),
-            },
-        );
-        let send_fee = ctx.accounts.mailer.send_fee;
-        token::transfer(transfer_ctx, send_fee)?;
-
-        // Record shares for revenue sharing
-        record_shares(
-            &mut ctx.accounts.recipient_claim,
-            &mut ctx.accounts.mailer,
-            sender,
-            send_fee,
-        )?;
-
-        emit!(MailSent {
-            from: sender,
-            to: sender, // Messages are sent to self
-            subject,
-            body,
-        });
-
-        Ok(())
+        tip: Option<Tip>,
+        to_external: Option<ExternalAddress>,
+        client_ref: Option<[u8; 16]>,
+    ) -> Result<[u8; 32]> {
+        messaging::send_priority(ctx, subject, body, tip, to_external, client_ref)
     }
 
     /// Send a priority message using a pre-prepared mail identifier
@@ -165,52 +635,64 @@ pub mod mailer {
     /// # Arguments
     /// * `ctx` - Anchor context with required accounts
     /// * `mail_id` - Pre-prepared message identifier (e.g., IPFS hash, UUID)
+    /// * `tip` - Optional tip, as in send_priority
+    /// * `to_external` - Optional recipient on another chain, as in send_priority
+    /// * `client_ref` - Optional caller reference echoed in the event, as in send_priority
     ///
     /// # Accounts
-    /// Same as send_priority
+    /// Same as send_priority, plus:
+    /// * `mail_id_record` - Optional `[b"mailid", sender, sha256(mail_id)]` PDA; when passed it
+    ///   is created by this send, so any later send of the same `mail_id` by the sender is rejected
     ///
     /// # Errors
     /// * `InsufficientFunds` - If sender doesn't have enough USDC
     /// * `TokenTransferFailed` - If USDC transfer fails
+    /// * `DuplicateMailId` - If `mail_id_record` already exists
+    /// * `InvalidMailIdRecord` - If `mail_id_record` is not the PDA for `mail_id`
     ///
     /// # Example
     /// ```rust
     /// let ipfs_hash = "QmX7Y8Z9...".to_string();
-    /// send_priority_prepared(ctx, ipfs_hash)?;
+    /// send_priority_prepared(ctx, ipfs_hash, None, None, None)?;
     /// ```
     pub fn send_priority_prepared(
         ctx: Context<SendMessage>,
         mail_id: String,
-    ) -> Result<()> {
-        let sender = ctx.accounts.sender.key();
-        
-        // Transfer full send fee from sender to mailer contract
-        let transfer_ctx = CpiContext::new(
-            ctx.accounts.token_program.to_account_info(),
-            Transfer {
-                from: ctx.accounts.sender_usdc_account.to_account_info(),
-                to: ctx.accounts.mailer_usdc_account.to_account_info(),
-                authority: ctx.accounts.sender.to_account_info(),
-            },
-        );
-        let send_fee = ctx.accounts.mailer.send_fee;
-        token::transfer(transfer_ctx, send_fee)?;
-
-        // Record shares for revenue sharing
-        record_shares(
-            &mut ctx.accounts.recipient_claim,
-            &mut ctx.accounts.mailer,
-            sender,
-            send_fee,
-        )?;
-
-        emit!(PreparedMailSent {
-            from: sender,
-            to: sender, // Messages are sent to self
-            mail_id,
-        });
-
-        Ok(())
+        tip: Option<Tip>,
+        to_external: Option<ExternalAddress>,
+        client_ref: Option<[u8; 16]>,
+    ) -> Result<[u8; 32]> {
+        messaging::send_priority_prepared(ctx, mail_id, tip, to_external, client_ref)
+    }
+
+    /// Send a priority message whose revenue share vests behind a 30-day cliff
+    ///
+    /// Like send_priority, but the sender's claim is credited 95% of the fee
+    /// instead of 90%, with the extra taken from the owner's share after any
+    /// referral. The whole share is locked until 30 days after the send and
+    /// cannot be claimed, netted or auto-claimed before then; each vested
+    /// send restarts the cliff for shares still locked. Campaign boosts do not
+    /// apply.
+    ///
+    /// # Arguments
+    /// * `subject` - Message subject line
+    /// * `body` - Message body content
+    /// * `client_ref` - Optional caller reference echoed in the event, as in send_priority
+    ///
+    /// # Accounts
+    /// Same as send_priority
+    ///
+    /// # Errors
+    /// Same as send_priority, plus:
+    /// * `VestingRequiresLumpClaim` - If the sender's claim streams
+    /// * `VestingOutlivesClaim` - If the sender's claim period ends within 30 days
+    pub fn send_priority_vested(
+        ctx: Context<SendMessage>,
+        subject: String,
+        body: String,
+        client_ref: Option<[u8; 16]>,
+    ) -> Result<[u8; 32]> {
+        messaging::send_priority_vested(ctx, subject, body, client_ref)
     }
 
     /// Send a standard message with 10% fee only (no revenue sharing)
@@ -223,6 +705,9 @@ pub mod mailer {
     /// * `ctx` - Anchor context with required accounts
     /// * `subject` - Message subject line (plain text)
     /// * `body` - Message content (plain text)
+    /// * `tip` - Optional tip, as in send_priority
+    /// * `to_external` - Optional recipient on another chain, as in send_priority
+    /// * `client_ref` - Optional caller reference echoed in the event, as in send_priority
     ///
     /// # Accounts
     /// Same as send_priority (recipient_claim account still required but not used)
@@ -230,41 +715,21 @@ pub mod mailer {
     /// # Errors
     /// * `InsufficientFunds` - If sender doesn't have enough USDC
     /// * `TokenTransferFailed` - If USDC transfer fails
+    /// * `MessageTooLarge` - If the subject or body exceeds the owner's length limits
     ///
     /// # Example
     /// ```rust
-    /// send(ctx, "Regular Update".to_string(), "Standard message".to_string())?;
+    /// send(ctx, "Regular Update".to_string(), "Standard message".to_string(), None, None, None)?;
     /// ```
     pub fn send(
         ctx: Context<SendMessage>,
         subject: String,
         body: String,
-    ) -> Result<()> {
-        let sender = ctx.accounts.sender.key();
-        let owner_fee = (ctx.accounts.mailer.send_fee * OWNER_SHARE) / 100;
-        
-        // Transfer only owner fee (10%) from sender to mailer contract
-        let transfer_ctx = CpiContext::new(
-            ctx.accounts.token_program.to_account_info(),
-            Transfer {
-                from: ctx.accounts.sender_usdc_account.to_account_info(),
-                to: ctx.accounts.mailer_usdc_account.to_account_info(),
-                authority: ctx.accounts.sender.to_account_info(),
-            },
-        );
-        token::transfer(transfer_ctx, owner_fee)?;
-
-        // Only add to owner claimable, no revenue sharing
-        ctx.accounts.mailer.owner_claimable += owner_fee;
-
-        emit!(MailSent {
-            from: sender,
-            to: sender, // Messages are sent to self
-            subject,
-            body,
-        });
-
-        Ok(())
+        tip: Option<Tip>,
+        to_external: Option<ExternalAddress>,
+        client_ref: Option<[u8; 16]>,
+    ) -> Result<[u8; 32]> {
+        messaging::send(ctx, subject, body, tip, to_external, client_ref)
     }
 
     /// Send a standard message using a pre-prepared mail identifier
@@ -275,409 +740,1639 @@ pub mod mailer {
     /// # Arguments
     /// * `ctx` - Anchor context with required accounts
     /// * `mail_id` - Pre-prepared message identifier
+    /// * `tip` - Optional tip, as in send_priority
+    /// * `to_external` - Optional recipient on another chain, as in send_priority
+    /// * `client_ref` - Optional caller reference echoed in the event, as in send_priority
     ///
     /// # Accounts
-    /// Same as send_priority
+    /// Same as send_priority, plus:
+    /// * `mail_id_record` - Optional `[b"mailid", sender, sha256(mail_id)]` PDA; when passed it
+    ///   is created by this send, so any later send of the same `mail_id` by the sender is rejected
     ///
     /// # Errors
     /// * `InsufficientFunds` - If sender doesn't have enough USDC
     /// * `TokenTransferFailed` - If USDC transfer fails
+    /// * `DuplicateMailId` - If `mail_id_record` already exists
+    /// * `InvalidMailIdRecord` - If `mail_id_record` is not the PDA for `mail_id`
     ///
     /// # Example
     /// ```rust
     /// let message_uuid = "msg-12345".to_string();
-    /// send_prepared(ctx, message_uuid)?;
+    /// send_prepared(ctx, message_uuid, None, None, None)?;
     /// ```
     pub fn send_prepared(
         ctx: Context<SendMessage>,
         mail_id: String,
+        tip: Option<Tip>,
+        to_external: Option<ExternalAddress>,
+        client_ref: Option<[u8; 16]>,
+    ) -> Result<[u8; 32]> {
+        messaging::send_prepared(ctx, mail_id, tip, to_external, client_ref)
+    }
+
+    /// Send many pre-prepared messages in one transaction
+    ///
+    /// Mail IDs are passed as a zstd-compressed, newline-separated payload so
+    /// far more fit in a transaction than as individual strings. The payload is
+    /// not decompressed on-chain; it is emitted with `ids_hash` (SHA-256 of the
+    /// uncompressed bytes) for indexers to verify. Each ID is charged the
+    /// priority or standard fee. The event carries the batch's slot so the
+    /// message ID of each entry can be derived from its sequence number.
+    ///
+    /// # Arguments
+    /// * `ctx` - Anchor context with required accounts
+    /// * `count` - Number of mail IDs in the payload (max 512)
+    /// * `ids_hash` - SHA-256 of the uncompressed payload
+    /// * `payload` - zstd frame of the newline-separated mail IDs (max 900 bytes)
+    /// * `priority` - Charge the full fee with revenue share instead of the standard fee
+    /// * `client_ref` - Optional caller reference echoed in the event, as in send_priority
+    ///
+    /// # Accounts
+    /// Same as send_priority
+    ///
+    /// # Errors
+    /// * `InvalidBatch` - If the batch is empty, too large or not a zstd frame
+    /// * `InsufficientFunds` - If sender doesn't have enough USDC
+    /// * `RateLimitExceeded` - If the batch would take the sender past the daily
+    ///   message limit; every mail ID in it counts
+    pub fn send_prepared_batch(
+        ctx: Context<SendMessage>,
+        count: u16,
+        ids_hash: [u8; 32],
+        payload: Vec<u8>,
+        priority: bool,
+        client_ref: Option<[u8; 16]>,
     ) -> Result<()> {
-        let sender = ctx.accounts.sender.key();
-        let owner_fee = (ctx.accounts.mailer.send_fee * OWNER_SHARE) / 100;
-        
-        // Transfer only owner fee (10%) from sender to mailer contract
-        let transfer_ctx = CpiContext::new(
-            ctx.accounts.token_program.to_account_info(),
-            Transfer {
-                from: ctx.accounts.sender_usdc_account.to_account_info(),
-                to: ctx.accounts.mailer_usdc_account.to_account_info(),
-                authority: ctx.accounts.sender.to_account_info(),
-            },
-        );
-        token::transfer(transfer_ctx, owner_fee)?;
-
-        // Only add to owner claimable, no revenue sharing
-        ctx.accounts.mailer.owner_claimable += owner_fee;
-
-        emit!(PreparedMailSent {
-            from: sender,
-            to: sender, // Messages are sent to self
-            mail_id,
-        });
-
-        Ok(())
+        messaging::send_prepared_batch(ctx, count, ids_hash, payload, priority, client_ref)
     }
 
-    pub fn claim_recipient_share(ctx: Context<ClaimRecipientShare>) -> Result<()> {
-        let claim = &mut ctx.accounts.recipient_claim;
-        let recipient = ctx.accounts.recipient.key();
-        
-        require!(claim.amount > 0, MailerError::NoClaimableAmount);
-        
-        // Check if claim period has expired
-        let current_time = Clock::get()?.unix_timestamp;
-        require!(
-            current_time <= claim.timestamp + CLAIM_PERIOD,
-            MailerError::ClaimPeriodExpired
-        );
-
-        let amount = claim.amount;
-        claim.amount = 0;
-        claim.timestamp = 0;
-
-        // Transfer USDC from mailer to recipient
-        let bump = ctx.accounts.mailer.bump;
-        let seeds = &[b"mailer".as_ref(), &[bump]];
-        let signer_seeds = &[&seeds[..]];
-        
-        let transfer_ctx = CpiContext::new_with_signer(
-            ctx.accounts.token_program.to_account_info(),
-            Transfer {
-                from: ctx.accounts.mailer_usdc_account.to_account_info(),
-                to: ctx.accounts.recipient_usdc_account.to_account_info(),
-                authority: ctx.accounts.mailer.to_account_info(),
-            },
-            signer_seeds,
-        );
-        token::transfer(transfer_ctx, amount)?;
-
-        emit!(RecipientClaimed {
-            recipient,
-            amount,
-        });
-
-        Ok(())
+    /// Send a message paying the fee from the sender's claimable balance first
+    ///
+    /// Nets the fee for the chosen tier against the sender's unexpired
+    /// revenue share. Only the uncovered remainder, if any, is transferred
+    /// from the sender's USDC account, so power users who both send and earn
+    /// rebates avoid round-trip token movement. Priority sends then record
+    /// shares exactly like `send_priority`.
+    ///
+    /// # Arguments
+    /// * `ctx` - Anchor context with required accounts
+    /// * `subject` - Message subject line (plain text)
+    /// * `body` - Message content (plain text)
+    /// * `priority` - Whether to send as a priority (full fee) message
+    /// * `client_ref` - Optional caller reference echoed in the event, as in send_priority
+    ///
+    /// # Accounts
+    /// Same as send_priority
+    ///
+    /// # Errors
+    /// * `InsufficientFunds` - If the uncovered remainder exceeds the sender's USDC
+    /// * `TokenTransferFailed` - If USDC transfer fails
+    /// * `MessageTooLarge` - If the subject or body exceeds the owner's length limits
+    pub fn send_using_claim(
+        ctx: Context<SendMessage>,
+        subject: String,
+        body: String,
+        priority: bool,
+        client_ref: Option<[u8; 16]>,
+    ) -> Result<[u8; 32]> {
+        messaging::send_using_claim(ctx, subject, body, priority, client_ref)
     }
 
-    pub fn claim_owner_share(ctx: Context<ClaimOwnerShare>) -> Result<()> {
-        let mailer = &mut ctx.accounts.mailer;
-        
-        require!(mailer.owner_claimable > 0, MailerError::NoClaimableAmount);
-
-        let amount = mailer.owner_claimable;
-        mailer.owner_claimable = 0;
-
-        // Transfer USDC from mailer to owner
-        let bump = mailer.bump;
-        let seeds = &[b"mailer".as_ref(), &[bump]];
-        let signer_seeds = &[&seeds[..]];
-        
-        let transfer_ctx = CpiContext::new_with_signer(
-            ctx.accounts.token_program.to_account_info(),
-            Transfer {
-                from: ctx.accounts.mailer_usdc_account.to_account_info(),
-                to: ctx.accounts.owner_usdc_account.to_account_info(),
-                authority: ctx.accounts.mailer.to_account_info(),
-            },
-            signer_seeds,
-        );
-        token::transfer(transfer_ctx, amount)?;
-
-        emit!(OwnerClaimed { amount });
-
-        Ok(())
+    /// Send a message on behalf of a mail service delegator
+    ///
+    /// The signer must be the accepted delegate in the delegator's
+    /// mail_service `Delegation` account, granted `PERMISSION_SEND`. The delegate pays the fee and earns
+    /// any priority revenue share, while the message is emitted with the
+    /// delegator as both sender and recipient. Enables team inboxes and
+    /// custodial senders.
+    ///
+    /// A delegator who opened a billing allowance for the delegation can
+    /// instead be billed for standard sends, up to the allowance's limit, by
+    /// passing its token account, letting backend services send for it
+    /// without holding funds.
+    ///
+    /// # Arguments
+    /// * `ctx` - Anchor context with required accounts
+    /// * `subject` - Message subject line (plain text)
+    /// * `body` - Message content (plain text)
+    /// * `priority` - Whether to send as a priority (full fee) message
+    /// * `client_ref` - Optional caller reference echoed in the event, as in send_priority
+    ///
+    /// # Accounts
+    /// * `send` - Same as send_priority, with the delegate as `sender`
+    /// * `delegation` - A mail_service `Delegation` of the delegator: its own or the signer's per-delegate one
+    /// * `delegator_preferences` - The delegator's preferences PDA
+    /// * `delegator_usdc_account` - Optional delegator token account to bill instead of the delegate
+    /// * `delegator_billing` - The delegation's `[b"billing", delegation]` allowance; required with `delegator_usdc_account`
+    ///
+    /// # Errors
+    /// * `NotDelegate` - If the signer is not the delegation's active delegate (a pending or expired one doesn't count)
+    /// * `DelegateCannotSend` - If the delegation doesn't grant `PERMISSION_SEND`
+    /// * `DelegatorFundsStandardOnly` - If a priority send is billed to the delegator
+    /// * `BillingNotApproved` - If a send is billed to the delegator without its billing allowance
+    /// * `BillingLimitExceeded` - If the fee would take the billing allowance past its limit
+    /// * `InsufficientAllowance` - If the delegator's approval of the mailer PDA does not cover the fee
+    /// * `InsufficientFunds` - If the delegate has insufficient USDC
+    /// * `MessageTooLarge` - If the subject or body exceeds the owner's length limits
+    pub fn send_as_delegator(
+        ctx: Context<SendAsDelegator>,
+        subject: String,
+        body: String,
+        priority: bool,
+        client_ref: Option<[u8; 16]>,
+    ) -> Result<[u8; 32]> {
+        messaging::send_as_delegator(ctx, subject, body, priority, client_ref)
     }
 
-    pub fn claim_expired_shares(ctx: Context<ClaimExpiredShares>) -> Result<()> {
-        let recipient_key = ctx.accounts.recipient_claim.recipient;
-        let claim = &mut ctx.accounts.recipient_claim;
-        
-        require!(claim.amount > 0, MailerError::NoClaimableAmount);
-        
-        // Check if claim period has expired
-        let current_time = Clock::get()?.unix_timestamp;
-        require!(
-            current_time > claim.timestamp + CLAIM_PERIOD,
-            MailerError::ClaimPeriodNotExpired
-        );
+    /// Send a prepared message without revealing the sender to event consumers
+    ///
+    /// The signing `sender` is a relayer or a one-off ephemeral key and is the
+    /// only sender recorded on-chain. The true sender travels inside
+    /// `envelope`, encrypted to the recipient, who alone can recover it. Sealed
+    /// sends pay the standard fee; there is no revenue share, as the signer is
+    /// not the real sender. The recipient's origin policy applies.
+    ///
+    /// # Arguments
+    /// * `to` - Recipient of the message
+    /// * `mail_id` - Pre-prepared message identifier
+    /// * `envelope` - Encrypted sender envelope, 1 to 256 bytes
+    ///
+    /// # Errors
+    /// * `InvalidEnvelope` - If the envelope is empty or too large
+    /// * `SenderBlocked` - If the recipient has blocked the signer
+    /// * `AddressDenied` - If the signer or recipient is on the compliance denylist
+    /// * `DepositRequired` - If the recipient requires a deposit and none was passed
+    /// * `OriginProgramDenied` - If the recipient does not accept CPI sends from the caller
+    pub fn send_sealed(
+        ctx: Context<SendSealed>,
+        to: Pubkey,
+        mail_id: String,
+        envelope: Vec<u8>,
+    ) -> Result<[u8; 32]> {
+        messaging::send_sealed(ctx, to, mail_id, envelope)
+    }
 
-        let amount = claim.amount;
-        claim.amount = 0;
-        claim.timestamp = 0;
+    /// Acknowledge a received message
+    ///
+    /// Emits `MailRead` so senders get a read receipt without off-chain
+    /// infrastructure. When `read_state` is passed, also sets the message's bit
+    /// in the reader's bitmap, creating it on first use at the reader's expense.
+    ///
+    /// # Arguments
+    /// * `message_id` - ID of the message being acknowledged
+    /// * `message_seq` - Sequence number of the message, selecting its bitmap slot
+    pub fn mark_read(ctx: Context<MarkRead>, message_id: [u8; 32], message_seq: u64) -> Result<()> {
+        messaging::mark_read(ctx, message_id, message_seq)
+    }
 
-        // Add expired amount to owner claimable
-        ctx.accounts.mailer.owner_claimable += amount;
+    /// Wrap exactly the fee of the next send into the sender's wSOL account
+    ///
+    /// Lets SOL-only wallets pay in wrapped SOL without a separate wrapping
+    /// step. Creates the sender's wSOL associated token account if needed and
+    /// tops it up to the priority or standard fee. Intended to run in one
+    /// transaction with the send and an SPL `close_account` that unwraps any
+    /// remainder back to SOL.
+    ///
+    /// # Arguments
+    /// * `priority` - Wrap the full priority fee instead of the standard fee
+    ///
+    /// # Errors
+    /// * `TokenDisabled` - If wrapped SOL is not an enabled fee token
+    /// * `PriceFeedRequired` / `PriceFeedMismatch` / `StalePrice` - If SOL is
+    ///   oracle-priced and no fresh price is passed
+    /// * `UncertainPrice` - If that price's confidence interval exceeds 2% of it
+    pub fn wrap_sol_fee(ctx: Context<WrapSolFee>, priority: bool) -> Result<()> {
+        messaging::wrap_sol_fee(ctx, priority)
+    }
 
-        emit!(ExpiredSharesClaimed {
-            recipient: recipient_key,
-            amount,
-        });
+    /// Send a standard message paid for with proof-of-work instead of a fee
+    ///
+    /// Available while the owner has set a non-zero `pow_difficulty`, so
+    /// senders without USDC can still message. `nonce` must make the challenge
+    /// hash (see [`pow::challenge_hash`]) start with `pow_difficulty` zero
+    /// bits. The challenge commits to the sender's proof-of-work counter,
+    /// which is incremented, so a solution pays for exactly one message. A
+    /// separate `payer` may fund the counters' rent, so the sender needs no SOL.
+    ///
+    /// # Arguments
+    /// * `subject` - Message subject line (plain text)
+    /// * `body` - Message content (plain text)
+    /// * `nonce` - Proof-of-work solution
+    /// * `client_ref` - Optional caller reference echoed in the event, as in send_priority
+    ///
+    /// # Errors
+    /// * `PowDisabled` - If proof-of-work sends are turned off
+    /// * `InsufficientWork` - If the nonce does not meet the difficulty
+    /// * `MessageTooLarge` - If the subject or body exceeds the owner's length limits
+    /// * `RateLimitExceeded` - If the sender has used up the owner's daily message limit
+    pub fn send_with_pow(
+        ctx: Context<SendWithPow>,
+        subject: String,
+        body: String,
+        nonce: u64,
+        client_ref: Option<[u8; 16]>,
+    ) -> Result<[u8; 32]> {
+        pow::send_with_pow(ctx, subject, body, nonce, client_ref)
+    }
 
-        Ok(())
+    /// Set which programs may send messages via CPI (owner only)
+    ///
+    /// Applies to every recipient, before their own origin policy. Wallet
+    /// sends are always accepted; CPI sends are accepted when `allow_all` is
+    /// set or the top-level program is listed. Until this is first called,
+    /// any program may send via CPI.
+    ///
+    /// # Arguments
+    /// * `programs` - Programs allowed to send via CPI (max 32)
+    /// * `allow_all` - `true` to accept CPI sends from any program
+    ///
+    /// # Errors
+    /// * `OnlyOwner` - If caller is not the program owner
+    /// * `TooManyCallerPrograms` - If more than 32 programs are listed
+    pub fn set_caller_policy(
+        ctx: Context<SetCallerPolicy>,
+        programs: Vec<Pubkey>,
+        allow_all: bool,
+    ) -> Result<()> {
+        messaging::set_caller_policy(ctx, programs, allow_all)
     }
 
-    pub fn set_fee(ctx: Context<SetFee>, new_fee: u64) -> Result<()> {
-        let mailer = &mut ctx.accounts.mailer;
-        let old_fee = mailer.send_fee;
-        mailer.send_fee = new_fee;
+    /// Set which CPI caller programs may deliver messages to you
+    ///
+    /// Messages sent directly by a wallet are always accepted. Messages sent
+    /// via CPI are checked against `origin_programs` using instruction
+    /// introspection: in allowlist mode only listed programs are accepted, in
+    /// denylist mode listed programs are rejected.
+    ///
+    /// # Arguments
+    /// * `origin_programs` - Programs to allow or deny (max 16)
+    /// * `allowlist` - `true` to allow only listed programs, `false` to deny them
+    ///
+    /// # Errors
+    /// * `TooManyOriginPrograms` - If more than 16 programs are listed
+    pub fn set_origin_policy(
+        ctx: Context<SetOriginPolicy>,
+        origin_programs: Vec<Pubkey>,
+        allowlist: bool,
+    ) -> Result<()> {
+        messaging::set_origin_policy(ctx, origin_programs, allowlist)
+    }
 
-        emit!(FeeUpdated { old_fee, new_fee });
+    /// Refuse mail from a sender
+    ///
+    /// Creates a `[b"block", recipient, sender]` marker, funded by the
+    /// recipient, that makes sends addressed to the recipient from `sender`
+    /// fail. Self-addressed sends are unaffected.
+    ///
+    /// # Arguments
+    /// * `sender` - Address to block
+    pub fn block_sender(ctx: Context<BlockSender>, sender: Pubkey) -> Result<()> {
+        messaging::block_sender(ctx, sender)
+    }
 
-        Ok(())
+    /// Accept mail from a blocked sender again, closing the block marker
+    pub fn unblock_sender(ctx: Context<UnblockSender>) -> Result<()> {
+        messaging::unblock_sender(ctx)
     }
-}
 
-fn record_shares(
-    claim: &mut Account<RecipientClaim>,
-    mailer: &mut Account<MailerState>,
-    recipient: Pubkey,
-    total_amount: u64,
-) -> Result<()> {
-    // Calculate owner amount first for precision
-    let owner_amount = (total_amount * OWNER_SHARE) / 100;
-    let recipient_amount = total_amount - owner_amount;
-
-    // Update recipient's claimable amount and set timestamp only if not already set
-    claim.recipient = recipient;
-    claim.amount += recipient_amount;
-    if claim.timestamp == 0 {
-        claim.timestamp = Clock::get()?.unix_timestamp;
-    }
-
-    // Update owner's claimable amount
-    mailer.owner_claimable += owner_amount;
-
-    emit!(SharesRecorded {
-        recipient,
-        recipient_amount,
-        owner_amount,
-    });
-
-    Ok(())
-}
+    /// Require a deposit from senders who are not your contacts
+    ///
+    /// Addressed sends from such senders lock `deposit_fees` times the send
+    /// fee in a per-sender deposit, which you later refund or confiscate.
+    ///
+    /// # Arguments
+    /// * `deposit_fees` - Deposit per message in send fees (max 100); 0 disables deposits
+    ///
+    /// # Errors
+    /// * `InvalidDepositFees` - If more than 100 send fees are required
+    pub fn set_spam_deposit(ctx: Context<SetSpamDeposit>, deposit_fees: u16) -> Result<()> {
+        deposits::set_spam_deposit(ctx, deposit_fees)
+    }
 
-#[derive(Accounts)]
-pub struct Initialize<'info> {
-    #[account(
-        init,
-        payer = owner,
-        space = 8 + MailerState::INIT_SPACE,
-        seeds = [b"mailer"],
-        bump
-    )]
-    pub mailer: Account<'info, MailerState>,
-    
-    #[account(mut)]
-    pub owner: Signer<'info>,
-    
-    pub system_program: Program<'info, System>,
-}
+    /// Make a sender a contact, whose mail needs no deposit
+    ///
+    /// # Arguments
+    /// * `sender` - Address to vouch for
+    pub fn approve_sender(ctx: Context<ApproveSender>, sender: Pubkey) -> Result<()> {
+        deposits::approve_sender(ctx, sender)
+    }
 
-#[derive(Accounts)]
-pub struct SendMessage<'info> {
-    #[account(
-        init_if_needed,
-        payer = sender,
-        space = 8 + RecipientClaim::INIT_SPACE,
-        seeds = [b"claim", sender.key().as_ref()],
-        bump
-    )]
-    pub recipient_claim: Account<'info, RecipientClaim>,
-    
-    #[account(seeds = [b"mailer"], bump = mailer.bump)]
-    pub mailer: Account<'info, MailerState>,
-    
-    #[account(mut)]
-    pub sender: Signer<'info>,
-    
-    #[account(
-        mut,
-        associated_token::mint = mailer.usdc_mint,
-        associated_token::authority = sender
-    )]
-    pub sender_usdc_account: Account<'info, TokenAccount>,
-    
-    #[account(
-        mut,
-        associated_token::mint = mailer.usdc_mint,
-        associated_token::authority = mailer
-    )]
-    pub mailer_usdc_account: Account<'info, TokenAccount>,
-    
-    pub token_program: Program<'info, Token>,
-    pub associated_token_program: Program<'info, AssociatedToken>,
-    pub system_program: Program<'info, System>,
-}
+    /// Return a sender's deposit as legitimate mail and make them a contact
+    ///
+    /// Closes the deposit; its rent returns to whoever funded it.
+    pub fn refund_spam_deposit(ctx: Context<RefundSpamDeposit>) -> Result<()> {
+        deposits::refund_spam_deposit(ctx)
+    }
 
-#[derive(Accounts)]
-pub struct ClaimRecipientShare<'info> {
-    #[account(
-        mut,
-        seeds = [b"claim", recipient.key().as_ref()],
-        bump,
-        has_one = recipient @ MailerError::InvalidRecipient
-    )]
-    pub recipient_claim: Account<'info, RecipientClaim>,
-    
-    #[account(seeds = [b"mailer"], bump = mailer.bump)]
-    pub mailer: Account<'info, MailerState>,
-    
-    pub recipient: Signer<'info>,
-    
-    #[account(
-        mut,
-        associated_token::mint = mailer.usdc_mint,
-        associated_token::authority = recipient
-    )]
-    pub recipient_usdc_account: Account<'info, TokenAccount>,
-    
-    #[account(
-        mut,
-        associated_token::mint = mailer.usdc_mint,
-        associated_token::authority = mailer
-    )]
-    pub mailer_usdc_account: Account<'info, TokenAccount>,
-    
-    pub token_program: Program<'info, Token>,
-}
+    /// Keep a spammer's deposit, crediting it to your claim
+    ///
+    /// Closes the deposit; its rent returns to whoever funded it.
+    ///
+    /// # Errors
+    /// * `ClaimMintMismatch` - If your claim holds a different mint with a balance
+    pub fn confiscate_spam_deposit(ctx: Context<ConfiscateSpamDeposit>) -> Result<()> {
+        deposits::confiscate_spam_deposit(ctx)
+    }
 
-#[derive(Accounts)]
-pub struct ClaimOwnerShare<'info> {
-    #[account(
-        mut,
-        seeds = [b"mailer"],
-        bump = mailer.bump,
-        has_one = owner @ MailerError::OnlyOwner
-    )]
-    pub mailer: Account<'info, MailerState>,
-    
-    #[account(mut)]
-    pub owner: Signer<'info>,
-    
-    #[account(
-        mut,
-        associated_token::mint = mailer.usdc_mint,
-        associated_token::authority = owner
-    )]
-    pub owner_usdc_account: Account<'info, TokenAccount>,
-    
-    #[account(
-        mut,
-        associated_token::mint = mailer.usdc_mint,
-        associated_token::authority = mailer
-    )]
-    pub mailer_usdc_account: Account<'info, TokenAccount>,
-    
-    pub token_program: Program<'info, Token>,
-}
+    /// Choose how your revenue shares are paid out
+    ///
+    /// `Lump` (the default) makes the whole claim available at once until it
+    /// expires 60 days after its first deposit. `Streaming` unlocks the claim
+    /// linearly over those 60 days instead and never expires, so it cannot be
+    /// swept; each deposit restarts the 60 days for whatever is still locked.
+    /// Creates the claim account if needed, funded by the recipient.
+    ///
+    /// # Errors
+    /// * `ClaimNotEmpty` - If the claim still holds an unclaimed amount
+    pub fn set_claim_mode(ctx: Context<SetClaimMode>, mode: ClaimMode) -> Result<()> {
+        claims::set_claim_mode(ctx, mode)
+    }
 
-#[derive(Accounts)]
-pub struct ClaimExpiredShares<'info> {
-    #[account(
-        mut,
-        seeds = [b"claim", recipient_claim.recipient.as_ref()],
-        bump
-    )]
-    pub recipient_claim: Account<'info, RecipientClaim>,
-    
-    #[account(
-        mut,
-        seeds = [b"mailer"],
-        bump = mailer.bump,
-        has_one = owner @ MailerError::OnlyOwner
-    )]
-    pub mailer: Account<'info, MailerState>,
-    
-    pub owner: Signer<'info>,
-}
+    /// Withdraw your revenue share: the whole claim, or the unlocked part of a streaming claim
+    ///
+    /// # Errors
+    /// * `AddressDenied` - If the recipient is on the compliance denylist
+    pub fn claim_recipient_share(ctx: Context<ClaimRecipientShare>) -> Result<()> {
+        claims::claim_recipient_share(ctx)
+    }
 
-#[derive(Accounts)]
-pub struct SetFee<'info> {
-    #[account(
-        mut,
-        seeds = [b"mailer"],
-        bump = mailer.bump,
-        has_one = owner @ MailerError::OnlyOwner
-    )]
-    pub mailer: Account<'info, MailerState>,
-    
-    pub owner: Signer<'info>,
-}
+    /// Register the automation thread allowed to auto-claim (owner only)
+    ///
+    /// # Arguments
+    /// * `auto_claimer` - Thread signer, e.g. a Clockwork thread PDA; `Pubkey::default()` disables auto-claiming
+    ///
+    /// # Errors
+    /// * `OnlyOwner` - If caller is not the owner
+    pub fn set_auto_claimer(ctx: Context<SetFee>, auto_claimer: Pubkey) -> Result<()> {
+        auto_claim::set_auto_claimer(ctx, auto_claimer)
+    }
 
-#[account]
-#[derive(InitSpace)]
-pub struct MailerState {
-    pub owner: Pubkey,
-    pub usdc_mint: Pubkey,
-    pub send_fee: u64,
-    pub owner_claimable: u64,
-    pub bump: u8,
-}
+    /// Opt in to (or out of) having the auto-claimer pay out your expiring claim
+    ///
+    /// Creates the claim account if needed, funded by the recipient.
+    pub fn enable_auto_claim(ctx: Context<EnableAutoClaim>, enabled: bool) -> Result<()> {
+        auto_claim::enable_auto_claim(ctx, enabled)
+    }
 
-#[account]
-#[derive(InitSpace)]
-pub struct RecipientClaim {
-    pub recipient: Pubkey,
-    pub amount: u64,
-    pub timestamp: i64,
-    pub bump: u8,
-}
+    /// Pay an opted-in recipient's lump claim out to their token account (auto-claimer only)
+    ///
+    /// Only allowed during the last 3 days of the claim period, so recipients
+    /// who claim by hand are not preempted.
+    ///
+    /// # Errors
+    /// * `NotAutoClaimer` - If the signer is not the registered auto-claimer
+    /// * `AutoClaimDisabled` - If the recipient has not enabled auto-claim
+    /// * `NoClaimableAmount` - If the claim is empty
+    /// * `AutoClaimNotDue` - If the claim is streaming or not within 3 days of expiry
+    /// * `AddressDenied` - If the recipient is on the compliance denylist
+    pub fn auto_claim(ctx: Context<AutoClaim>) -> Result<()> {
+        auto_claim::auto_claim(ctx)
+    }
 
-#[event]
-pub struct MailSent {
-    pub from: Pubkey,
-    pub to: Pubkey,
-    pub subject: String,
-    pub body: String,
-}
+    /// Close a fully claimed RecipientClaim account and refund its rent
+    ///
+    /// Once a recipient has claimed their revenue share the claim PDA only holds
+    /// rent. Closing it returns the lamports to whoever paid for the account
+    /// (the sender or relayer). The account is recreated automatically by the
+    /// next priority send.
+    ///
+    /// # Accounts
+    /// * `recipient_claim` - The recipient's claim PDA (closed to `rent_payer`)
+    /// * `recipient` - Owner of the claim (signer)
+    /// * `rent_payer` - Account that funded the claim PDA, receives the rent
+    ///
+    /// # Errors
+    /// * `ClaimNotEmpty` - If the claim still holds an unclaimed amount
+    /// * `ClaimHasSendCredit` - If the claim still holds send credit
+    pub fn close_claim(ctx: Context<CloseClaim>) -> Result<()> {
+        claims::close_claim(ctx)
+    }
 
-#[event]
-pub struct PreparedMailSent {
-    pub from: Pubkey,
-    pub to: Pubkey,
-    pub mail_id: String,
-}
+    /// Grow a RecipientClaim created by an earlier program version to the current layout
+    ///
+    /// Fields are only ever appended to the claim, so existing data keeps its
+    /// meaning and the new fields start zeroed. Claims from before the rent
+    /// payer was recorded refund their rent to the recipient. Permissionless;
+    /// `payer` covers the extra rent. Claims already at the current size are
+    /// left unchanged.
+    ///
+    /// # Errors
+    /// * `InvalidMigrationAccount` - If `recipient_claim` is not a mailer-owned claim
+    pub fn migrate_claim(ctx: Context<MigrateClaim>, recipient: Pubkey) -> Result<()> {
+        claims::migrate_claim(ctx, recipient)
+    }
 
-#[event]
-pub struct FeeUpdated {
-    pub old_fee: u64,
-    pub new_fee: u64,
-}
+    /// Withdraw one owner share bucket to its configured destination (owner only)
+    ///
+    /// Send fees and expired shares accrue to separate buckets per mint, so
+    /// each revenue stream can be withdrawn and budgeted independently.
+    ///
+    /// # Accounts
+    /// * `owner_bucket` - Bucket to withdraw
+    /// * `destination_usdc_account` - Bucket destination's token account for the bucket mint
+    ///
+    /// # Errors
+    /// * `OnlyOwner` - If caller is not the owner
+    /// * `NoClaimableAmount` - If the bucket is empty
+    pub fn claim_owner_share(ctx: Context<ClaimOwnerShare>) -> Result<()> {
+        claims::claim_owner_share(ctx)
+    }
 
-#[event]
-pub struct SharesRecorded {
-    pub recipient: Pubkey,
-    pub recipient_amount: u64,
-    pub owner_amount: u64,
-}
+    /// Sweep an expired revenue share back to the owner (permissionless crank)
+    ///
+    /// Anyone may crank a claim whose 60-day period has passed. The cranker is
+    /// paid `crank_bounty_bps` of the swept amount and the remainder is added to
+    /// `owner_claimable`.
+    ///
+    /// # Accounts
+    /// * `recipient_claim` - Expired claim PDA to sweep
+    /// * `mailer` - Main program state account
+    /// * `cranker` - Any signer; receives the bounty
+    /// * `cranker_usdc_account` - Cranker's USDC associated token account
+    /// * `mailer_usdc_account` - Program's USDC associated token account
+    /// * `expiry_index` - Expiry bucket the claim is listed in; the claim is removed
+    /// * `token_program` - SPL Token or Token-2022 program owning `mint`
+    ///
+    /// # Errors
+    /// * `NoClaimableAmount` - If the claim is empty
+    /// * `ClaimPeriodNotExpired` - If the claim period is still running
+    /// * `InvalidExpiryIndex` - If `expiry_index` is not the claim's expiry week
+    pub fn claim_expired_shares(ctx: Context<ClaimExpiredShares>) -> Result<()> {
+        claims::claim_expired_shares(ctx)
+    }
 
-#[event]
-pub struct RecipientClaimed {
-    pub recipient: Pubkey,
-    pub amount: u64,
-}
+    /// Sweep many expired revenue shares in a single transaction
+    ///
+    /// `remaining_accounts` holds pairs of a writable `RecipientClaim` PDA and
+    /// the writable expiry bucket of the claim's week. Expired, non-empty claims
+    /// are swept exactly as in `claim_expired_shares`, including removal from
+    /// their bucket; claims that are empty or still claimable are skipped so a
+    /// stale batch never fails outright. The cranker's bounty is paid once for
+    /// the whole batch.
+    ///
+    /// # Accounts
+    /// Same as claim_expired_shares, without `recipient_claim` and `expiry_index`
+    ///
+    /// # Errors
+    /// * `InvalidClaimAccount` - If a remaining account is not a writable claim PDA,
+    ///   or a claim has no expiry bucket after it
+    /// * `InvalidExpiryIndex` - If a swept claim's bucket is not its expiry week
+    /// * `NoClaimableAmount` - If no claim in the batch was swept
+    pub fn claim_expired_shares_batch<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ClaimExpiredSharesBatch<'info>>,
+    ) -> Result<()> {
+        claims::claim_expired_shares_batch(ctx)
+    }
 
-#[event]
-pub struct OwnerClaimed {
-    pub amount: u64,
-}
+    /// Emit `ClaimExpiringSoon` for claims nearing expiry (permissionless crank)
+    ///
+    /// Each account in `remaining_accounts` must be a `RecipientClaim` PDA.
+    /// Non-empty lump claims expiring within `within_days` emit an event with
+    /// their recipient, amount and expiry; other claims are skipped, so
+    /// notification services can ping a whole watchlist at once.
+    ///
+    /// # Arguments
+    /// * `within_days` - Notice window before expiry, 1 to 60 days
+    ///
+    /// # Errors
+    /// * `InvalidPingWindow` - If `within_days` is 0 or over 60
+    /// * `InvalidClaimAccount` - If an account is not a recipient claim PDA
+    pub fn ping_claims<'info>(
+        ctx: Context<'_, '_, 'info, 'info, PingClaims<'info>>,
+        within_days: u16,
+    ) -> Result<()> {
+        claims::ping_claims(ctx, within_days)
+    }
 
-#[event]
-pub struct ExpiredSharesClaimed {
-    pub recipient: Pubkey,
-    pub amount: u64,
-}
+    /// Set the bounty paid to crankers of expired claims (owner only)
+    ///
+    /// # Arguments
+    /// * `new_bounty_bps` - Bounty in basis points of the swept amount
+    ///
+    /// # Errors
+    /// * `OnlyOwner` - If caller is not the owner
+    /// * `InvalidCrankBounty` - If the bounty exceeds the 10% cap
+    pub fn set_crank_bounty(ctx: Context<SetFee>, new_bounty_bps: u16) -> Result<()> {
+        fees::set_crank_bounty(ctx, new_bounty_bps)
+    }
+
+    /// Set the proof-of-work difficulty for `send_with_pow` (owner only)
+    ///
+    /// # Arguments
+    /// * `new_difficulty` - Required leading zero bits; 0 disables proof-of-work sends
+    ///
+    /// # Errors
+    /// * `OnlyOwner` - If caller is not the owner
+    /// * `InvalidPowDifficulty` - If the difficulty exceeds 32 bits
+    pub fn set_pow_difficulty(ctx: Context<SetFee>, new_difficulty: u8) -> Result<()> {
+        pow::set_pow_difficulty(ctx, new_difficulty)
+    }
+
+    /// Set the longest subject and body a send accepts, in bytes (owner only)
+    ///
+    /// Bounds what a send can write to the program logs. Applies to every send
+    /// carrying a subject and body; prepared sends only carry a mail ID.
+    ///
+    /// # Arguments
+    /// * `max_subject_len` - Longest accepted subject
+    /// * `max_body_len` - Longest accepted body
+    ///
+    /// # Errors
+    /// * `OnlyOwner` - If caller is not the owner
+    /// * `InvalidMessageLimits` - If either limit is zero
+    pub fn set_message_limits(
+        ctx: Context<SetFee>,
+        max_subject_len: u32,
+        max_body_len: u32,
+    ) -> Result<()> {
+        messaging::set_message_limits(ctx, max_subject_len, max_body_len)
+    }
+
+    /// Set how many messages one sender may send per day (owner only)
+    ///
+    /// Each sender's `[b"rate", sender]` PDA counts their messages in a
+    /// one-day window that restarts on the first send after it ends. Every
+    /// send path counts, with a batch counting each of its mail IDs. A value
+    /// of 0 disables the limit.
+    ///
+    /// # Arguments
+    /// * `max_messages_per_day` - Messages allowed per sender per window
+    ///
+    /// # Errors
+    /// * `OnlyOwner` - If caller is not the owner
+    pub fn set_rate_limit(ctx: Context<SetFee>, max_messages_per_day: u32) -> Result<()> {
+        messaging::set_rate_limit(ctx, max_messages_per_day)
+    }
 
-#[error_code]
-pub enum MailerError {
-    #[msg("Only the owner can perform this action")]
-    OnlyOwner,
-    #[msg("No claimable amount available")]
-    NoClaimableAmount,
-    #[msg("Claim period has expired")]
-    ClaimPeriodExpired,
-    #[msg("Claim period has not expired yet")]
-    ClaimPeriodNotExpired,
-    #[msg("Invalid recipient")]
-    InvalidRecipient,
-}
\ No newline at end of file
+    /// Put an address on the compliance denylist (owner only)
+    ///
+    /// Creates a `[b"deny", address]` marker, funded by the owner. While it
+    /// exists, sends signed by the address, addressed sends to it and its
+    /// recipient share claims fail.
+    ///
+    /// # Arguments
+    /// * `address` - Address to deny
+    ///
+    /// # Errors
+    /// * `OnlyOwner` - If caller is not the owner
+    pub fn deny_address(ctx: Context<DenyAddress>, address: Pubkey) -> Result<()> {
+        compliance::deny_address(ctx, address)
+    }
+
+    /// Take an address off the compliance denylist, closing its marker (owner only)
+    ///
+    /// # Errors
+    /// * `OnlyOwner` - If caller is not the owner
+    pub fn undeny_address(ctx: Context<UndenyAddress>) -> Result<()> {
+        compliance::undeny_address(ctx)
+    }
+
+    /// Set the portion of expired shares rolled into the recipient's send credit (owner only)
+    ///
+    /// Rolled-over credit is non-withdrawable and is drawn down first by
+    /// future sends. A value of 0 disables rollover so expired shares go
+    /// entirely to the owner.
+    ///
+    /// # Arguments
+    /// * `new_rollover_bps` - Rollover in basis points of the swept amount (after bounty)
+    ///
+    /// # Errors
+    /// * `OnlyOwner` - If caller is not the owner
+    /// * `InvalidRollover` - If the rollover exceeds 100%
+    pub fn set_rollover(ctx: Context<SetFee>, new_rollover_bps: u16) -> Result<()> {
+        fees::set_rollover(ctx, new_rollover_bps)
+    }
+
+    /// Extend the claim period of a recipient's revenue share (owner only)
+    ///
+    /// Grace period for users who missed the 60-day window due to wallet
+    /// issues. Pushes out `RecipientClaim.timestamp` by `extra_seconds`, capped
+    /// at 30 days per call and never beyond a fresh claim period from now.
+    ///
+    /// # Arguments
+    /// * `recipient` - Address whose claim is extended
+    /// * `extra_seconds` - Seconds to add to the claim window
+    ///
+    /// # Errors
+    /// * `OnlyOwner` - If caller is not the owner
+    /// * `NoClaimableAmount` - If the claim is empty
+    /// * `InvalidExtension` - If the extension is zero or exceeds the cap
+    /// * `InvalidExpiryIndex` - If either expiry bucket is not the claim's week
+    pub fn extend_claim(
+        ctx: Context<ExtendClaim>,
+        recipient: Pubkey,
+        extra_seconds: i64,
+    ) -> Result<()> {
+        claims::extend_claim(ctx, recipient, extra_seconds)
+    }
+
+    /// Update the reference send fee stored on `MailerState` (owner only)
+    ///
+    /// Sends are charged the fee of the paying mint's `TokenConfig`; use
+    /// `add_token` to change what a given mint is charged.
+    ///
+    /// # Errors
+    /// * `OnlyOwner` - If caller is not the owner
+    /// * `InvalidFee` - If the standard fee would be zero or a full prepared batch would overflow
+    pub fn set_fee(ctx: Context<SetFee>, new_fee: u64) -> Result<()> {
+        fees::set_fee(ctx, new_fee)
+    }
+
+    /// Dry-run a fee or parameter change without applying it
+    ///
+    /// Runs the same validation as the matching setter against the current
+    /// `MailerState` and returns a report (also emitted as
+    /// `ConfigChangeSimulated`) with the old and new values and the error the
+    /// setter would fail with, or 0 if it would succeed. Permissionless and
+    /// read-only, so it can be simulated by anyone reviewing a proposed change.
+    ///
+    /// # Arguments
+    /// * `change` - Parameter and proposed value
+    pub fn simulate_config_change(
+        ctx: Context<SimulateConfigChange>,
+        change: ConfigChange,
+    ) -> Result<ConfigChangeReport> {
+        fees::simulate_config_change(ctx, change)
+    }
+
+    /// Let an address send without paying the fee (owner only)
+    ///
+    /// Creates a `[b"exempt", address]` marker, funded by the owner. Sends that
+    /// pass it as `fee_exempt` are charged no fee and record no revenue shares,
+    /// and emit `FeeExemptSend` so waived fees can be accounted for. Tips and
+    /// spam deposits are still paid.
+    ///
+    /// # Arguments
+    /// * `address` - Sender to exempt, e.g. a support bot or partner integration
+    ///
+    /// # Errors
+    /// * `OnlyOwner` - If caller is not the owner
+    pub fn grant_fee_exemption(ctx: Context<GrantFeeExemption>, address: Pubkey) -> Result<()> {
+        fees::grant_fee_exemption(ctx, address)
+    }
+
+    /// Make an exempt address pay fees again, closing its marker (owner only)
+    ///
+    /// # Errors
+    /// * `OnlyOwner` - If caller is not the owner
+    pub fn revoke_fee_exemption(ctx: Context<RevokeFeeExemption>) -> Result<()> {
+        fees::revoke_fee_exemption(ctx)
+    }
+
+    /// Let a delegation's delegate bill standard sends to the delegator, up to a limit
+    ///
+    /// Creates the delegator-funded `[b"billing", delegation]` allowance and
+    /// approves the mailer PDA for `limit` more on the delegator's token
+    /// account, on top of what open sessions already hold. Each delegation
+    /// needs its own allowance; close and reopen it to change the limit.
+    ///
+    /// # Arguments
+    /// * `limit` - Most the delegate may bill in total, in the mint's base units
+    ///
+    /// # Errors
+    /// * `NotDelegator` - If the signer is not the delegation's delegator
+    /// * `InvalidBillingLimit` - If `limit` is zero
+    pub fn open_delegator_billing(ctx: Context<OpenDelegatorBilling>, limit: u64) -> Result<()> {
+        fees::open_delegator_billing(ctx, limit)
+    }
+
+    /// Withdraw a billing allowance, closing it and giving back its unspent approval
+    ///
+    /// # Errors
+    /// * `NotDelegator` - If the signer is not the allowance's delegator
+    pub fn close_delegator_billing(ctx: Context<CloseDelegatorBilling>) -> Result<()> {
+        fees::close_delegator_billing(ctx)
+    }
+
+    /// Set the slice of the owner's share paid to referrers (owner only)
+    ///
+    /// Sends made through a referral code move this portion of the owner's
+    /// share of their fee into the referrer's claim. A value of 0 pays nothing.
+    ///
+    /// # Arguments
+    /// * `new_referral_bps` - Referral share in basis points of the owner share
+    ///
+    /// # Errors
+    /// * `OnlyOwner` - If caller is not the owner
+    /// * `InvalidReferralShare` - If the share exceeds 100%
+    pub fn set_referral_share(ctx: Context<SetFee>, new_referral_bps: u16) -> Result<()> {
+        fees::set_referral_share(ctx, new_referral_bps)
+    }
+
+    /// Register a referral code naming the caller as its referrer
+    ///
+    /// Creates the `[b"referral", sha256(code)]` PDA and, if needed, the
+    /// caller's claim that referral shares accrue to, both funded by the caller.
+    /// Codes are first come, first served.
+    ///
+    /// # Arguments
+    /// * `code` - Referral code, 1 to 32 bytes
+    ///
+    /// # Errors
+    /// * `InvalidReferralCode` - If the code is empty or too long
+    pub fn register_referral_code(ctx: Context<RegisterReferralCode>, code: String) -> Result<()> {
+        referrals::register_referral_code(ctx, code)
+    }
+
+    /// Create a promotional campaign boosting the recipient share (owner only)
+    ///
+    /// Priority sends passing the campaign between `start_time` and `end_time`
+    /// credit the recipient `recipient_share` percent of the fee instead of the
+    /// standard share. The extra comes out of the owner's share and is charged
+    /// to `budget`, in the mailer's USDC mint, until it is spent.
+    ///
+    /// # Arguments
+    /// * `id` - Campaign ID; seeds the `[b"campaign", id]` PDA
+    /// * `recipient_share` - Boosted recipient share in percent, e.g. 95
+    /// * `start_time` - Unix timestamp the boost starts at
+    /// * `end_time` - Unix timestamp the boost ends at
+    /// * `budget` - Most the boost may pay out, in USDC base units
+    ///
+    /// # Errors
+    /// * `OnlyOwner` - If caller is not the owner
+    /// * `InvalidCampaignShare` - If the share is not above the standard share or exceeds 100%
+    /// * `InvalidCampaignWindow` - If the campaign ends before it starts
+    /// * `InvalidCampaignBudget` - If the budget is zero
+    pub fn create_campaign(
+        ctx: Context<CreateCampaign>,
+        id: u64,
+        recipient_share: u8,
+        start_time: i64,
+        end_time: i64,
+        budget: u64,
+    ) -> Result<()> {
+        campaigns::create_campaign(ctx, id, recipient_share, start_time, end_time, budget)
+    }
+
+    /// Close a campaign, ending its boost and returning its rent (owner only)
+    ///
+    /// # Errors
+    /// * `OnlyOwner` - If caller is not the owner
+    pub fn close_campaign(ctx: Context<CloseCampaign>) -> Result<()> {
+        campaigns::close_campaign(ctx)
+    }
+
+    /// Configure loyalty point accrual and redemption (owner only)
+    ///
+    /// # Arguments
+    /// * `priority_points` - Points earned per paid priority message
+    /// * `standard_points` - Points earned per paid standard message
+    /// * `point_value` - Send credit per redeemed point, in USDC base units; 0 disables redemption
+    ///
+    /// # Errors
+    /// * `OnlyOwner` - If caller is not the owner
+    pub fn set_points_rates(
+        ctx: Context<SetFee>,
+        priority_points: u32,
+        standard_points: u32,
+        point_value: u64,
+    ) -> Result<()> {
+        loyalty::set_points_rates(ctx, priority_points, standard_points, point_value)
+    }
+
+    /// Redeem loyalty points for send credit
+    ///
+    /// The credit, `points * point_value` in the USDC mint, moves from the
+    /// owner's send bucket to the sender's claim, where sends draw it down
+    /// before charging tokens.
+    ///
+    /// # Arguments
+    /// * `points` - Points to redeem
+    ///
+    /// # Errors
+    /// * `RedemptionDisabled` - If `point_value` is 0
+    /// * `InsufficientPoints` - If `points` is 0 or more than the sender holds
+    /// * `ClaimMintMismatch` - If the sender's claim holds another mint
+    /// * `RedemptionUnfunded` - If the owner's send bucket holds less than the credit
+    pub fn redeem_points(ctx: Context<RedeemPoints>, points: u64) -> Result<()> {
+        loyalty::redeem_points(ctx, points)
+    }
+
+    /// Configure the fee discount for holders of an NFT collection (owner only)
+    ///
+    /// Sends passing the sender's token account for an NFT whose Metaplex
+    /// metadata carries `collection` as a verified collection are charged the
+    /// fee less `discount_bps`.
+    ///
+    /// # Arguments
+    /// * `collection` - Collection mint; `Pubkey::default()` disables the perk
+    /// * `discount_bps` - Discount in basis points; 10000 waives the fee
+    ///
+    /// # Errors
+    /// * `OnlyOwner` - If caller is not the owner
+    /// * `InvalidNftDiscount` - If the discount exceeds 100%
+    pub fn set_nft_perk(ctx: Context<SetFee>, collection: Pubkey, discount_bps: u16) -> Result<()> {
+        nft_perks::set_nft_perk(ctx, collection, discount_bps)
+    }
+
+    /// Point cNFT receipts at a Bubblegum tree (owner only)
+    ///
+    /// The mailer PDA must be the tree's creator or delegate. Priority sends
+    /// passing the receipt accounts then mint a receipt whose URI is
+    /// `base_uri` followed by the hex message ID and the send timestamp.
+    ///
+    /// # Arguments
+    /// * `merkle_tree` - Bubblegum tree; `Pubkey::default()` disables receipts
+    /// * `base_uri` - Prefix of receipt URIs, at most 110 bytes
+    ///
+    /// # Errors
+    /// * `OnlyOwner` - If caller is not the owner
+    /// * `InvalidReceiptUri` - If `base_uri` is too long
+    pub fn set_receipt_tree(
+        ctx: Context<SetReceiptTree>,
+        merkle_tree: Pubkey,
+        base_uri: String,
+    ) -> Result<()> {
+        receipts::set_receipt_tree(ctx, merkle_tree, base_uri)
+    }
+
+    /// Start the compressed message log on a new tree (owner only)
+    ///
+    /// `merkle_tree` must be an empty account allocated to the SPL Account
+    /// Compression program for `max_depth` and `max_buffer_size`; it is
+    /// initialized with the mailer PDA as authority. Calling this again moves
+    /// the log to a fresh tree, e.g. when the current one is full.
+    ///
+    /// # Arguments
+    /// * `max_depth` - Tree depth; the tree holds `2^max_depth` messages
+    /// * `max_buffer_size` - Concurrent changes the tree tolerates per slot
+    ///
+    /// # Errors
+    /// * `OnlyOwner` - If caller is not the owner
+    pub fn init_message_log(
+        ctx: Context<InitMessageLog>,
+        max_depth: u32,
+        max_buffer_size: u32,
+    ) -> Result<()> {
+        message_log::init_message_log(ctx, max_depth, max_buffer_size)
+    }
+
+    /// Prove a message is in the current message log tree
+    ///
+    /// The proof nodes, leaf to root, follow in `remaining_accounts`. Fails
+    /// unless the compression program accepts the proof; on success emits
+    /// `MessageVerified`.
+    ///
+    /// # Arguments
+    /// * `message_id` / `from` / `to` - As emitted with the message
+    /// * `content_hash` - `message_content_hash(subject, body)`, `mail_id_hash(mail_id)` or
+    ///   the SHA-256 of a sealed envelope
+    /// * `root` - Tree root the proof was built against
+    /// * `index` - Leaf index from the `MessageLogged` event
+    ///
+    /// # Errors
+    /// * `InvalidMessageLogTree` - If `merkle_tree` is not the log's tree
+    pub fn verify_message<'info>(
+        ctx: Context<'_, '_, 'info, 'info, VerifyMessage<'info>>,
+        message_id: [u8; 32],
+        from: Pubkey,
+        to: Pubkey,
+        content_hash: [u8; 32],
+        root: [u8; 32],
+        index: u32,
+    ) -> Result<()> {
+        message_log::verify_message(ctx, message_id, from, to, content_hash, root, index)
+    }
+
+    /// Appoint a key besides the owner to publish daily message roots (owner only)
+    ///
+    /// # Arguments
+    /// * `publisher` - Root publisher, e.g. an indexer's crank key; `Pubkey::default()` for none
+    ///
+    /// # Errors
+    /// * `OnlyOwner` - If caller is not the owner
+    pub fn set_root_publisher(ctx: Context<SetFee>, publisher: Pubkey) -> Result<()> {
+        message_roots::set_root_publisher(ctx, publisher)
+    }
+
+    /// Anchor the Merkle root of a finished day's messages (owner or root publisher)
+    ///
+    /// Roots are write-once; the publisher pays the `[b"root", day]` account's rent.
+    ///
+    /// # Arguments
+    /// * `day` - Day number, `unix_timestamp / 86400`
+    /// * `root` - Root over the day's message leaves, see [`message_roots`]
+    /// * `message_count` - Messages the root covers
+    ///
+    /// # Errors
+    /// * `NotRootPublisher` - If caller is neither the owner nor the root publisher
+    /// * `DayNotFinished` - If `day` is today or later
+    pub fn publish_message_root(
+        ctx: Context<PublishMessageRoot>,
+        day: u64,
+        root: [u8; 32],
+        message_count: u64,
+    ) -> Result<()> {
+        message_roots::publish_message_root(ctx, day, root, message_count)
+    }
+
+    /// Check a message is included in a published daily root
+    ///
+    /// Succeeds, emitting `InclusionVerified`, only if the proof leads to the root.
+    ///
+    /// # Arguments
+    /// * `message_hash` - The message's `message_leaf`
+    /// * `proof` - Sibling hashes from the leaf up to the root
+    ///
+    /// # Errors
+    /// * `InvalidInclusionProof` - If the proof is longer than 32 nodes or does not lead to the root
+    pub fn verify_inclusion(
+        ctx: Context<VerifyInclusion>,
+        message_hash: [u8; 32],
+        proof: Vec<[u8; 32]>,
+    ) -> Result<()> {
+        message_roots::verify_inclusion(ctx, message_hash, proof)
+    }
+
+    /// Route an owner share bucket to a new destination wallet (owner only)
+    ///
+    /// Creates the bucket if the mint was added before buckets existed.
+    ///
+    /// # Arguments
+    /// * `source` - Fee source whose bucket is routed
+    /// * `destination` - Wallet that receives withdrawals from the bucket
+    ///
+    /// # Errors
+    /// * `OnlyOwner` - If caller is not the owner
+    pub fn set_bucket_destination(
+        ctx: Context<SetBucketDestination>,
+        source: FeeSource,
+        destination: Pubkey,
+    ) -> Result<()> {
+        fees::set_bucket_destination(ctx, source, destination)
+    }
+
+    /// Accept a mint for fees, or update an accepted mint's fee (owner only)
+    ///
+    /// Creates the mint's `TokenConfig` PDA on first use and records the
+    /// mint's decimals, along with the mint's owner share buckets (routed to
+    /// the owner by default). Every send, claim and sweep is validated against the
+    /// config of the mint it moves, so USDT, PYUSD and other stablecoins can
+    /// be offered alongside USDC. The original `usdc_mint` must be added too.
+    ///
+    /// # Arguments
+    /// * `send_fee` - Priority send fee in the mint's base units
+    ///
+    /// # Errors
+    /// * `OnlyOwner` - If caller is not the owner
+    /// * `InvalidFee` - If the standard fee would be zero or a full prepared batch would overflow
+    pub fn add_token(ctx: Context<ConfigureToken>, send_fee: u64) -> Result<()> {
+        tokens::add_token(ctx, send_fee)
+    }
+
+    /// Stop accepting a mint for new sends (owner only)
+    ///
+    /// Outstanding recipient claims and the owner share in the mint remain
+    /// claimable. The mint can be re-enabled with `add_token`.
+    ///
+    /// # Errors
+    /// * `OnlyOwner` - If caller is not the owner
+    /// * `TokenDisabled` - If the mint is already disabled
+    pub fn remove_token(ctx: Context<RemoveToken>) -> Result<()> {
+        tokens::remove_token(ctx)
+    }
+
+    /// Set how clients should display a fee token (owner only)
+    ///
+    /// Stores the symbol and a formatting hint in the mint's `TokenDisplay`
+    /// PDA, along with its decimals read from the mint, so multi-token
+    /// deployments don't need per-mint formatting in clients. Call again after
+    /// adding a new mint or replacing the USDC mint.
+    ///
+    /// # Arguments
+    /// * `symbol` - Ticker shown to users, e.g. "USDC" (1-10 bytes)
+    /// * `display_hint` - Formatting hint, e.g. "$0.00" (max 32 bytes)
+    ///
+    /// # Errors
+    /// * `OnlyOwner` - If caller is not the owner
+    /// * `InvalidDisplayMetadata` - If the symbol or hint is out of bounds
+    pub fn set_token_display(
+        ctx: Context<SetTokenDisplay>,
+        symbol: String,
+        display_hint: String,
+    ) -> Result<()> {
+        tokens::set_token_display(ctx, symbol, display_hint)
+    }
+
+    /// Quote the fee for one send in a mint, with its display metadata
+    ///
+    /// Read-only; the quote is returned as instruction return data so clients
+    /// can simulate it. Oracle-priced mints need a fresh `price_update`.
+    ///
+    /// # Arguments
+    /// * `priority` - Quote the priority fee instead of the standard fee
+    ///
+    /// # Errors
+    /// * `TokenDisabled` - If the mint is not accepted for fees
+    /// * `PriceFeedRequired` / `PriceFeedMismatch` / `StalePrice` - If an oracle-priced
+    ///   mint is quoted without a fresh price for its feed
+    /// * `UncertainPrice` - If that price's confidence interval exceeds 2% of it
+    pub fn quote_fee(ctx: Context<QuoteFee>, priority: bool) -> Result<FeeQuote> {
+        tokens::quote_fee(ctx, priority)
+    }
+
+    /// Break down the send fee in a mint into its shares and claim terms
+    ///
+    /// Read-only, like `quote_fee`: the breakdown is returned as instruction
+    /// return data so clients can simulate it without decoding accounts.
+    ///
+    /// # Errors
+    /// * `TokenDisabled` - If the mint is not accepted for fees
+    /// * `PriceFeedRequired` / `PriceFeedMismatch` / `StalePrice` / `UncertainPrice` - As in quote_fee
+    pub fn get_fee_breakdown(ctx: Context<QuoteFee>) -> Result<FeeBreakdown> {
+        fees::get_fee_breakdown(ctx)
+    }
+
+    /// Report a recipient's claimable balance, returned as instruction return data
+    ///
+    /// Read-only and callable for any recipient. Recipients who never received
+    /// a share get an empty claim rather than an error.
+    ///
+    /// # Arguments
+    /// * `recipient` - Recipient whose claim to report
+    pub fn get_claimable(ctx: Context<GetClaimable>, recipient: Pubkey) -> Result<ClaimableView> {
+        claims::get_claimable(ctx, recipient)
+    }
+
+    /// Price a mint's sends from a Pyth feed instead of its fixed fee (owner only)
+    ///
+    /// Sends in the mint are then charged the token equivalent of
+    /// `usd_fee_cents` and must pass a `price_update` no older than 60 seconds.
+    ///
+    /// # Arguments
+    /// * `price_feed_id` - Pyth feed ID pricing the mint in USD; all zeroes reverts to `send_fee`
+    ///
+    /// # Errors
+    /// * `OnlyOwner` - If caller is not the owner
+    pub fn set_price_feed(ctx: Context<SetPriceFeed>, price_feed_id: [u8; 32]) -> Result<()> {
+        tokens::set_price_feed(ctx, price_feed_id)
+    }
+
+    /// Set the priority send fee for oracle-priced mints, in US cents (owner only)
+    ///
+    /// # Errors
+    /// * `OnlyOwner` - If caller is not the owner
+    /// * `InvalidFee` - If the fee is zero
+    pub fn set_usd_fee(ctx: Context<SetFee>, new_usd_fee_cents: u64) -> Result<()> {
+        fees::set_usd_fee(ctx, new_usd_fee_cents)
+    }
+
+    /// Send a message with a payment escrowed for the recipient
+    ///
+    /// The payment is held by the program until the recipient acknowledges the
+    /// message. With an `ack_deadline`, anyone may refund it to the sender via
+    /// `refund_unread` once the deadline passes, so funds never wait on an
+    /// inactive recipient. Without one, the sender may `reclaim_payment` after
+    /// 30 days. The escrow's rent may be sponsored by a separate `payer`, who
+    /// gets it back when the payment is resolved. Returns the message ID,
+    /// derived like every other send's; the escrow is keyed by `payment_id`.
+    ///
+    /// # Arguments
+    /// * `args` - Payment to send:
+    ///   * `payment_id` - Unique 32-byte key chosen by the caller; seeds the escrow PDA
+    ///   * `recipient` - Address that may acknowledge and collect the payment
+    ///   * `amount` - Payment in the mint's base units
+    ///   * `mail_id` - Pre-prepared message identifier
+    ///   * `ack_deadline` - Optional unix time after which the payment is refundable
+    ///   * `recipient_external` - Optional recipient on another chain, stored on the
+    ///     escrow for the relay; `recipient` still acknowledges on Solana
+    ///   * `client_ref` - Optional caller reference echoed in the event, as in send_priority
+    ///
+    /// # Errors
+    /// * `InvalidPaymentAmount` - If the amount is zero
+    /// * `InvalidAckDeadline` - If the deadline is not in the future
+    /// * `TokenDisabled` - If the mint is not an enabled fee token
+    /// * `InvalidExternalAddress` - If `recipient_external` is malformed
+    /// * `SenderBlocked` - If the recipient has blocked the sender
+    /// * `AddressDenied` - If the sender or recipient is on the compliance denylist
+    pub fn send_with_payment(
+        ctx: Context<SendWithPayment>,
+        args: SendWithPaymentArgs,
+    ) -> Result<[u8; 32]> {
+        escrow::send_with_payment(ctx, args)
+    }
+
+    /// Acknowledge a payment-bearing message and collect its payment
+    ///
+    /// # Errors
+    /// * `InvalidEscrowParty` - If the signer is not the recipient
+    /// * `AcknowledgementExpired` - If the acknowledgement deadline has passed
+    /// * `AddressDenied` - If the recipient is on the compliance denylist
+    pub fn acknowledge_payment(ctx: Context<AcknowledgePayment>) -> Result<()> {
+        escrow::acknowledge_payment(ctx)
+    }
+
+    /// Refund an unacknowledged payment to its sender after the deadline (permissionless)
+    ///
+    /// Emits `MailExpiredUnread` and closes the escrow, returning its rent to
+    /// whoever funded it.
+    ///
+    /// # Errors
+    /// * `NoAckDeadline` - If the payment was sent without a deadline
+    /// * `AckDeadlineNotReached` - If the deadline has not passed yet
+    pub fn refund_unread(ctx: Context<RefundUnread>) -> Result<()> {
+        escrow::refund_unread(ctx)
+    }
+
+    /// Reclaim an unacknowledged payment as its sender after the reclaim timeout
+    ///
+    /// Covers payments sent without an `ack_deadline`: once 30 days have
+    /// passed since the payment was sent, the sender may take it back.
+    /// Payments with a deadline are refunded by `refund_unread` only, so the
+    /// recipient keeps the full window the sender promised. Closes the escrow,
+    /// returning its rent to whoever funded it.
+    ///
+    /// # Errors
+    /// * `InvalidEscrowParty` - If the signer is not the sender
+    /// * `HasAckDeadline` - If the payment was sent with an acknowledgement deadline
+    /// * `ReclaimTimeoutNotReached` - If 30 days have not passed since sending
+    pub fn reclaim_payment(ctx: Context<ReclaimPayment>) -> Result<()> {
+        escrow::reclaim_payment(ctx)
+    }
+
+    /// Schedule a prepared message to be sent after a given time
+    ///
+    /// Escrows the standard fee in the `[b"scheduled", sender, sha256(mail_id)]`
+    /// PDA, counting the message against the sender's daily limit now. The
+    /// PDA's rent may be sponsored by a separate `payer`.
+    ///
+    /// # Arguments
+    /// * `mail_id` - Pre-prepared message identifier, 1 to 64 bytes
+    /// * `execute_after` - Unix time from which anyone may execute the send
+    ///
+    /// # Errors
+    /// * `InvalidScheduledMailId` - If the mail ID is empty or longer than 64 bytes
+    /// * `InvalidExecuteAfter` - If `execute_after` is not in the future
+    /// * `TokenDisabled` - If the mint is not an enabled fee token
+    /// * `RateLimitExceeded` - If the sender has used up the owner's daily message limit
+    /// * `AddressDenied` - If the sender is on the compliance denylist
+    pub fn schedule_send(
+        ctx: Context<ScheduleSend>,
+        mail_id: String,
+        execute_after: i64,
+    ) -> Result<()> {
+        scheduled::schedule_send(ctx, mail_id, execute_after)
+    }
+
+    /// Send a due scheduled message (permissionless crank)
+    ///
+    /// Books the escrowed fee to the owner and emits `StandardPreparedMailSent`
+    /// from the scheduling sender. Closes the schedule, returning its rent to
+    /// whoever funded it.
+    ///
+    /// # Errors
+    /// * `NotYetExecutable` - If `execute_after` has not been reached
+    pub fn execute_scheduled(ctx: Context<ExecuteScheduled>) -> Result<()> {
+        scheduled::execute_scheduled(ctx)
+    }
+
+    /// Cancel a scheduled message before it is executed, refunding its fee
+    ///
+    /// # Errors
+    /// * `InvalidScheduledSender` - If the signer did not schedule the message
+    pub fn cancel_scheduled(ctx: Context<CancelScheduled>) -> Result<()> {
+        scheduled::cancel_scheduled(ctx)
+    }
+
+    /// Open a session letting `session_key` send on the signer's behalf
+    ///
+    /// Stores the `[b"session", owner, session_key]` PDA and approves the
+    /// mailer PDA as delegate of the owner's fee token account for
+    /// `max_spend` on top of any allowance other sessions hold.
+    ///
+    /// # Arguments
+    /// * `session_key` - Key allowed to send, e.g. one kept in a browser or on a phone
+    /// * `expires_at` - Unix time the session ends, at most 30 days away
+    /// * `max_spend` - Most the session may spend on fees, in the mint's base units
+    ///
+    /// # Errors
+    /// * `InvalidSessionExpiry` - If `expires_at` is not in the next 30 days
+    /// * `TokenDisabled` - If the mint is not an enabled fee token
+    pub fn create_session(
+        ctx: Context<CreateSession>,
+        session_key: Pubkey,
+        expires_at: i64,
+        max_spend: u64,
+    ) -> Result<()> {
+        sessions::create_session(ctx, session_key, expires_at, max_spend)
+    }
+
+    /// Close a session, handing back the allowance it did not spend
+    ///
+    /// # Errors
+    /// * `InvalidSessionOwner` - If the signer did not open the session
+    pub fn revoke_session(ctx: Context<RevokeSession>) -> Result<()> {
+        sessions::revoke_session(ctx)
+    }
+
+    /// Send a message to self on behalf of a session's owner
+    ///
+    /// Signed by the session key; the fee is pulled from the owner's token
+    /// account by the mailer PDA as its delegate, and priority revenue shares
+    /// accrue to the owner. The message is emitted from the owner.
+    ///
+    /// # Arguments
+    /// * `subject` - Message subject line (plain text)
+    /// * `body` - Message content (plain text)
+    /// * `priority` - Whether to send as a priority (full fee) message
+    /// * `client_ref` - Optional caller reference echoed in the event, as in send_priority
+    ///
+    /// # Errors
+    /// * `SessionExpired` - If the session has reached `expires_at`
+    /// * `SessionSpendExceeded` - If the fee would take the session past `max_spend`
+    /// * `MessageTooLarge` - If the subject or body exceeds the owner's length limits
+    /// * `RateLimitExceeded` - If the owner has used up the daily message limit
+    /// * `AddressDenied` - If the owner is on the compliance denylist
+    pub fn send_with_session(
+        ctx: Context<SendWithSession>,
+        subject: String,
+        body: String,
+        priority: bool,
+        client_ref: Option<[u8; 16]>,
+    ) -> Result<[u8; 32]> {
+        sessions::send_with_session(ctx, subject, body, priority, client_ref)
+    }
+
+    /// Relay a prepared send signed off chain by its sender
+    ///
+    /// Must directly follow an Ed25519 program instruction verifying the
+    /// sender's signature over `signed_send_payload(sender, mail_id, nonce,
+    /// expiry)`. The message is sent from the signer and its standard fee is
+    /// pulled from their token account by the mailer PDA, which they must have
+    /// approved as delegate; the relayer pays only SOL.
+    ///
+    /// # Arguments
+    /// * `mail_id` - Pre-prepared message identifier, 1 to 64 bytes
+    /// * `nonce` - The sender's next nonce in their `[b"nonce", sender]` registry
+    /// * `expiry` - Unix time after which the signed payload may no longer be relayed
+    ///
+    /// # Errors
+    /// * `InvalidSignedMailId` - If the mail ID is empty or longer than 64 bytes
+    /// * `SignatureExpired` - If `expiry` has passed
+    /// * `MissingSignatureVerification` - If the previous instruction is not an Ed25519 verification
+    /// * `InvalidSignatureVerification` - If it does not verify the sender's signature over the payload
+    /// * `InvalidNonce` - If `nonce` is not the sender's next nonce
+    /// * `RateLimitExceeded` - If the sender has used up the owner's daily message limit
+    /// * `AddressDenied` - If the sender is on the compliance denylist
+    pub fn send_with_signature(
+        ctx: Context<SendWithSignature>,
+        mail_id: String,
+        nonce: u64,
+        expiry: i64,
+    ) -> Result<[u8; 32]> {
+        signed_sends::send_with_signature(ctx, mail_id, nonce, expiry)
+    }
+
+    /// Deposit fee tokens as prepaid credit
+    ///
+    /// Creates or tops up the `[b"credit", owner, mint]` PDA. Sends passing it
+    /// draw their fee from the balance before transferring any tokens.
+    ///
+    /// # Arguments
+    /// * `amount` - Tokens to deposit; the credit grows by what arrives net of transfer fees
+    ///
+    /// # Errors
+    /// * `InvalidCreditAmount` - If `amount` is zero
+    /// * `TokenDisabled` - If the mint is not an enabled fee token
+    pub fn deposit_credit(ctx: Context<DepositCredit>, amount: u64) -> Result<()> {
+        credits::deposit_credit(ctx, amount)
+    }
+
+    /// Withdraw unspent prepaid credit back to the owner's token account
+    ///
+    /// # Arguments
+    /// * `amount` - Credit to withdraw
+    ///
+    /// # Errors
+    /// * `InvalidCreditAmount` - If `amount` is zero
+    /// * `InsufficientCredit` - If `amount` exceeds the balance
+    pub fn withdraw_credit(ctx: Context<WithdrawCredit>, amount: u64) -> Result<()> {
+        credits::withdraw_credit(ctx, amount)
+    }
+
+    /// Create a subscription plan (owner only)
+    ///
+    /// Subscribers pay `price` in the mailer's USDC mint for `duration`
+    /// seconds of standard sends without a per-message fee.
+    ///
+    /// # Arguments
+    /// * `id` - Plan ID; seeds the `[b"plan", id]` PDA
+    /// * `price` - Price of one period, in USDC base units
+    /// * `duration` - Length of one period, in seconds
+    /// * `message_cap` - Standard messages one period covers; 0 for unlimited
+    ///
+    /// # Errors
+    /// * `OnlyOwner` - If caller is not the owner
+    /// * `InvalidPlanPrice` - If the price is zero
+    /// * `InvalidPlanDuration` - If the duration is not positive
+    pub fn create_subscription_plan(
+        ctx: Context<CreateSubscriptionPlan>,
+        id: u64,
+        price: u64,
+        duration: i64,
+        message_cap: u64,
+    ) -> Result<()> {
+        subscriptions::create_subscription_plan(ctx, id, price, duration, message_cap)
+    }
+
+    /// Withdraw a subscription plan from sale (owner only)
+    ///
+    /// Existing subscriptions keep running on the terms they were bought on.
+    ///
+    /// # Errors
+    /// * `OnlyOwner` - If caller is not the owner
+    pub fn close_subscription_plan(ctx: Context<CloseSubscriptionPlan>) -> Result<()> {
+        subscriptions::close_subscription_plan(ctx)
+    }
+
+    /// Buy a period of a subscription plan
+    ///
+    /// The price is booked to the owner. Buying the plan the signer is
+    /// already subscribed to extends the period and adds to its message cap;
+    /// otherwise a fresh period starts now. Standard sends passing the
+    /// `[b"plan_subscription", sender]` PDA are then free while it is active and
+    /// has cap left.
+    ///
+    /// # Arguments
+    /// * `plan_id` - ID of the plan to buy
+    ///
+    /// # Errors
+    /// * `ClaimMintMismatch` - If `mint` is not the plan's mint
+    pub fn purchase_subscription(ctx: Context<PurchaseSubscription>, plan_id: u64) -> Result<()> {
+        subscriptions::purchase_subscription(ctx, plan_id)
+    }
+
+    /// Configure the fee discount tiers for stakers (owner only)
+    ///
+    /// Sends passing the sender's stake are charged the fee less the discount
+    /// of the highest tier the stake reaches, or the NFT holder discount if
+    /// that is larger. Replaces any previous tiers; an empty list disables
+    /// stake discounts.
+    ///
+    /// # Arguments
+    /// * `tiers` - Up to 4 tiers, rising strictly in `min_stake` and `discount_bps`
+    ///
+    /// # Errors
+    /// * `OnlyOwner` - If caller is not the owner
+    /// * `InvalidStakeTiers` - If there are too many tiers, they do not rise, a
+    ///   tier has no minimum stake or a discount exceeds 100%
+    pub fn set_stake_tiers(ctx: Context<SetFee>, tiers: Vec<StakeTier>) -> Result<()> {
+        staking::set_stake_tiers(ctx, tiers)
+    }
+
+    /// Lock USDC in the signer's stake
+    ///
+    /// Creates or tops up the `[b"stake", owner]` PDA. Each deposit locks the
+    /// whole stake for another 7 days.
+    ///
+    /// # Arguments
+    /// * `amount` - USDC to stake; the stake grows by what arrives net of transfer fees
+    ///
+    /// # Errors
+    /// * `InvalidStakeAmount` - If `amount` is zero
+    /// * `ClaimMintMismatch` - If `mint` is not the mailer's USDC mint
+    pub fn stake(ctx: Context<StakeTokens>, amount: u64) -> Result<()> {
+        staking::stake(ctx, amount)
+    }
+
+    /// Withdraw the signer's whole stake and close the stake account
+    ///
+    /// # Errors
+    /// * `StakeLocked` - If 7 days have not passed since the last deposit
+    pub fn unstake(ctx: Context<Unstake>) -> Result<()> {
+        staking::unstake(ctx)
+    }
+
+    /// Create a broadcast channel owned by the signer
+    ///
+    /// The channel PDA is derived from the SHA-256 of its name, so each name
+    /// can be claimed once.
+    ///
+    /// # Arguments
+    /// * `name` - Channel name, 1 to 32 bytes
+    ///
+    /// # Errors
+    /// * `InvalidChannelName` - If the name is empty or too long
+    pub fn create_channel(ctx: Context<CreateChannel>, name: String) -> Result<()> {
+        channels::create_channel(ctx, name)
+    }
+
+    /// Subscribe the signer to a channel
+    ///
+    /// Creates the subscriber's subscription PDA, funded by the subscriber;
+    /// subscribing twice fails because the PDA already exists.
+    pub fn subscribe(ctx: Context<Subscribe>) -> Result<()> {
+        channels::subscribe(ctx)
+    }
+
+    /// Post a prepared message to a channel
+    ///
+    /// Charged like `send_priority_prepared`, except the recipient share is
+    /// credited to the channel owner's claim, under the usual claim period.
+    /// Emits `ChannelPosted` with the channel key for subscribers to follow.
+    /// Returns the message ID.
+    ///
+    /// # Arguments
+    /// * `mail_id` - Pre-prepared message identifier
+    ///
+    /// # Accounts
+    /// Same as send_priority_prepared under `send`, plus the channel and the
+    /// owner's claim (omitted when the owner is posting)
+    ///
+    /// On a readers-only channel, posters other than the owner and moderators
+    /// must also pass their subscription, which must be active.
+    ///
+    /// # Errors
+    /// * `SenderMuted` - If a moderator muted the poster in this channel
+    /// * `SubscriptionRequired` - If the channel is readers-only and the poster has no active subscription
+    /// * `InvalidOwnerClaim` - If the owner's claim is passed by the owner or omitted by anyone else
+    /// * `DuplicateMailId` - If a `mail_id_record` is passed and the mail ID was already sent
+    pub fn post_to_channel(ctx: Context<PostToChannel>, mail_id: String) -> Result<[u8; 32]> {
+        channels::post_to_channel(ctx, mail_id)
+    }
+
+    /// Set a channel's subscription fee and posting policy (channel owner only)
+    ///
+    /// # Arguments
+    /// * `subscription_fee` - Fee per 30-day epoch in the mint's base units; zero makes the channel free
+    /// * `readers_only` - Only accept posts from the owner and active subscribers
+    ///
+    /// # Errors
+    /// * `NotChannelOwner` - If caller is not the channel owner
+    /// * `TokenDisabled` - If the mint is not an enabled fee token
+    /// * `ChannelFeesOutstanding` - If the mint changes while collected fees are unwithdrawn
+    pub fn set_channel_terms(
+        ctx: Context<SetChannelTerms>,
+        subscription_fee: u64,
+        readers_only: bool,
+    ) -> Result<()> {
+        channels::set_channel_terms(ctx, subscription_fee, readers_only)
+    }
+
+    /// Pay a channel's subscription fee for one or more epochs
+    ///
+    /// The fee is credited to the channel's claimable balance. A running
+    /// subscription is extended from its current end; a lapsed one restarts now.
+    ///
+    /// # Arguments
+    /// * `epochs` - Number of 30-day epochs to pay for
+    ///
+    /// # Errors
+    /// * `InvalidEpochs` - If `epochs` is zero
+    /// * `FreeChannel` - If the channel charges no subscription fee
+    pub fn pay_subscription(ctx: Context<PaySubscription>, epochs: u32) -> Result<()> {
+        channels::pay_subscription(ctx, epochs)
+    }
+
+    /// Withdraw a channel's collected subscription fees (channel owner only)
+    ///
+    /// # Errors
+    /// * `NotChannelOwner` - If caller is not the channel owner
+    /// * `NothingToWithdraw` - If no fees have been collected since the last withdrawal
+    /// * `AddressDenied` - If the owner is on the compliance denylist
+    pub fn withdraw_channel_fees(ctx: Context<WithdrawChannelFees>) -> Result<()> {
+        channels::withdraw_channel_fees(ctx)
+    }
+
+    /// Appoint a channel moderator (channel owner only)
+    ///
+    /// Moderators may remove subscribers, pin a post and mute senders, and
+    /// post to readers-only channels without a subscription.
+    ///
+    /// # Errors
+    /// * `NotChannelOwner` - If caller is not the channel owner
+    /// * `AlreadyModerator` - If the address is the owner or already a moderator
+    /// * `TooManyModerators` - If the channel already has 4 moderators
+    pub fn grant_channel_moderator(ctx: Context<ManageChannel>, moderator: Pubkey) -> Result<()> {
+        channels::grant_channel_moderator(ctx, moderator)
+    }
+
+    /// Remove a channel moderator (channel owner only)
+    ///
+    /// # Errors
+    /// * `NotChannelOwner` - If caller is not the channel owner
+    /// * `UnknownModerator` - If the address is not a moderator
+    pub fn revoke_channel_moderator(ctx: Context<ManageChannel>, moderator: Pubkey) -> Result<()> {
+        channels::revoke_channel_moderator(ctx, moderator)
+    }
+
+    /// Remove a subscriber from a channel (channel owner or moderator)
+    ///
+    /// Closes the subscription, returning its rent to the subscriber; any paid
+    /// time is forfeited.
+    ///
+    /// # Errors
+    /// * `NotModerator` - If caller is neither the channel owner nor a moderator
+    pub fn remove_subscriber(ctx: Context<RemoveSubscriber>) -> Result<()> {
+        channels::remove_subscriber(ctx)
+    }
+
+    /// Pin a post in a channel, replacing any earlier pin (channel owner or moderator)
+    ///
+    /// # Arguments
+    /// * `message_id` - Message ID of the post, as emitted in `ChannelPosted`
+    ///
+    /// # Errors
+    /// * `NotModerator` - If caller is neither the channel owner nor a moderator
+    pub fn pin_post(ctx: Context<ModerateChannel>, message_id: [u8; 32]) -> Result<()> {
+        channels::pin_post(ctx, message_id)
+    }
+
+    /// Mute a sender in a channel until unmuted (channel owner or moderator)
+    ///
+    /// Creates the sender's mute record, funded by the moderator; muted
+    /// senders cannot post to the channel.
+    ///
+    /// # Errors
+    /// * `NotModerator` - If caller is neither the channel owner nor a moderator
+    /// * `CannotMuteModerator` - If the sender is the owner or a moderator
+    pub fn mute_sender(ctx: Context<MuteSender>, sender: Pubkey) -> Result<()> {
+        channels::mute_sender(ctx, sender)
+    }
+
+    /// Unmute a sender in a channel (channel owner or moderator)
+    ///
+    /// Closes the mute record, returning its rent to the moderator who muted.
+    ///
+    /// # Errors
+    /// * `NotModerator` - If caller is neither the channel owner nor a moderator
+    pub fn unmute_sender(ctx: Context<UnmuteSender>) -> Result<()> {
+        channels::unmute_sender(ctx)
+    }
+
+    /// Create a group administered by the signer
+    ///
+    /// # Arguments
+    /// * `name` - Group name, 1 to 32 bytes; seeds the group PDA by its SHA-256
+    ///
+    /// # Errors
+    /// * `InvalidGroupName` - If the name is empty or too long
+    pub fn create_group(ctx: Context<CreateGroup>, name: String) -> Result<()> {
+        groups::create_group(ctx, name)
+    }
+
+    /// Add a member to a group (group admin only)
+    ///
+    /// Creates the member's claim, funded by the admin, if it does not exist yet.
+    ///
+    /// # Errors
+    /// * `NotGroupAdmin` - If caller is not the group admin
+    /// * `AlreadyMember` - If the address is already a member
+    /// * `GroupFull` - If the group already has 16 members
+    pub fn add_member(ctx: Context<AddMember>, member: Pubkey) -> Result<()> {
+        groups::add_member(ctx, member)
+    }
+
+    /// Remove a member from a group (group admin or moderator)
+    ///
+    /// # Errors
+    /// * `NotGroupModerator` - If caller is neither the group admin nor a moderator
+    /// * `NotMember` - If the address is not a member
+    pub fn remove_member(ctx: Context<ModerateGroup>, member: Pubkey) -> Result<()> {
+        groups::remove_member(ctx, member)
+    }
+
+    /// Appoint a group moderator (group admin only)
+    ///
+    /// # Errors
+    /// * `NotGroupAdmin` - If caller is not the group admin
+    /// * `AlreadyModerator` - If the address is the admin or already a moderator
+    /// * `TooManyModerators` - If the group already has 4 moderators
+    pub fn grant_group_moderator(ctx: Context<ManageGroup>, moderator: Pubkey) -> Result<()> {
+        groups::grant_group_moderator(ctx, moderator)
+    }
+
+    /// Remove a group moderator (group admin only)
+    ///
+    /// # Errors
+    /// * `NotGroupAdmin` - If caller is not the group admin
+    /// * `UnknownModerator` - If the address is not a moderator
+    pub fn revoke_group_moderator(ctx: Context<ManageGroup>, moderator: Pubkey) -> Result<()> {
+        groups::revoke_group_moderator(ctx, moderator)
+    }
+
+    /// Send a prepared message to every member of a group
+    ///
+    /// Charged like `send_priority_prepared`. The owner share is credited as
+    /// usual; the recipient share is split equally across the members'
+    /// claims, and the remainder of that split goes to the owner. Returns the
+    /// message ID.
+    ///
+    /// # Accounts
+    /// Same as send_priority_prepared under `send`, plus the group. Each
+    /// member's claim PDA must follow in `remaining_accounts`, writable and in
+    /// member order. Claims a member has closed, or never opened, are created
+    /// at the payer's expense.
+    ///
+    /// # Errors
+    /// * `NotMember` - If the sender is neither a member nor the group admin
+    /// * `EmptyGroup` - If the group has no members
+    /// * `InvalidMemberClaim` - If the remaining accounts are not the members' claims
+    /// * `ClaimMintMismatch` - If a member's claim holds a balance in another mint
+    pub fn send_to_group<'info>(
+        ctx: Context<'_, '_, 'info, 'info, SendToGroup<'info>>,
+        mail_id: String,
+    ) -> Result<[u8; 32]> {
+        groups::send_to_group(ctx, mail_id)
+    }
+}
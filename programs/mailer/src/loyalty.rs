@@ -0,0 +1,209 @@
+//! Loyalty points.
+//!
+//! Senders that pass their `SenderStats` PDA (`[b"stats", sender]`, created on
+//! first use) earn `priority_points` per priority message and
+//! `standard_points` per standard message they pay for; fee-exempt sends earn
+//! nothing. Points cannot be transferred. `redeem_points` turns them into send
+//! credit in the mailer's USDC mint at `point_value` base units per point,
+//! funded from the owner's send bucket, so later sends draw it down before
+//! charging tokens.
+
+use anchor_lang::prelude::*;
+use mailbox_common::seeds;
+
+use crate::admin::MailerState;
+use crate::claims::RecipientClaim;
+use crate::fees::{FeeError, FeeSource, OwnerBucket, SetFee};
+use crate::messaging::SendMessage;
+use crate::tokens::{claim_mint, TokenError};
+
+pub fn set_points_rates(
+    ctx: Context<SetFee>,
+    priority_points: u32,
+    standard_points: u32,
+    point_value: u64,
+) -> Result<()> {
+    let mailer = &mut ctx.accounts.mailer;
+    mailer.priority_points = priority_points;
+    mailer.standard_points = standard_points;
+    mailer.point_value = point_value;
+
+    emit!(PointsRatesUpdated {
+        priority_points,
+        standard_points,
+        point_value,
+    });
+
+    Ok(())
+}
+
+pub fn redeem_points(ctx: Context<RedeemPoints>, points: u64) -> Result<()> {
+    let point_value = ctx.accounts.mailer.point_value;
+    require!(point_value > 0, LoyaltyError::RedemptionDisabled);
+    let stats = &mut ctx.accounts.sender_stats;
+    require!(
+        points > 0 && points <= stats.points,
+        LoyaltyError::InsufficientPoints
+    );
+
+    let sender = ctx.accounts.sender.key();
+    let claim = &mut ctx.accounts.recipient_claim;
+    require_keys_eq!(
+        claim_mint(claim, &ctx.accounts.mailer),
+        ctx.accounts.mailer.usdc_mint,
+        TokenError::ClaimMintMismatch
+    );
+
+    let credit = points
+        .checked_mul(point_value)
+        .ok_or(FeeError::MathOverflow)?;
+    ctx.accounts.owner_bucket.claimable = ctx
+        .accounts
+        .owner_bucket
+        .claimable
+        .checked_sub(credit)
+        .ok_or(LoyaltyError::RedemptionUnfunded)?;
+    stats.points -= points;
+
+    if claim.recipient == Pubkey::default() {
+        claim.recipient = sender;
+        claim.rent_payer = sender;
+    }
+    claim.send_credit = claim
+        .send_credit
+        .checked_add(credit)
+        .ok_or(FeeError::MathOverflow)?;
+
+    emit!(PointsRedeemed {
+        sender,
+        points,
+        credit,
+        remaining_points: stats.points,
+    });
+
+    Ok(())
+}
+
+/// Credit the sender the points for `messages` paid sends, when it passed its stats
+pub(crate) fn earn_points(accounts: &mut SendMessage, messages: u64, priority: bool) -> Result<()> {
+    let rate = if priority {
+        accounts.mailer.priority_points
+    } else {
+        accounts.mailer.standard_points
+    };
+    let sender = accounts.sender.key();
+    let Some(stats) = accounts.sender_stats.as_mut() else {
+        return Ok(());
+    };
+    stats.sender = sender;
+    stats.earn(
+        messages
+            .checked_mul(u64::from(rate))
+            .ok_or(FeeError::MathOverflow)?,
+    )
+}
+
+#[derive(Accounts)]
+pub struct RedeemPoints<'info> {
+    #[account(mut, seeds = [b"stats", sender.key().as_ref()], bump)]
+    pub sender_stats: Account<'info, SenderStats>,
+
+    /// Sender's claim, credited with the redeemed send credit
+    #[account(
+        init_if_needed,
+        payer = sender,
+        space = 8 + RecipientClaim::INIT_SPACE,
+        seeds = [b"claim", sender.key().as_ref()],
+        bump
+    )]
+    pub recipient_claim: Account<'info, RecipientClaim>,
+
+    /// Owner's USDC send bucket, which funds redemptions
+    #[account(
+        mut,
+        seeds = [b"bucket", mailer.usdc_mint.as_ref(), FeeSource::Send.seed()],
+        bump = owner_bucket.bump
+    )]
+    pub owner_bucket: Account<'info, OwnerBucket>,
+
+    #[account(seeds = [seeds::MAILER], bump = mailer.bump)]
+    pub mailer: Account<'info, MailerState>,
+
+    #[account(mut)]
+    pub sender: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// A sender's non-transferable loyalty points
+#[account]
+#[derive(InitSpace)]
+pub struct SenderStats {
+    pub sender: Pubkey,
+    /// Points available to redeem
+    pub points: u64,
+    /// Points earned since the stats were created, including redeemed ones
+    pub lifetime_points: u64,
+}
+
+impl SenderStats {
+    pub fn earn(&mut self, points: u64) -> Result<()> {
+        self.points = self
+            .points
+            .checked_add(points)
+            .ok_or(FeeError::MathOverflow)?;
+        self.lifetime_points = self
+            .lifetime_points
+            .checked_add(points)
+            .ok_or(FeeError::MathOverflow)?;
+        Ok(())
+    }
+}
+
+#[event]
+pub struct PointsRatesUpdated {
+    pub priority_points: u32,
+    pub standard_points: u32,
+    pub point_value: u64,
+}
+
+#[event]
+pub struct PointsRedeemed {
+    pub sender: Pubkey,
+    pub points: u64,
+    /// Send credit granted, in USDC base units
+    pub credit: u64,
+    pub remaining_points: u64,
+}
+
+#[error_code(offset = 7300)]
+pub enum LoyaltyError {
+    #[msg("Point redemption is disabled")]
+    RedemptionDisabled,
+    #[msg("Not enough points to redeem")]
+    InsufficientPoints,
+    #[msg("Owner send bucket cannot fund this redemption")]
+    RedemptionUnfunded,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn earning_adds_to_balance_and_lifetime_total() {
+        let mut stats = SenderStats {
+            sender: Pubkey::new_unique(),
+            points: 0,
+            lifetime_points: 0,
+        };
+        stats.earn(10).unwrap();
+        stats.points -= 4;
+        stats.earn(1).unwrap();
+        assert_eq!(stats.points, 7);
+        assert_eq!(stats.lifetime_points, 11);
+
+        stats.points = u64::MAX;
+        assert!(stats.earn(1).is_err());
+    }
+}
@@ -0,0 +1,321 @@
+//! Compressed message log.
+//!
+//! The owner creates an SPL Account Compression tree with the mailer PDA as
+//! its authority and records it in the `MessageLog` PDA (`[b"log"]`). Single
+//! sends that pass the log, its tree and the noop and compression programs
+//! append the message's leaf to the tree, so its history can be proven with
+//! `verify_message` without per-message rent or relying on RPC log
+//! retention. Batch sends, channel posts and group sends are not logged.
+//!
+//! A leaf is `message_leaf(message_id, from, to, content_hash)`, where the
+//! content hash is `message_content_hash(subject, body)` for inline mail,
+//! `mail_id_hash(mail_id)` for prepared mail and the SHA-256 of the envelope
+//! for sealed mail.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::hash::hashv;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::program::{invoke, invoke_signed};
+use mailbox_common::seeds;
+
+use crate::admin::{MailerError, MailerState};
+use crate::fees::FeeError;
+use crate::messaging::SendMessage;
+use crate::receipts::{SPL_ACCOUNT_COMPRESSION_ID, SPL_NOOP_ID};
+
+/// Domain separator of message log leaves
+pub const MESSAGE_LEAF_DOMAIN: &[u8] = b"mailer-log";
+
+/// Account Compression instruction discriminators
+const INIT_EMPTY_MERKLE_TREE: [u8; 8] = [191, 11, 119, 7, 180, 107, 220, 110];
+const APPEND: [u8; 8] = [149, 120, 18, 222, 236, 225, 88, 203];
+const VERIFY_LEAF: [u8; 8] = [124, 220, 22, 223, 104, 10, 250, 224];
+
+pub fn init_message_log(
+    ctx: Context<InitMessageLog>,
+    max_depth: u32,
+    max_buffer_size: u32,
+) -> Result<()> {
+    let mut data = INIT_EMPTY_MERKLE_TREE.to_vec();
+    data.extend_from_slice(&max_depth.to_le_bytes());
+    data.extend_from_slice(&max_buffer_size.to_le_bytes());
+    modify_tree(
+        &ctx.accounts.compression_program,
+        &ctx.accounts.merkle_tree,
+        &ctx.accounts.mailer,
+        &ctx.accounts.log_wrapper,
+        data,
+    )?;
+
+    let merkle_tree = ctx.accounts.merkle_tree.key();
+    let log = &mut ctx.accounts.message_log;
+    log.merkle_tree = merkle_tree;
+    log.leaves = 0;
+    log.bump = ctx.bumps.message_log;
+
+    emit!(MessageLogCreated {
+        merkle_tree,
+        max_depth,
+        max_buffer_size,
+    });
+
+    Ok(())
+}
+
+pub fn verify_message<'info>(
+    ctx: Context<'_, '_, 'info, 'info, VerifyMessage<'info>>,
+    message_id: [u8; 32],
+    from: Pubkey,
+    to: Pubkey,
+    content_hash: [u8; 32],
+    root: [u8; 32],
+    index: u32,
+) -> Result<()> {
+    let leaf = message_leaf(message_id, from, to, content_hash);
+    let mut data = VERIFY_LEAF.to_vec();
+    data.extend_from_slice(&root);
+    data.extend_from_slice(&leaf);
+    data.extend_from_slice(&index.to_le_bytes());
+
+    let merkle_tree = ctx.accounts.merkle_tree.to_account_info();
+    let mut metas = vec![AccountMeta::new_readonly(merkle_tree.key(), false)];
+    let mut infos = vec![merkle_tree];
+    for node in ctx.remaining_accounts {
+        metas.push(AccountMeta::new_readonly(node.key(), false));
+        infos.push(node.clone());
+    }
+    infos.push(ctx.accounts.compression_program.to_account_info());
+    let ix = Instruction {
+        program_id: SPL_ACCOUNT_COMPRESSION_ID,
+        accounts: metas,
+        data,
+    };
+    invoke(&ix, &infos)?;
+
+    emit!(MessageVerified {
+        message_id,
+        leaf,
+        index,
+    });
+
+    Ok(())
+}
+
+/// Append the leaf of a message to the log, when the send passed it
+pub(crate) fn log_message(
+    accounts: &mut SendMessage,
+    message_id: [u8; 32],
+    from: Pubkey,
+    to: Pubkey,
+    content_hash: [u8; 32],
+) -> Result<()> {
+    let Some(log) = accounts.message_log.as_mut() else {
+        return Ok(());
+    };
+    let missing = || error!(MessageLogError::MissingMessageLogAccounts);
+    let merkle_tree = accounts.message_log_tree.as_ref().ok_or_else(missing)?;
+    let log_wrapper = accounts.log_wrapper.as_ref().ok_or_else(missing)?;
+    let compression = accounts.compression_program.as_ref().ok_or_else(missing)?;
+    require_keys_eq!(
+        merkle_tree.key(),
+        log.merkle_tree,
+        MessageLogError::InvalidMessageLogTree
+    );
+
+    let leaf = message_leaf(message_id, from, to, content_hash);
+    let mut data = APPEND.to_vec();
+    data.extend_from_slice(&leaf);
+    modify_tree(
+        compression,
+        merkle_tree,
+        &accounts.mailer,
+        log_wrapper,
+        data,
+    )?;
+
+    let index = log.leaves;
+    log.leaves = index.checked_add(1).ok_or(FeeError::MathOverflow)?;
+
+    emit!(MessageLogged {
+        message_id,
+        leaf,
+        index,
+    });
+
+    Ok(())
+}
+
+/// Leaf of a message in the log
+pub fn message_leaf(
+    message_id: [u8; 32],
+    from: Pubkey,
+    to: Pubkey,
+    content_hash: [u8; 32],
+) -> [u8; 32] {
+    hashv(&[
+        MESSAGE_LEAF_DOMAIN,
+        &message_id,
+        from.as_ref(),
+        to.as_ref(),
+        &content_hash,
+    ])
+    .to_bytes()
+}
+
+/// Content hash of an inline message; the subject is length-prefixed so it
+/// cannot run into the body
+pub fn message_content_hash(subject: &str, body: &str) -> [u8; 32] {
+    hashv(&[
+        &(subject.len() as u32).to_le_bytes(),
+        subject.as_bytes(),
+        body.as_bytes(),
+    ])
+    .to_bytes()
+}
+
+/// Call the compression program on `merkle_tree`, signing as the tree authority
+fn modify_tree<'info>(
+    compression: &impl ToAccountInfo<'info>,
+    merkle_tree: &impl ToAccountInfo<'info>,
+    mailer: &Account<'info, MailerState>,
+    log_wrapper: &impl ToAccountInfo<'info>,
+    data: Vec<u8>,
+) -> Result<()> {
+    let merkle_tree = merkle_tree.to_account_info();
+    let authority = mailer.to_account_info();
+    let log_wrapper = log_wrapper.to_account_info();
+    let ix = Instruction {
+        program_id: SPL_ACCOUNT_COMPRESSION_ID,
+        accounts: vec![
+            AccountMeta::new(merkle_tree.key(), false),
+            AccountMeta::new_readonly(authority.key(), true),
+            AccountMeta::new_readonly(log_wrapper.key(), false),
+        ],
+        data,
+    };
+    invoke_signed(
+        &ix,
+        &[
+            merkle_tree,
+            authority,
+            log_wrapper,
+            compression.to_account_info(),
+        ],
+        &[&[seeds::MAILER, &[mailer.bump]]],
+    )?;
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct InitMessageLog<'info> {
+    /// Re-pointed at a fresh tree when the current one fills up
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = 8 + MessageLog::INIT_SPACE,
+        seeds = [b"log"],
+        bump
+    )]
+    pub message_log: Account<'info, MessageLog>,
+
+    /// CHECK: Empty tree account allocated to the compression program; initialized by it
+    #[account(mut)]
+    pub merkle_tree: UncheckedAccount<'info>,
+
+    #[account(
+        seeds = [seeds::MAILER],
+        bump = mailer.bump,
+        has_one = owner @ MailerError::OnlyOwner
+    )]
+    pub mailer: Account<'info, MailerState>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    /// CHECK: SPL Noop program
+    #[account(address = SPL_NOOP_ID)]
+    pub log_wrapper: UncheckedAccount<'info>,
+
+    /// CHECK: SPL Account Compression program
+    #[account(address = SPL_ACCOUNT_COMPRESSION_ID)]
+    pub compression_program: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct VerifyMessage<'info> {
+    #[account(
+        seeds = [b"log"],
+        bump = message_log.bump,
+        has_one = merkle_tree @ MessageLogError::InvalidMessageLogTree
+    )]
+    pub message_log: Account<'info, MessageLog>,
+
+    /// CHECK: The log's current tree, checked by `has_one`
+    pub merkle_tree: UncheckedAccount<'info>,
+
+    /// CHECK: SPL Account Compression program
+    #[account(address = SPL_ACCOUNT_COMPRESSION_ID)]
+    pub compression_program: UncheckedAccount<'info>,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct MessageLog {
+    /// Tree messages are appended to
+    pub merkle_tree: Pubkey,
+    /// Leaves appended to `merkle_tree`; the next leaf's index
+    pub leaves: u64,
+    pub bump: u8,
+}
+
+#[event]
+pub struct MessageLogCreated {
+    pub merkle_tree: Pubkey,
+    pub max_depth: u32,
+    pub max_buffer_size: u32,
+}
+
+#[event]
+pub struct MessageLogged {
+    pub message_id: [u8; 32],
+    pub leaf: [u8; 32],
+    /// Leaf index in the log's tree, needed to prove the message later
+    pub index: u64,
+}
+
+#[event]
+pub struct MessageVerified {
+    pub message_id: [u8; 32],
+    pub leaf: [u8; 32],
+    pub index: u32,
+}
+
+#[error_code(offset = 7600)]
+pub enum MessageLogError {
+    #[msg("Logging a message needs the log tree, noop and compression accounts")]
+    MissingMessageLogAccounts,
+    #[msg("Tree does not match the message log's tree")]
+    InvalidMessageLogTree,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn leaves_commit_to_every_field() {
+        let from = Pubkey::new_unique();
+        let to = Pubkey::new_unique();
+        let content = message_content_hash("Hi", "there");
+        let leaf = message_leaf([1; 32], from, to, content);
+
+        assert_ne!(leaf, message_leaf([2; 32], from, to, content));
+        assert_ne!(leaf, message_leaf([1; 32], to, from, content));
+        assert_ne!(
+            leaf,
+            message_leaf([1; 32], from, to, message_content_hash("Hit", "here"))
+        );
+    }
+}
@@ -0,0 +1,216 @@
+//! Daily message root anchoring.
+//!
+//! After a UTC day ends, the owner or the owner-appointed root publisher
+//! anchors the Merkle root over every message emitted that day in a
+//! `MessageRoot` PDA (`[b"root", day]`). Roots are write-once, so auditors
+//! can prove with `verify_inclusion` that a message existed even after RPC
+//! log history is pruned.
+//!
+//! Leaves are each message's `message_log::message_leaf`, in send order.
+//! Leaves are hashed under `ROOT_LEAF_PREFIX` and nodes under
+//! `ROOT_NODE_PREFIX` over the sorted pair of children, and an unpaired node
+//! at the end of a level is carried up as is. A proof is then just the
+//! sibling hashes from the leaf up, and cannot pass off a node as a leaf.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::hash::hashv;
+use mailbox_common::seeds;
+
+use crate::admin::MailerState;
+use crate::fees::SetFee;
+
+/// Length of a root's day, in seconds; days start at UTC midnight
+pub const ROOT_DAY: i64 = 24 * 60 * 60;
+
+/// Longest accepted inclusion proof, enough for 2^32 messages a day
+pub const MAX_ROOT_PROOF_LEN: usize = 32;
+
+/// Domain separators of root leaves and nodes
+pub const ROOT_LEAF_PREFIX: &[u8] = &[0];
+pub const ROOT_NODE_PREFIX: &[u8] = &[1];
+
+pub fn set_root_publisher(ctx: Context<SetFee>, publisher: Pubkey) -> Result<()> {
+    ctx.accounts.mailer.root_publisher = publisher;
+
+    emit!(RootPublisherUpdated { publisher });
+
+    Ok(())
+}
+
+pub fn publish_message_root(
+    ctx: Context<PublishMessageRoot>,
+    day: u64,
+    root: [u8; 32],
+    message_count: u64,
+) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    require!(day < current_day(now), MessageRootError::DayNotFinished);
+
+    let message_root = &mut ctx.accounts.message_root;
+    message_root.day = day;
+    message_root.root = root;
+    message_root.message_count = message_count;
+    message_root.published_at = now;
+    message_root.bump = ctx.bumps.message_root;
+
+    emit!(MessageRootPublished {
+        day,
+        root,
+        message_count,
+        publisher: ctx.accounts.publisher.key(),
+    });
+
+    Ok(())
+}
+
+pub fn verify_inclusion(
+    ctx: Context<VerifyInclusion>,
+    message_hash: [u8; 32],
+    proof: Vec<[u8; 32]>,
+) -> Result<()> {
+    require!(
+        proof.len() <= MAX_ROOT_PROOF_LEN,
+        MessageRootError::InvalidInclusionProof
+    );
+    let message_root = &ctx.accounts.message_root;
+    require!(
+        proof_root(message_hash, &proof) == message_root.root,
+        MessageRootError::InvalidInclusionProof
+    );
+
+    emit!(InclusionVerified {
+        day: message_root.day,
+        message_hash,
+    });
+
+    Ok(())
+}
+
+/// Day number of `timestamp`: whole days since the Unix epoch
+pub fn current_day(timestamp: i64) -> u64 {
+    timestamp.div_euclid(ROOT_DAY) as u64
+}
+
+pub fn root_leaf(message_hash: [u8; 32]) -> [u8; 32] {
+    hashv(&[ROOT_LEAF_PREFIX, &message_hash]).to_bytes()
+}
+
+pub fn root_node(a: [u8; 32], b: [u8; 32]) -> [u8; 32] {
+    let (left, right) = if a <= b { (a, b) } else { (b, a) };
+    hashv(&[ROOT_NODE_PREFIX, &left, &right]).to_bytes()
+}
+
+/// Root reached by folding `proof` into the leaf of `message_hash`
+pub fn proof_root(message_hash: [u8; 32], proof: &[[u8; 32]]) -> [u8; 32] {
+    proof.iter().fold(root_leaf(message_hash), |node, sibling| {
+        root_node(node, *sibling)
+    })
+}
+
+#[derive(Accounts)]
+#[instruction(day: u64)]
+pub struct PublishMessageRoot<'info> {
+    /// Fails if the day's root is already published
+    #[account(
+        init,
+        payer = publisher,
+        space = 8 + MessageRoot::INIT_SPACE,
+        seeds = [b"root", day.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub message_root: Account<'info, MessageRoot>,
+
+    #[account(
+        seeds = [seeds::MAILER],
+        bump = mailer.bump,
+        constraint = mailer.can_publish_roots(&publisher.key()) @ MessageRootError::NotRootPublisher
+    )]
+    pub mailer: Account<'info, MailerState>,
+
+    #[account(mut)]
+    pub publisher: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct VerifyInclusion<'info> {
+    #[account(
+        seeds = [b"root", message_root.day.to_le_bytes().as_ref()],
+        bump = message_root.bump
+    )]
+    pub message_root: Account<'info, MessageRoot>,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct MessageRoot {
+    /// Day the root covers, per `current_day`
+    pub day: u64,
+    pub root: [u8; 32],
+    /// Messages the root covers
+    pub message_count: u64,
+    pub published_at: i64,
+    pub bump: u8,
+}
+
+#[event]
+pub struct RootPublisherUpdated {
+    pub publisher: Pubkey,
+}
+
+#[event]
+pub struct MessageRootPublished {
+    pub day: u64,
+    pub root: [u8; 32],
+    pub message_count: u64,
+    pub publisher: Pubkey,
+}
+
+#[event]
+pub struct InclusionVerified {
+    pub day: u64,
+    pub message_hash: [u8; 32],
+}
+
+#[error_code(offset = 7700)]
+pub enum MessageRootError {
+    #[msg("Only the owner or the root publisher can publish message roots")]
+    NotRootPublisher,
+    #[msg("Roots can only be published for days that have ended")]
+    DayNotFinished,
+    #[msg("Proof does not lead from the message hash to the day's root")]
+    InvalidInclusionProof,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn proofs_lead_to_the_root_of_their_leaves() {
+        let hashes = [[1u8; 32], [2; 32], [3; 32]];
+        let leaves = hashes.map(root_leaf);
+        // An odd node is carried up unpaired
+        let root = root_node(root_node(leaves[0], leaves[1]), leaves[2]);
+
+        assert_eq!(proof_root(hashes[0], &[leaves[1], leaves[2]]), root);
+        assert_eq!(
+            proof_root(hashes[2], &[root_node(leaves[0], leaves[1])]),
+            root
+        );
+        assert_ne!(proof_root([4; 32], &[leaves[1], leaves[2]]), root);
+        // A node is not accepted as a message hash
+        assert_ne!(
+            proof_root(root_node(leaves[0], leaves[1]), &[leaves[2]]),
+            root
+        );
+    }
+
+    #[test]
+    fn days_start_at_utc_midnight() {
+        assert_eq!(current_day(0), 0);
+        assert_eq!(current_day(ROOT_DAY - 1), 0);
+        assert_eq!(current_day(ROOT_DAY), 1);
+    }
+}
@@ -0,0 +1,2141 @@
+//! Message sending and recipient delivery preferences.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::hash::{hash, hashv};
+use anchor_lang::solana_program::sysvar::instructions::{self as sysvar_instructions, get_instruction_relative};
+use anchor_lang::system_program::{self, Transfer};
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::metadata::MetadataAccount;
+use anchor_spl::token::spl_token::native_mint;
+use anchor_spl::token_interface::{self, Mint, SyncNative, TokenAccount, TokenInterface};
+use mail_service::{Delegation, PERMISSION_SEND};
+use mailbox_common::seeds;
+use pyth_solana_receiver_sdk::price_update::PriceUpdateV2;
+
+use crate::admin::{MailerError, MailerState, MailerStats};
+use crate::campaigns::{boost_shares, Campaign};
+use crate::claims::{ClaimMode, RecipientClaim};
+use crate::compliance::ComplianceError;
+use crate::credits::Credit;
+use crate::deposits::{self, DepositError, SpamDeposit};
+use crate::expiry;
+use crate::inbox;
+use crate::loyalty::{earn_points, SenderStats};
+use crate::message_log::{log_message, message_content_hash, MessageLog};
+use crate::nft_perks::{discounted_fee, holder_discount_bps};
+use crate::receipts::{mint_receipt, ReceiptConfig, SPL_ACCOUNT_COMPRESSION_ID, SPL_NOOP_ID};
+use crate::referrals::{pay_referral, ReferralCode};
+use crate::fees::{
+    charge_allowance, charge_sender, credit_claim, record_shares, standard_fee, transfer_from_sender,
+    DelegatorBilling, FeeError, FeeExempt, FeeExemptSend, FeeSource, OwnerBucket, SetFee, Tip, TipPaid, TipTarget,
+};
+use crate::staking::{staker_discount_bps, Stake};
+use crate::subscriptions::{covers_standard_sends, PlanSubscription};
+use crate::vesting::vest_shares;
+use crate::tokens::{claim_mint, current_send_fee, TokenConfig, TokenError};
+
+/// Maximum number of CPI origin programs a recipient can list
+pub const MAX_ORIGIN_PROGRAMS: usize = 16;
+
+/// Maximum number of programs on the owner's CPI caller allowlist
+pub const MAX_CALLER_PROGRAMS: usize = 32;
+
+/// Maximum mail IDs a single prepared batch may pay for
+pub const MAX_PREPARED_BATCH: u16 = 512;
+
+/// Maximum compressed payload size; keeps a batch within one transaction
+pub const MAX_BATCH_PAYLOAD: usize = 900;
+
+/// zstd frame magic number as it appears on the wire
+pub const ZSTD_MAGIC: [u8; 4] = [0x28, 0xB5, 0x2F, 0xFD];
+
+/// Maximum length of a recipient address on another chain
+pub const MAX_EXTERNAL_ADDRESS_LEN: usize = 64;
+
+/// Domain separator mixed into every message ID
+pub const MESSAGE_ID_DOMAIN: &[u8] = b"mailer-message";
+
+/// Bodies longer than this many bytes are emitted as a hash and length only
+pub const BODY_HASH_THRESHOLD: usize = 512;
+
+/// Emit a mail-sent event by self-CPI when built with `event-cpi`, or to the
+/// program log otherwise
+///
+/// `emit_cpi!` reads the `#[event_cpi]` accounts through a binding named
+/// `ctx`, so the instruction's context is passed in explicitly.
+macro_rules! emit_send_event {
+    ($ctx:ident, $event:expr) => {{
+        #[cfg(feature = "event-cpi")]
+        {
+            let ctx = &$ctx;
+            emit_cpi!($event);
+        }
+        #[cfg(not(feature = "event-cpi"))]
+        emit!($event);
+    }};
+}
+pub(crate) use emit_send_event;
+
+/// Maximum size of a sealed-sender envelope, in bytes
+pub const MAX_SEALED_ENVELOPE_LEN: usize = 256;
+
+/// Size of a read-state bitmap; tracks the read flag of this many message sequence slots
+pub const READ_STATE_BITS: u64 = 1024;
+
+/// Length of a sender's rate limit window: one day in seconds
+pub const RATE_LIMIT_WINDOW: i64 = 24 * 60 * 60;
+
+/// Initial cap on a message subject, in bytes
+pub const DEFAULT_MAX_SUBJECT_LEN: u32 = 200;
+
+/// Initial cap on a message body, in bytes
+pub const DEFAULT_MAX_BODY_LEN: u32 = 800;
+
+pub fn send_priority(
+    ctx: Context<SendMessage>,
+    subject: String,
+    body: String,
+    tip: Option<Tip>,
+    to_external: Option<ExternalAddress>,
+    client_ref: Option<[u8; 16]>,
+) -> Result<[u8; 32]> {
+    priority_send(ctx, subject, body, tip, to_external, client_ref, false)
+}
+
+pub fn send_priority_vested(
+    ctx: Context<SendMessage>,
+    subject: String,
+    body: String,
+    client_ref: Option<[u8; 16]>,
+) -> Result<[u8; 32]> {
+    priority_send(ctx, subject, body, None, None, client_ref, true)
+}
+
+/// Priority send shared by the plain and vested variants; `vested` locks the
+/// sender's bonus shares instead of applying any campaign boost
+fn priority_send(
+    ctx: Context<SendMessage>,
+    subject: String,
+    body: String,
+    tip: Option<Tip>,
+    to_external: Option<ExternalAddress>,
+    client_ref: Option<[u8; 16]>,
+    vested: bool,
+) -> Result<[u8; 32]> {
+    let sender = ctx.accounts.sender.key();
+    validate_external_address(&to_external)?;
+    check_message_size(&ctx.accounts.mailer, &subject, &body)?;
+    check_message_origin(
+        &ctx.accounts.caller_policy,
+        &ctx.accounts.recipient_preferences,
+        &ctx.accounts.instructions,
+    )?;
+    count_sends(ctx.accounts, 1, true)?;
+
+    // Charge full send fee, drawing down send credit first
+    let send_fee = ctx.accounts.send_fee()?;
+    let charge = charge_sender(ctx.accounts, send_fee)?;
+
+    // Record shares for revenue sharing on the amount actually received
+    let started = record_shares(
+        &mut ctx.accounts.recipient_claim,
+        &mut ctx.accounts.owner_bucket,
+        &ctx.accounts.mailer,
+        sender,
+        ctx.accounts.payer.key(),
+        charge.net_of(send_fee)?,
+    )?;
+    if started {
+        track_claim(ctx.accounts)?;
+    }
+    let referred = pay_referral(ctx.accounts, charge.net_of(send_fee)?, true)?;
+    if vested {
+        vest_shares(ctx.accounts, charge.net_of(send_fee)?, referred)?;
+    } else {
+        boost_shares(ctx.accounts, charge.net_of(send_fee)?, referred)?;
+    }
+
+    let message_seq = record_sends(ctx.accounts, 1, true, charge.net_of(send_fee)?)?;
+    let clock = Clock::get()?;
+    let message_id = message_id(&sender, message_seq, clock.slot);
+    record_delivery(ctx.accounts, sender, sender, message_seq, 1)?;
+    log_message(
+        ctx.accounts,
+        message_id,
+        sender,
+        sender,
+        message_content_hash(&subject, &body),
+    )?;
+    pay_tip(ctx.accounts, tip)?;
+    mint_receipt(ctx.accounts, message_id, message_seq, clock.unix_timestamp)?;
+
+    let (body, body_hash, body_len) = emitted_body(body);
+    let event = PriorityMailSent {
+        from: sender,
+        to: sender, // Messages are sent to self
+        message_seq,
+        message_id,
+        timestamp: clock.unix_timestamp,
+        client_ref,
+        to_external,
+        subject,
+        body,
+        body_hash,
+        body_len,
+    };
+    emit_send_event!(ctx, event);
+
+    Ok(message_id)
+}
+
+pub fn send_priority_prepared(
+    ctx: Context<SendMessage>,
+    mail_id: String,
+    tip: Option<Tip>,
+    to_external: Option<ExternalAddress>,
+    client_ref: Option<[u8; 16]>,
+) -> Result<[u8; 32]> {
+    let sender = ctx.accounts.sender.key();
+    validate_external_address(&to_external)?;
+    check_message_origin(
+        &ctx.accounts.caller_policy,
+        &ctx.accounts.recipient_preferences,
+        &ctx.accounts.instructions,
+    )?;
+    count_sends(ctx.accounts, 1, true)?;
+
+    // Charge full send fee, drawing down send credit first
+    let send_fee = ctx.accounts.send_fee()?;
+    let charge = charge_sender(ctx.accounts, send_fee)?;
+
+    // Record shares for revenue sharing on the amount actually received
+    let started = record_shares(
+        &mut ctx.accounts.recipient_claim,
+        &mut ctx.accounts.owner_bucket,
+        &ctx.accounts.mailer,
+        sender,
+        ctx.accounts.payer.key(),
+        charge.net_of(send_fee)?,
+    )?;
+    if started {
+        track_claim(ctx.accounts)?;
+    }
+    let referred = pay_referral(ctx.accounts, charge.net_of(send_fee)?, true)?;
+    boost_shares(ctx.accounts, charge.net_of(send_fee)?, referred)?;
+
+    let message_seq = record_sends(ctx.accounts, 1, true, charge.net_of(send_fee)?)?;
+    let clock = Clock::get()?;
+    let message_id = message_id(&sender, message_seq, clock.slot);
+    record_mail_id(ctx.accounts, &mail_id, message_id)?;
+    record_delivery(ctx.accounts, sender, sender, message_seq, 1)?;
+    log_message(
+        ctx.accounts,
+        message_id,
+        sender,
+        sender,
+        mail_id_hash(&mail_id),
+    )?;
+    pay_tip(ctx.accounts, tip)?;
+    mint_receipt(ctx.accounts, message_id, message_seq, clock.unix_timestamp)?;
+
+    let event = PriorityPreparedMailSent {
+        from: sender,
+        to: sender, // Messages are sent to self
+        message_seq,
+        message_id,
+        timestamp: clock.unix_timestamp,
+        client_ref,
+        to_external,
+        mail_id,
+    };
+    emit_send_event!(ctx, event);
+
+    Ok(message_id)
+}
+
+pub fn send(
+    ctx: Context<SendMessage>,
+    subject: String,
+    body: String,
+    tip: Option<Tip>,
+    to_external: Option<ExternalAddress>,
+    client_ref: Option<[u8; 16]>,
+) -> Result<[u8; 32]> {
+    let sender = ctx.accounts.sender.key();
+    validate_external_address(&to_external)?;
+    check_message_size(&ctx.accounts.mailer, &subject, &body)?;
+    check_message_origin(
+        &ctx.accounts.caller_policy,
+        &ctx.accounts.recipient_preferences,
+        &ctx.accounts.instructions,
+    )?;
+    count_sends(ctx.accounts, 1, false)?;
+    let owner_fee = ctx.accounts.standard_send_fee(1)?;
+
+    // Charge only owner fee (10%), drawing down send credit first
+    let charge = charge_sender(ctx.accounts, owner_fee)?;
+
+    // Only add to owner claimable, no revenue sharing
+    ctx.accounts.owner_bucket.credit(charge.net_of(owner_fee)?)?;
+    pay_referral(ctx.accounts, charge.net_of(owner_fee)?, false)?;
+
+    let message_seq = record_sends(ctx.accounts, 1, false, charge.net_of(owner_fee)?)?;
+    let clock = Clock::get()?;
+    let message_id = message_id(&sender, message_seq, clock.slot);
+    record_delivery(ctx.accounts, sender, sender, message_seq, 1)?;
+    log_message(
+        ctx.accounts,
+        message_id,
+        sender,
+        sender,
+        message_content_hash(&subject, &body),
+    )?;
+    pay_tip(ctx.accounts, tip)?;
+
+    let (body, body_hash, body_len) = emitted_body(body);
+    let event = StandardMailSent {
+        from: sender,
+        to: sender, // Messages are sent to self
+        message_seq,
+        message_id,
+        timestamp: clock.unix_timestamp,
+        client_ref,
+        to_external,
+        subject,
+        body,
+        body_hash,
+        body_len,
+    };
+    emit_send_event!(ctx, event);
+
+    Ok(message_id)
+}
+
+pub fn send_prepared(
+    ctx: Context<SendMessage>,
+    mail_id: String,
+    tip: Option<Tip>,
+    to_external: Option<ExternalAddress>,
+    client_ref: Option<[u8; 16]>,
+) -> Result<[u8; 32]> {
+    let sender = ctx.accounts.sender.key();
+    validate_external_address(&to_external)?;
+    check_message_origin(
+        &ctx.accounts.caller_policy,
+        &ctx.accounts.recipient_preferences,
+        &ctx.accounts.instructions,
+    )?;
+    count_sends(ctx.accounts, 1, false)?;
+    let owner_fee = ctx.accounts.standard_send_fee(1)?;
+
+    // Charge only owner fee (10%), drawing down send credit first
+    let charge = charge_sender(ctx.accounts, owner_fee)?;
+
+    // Only add to owner claimable, no revenue sharing
+    ctx.accounts.owner_bucket.credit(charge.net_of(owner_fee)?)?;
+    pay_referral(ctx.accounts, charge.net_of(owner_fee)?, false)?;
+
+    let message_seq = record_sends(ctx.accounts, 1, false, charge.net_of(owner_fee)?)?;
+    let clock = Clock::get()?;
+    let message_id = message_id(&sender, message_seq, clock.slot);
+    record_mail_id(ctx.accounts, &mail_id, message_id)?;
+    record_delivery(ctx.accounts, sender, sender, message_seq, 1)?;
+    log_message(
+        ctx.accounts,
+        message_id,
+        sender,
+        sender,
+        mail_id_hash(&mail_id),
+    )?;
+    pay_tip(ctx.accounts, tip)?;
+
+    let event = StandardPreparedMailSent {
+        from: sender,
+        to: sender, // Messages are sent to self
+        message_seq,
+        message_id,
+        timestamp: clock.unix_timestamp,
+        client_ref,
+        to_external,
+        mail_id,
+    };
+    emit_send_event!(ctx, event);
+
+    Ok(message_id)
+}
+
+pub fn send_prepared_batch(
+    ctx: Context<SendMessage>,
+    count: u16,
+    ids_hash: [u8; 32],
+    payload: Vec<u8>,
+    priority: bool,
+    client_ref: Option<[u8; 16]>,
+) -> Result<()> {
+    let sender = ctx.accounts.sender.key();
+    check_message_origin(
+        &ctx.accounts.caller_policy,
+        &ctx.accounts.recipient_preferences,
+        &ctx.accounts.instructions,
+    )?;
+    require!(is_valid_batch(count, &payload), MessagingError::InvalidBatch);
+    count_sends(ctx.accounts, count as u64, priority)?;
+
+    // The payload is not decompressed on-chain; indexers check it against `ids_hash`
+    let send_fee = ctx.accounts.send_fee()?;
+    let unit_fee = if priority {
+        send_fee
+    } else {
+        ctx.accounts.standard_send_fee(count as u64)?
+    };
+    let fee = unit_fee
+        .checked_mul(count as u64)
+        .ok_or(FeeError::MathOverflow)?;
+
+    // Charge every message in the batch at once, drawing down send credit first
+    let charge = charge_sender(ctx.accounts, fee)?;
+
+    if priority {
+        let started = record_shares(
+            &mut ctx.accounts.recipient_claim,
+            &mut ctx.accounts.owner_bucket,
+            &ctx.accounts.mailer,
+            sender,
+            ctx.accounts.payer.key(),
+            charge.net_of(fee)?,
+        )?;
+        if started {
+            track_claim(ctx.accounts)?;
+        }
+    } else {
+        ctx.accounts.owner_bucket.credit(charge.net_of(fee)?)?;
+    }
+    let referred = pay_referral(ctx.accounts, charge.net_of(fee)?, priority)?;
+    if priority {
+        boost_shares(ctx.accounts, charge.net_of(fee)?, referred)?;
+    }
+    let message_seq = record_sends(ctx.accounts, count as u64, priority, charge.net_of(fee)?)?;
+    let clock = Clock::get()?;
+    record_delivery(
+        ctx.accounts,
+        sender,
+        sender,
+        message_seq + count as u64 - 1,
+        count as u64,
+    )?;
+
+    let event = PreparedBatchSent {
+        from: sender,
+        to: sender, // Messages are sent to self
+        message_seq,
+        message_id: message_id(&sender, message_seq, clock.slot),
+        slot: clock.slot,
+        timestamp: clock.unix_timestamp,
+        client_ref,
+        count,
+        priority,
+        ids_hash,
+        payload,
+    };
+    emit_send_event!(ctx, event);
+
+    Ok(())
+}
+
+/// Deterministic ID of the `message_seq`-th message, sent by `sender` in `slot`
+///
+/// Emitted with every send so the client, the indexer and prepared mail
+/// references all agree on one ID instead of inventing their own.
+pub fn message_id(sender: &Pubkey, message_seq: u64, slot: u64) -> [u8; 32] {
+    hashv(&[
+        MESSAGE_ID_DOMAIN,
+        sender.as_ref(),
+        &message_seq.to_le_bytes(),
+        &slot.to_le_bytes(),
+    ])
+    .to_bytes()
+}
+
+/// Whether a compressed batch payload is acceptable for `count` mail IDs
+pub fn is_valid_batch(count: u16, payload: &[u8]) -> bool {
+    count > 0
+        && count <= MAX_PREPARED_BATCH
+        && payload.len() <= MAX_BATCH_PAYLOAD
+        && payload.starts_with(&ZSTD_MAGIC)
+}
+
+pub fn send_using_claim(
+    ctx: Context<SendMessage>,
+    subject: String,
+    body: String,
+    priority: bool,
+    client_ref: Option<[u8; 16]>,
+) -> Result<[u8; 32]> {
+    let sender = ctx.accounts.sender.key();
+    check_message_size(&ctx.accounts.mailer, &subject, &body)?;
+    check_message_origin(
+        &ctx.accounts.caller_policy,
+        &ctx.accounts.recipient_preferences,
+        &ctx.accounts.instructions,
+    )?;
+    count_sends(ctx.accounts, 1, priority)?;
+    let send_fee = ctx.accounts.send_fee()?;
+    let fee = if priority {
+        send_fee
+    } else {
+        ctx.accounts.standard_send_fee(1)?
+    };
+
+    // Send credit is drawn first, then the claim covers what it can.
+    // Only the available part of a claim belongs to the sender: expired balances
+    // belong to the owner and a streaming claim's locked balance is not yet theirs
+    let same_mint =
+        claim_mint(&ctx.accounts.recipient_claim, &ctx.accounts.mailer) == ctx.accounts.mint.key();
+    let claim = &mut ctx.accounts.recipient_claim;
+    let credit_covered = if same_mint { claim.send_credit.min(fee) } else { 0 };
+    let clock = Clock::get()?;
+    let netted = if same_mint && claim.amount > 0 {
+        claim
+            .available(clock.unix_timestamp)?
+            .min(fee.checked_sub(credit_covered).ok_or(FeeError::MathOverflow)?)
+    } else {
+        0
+    };
+    if netted > 0 {
+        claim.withdraw(netted)?;
+    }
+
+    // Credit and transfer cover whatever the claim did not
+    let charge = charge_sender(
+        ctx.accounts,
+        fee.checked_sub(netted).ok_or(FeeError::MathOverflow)?,
+    )?;
+
+    emit!(ClaimNetted {
+        sender,
+        netted,
+        transferred: charge.transferred,
+    });
+    let message_seq = record_sends(ctx.accounts, 1, priority, charge.net_of(fee)?)?;
+    let message_id = message_id(&sender, message_seq, clock.slot);
+    record_delivery(ctx.accounts, sender, sender, message_seq, 1)?;
+    log_message(
+        ctx.accounts,
+        message_id,
+        sender,
+        sender,
+        message_content_hash(&subject, &body),
+    )?;
+
+    if priority {
+        let started = record_shares(
+            &mut ctx.accounts.recipient_claim,
+            &mut ctx.accounts.owner_bucket,
+            &ctx.accounts.mailer,
+            sender,
+            ctx.accounts.payer.key(),
+            charge.net_of(send_fee)?,
+        )?;
+        if started {
+            track_claim(ctx.accounts)?;
+        }
+        let referred = pay_referral(ctx.accounts, charge.net_of(send_fee)?, true)?;
+        boost_shares(ctx.accounts, charge.net_of(send_fee)?, referred)?;
+        mint_receipt(ctx.accounts, message_id, message_seq, clock.unix_timestamp)?;
+
+        let (body, body_hash, body_len) = emitted_body(body);
+        let event = PriorityMailSent {
+            from: sender,
+            to: sender, // Messages are sent to self
+            message_seq,
+            message_id,
+            timestamp: clock.unix_timestamp,
+            client_ref,
+            to_external: None,
+            subject,
+            body,
+            body_hash,
+            body_len,
+        };
+        emit_send_event!(ctx, event);
+    } else {
+        ctx.accounts.owner_bucket.credit(charge.net_of(fee)?)?;
+        pay_referral(ctx.accounts, charge.net_of(fee)?, false)?;
+
+        let (body, body_hash, body_len) = emitted_body(body);
+        let event = StandardMailSent {
+            from: sender,
+            to: sender, // Messages are sent to self
+            message_seq,
+            message_id,
+            timestamp: clock.unix_timestamp,
+            client_ref,
+            to_external: None,
+            subject,
+            body,
+            body_hash,
+            body_len,
+        };
+        emit_send_event!(ctx, event);
+    }
+
+    Ok(message_id)
+}
+
+/// Send on behalf of a mail service delegator
+///
+/// The signing delegate pays the priority or standard fee, and any priority
+/// revenue share is recorded on the delegate's own claim. The message is
+/// delivered to and reported from the delegator, whose origin policy applies.
+pub fn send_as_delegator(
+    ctx: Context<SendAsDelegator>,
+    subject: String,
+    body: String,
+    priority: bool,
+    client_ref: Option<[u8; 16]>,
+) -> Result<[u8; 32]> {
+    let delegator = ctx.accounts.delegation.delegator;
+    let delegate = ctx.accounts.send.sender.key();
+    let clock = Clock::get()?;
+    let delegation = &ctx.accounts.delegation;
+    require!(
+        delegation.active_delegate(clock.unix_timestamp) == Some(delegate),
+        MessagingError::NotDelegate
+    );
+    require!(
+        delegation.allows(delegate, PERMISSION_SEND, clock.unix_timestamp),
+        MessagingError::DelegateCannotSend
+    );
+    check_message_size(&ctx.accounts.send.mailer, &subject, &body)?;
+    check_message_origin(
+        &ctx.accounts.send.caller_policy,
+        &ctx.accounts.delegator_preferences,
+        &ctx.accounts.send.instructions,
+    )?;
+    count_sends(&mut ctx.accounts.send, 1, priority)?;
+
+    let send_fee = ctx.accounts.send.send_fee()?;
+    let fee = if priority {
+        send_fee
+    } else {
+        ctx.accounts.send.standard_send_fee(1)?
+    };
+    // A delegator who opened a billing allowance for this delegation pays for
+    // its delegate's sends up to the limit; priority shares would go to the
+    // delegate, so those stay delegate-funded
+    let charge = match &ctx.accounts.delegator_usdc_account {
+        Some(delegator_usdc_account) => {
+            require!(!priority, MessagingError::DelegatorFundsStandardOnly);
+            ctx.accounts
+                .delegator_billing
+                .as_mut()
+                .ok_or(FeeError::BillingNotApproved)?
+                .spend(fee)?;
+            charge_allowance(&mut ctx.accounts.send, delegator_usdc_account, fee)?
+        }
+        None => charge_sender(&mut ctx.accounts.send, fee)?,
+    };
+
+    let message_seq = record_sends(&mut ctx.accounts.send, 1, priority, charge.net_of(fee)?)?;
+    let message_id = message_id(&delegator, message_seq, clock.slot);
+    record_delivery(&ctx.accounts.send, delegator, delegator, message_seq, 1)?;
+    log_message(
+        &mut ctx.accounts.send,
+        message_id,
+        delegator,
+        delegator,
+        message_content_hash(&subject, &body),
+    )?;
+    emit!(SentAsDelegate {
+        delegator,
+        delegate,
+        message_id,
+    });
+
+    let (body, body_hash, body_len) = emitted_body(body);
+    if priority {
+        let accounts = &mut ctx.accounts.send;
+        let started = record_shares(
+            &mut accounts.recipient_claim,
+            &mut accounts.owner_bucket,
+            &accounts.mailer,
+            delegate,
+            accounts.payer.key(),
+            charge.net_of(send_fee)?,
+        )?;
+        if started {
+            track_claim(accounts)?;
+        }
+        let referred = pay_referral(accounts, charge.net_of(send_fee)?, true)?;
+        boost_shares(accounts, charge.net_of(send_fee)?, referred)?;
+
+        let event = PriorityMailSent {
+            from: delegator,
+            to: delegator, // Delivered to the delegator's own mailbox
+            message_seq,
+            message_id,
+            timestamp: clock.unix_timestamp,
+            client_ref,
+            to_external: None,
+            subject,
+            body,
+            body_hash,
+            body_len,
+        };
+        emit_send_event!(ctx, event);
+    } else {
+        ctx.accounts.send.owner_bucket.credit(charge.net_of(fee)?)?;
+        pay_referral(&mut ctx.accounts.send, charge.net_of(fee)?, false)?;
+
+        let event = StandardMailSent {
+            from: delegator,
+            to: delegator, // Delivered to the delegator's own mailbox
+            message_seq,
+            message_id,
+            timestamp: clock.unix_timestamp,
+            client_ref,
+            to_external: None,
+            subject,
+            body,
+            body_hash,
+            body_len,
+        };
+        emit_send_event!(ctx, event);
+    }
+
+    Ok(message_id)
+}
+
+pub fn send_sealed(
+    ctx: Context<SendSealed>,
+    to: Pubkey,
+    mail_id: String,
+    envelope: Vec<u8>,
+) -> Result<[u8; 32]> {
+    require!(
+        !envelope.is_empty() && envelope.len() <= MAX_SEALED_ENVELOPE_LEN,
+        MessagingError::InvalidEnvelope
+    );
+    require!(ctx.accounts.block.data_is_empty(), MessagingError::SenderBlocked);
+    check_message_origin(
+        &ctx.accounts.send.caller_policy,
+        &ctx.accounts.recipient_preferences,
+        &ctx.accounts.send.instructions,
+    )?;
+    count_sends(&mut ctx.accounts.send, 1, false)?;
+
+    // Strangers lock the recipient's deposit on top of the fee; a fee exemption
+    // does not waive it, so it is sized from the quoted fee
+    let required = deposits::required_deposit(
+        &ctx.accounts.spam_policy,
+        &ctx.accounts.contact,
+        ctx.accounts.send.quoted_fee()?,
+    )?;
+    if required > 0 && to != ctx.accounts.send.sender.key() {
+        let deposit = ctx
+            .accounts
+            .deposit
+            .as_mut()
+            .ok_or(DepositError::DepositRequired)?;
+        let bump = ctx.bumps.deposit.unwrap();
+        deposits::lock(deposit, &mut ctx.accounts.send, to, required, bump)?;
+    }
+
+    // The signer is only a relayer or ephemeral key, so there is no one to return a
+    // revenue share to; sealed sends pay the standard fee
+    let accounts = &mut ctx.accounts.send;
+    let owner_fee = accounts.standard_send_fee(1)?;
+    let charge = charge_sender(accounts, owner_fee)?;
+    accounts.owner_bucket.credit(charge.net_of(owner_fee)?)?;
+    pay_referral(accounts, charge.net_of(owner_fee)?, false)?;
+
+    let from = accounts.sender.key();
+    let message_seq = record_sends(accounts, 1, false, charge.net_of(owner_fee)?)?;
+    let clock = Clock::get()?;
+    let message_id = message_id(&from, message_seq, clock.slot);
+    record_mail_id(accounts, &mail_id, message_id)?;
+    record_delivery(accounts, from, to, message_seq, 1)?;
+    log_message(accounts, message_id, from, to, hash(&envelope).to_bytes())?;
+
+    let event = SealedMailSent {
+        from,
+        to,
+        message_seq,
+        message_id,
+        timestamp: clock.unix_timestamp,
+        mail_id,
+        envelope,
+    };
+    emit_send_event!(ctx, event);
+
+    Ok(message_id)
+}
+
+pub fn mark_read(ctx: Context<MarkRead>, message_id: [u8; 32], message_seq: u64) -> Result<()> {
+    let reader = ctx.accounts.reader.key();
+    if let Some(read_state) = ctx.accounts.read_state.as_mut() {
+        if read_state.reader == Pubkey::default() {
+            read_state.reader = reader;
+            read_state.bump = ctx.bumps.read_state.unwrap();
+        }
+        read_state.mark(message_seq);
+    }
+
+    emit!(MailRead {
+        reader,
+        message_id,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+pub fn set_rate_limit(ctx: Context<SetFee>, max_messages_per_day: u32) -> Result<()> {
+    let mailer = &mut ctx.accounts.mailer;
+    let old_max_messages_per_day = mailer.max_messages_per_day;
+    mailer.max_messages_per_day = max_messages_per_day;
+
+    emit!(RateLimitUpdated {
+        old_max_messages_per_day,
+        new_max_messages_per_day: max_messages_per_day,
+    });
+
+    Ok(())
+}
+
+pub fn set_message_limits(
+    ctx: Context<SetFee>,
+    max_subject_len: u32,
+    max_body_len: u32,
+) -> Result<()> {
+    validate_message_limits(max_subject_len, max_body_len)?;
+
+    let mailer = &mut ctx.accounts.mailer;
+    let old_max_subject_len = mailer.max_subject_len;
+    let old_max_body_len = mailer.max_body_len;
+    mailer.max_subject_len = max_subject_len;
+    mailer.max_body_len = max_body_len;
+
+    emit!(MessageLimitsUpdated {
+        old_max_subject_len,
+        old_max_body_len,
+        new_max_subject_len: max_subject_len,
+        new_max_body_len: max_body_len,
+    });
+
+    Ok(())
+}
+
+/// Limits of zero would refuse every message with a subject or body
+pub fn validate_message_limits(max_subject_len: u32, max_body_len: u32) -> Result<()> {
+    require!(
+        max_subject_len > 0 && max_body_len > 0,
+        MessagingError::InvalidMessageLimits
+    );
+    Ok(())
+}
+
+/// Body as it goes into a send event, with its hash and length when it is left out
+///
+/// Bodies up to `BODY_HASH_THRESHOLD` bytes are emitted in full. Longer ones
+/// are replaced by their SHA-256 so the event stays well under the log limit;
+/// the full body is still in the instruction data to check the hash against.
+pub(crate) fn emitted_body(body: String) -> (String, Option<[u8; 32]>, u32) {
+    let body_len = body.len() as u32;
+    if body.len() <= BODY_HASH_THRESHOLD {
+        (body, None, body_len)
+    } else {
+        (String::new(), Some(hash(body.as_bytes()).to_bytes()), body_len)
+    }
+}
+
+/// Reject a subject or body longer than the owner's limits, in bytes
+pub(crate) fn check_message_size(mailer: &MailerState, subject: &str, body: &str) -> Result<()> {
+    require!(
+        subject.len() <= mailer.max_subject_len as usize
+            && body.len() <= mailer.max_body_len as usize,
+        MessagingError::MessageTooLarge
+    );
+    Ok(())
+}
+
+pub fn wrap_sol_fee(ctx: Context<WrapSolFee>, priority: bool) -> Result<()> {
+    let send_fee = current_send_fee(
+        &ctx.accounts.token_config,
+        &ctx.accounts.mailer,
+        ctx.accounts.price_update.as_deref(),
+    )?;
+    let fee = if priority {
+        send_fee
+    } else {
+        standard_fee(send_fee)?
+    };
+
+    // Only top up what the account is missing; leftovers return when it is closed
+    let amount = fee.saturating_sub(ctx.accounts.sender_wsol_account.amount);
+    if amount > 0 {
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.sender.to_account_info(),
+                    to: ctx.accounts.sender_wsol_account.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+        token_interface::sync_native(CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            SyncNative {
+                account: ctx.accounts.sender_wsol_account.to_account_info(),
+            },
+        ))?;
+    }
+
+    emit!(SolWrapped {
+        sender: ctx.accounts.sender.key(),
+        amount,
+        fee,
+    });
+
+    Ok(())
+}
+
+pub fn set_origin_policy(
+    ctx: Context<SetOriginPolicy>,
+    origin_programs: Vec<Pubkey>,
+    allowlist: bool,
+) -> Result<()> {
+    require!(
+        origin_programs.len() <= MAX_ORIGIN_PROGRAMS,
+        MessagingError::TooManyOriginPrograms
+    );
+
+    let preferences = &mut ctx.accounts.recipient_preferences;
+    preferences.recipient = ctx.accounts.recipient.key();
+    preferences.origin_allowlist = allowlist;
+    preferences.origin_programs = origin_programs.clone();
+    preferences.bump = ctx.bumps.recipient_preferences;
+
+    emit!(OriginPolicyUpdated {
+        recipient: preferences.recipient,
+        origin_programs,
+        allowlist,
+    });
+
+    Ok(())
+}
+
+pub fn block_sender(ctx: Context<BlockSender>, sender: Pubkey) -> Result<()> {
+    let block = &mut ctx.accounts.block;
+    block.recipient = ctx.accounts.recipient.key();
+    block.sender = sender;
+    block.blocked_at = Clock::get()?.unix_timestamp;
+    block.bump = ctx.bumps.block;
+
+    emit!(SenderBlocked {
+        recipient: block.recipient,
+        sender,
+    });
+
+    Ok(())
+}
+
+pub fn unblock_sender(ctx: Context<UnblockSender>) -> Result<()> {
+    emit!(SenderUnblocked {
+        recipient: ctx.accounts.recipient.key(),
+        sender: ctx.accounts.block.sender,
+    });
+
+    Ok(())
+}
+
+/// Replace the owner's CPI caller policy with `programs`, or accept every caller with `allow_all`
+pub fn set_caller_policy(
+    ctx: Context<SetCallerPolicy>,
+    programs: Vec<Pubkey>,
+    allow_all: bool,
+) -> Result<()> {
+    require!(
+        programs.len() <= MAX_CALLER_PROGRAMS,
+        MessagingError::TooManyCallerPrograms
+    );
+
+    let policy = &mut ctx.accounts.caller_policy;
+    policy.allow_all = allow_all;
+    policy.programs = programs.clone();
+    policy.bump = ctx.bumps.caller_policy;
+
+    emit!(CallerPolicyUpdated { programs, allow_all });
+
+    Ok(())
+}
+
+/// Whether a CPI from `program_id` is accepted under the owner's caller policy
+pub fn caller_allowed(programs: &[Pubkey], allow_all: bool, program_id: &Pubkey) -> bool {
+    allow_all || programs.contains(program_id)
+}
+
+/// Whether a CPI from `program_id` is accepted under the given origin policy
+pub fn origin_allowed(origin_programs: &[Pubkey], allowlist: bool, program_id: &Pubkey) -> bool {
+    origin_programs.contains(program_id) == allowlist
+}
+
+impl SendMessage<'_> {
+    /// Priority send fee in the paying mint, quoted from the oracle when priced
+    pub(crate) fn quoted_fee(&self) -> Result<u64> {
+        current_send_fee(&self.token_config, &self.mailer, self.price_update.as_deref())
+    }
+
+    /// Priority fee this send is charged: the quoted fee less the larger of
+    /// any NFT holder and stake discounts, or zero for a fee-exempt sender
+    pub(crate) fn send_fee(&self) -> Result<u64> {
+        if self.fee_exempt.is_some() {
+            return Ok(0);
+        }
+        let discount_bps = holder_discount_bps(self)?.max(staker_discount_bps(self));
+        discounted_fee(self.quoted_fee()?, discount_bps)
+    }
+
+    /// Owner fee charged for each of `messages` standard sends: zero when the
+    /// sender's subscription covers them all, otherwise the standard share of
+    /// `send_fee`
+    pub(crate) fn standard_send_fee(&mut self, messages: u64) -> Result<u64> {
+        if self.fee_exempt.is_none() && covers_standard_sends(self, messages)? {
+            return Ok(0);
+        }
+        standard_fee(self.send_fee()?)
+    }
+}
+
+/// Collect an optional tip on top of the fee and credit it to its target
+fn pay_tip(accounts: &mut SendMessage, tip: Option<Tip>) -> Result<()> {
+    let Some(tip) = tip.filter(|tip| tip.amount > 0) else {
+        return Ok(());
+    };
+
+    // Tips are always transferred; send credit only covers fees
+    let received = transfer_from_sender(accounts, tip.amount)?;
+    let sender = accounts.sender.key();
+    let to = match tip.target {
+        TipTarget::Owner => {
+            accounts.owner_bucket.credit(received)?;
+            accounts.owner_bucket.destination
+        }
+        TipTarget::Recipient => {
+            let started = credit_claim(
+                &mut accounts.recipient_claim,
+                &accounts.mailer,
+                accounts.mint.key(),
+                sender, // Messages are sent to self
+                accounts.payer.key(),
+                received,
+            )?;
+            if started {
+                track_claim(accounts)?;
+            }
+            sender
+        }
+    };
+
+    emit!(TipPaid {
+        from: sender,
+        to,
+        mint: accounts.mint.key(),
+        amount: received,
+        target: tip.target,
+    });
+
+    Ok(())
+}
+
+/// List a claim whose period just started in its expiry bucket
+fn track_claim(accounts: &SendMessage) -> Result<()> {
+    // Streaming claims never expire, so keepers have nothing to sweep
+    if accounts.recipient_claim.mode == ClaimMode::Streaming {
+        return Ok(());
+    }
+    expiry::track(
+        &accounts.expiry_index,
+        &accounts.payer.to_account_info(),
+        &accounts.system_program.to_account_info(),
+        accounts.recipient_claim.recipient,
+        accounts.recipient_claim.timestamp,
+    )
+}
+
+/// Count a delivery in the recipient's inbox when the send passed it
+pub(crate) fn record_delivery(
+    accounts: &SendMessage,
+    from: Pubkey,
+    to: Pubkey,
+    last_message_seq: u64,
+    messages: u64,
+) -> Result<()> {
+    let Some(inbox) = &accounts.inbox else {
+        return Ok(());
+    };
+    inbox::record(
+        &inbox.to_account_info(),
+        &accounts.payer.to_account_info(),
+        &accounts.system_program.to_account_info(),
+        to,
+        from,
+        last_message_seq,
+        messages,
+    )
+}
+
+/// Count `messages` against the sender's daily rate limit, flagging them when
+/// the sender is fee exempt and otherwise crediting their loyalty points
+pub(crate) fn count_sends(accounts: &mut SendMessage, messages: u64, priority: bool) -> Result<()> {
+    let max_per_day = accounts.mailer.max_messages_per_day;
+    let sender = accounts.sender.key();
+    if accounts.fee_exempt.is_some() {
+        emit!(FeeExemptSend { sender, messages });
+    } else {
+        earn_points(accounts, messages, priority)?;
+    }
+    let rate_limit = &mut accounts.rate_limit;
+    rate_limit.sender = sender;
+    rate_limit.record(messages, max_per_day, Clock::get()?.unix_timestamp)
+}
+
+/// Add `messages` sends paying `fee` in total to the usage counters and to
+/// the fee total of the paying mint
+///
+/// Returns the sequence number assigned to the first of the messages.
+pub(crate) fn record_sends(
+    accounts: &mut SendMessage,
+    messages: u64,
+    priority: bool,
+    fee: u64,
+) -> Result<u64> {
+    accounts.owner_bucket.record_fees(fee)?;
+    accounts.stats.record_sends(messages, priority)
+}
+
+/// Key a prepared mail ID's replay record is derived from
+pub fn mail_id_hash(mail_id: &str) -> [u8; 32] {
+    hash(mail_id.as_bytes()).to_bytes()
+}
+
+/// Create the sender's `MailIdRecord` for `mail_id` when they opted in by passing one
+///
+/// The record can only be created once, so a second send of the same ID by
+/// the same sender fails. Records are per sender, so nobody can claim an ID
+/// another sender has yet to use.
+pub(crate) fn record_mail_id(accounts: &SendMessage, mail_id: &str, message_id: [u8; 32]) -> Result<()> {
+    let Some(record) = &accounts.mail_id_record else {
+        return Ok(());
+    };
+
+    let sender = accounts.sender.key();
+    let id_hash = mail_id_hash(mail_id);
+    let (expected, bump) =
+        Pubkey::find_program_address(&[b"mailid", sender.as_ref(), &id_hash], &crate::ID);
+    require_keys_eq!(expected, record.key(), MessagingError::InvalidMailIdRecord);
+    require!(record.data_is_empty(), MessagingError::DuplicateMailId);
+
+    let space = 8 + MailIdRecord::INIT_SPACE;
+    let seeds: &[&[u8]] = &[b"mailid", sender.as_ref(), &id_hash, &[bump]];
+    expiry::create_pda(
+        &record.to_account_info(),
+        &accounts.payer.to_account_info(),
+        &accounts.system_program.to_account_info(),
+        seeds,
+        space,
+    )?;
+
+    let entry = MailIdRecord {
+        sender,
+        message_id,
+        bump,
+    };
+    let mut data = record.try_borrow_mut_data()?;
+    let mut writer: &mut [u8] = &mut data[..];
+    entry.try_serialize(&mut writer)?;
+
+    Ok(())
+}
+
+/// Reject malformed recipient addresses on other chains
+pub(crate) fn validate_external_address(address: &Option<ExternalAddress>) -> Result<()> {
+    if let Some(address) = address {
+        require!(
+            address.chain_id != 0
+                && !address.address.is_empty()
+                && address.address.len() <= MAX_EXTERNAL_ADDRESS_LEN,
+            MessagingError::InvalidExternalAddress
+        );
+    }
+    Ok(())
+}
+
+/// Reject CPI-originated messages the recipient has opted out of
+///
+/// The preferences account is optional on-chain state: when it has not been
+/// created every origin is accepted.
+pub(crate) fn check_message_origin(
+    caller_policy: &UncheckedAccount,
+    recipient_preferences: &UncheckedAccount,
+    instructions: &UncheckedAccount,
+) -> Result<()> {
+    // The top-level instruction targets another program only when we are invoked via CPI
+    let current_ix = get_instruction_relative(0, &instructions.to_account_info())?;
+    if current_ix.program_id == crate::ID {
+        return Ok(());
+    }
+
+    // Until the owner sets a caller policy, any program may send via CPI
+    if let Some(policy) = load_policy::<CallerPolicy>(caller_policy)? {
+        require!(
+            caller_allowed(&policy.programs, policy.allow_all, &current_ix.program_id),
+            MessagingError::CallerProgramDenied
+        );
+    }
+
+    if let Some(preferences) = load_policy::<RecipientPreferences>(recipient_preferences)? {
+        require!(
+            origin_allowed(
+                &preferences.origin_programs,
+                preferences.origin_allowlist,
+                &current_ix.program_id,
+            ),
+            MessagingError::OriginProgramDenied
+        );
+    }
+
+    Ok(())
+}
+
+/// Deserialize a policy PDA, or `None` if it was never created
+pub(crate) fn load_policy<T: AccountDeserialize>(account: &UncheckedAccount) -> Result<Option<T>> {
+    if account.owner != &crate::ID || account.data_is_empty() {
+        return Ok(None);
+    }
+    let data = account.try_borrow_data()?;
+    Ok(Some(T::try_deserialize(&mut &data[..])?))
+}
+
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+pub struct SendMessage<'info> {
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + RecipientClaim::INIT_SPACE,
+        seeds = [b"claim", sender.key().as_ref()],
+        bump
+    )]
+    pub recipient_claim: Account<'info, RecipientClaim>,
+
+    #[account(seeds = [seeds::MAILER], bump = mailer.bump)]
+    pub mailer: Account<'info, MailerState>,
+
+    /// Usage counters; assigns the send its sequence number
+    #[account(mut, seeds = [b"mailer_stats"], bump = stats.bump)]
+    pub stats: Account<'info, MailerStats>,
+
+    /// Fee token configuration; the send is refused unless the mint is enabled
+    #[account(
+        seeds = [b"token", mint.key().as_ref()],
+        bump = token_config.bump,
+        constraint = token_config.enabled @ TokenError::TokenDisabled
+    )]
+    pub token_config: Account<'info, TokenConfig>,
+
+    /// Fresh Pyth price for `mint`; required only when `token_config` has a price feed
+    pub price_update: Option<Account<'info, PriceUpdateV2>>,
+
+    /// Owner share bucket for send fees in this mint
+    #[account(
+        mut,
+        seeds = [b"bucket", mint.key().as_ref(), FeeSource::Send.seed()],
+        bump = owner_bucket.bump
+    )]
+    pub owner_bucket: Account<'info, OwnerBucket>,
+
+    /// Mint the fee is paid in
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    pub sender: Signer<'info>,
+
+    /// Pays rent for any recipient-side PDAs created by this send. Usually the
+    /// sender, but may be a relayer so recipients never need SOL.
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = sender,
+        associated_token::token_program = token_program
+    )]
+    pub sender_usdc_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = mailer,
+        associated_token::token_program = token_program
+    )]
+    pub mailer_usdc_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// CHECK: Recipient's preferences PDA; may be uninitialized, parsed in `check_message_origin`
+    #[account(seeds = [b"prefs", sender.key().as_ref()], bump)]
+    pub recipient_preferences: UncheckedAccount<'info>,
+
+    /// CHECK: Owner's CPI caller policy PDA; may be uninitialized, parsed in `check_message_origin`
+    #[account(seeds = [b"callers"], bump)]
+    pub caller_policy: UncheckedAccount<'info>,
+
+    /// CHECK: Instructions sysvar, validated by address
+    #[account(address = sysvar_instructions::ID)]
+    pub instructions: UncheckedAccount<'info>,
+
+    /// CHECK: Expiry bucket for the current week; only written, and verified in
+    /// `expiry::track`, when this send starts a new claim period
+    #[account(mut)]
+    pub expiry_index: UncheckedAccount<'info>,
+
+    /// CHECK: `[b"mailid", sender, mail_id_hash(mail_id)]`; prepared sends create it to
+    /// reject the sender reusing the mail ID, verified in `record_mail_id`. Omit to skip replay protection
+    #[account(mut)]
+    pub mail_id_record: Option<UncheckedAccount<'info>>,
+
+    /// Sender's daily message counter, created on first send
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + RateLimit::INIT_SPACE,
+        seeds = [b"rate", sender.key().as_ref()],
+        bump
+    )]
+    pub rate_limit: Account<'info, RateLimit>,
+
+    /// CHECK: Sender's compliance denylist marker; must not exist
+    #[account(
+        seeds = [b"deny", sender.key().as_ref()],
+        bump,
+        constraint = denylist.data_is_empty() @ ComplianceError::AddressDenied
+    )]
+    pub denylist: UncheckedAccount<'info>,
+
+    /// Sender's fee exemption; when passed the send is charged no fee
+    #[account(seeds = [b"exempt", sender.key().as_ref()], bump = fee_exempt.bump)]
+    pub fee_exempt: Option<Account<'info, FeeExempt>>,
+
+    /// Referral code the send was made through; omit for sends without a referrer
+    pub referral: Option<Account<'info, ReferralCode>>,
+
+    /// Referrer's claim, credited with the referral share; required with `referral`
+    /// and verified in `referrals::pay_referral`
+    #[account(mut)]
+    pub referrer_claim: Option<Account<'info, RecipientClaim>>,
+
+    /// Campaign boosting the recipient share of priority sends; omit outside campaigns
+    #[account(mut)]
+    pub campaign: Option<Account<'info, Campaign>>,
+
+    /// Sender's loyalty points, created on first use; omit to send without earning points
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + SenderStats::INIT_SPACE,
+        seeds = [b"stats", sender.key().as_ref()],
+        bump
+    )]
+    pub sender_stats: Option<Account<'info, SenderStats>>,
+
+    /// Sender's token account holding an NFT of the perk collection; pass with
+    /// `nft_metadata` to be charged the holder discount
+    pub nft_token: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Metaplex metadata of `nft_token`'s mint, verified in `nft_perks::holder_discount_bps`
+    pub nft_metadata: Option<Account<'info, MetadataAccount>>,
+
+    /// Sender's stake; charges the fee less the discount of the tier it reaches
+    #[account(seeds = [b"stake", sender.key().as_ref()], bump = stake.bump)]
+    pub stake: Option<Account<'info, Stake>>,
+
+    /// Receipt configuration; pass with the other receipt accounts to mint a cNFT
+    /// receipt of a single priority send
+    #[account(seeds = [b"receipts"], bump = receipt_config.bump)]
+    pub receipt_config: Option<Account<'info, ReceiptConfig>>,
+
+    /// CHECK: Bubblegum tree config of the receipt tree, verified by Bubblegum
+    #[account(mut)]
+    pub receipt_tree_config: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: Receipt merkle tree; checked against `receipt_config` in `receipts::mint_receipt`
+    #[account(mut)]
+    pub receipt_tree: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: Bubblegum program
+    #[account(address = mpl_bubblegum::ID)]
+    pub bubblegum_program: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: SPL Noop program; needed for receipts and the message log
+    #[account(address = SPL_NOOP_ID)]
+    pub log_wrapper: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: SPL Account Compression program; needed for receipts and the message log
+    #[account(address = SPL_ACCOUNT_COMPRESSION_ID)]
+    pub compression_program: Option<UncheckedAccount<'info>>,
+
+    /// Message log; pass with its tree to append the message's leaf
+    #[account(mut, seeds = [b"log"], bump = message_log.bump)]
+    pub message_log: Option<Account<'info, MessageLog>>,
+
+    /// CHECK: Message log tree; checked against `message_log` in `message_log::log_message`
+    #[account(mut)]
+    pub message_log_tree: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: Recipient's `[b"inbox", recipient]` counters, created on first delivery and
+    /// verified in `inbox::record`. Omit to leave them untouched
+    #[account(mut)]
+    pub inbox: Option<UncheckedAccount<'info>>,
+
+    /// Sender's prepaid credit in this mint; fees are drawn from it before tokens are transferred
+    #[account(
+        mut,
+        seeds = [b"credit", sender.key().as_ref(), mint.key().as_ref()],
+        bump = credit.bump
+    )]
+    pub credit: Option<Account<'info, Credit>>,
+
+    /// Sender's subscription; while active it waives the fee of standard sends
+    #[account(
+        mut,
+        seeds = [b"plan_subscription", sender.key().as_ref()],
+        bump = plan_subscription.bump
+    )]
+    pub plan_subscription: Option<Account<'info, PlanSubscription>>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+pub struct SendAsDelegator<'info> {
+    /// Send accounts for the signing delegate, who pays the fee unless the delegator does
+    pub send: SendMessage<'info>,
+
+    /// Any of the delegator's mail service delegations, its own or a per-delegate one; the
+    /// handler checks it names the signer as an unexpired delegate with send permission
+    #[account(owner = mail_service::ID)]
+    pub delegation: Account<'info, Delegation>,
+
+    /// CHECK: Delegator's preferences PDA; may be uninitialized, parsed in `check_message_origin`
+    #[account(seeds = [b"prefs", delegation.delegator.as_ref()], bump)]
+    pub delegator_preferences: UncheckedAccount<'info>,
+
+    /// Delegator's token account in the send's mint; when passed, a standard fee is
+    /// pulled from it through the delegator's approval of the mailer PDA
+    #[account(
+        mut,
+        token::mint = send.mint,
+        token::authority = delegation.delegator,
+        token::token_program = send.token_program
+    )]
+    pub delegator_usdc_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    /// The delegator's billing allowance for this delegation; required with
+    /// `delegator_usdc_account`, and charged every fee billed to the delegator
+    #[account(
+        mut,
+        seeds = [b"billing", delegation.key().as_ref()],
+        bump = delegator_billing.bump,
+        has_one = delegation,
+        constraint = delegator_billing.mint == send.mint.key() @ TokenError::ClaimMintMismatch
+    )]
+    pub delegator_billing: Option<Account<'info, DelegatorBilling>>,
+}
+
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+#[instruction(to: Pubkey)]
+pub struct SendSealed<'info> {
+    /// Send accounts for the relayer or ephemeral key, which signs and pays the fee
+    pub send: SendMessage<'info>,
+
+    /// CHECK: Recipient's preferences PDA; may be uninitialized, parsed in `check_message_origin`
+    #[account(seeds = [b"prefs", to.as_ref()], bump)]
+    pub recipient_preferences: UncheckedAccount<'info>,
+
+    /// CHECK: Recipient's block on the signer; must not exist, checked in `send_sealed`
+    #[account(seeds = [b"block", to.as_ref(), send.sender.key().as_ref()], bump)]
+    pub block: UncheckedAccount<'info>,
+
+    /// CHECK: Recipient's compliance denylist marker; must not exist
+    #[account(
+        seeds = [b"deny", to.as_ref()],
+        bump,
+        constraint = recipient_denylist.data_is_empty() @ ComplianceError::AddressDenied
+    )]
+    pub recipient_denylist: UncheckedAccount<'info>,
+
+    /// CHECK: Recipient's spam policy PDA; may be uninitialized, parsed in `deposits::required_deposit`
+    #[account(seeds = [b"spam", to.as_ref()], bump)]
+    pub spam_policy: UncheckedAccount<'info>,
+
+    /// CHECK: Recipient's contact marker for the signer; may be uninitialized
+    #[account(seeds = [b"contact", to.as_ref(), send.sender.key().as_ref()], bump)]
+    pub contact: UncheckedAccount<'info>,
+
+    /// Signer's outstanding deposit with the recipient; required when the
+    /// recipient's spam policy asks the signer for one
+    #[account(
+        init_if_needed,
+        payer = send.payer,
+        space = 8 + SpamDeposit::INIT_SPACE,
+        seeds = [b"deposit", to.as_ref(), send.sender.key().as_ref()],
+        bump
+    )]
+    pub deposit: Option<Account<'info, SpamDeposit>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MarkRead<'info> {
+    #[account(mut)]
+    pub reader: Signer<'info>,
+
+    /// Reader's read-state bitmap, created on first use; omit to only emit the receipt
+    #[account(
+        init_if_needed,
+        payer = reader,
+        space = 8 + ReadState::INIT_SPACE,
+        seeds = [b"read", reader.key().as_ref()],
+        bump
+    )]
+    pub read_state: Option<Account<'info, ReadState>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct WrapSolFee<'info> {
+    #[account(seeds = [seeds::MAILER], bump = mailer.bump)]
+    pub mailer: Account<'info, MailerState>,
+
+    /// Fee configuration of wrapped SOL; SOL must be an enabled fee token
+    #[account(
+        seeds = [b"token", mint.key().as_ref()],
+        bump = token_config.bump,
+        constraint = token_config.enabled @ TokenError::TokenDisabled
+    )]
+    pub token_config: Account<'info, TokenConfig>,
+
+    /// Fresh Pyth SOL price; required only when `token_config` has a price feed
+    pub price_update: Option<Account<'info, PriceUpdateV2>>,
+
+    #[account(address = native_mint::ID)]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(mut)]
+    pub sender: Signer<'info>,
+
+    /// Sender's wSOL account, created on first use; close it after the send to unwrap
+    #[account(
+        init_if_needed,
+        payer = sender,
+        associated_token::mint = mint,
+        associated_token::authority = sender,
+        associated_token::token_program = token_program
+    )]
+    pub sender_wsol_account: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetOriginPolicy<'info> {
+    #[account(
+        init_if_needed,
+        payer = recipient,
+        space = 8 + RecipientPreferences::INIT_SPACE,
+        seeds = [b"prefs", recipient.key().as_ref()],
+        bump
+    )]
+    pub recipient_preferences: Account<'info, RecipientPreferences>,
+
+    #[account(mut)]
+    pub recipient: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(sender: Pubkey)]
+pub struct BlockSender<'info> {
+    /// Fails if the sender is already blocked
+    #[account(
+        init,
+        payer = recipient,
+        space = 8 + SenderBlock::INIT_SPACE,
+        seeds = [b"block", recipient.key().as_ref(), sender.as_ref()],
+        bump
+    )]
+    pub block: Account<'info, SenderBlock>,
+
+    #[account(mut)]
+    pub recipient: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UnblockSender<'info> {
+    /// Closed on unblock; rent returns to the recipient
+    #[account(
+        mut,
+        close = recipient,
+        seeds = [b"block", recipient.key().as_ref(), block.sender.as_ref()],
+        bump = block.bump,
+        has_one = recipient
+    )]
+    pub block: Account<'info, SenderBlock>,
+
+    #[account(mut)]
+    pub recipient: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetCallerPolicy<'info> {
+    #[account(
+        seeds = [seeds::MAILER],
+        bump = mailer.bump,
+        has_one = owner @ MailerError::OnlyOwner
+    )]
+    pub mailer: Account<'info, MailerState>,
+
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = 8 + CallerPolicy::INIT_SPACE,
+        seeds = [b"callers"],
+        bump
+    )]
+    pub caller_policy: Account<'info, CallerPolicy>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct RecipientPreferences {
+    pub recipient: Pubkey,
+    pub origin_allowlist: bool,
+    #[max_len(16)]
+    pub origin_programs: Vec<Pubkey>,
+    pub bump: u8,
+}
+
+/// Marks a prepared mail ID as sent; its existence makes later sends of the ID fail
+/// Owner's policy on which programs may send via CPI
+#[account]
+#[derive(InitSpace)]
+pub struct CallerPolicy {
+    /// Accept CPI sends from any program, ignoring `programs`
+    pub allow_all: bool,
+    #[max_len(32)]
+    pub programs: Vec<Pubkey>,
+    pub bump: u8,
+}
+
+/// Marks a sender whose mail the recipient refuses; closed to unblock
+#[account]
+#[derive(InitSpace)]
+pub struct SenderBlock {
+    pub recipient: Pubkey,
+    pub sender: Pubkey,
+    pub blocked_at: i64,
+    pub bump: u8,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct MailIdRecord {
+    pub sender: Pubkey,
+    /// ID of the message that first used the mail ID
+    pub message_id: [u8; 32],
+    pub bump: u8,
+}
+
+/// Messages a sender has sent in their current rate limit window
+#[account]
+#[derive(InitSpace)]
+pub struct RateLimit {
+    pub sender: Pubkey,
+    /// Start of the current window; it resets `RATE_LIMIT_WINDOW` seconds later
+    pub window_start: i64,
+    /// Messages sent since `window_start`
+    pub count: u64,
+}
+
+impl RateLimit {
+    /// Count `messages` sent at `now`, failing once the window holds more than `max_per_day`
+    pub fn record(&mut self, messages: u64, max_per_day: u32, now: i64) -> Result<()> {
+        if now.saturating_sub(self.window_start) >= RATE_LIMIT_WINDOW {
+            self.window_start = now;
+            self.count = 0;
+        }
+        self.count = self.count.checked_add(messages).ok_or(FeeError::MathOverflow)?;
+        require!(
+            max_per_day == 0 || self.count <= u64::from(max_per_day),
+            MessagingError::RateLimitExceeded
+        );
+        Ok(())
+    }
+}
+
+/// Per-reader bitmap of read messages, indexed by message sequence modulo `READ_STATE_BITS`
+///
+/// Sequences `READ_STATE_BITS` apart share a bit, so a set bit means the slot was
+/// read at least once; clients pair it with the `MailRead` events for certainty.
+#[account]
+#[derive(InitSpace)]
+pub struct ReadState {
+    pub reader: Pubkey,
+    pub bits: [u8; READ_STATE_BITS as usize / 8],
+    pub bump: u8,
+}
+
+impl ReadState {
+    pub fn mark(&mut self, message_seq: u64) {
+        let (byte, mask) = Self::position(message_seq);
+        self.bits[byte] |= mask;
+    }
+
+    pub fn is_read(&self, message_seq: u64) -> bool {
+        let (byte, mask) = Self::position(message_seq);
+        self.bits[byte] & mask != 0
+    }
+
+    fn position(message_seq: u64) -> (usize, u8) {
+        let bit = message_seq % READ_STATE_BITS;
+        ((bit / 8) as usize, 1 << (bit % 8))
+    }
+}
+
+/// Recipient address on another chain, e.g. an EVM account reached through the relay
+///
+/// `chain_id` is the chain's numeric ID (EIP-155 for EVM chains) and `address`
+/// its raw address bytes, so one format covers every supported chain.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq, InitSpace)]
+pub struct ExternalAddress {
+    pub chain_id: u64,
+    #[max_len(MAX_EXTERNAL_ADDRESS_LEN)]
+    pub address: Vec<u8>,
+}
+
+/// Emitted by every send before priority and standard sends got their own
+/// events; no longer emitted, kept so indexers can decode earlier history
+#[event]
+pub struct MailSent {
+    pub from: Pubkey,
+    pub to: Pubkey,
+    pub subject: String,
+    pub body: String,
+}
+
+/// Prepared counterpart of `MailSent`; no longer emitted
+#[event]
+pub struct PreparedMailSent {
+    pub from: Pubkey,
+    pub to: Pubkey,
+    pub mail_id: String,
+}
+
+/// Emitted by `send_priority` (full fee, revenue share recorded)
+#[event]
+pub struct PriorityMailSent {
+    pub from: Pubkey,
+    pub to: Pubkey,
+    pub to_external: Option<ExternalAddress>,
+    /// Global order of the message across all send paths
+    pub message_seq: u64,
+    /// `message_id(from, message_seq, slot)`
+    pub message_id: [u8; 32],
+    pub timestamp: i64,
+    /// Caller-chosen reference passed through unchanged, e.g. an idempotency key
+    pub client_ref: Option<[u8; 16]>,
+    pub subject: String,
+    pub body: String,
+    /// SHA-256 of the body when it exceeded `BODY_HASH_THRESHOLD`; `body` is then empty
+    pub body_hash: Option<[u8; 32]>,
+    /// Length of the body in bytes, whether or not it was emitted
+    pub body_len: u32,
+}
+
+/// Emitted by `send` (owner fee only, no revenue share)
+#[event]
+pub struct StandardMailSent {
+    pub from: Pubkey,
+    pub to: Pubkey,
+    pub to_external: Option<ExternalAddress>,
+    /// Global order of the message across all send paths
+    pub message_seq: u64,
+    /// `message_id(from, message_seq, slot)`
+    pub message_id: [u8; 32],
+    pub timestamp: i64,
+    /// Caller-chosen reference passed through unchanged, e.g. an idempotency key
+    pub client_ref: Option<[u8; 16]>,
+    pub subject: String,
+    pub body: String,
+    /// SHA-256 of the body when it exceeded `BODY_HASH_THRESHOLD`; `body` is then empty
+    pub body_hash: Option<[u8; 32]>,
+    /// Length of the body in bytes, whether or not it was emitted
+    pub body_len: u32,
+}
+
+/// Emitted by `send_priority_prepared` (full fee, revenue share recorded)
+#[event]
+pub struct PriorityPreparedMailSent {
+    pub from: Pubkey,
+    pub to: Pubkey,
+    pub to_external: Option<ExternalAddress>,
+    /// Global order of the message across all send paths
+    pub message_seq: u64,
+    /// `message_id(from, message_seq, slot)`
+    pub message_id: [u8; 32],
+    pub timestamp: i64,
+    /// Caller-chosen reference passed through unchanged, e.g. an idempotency key
+    pub client_ref: Option<[u8; 16]>,
+    pub mail_id: String,
+}
+
+/// Emitted by `send_sealed` (owner fee only, no revenue share)
+///
+/// `from` is the relayer or ephemeral key that signed; the true sender is only
+/// inside `envelope`, encrypted to the recipient.
+#[event]
+pub struct SealedMailSent {
+    pub from: Pubkey,
+    pub to: Pubkey,
+    /// Global order of the message across all send paths
+    pub message_seq: u64,
+    /// `message_id(from, message_seq, slot)`
+    pub message_id: [u8; 32],
+    pub timestamp: i64,
+    pub mail_id: String,
+    /// Opaque to the program; see `app/sealed-envelope.ts` for the client format
+    pub envelope: Vec<u8>,
+}
+
+/// Emitted by `send_prepared` (owner fee only, no revenue share)
+#[event]
+pub struct StandardPreparedMailSent {
+    pub from: Pubkey,
+    pub to: Pubkey,
+    pub to_external: Option<ExternalAddress>,
+    /// Global order of the message across all send paths
+    pub message_seq: u64,
+    /// `message_id(from, message_seq, slot)`
+    pub message_id: [u8; 32],
+    pub timestamp: i64,
+    /// Caller-chosen reference passed through unchanged, e.g. an idempotency key
+    pub client_ref: Option<[u8; 16]>,
+    pub mail_id: String,
+}
+
+/// Emitted by `send_prepared_batch`
+///
+/// `payload` is a zstd frame of the newline-separated mail IDs and `ids_hash`
+/// the SHA-256 of the uncompressed bytes; indexers drop batches that do not
+/// decompress to `count` IDs matching the hash.
+#[event]
+pub struct PreparedBatchSent {
+    pub from: Pubkey,
+    pub to: Pubkey,
+    /// Sequence number of the first mail ID; the batch covers `count` consecutive numbers
+    pub message_seq: u64,
+    /// ID of the first message; message `i` of the batch is `message_id(from, message_seq + i, slot)`
+    pub message_id: [u8; 32],
+    pub slot: u64,
+    pub timestamp: i64,
+    /// Caller-chosen reference passed through unchanged, e.g. an idempotency key
+    pub client_ref: Option<[u8; 16]>,
+    pub count: u16,
+    pub priority: bool,
+    pub ids_hash: [u8; 32],
+    pub payload: Vec<u8>,
+}
+
+#[event]
+pub struct MailRead {
+    pub reader: Pubkey,
+    pub message_id: [u8; 32],
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RateLimitUpdated {
+    pub old_max_messages_per_day: u32,
+    pub new_max_messages_per_day: u32,
+}
+
+#[event]
+pub struct MessageLimitsUpdated {
+    pub old_max_subject_len: u32,
+    pub old_max_body_len: u32,
+    pub new_max_subject_len: u32,
+    pub new_max_body_len: u32,
+}
+
+#[event]
+pub struct SolWrapped {
+    pub sender: Pubkey,
+    pub amount: u64,
+    pub fee: u64,
+}
+
+#[event]
+pub struct ClaimNetted {
+    pub sender: Pubkey,
+    pub netted: u64,
+    pub transferred: u64,
+}
+
+#[event]
+pub struct SentAsDelegate {
+    pub delegator: Pubkey,
+    pub delegate: Pubkey,
+    pub message_id: [u8; 32],
+}
+
+#[event]
+pub struct CallerPolicyUpdated {
+    pub programs: Vec<Pubkey>,
+    pub allow_all: bool,
+}
+
+#[event]
+pub struct OriginPolicyUpdated {
+    pub recipient: Pubkey,
+    pub origin_programs: Vec<Pubkey>,
+    pub allowlist: bool,
+}
+
+#[event]
+pub struct SenderBlocked {
+    pub recipient: Pubkey,
+    pub sender: Pubkey,
+}
+
+#[event]
+pub struct SenderUnblocked {
+    pub recipient: Pubkey,
+    pub sender: Pubkey,
+}
+
+#[error_code(offset = 6200)]
+pub enum MessagingError {
+    #[msg("Too many origin programs")]
+    TooManyOriginPrograms,
+    #[msg("Recipient does not accept messages from this program")]
+    OriginProgramDenied,
+    #[msg("Prepared batch is empty, too large or not zstd-compressed")]
+    InvalidBatch,
+    #[msg("External address needs a chain ID and 1-64 address bytes")]
+    InvalidExternalAddress,
+    #[msg("Mail ID has already been sent")]
+    DuplicateMailId,
+    #[msg("Mail ID record does not match the mail ID")]
+    InvalidMailIdRecord,
+    #[msg("Subject or body exceeds the maximum length")]
+    MessageTooLarge,
+    #[msg("Message length limits must be greater than zero")]
+    InvalidMessageLimits,
+    #[msg("Signer is not the delegate of this delegation")]
+    NotDelegate,
+    #[msg("Too many caller programs")]
+    TooManyCallerPrograms,
+    #[msg("Mailer does not accept CPI sends from this program")]
+    CallerProgramDenied,
+    #[msg("Delegation does not grant the send permission")]
+    DelegateCannotSend,
+    #[msg("Sealed envelope is empty or too large")]
+    InvalidEnvelope,
+    #[msg("Inbox does not belong to the recipient")]
+    InvalidInbox,
+    #[msg("Recipient has blocked this sender")]
+    SenderBlocked,
+    #[msg("Sender has reached the daily message limit")]
+    RateLimitExceeded,
+    #[msg("Only standard sends can be billed to the delegator")]
+    DelegatorFundsStandardOnly,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn denylist_rejects_only_listed_programs() {
+        let noisy = Pubkey::new_unique();
+        let other = Pubkey::new_unique();
+        assert!(!origin_allowed(&[noisy], false, &noisy));
+        assert!(origin_allowed(&[noisy], false, &other));
+    }
+
+    #[test]
+    fn allowlist_accepts_only_listed_programs() {
+        let trusted = Pubkey::new_unique();
+        let other = Pubkey::new_unique();
+        assert!(origin_allowed(&[trusted], true, &trusted));
+        assert!(!origin_allowed(&[trusted], true, &other));
+    }
+
+    #[test]
+    fn caller_policy_accepts_listed_programs_or_all() {
+        let dao = Pubkey::new_unique();
+        let spammer = Pubkey::new_unique();
+        assert!(caller_allowed(&[dao], false, &dao));
+        assert!(!caller_allowed(&[dao], false, &spammer));
+        assert!(caller_allowed(&[], true, &spammer));
+        assert_eq!(CallerPolicy::INIT_SPACE, 1 + 4 + 32 * MAX_CALLER_PROGRAMS + 1);
+    }
+
+    #[test]
+    fn batch_requires_zstd_frame_and_bounded_count() {
+        let frame = [ZSTD_MAGIC.as_slice(), &[0u8; 8]].concat();
+        assert!(is_valid_batch(1, &frame));
+        assert!(is_valid_batch(MAX_PREPARED_BATCH, &frame));
+        assert!(!is_valid_batch(0, &frame));
+        assert!(!is_valid_batch(MAX_PREPARED_BATCH + 1, &frame));
+        assert!(!is_valid_batch(1, b"mail-1\nmail-2"));
+    }
+
+    #[test]
+    fn batch_payload_must_fit_in_a_transaction() {
+        let oversized = [ZSTD_MAGIC.as_slice(), &[0u8; MAX_BATCH_PAYLOAD]].concat();
+        assert!(!is_valid_batch(1, &oversized));
+    }
+
+    #[test]
+    fn preferences_space_fits_max_origin_programs() {
+        assert_eq!(
+            RecipientPreferences::INIT_SPACE,
+            32 + 1 + 4 + 32 * MAX_ORIGIN_PROGRAMS + 1
+        );
+    }
+
+    #[test]
+    fn external_address_needs_chain_and_bounded_bytes() {
+        let evm = |address: Vec<u8>| Some(ExternalAddress { chain_id: 1, address });
+        assert!(validate_external_address(&None).is_ok());
+        assert!(validate_external_address(&evm(vec![0xAB; 20])).is_ok());
+        assert!(validate_external_address(&evm(vec![0xAB; MAX_EXTERNAL_ADDRESS_LEN])).is_ok());
+        assert!(validate_external_address(&evm(vec![0xAB; MAX_EXTERNAL_ADDRESS_LEN + 1])).is_err());
+        assert!(validate_external_address(&evm(Vec::new())).is_err());
+        assert!(validate_external_address(&Some(ExternalAddress {
+            chain_id: 0,
+            address: vec![0xAB; 20],
+        }))
+        .is_err());
+    }
+
+    #[test]
+    fn message_size_is_checked_in_bytes() {
+        let mut mailer = crate::tokens::tests::mailer_with_usdc(Pubkey::new_unique());
+        mailer.max_subject_len = 4;
+        mailer.max_body_len = 8;
+        assert!(check_message_size(&mailer, "Hi", "Body").is_ok());
+        assert!(check_message_size(&mailer, "Subj", "12345678").is_ok());
+        assert!(check_message_size(&mailer, "Subje", "Body").is_err());
+        assert!(check_message_size(&mailer, "Hi", "123456789").is_err());
+        // Multi-byte characters count by their encoded length
+        assert!(check_message_size(&mailer, "éé", "Body").is_ok());
+        assert!(check_message_size(&mailer, "ééé", "Body").is_err());
+    }
+
+    #[test]
+    fn message_limits_must_be_positive() {
+        assert!(validate_message_limits(1, 1).is_ok());
+        assert!(validate_message_limits(0, 800).is_err());
+        assert!(validate_message_limits(200, 0).is_err());
+    }
+
+    #[test]
+    fn long_bodies_are_emitted_as_hash() {
+        let short = "x".repeat(BODY_HASH_THRESHOLD);
+        assert_eq!(
+            emitted_body(short.clone()),
+            (short, None, BODY_HASH_THRESHOLD as u32)
+        );
+
+        let long = "x".repeat(BODY_HASH_THRESHOLD + 1);
+        let (body, body_hash, body_len) = emitted_body(long.clone());
+        assert!(body.is_empty());
+        assert_eq!(body_hash, Some(hash(long.as_bytes()).to_bytes()));
+        assert_eq!(body_len, BODY_HASH_THRESHOLD as u32 + 1);
+    }
+
+    #[test]
+    fn rate_limit_resets_each_window() {
+        let mut rate_limit = RateLimit {
+            sender: Pubkey::new_unique(),
+            window_start: 0,
+            count: 0,
+        };
+        let start = 1_700_000_000;
+        assert!(rate_limit.record(2, 3, start).is_ok());
+        assert!(rate_limit.record(1, 3, start + 60).is_ok());
+        assert!(rate_limit.record(1, 3, start + 120).is_err());
+
+        // The next window starts from zero, and a batch counts every message
+        let mut rate_limit = RateLimit { count: 3, ..rate_limit };
+        assert!(rate_limit.record(3, 3, start + RATE_LIMIT_WINDOW).is_ok());
+        assert_eq!(rate_limit.window_start, start + RATE_LIMIT_WINDOW);
+        assert!(rate_limit.record(4, 3, start + 2 * RATE_LIMIT_WINDOW).is_err());
+    }
+
+    #[test]
+    fn rate_limit_of_zero_is_unlimited() {
+        let mut rate_limit = RateLimit {
+            sender: Pubkey::new_unique(),
+            window_start: 0,
+            count: 0,
+        };
+        assert!(rate_limit.record(u64::from(u32::MAX) + 1, 0, 1_700_000_000).is_ok());
+    }
+
+    #[test]
+    fn read_state_marks_sequence_slots() {
+        let mut read_state = ReadState {
+            reader: Pubkey::new_unique(),
+            bits: [0; READ_STATE_BITS as usize / 8],
+            bump: 0,
+        };
+        read_state.mark(9);
+        assert!(read_state.is_read(9));
+        assert!(!read_state.is_read(8));
+        assert!(!read_state.is_read(10));
+        // Sequences a full bitmap apart share a slot
+        assert!(read_state.is_read(9 + READ_STATE_BITS));
+        read_state.mark(u64::MAX);
+        assert!(read_state.is_read(u64::MAX));
+        assert_eq!(ReadState::INIT_SPACE, 32 + 128 + 1);
+    }
+
+    #[test]
+    fn message_id_commits_to_sender_seq_and_slot() {
+        let sender = Pubkey::new_unique();
+        let id = message_id(&sender, 7, 1_000);
+        assert_eq!(id, message_id(&sender, 7, 1_000));
+        assert_ne!(id, message_id(&Pubkey::new_unique(), 7, 1_000));
+        assert_ne!(id, message_id(&sender, 8, 1_000));
+        assert_ne!(id, message_id(&sender, 7, 1_001));
+    }
+}
@@ -0,0 +1,114 @@
+//! NFT holder perks.
+//!
+//! The owner names a Metaplex collection and a discount. Sends that pass the
+//! sender's token account holding an NFT of that collection, together with the
+//! NFT's metadata, are charged the fee less the discount; a 100% discount
+//! waives it. The collection must be verified on the metadata, so holders of
+//! look-alike NFTs get no perk.
+
+use anchor_lang::prelude::*;
+use anchor_spl::metadata::MetadataAccount;
+use anchor_spl::token_interface::TokenAccount;
+use mailbox_common::fees::{split_fee, BPS_DENOMINATOR};
+
+use crate::fees::{ConfigChange, SetFee};
+use crate::messaging::SendMessage;
+
+pub fn set_nft_perk(ctx: Context<SetFee>, collection: Pubkey, discount_bps: u16) -> Result<()> {
+    ConfigChange::NftDiscount { discount_bps }.validate()?;
+
+    let mailer = &mut ctx.accounts.mailer;
+    mailer.nft_collection = collection;
+    mailer.nft_discount_bps = discount_bps;
+
+    emit!(NftPerkUpdated {
+        collection,
+        discount_bps,
+    });
+
+    Ok(())
+}
+
+pub fn validate_nft_discount(discount_bps: u16) -> Result<()> {
+    require!(
+        discount_bps <= BPS_DENOMINATOR,
+        NftPerkError::InvalidNftDiscount
+    );
+    Ok(())
+}
+
+/// Discount the sender is entitled to, in basis points; 0 when it passes no NFT
+pub(crate) fn holder_discount_bps(accounts: &SendMessage) -> Result<u16> {
+    let Some(token) = accounts.nft_token.as_ref() else {
+        return Ok(0);
+    };
+    let collection = accounts.mailer.nft_collection;
+    require_keys_neq!(collection, Pubkey::default(), NftPerkError::NftPerkDisabled);
+
+    let metadata = accounts
+        .nft_metadata
+        .as_ref()
+        .ok_or(NftPerkError::InvalidNftProof)?;
+    require!(
+        holds_collection_nft(token, metadata, accounts.sender.key(), collection),
+        NftPerkError::InvalidNftProof
+    );
+    Ok(accounts.mailer.nft_discount_bps)
+}
+
+/// Whether `token` is `holder`'s account holding the NFT `metadata` describes,
+/// and the NFT is a verified member of `collection`
+pub fn holds_collection_nft(
+    token: &TokenAccount,
+    metadata: &MetadataAccount,
+    holder: Pubkey,
+    collection: Pubkey,
+) -> bool {
+    token.owner == holder
+        && token.amount >= 1
+        && metadata.mint == token.mint
+        && metadata
+            .collection
+            .as_ref()
+            .is_some_and(|member| member.verified && member.key == collection)
+}
+
+/// `fee` less a discount of `discount_bps`
+pub fn discounted_fee(fee: u64, discount_bps: u16) -> Result<u64> {
+    let (_, charged) = split_fee(fee, discount_bps).ok_or(NftPerkError::InvalidNftDiscount)?;
+    Ok(charged)
+}
+
+#[event]
+pub struct NftPerkUpdated {
+    /// Metaplex collection whose holders get the perk; default disables it
+    pub collection: Pubkey,
+    pub discount_bps: u16,
+}
+
+#[error_code(offset = 7400)]
+pub enum NftPerkError {
+    #[msg("NFT discount cannot exceed 100%")]
+    InvalidNftDiscount,
+    #[msg("No NFT collection is configured for holder perks")]
+    NftPerkDisabled,
+    #[msg("NFT account does not prove the sender holds a verified NFT of the perk collection")]
+    InvalidNftProof,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn discounts_scale_the_fee() {
+        assert_eq!(discounted_fee(100_000, 0).unwrap(), 100_000);
+        assert_eq!(discounted_fee(100_000, 2_500).unwrap(), 75_000);
+        assert_eq!(discounted_fee(100_000, BPS_DENOMINATOR).unwrap(), 0);
+        assert_eq!(
+            discounted_fee(u64::MAX, 2).unwrap(),
+            u64::MAX - u64::MAX / 5_000
+        );
+        assert!(discounted_fee(100_000, BPS_DENOMINATOR + 1).is_err());
+    }
+}
@@ -0,0 +1,290 @@
+//! Proof-of-work standard sends for senders without fee tokens.
+//!
+//! When the owner sets a non-zero `pow_difficulty`, a standard message can be
+//! paid for with a nonce whose challenge hash has at least that many leading
+//! zero bits instead of a token fee. The challenge commits to the sender, the
+//! message and a per-sender counter, so each solution is good for exactly one
+//! message.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::hash::hashv;
+use mailbox_common::seeds;
+
+use crate::admin::{MailerState, MailerStats};
+use crate::compliance::ComplianceError;
+use crate::fees::{ConfigChange, FeeError, SetFee};
+use crate::inbox;
+use crate::messaging::{
+    check_message_origin, check_message_size, emit_send_event, emitted_body, message_id,
+    RateLimit,
+};
+
+/// Upper bound on the proof-of-work difficulty, in leading zero bits
+pub const MAX_POW_DIFFICULTY: u8 = 32;
+
+/// Domain separator mixed into every challenge hash
+pub const POW_DOMAIN: &[u8] = b"mailer-pow";
+
+pub fn set_pow_difficulty(ctx: Context<SetFee>, new_difficulty: u8) -> Result<()> {
+    ConfigChange::PowDifficulty { new_difficulty }.validate()?;
+
+    let mailer = &mut ctx.accounts.mailer;
+    let old_difficulty = mailer.pow_difficulty;
+    mailer.pow_difficulty = new_difficulty;
+
+    emit!(PowDifficultyUpdated {
+        old_difficulty,
+        new_difficulty,
+    });
+
+    Ok(())
+}
+
+pub fn send_with_pow(
+    ctx: Context<SendWithPow>,
+    subject: String,
+    body: String,
+    nonce: u64,
+    client_ref: Option<[u8; 16]>,
+) -> Result<[u8; 32]> {
+    let difficulty = ctx.accounts.mailer.pow_difficulty;
+    require!(difficulty > 0, PowError::PowDisabled);
+    check_message_size(&ctx.accounts.mailer, &subject, &body)?;
+    check_message_origin(
+        &ctx.accounts.caller_policy,
+        &ctx.accounts.recipient_preferences,
+        &ctx.accounts.instructions,
+    )?;
+
+    let sender = ctx.accounts.sender.key();
+    let pow_state = &mut ctx.accounts.pow_state;
+    let hash = challenge_hash(&sender, pow_state.counter, &subject, &body, nonce);
+    require!(
+        leading_zero_bits(&hash) >= u32::from(difficulty),
+        PowError::InsufficientWork
+    );
+
+    // Consume the challenge so the same solution cannot pay for another message
+    pow_state.sender = sender;
+    pow_state.counter = pow_state.counter.checked_add(1).ok_or(FeeError::MathOverflow)?;
+    pow_state.bump = ctx.bumps.pow_state;
+    let clock = Clock::get()?;
+    let rate_limit = &mut ctx.accounts.rate_limit;
+    rate_limit.sender = sender;
+    rate_limit.record(1, ctx.accounts.mailer.max_messages_per_day, clock.unix_timestamp)?;
+    let message_seq = ctx.accounts.stats.record_sends(1, false)?;
+    let message_id = message_id(&sender, message_seq, clock.slot);
+    if let Some(inbox) = &ctx.accounts.inbox {
+        inbox::record(
+            &inbox.to_account_info(),
+            &ctx.accounts.payer.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            sender,
+            sender,
+            message_seq,
+            1,
+        )?;
+    }
+
+    let (body, body_hash, body_len) = emitted_body(body);
+    let event = PowMailSent {
+        from: sender,
+        to: sender, // Messages are sent to self
+        message_seq,
+        message_id,
+        timestamp: clock.unix_timestamp,
+        client_ref,
+        subject,
+        body,
+        body_hash,
+        body_len,
+        difficulty,
+    };
+    emit_send_event!(ctx, event);
+
+    Ok(message_id)
+}
+
+pub fn validate_pow_difficulty(difficulty: u8) -> Result<()> {
+    require!(
+        difficulty <= MAX_POW_DIFFICULTY,
+        PowError::InvalidPowDifficulty
+    );
+    Ok(())
+}
+
+/// Challenge hash for a sender's `counter`-th proof-of-work message
+///
+/// The subject is length-prefixed so a subject/body split cannot be shifted
+/// to reuse work for a different message.
+pub fn challenge_hash(sender: &Pubkey, counter: u64, subject: &str, body: &str, nonce: u64) -> [u8; 32] {
+    hashv(&[
+        POW_DOMAIN,
+        sender.as_ref(),
+        &counter.to_le_bytes(),
+        &(subject.len() as u32).to_le_bytes(),
+        subject.as_bytes(),
+        body.as_bytes(),
+        &nonce.to_le_bytes(),
+    ])
+    .to_bytes()
+}
+
+/// Number of leading zero bits in a hash
+pub fn leading_zero_bits(hash: &[u8; 32]) -> u32 {
+    let mut bits = 0;
+    for byte in hash {
+        bits += byte.leading_zeros();
+        if *byte != 0 {
+            break;
+        }
+    }
+    bits
+}
+
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+pub struct SendWithPow<'info> {
+    #[account(seeds = [seeds::MAILER], bump = mailer.bump)]
+    pub mailer: Account<'info, MailerState>,
+
+    #[account(mut, seeds = [b"mailer_stats"], bump = stats.bump)]
+    pub stats: Account<'info, MailerStats>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + PowState::INIT_SPACE,
+        seeds = [b"pow", sender.key().as_ref()],
+        bump
+    )]
+    pub pow_state: Account<'info, PowState>,
+
+    pub sender: Signer<'info>,
+
+    /// Pays rent for any PDAs created by this send. Usually the sender, but
+    /// may be a relayer sponsoring SOL for senders who only hold fee tokens.
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// Sender's daily message counter, created on first send
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + RateLimit::INIT_SPACE,
+        seeds = [b"rate", sender.key().as_ref()],
+        bump
+    )]
+    pub rate_limit: Account<'info, RateLimit>,
+
+    /// CHECK: Sender's compliance denylist marker; must not exist
+    #[account(
+        seeds = [b"deny", sender.key().as_ref()],
+        bump,
+        constraint = denylist.data_is_empty() @ ComplianceError::AddressDenied
+    )]
+    pub denylist: UncheckedAccount<'info>,
+
+    /// CHECK: Recipient's preferences PDA; may be uninitialized, parsed in `check_message_origin`
+    #[account(seeds = [b"prefs", sender.key().as_ref()], bump)]
+    pub recipient_preferences: UncheckedAccount<'info>,
+
+    /// CHECK: Owner's CPI caller policy PDA; may be uninitialized, parsed in `check_message_origin`
+    #[account(seeds = [b"callers"], bump)]
+    pub caller_policy: UncheckedAccount<'info>,
+
+    /// CHECK: Instructions sysvar, validated by address
+    #[account(address = anchor_lang::solana_program::sysvar::instructions::ID)]
+    pub instructions: UncheckedAccount<'info>,
+
+    /// CHECK: Recipient's `[b"inbox", recipient]` counters, created on first delivery and
+    /// verified in `inbox::record`. Omit to leave them untouched
+    #[account(mut)]
+    pub inbox: Option<UncheckedAccount<'info>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct PowState {
+    pub sender: Pubkey,
+    /// Proof-of-work messages sent so far; the next challenge commits to it
+    pub counter: u64,
+    pub bump: u8,
+}
+
+/// Emitted by `send_with_pow` (no fee, paid for with proof-of-work)
+#[event]
+pub struct PowMailSent {
+    pub from: Pubkey,
+    pub to: Pubkey,
+    /// Global order of the message across all send paths
+    pub message_seq: u64,
+    /// `message_id(from, message_seq, slot)`
+    pub message_id: [u8; 32],
+    pub timestamp: i64,
+    /// Caller-chosen reference passed through unchanged, e.g. an idempotency key
+    pub client_ref: Option<[u8; 16]>,
+    pub subject: String,
+    pub body: String,
+    /// SHA-256 of the body when it exceeded `BODY_HASH_THRESHOLD`; `body` is then empty
+    pub body_hash: Option<[u8; 32]>,
+    /// Length of the body in bytes, whether or not it was emitted
+    pub body_len: u32,
+    pub difficulty: u8,
+}
+
+#[event]
+pub struct PowDifficultyUpdated {
+    pub old_difficulty: u8,
+    pub new_difficulty: u8,
+}
+
+#[error_code(offset = 6600)]
+pub enum PowError {
+    #[msg("Proof-of-work sends are disabled")]
+    PowDisabled,
+    #[msg("Proof-of-work does not meet the required difficulty")]
+    InsufficientWork,
+    #[msg("Proof-of-work difficulty exceeds the maximum allowed")]
+    InvalidPowDifficulty,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn solve(sender: &Pubkey, counter: u64, difficulty: u32) -> u64 {
+        (0..)
+            .find(|nonce| {
+                leading_zero_bits(&challenge_hash(sender, counter, "Hi", "Body", *nonce)) >= difficulty
+            })
+            .unwrap()
+    }
+
+    #[test]
+    fn counts_leading_zero_bits_across_bytes() {
+        let mut hash = [0xFFu8; 32];
+        assert_eq!(leading_zero_bits(&hash), 0);
+        hash[0] = 0;
+        hash[1] = 0b0001_0000;
+        assert_eq!(leading_zero_bits(&hash), 11);
+        assert_eq!(leading_zero_bits(&[0u8; 32]), 256);
+    }
+
+    #[test]
+    fn solution_is_bound_to_counter_and_message() {
+        let sender = Pubkey::new_unique();
+        let nonce = solve(&sender, 0, 8);
+        assert!(leading_zero_bits(&challenge_hash(&sender, 0, "Hi", "Body", nonce)) >= 8);
+
+        // With overwhelming probability the same nonce fails once anything changes
+        let reused = [
+            challenge_hash(&sender, 1, "Hi", "Body", nonce),
+            challenge_hash(&sender, 0, "H", "iBody", nonce),
+            challenge_hash(&Pubkey::new_unique(), 0, "Hi", "Body", nonce),
+        ];
+        assert!(reused.iter().any(|hash| leading_zero_bits(hash) < 8));
+    }
+}
@@ -0,0 +1,252 @@
+//! Compressed NFT receipts.
+//!
+//! The owner points the `ReceiptConfig` PDA (`[b"receipts"]`) at a Bubblegum
+//! merkle tree whose tree delegate is the mailer PDA. Single priority sends
+//! that pass the config and the `receipt_*` accounts mint a cNFT to the
+//! sender (who is also the recipient) whose URI carries the message ID and
+//! timestamp, giving a wallet-visible, transferable proof of correspondence.
+//! Batch, delegated, channel and group sends do not mint receipts.
+
+use anchor_lang::prelude::*;
+use mailbox_common::seeds;
+use mpl_bubblegum::instructions::MintV1CpiBuilder;
+use mpl_bubblegum::types::{MetadataArgs, TokenProgramVersion, TokenStandard};
+
+use crate::admin::{MailerError, MailerState};
+use crate::messaging::SendMessage;
+
+/// SPL Noop program Bubblegum logs minted leaves through
+/// (`noopb9bkMVfRPU8AsbpTUg8AQkHtKwMYZiFUjNRtMmV`)
+pub const SPL_NOOP_ID: Pubkey = Pubkey::new_from_array([
+    11, 188, 15, 192, 187, 71, 202, 47, 116, 196, 17, 46, 148, 171, 19, 207, 163, 198, 52, 229,
+    220, 23, 234, 203, 3, 205, 26, 35, 205, 126, 120, 124,
+]);
+
+/// SPL Account Compression program owning Bubblegum trees
+/// (`cmtDvXumGCrqC1Age74AVPhSRVXJMd8PJS91L8KbNCK`)
+pub const SPL_ACCOUNT_COMPRESSION_ID: Pubkey = Pubkey::new_from_array([
+    9, 42, 19, 238, 149, 196, 28, 186, 8, 166, 127, 90, 198, 126, 141, 247, 225, 218, 17, 98, 94,
+    29, 100, 19, 127, 143, 79, 35, 131, 3, 127, 20,
+]);
+
+/// Maximum length of the receipt base URI, in bytes; leaves room for the ID and timestamp
+pub const MAX_RECEIPT_BASE_URI_LEN: usize = 110;
+
+/// Symbol of every receipt cNFT
+pub const RECEIPT_SYMBOL: &str = "MAIL";
+
+pub fn set_receipt_tree(
+    ctx: Context<SetReceiptTree>,
+    merkle_tree: Pubkey,
+    base_uri: String,
+) -> Result<()> {
+    require!(
+        base_uri.len() <= MAX_RECEIPT_BASE_URI_LEN,
+        ReceiptError::InvalidReceiptUri
+    );
+
+    let config = &mut ctx.accounts.receipt_config;
+    config.merkle_tree = merkle_tree;
+    config.base_uri = base_uri.clone();
+    config.bump = ctx.bumps.receipt_config;
+
+    emit!(ReceiptTreeUpdated {
+        merkle_tree,
+        base_uri,
+    });
+
+    Ok(())
+}
+
+/// Mint a receipt of message `message_id` to `accounts.sender`, when the send
+/// passed the receipt accounts
+pub(crate) fn mint_receipt(
+    accounts: &SendMessage,
+    message_id: [u8; 32],
+    message_seq: u64,
+    timestamp: i64,
+) -> Result<()> {
+    let Some(config) = accounts.receipt_config.as_ref() else {
+        return Ok(());
+    };
+    require_keys_neq!(
+        config.merkle_tree,
+        Pubkey::default(),
+        ReceiptError::ReceiptsDisabled
+    );
+    let missing = || error!(ReceiptError::MissingReceiptAccounts);
+    let tree_config = accounts.receipt_tree_config.as_ref().ok_or_else(missing)?;
+    let merkle_tree = accounts.receipt_tree.as_ref().ok_or_else(missing)?;
+    let bubblegum = accounts.bubblegum_program.as_ref().ok_or_else(missing)?;
+    let log_wrapper = accounts.log_wrapper.as_ref().ok_or_else(missing)?;
+    let compression = accounts.compression_program.as_ref().ok_or_else(missing)?;
+    require_keys_eq!(
+        merkle_tree.key(),
+        config.merkle_tree,
+        ReceiptError::InvalidReceiptTree
+    );
+
+    let mailer = accounts.mailer.to_account_info();
+    let owner = accounts.sender.to_account_info();
+    let payer = accounts.payer.to_account_info();
+    let system_program = accounts.system_program.to_account_info();
+    let bump = accounts.mailer.bump;
+    let signer_seeds: &[&[u8]] = &[seeds::MAILER, &[bump]];
+
+    MintV1CpiBuilder::new(bubblegum)
+        .tree_config(tree_config)
+        .leaf_owner(&owner)
+        .leaf_delegate(&owner)
+        .merkle_tree(merkle_tree)
+        .payer(&payer)
+        .tree_creator_or_delegate(&mailer)
+        .log_wrapper(log_wrapper)
+        .compression_program(compression)
+        .system_program(&system_program)
+        .metadata(receipt_metadata(
+            &config.base_uri,
+            message_id,
+            message_seq,
+            timestamp,
+        ))
+        .invoke_signed(&[signer_seeds])?;
+
+    emit!(ReceiptMinted {
+        owner: owner.key(),
+        message_id,
+        merkle_tree: merkle_tree.key(),
+    });
+
+    Ok(())
+}
+
+/// Metadata of the receipt of a message; the URI is `base_uri` followed by the
+/// hex message ID and the send timestamp
+pub fn receipt_metadata(
+    base_uri: &str,
+    message_id: [u8; 32],
+    message_seq: u64,
+    timestamp: i64,
+) -> MetadataArgs {
+    let id: String = message_id
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect();
+    MetadataArgs {
+        name: format!("Mail #{message_seq}"),
+        symbol: RECEIPT_SYMBOL.to_string(),
+        uri: format!("{base_uri}{id}?t={timestamp}"),
+        seller_fee_basis_points: 0,
+        primary_sale_happened: false,
+        is_mutable: false,
+        edition_nonce: None,
+        token_standard: Some(TokenStandard::NonFungible),
+        collection: None,
+        uses: None,
+        token_program_version: TokenProgramVersion::Original,
+        creators: Vec::new(),
+    }
+}
+
+#[derive(Accounts)]
+pub struct SetReceiptTree<'info> {
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = 8 + ReceiptConfig::INIT_SPACE,
+        seeds = [b"receipts"],
+        bump
+    )]
+    pub receipt_config: Account<'info, ReceiptConfig>,
+
+    #[account(
+        seeds = [seeds::MAILER],
+        bump = mailer.bump,
+        has_one = owner @ MailerError::OnlyOwner
+    )]
+    pub mailer: Account<'info, MailerState>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct ReceiptConfig {
+    /// Bubblegum tree receipts are minted into; default disables receipts
+    pub merkle_tree: Pubkey,
+    /// Prefix of every receipt URI
+    #[max_len(MAX_RECEIPT_BASE_URI_LEN)]
+    pub base_uri: String,
+    pub bump: u8,
+}
+
+#[event]
+pub struct ReceiptTreeUpdated {
+    pub merkle_tree: Pubkey,
+    pub base_uri: String,
+}
+
+#[event]
+pub struct ReceiptMinted {
+    pub owner: Pubkey,
+    pub message_id: [u8; 32],
+    pub merkle_tree: Pubkey,
+}
+
+#[error_code(offset = 7500)]
+pub enum ReceiptError {
+    #[msg("Receipt base URI is too long")]
+    InvalidReceiptUri,
+    #[msg("No receipt tree is configured")]
+    ReceiptsDisabled,
+    #[msg(
+        "Minting a receipt needs the tree config, tree, Bubblegum, noop and compression accounts"
+    )]
+    MissingReceiptAccounts,
+    #[msg("Receipt tree does not match the configured tree")]
+    InvalidReceiptTree,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn receipt_uri_carries_the_message_id_and_timestamp() {
+        let mut message_id = [0u8; 32];
+        message_id[0] = 0xab;
+        message_id[31] = 0x01;
+        let metadata = receipt_metadata("https://mail.example/r/", message_id, 42, 1_700_000_000);
+        assert_eq!(metadata.name, "Mail #42");
+        assert_eq!(
+            metadata.uri,
+            format!(
+                "https://mail.example/r/ab{}01?t=1700000000",
+                "00".repeat(30)
+            )
+        );
+
+        // The longest base URI still fits Bubblegum's 200-byte URI limit
+        let base = "x".repeat(MAX_RECEIPT_BASE_URI_LEN);
+        let metadata = receipt_metadata(&base, [0xff; 32], u64::MAX, i64::MAX);
+        assert!(metadata.uri.len() <= 200);
+        assert!(metadata.name.len() <= 32);
+    }
+
+    #[test]
+    fn program_ids_match_their_addresses() {
+        use std::str::FromStr;
+
+        assert_eq!(
+            SPL_NOOP_ID,
+            Pubkey::from_str("noopb9bkMVfRPU8AsbpTUg8AQkHtKwMYZiFUjNRtMmV").unwrap()
+        );
+        assert_eq!(
+            SPL_ACCOUNT_COMPRESSION_ID,
+            Pubkey::from_str("cmtDvXumGCrqC1Age74AVPhSRVXJMd8PJS91L8KbNCK").unwrap()
+        );
+    }
+}
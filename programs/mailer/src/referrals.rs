@@ -0,0 +1,201 @@
+//! Referral codes.
+//!
+//! Wallets and dApps embedding the mailer register a code naming them as the
+//! referrer. Sends made through a code pass its `ReferralCode` PDA and the
+//! referrer's claim, and `referral_bps` of the owner's share of the fee is
+//! moved from the owner's send bucket into that claim, where it is claimed
+//! like any other revenue share. Channel posts and group sends do not accept
+//! referrals.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::hash::hash;
+
+use crate::claims::{ClaimMode, RecipientClaim};
+use crate::expiry;
+use crate::fees::{credit_claim, referral_amount, standard_fee, FeeError};
+use crate::messaging::SendMessage;
+
+/// Maximum length of a referral code, in bytes
+pub const MAX_REFERRAL_CODE_LEN: usize = 32;
+
+pub fn register_referral_code(ctx: Context<RegisterReferralCode>, code: String) -> Result<()> {
+    validate_referral_code(&code)?;
+
+    let referrer = ctx.accounts.referrer.key();
+    let referral = &mut ctx.accounts.referral;
+    referral.referrer = referrer;
+    referral.code_hash = referral_code_hash(&code);
+    referral.code = code.clone();
+    referral.bump = ctx.bumps.referral;
+
+    // Create the claim referral shares accrue to, so sends never have to
+    let claim = &mut ctx.accounts.referrer_claim;
+    if claim.recipient == Pubkey::default() {
+        claim.recipient = referrer;
+        claim.rent_payer = referrer;
+    }
+
+    emit!(ReferralCodeRegistered { referrer, code });
+
+    Ok(())
+}
+
+pub fn validate_referral_code(code: &str) -> Result<()> {
+    require!(
+        !code.is_empty() && code.len() <= MAX_REFERRAL_CODE_LEN,
+        ReferralError::InvalidReferralCode
+    );
+    Ok(())
+}
+
+/// Key a referral code's PDA is derived from
+pub fn referral_code_hash(code: &str) -> [u8; 32] {
+    hash(code.as_bytes()).to_bytes()
+}
+
+/// Move the referrer's slice of the owner's share from the owner's send bucket
+/// to the referrer's claim, when the send was made through a referral code
+///
+/// `net_fee` is what the mailer kept of the fee; of a priority fee only the
+/// owner's share counts, the rest having gone to the sender's claim. Returns
+/// the amount paid to the referrer.
+pub(crate) fn pay_referral(
+    accounts: &mut SendMessage,
+    net_fee: u64,
+    priority: bool,
+) -> Result<u64> {
+    let Some(referral) = accounts.referral.as_ref() else {
+        return Ok(0);
+    };
+    let referrer = referral.referrer;
+    let sender = accounts.sender.key();
+    require_keys_neq!(referrer, sender, ReferralError::SelfReferral);
+
+    let claim = accounts
+        .referrer_claim
+        .as_mut()
+        .ok_or(ReferralError::InvalidReferrerClaim)?;
+    let (expected, _) = Pubkey::find_program_address(&[b"claim", referrer.as_ref()], &crate::ID);
+    require_keys_eq!(claim.key(), expected, ReferralError::InvalidReferrerClaim);
+
+    let owner_amount = if priority {
+        standard_fee(net_fee)?
+    } else {
+        net_fee
+    };
+    let amount = referral_amount(owner_amount, accounts.mailer.referral_bps)?;
+    if amount == 0 {
+        return Ok(0);
+    }
+    accounts.owner_bucket.claimable = accounts
+        .owner_bucket
+        .claimable
+        .checked_sub(amount)
+        .ok_or(FeeError::MathOverflow)?;
+    let mint = accounts.owner_bucket.mint;
+    let started = credit_claim(
+        claim,
+        &accounts.mailer,
+        mint,
+        referrer,
+        accounts.payer.key(),
+        amount,
+    )?;
+    if started && claim.mode != ClaimMode::Streaming {
+        expiry::track(
+            &accounts.expiry_index,
+            &accounts.payer.to_account_info(),
+            &accounts.system_program.to_account_info(),
+            referrer,
+            claim.timestamp,
+        )?;
+    }
+
+    emit!(ReferralPaid {
+        referrer,
+        sender,
+        mint,
+        amount,
+    });
+
+    Ok(amount)
+}
+
+#[derive(Accounts)]
+#[instruction(code: String)]
+pub struct RegisterReferralCode<'info> {
+    /// Fails if the code is already taken
+    #[account(
+        init,
+        payer = referrer,
+        space = 8 + ReferralCode::INIT_SPACE,
+        seeds = [b"referral", referral_code_hash(&code).as_ref()],
+        bump
+    )]
+    pub referral: Account<'info, ReferralCode>,
+
+    #[account(
+        init_if_needed,
+        payer = referrer,
+        space = 8 + RecipientClaim::INIT_SPACE,
+        seeds = [b"claim", referrer.key().as_ref()],
+        bump
+    )]
+    pub referrer_claim: Account<'info, RecipientClaim>,
+
+    #[account(mut)]
+    pub referrer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct ReferralCode {
+    /// Wallet whose claim receives the referral share of sends made with the code
+    pub referrer: Pubkey,
+    /// `referral_code_hash(code)`; seeds the referral PDA
+    pub code_hash: [u8; 32],
+    #[max_len(MAX_REFERRAL_CODE_LEN)]
+    pub code: String,
+    pub bump: u8,
+}
+
+#[event]
+pub struct ReferralCodeRegistered {
+    pub referrer: Pubkey,
+    pub code: String,
+}
+
+#[event]
+pub struct ReferralPaid {
+    pub referrer: Pubkey,
+    pub sender: Pubkey,
+    pub mint: Pubkey,
+    pub amount: u64,
+}
+
+#[error_code(offset = 7100)]
+pub enum ReferralError {
+    #[msg("Referral code must be 1 to 32 bytes")]
+    InvalidReferralCode,
+    #[msg("Referrer claim must be the referral code's referrer's claim PDA")]
+    InvalidReferrerClaim,
+    #[msg("Senders cannot refer themselves")]
+    SelfReferral,
+    #[msg("Channel posts and group sends do not accept referrals")]
+    ReferralNotAccepted,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn referral_codes_are_bounded() {
+        assert!(validate_referral_code("wallet-x").is_ok());
+        assert!(validate_referral_code("").is_err());
+        assert!(validate_referral_code(&"x".repeat(MAX_REFERRAL_CODE_LEN)).is_ok());
+        assert!(validate_referral_code(&"x".repeat(MAX_REFERRAL_CODE_LEN + 1)).is_err());
+    }
+}
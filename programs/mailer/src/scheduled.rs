@@ -0,0 +1,360 @@
+//! Scheduled sends.
+//!
+//! `schedule_send` escrows the standard fee of a prepared message in a
+//! `ScheduledMessage` PDA (`[b"scheduled", sender, sha256(mail_id)]`). Once
+//! `execute_after` has passed, anyone may crank `execute_scheduled`, which
+//! books the fee to the owner and emits the message as a standard prepared
+//! send. Until then the sender may cancel and get the fee back.
+
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{self, Mint, TokenAccount, TokenInterface, TransferChecked};
+use mailbox_common::seeds;
+use pyth_solana_receiver_sdk::price_update::PriceUpdateV2;
+
+use crate::admin::{MailerState, MailerStats};
+use crate::compliance::ComplianceError;
+use crate::escrow::release;
+use crate::fees::{standard_fee, FeeError, FeeSource, OwnerBucket};
+use crate::messaging::{
+    emit_send_event, mail_id_hash, message_id, RateLimit, StandardPreparedMailSent,
+};
+use crate::tokens::{current_send_fee, TokenConfig, TokenError};
+
+/// Maximum length of a scheduled message's mail ID, in bytes
+pub const MAX_SCHEDULED_MAIL_ID_LEN: usize = 64;
+
+pub fn schedule_send(
+    ctx: Context<ScheduleSend>,
+    mail_id: String,
+    execute_after: i64,
+) -> Result<()> {
+    require!(
+        !mail_id.is_empty() && mail_id.len() <= MAX_SCHEDULED_MAIL_ID_LEN,
+        ScheduleError::InvalidScheduledMailId
+    );
+    let current_time = Clock::get()?.unix_timestamp;
+    require!(
+        execute_after > current_time,
+        ScheduleError::InvalidExecuteAfter
+    );
+    let max_per_day = ctx.accounts.mailer.max_messages_per_day;
+    ctx.accounts.rate_limit.sender = ctx.accounts.sender.key();
+    ctx.accounts
+        .rate_limit
+        .record(1, max_per_day, current_time)?;
+
+    let send_fee = current_send_fee(
+        &ctx.accounts.token_config,
+        &ctx.accounts.mailer,
+        ctx.accounts.price_update.as_deref(),
+    )?;
+    let fee = standard_fee(send_fee)?;
+    let balance_before = ctx.accounts.mailer_usdc_account.amount;
+    let transfer_ctx = CpiContext::new(
+        ctx.accounts.token_program.to_account_info(),
+        TransferChecked {
+            from: ctx.accounts.sender_usdc_account.to_account_info(),
+            mint: ctx.accounts.mint.to_account_info(),
+            to: ctx.accounts.mailer_usdc_account.to_account_info(),
+            authority: ctx.accounts.sender.to_account_info(),
+        },
+    );
+    token_interface::transfer_checked(transfer_ctx, fee, ctx.accounts.mint.decimals)?;
+
+    // Escrow what actually arrived, net of any Token-2022 transfer fee
+    ctx.accounts.mailer_usdc_account.reload()?;
+    let received = ctx
+        .accounts
+        .mailer_usdc_account
+        .amount
+        .checked_sub(balance_before)
+        .ok_or(FeeError::MathOverflow)?;
+
+    let scheduled = &mut ctx.accounts.scheduled;
+    scheduled.sender = ctx.accounts.sender.key();
+    scheduled.mint = ctx.accounts.mint.key();
+    scheduled.amount = received;
+    scheduled.execute_after = execute_after;
+    scheduled.mail_id = mail_id.clone();
+    scheduled.bump = ctx.bumps.scheduled;
+    scheduled.rent_payer = ctx.accounts.payer.key();
+
+    emit!(SendScheduled {
+        sender: scheduled.sender,
+        mail_id,
+        execute_after,
+        mint: scheduled.mint,
+        amount: received,
+    });
+
+    Ok(())
+}
+
+pub fn execute_scheduled(ctx: Context<ExecuteScheduled>) -> Result<()> {
+    let scheduled = &ctx.accounts.scheduled;
+    let clock = Clock::get()?;
+    require!(
+        clock.unix_timestamp >= scheduled.execute_after,
+        ScheduleError::NotYetExecutable
+    );
+
+    ctx.accounts.owner_bucket.credit(scheduled.amount)?;
+    ctx.accounts.owner_bucket.record_fees(scheduled.amount)?;
+    let message_seq = ctx.accounts.stats.record_sends(1, false)?;
+    let message_id = message_id(&scheduled.sender, message_seq, clock.slot);
+
+    emit!(ScheduledSendExecuted {
+        sender: scheduled.sender,
+        message_id,
+        cranker: ctx.accounts.cranker.key(),
+    });
+
+    let event = StandardPreparedMailSent {
+        from: scheduled.sender,
+        to: scheduled.sender, // Messages are sent to self
+        to_external: None,
+        message_seq,
+        message_id,
+        timestamp: clock.unix_timestamp,
+        client_ref: None,
+        mail_id: scheduled.mail_id.clone(),
+    };
+    emit_send_event!(ctx, event);
+
+    Ok(())
+}
+
+pub fn cancel_scheduled(ctx: Context<CancelScheduled>) -> Result<()> {
+    let scheduled = &ctx.accounts.scheduled;
+    release(
+        &ctx.accounts.mailer,
+        &ctx.accounts.mint,
+        &ctx.accounts.mailer_usdc_account,
+        &ctx.accounts.sender_usdc_account,
+        &ctx.accounts.token_program,
+        scheduled.amount,
+    )?;
+
+    emit!(ScheduledSendCancelled {
+        sender: scheduled.sender,
+        mail_id: scheduled.mail_id.clone(),
+        refunded: scheduled.amount,
+    });
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(mail_id: String)]
+pub struct ScheduleSend<'info> {
+    /// Fails if the sender already scheduled this mail ID
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + ScheduledMessage::INIT_SPACE,
+        seeds = [b"scheduled", sender.key().as_ref(), mail_id_hash(&mail_id).as_ref()],
+        bump
+    )]
+    pub scheduled: Account<'info, ScheduledMessage>,
+
+    #[account(seeds = [seeds::MAILER], bump = mailer.bump)]
+    pub mailer: Account<'info, MailerState>,
+
+    #[account(
+        seeds = [b"token", mint.key().as_ref()],
+        bump = token_config.bump,
+        constraint = token_config.enabled @ TokenError::TokenDisabled
+    )]
+    pub token_config: Account<'info, TokenConfig>,
+
+    /// Fresh Pyth price for `mint`; required only when `token_config` has a price feed
+    pub price_update: Option<Account<'info, PriceUpdateV2>>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    pub sender: Signer<'info>,
+
+    /// Pays rent for any PDAs created by this send. Usually the sender, but
+    /// may be a relayer sponsoring SOL for senders who only hold fee tokens.
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = sender,
+        associated_token::token_program = token_program
+    )]
+    pub sender_usdc_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = mailer,
+        associated_token::token_program = token_program
+    )]
+    pub mailer_usdc_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// Sender's daily message counter, created on first send
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + RateLimit::INIT_SPACE,
+        seeds = [b"rate", sender.key().as_ref()],
+        bump
+    )]
+    pub rate_limit: Account<'info, RateLimit>,
+
+    /// CHECK: Sender's compliance denylist marker; must not exist
+    #[account(
+        seeds = [b"deny", sender.key().as_ref()],
+        bump,
+        constraint = denylist.data_is_empty() @ ComplianceError::AddressDenied
+    )]
+    pub denylist: UncheckedAccount<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+pub struct ExecuteScheduled<'info> {
+    /// Closed on execution; rent returns to whoever funded it
+    #[account(
+        mut,
+        close = rent_payer,
+        seeds = [
+            b"scheduled",
+            scheduled.sender.as_ref(),
+            mail_id_hash(&scheduled.mail_id).as_ref()
+        ],
+        bump = scheduled.bump,
+        has_one = rent_payer @ ScheduleError::InvalidScheduledRentPayer
+    )]
+    pub scheduled: Account<'info, ScheduledMessage>,
+
+    #[account(seeds = [seeds::MAILER], bump = mailer.bump)]
+    pub mailer: Account<'info, MailerState>,
+
+    #[account(mut, seeds = [b"mailer_stats"], bump = stats.bump)]
+    pub stats: Account<'info, MailerStats>,
+
+    /// Owner share bucket for send fees in the escrowed mint
+    #[account(
+        mut,
+        seeds = [b"bucket", scheduled.mint.as_ref(), FeeSource::Send.seed()],
+        bump = owner_bucket.bump
+    )]
+    pub owner_bucket: Account<'info, OwnerBucket>,
+
+    /// CHECK: Funded the scheduled message's rent, validated by `has_one`; receives it back
+    #[account(mut)]
+    pub rent_payer: UncheckedAccount<'info>,
+
+    /// Anyone may crank a scheduled send once it is due
+    pub cranker: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CancelScheduled<'info> {
+    /// Closed on cancellation; rent returns to whoever funded it
+    #[account(
+        mut,
+        close = rent_payer,
+        seeds = [
+            b"scheduled",
+            sender.key().as_ref(),
+            mail_id_hash(&scheduled.mail_id).as_ref()
+        ],
+        bump = scheduled.bump,
+        has_one = sender @ ScheduleError::InvalidScheduledSender,
+        has_one = rent_payer @ ScheduleError::InvalidScheduledRentPayer,
+        has_one = mint @ TokenError::ClaimMintMismatch
+    )]
+    pub scheduled: Account<'info, ScheduledMessage>,
+
+    #[account(seeds = [seeds::MAILER], bump = mailer.bump)]
+    pub mailer: Account<'info, MailerState>,
+
+    pub sender: Signer<'info>,
+
+    /// CHECK: Funded the scheduled message's rent, validated by `has_one`; receives it back
+    #[account(mut)]
+    pub rent_payer: UncheckedAccount<'info>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = sender,
+        associated_token::token_program = token_program
+    )]
+    pub sender_usdc_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = mailer,
+        associated_token::token_program = token_program
+    )]
+    pub mailer_usdc_account: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct ScheduledMessage {
+    pub sender: Pubkey,
+    /// Mint the escrowed fee is held in
+    pub mint: Pubkey,
+    /// Escrowed standard fee, in `mint` base units
+    pub amount: u64,
+    /// Earliest Unix timestamp the send may be executed at
+    pub execute_after: i64,
+    #[max_len(MAX_SCHEDULED_MAIL_ID_LEN)]
+    pub mail_id: String,
+    pub bump: u8,
+    /// Funded this account's rent; refunded when the message is executed or cancelled
+    pub rent_payer: Pubkey,
+}
+
+#[event]
+pub struct SendScheduled {
+    pub sender: Pubkey,
+    pub mail_id: String,
+    pub execute_after: i64,
+    pub mint: Pubkey,
+    pub amount: u64,
+}
+
+/// Emitted by `execute_scheduled` alongside the `StandardPreparedMailSent` it delivers
+#[event]
+pub struct ScheduledSendExecuted {
+    pub sender: Pubkey,
+    pub message_id: [u8; 32],
+    pub cranker: Pubkey,
+}
+
+#[event]
+pub struct ScheduledSendCancelled {
+    pub sender: Pubkey,
+    pub mail_id: String,
+    pub refunded: u64,
+}
+
+#[error_code(offset = 7800)]
+pub enum ScheduleError {
+    #[msg("Scheduled mail ID must be 1 to 64 bytes")]
+    InvalidScheduledMailId,
+    #[msg("Scheduled sends must execute in the future")]
+    InvalidExecuteAfter,
+    #[msg("Scheduled send is not due yet")]
+    NotYetExecutable,
+    #[msg("Account is not the scheduled message's sender")]
+    InvalidScheduledSender,
+    #[msg("Account did not fund the scheduled message")]
+    InvalidScheduledRentPayer,
+}
@@ -0,0 +1,484 @@
+//! Session keys.
+//!
+//! A wallet opens a `Session` PDA (`[b"session", owner, session_key]`) letting
+//! a short-lived key, e.g. one kept on a phone, send messages on its behalf
+//! until `expires_at` and for at most `max_spend` in fees. Opening a session
+//! approves the mailer PDA as delegate of the owner's fee token account for
+//! `max_spend` more, so `send_with_session` draws fees from the owner's wallet
+//! without it signing each message. Messages and revenue shares are the
+//! owner's; the session key can do nothing but send.
+
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+use mailbox_common::seeds;
+use pyth_solana_receiver_sdk::price_update::PriceUpdateV2;
+
+use crate::admin::{MailerState, MailerStats};
+use crate::claims::{ClaimMode, RecipientClaim};
+use crate::compliance::ComplianceError;
+use crate::expiry;
+use crate::fees::{
+    record_shares, release_allowance, reserve_allowance, standard_fee, transfer_from_allowance,
+    FeeError, FeeSource, OwnerBucket,
+};
+use crate::messaging::{
+    check_message_origin, check_message_size, emit_send_event, emitted_body, message_id,
+    PriorityMailSent, RateLimit, StandardMailSent,
+};
+use crate::tokens::{current_send_fee, TokenConfig, TokenError};
+
+/// Longest a session may last: 30 days in seconds
+pub const MAX_SESSION_DURATION: i64 = 30 * 24 * 60 * 60;
+
+pub fn create_session(
+    ctx: Context<CreateSession>,
+    session_key: Pubkey,
+    expires_at: i64,
+    max_spend: u64,
+) -> Result<()> {
+    let current_time = Clock::get()?.unix_timestamp;
+    require!(
+        expires_at > current_time && expires_at - current_time <= MAX_SESSION_DURATION,
+        SessionError::InvalidSessionExpiry
+    );
+
+    // Other sessions and billing allowances may already hold part of the mailer's allowance
+    reserve_allowance(
+        &ctx.accounts.mailer,
+        &ctx.accounts.owner_usdc_account,
+        &ctx.accounts.owner,
+        &ctx.accounts.token_program,
+        max_spend,
+    )?;
+
+    let session = &mut ctx.accounts.session;
+    session.owner = ctx.accounts.owner.key();
+    session.session_key = session_key;
+    session.mint = ctx.accounts.mint.key();
+    session.expires_at = expires_at;
+    session.max_spend = max_spend;
+    session.spent = 0;
+    session.bump = ctx.bumps.session;
+
+    emit!(SessionCreated {
+        owner: session.owner,
+        session_key,
+        mint: session.mint,
+        expires_at,
+        max_spend,
+    });
+
+    Ok(())
+}
+
+pub fn revoke_session(ctx: Context<RevokeSession>) -> Result<()> {
+    let session = &ctx.accounts.session;
+    let unspent = session.max_spend.saturating_sub(session.spent);
+
+    // Give back the allowance the session did not use
+    release_allowance(
+        &ctx.accounts.mailer,
+        &ctx.accounts.owner_usdc_account,
+        &ctx.accounts.owner,
+        &ctx.accounts.token_program,
+        unspent,
+    )?;
+
+    emit!(SessionRevoked {
+        owner: session.owner,
+        session_key: session.session_key,
+        spent: session.spent,
+    });
+
+    Ok(())
+}
+
+pub fn send_with_session(
+    mut ctx: Context<SendWithSession>,
+    subject: String,
+    body: String,
+    priority: bool,
+    client_ref: Option<[u8; 16]>,
+) -> Result<[u8; 32]> {
+    let owner = ctx.accounts.owner.key();
+    let clock = Clock::get()?;
+    require!(
+        clock.unix_timestamp < ctx.accounts.session.expires_at,
+        SessionError::SessionExpired
+    );
+    check_message_size(&ctx.accounts.mailer, &subject, &body)?;
+    check_message_origin(
+        &ctx.accounts.caller_policy,
+        &ctx.accounts.recipient_preferences,
+        &ctx.accounts.instructions,
+    )?;
+    let max_per_day = ctx.accounts.mailer.max_messages_per_day;
+    ctx.accounts.rate_limit.sender = owner;
+    ctx.accounts
+        .rate_limit
+        .record(1, max_per_day, clock.unix_timestamp)?;
+
+    let send_fee = current_send_fee(
+        &ctx.accounts.token_config,
+        &ctx.accounts.mailer,
+        ctx.accounts.price_update.as_deref(),
+    )?;
+    let fee = if priority {
+        send_fee
+    } else {
+        standard_fee(send_fee)?
+    };
+    ctx.accounts.session.spend(fee)?;
+
+    // The mailer PDA pulls the fee as the delegate approved in `create_session`
+    let received = transfer_from_allowance(
+        &ctx.accounts.mailer,
+        &ctx.accounts.mint,
+        &ctx.accounts.owner_usdc_account,
+        &mut ctx.accounts.mailer_usdc_account,
+        &ctx.accounts.token_program,
+        fee,
+    )?;
+
+    let accounts = &mut ctx.accounts;
+    if priority {
+        let started = record_shares(
+            &mut accounts.recipient_claim,
+            &mut accounts.owner_bucket,
+            &accounts.mailer,
+            owner,
+            accounts.payer.key(),
+            received,
+        )?;
+        // Streaming claims never expire, so keepers have nothing to sweep
+        if started && accounts.recipient_claim.mode == ClaimMode::Lump {
+            expiry::track(
+                &accounts.expiry_index,
+                &accounts.payer.to_account_info(),
+                &accounts.system_program.to_account_info(),
+                owner,
+                accounts.recipient_claim.timestamp,
+            )?;
+        }
+    } else {
+        accounts.owner_bucket.credit(received)?;
+    }
+
+    accounts.owner_bucket.record_fees(received)?;
+    let message_seq = accounts.stats.record_sends(1, priority)?;
+    let message_id = message_id(&owner, message_seq, clock.slot);
+
+    emit!(SessionSend {
+        owner,
+        session_key: accounts.session_key.key(),
+        message_id,
+        fee: received,
+    });
+
+    let (body, body_hash, body_len) = emitted_body(body);
+    if priority {
+        let event = PriorityMailSent {
+            from: owner,
+            to: owner, // Messages are sent to self
+            to_external: None,
+            message_seq,
+            message_id,
+            timestamp: clock.unix_timestamp,
+            client_ref,
+            subject,
+            body,
+            body_hash,
+            body_len,
+        };
+        emit_send_event!(ctx, event);
+    } else {
+        let event = StandardMailSent {
+            from: owner,
+            to: owner, // Messages are sent to self
+            to_external: None,
+            message_seq,
+            message_id,
+            timestamp: clock.unix_timestamp,
+            client_ref,
+            subject,
+            body,
+            body_hash,
+            body_len,
+        };
+        emit_send_event!(ctx, event);
+    }
+
+    Ok(message_id)
+}
+
+#[derive(Accounts)]
+#[instruction(session_key: Pubkey)]
+pub struct CreateSession<'info> {
+    /// Fails if this key already has an open session for the owner
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + Session::INIT_SPACE,
+        seeds = [b"session", owner.key().as_ref(), session_key.as_ref()],
+        bump
+    )]
+    pub session: Account<'info, Session>,
+
+    #[account(seeds = [seeds::MAILER], bump = mailer.bump)]
+    pub mailer: Account<'info, MailerState>,
+
+    #[account(
+        seeds = [b"token", mint.key().as_ref()],
+        bump = token_config.bump,
+        constraint = token_config.enabled @ TokenError::TokenDisabled
+    )]
+    pub token_config: Account<'info, TokenConfig>,
+
+    /// Mint the session's fees are paid in
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = owner,
+        associated_token::token_program = token_program
+    )]
+    pub owner_usdc_account: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RevokeSession<'info> {
+    /// Closed on revocation; rent returns to the owner
+    #[account(
+        mut,
+        close = owner,
+        seeds = [b"session", owner.key().as_ref(), session.session_key.as_ref()],
+        bump = session.bump,
+        has_one = owner @ SessionError::InvalidSessionOwner,
+        has_one = mint @ TokenError::ClaimMintMismatch
+    )]
+    pub session: Account<'info, Session>,
+
+    #[account(seeds = [seeds::MAILER], bump = mailer.bump)]
+    pub mailer: Account<'info, MailerState>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = owner,
+        associated_token::token_program = token_program
+    )]
+    pub owner_usdc_account: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+pub struct SendWithSession<'info> {
+    #[account(
+        mut,
+        seeds = [b"session", owner.key().as_ref(), session_key.key().as_ref()],
+        bump = session.bump,
+        has_one = owner @ SessionError::InvalidSessionOwner,
+        has_one = mint @ TokenError::ClaimMintMismatch
+    )]
+    pub session: Account<'info, Session>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + RecipientClaim::INIT_SPACE,
+        seeds = [b"claim", owner.key().as_ref()],
+        bump
+    )]
+    pub recipient_claim: Account<'info, RecipientClaim>,
+
+    #[account(seeds = [seeds::MAILER], bump = mailer.bump)]
+    pub mailer: Account<'info, MailerState>,
+
+    #[account(mut, seeds = [b"mailer_stats"], bump = stats.bump)]
+    pub stats: Account<'info, MailerStats>,
+
+    #[account(
+        seeds = [b"token", mint.key().as_ref()],
+        bump = token_config.bump,
+        constraint = token_config.enabled @ TokenError::TokenDisabled
+    )]
+    pub token_config: Account<'info, TokenConfig>,
+
+    /// Fresh Pyth price for `mint`; required only when `token_config` has a price feed
+    pub price_update: Option<Account<'info, PriceUpdateV2>>,
+
+    /// Owner share bucket for send fees in this mint
+    #[account(
+        mut,
+        seeds = [b"bucket", mint.key().as_ref(), FeeSource::Send.seed()],
+        bump = owner_bucket.bump
+    )]
+    pub owner_bucket: Account<'info, OwnerBucket>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    /// CHECK: Session's owner, validated by `has_one`; the message's sender
+    pub owner: UncheckedAccount<'info>,
+
+    pub session_key: Signer<'info>,
+
+    /// Pays rent for any PDAs created by this send; may be the session key or a relayer
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = owner,
+        associated_token::token_program = token_program
+    )]
+    pub owner_usdc_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = mailer,
+        associated_token::token_program = token_program
+    )]
+    pub mailer_usdc_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// CHECK: Session owner's preferences PDA; may be uninitialized, parsed in `check_message_origin`
+    #[account(seeds = [b"prefs", owner.key().as_ref()], bump)]
+    pub recipient_preferences: UncheckedAccount<'info>,
+
+    /// CHECK: Owner's CPI caller policy PDA; may be uninitialized, parsed in `check_message_origin`
+    #[account(seeds = [b"callers"], bump)]
+    pub caller_policy: UncheckedAccount<'info>,
+
+    /// CHECK: Instructions sysvar, validated by address
+    #[account(address = anchor_lang::solana_program::sysvar::instructions::ID)]
+    pub instructions: UncheckedAccount<'info>,
+
+    /// CHECK: Expiry bucket for the current week; only written, and verified in
+    /// `expiry::track`, when this send starts a new claim period
+    #[account(mut)]
+    pub expiry_index: UncheckedAccount<'info>,
+
+    /// Owner's daily message counter, created on first send
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + RateLimit::INIT_SPACE,
+        seeds = [b"rate", owner.key().as_ref()],
+        bump
+    )]
+    pub rate_limit: Account<'info, RateLimit>,
+
+    /// CHECK: Owner's compliance denylist marker; must not exist
+    #[account(
+        seeds = [b"deny", owner.key().as_ref()],
+        bump,
+        constraint = denylist.data_is_empty() @ ComplianceError::AddressDenied
+    )]
+    pub denylist: UncheckedAccount<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Session {
+    pub owner: Pubkey,
+    /// Key allowed to send on the owner's behalf
+    pub session_key: Pubkey,
+    /// Mint the session's fees are paid in
+    pub mint: Pubkey,
+    /// Unix time from which the session can no longer send
+    pub expires_at: i64,
+    /// Most the session may spend on fees, in `mint` base units
+    pub max_spend: u64,
+    /// Fees spent so far
+    pub spent: u64,
+    pub bump: u8,
+}
+
+impl Session {
+    /// Count `fee` against the spend limit
+    pub fn spend(&mut self, fee: u64) -> Result<()> {
+        let spent = self.spent.checked_add(fee).ok_or(FeeError::MathOverflow)?;
+        require!(spent <= self.max_spend, SessionError::SessionSpendExceeded);
+        self.spent = spent;
+        Ok(())
+    }
+}
+
+#[event]
+pub struct SessionCreated {
+    pub owner: Pubkey,
+    pub session_key: Pubkey,
+    pub mint: Pubkey,
+    pub expires_at: i64,
+    pub max_spend: u64,
+}
+
+#[event]
+pub struct SessionRevoked {
+    pub owner: Pubkey,
+    pub session_key: Pubkey,
+    pub spent: u64,
+}
+
+/// Emitted by `send_with_session` alongside the mail-sent event from the owner
+#[event]
+pub struct SessionSend {
+    pub owner: Pubkey,
+    pub session_key: Pubkey,
+    pub message_id: [u8; 32],
+    pub fee: u64,
+}
+
+#[error_code(offset = 8000)]
+pub enum SessionError {
+    #[msg("Session must expire in the future and within 30 days")]
+    InvalidSessionExpiry,
+    #[msg("Session has expired")]
+    SessionExpired,
+    #[msg("Send would exceed the session's spend limit")]
+    SessionSpendExceeded,
+    #[msg("Account is not the session's owner")]
+    InvalidSessionOwner,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sessions_cannot_spend_past_their_limit() {
+        let mut session = Session {
+            owner: Pubkey::new_unique(),
+            session_key: Pubkey::new_unique(),
+            mint: Pubkey::new_unique(),
+            expires_at: 0,
+            max_spend: 250_000,
+            spent: 0,
+            bump: 0,
+        };
+        session.spend(100_000).unwrap();
+        session.spend(100_000).unwrap();
+        assert!(session.spend(100_000).is_err());
+        assert_eq!(session.spent, 200_000);
+        session.spend(50_000).unwrap();
+        assert!(session.spend(1).is_err());
+    }
+}
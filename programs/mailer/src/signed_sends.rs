@@ -0,0 +1,387 @@
+//! Signed sends.
+//!
+//! A sender signs `signed_send_payload(sender, mail_id, nonce, expiry)` off
+//! chain, and any relayer may submit it with `send_with_signature`, placing
+//! the Ed25519 program's verification of that signature right before it in
+//! the transaction. The message is sent from, and billed to, the signer: the
+//! standard fee is pulled from their token account by the mailer PDA, which
+//! they must have approved as delegate. The relayer only pays SOL.
+//!
+//! Each sender's `NonceRegistry` PDA (`[b"nonce", sender]`) holds the next
+//! nonce it accepts, so every payload is relayed at most once and in order.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::ed25519_program;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::sysvar::instructions::get_instruction_relative;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+use mailbox_common::seeds;
+use pyth_solana_receiver_sdk::price_update::PriceUpdateV2;
+
+use crate::admin::{MailerState, MailerStats};
+use crate::compliance::ComplianceError;
+use crate::fees::{standard_fee, transfer_from_allowance, FeeError, FeeSource, OwnerBucket};
+use crate::messaging::{
+    check_message_origin, emit_send_event, message_id, RateLimit, StandardPreparedMailSent,
+};
+use crate::tokens::{current_send_fee, TokenConfig, TokenError};
+
+/// Domain separator of signed send payloads
+pub const SIGNED_SEND_DOMAIN: &[u8] = b"mailer-signed-send";
+
+/// Maximum length of a signed send's mail ID, in bytes
+pub const MAX_SIGNED_MAIL_ID_LEN: usize = 64;
+
+/// Layout of Ed25519 program instruction data: a signature count and padding
+/// byte, then per signature seven little-endian u16 offsets
+const ED25519_HEADER_LEN: usize = 2;
+const ED25519_OFFSETS_LEN: usize = 14;
+/// Instruction index meaning "this instruction" in Ed25519 offsets
+const CURRENT_INSTRUCTION: u16 = u16::MAX;
+
+pub fn send_with_signature(
+    ctx: Context<SendWithSignature>,
+    mail_id: String,
+    nonce: u64,
+    expiry: i64,
+) -> Result<[u8; 32]> {
+    require!(
+        !mail_id.is_empty() && mail_id.len() <= MAX_SIGNED_MAIL_ID_LEN,
+        SignedSendError::InvalidSignedMailId
+    );
+    let sender = ctx.accounts.sender.key();
+    let clock = Clock::get()?;
+    require!(
+        clock.unix_timestamp <= expiry,
+        SignedSendError::SignatureExpired
+    );
+
+    let ed25519_ix = get_instruction_relative(-1, &ctx.accounts.instructions.to_account_info())
+        .map_err(|_| error!(SignedSendError::MissingSignatureVerification))?;
+    verify_ed25519_instruction(
+        &ed25519_ix,
+        &sender,
+        &signed_send_payload(&sender, &mail_id, nonce, expiry),
+    )?;
+
+    let registry = &mut ctx.accounts.nonce_registry;
+    require!(nonce == registry.next_nonce, SignedSendError::InvalidNonce);
+    registry.sender = sender;
+    registry.next_nonce = nonce.checked_add(1).ok_or(FeeError::MathOverflow)?;
+    registry.bump = ctx.bumps.nonce_registry;
+
+    check_message_origin(
+        &ctx.accounts.caller_policy,
+        &ctx.accounts.recipient_preferences,
+        &ctx.accounts.instructions,
+    )?;
+    let max_per_day = ctx.accounts.mailer.max_messages_per_day;
+    ctx.accounts.rate_limit.sender = sender;
+    ctx.accounts
+        .rate_limit
+        .record(1, max_per_day, clock.unix_timestamp)?;
+
+    let send_fee = current_send_fee(
+        &ctx.accounts.token_config,
+        &ctx.accounts.mailer,
+        ctx.accounts.price_update.as_deref(),
+    )?;
+    let fee = standard_fee(send_fee)?;
+
+    // The signer did not sign this transaction, so the mailer PDA pulls the
+    // fee as their approved delegate
+    let received = transfer_from_allowance(
+        &ctx.accounts.mailer,
+        &ctx.accounts.mint,
+        &ctx.accounts.sender_usdc_account,
+        &mut ctx.accounts.mailer_usdc_account,
+        &ctx.accounts.token_program,
+        fee,
+    )?;
+
+    ctx.accounts.owner_bucket.credit(received)?;
+    ctx.accounts.owner_bucket.record_fees(received)?;
+    let message_seq = ctx.accounts.stats.record_sends(1, false)?;
+    let message_id = message_id(&sender, message_seq, clock.slot);
+
+    emit!(SignedSendRelayed {
+        sender,
+        relayer: ctx.accounts.relayer.key(),
+        nonce,
+        message_id,
+    });
+
+    let event = StandardPreparedMailSent {
+        from: sender,
+        to: sender, // Messages are sent to self
+        to_external: None,
+        message_seq,
+        message_id,
+        timestamp: clock.unix_timestamp,
+        client_ref: None,
+        mail_id,
+    };
+    emit_send_event!(ctx, event);
+
+    Ok(message_id)
+}
+
+/// Bytes a sender signs to authorize relaying a prepared send; the program ID
+/// binds the signature to this deployment
+pub fn signed_send_payload(sender: &Pubkey, mail_id: &str, nonce: u64, expiry: i64) -> Vec<u8> {
+    let mut payload = Vec::with_capacity(SIGNED_SEND_DOMAIN.len() + 80 + mail_id.len());
+    payload.extend_from_slice(SIGNED_SEND_DOMAIN);
+    payload.extend_from_slice(crate::ID.as_ref());
+    payload.extend_from_slice(sender.as_ref());
+    payload.extend_from_slice(&nonce.to_le_bytes());
+    payload.extend_from_slice(&expiry.to_le_bytes());
+    payload.extend_from_slice(mail_id.as_bytes());
+    payload
+}
+
+/// Check that `ix` is an Ed25519 program instruction verifying exactly one
+/// signature by `signer` over `message`, with all data inline
+///
+/// The Ed25519 program fails the transaction if the signature is invalid, so
+/// matching its inputs is enough.
+pub fn verify_ed25519_instruction(ix: &Instruction, signer: &Pubkey, message: &[u8]) -> Result<()> {
+    require_keys_eq!(
+        ix.program_id,
+        ed25519_program::ID,
+        SignedSendError::MissingSignatureVerification
+    );
+    let data = &ix.data;
+    require!(
+        ix.accounts.is_empty()
+            && data.len() >= ED25519_HEADER_LEN + ED25519_OFFSETS_LEN
+            && data[0] == 1,
+        SignedSendError::InvalidSignatureVerification
+    );
+
+    let offset = |i: usize| {
+        let at = ED25519_HEADER_LEN + 2 * i;
+        u16::from_le_bytes([data[at], data[at + 1]])
+    };
+    let (signature_ix, public_key_offset, public_key_ix) = (offset(1), offset(2), offset(3));
+    let (message_offset, message_size, message_ix) = (offset(4), offset(5), offset(6));
+    // Offsets pointing into other instructions could verify data we never see
+    require!(
+        signature_ix == CURRENT_INSTRUCTION
+            && public_key_ix == CURRENT_INSTRUCTION
+            && message_ix == CURRENT_INSTRUCTION,
+        SignedSendError::InvalidSignatureVerification
+    );
+
+    let public_key = slice(data, public_key_offset as usize, 32)?;
+    require!(
+        public_key == signer.as_ref(),
+        SignedSendError::InvalidSignatureVerification
+    );
+    let signed = slice(data, message_offset as usize, message_size as usize)?;
+    require!(
+        signed == message,
+        SignedSendError::InvalidSignatureVerification
+    );
+
+    Ok(())
+}
+
+fn slice(data: &[u8], start: usize, len: usize) -> Result<&[u8]> {
+    data.get(start..start.saturating_add(len))
+        .ok_or_else(|| error!(SignedSendError::InvalidSignatureVerification))
+}
+
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+pub struct SendWithSignature<'info> {
+    /// Sender's replay protection, created on their first signed send
+    #[account(
+        init_if_needed,
+        payer = relayer,
+        space = 8 + NonceRegistry::INIT_SPACE,
+        seeds = [b"nonce", sender.key().as_ref()],
+        bump
+    )]
+    pub nonce_registry: Account<'info, NonceRegistry>,
+
+    #[account(seeds = [seeds::MAILER], bump = mailer.bump)]
+    pub mailer: Account<'info, MailerState>,
+
+    #[account(mut, seeds = [b"mailer_stats"], bump = stats.bump)]
+    pub stats: Account<'info, MailerStats>,
+
+    #[account(
+        seeds = [b"token", mint.key().as_ref()],
+        bump = token_config.bump,
+        constraint = token_config.enabled @ TokenError::TokenDisabled
+    )]
+    pub token_config: Account<'info, TokenConfig>,
+
+    /// Fresh Pyth price for `mint`; required only when `token_config` has a price feed
+    pub price_update: Option<Account<'info, PriceUpdateV2>>,
+
+    /// Owner share bucket for send fees in this mint
+    #[account(
+        mut,
+        seeds = [b"bucket", mint.key().as_ref(), FeeSource::Send.seed()],
+        bump = owner_bucket.bump
+    )]
+    pub owner_bucket: Account<'info, OwnerBucket>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    /// CHECK: Signer of the payload, verified against the Ed25519 instruction
+    pub sender: UncheckedAccount<'info>,
+
+    /// Submits the transaction and pays its SOL costs
+    #[account(mut)]
+    pub relayer: Signer<'info>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = sender,
+        associated_token::token_program = token_program
+    )]
+    pub sender_usdc_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = mailer,
+        associated_token::token_program = token_program
+    )]
+    pub mailer_usdc_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// CHECK: Sender's preferences PDA; may be uninitialized, parsed in `check_message_origin`
+    #[account(seeds = [b"prefs", sender.key().as_ref()], bump)]
+    pub recipient_preferences: UncheckedAccount<'info>,
+
+    /// CHECK: Owner's CPI caller policy PDA; may be uninitialized, parsed in `check_message_origin`
+    #[account(seeds = [b"callers"], bump)]
+    pub caller_policy: UncheckedAccount<'info>,
+
+    /// CHECK: Instructions sysvar, validated by address
+    #[account(address = anchor_lang::solana_program::sysvar::instructions::ID)]
+    pub instructions: UncheckedAccount<'info>,
+
+    /// Sender's daily message counter, created on first send
+    #[account(
+        init_if_needed,
+        payer = relayer,
+        space = 8 + RateLimit::INIT_SPACE,
+        seeds = [b"rate", sender.key().as_ref()],
+        bump
+    )]
+    pub rate_limit: Account<'info, RateLimit>,
+
+    /// CHECK: Sender's compliance denylist marker; must not exist
+    #[account(
+        seeds = [b"deny", sender.key().as_ref()],
+        bump,
+        constraint = denylist.data_is_empty() @ ComplianceError::AddressDenied
+    )]
+    pub denylist: UncheckedAccount<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct NonceRegistry {
+    pub sender: Pubkey,
+    /// Nonce the sender's next signed send must carry
+    pub next_nonce: u64,
+    pub bump: u8,
+}
+
+/// Emitted by `send_with_signature` alongside the `StandardPreparedMailSent` it delivers
+#[event]
+pub struct SignedSendRelayed {
+    pub sender: Pubkey,
+    pub relayer: Pubkey,
+    pub nonce: u64,
+    pub message_id: [u8; 32],
+}
+
+#[error_code(offset = 8100)]
+pub enum SignedSendError {
+    #[msg("Signed mail ID must be 1 to 64 bytes")]
+    InvalidSignedMailId,
+    #[msg("Signed payload has expired")]
+    SignatureExpired,
+    #[msg("Signed sends must directly follow an Ed25519 program instruction")]
+    MissingSignatureVerification,
+    #[msg("Ed25519 instruction does not verify the sender's signature over the payload")]
+    InvalidSignatureVerification,
+    #[msg("Nonce is not the sender's next nonce")]
+    InvalidNonce,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Ed25519 program instruction data for one inline signature
+    fn ed25519_data(signer: &Pubkey, message: &[u8], other_ix: bool) -> Vec<u8> {
+        let public_key_offset = (ED25519_HEADER_LEN + ED25519_OFFSETS_LEN) as u16;
+        let signature_offset = public_key_offset + 32;
+        let message_offset = signature_offset + 64;
+        let ix_index = if other_ix { 0 } else { CURRENT_INSTRUCTION };
+        let mut data = vec![1, 0];
+        for field in [
+            signature_offset,
+            ix_index,
+            public_key_offset,
+            ix_index,
+            message_offset,
+            message.len() as u16,
+            ix_index,
+        ] {
+            data.extend_from_slice(&field.to_le_bytes());
+        }
+        data.extend_from_slice(signer.as_ref());
+        data.extend_from_slice(&[0; 64]);
+        data.extend_from_slice(message);
+        data
+    }
+
+    fn ed25519_ix(data: Vec<u8>) -> Instruction {
+        Instruction {
+            program_id: ed25519_program::ID,
+            accounts: vec![],
+            data,
+        }
+    }
+
+    #[test]
+    fn only_the_senders_signature_over_the_payload_is_accepted() {
+        let sender = Pubkey::new_unique();
+        let payload = signed_send_payload(&sender, "mail-1", 0, 100);
+
+        let ix = ed25519_ix(ed25519_data(&sender, &payload, false));
+        assert!(verify_ed25519_instruction(&ix, &sender, &payload).is_ok());
+
+        let other = Pubkey::new_unique();
+        assert!(verify_ed25519_instruction(&ix, &other, &payload).is_err());
+        let replayed = signed_send_payload(&sender, "mail-1", 1, 100);
+        assert!(verify_ed25519_instruction(&ix, &sender, &replayed).is_err());
+
+        let elsewhere = ed25519_ix(ed25519_data(&sender, &payload, true));
+        assert!(verify_ed25519_instruction(&elsewhere, &sender, &payload).is_err());
+
+        let mut not_ed25519 = ix.clone();
+        not_ed25519.program_id = Pubkey::new_unique();
+        assert!(verify_ed25519_instruction(&not_ed25519, &sender, &payload).is_err());
+    }
+
+    #[test]
+    fn truncated_instruction_data_is_rejected() {
+        let sender = Pubkey::new_unique();
+        let payload = signed_send_payload(&sender, "mail-1", 0, 100);
+        let mut data = ed25519_data(&sender, &payload, false);
+        data.truncate(data.len() - 1);
+        assert!(verify_ed25519_instruction(&ed25519_ix(data), &sender, &payload).is_err());
+    }
+}
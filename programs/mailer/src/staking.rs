@@ -0,0 +1,306 @@
+//! Staking tiers.
+//!
+//! Users lock the mailer's USDC in a `Stake` PDA (`[b"stake", owner]`) with
+//! `stake` and take it all back with `unstake` once `UNSTAKE_LOCK` has passed
+//! since their last deposit. The owner configures up to `MAX_STAKE_TIERS`
+//! tiers, each a minimum stake and a fee discount. Sends that pass the
+//! sender's stake are charged the fee less the discount of the highest tier
+//! it reaches. The base fee is unchanged, and a stake discount does not stack
+//! with an NFT holder discount; the larger of the two applies.
+
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{self, Mint, TokenAccount, TokenInterface, TransferChecked};
+use mailbox_common::fees::BPS_DENOMINATOR;
+use mailbox_common::seeds;
+
+use crate::admin::MailerState;
+use crate::escrow::release;
+use crate::fees::{FeeError, SetFee};
+use crate::messaging::SendMessage;
+use crate::tokens::TokenError;
+
+/// Most discount tiers the owner can configure
+pub const MAX_STAKE_TIERS: usize = 4;
+
+/// Time a deposit stays locked before the stake can be withdrawn, in seconds
+pub const UNSTAKE_LOCK: i64 = 7 * 24 * 60 * 60;
+
+pub fn set_stake_tiers(ctx: Context<SetFee>, tiers: Vec<StakeTier>) -> Result<()> {
+    require!(valid_tiers(&tiers), StakeError::InvalidStakeTiers);
+
+    let mailer = &mut ctx.accounts.mailer;
+    mailer.stake_tiers = [StakeTier::default(); MAX_STAKE_TIERS];
+    mailer.stake_tiers[..tiers.len()].copy_from_slice(&tiers);
+
+    emit!(StakeTiersUpdated { tiers });
+
+    Ok(())
+}
+
+pub fn stake(ctx: Context<StakeTokens>, amount: u64) -> Result<()> {
+    require!(amount > 0, StakeError::InvalidStakeAmount);
+
+    let balance_before = ctx.accounts.mailer_usdc_account.amount;
+    let transfer_ctx = CpiContext::new(
+        ctx.accounts.token_program.to_account_info(),
+        TransferChecked {
+            from: ctx.accounts.owner_usdc_account.to_account_info(),
+            mint: ctx.accounts.mint.to_account_info(),
+            to: ctx.accounts.mailer_usdc_account.to_account_info(),
+            authority: ctx.accounts.owner.to_account_info(),
+        },
+    );
+    token_interface::transfer_checked(transfer_ctx, amount, ctx.accounts.mint.decimals)?;
+
+    // Stake what actually arrived, net of any Token-2022 transfer fee
+    ctx.accounts.mailer_usdc_account.reload()?;
+    let received = ctx
+        .accounts
+        .mailer_usdc_account
+        .amount
+        .checked_sub(balance_before)
+        .ok_or(FeeError::MathOverflow)?;
+
+    let stake = &mut ctx.accounts.stake;
+    stake.owner = ctx.accounts.owner.key();
+    stake.mint = ctx.accounts.mint.key();
+    stake.bump = ctx.bumps.stake;
+    stake.amount = stake
+        .amount
+        .checked_add(received)
+        .ok_or(FeeError::MathOverflow)?;
+    stake.locked_until = Clock::get()?
+        .unix_timestamp
+        .checked_add(UNSTAKE_LOCK)
+        .ok_or(FeeError::MathOverflow)?;
+
+    emit!(Staked {
+        owner: stake.owner,
+        amount: received,
+        total: stake.amount,
+        locked_until: stake.locked_until,
+    });
+
+    Ok(())
+}
+
+pub fn unstake(ctx: Context<Unstake>) -> Result<()> {
+    let stake = &ctx.accounts.stake;
+    require!(
+        Clock::get()?.unix_timestamp >= stake.locked_until,
+        StakeError::StakeLocked
+    );
+
+    release(
+        &ctx.accounts.mailer,
+        &ctx.accounts.mint,
+        &ctx.accounts.mailer_usdc_account,
+        &ctx.accounts.owner_usdc_account,
+        &ctx.accounts.token_program,
+        stake.amount,
+    )?;
+
+    emit!(Unstaked {
+        owner: stake.owner,
+        amount: stake.amount,
+    });
+
+    Ok(())
+}
+
+/// Discount the sender's stake earns, in basis points; 0 when it passes no stake
+pub(crate) fn staker_discount_bps(accounts: &SendMessage) -> u16 {
+    accounts.stake.as_ref().map_or(0, |stake| {
+        tier_discount_bps(&accounts.mailer.stake_tiers, stake.amount)
+    })
+}
+
+/// Discount of the highest configured tier `amount` reaches
+pub fn tier_discount_bps(tiers: &[StakeTier], amount: u64) -> u16 {
+    tiers
+        .iter()
+        .filter(|tier| tier.min_stake > 0 && amount >= tier.min_stake)
+        .map(|tier| tier.discount_bps)
+        .max()
+        .unwrap_or(0)
+}
+
+/// Whether `tiers` fit the mailer and rise strictly in stake and discount
+pub fn valid_tiers(tiers: &[StakeTier]) -> bool {
+    tiers.len() <= MAX_STAKE_TIERS
+        && tiers.first().is_none_or(|tier| tier.min_stake > 0)
+        && tiers
+            .last()
+            .is_none_or(|tier| tier.discount_bps <= BPS_DENOMINATOR)
+        && tiers.windows(2).all(|pair| {
+            pair[0].min_stake < pair[1].min_stake && pair[0].discount_bps < pair[1].discount_bps
+        })
+}
+
+#[derive(Accounts)]
+pub struct StakeTokens<'info> {
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = 8 + Stake::INIT_SPACE,
+        seeds = [b"stake", owner.key().as_ref()],
+        bump
+    )]
+    pub stake: Account<'info, Stake>,
+
+    #[account(seeds = [seeds::MAILER], bump = mailer.bump)]
+    pub mailer: Account<'info, MailerState>,
+
+    /// Stakes are held in the mailer's USDC mint, so tiers compare like amounts
+    #[account(address = mailer.usdc_mint @ TokenError::ClaimMintMismatch)]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = owner,
+        associated_token::token_program = token_program
+    )]
+    pub owner_usdc_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = mailer,
+        associated_token::token_program = token_program
+    )]
+    pub mailer_usdc_account: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Unstake<'info> {
+    /// Closed on withdrawal; rent returns to the owner
+    #[account(
+        mut,
+        close = owner,
+        seeds = [b"stake", owner.key().as_ref()],
+        bump = stake.bump,
+        has_one = mint @ TokenError::ClaimMintMismatch
+    )]
+    pub stake: Account<'info, Stake>,
+
+    #[account(seeds = [seeds::MAILER], bump = mailer.bump)]
+    pub mailer: Account<'info, MailerState>,
+
+    /// Mint the stake was deposited in, even if the mailer has since moved to another
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = owner,
+        associated_token::token_program = token_program
+    )]
+    pub owner_usdc_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = mailer,
+        associated_token::token_program = token_program
+    )]
+    pub mailer_usdc_account: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+/// Fee discount for senders staking at least `min_stake`
+#[derive(
+    AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq, InitSpace,
+)]
+pub struct StakeTier {
+    /// Stake required, in USDC base units; 0 marks an unused slot
+    pub min_stake: u64,
+    /// Discount in basis points; 10000 waives the fee
+    pub discount_bps: u16,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Stake {
+    pub owner: Pubkey,
+    /// Mint the stake is held in
+    pub mint: Pubkey,
+    /// Staked, in `mint` base units
+    pub amount: u64,
+    /// Unix timestamp from which the stake can be withdrawn; each deposit resets it
+    pub locked_until: i64,
+    pub bump: u8,
+}
+
+#[event]
+pub struct StakeTiersUpdated {
+    pub tiers: Vec<StakeTier>,
+}
+
+#[event]
+pub struct Staked {
+    pub owner: Pubkey,
+    /// Amount staked, net of any transfer fee
+    pub amount: u64,
+    pub total: u64,
+    pub locked_until: i64,
+}
+
+#[event]
+pub struct Unstaked {
+    pub owner: Pubkey,
+    pub amount: u64,
+}
+
+#[error_code(offset = 8400)]
+pub enum StakeError {
+    #[msg("Stake tiers must rise strictly in stake and discount, up to 4 tiers and 100%")]
+    InvalidStakeTiers,
+    #[msg("Stake amount must be greater than zero")]
+    InvalidStakeAmount,
+    #[msg("Stake is still locked")]
+    StakeLocked,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tier(min_stake: u64, discount_bps: u16) -> StakeTier {
+        StakeTier {
+            min_stake,
+            discount_bps,
+        }
+    }
+
+    #[test]
+    fn highest_reached_tier_applies() {
+        let mut tiers = [StakeTier::default(); MAX_STAKE_TIERS];
+        tiers[..2].copy_from_slice(&[tier(100_000_000, 1_000), tier(1_000_000_000, 2_500)]);
+        assert_eq!(tier_discount_bps(&tiers, 0), 0);
+        assert_eq!(tier_discount_bps(&tiers, 99_999_999), 0);
+        assert_eq!(tier_discount_bps(&tiers, 100_000_000), 1_000);
+        assert_eq!(tier_discount_bps(&tiers, u64::MAX), 2_500);
+    }
+
+    #[test]
+    fn tiers_must_rise() {
+        assert!(valid_tiers(&[]));
+        assert!(valid_tiers(&[tier(1, 100), tier(2, BPS_DENOMINATOR)]));
+        assert!(!valid_tiers(&[tier(0, 100)]));
+        assert!(!valid_tiers(&[tier(2, 100), tier(1, 200)]));
+        assert!(!valid_tiers(&[tier(1, 200), tier(2, 200)]));
+        assert!(!valid_tiers(&[tier(1, BPS_DENOMINATOR + 1)]));
+        assert!(!valid_tiers(&[tier(1, 1); MAX_STAKE_TIERS + 1]));
+    }
+}
@@ -0,0 +1,398 @@
+//! Subscription plans.
+//!
+//! The owner publishes `SubscriptionPlan` PDAs at `[b"plan", id]` with a
+//! price in the mailer's USDC mint, a duration and an optional cap on the
+//! standard messages a period covers. `purchase_subscription` books the price
+//! to the owner's send bucket and records the period on the buyer's
+//! `PlanSubscription` PDA (`[b"plan_subscription", subscriber]`). Standard
+//! sends that pass an active subscription with cap left are not charged a
+//! fee; priority sends, channel posts and group sends are unaffected.
+//!
+//! Buying the same plan again while subscribed extends the period and adds
+//! the plan's cap; buying a different plan, or buying after expiry, starts a
+//! fresh period. A subscription keeps the terms it was bought on if its plan
+//! is later closed.
+
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{self, Mint, TokenAccount, TokenInterface, TransferChecked};
+use mailbox_common::seeds;
+
+use crate::admin::{MailerError, MailerState};
+use crate::fees::{FeeError, FeeSource, OwnerBucket};
+use crate::messaging::SendMessage;
+use crate::tokens::TokenError;
+
+pub fn create_subscription_plan(
+    ctx: Context<CreateSubscriptionPlan>,
+    id: u64,
+    price: u64,
+    duration: i64,
+    message_cap: u64,
+) -> Result<()> {
+    require!(price > 0, SubscriptionError::InvalidPlanPrice);
+    require!(duration > 0, SubscriptionError::InvalidPlanDuration);
+
+    let plan = &mut ctx.accounts.plan;
+    plan.id = id;
+    plan.mint = ctx.accounts.mailer.usdc_mint;
+    plan.price = price;
+    plan.duration = duration;
+    plan.message_cap = message_cap;
+    plan.bump = ctx.bumps.plan;
+
+    emit!(SubscriptionPlanCreated {
+        id,
+        mint: plan.mint,
+        price,
+        duration,
+        message_cap,
+    });
+
+    Ok(())
+}
+
+pub fn close_subscription_plan(ctx: Context<CloseSubscriptionPlan>) -> Result<()> {
+    emit!(SubscriptionPlanClosed {
+        id: ctx.accounts.plan.id,
+    });
+
+    Ok(())
+}
+
+pub fn purchase_subscription(ctx: Context<PurchaseSubscription>, plan_id: u64) -> Result<()> {
+    let price = ctx.accounts.plan.price;
+    let balance_before = ctx.accounts.mailer_usdc_account.amount;
+    let transfer_ctx = CpiContext::new(
+        ctx.accounts.token_program.to_account_info(),
+        TransferChecked {
+            from: ctx.accounts.subscriber_usdc_account.to_account_info(),
+            mint: ctx.accounts.mint.to_account_info(),
+            to: ctx.accounts.mailer_usdc_account.to_account_info(),
+            authority: ctx.accounts.subscriber.to_account_info(),
+        },
+    );
+    token_interface::transfer_checked(transfer_ctx, price, ctx.accounts.mint.decimals)?;
+
+    // Book what actually arrived, net of any Token-2022 transfer fee
+    ctx.accounts.mailer_usdc_account.reload()?;
+    let received = ctx
+        .accounts
+        .mailer_usdc_account
+        .amount
+        .checked_sub(balance_before)
+        .ok_or(FeeError::MathOverflow)?;
+    ctx.accounts.owner_bucket.credit(received)?;
+
+    let current_time = Clock::get()?.unix_timestamp;
+    let plan = &ctx.accounts.plan;
+    let subscription = &mut ctx.accounts.subscription;
+    subscription.subscriber = ctx.accounts.subscriber.key();
+    subscription.bump = ctx.bumps.subscription;
+    subscription.renew(plan, current_time)?;
+
+    emit!(SubscriptionPurchased {
+        subscriber: subscription.subscriber,
+        plan_id,
+        price: received,
+        expires_at: subscription.expires_at,
+        message_cap: subscription.message_cap,
+    });
+
+    Ok(())
+}
+
+/// Whether the sender's subscription covers `messages` more standard sends,
+/// counting them against its cap when it does
+pub(crate) fn covers_standard_sends(accounts: &mut SendMessage, messages: u64) -> Result<bool> {
+    let Some(subscription) = accounts.plan_subscription.as_mut() else {
+        return Ok(false);
+    };
+    if !subscription.cover(messages, Clock::get()?.unix_timestamp) {
+        return Ok(false);
+    }
+
+    emit!(SubscriptionSendsCovered {
+        subscriber: subscription.subscriber,
+        messages,
+        messages_used: subscription.messages_used,
+    });
+
+    Ok(true)
+}
+
+#[derive(Accounts)]
+#[instruction(id: u64)]
+pub struct CreateSubscriptionPlan<'info> {
+    /// Fails if a plan with this ID already exists
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + SubscriptionPlan::INIT_SPACE,
+        seeds = [b"plan", id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub plan: Account<'info, SubscriptionPlan>,
+
+    #[account(
+        seeds = [seeds::MAILER],
+        bump = mailer.bump,
+        has_one = owner @ MailerError::OnlyOwner
+    )]
+    pub mailer: Account<'info, MailerState>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CloseSubscriptionPlan<'info> {
+    /// Closed when the plan is withdrawn from sale; rent returns to the owner
+    #[account(
+        mut,
+        close = owner,
+        seeds = [b"plan", plan.id.to_le_bytes().as_ref()],
+        bump = plan.bump
+    )]
+    pub plan: Account<'info, SubscriptionPlan>,
+
+    #[account(
+        seeds = [seeds::MAILER],
+        bump = mailer.bump,
+        has_one = owner @ MailerError::OnlyOwner
+    )]
+    pub mailer: Account<'info, MailerState>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(plan_id: u64)]
+pub struct PurchaseSubscription<'info> {
+    #[account(
+        init_if_needed,
+        payer = subscriber,
+        space = 8 + PlanSubscription::INIT_SPACE,
+        seeds = [b"plan_subscription", subscriber.key().as_ref()],
+        bump
+    )]
+    pub subscription: Account<'info, PlanSubscription>,
+
+    #[account(
+        seeds = [b"plan", plan_id.to_le_bytes().as_ref()],
+        bump = plan.bump,
+        has_one = mint @ TokenError::ClaimMintMismatch
+    )]
+    pub plan: Account<'info, SubscriptionPlan>,
+
+    #[account(seeds = [seeds::MAILER], bump = mailer.bump)]
+    pub mailer: Account<'info, MailerState>,
+
+    /// Owner share bucket for send fees in the plan's mint
+    #[account(
+        mut,
+        seeds = [b"bucket", mint.key().as_ref(), FeeSource::Send.seed()],
+        bump = owner_bucket.bump
+    )]
+    pub owner_bucket: Account<'info, OwnerBucket>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(mut)]
+    pub subscriber: Signer<'info>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = subscriber,
+        associated_token::token_program = token_program
+    )]
+    pub subscriber_usdc_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = mailer,
+        associated_token::token_program = token_program
+    )]
+    pub mailer_usdc_account: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct SubscriptionPlan {
+    pub id: u64,
+    /// Mint the price is paid in
+    pub mint: Pubkey,
+    /// Price of one period, in `mint` base units
+    pub price: u64,
+    /// Length of one period, in seconds
+    pub duration: i64,
+    /// Standard messages one period covers; 0 means unlimited
+    pub message_cap: u64,
+    pub bump: u8,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct PlanSubscription {
+    pub subscriber: Pubkey,
+    /// Plan of the current period
+    pub plan_id: u64,
+    /// First second the subscription no longer covers sends
+    pub expires_at: i64,
+    /// Standard messages the current period covers; 0 means unlimited
+    pub message_cap: u64,
+    /// Standard messages covered so far this period
+    pub messages_used: u64,
+    pub bump: u8,
+}
+
+impl PlanSubscription {
+    pub fn is_active(&self, now: i64) -> bool {
+        now < self.expires_at
+    }
+
+    /// Start a new period of `plan`, or extend the current one if it is
+    /// still running on the same plan
+    pub fn renew(&mut self, plan: &SubscriptionPlan, now: i64) -> Result<()> {
+        if self.is_active(now) && self.plan_id == plan.id {
+            self.expires_at = self
+                .expires_at
+                .checked_add(plan.duration)
+                .ok_or(FeeError::MathOverflow)?;
+            if self.message_cap > 0 {
+                self.message_cap = self
+                    .message_cap
+                    .checked_add(plan.message_cap)
+                    .ok_or(FeeError::MathOverflow)?;
+            }
+        } else {
+            self.plan_id = plan.id;
+            self.expires_at = now
+                .checked_add(plan.duration)
+                .ok_or(FeeError::MathOverflow)?;
+            self.message_cap = plan.message_cap;
+            self.messages_used = 0;
+        }
+        Ok(())
+    }
+
+    /// Count `messages` standard sends against the period if it is running
+    /// and has cap left for all of them, returning whether it did
+    pub fn cover(&mut self, messages: u64, now: i64) -> bool {
+        if !self.is_active(now) {
+            return false;
+        }
+        let Some(used) = self.messages_used.checked_add(messages) else {
+            return false;
+        };
+        if self.message_cap > 0 && used > self.message_cap {
+            return false;
+        }
+        self.messages_used = used;
+        true
+    }
+}
+
+#[event]
+pub struct SubscriptionPlanCreated {
+    pub id: u64,
+    pub mint: Pubkey,
+    pub price: u64,
+    pub duration: i64,
+    pub message_cap: u64,
+}
+
+#[event]
+pub struct SubscriptionPlanClosed {
+    pub id: u64,
+}
+
+#[event]
+pub struct SubscriptionPurchased {
+    pub subscriber: Pubkey,
+    pub plan_id: u64,
+    /// Amount booked to the owner, net of any transfer fee
+    pub price: u64,
+    pub expires_at: i64,
+    pub message_cap: u64,
+}
+
+/// Emitted by standard sends whose fee the sender's subscription waived
+#[event]
+pub struct SubscriptionSendsCovered {
+    pub subscriber: Pubkey,
+    pub messages: u64,
+    pub messages_used: u64,
+}
+
+#[error_code(offset = 8300)]
+pub enum SubscriptionError {
+    #[msg("Plan price must be greater than zero")]
+    InvalidPlanPrice,
+    #[msg("Plan duration must be greater than zero")]
+    InvalidPlanDuration,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plan(id: u64, message_cap: u64) -> SubscriptionPlan {
+        SubscriptionPlan {
+            id,
+            mint: Pubkey::new_unique(),
+            price: 5_000_000,
+            duration: 100,
+            message_cap,
+            bump: 0,
+        }
+    }
+
+    fn subscription() -> PlanSubscription {
+        PlanSubscription {
+            subscriber: Pubkey::new_unique(),
+            plan_id: 0,
+            expires_at: 0,
+            message_cap: 0,
+            messages_used: 0,
+            bump: 0,
+        }
+    }
+
+    #[test]
+    fn caps_limit_covered_sends_until_expiry() {
+        let mut subscription = subscription();
+        subscription.renew(&plan(1, 3), 1_000).unwrap();
+        assert!(subscription.cover(2, 1_000));
+        assert!(!subscription.cover(2, 1_000));
+        assert!(subscription.cover(1, 1_099));
+        assert!(!subscription.cover(1, 1_099));
+
+        let mut unlimited = self::subscription();
+        unlimited.renew(&plan(2, 0), 1_000).unwrap();
+        assert!(unlimited.cover(1_000, 1_050));
+        assert!(!unlimited.cover(1, 1_100));
+    }
+
+    #[test]
+    fn renewing_the_same_plan_extends_the_period() {
+        let mut subscription = subscription();
+        subscription.renew(&plan(1, 3), 1_000).unwrap();
+        assert!(subscription.cover(3, 1_000));
+        subscription.renew(&plan(1, 3), 1_050).unwrap();
+        assert_eq!(subscription.expires_at, 1_200);
+        assert!(subscription.cover(3, 1_150));
+
+        // Another plan starts over
+        subscription.renew(&plan(2, 5), 1_150).unwrap();
+        assert_eq!(subscription.expires_at, 1_250);
+        assert_eq!(subscription.messages_used, 0);
+    }
+}
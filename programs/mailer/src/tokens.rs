@@ -0,0 +1,547 @@
+//! Accepted fee tokens and their per-mint configuration.
+
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::Mint;
+use mailbox_common::fees::BPS_DENOMINATOR;
+use mailbox_common::seeds;
+use pyth_solana_receiver_sdk::price_update::PriceUpdateV2;
+
+use crate::admin::{MailerError, MailerState};
+use crate::claims::RecipientClaim;
+use crate::fees::{init_owner_bucket, standard_fee, validate_send_fee, FeeSource, OwnerBucket};
+
+/// Maximum length of a fee token's display symbol, e.g. "USDC"
+pub const MAX_SYMBOL_LEN: usize = 10;
+
+/// Maximum length of a fee token's display hint, e.g. "$0.00" or "0.00 PYUSD"
+pub const MAX_DISPLAY_HINT_LEN: usize = 32;
+
+pub fn add_token(ctx: Context<ConfigureToken>, send_fee: u64) -> Result<()> {
+    validate_send_fee(send_fee)?;
+
+    let mint = &ctx.accounts.mint;
+    let token_config = &mut ctx.accounts.token_config;
+
+    // Re-adding a previously removed mint keeps its owner bucket balances
+    token_config.mint = mint.key();
+    token_config.enabled = true;
+    token_config.send_fee = send_fee;
+    token_config.decimals = mint.decimals;
+    token_config.bump = ctx.bumps.token_config;
+
+    // Owner shares accrue to the owner until a bucket is routed elsewhere
+    let owner = ctx.accounts.owner.key();
+    init_owner_bucket(
+        &mut ctx.accounts.send_bucket,
+        mint.key(),
+        FeeSource::Send,
+        owner,
+        ctx.bumps.send_bucket,
+    );
+    init_owner_bucket(
+        &mut ctx.accounts.expired_bucket,
+        mint.key(),
+        FeeSource::Expired,
+        owner,
+        ctx.bumps.expired_bucket,
+    );
+
+    emit!(TokenAdded {
+        mint: token_config.mint,
+        send_fee,
+        decimals: token_config.decimals,
+    });
+
+    Ok(())
+}
+
+pub fn remove_token(ctx: Context<RemoveToken>) -> Result<()> {
+    let token_config = &mut ctx.accounts.token_config;
+    require!(token_config.enabled, TokenError::TokenDisabled);
+
+    // Only new sends are refused; outstanding claims in this mint stay claimable
+    token_config.enabled = false;
+
+    emit!(TokenRemoved {
+        mint: token_config.mint,
+    });
+
+    Ok(())
+}
+
+pub fn set_price_feed(ctx: Context<SetPriceFeed>, price_feed_id: [u8; 32]) -> Result<()> {
+    let token_config = &mut ctx.accounts.token_config;
+    token_config.price_feed_id = price_feed_id;
+
+    emit!(PriceFeedUpdated {
+        mint: token_config.mint,
+        price_feed_id,
+    });
+
+    Ok(())
+}
+
+pub fn set_token_display(
+    ctx: Context<SetTokenDisplay>,
+    symbol: String,
+    display_hint: String,
+) -> Result<()> {
+    require!(
+        !symbol.is_empty() && symbol.len() <= MAX_SYMBOL_LEN,
+        TokenError::InvalidDisplayMetadata
+    );
+    require!(
+        display_hint.len() <= MAX_DISPLAY_HINT_LEN,
+        TokenError::InvalidDisplayMetadata
+    );
+
+    // Decimals are re-read from the mint so the display never drifts from it
+    let token_display = &mut ctx.accounts.token_display;
+    token_display.mint = ctx.accounts.mint.key();
+    token_display.symbol = symbol;
+    token_display.decimals = ctx.accounts.mint.decimals;
+    token_display.display_hint = display_hint;
+    token_display.bump = ctx.bumps.token_display;
+
+    emit!(TokenDisplayUpdated {
+        mint: token_display.mint,
+        symbol: token_display.symbol.clone(),
+        decimals: token_display.decimals,
+        display_hint: token_display.display_hint.clone(),
+    });
+
+    Ok(())
+}
+
+pub fn quote_fee(ctx: Context<QuoteFee>, priority: bool) -> Result<FeeQuote> {
+    let token_config = &ctx.accounts.token_config;
+    let send_fee = current_send_fee(
+        token_config,
+        &ctx.accounts.mailer,
+        ctx.accounts.price_update.as_deref(),
+    )?;
+    let amount = if priority {
+        send_fee
+    } else {
+        standard_fee(send_fee)?
+    };
+
+    // Mints without display metadata quote an empty symbol and hint
+    let (symbol, display_hint) = match &ctx.accounts.token_display {
+        Some(display) => (display.symbol.clone(), display.display_hint.clone()),
+        None => (String::new(), String::new()),
+    };
+
+    Ok(FeeQuote {
+        mint: token_config.mint,
+        amount,
+        decimals: token_config.decimals,
+        symbol,
+        display_hint,
+    })
+}
+
+/// Maximum age of a Pyth price used to quote a send fee: 60 seconds
+pub const MAX_PRICE_AGE: u64 = 60;
+
+/// Widest Pyth confidence interval accepted, relative to the price: 2% (basis points)
+pub const MAX_PRICE_CONF_BPS: u64 = 200;
+
+/// Priority send fee for a mint, in its base units
+///
+/// Mints without a price feed are charged their fixed `send_fee`. Mints with
+/// one are charged the token equivalent of `usd_fee_cents` at the current
+/// Pyth price, which must be fresh, for the configured feed and within
+/// `MAX_PRICE_CONF_BPS` of certain.
+pub(crate) fn current_send_fee(
+    token_config: &TokenConfig,
+    mailer: &MailerState,
+    price_update: Option<&PriceUpdateV2>,
+) -> Result<u64> {
+    if token_config.price_feed_id == [0u8; 32] {
+        return Ok(token_config.send_fee);
+    }
+
+    let price_update = price_update.ok_or(TokenError::PriceFeedRequired)?;
+    require!(
+        price_update.price_message.feed_id == token_config.price_feed_id,
+        TokenError::PriceFeedMismatch
+    );
+    let price = price_update
+        .get_price_no_older_than(&Clock::get()?, MAX_PRICE_AGE, &token_config.price_feed_id)
+        .map_err(|_| error!(TokenError::StalePrice))?;
+    require!(
+        is_price_confident(price.price, price.conf),
+        TokenError::UncertainPrice
+    );
+
+    usd_to_token_amount(
+        mailer.usd_fee_cents,
+        price.price,
+        price.exponent,
+        token_config.decimals,
+    )
+    .ok_or_else(|| error!(TokenError::InvalidPrice))
+}
+
+/// Whether a Pyth confidence interval `conf` is narrow enough to quote a fee at
+/// `price`; both share the price's exponent
+pub fn is_price_confident(price: i64, conf: u64) -> bool {
+    price > 0
+        && (conf as u128) * (BPS_DENOMINATOR as u128)
+            <= (price as u128) * (MAX_PRICE_CONF_BPS as u128)
+}
+
+/// Base units of a token worth `cents`, given its USD price `price * 10^exponent`
+///
+/// Rounds up so an oracle-priced send is never charged less than the target.
+pub fn usd_to_token_amount(cents: u64, price: i64, exponent: i32, decimals: u8) -> Option<u64> {
+    if price <= 0 {
+        return None;
+    }
+
+    let mut numerator = (cents as u128).checked_mul(10u128.checked_pow(decimals as u32)?)?;
+    let mut denominator = (price as u128).checked_mul(100)?;
+    if exponent < 0 {
+        numerator = numerator.checked_mul(10u128.checked_pow(exponent.unsigned_abs())?)?;
+    } else {
+        denominator = denominator.checked_mul(10u128.checked_pow(exponent as u32)?)?;
+    }
+
+    u64::try_from(numerator.div_ceil(denominator)).ok()
+}
+
+/// Mint a claim's balances are denominated in
+///
+/// Claims created before multi-token support have no recorded mint and are
+/// always denominated in the original `usdc_mint`.
+pub fn claim_mint(claim: &RecipientClaim, mailer: &MailerState) -> Pubkey {
+    if claim.mint == Pubkey::default() {
+        mailer.usdc_mint
+    } else {
+        claim.mint
+    }
+}
+
+#[derive(Accounts)]
+pub struct ConfigureToken<'info> {
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = 8 + TokenConfig::INIT_SPACE,
+        seeds = [b"token", mint.key().as_ref()],
+        bump
+    )]
+    pub token_config: Account<'info, TokenConfig>,
+
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = 8 + OwnerBucket::INIT_SPACE,
+        seeds = [b"bucket", mint.key().as_ref(), FeeSource::Send.seed()],
+        bump
+    )]
+    pub send_bucket: Account<'info, OwnerBucket>,
+
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = 8 + OwnerBucket::INIT_SPACE,
+        seeds = [b"bucket", mint.key().as_ref(), FeeSource::Expired.seed()],
+        bump
+    )]
+    pub expired_bucket: Account<'info, OwnerBucket>,
+
+    #[account(
+        seeds = [seeds::MAILER],
+        bump = mailer.bump,
+        has_one = owner @ MailerError::OnlyOwner
+    )]
+    pub mailer: Account<'info, MailerState>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RemoveToken<'info> {
+    #[account(
+        mut,
+        seeds = [b"token", token_config.mint.as_ref()],
+        bump = token_config.bump
+    )]
+    pub token_config: Account<'info, TokenConfig>,
+
+    #[account(
+        seeds = [seeds::MAILER],
+        bump = mailer.bump,
+        has_one = owner @ MailerError::OnlyOwner
+    )]
+    pub mailer: Account<'info, MailerState>,
+
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetPriceFeed<'info> {
+    #[account(
+        mut,
+        seeds = [b"token", token_config.mint.as_ref()],
+        bump = token_config.bump
+    )]
+    pub token_config: Account<'info, TokenConfig>,
+
+    #[account(
+        seeds = [seeds::MAILER],
+        bump = mailer.bump,
+        has_one = owner @ MailerError::OnlyOwner
+    )]
+    pub mailer: Account<'info, MailerState>,
+
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetTokenDisplay<'info> {
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = 8 + TokenDisplay::INIT_SPACE,
+        seeds = [b"display", mint.key().as_ref()],
+        bump
+    )]
+    pub token_display: Account<'info, TokenDisplay>,
+
+    /// Display metadata is only kept for mints that have been added
+    #[account(seeds = [b"token", mint.key().as_ref()], bump = token_config.bump)]
+    pub token_config: Account<'info, TokenConfig>,
+
+    #[account(
+        seeds = [seeds::MAILER],
+        bump = mailer.bump,
+        has_one = owner @ MailerError::OnlyOwner
+    )]
+    pub mailer: Account<'info, MailerState>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct QuoteFee<'info> {
+    #[account(seeds = [seeds::MAILER], bump = mailer.bump)]
+    pub mailer: Account<'info, MailerState>,
+
+    #[account(
+        seeds = [b"token", token_config.mint.as_ref()],
+        bump = token_config.bump,
+        constraint = token_config.enabled @ TokenError::TokenDisabled
+    )]
+    pub token_config: Account<'info, TokenConfig>,
+
+    /// Display metadata for the mint, if the owner has set any
+    #[account(seeds = [b"display", token_config.mint.as_ref()], bump = token_display.bump)]
+    pub token_display: Option<Account<'info, TokenDisplay>>,
+
+    /// Fresh Pyth price for the mint; required only when it has a price feed
+    pub price_update: Option<Account<'info, PriceUpdateV2>>,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct TokenConfig {
+    pub mint: Pubkey,
+    pub enabled: bool,
+    /// Priority send fee in the mint's base units, used when no price feed is set
+    pub send_fee: u64,
+    pub decimals: u8,
+    pub bump: u8,
+    /// Pyth feed pricing the mint in USD; all zeroes means the fixed `send_fee`
+    pub price_feed_id: [u8; 32],
+}
+
+/// How clients should present amounts of a fee token
+#[account]
+#[derive(InitSpace)]
+pub struct TokenDisplay {
+    pub mint: Pubkey,
+    #[max_len(MAX_SYMBOL_LEN)]
+    pub symbol: String,
+    pub decimals: u8,
+    /// Free-form formatting hint, e.g. "$0.00" for USD stablecoins
+    #[max_len(MAX_DISPLAY_HINT_LEN)]
+    pub display_hint: String,
+    pub bump: u8,
+}
+
+/// Fee for one send in a given mint, returned by `quote_fee`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct FeeQuote {
+    pub mint: Pubkey,
+    /// Fee in the mint's base units
+    pub amount: u64,
+    pub decimals: u8,
+    /// Display symbol; empty when no `TokenDisplay` is set
+    pub symbol: String,
+    pub display_hint: String,
+}
+
+#[event]
+pub struct TokenAdded {
+    pub mint: Pubkey,
+    pub send_fee: u64,
+    pub decimals: u8,
+}
+
+#[event]
+pub struct TokenRemoved {
+    pub mint: Pubkey,
+}
+
+#[event]
+pub struct TokenDisplayUpdated {
+    pub mint: Pubkey,
+    pub symbol: String,
+    pub decimals: u8,
+    pub display_hint: String,
+}
+
+#[event]
+pub struct PriceFeedUpdated {
+    pub mint: Pubkey,
+    pub price_feed_id: [u8; 32],
+}
+
+#[error_code(offset = 6400)]
+pub enum TokenError {
+    #[msg("Token is not accepted for fees")]
+    TokenDisabled,
+    #[msg("Claim balance is denominated in a different token")]
+    ClaimMintMismatch,
+    #[msg("Token is priced by an oracle; a price update account is required")]
+    PriceFeedRequired,
+    #[msg("Price update is for a different feed")]
+    PriceFeedMismatch,
+    #[msg("Price update is too old")]
+    StalePrice,
+    #[msg("Oracle price cannot quote the send fee")]
+    InvalidPrice,
+    #[msg("Display symbol must be 1-10 bytes and the hint at most 32 bytes")]
+    InvalidDisplayMetadata,
+    #[msg("Oracle price confidence interval is too wide")]
+    UncertainPrice,
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::claims::ClaimMode;
+    use crate::fees::FeeSnapshot;
+    use crate::staking::{StakeTier, MAX_STAKE_TIERS};
+
+    fn claim_with_mint(mint: Pubkey) -> RecipientClaim {
+        RecipientClaim {
+            recipient: Pubkey::new_unique(),
+            rent_payer: Pubkey::new_unique(),
+            amount: 0,
+            timestamp: 0,
+            send_credit: 0,
+            bump: 0,
+            fee_snapshot: FeeSnapshot::default(),
+            mint,
+            mode: ClaimMode::Lump,
+            streamed: 0,
+            auto_claim: false,
+            vested: 0,
+            vesting_start: 0,
+            released: 0,
+        }
+    }
+
+    pub(crate) fn mailer_with_usdc(usdc_mint: Pubkey) -> MailerState {
+        MailerState {
+            owner: Pubkey::new_unique(),
+            usdc_mint,
+            send_fee: 0,
+            owner_claimable: 0,
+            bump: 0,
+            crank_bounty_bps: 0,
+            rollover_bps: 0,
+            usd_fee_cents: 0,
+            pow_difficulty: 0,
+            max_subject_len: 0,
+            max_body_len: 0,
+            max_messages_per_day: 0,
+            referral_bps: 0,
+            priority_points: 0,
+            standard_points: 0,
+            point_value: 0,
+            nft_collection: Pubkey::default(),
+            nft_discount_bps: 0,
+            root_publisher: Pubkey::default(),
+            auto_claimer: Pubkey::default(),
+            stake_tiers: [StakeTier::default(); MAX_STAKE_TIERS],
+        }
+    }
+
+    #[test]
+    fn legacy_claims_are_denominated_in_usdc() {
+        let usdc = Pubkey::new_unique();
+        let mailer = mailer_with_usdc(usdc);
+        assert_eq!(claim_mint(&claim_with_mint(Pubkey::default()), &mailer), usdc);
+    }
+
+    #[test]
+    fn recorded_claim_mint_wins() {
+        let pyusd = Pubkey::new_unique();
+        let mailer = mailer_with_usdc(Pubkey::new_unique());
+        assert_eq!(claim_mint(&claim_with_mint(pyusd), &mailer), pyusd);
+    }
+
+    #[test]
+    fn usd_fee_converts_at_oracle_price() {
+        // USDC at $1.00000000, 6 decimals: 10 cents is 100_000 base units
+        assert_eq!(usd_to_token_amount(10, 100_000_000, -8, 6), Some(100_000));
+        // SOL at $150, 9 decimals: 10 cents is 666_666.67 lamports, rounded up
+        assert_eq!(usd_to_token_amount(10, 15_000_000_000, -8, 9), Some(666_667));
+        // Positive exponents divide instead of multiply
+        assert_eq!(usd_to_token_amount(10, 15, 1, 9), Some(666_667));
+    }
+
+    #[test]
+    fn non_positive_prices_cannot_quote_a_fee() {
+        assert_eq!(usd_to_token_amount(10, 0, -8, 6), None);
+        assert_eq!(usd_to_token_amount(10, -1, -8, 6), None);
+    }
+
+    #[test]
+    fn wide_confidence_intervals_cannot_quote_a_fee() {
+        // $150.00000000 with a $3 (2%) interval is still accepted
+        assert!(is_price_confident(15_000_000_000, 300_000_000));
+        assert!(!is_price_confident(15_000_000_000, 300_000_001));
+        assert!(is_price_confident(i64::MAX, 0));
+        assert!(!is_price_confident(0, 0));
+    }
+
+    #[test]
+    fn unpriced_mint_uses_fixed_fee() {
+        let token_config = TokenConfig {
+            mint: Pubkey::new_unique(),
+            enabled: true,
+            send_fee: 123_456,
+            decimals: 6,
+            bump: 0,
+            price_feed_id: [0u8; 32],
+        };
+        let mailer = mailer_with_usdc(Pubkey::new_unique());
+        assert_eq!(current_send_fee(&token_config, &mailer, None).unwrap(), 123_456);
+    }
+}
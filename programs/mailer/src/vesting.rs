@@ -0,0 +1,124 @@
+//! Vested revenue shares.
+//!
+//! `send_priority_vested` credits the sender's claim `VESTED_RECIPIENT_SHARE`
+//! percent of the fee instead of the standard share, in exchange for locking
+//! the whole share behind a `VESTING_CLIFF` that starts at the send. The
+//! extra comes out of the owner's share, after any referral. Locked shares sit
+//! in the claim's `vested` balance and cannot be claimed, netted against fees
+//! or auto-claimed until the cliff has passed; each vested send restarts the
+//! cliff for everything still locked.
+//!
+//! Only lump claims can vest, and only while the claim period outlasts the
+//! cliff, so vested shares are always unlocked before they could expire.
+
+use anchor_lang::prelude::*;
+
+use crate::claims::{ClaimMode, CLAIM_PERIOD};
+use crate::fees::{split_shares, standard_fee, FeeError};
+use crate::messaging::SendMessage;
+
+/// Time vested shares stay locked after a vested send: 30 days in seconds
+pub const VESTING_CLIFF: i64 = 30 * 24 * 60 * 60;
+
+/// Recipient share of a vested priority send, in percent
+pub const VESTED_RECIPIENT_SHARE: u64 = 95;
+
+/// Raise the share a priority send just recorded to `VESTED_RECIPIENT_SHARE`
+/// and lock all of it behind the cliff
+///
+/// `net_fee` is the priority fee the mailer kept and `referred` the part of
+/// its owner share already paid to a referrer; the bonus never exceeds what
+/// is left of the owner share.
+pub(crate) fn vest_shares(accounts: &mut SendMessage, net_fee: u64, referred: u64) -> Result<()> {
+    let current_time = Clock::get()?.unix_timestamp;
+    let claim = &accounts.recipient_claim;
+    require!(
+        claim.mode == ClaimMode::Lump,
+        VestingError::VestingRequiresLumpClaim
+    );
+    require!(
+        current_time.saturating_add(VESTING_CLIFF) <= claim.timestamp.saturating_add(CLAIM_PERIOD),
+        VestingError::VestingOutlivesClaim
+    );
+
+    let owner_left = standard_fee(net_fee)?
+        .checked_sub(referred)
+        .ok_or(FeeError::MathOverflow)?;
+    let bonus = vesting_bonus(net_fee, owner_left)?;
+    accounts.owner_bucket.claimable = accounts
+        .owner_bucket
+        .claimable
+        .checked_sub(bonus)
+        .ok_or(FeeError::MathOverflow)?;
+
+    let (_, recipient_amount) = split_shares(net_fee)?;
+    let vested = recipient_amount
+        .checked_add(bonus)
+        .ok_or(FeeError::MathOverflow)?;
+    let claim = &mut accounts.recipient_claim;
+    claim.amount = claim
+        .amount
+        .checked_add(bonus)
+        .ok_or(FeeError::MathOverflow)?;
+    claim.vested = claim
+        .locked(current_time)
+        .checked_add(vested)
+        .ok_or(FeeError::MathOverflow)?;
+    claim.vesting_start = current_time;
+
+    emit!(SharesVested {
+        recipient: claim.recipient,
+        amount: vested,
+        bonus,
+        unlocks_at: current_time.saturating_add(VESTING_CLIFF),
+    });
+
+    Ok(())
+}
+
+/// Extra recipient share a vested send earns on `net_fee`, capped at what is
+/// left of the owner share
+pub fn vesting_bonus(net_fee: u64, owner_left: u64) -> Result<u64> {
+    let boosted = (net_fee as u128)
+        .checked_mul(VESTED_RECIPIENT_SHARE as u128)
+        .ok_or(FeeError::MathOverflow)?
+        / 100;
+    let (_, recipient_amount) = split_shares(net_fee)?;
+    // `boosted <= net_fee`, so the cast is lossless
+    Ok((boosted as u64)
+        .saturating_sub(recipient_amount)
+        .min(owner_left))
+}
+
+/// Emitted by `send_priority_vested` alongside the `PriorityMailSent` it delivers
+#[event]
+pub struct SharesVested {
+    pub recipient: Pubkey,
+    /// Share locked by this send, including the bonus
+    pub amount: u64,
+    /// Share credited above the standard share
+    pub bonus: u64,
+    pub unlocks_at: i64,
+}
+
+#[error_code(offset = 8500)]
+pub enum VestingError {
+    #[msg("Only lump claims can vest shares")]
+    VestingRequiresLumpClaim,
+    #[msg("Claim period ends before the vesting cliff; claim it before vesting more shares")]
+    VestingOutlivesClaim,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bonus_raises_the_share_within_the_owner_share() {
+        // 95% of 100_000 against the standard 90_000
+        assert_eq!(vesting_bonus(100_000, 10_000).unwrap(), 5_000);
+        // A referral leaves less of the owner share to give
+        assert_eq!(vesting_bonus(100_000, 2_000).unwrap(), 2_000);
+        assert_eq!(vesting_bonus(0, 0).unwrap(), 0);
+    }
+}
@@ -0,0 +1,227 @@
+import { createHash } from 'crypto';
+import fs from 'fs';
+import path from 'path';
+
+/**
+ * Account and event layout audit.
+ *
+ * Reads every IDL produced by `anchor build` (target/idl/*.json), extracts the
+ * discriminator and field layout of each account and event, and compares them
+ * against the committed snapshots in layouts/. The audit fails when an upgrade
+ * would make existing on-chain data (or already-indexed events) ambiguous:
+ *
+ * - two accounts/events in the same program share a discriminator
+ * - a previously recorded account/event was removed or renamed
+ * - a recorded field was removed, renamed, reordered or changed type,
+ *   including any change inside a struct or enum the field holds
+ * - a program has no committed snapshot to compare against
+ *
+ * Appending fields after the recorded layout is the only change allowed; it is
+ * reported as a warning because existing accounts need a realloc/migration
+ * before they can hold the new data.
+ *
+ * The snapshots start from the layouts deployed at launch. --update records
+ * appended fields and new accounts/events, but refuses to write a program whose
+ * audit fails, so a snapshot can only ever grow by appending.
+ *
+ * Usage:
+ *   ts-node scripts/audit-layouts.ts            # audit against snapshots
+ *   ts-node scripts/audit-layouts.ts --update   # record append-only changes
+ *
+ * Commit the snapshots --update writes; the audit fails for a program without one.
+ */
+
+const IDL_DIR = path.join(__dirname, '..', 'target', 'idl');
+const SNAPSHOT_DIR = path.join(__dirname, '..', 'layouts');
+
+interface FieldLayout {
+    name: string;
+    type: string;
+}
+
+interface TypeLayout {
+    discriminator: string;
+    fields: FieldLayout[];
+}
+
+interface ProgramLayout {
+    program: string;
+    accounts: Record<string, TypeLayout>;
+    events: Record<string, TypeLayout>;
+}
+
+function anchorDiscriminator(namespace: string, name: string): string {
+    // Anchor uses the PascalCase type name in the discriminator preimage
+    const typeName = name.charAt(0).toUpperCase() + name.slice(1);
+    return createHash('sha256')
+        .update(`${namespace}:${typeName}`)
+        .digest()
+        .subarray(0, 8)
+        .toString('hex');
+}
+
+function findTypeFields(idl: any, name: string): any[] {
+    const typeDef = (idl.types || []).find((t: any) => t.name === name);
+    return typeDef?.type?.fields || [];
+}
+
+/**
+ * Inline the definition of every struct or enum `type` refers to, so a change
+ * inside a nested type changes the recorded type of the field holding it
+ */
+function expandType(idl: any, type: any): any {
+    if (Array.isArray(type)) {
+        return type.map((t) => expandType(idl, t));
+    }
+    if (typeof type !== 'object' || type === null) {
+        return type;
+    }
+    if (type.defined) {
+        const name = typeof type.defined === 'string' ? type.defined : type.defined.name;
+        const typeDef = (idl.types || []).find((t: any) => t.name === name);
+        if (!typeDef) {
+            return type;
+        }
+        const expandFields = (fields: any[] | undefined) =>
+            (fields || []).map((f: any) =>
+                f && typeof f === 'object' && 'name' in f
+                    ? { name: f.name, type: expandType(idl, f.type) }
+                    : expandType(idl, f)
+            );
+        if (typeDef.type.kind === 'enum') {
+            return {
+                defined: name,
+                variants: typeDef.type.variants.map((v: any) => ({ name: v.name, fields: expandFields(v.fields) })),
+            };
+        }
+        return { defined: name, fields: expandFields(typeDef.type.fields) };
+    }
+    return Object.fromEntries(Object.entries(type).map(([key, value]) => [key, expandType(idl, value)]));
+}
+
+function extractLayouts(idl: any, entries: any[] | undefined, namespace: string): Record<string, TypeLayout> {
+    const layouts: Record<string, TypeLayout> = {};
+    for (const entry of entries || []) {
+        // Anchor >= 0.30 stores discriminators and moves field layouts into `types`
+        const discriminator = entry.discriminator
+            ? Buffer.from(entry.discriminator).toString('hex')
+            : anchorDiscriminator(namespace, entry.name);
+        const fields = entry.type?.fields || entry.fields || findTypeFields(idl, entry.name);
+        layouts[entry.name] = {
+            discriminator,
+            fields: fields.map((f: any) => ({ name: f.name, type: JSON.stringify(expandType(idl, f.type)) })),
+        };
+    }
+    return layouts;
+}
+
+function loadProgramLayout(idlPath: string): ProgramLayout {
+    const idl = JSON.parse(fs.readFileSync(idlPath, 'utf8'));
+    const program = idl.metadata?.name || idl.name || path.basename(idlPath, '.json');
+    return {
+        program,
+        accounts: extractLayouts(idl, idl.accounts, 'account'),
+        events: extractLayouts(idl, idl.events, 'event'),
+    };
+}
+
+function checkCollisions(layout: ProgramLayout, errors: string[]) {
+    const seen = new Map<string, string>();
+    const all = [
+        ...Object.entries(layout.accounts).map(([name, l]) => [`account ${name}`, l] as const),
+        ...Object.entries(layout.events).map(([name, l]) => [`event ${name}`, l] as const),
+    ];
+    for (const [label, l] of all) {
+        const existing = seen.get(l.discriminator);
+        if (existing) {
+            errors.push(`${layout.program}: ${label} collides with ${existing} (discriminator ${l.discriminator})`);
+        }
+        seen.set(l.discriminator, label);
+    }
+}
+
+function compareLayouts(
+    program: string,
+    kind: 'account' | 'event',
+    previous: Record<string, TypeLayout>,
+    current: Record<string, TypeLayout>,
+    errors: string[],
+    warnings: string[]
+) {
+    for (const [name, old] of Object.entries(previous)) {
+        const now = current[name];
+        if (!now) {
+            errors.push(`${program}: ${kind} ${name} was removed or renamed`);
+            continue;
+        }
+        if (now.discriminator !== old.discriminator) {
+            errors.push(`${program}: ${kind} ${name} discriminator changed ${old.discriminator} -> ${now.discriminator}`);
+        }
+        old.fields.forEach((field, i) => {
+            const updated = now.fields[i];
+            if (!updated) {
+                errors.push(`${program}: ${kind} ${name} field ${field.name} was removed`);
+            } else if (updated.name !== field.name || updated.type !== field.type) {
+                errors.push(
+                    `${program}: ${kind} ${name} field #${i} changed ` +
+                    `${field.name}: ${field.type} -> ${updated.name}: ${updated.type}`
+                );
+            }
+        });
+        const appended = now.fields.slice(old.fields.length).map((f) => f.name);
+        if (appended.length > 0) {
+            const note = kind === 'account' ? ' (existing accounts need realloc/migration)' : '';
+            warnings.push(`${program}: ${kind} ${name} appended fields ${appended.join(', ')}${note}`);
+        }
+    }
+}
+
+function main() {
+    const update = process.argv.includes('--update');
+
+    if (!fs.existsSync(IDL_DIR)) {
+        console.error(`❌ No IDL directory at ${IDL_DIR}; run \`anchor build\` first`);
+        process.exit(1);
+    }
+    if (update) {
+        fs.mkdirSync(SNAPSHOT_DIR, { recursive: true });
+    }
+
+    const errors: string[] = [];
+    const warnings: string[] = [];
+
+    for (const file of fs.readdirSync(IDL_DIR).filter((f) => f.endsWith('.json')).sort()) {
+        const layout = loadProgramLayout(path.join(IDL_DIR, file));
+        const programErrors: string[] = [];
+        checkCollisions(layout, programErrors);
+
+        const snapshotPath = path.join(SNAPSHOT_DIR, `${layout.program}.json`);
+        if (fs.existsSync(snapshotPath)) {
+            const previous: ProgramLayout = JSON.parse(fs.readFileSync(snapshotPath, 'utf8'));
+            compareLayouts(layout.program, 'account', previous.accounts, layout.accounts, programErrors, warnings);
+            compareLayouts(layout.program, 'event', previous.events, layout.events, programErrors, warnings);
+        } else if (!update) {
+            // A missing baseline would let any layout through; record one deliberately
+            programErrors.push(`${layout.program}: no committed layout snapshot; run with --update to record one`);
+        }
+
+        if (update && programErrors.length === 0) {
+            fs.writeFileSync(snapshotPath, JSON.stringify(layout, null, 2) + '\n');
+            console.log(`📝 Recorded layout snapshot for ${layout.program}`);
+        } else if (update) {
+            console.error(`❌ Not recording ${layout.program}: only appended fields and new accounts/events can be recorded`);
+        }
+        errors.push(...programErrors);
+    }
+
+    warnings.forEach((w) => console.warn(`⚠️  ${w}`));
+    errors.forEach((e) => console.error(`❌ ${e}`));
+
+    if (errors.length > 0) {
+        console.error(`\nLayout audit failed with ${errors.length} error(s)`);
+        process.exit(1);
+    }
+    console.log('✅ Account and event layouts are upgrade-safe');
+}
+
+main();
@@ -242,14 +242,40 @@ async function deployToNetwork(
     if (!mailerDeployed) {
         console.log("📧 Deploying Mailer...");
         const tx = await mailerProgram.methods
-            .initialize(usdcMint)
+            .initialize()
             .accounts({
                 mailer: predictedAddresses.mailer.address,
+                usdcMint: usdcMint,
                 owner: wallet.publicKey,
                 systemProgram: SystemProgram.programId,
             })
             .rpc();
         transactions.mailer = tx;
+
+        // Accept USDC for fees at the default send fee
+        const [usdcTokenConfig] = PublicKey.findProgramAddressSync(
+            [Buffer.from('token'), usdcMint.toBuffer()],
+            mailerProgram.programId
+        );
+        const mailerAccount = await mailerProgram.account.mailerState.fetch(predictedAddresses.mailer.address);
+        await mailerProgram.methods
+            .addToken(mailerAccount.sendFee)
+            .accounts({
+                tokenConfig: usdcTokenConfig,
+                sendBucket: PublicKey.findProgramAddressSync(
+                    [Buffer.from('bucket'), usdcMint.toBuffer(), Buffer.from('send')],
+                    mailerProgram.programId
+                )[0],
+                expiredBucket: PublicKey.findProgramAddressSync(
+                    [Buffer.from('bucket'), usdcMint.toBuffer(), Buffer.from('expired')],
+                    mailerProgram.programId
+                )[0],
+                mailer: predictedAddresses.mailer.address,
+                mint: usdcMint,
+                owner: wallet.publicKey,
+                systemProgram: SystemProgram.programId,
+            })
+            .rpc();
         console.log("✅ Mailer deployed:", tx);
     } else {
         console.log("⏭️  Mailer already deployed, skipping");
@@ -262,6 +288,10 @@ async function deployToNetwork(
             .initialize(usdcMint)
             .accounts({
                 mailService: predictedAddresses.mailService.address,
+                delegationBucket: PublicKey.findProgramAddressSync(
+                    [Buffer.from('bucket'), Buffer.from('delegation')],
+                    mailServiceProgram.programId
+                )[0],
                 owner: wallet.publicKey,
                 systemProgram: SystemProgram.programId,
             })
@@ -176,16 +176,41 @@ async function deployMailer(
     );
     
     const tx = await mailerProgram.methods
-        .initialize(usdcMint)
+        .initialize()
+        .accounts({
+            mailer: mailerPda,
+            usdcMint: usdcMint,
+            owner: provider.wallet.publicKey,
+            systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
+    // Accept USDC for fees at the default send fee
+    const [usdcTokenConfig] = PublicKey.findProgramAddressSync(
+        [Buffer.from('token'), usdcMint.toBuffer()],
+        mailerProgram.programId
+    );
+    const mailerAccount = await mailerProgram.account.mailerState.fetch(mailerPda);
+    await mailerProgram.methods
+        .addToken(mailerAccount.sendFee)
         .accounts({
+            tokenConfig: usdcTokenConfig,
+            sendBucket: PublicKey.findProgramAddressSync(
+                [Buffer.from('bucket'), usdcMint.toBuffer(), Buffer.from('send')],
+                mailerProgram.programId
+            )[0],
+            expiredBucket: PublicKey.findProgramAddressSync(
+                [Buffer.from('bucket'), usdcMint.toBuffer(), Buffer.from('expired')],
+                mailerProgram.programId
+            )[0],
             mailer: mailerPda,
+            mint: usdcMint,
             owner: provider.wallet.publicKey,
             systemProgram: SystemProgram.programId,
         })
         .rpc();
     
     // Fetch fees for documentation
-    const mailerAccount = await mailerProgram.account.mailerState.fetch(mailerPda);
     const fees = {
         sendFee: (mailerAccount.sendFee.toNumber() / 1_000_000).toString() + " USDC"
     };
@@ -222,6 +247,10 @@ async function deployMailService(
         .initialize(usdcMint)
         .accounts({
             mailService: mailServicePda,
+            delegationBucket: PublicKey.findProgramAddressSync(
+                [Buffer.from('bucket'), Buffer.from('delegation')],
+                mailServiceProgram.programId
+            )[0],
             owner: provider.wallet.publicKey,
             systemProgram: SystemProgram.programId,
         })
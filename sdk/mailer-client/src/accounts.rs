@@ -0,0 +1,41 @@
+//! Decoders for mailer account data.
+
+use anchor_lang::{AccountDeserialize, Result};
+
+pub use mailer::{
+    CallerPolicy, ExpiryIndex, MailIdRecord, MailerState, MailerStats, OwnerBucket, PaymentEscrow,
+    PowState, RecipientClaim, RecipientPreferences, TokenConfig,
+};
+
+/// Decode raw account data as `T`, checking its discriminator
+///
+/// Fails with Anchor's error, which `?` converts into `ClientError::Decode`.
+///
+/// ```rust,ignore
+/// let claim: RecipientClaim = decode(&account.data)?;
+/// ```
+pub fn decode<T: AccountDeserialize>(data: &[u8]) -> Result<T> {
+    T::try_deserialize(&mut &data[..])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anchor_lang::prelude::Pubkey;
+    use anchor_lang::AccountSerialize;
+
+    #[test]
+    fn decodes_serialized_accounts_and_rejects_other_types() {
+        let policy = CallerPolicy {
+            allow_all: false,
+            programs: vec![Pubkey::new_unique()],
+            bump: 254,
+        };
+        let mut data = Vec::new();
+        policy.try_serialize(&mut data).unwrap();
+
+        let decoded: CallerPolicy = decode(&data).unwrap();
+        assert_eq!(decoded.programs, policy.programs);
+        assert!(decode::<RecipientPreferences>(&data).is_err());
+    }
+}
@@ -0,0 +1,24 @@
+//! Errors returned by the client.
+
+use anchor_lang::prelude::Pubkey;
+
+#[derive(Debug, thiserror::Error)]
+pub enum ClientError {
+    #[error("account {0} does not exist")]
+    AccountNotFound(Pubkey),
+    #[error("failed to decode account: {0}")]
+    Decode(#[from] anchor_lang::error::Error),
+    /// Boxed: the RPC error is far larger than the other variants
+    #[cfg(feature = "rpc")]
+    #[error("RPC request failed: {0}")]
+    Rpc(Box<solana_client::client_error::ClientError>),
+}
+
+#[cfg(feature = "rpc")]
+impl From<solana_client::client_error::ClientError> for ClientError {
+    fn from(error: solana_client::client_error::ClientError) -> Self {
+        Self::Rpc(Box::new(error))
+    }
+}
+
+pub type Result<T> = std::result::Result<T, ClientError>;
@@ -0,0 +1,186 @@
+//! Typed decoding of mailer events.
+//!
+//! Events reach clients either as `Program data:` log lines (`emit!`) or, for
+//! builds with the `event-cpi` feature, as self-CPI inner instructions
+//! (`emit_cpi!`). Both carry the same bytes: the event's 8-byte discriminator
+//! followed by its Borsh-encoded fields.
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::{AnchorDeserialize, Discriminator};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+
+/// Prefix of Anchor's self-CPI event instructions (`EVENT_IX_TAG_LE`)
+pub const EVENT_IX_TAG_LE: [u8; 8] = [0xe4, 0x45, 0xa5, 0x2e, 0x51, 0xcb, 0x9a, 0x1d];
+
+const PROGRAM_DATA: &str = "Program data: ";
+
+macro_rules! mailer_events {
+    ($($name:ident),* $(,)?) => {
+        /// Every event the mailer emits, wrapping the program's own event structs
+        pub enum MailerEvent {
+            $($name(mailer::$name),)*
+        }
+
+        impl MailerEvent {
+            /// Decode an event from its discriminator and Borsh data
+            pub fn try_from_bytes(data: &[u8]) -> Option<Self> {
+                if data.len() < 8 {
+                    return None;
+                }
+                let (discriminator, mut fields) = data.split_at(8);
+                $(
+                    if discriminator == mailer::$name::DISCRIMINATOR.as_slice() {
+                        return mailer::$name::deserialize(&mut fields).ok().map(Self::$name);
+                    }
+                )*
+                None
+            }
+
+            /// Name of the event struct, e.g. `"PriorityMailSent"`
+            pub fn name(&self) -> &'static str {
+                match self {
+                    $(Self::$name(_) => stringify!($name),)*
+                }
+            }
+        }
+    };
+}
+
+mailer_events!(
+    // Sends
+    PriorityMailSent,
+    StandardMailSent,
+    PriorityPreparedMailSent,
+    StandardPreparedMailSent,
+    PreparedBatchSent,
+    PowMailSent,
+    PaymentMailSent,
+    SentAsDelegate,
+    ClaimNetted,
+    SendCreditUsed,
+    SolWrapped,
+    TipPaid,
+    // Shares and claims
+    SharesRecorded,
+    RecipientClaimed,
+    ClaimModeUpdated,
+    ClaimClosed,
+    ClaimExtended,
+    OwnerClaimed,
+    ExpiredSharesClaimed,
+    // Escrow
+    PaymentAcknowledged,
+    MailExpiredUnread,
+    PaymentReclaimed,
+    // Configuration
+    UsdcMintUpdated,
+    FeeUpdated,
+    UsdFeeUpdated,
+    CrankBountyUpdated,
+    RolloverUpdated,
+    BucketDestinationUpdated,
+    ConfigChangeSimulated,
+    MessageLimitsUpdated,
+    CallerPolicyUpdated,
+    OriginPolicyUpdated,
+    PowDifficultyUpdated,
+    TokenAdded,
+    TokenRemoved,
+    TokenDisplayUpdated,
+    PriceFeedUpdated,
+);
+
+impl MailerEvent {
+    /// Decode the events the mailer logged in a transaction's log messages
+    ///
+    /// Tracks the invocation stack so `Program data:` lines emitted by other
+    /// programs in the same transaction are skipped. Unknown or malformed
+    /// entries are ignored; logs cut off by truncation simply yield fewer events.
+    pub fn try_from_logs<S: AsRef<str>>(logs: &[S]) -> Vec<Self> {
+        let mailer_id = mailer::ID.to_string();
+        let mut stack: Vec<&str> = Vec::new();
+        let mut events = Vec::new();
+
+        for log in logs {
+            let log = log.as_ref();
+            if let Some(data) = log.strip_prefix(PROGRAM_DATA) {
+                if stack.last() == Some(&mailer_id.as_str()) {
+                    if let Some(event) = STANDARD.decode(data).ok().and_then(|bytes| Self::try_from_bytes(&bytes)) {
+                        events.push(event);
+                    }
+                }
+            } else if let Some(rest) = log.strip_prefix("Program ") {
+                let mut words = rest.split_whitespace();
+                let (program, action) = (words.next(), words.next());
+                match action {
+                    Some("invoke") => stack.extend(program),
+                    Some("success") | Some("failed:") => {
+                        stack.pop();
+                    }
+                    _ => {}
+                }
+            }
+        }
+        events
+    }
+
+    /// Decode an event from the data of a self-CPI inner instruction
+    ///
+    /// `program_id` is the inner instruction's program; anything other than
+    /// a mailer event instruction yields `None`.
+    pub fn try_from_cpi_data(program_id: &Pubkey, data: &[u8]) -> Option<Self> {
+        if *program_id != mailer::ID {
+            return None;
+        }
+        data.strip_prefix(EVENT_IX_TAG_LE.as_slice())
+            .and_then(Self::try_from_bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anchor_lang::Event;
+
+    fn shares_recorded(recipient: Pubkey) -> mailer::SharesRecorded {
+        mailer::SharesRecorded {
+            recipient,
+            recipient_amount: 90_000,
+            owner_amount: 10_000,
+            fee_snapshot: mailer::FeeSnapshot::default(),
+            mint: Pubkey::new_unique(),
+        }
+    }
+
+    #[test]
+    fn logs_yield_only_mailer_events() {
+        let recipient = Pubkey::new_unique();
+        let data = format!("{}{}", PROGRAM_DATA, STANDARD.encode(shares_recorded(recipient).data()));
+        let other = Pubkey::new_unique();
+        let logs = vec![
+            format!("Program {} invoke [1]", other),
+            data.clone(),
+            format!("Program {} invoke [2]", mailer::ID),
+            "Program log: Instruction: SendPriority".to_string(),
+            data.clone(),
+            format!("Program {} consumed 5000 of 200000 compute units", mailer::ID),
+            format!("Program {} success", mailer::ID),
+            data,
+            format!("Program {} success", other),
+        ];
+
+        let events = MailerEvent::try_from_logs(&logs);
+        assert_eq!(events.len(), 1);
+        assert!(matches!(&events[0], MailerEvent::SharesRecorded(event) if event.recipient == recipient));
+        assert_eq!(events[0].name(), "SharesRecorded");
+    }
+
+    #[test]
+    fn cpi_data_needs_event_tag_and_mailer_program() {
+        let tagged = [EVENT_IX_TAG_LE.as_slice(), &shares_recorded(Pubkey::new_unique()).data()].concat();
+        assert!(MailerEvent::try_from_cpi_data(&mailer::ID, &tagged).is_some());
+        assert!(MailerEvent::try_from_cpi_data(&Pubkey::new_unique(), &tagged).is_none());
+        assert!(MailerEvent::try_from_cpi_data(&mailer::ID, &tagged[8..]).is_none());
+    }
+}
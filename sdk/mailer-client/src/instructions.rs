@@ -0,0 +1,291 @@
+//! Instruction builders.
+//!
+//! Each builder derives every account the instruction needs and returns a
+//! plain `Instruction` ready to be signed, so callers only supply keys and
+//! arguments.
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::{system_program, sysvar};
+use anchor_lang::{InstructionData, ToAccountMetas};
+use mailer::{FeeSource, SendWithPaymentArgs};
+
+use crate::pda::{
+    block_address, caller_policy_address, claim_address, credit_address, denylist_address,
+    escrow_address, expiry_index_address, inbox_address, mailer_address, owner_bucket_address,
+    plan_subscription_address, preferences_address, rate_limit_address, stats_address,
+    token_account_address, token_config_address,
+};
+
+/// Signers, mint and programs of a fee-paying send
+#[derive(Clone, Debug)]
+pub struct SendAccounts {
+    pub sender: Pubkey,
+    /// Pays rent for recipient-side PDAs; usually the sender, or a relayer
+    pub payer: Pubkey,
+    /// Mint the fee is paid in
+    pub mint: Pubkey,
+    /// Token program owning `mint` (SPL Token or Token-2022)
+    pub token_program: Pubkey,
+    /// Pyth price update, required when the mint is priced by an oracle
+    pub price_update: Option<Pubkey>,
+    /// Draw fees from the sender's prepaid credit in `mint` before transferring tokens
+    pub use_credit: bool,
+    /// Cover standard sends with the sender's plan subscription while it lasts
+    pub use_subscription: bool,
+}
+
+impl SendAccounts {
+    /// Sender paying its own rent, in a mint without an oracle price
+    pub fn new(sender: Pubkey, mint: Pubkey, token_program: Pubkey) -> Self {
+        Self {
+            sender,
+            payer: sender,
+            mint,
+            token_program,
+            price_update: None,
+            use_credit: false,
+            use_subscription: false,
+        }
+    }
+
+    fn to_account_metas(&self, now: i64) -> Vec<AccountMeta> {
+        let mailer = mailer_address();
+        mailer::accounts::SendMessage {
+            recipient_claim: claim_address(&self.sender),
+            mailer,
+            stats: stats_address(),
+            token_config: token_config_address(&self.mint),
+            price_update: self.price_update,
+            owner_bucket: owner_bucket_address(&self.mint, FeeSource::Send),
+            mint: self.mint,
+            sender: self.sender,
+            payer: self.payer,
+            sender_usdc_account: token_account_address(&self.sender, &self.mint, &self.token_program),
+            mailer_usdc_account: token_account_address(&mailer, &self.mint, &self.token_program),
+            recipient_preferences: preferences_address(&self.sender),
+            caller_policy: caller_policy_address(),
+            instructions: sysvar::instructions::ID,
+            expiry_index: expiry_index_address(now),
+            mail_id_record: None,
+            rate_limit: rate_limit_address(&self.sender),
+            denylist: denylist_address(&self.sender),
+            fee_exempt: None,
+            referral: None,
+            referrer_claim: None,
+            campaign: None,
+            sender_stats: None,
+            nft_token: None,
+            nft_metadata: None,
+            stake: None,
+            receipt_config: None,
+            receipt_tree_config: None,
+            receipt_tree: None,
+            bubblegum_program: None,
+            log_wrapper: None,
+            compression_program: None,
+            message_log: None,
+            message_log_tree: None,
+            inbox: Some(inbox_address(&self.sender)),
+            credit: self
+                .use_credit
+                .then(|| credit_address(&self.sender, &self.mint)),
+            plan_subscription: self
+                .use_subscription
+                .then(|| plan_subscription_address(&self.sender)),
+            token_program: self.token_program,
+            associated_token_program: anchor_spl::associated_token::ID,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None)
+    }
+}
+
+/// `send_priority`: full fee with a revenue share back to the sender
+///
+/// `now` is the current unix time, used to pick the expiry bucket the send
+/// may list the sender's claim in.
+pub fn build_send_priority_ix(
+    accounts: &SendAccounts,
+    subject: String,
+    body: String,
+    client_ref: Option<[u8; 16]>,
+    now: i64,
+) -> Instruction {
+    Instruction {
+        program_id: mailer::ID,
+        accounts: accounts.to_account_metas(now),
+        data: mailer::instruction::SendPriority {
+            subject,
+            body,
+            tip: None,
+            to_external: None,
+            client_ref,
+        }
+        .data(),
+    }
+}
+
+/// `send`: standard message paying only the owner fee
+pub fn build_send_ix(
+    accounts: &SendAccounts,
+    subject: String,
+    body: String,
+    client_ref: Option<[u8; 16]>,
+    now: i64,
+) -> Instruction {
+    Instruction {
+        program_id: mailer::ID,
+        accounts: accounts.to_account_metas(now),
+        data: mailer::instruction::Send {
+            subject,
+            body,
+            tip: None,
+            to_external: None,
+            client_ref,
+        }
+        .data(),
+    }
+}
+
+/// `send_with_payment`: escrow `args.amount` for the recipient alongside a message
+///
+/// Only the signers, mint and token program of `accounts` are used; payments
+/// pay no fee, so credit and subscription options are ignored.
+pub fn build_send_with_payment_ix(accounts: &SendAccounts, args: SendWithPaymentArgs) -> Instruction {
+    let mailer = mailer_address();
+    Instruction {
+        program_id: mailer::ID,
+        accounts: mailer::accounts::SendWithPayment {
+            escrow: escrow_address(&args.payment_id),
+            mailer,
+            stats: stats_address(),
+            token_config: token_config_address(&accounts.mint),
+            mint: accounts.mint,
+            sender: accounts.sender,
+            payer: accounts.payer,
+            sender_usdc_account: token_account_address(&accounts.sender, &accounts.mint, &accounts.token_program),
+            mailer_usdc_account: token_account_address(&mailer, &accounts.mint, &accounts.token_program),
+            rate_limit: rate_limit_address(&accounts.sender),
+            denylist: denylist_address(&accounts.sender),
+            recipient_denylist: denylist_address(&args.recipient),
+            block: block_address(&args.recipient, &accounts.sender),
+            inbox: Some(inbox_address(&args.recipient)),
+            token_program: accounts.token_program,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: mailer::instruction::SendWithPayment { args }.data(),
+    }
+}
+
+/// `claim_recipient_share`: withdraw the recipient's claimable revenue share
+///
+/// `mint` is the claim's mint and `claim_timestamp` its `timestamp` field,
+/// which selects the expiry bucket the claim is listed in.
+pub fn build_claim_ix(
+    recipient: Pubkey,
+    mint: Pubkey,
+    token_program: Pubkey,
+    claim_timestamp: i64,
+) -> Instruction {
+    let mailer = mailer_address();
+    Instruction {
+        program_id: mailer::ID,
+        accounts: mailer::accounts::ClaimRecipientShare {
+            recipient_claim: claim_address(&recipient),
+            mailer,
+            recipient,
+            mint,
+            recipient_usdc_account: token_account_address(&recipient, &mint, &token_program),
+            mailer_usdc_account: token_account_address(&mailer, &mint, &token_program),
+            expiry_index: expiry_index_address(claim_timestamp),
+            denylist: denylist_address(&recipient),
+            token_program,
+        }
+        .to_account_metas(None),
+        data: mailer::instruction::ClaimRecipientShare {}.data(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anchor_lang::Discriminator;
+
+    #[test]
+    fn send_priority_targets_the_mailer_with_signers_marked() {
+        let accounts = SendAccounts::new(Pubkey::new_unique(), Pubkey::new_unique(), anchor_spl::token::ID);
+        let ix = build_send_priority_ix(&accounts, "Subject".into(), "Body".into(), None, 1_700_000_000);
+
+        assert_eq!(ix.program_id, mailer::ID);
+        assert_eq!(&ix.data[..8], &mailer::instruction::SendPriority::DISCRIMINATOR);
+        let signers: Vec<_> = ix.accounts.iter().filter(|meta| meta.is_signer).map(|meta| meta.pubkey).collect();
+        assert_eq!(signers, vec![accounts.sender, accounts.payer]);
+        assert_eq!(ix.accounts[0].pubkey, claim_address(&accounts.sender));
+    }
+
+    #[test]
+    fn claim_lists_the_claims_expiry_bucket() {
+        let recipient = Pubkey::new_unique();
+        let ix = build_claim_ix(recipient, Pubkey::new_unique(), anchor_spl::token::ID, 1_700_000_000);
+
+        assert_eq!(&ix.data[..], &mailer::instruction::ClaimRecipientShare::DISCRIMINATOR);
+        assert!(ix
+            .accounts
+            .iter()
+            .any(|meta| meta.pubkey == expiry_index_address(1_700_000_000) && meta.is_writable));
+    }
+
+    #[test]
+    fn claim_accounts_follow_the_program_order() {
+        let recipient = Pubkey::new_unique();
+        let mint = Pubkey::new_unique();
+        let token_program = anchor_spl::token::ID;
+        let mailer = mailer_address();
+        let ix = build_claim_ix(recipient, mint, token_program, 1_700_000_000);
+
+        let accounts: Vec<_> = ix
+            .accounts
+            .iter()
+            .map(|meta| (meta.pubkey, meta.is_writable, meta.is_signer))
+            .collect();
+        assert_eq!(
+            accounts,
+            vec![
+                (claim_address(&recipient), true, false),
+                (mailer, false, false),
+                (recipient, false, true),
+                (mint, false, false),
+                (token_account_address(&recipient, &mint, &token_program), true, false),
+                (token_account_address(&mailer, &mint, &token_program), true, false),
+                (expiry_index_address(1_700_000_000), true, false),
+                (denylist_address(&recipient), false, false),
+                (token_program, false, false),
+            ]
+        );
+    }
+
+    #[test]
+    fn payment_escrow_is_keyed_by_the_payment_id() {
+        let accounts = SendAccounts::new(Pubkey::new_unique(), Pubkey::new_unique(), anchor_spl::token::ID);
+        let args = SendWithPaymentArgs {
+            payment_id: [7; 32],
+            recipient: Pubkey::new_unique(),
+            amount: 5_000_000,
+            mail_id: "paid-mail".into(),
+            ack_deadline: None,
+            recipient_external: None,
+            client_ref: None,
+        };
+        let ix = build_send_with_payment_ix(&accounts, args.clone());
+
+        assert_eq!(&ix.data[..8], &mailer::instruction::SendWithPayment::DISCRIMINATOR);
+        assert_eq!(ix.accounts[0].pubkey, escrow_address(&args.payment_id));
+        assert!(ix.accounts[0].is_writable);
+        assert!(ix
+            .accounts
+            .iter()
+            .any(|meta| meta.pubkey == block_address(&args.recipient, &accounts.sender)));
+    }
+}
@@ -0,0 +1,33 @@
+//! # Mailer Client
+//!
+//! Off-chain helpers for Rust backends that talk to the mailer program
+//! without the Anchor client stack:
+//!
+//! - [`pda`]: address derivation for every mailer account
+//! - [`instructions`]: builders returning plain `Instruction`s
+//! - [`accounts`]: decoders for fetched account data
+//! - [`events`]: typed decoding of logged and self-CPI events
+//! - [`rpc`]: async fetch and send helpers over `solana-client` (feature `rpc`, on by default)
+//!
+//! ```rust,ignore
+//! let accounts = SendAccounts::new(sender, usdc_mint, spl_token::ID);
+//! let ix = build_send_priority_ix(&accounts, "Subject".into(), "Body".into(), None, now);
+//! let signature = MailerRpc::new(rpc_client).send_instructions(&[ix], &sender_keypair).await?;
+//! ```
+
+pub mod accounts;
+pub mod error;
+pub mod events;
+pub mod instructions;
+pub mod pda;
+#[cfg(feature = "rpc")]
+pub mod rpc;
+
+pub use error::{ClientError, Result};
+pub use events::MailerEvent;
+pub use instructions::{
+    build_claim_ix, build_send_ix, build_send_priority_ix, build_send_with_payment_ix, SendAccounts,
+};
+pub use mailer::ID as MAILER_PROGRAM_ID;
+#[cfg(feature = "rpc")]
+pub use rpc::MailerRpc;
@@ -0,0 +1,110 @@
+//! Program derived addresses of mailer accounts.
+//!
+//! Seeds mirror the account constraints in the program; see the PDA list in
+//! the mailer crate docs.
+
+use anchor_lang::prelude::Pubkey;
+use anchor_spl::associated_token::get_associated_token_address_with_program_id;
+use mailer::expiry::expiry_week;
+use mailer::messaging::mail_id_hash;
+use mailer::referrals::referral_code_hash;
+use mailer::FeeSource;
+
+fn find(seeds: &[&[u8]]) -> Pubkey {
+    Pubkey::find_program_address(seeds, &mailer::ID).0
+}
+
+/// Global `MailerState`
+pub fn mailer_address() -> Pubkey {
+    find(&[b"mailer"])
+}
+
+/// Global `MailerStats` usage counters
+pub fn stats_address() -> Pubkey {
+    find(&[b"mailer_stats"])
+}
+
+/// A recipient's `RecipientClaim`
+pub fn claim_address(recipient: &Pubkey) -> Pubkey {
+    find(&[b"claim", recipient.as_ref()])
+}
+
+/// A recipient's `RecipientPreferences`
+pub fn preferences_address(recipient: &Pubkey) -> Pubkey {
+    find(&[b"prefs", recipient.as_ref()])
+}
+
+/// The owner's `CallerPolicy`
+pub fn caller_policy_address() -> Pubkey {
+    find(&[b"callers"])
+}
+
+/// Fee configuration of an accepted mint
+pub fn token_config_address(mint: &Pubkey) -> Pubkey {
+    find(&[b"token", mint.as_ref()])
+}
+
+/// Owner share bucket for one mint and fee source
+pub fn owner_bucket_address(mint: &Pubkey, source: FeeSource) -> Pubkey {
+    find(&[b"bucket", mint.as_ref(), source.seed()])
+}
+
+/// Expiry index bucket for a claim recorded at `timestamp`
+pub fn expiry_index_address(timestamp: i64) -> Pubkey {
+    find(&[b"expiry", &expiry_week(timestamp).to_le_bytes()])
+}
+
+/// Replay-protection record of a sender's prepared mail ID
+pub fn mail_id_record_address(sender: &Pubkey, mail_id: &str) -> Pubkey {
+    find(&[b"mailid", sender.as_ref(), &mail_id_hash(mail_id)])
+}
+
+/// A sender's daily message counter
+pub fn rate_limit_address(sender: &Pubkey) -> Pubkey {
+    find(&[b"rate", sender.as_ref()])
+}
+
+/// Compliance denylist marker of an address
+pub fn denylist_address(address: &Pubkey) -> Pubkey {
+    find(&[b"deny", address.as_ref()])
+}
+
+/// A registered referral code
+pub fn referral_code_address(code: &str) -> Pubkey {
+    find(&[b"referral", &referral_code_hash(code)])
+}
+
+/// A recipient's delivery counters
+pub fn inbox_address(recipient: &Pubkey) -> Pubkey {
+    find(&[b"inbox", recipient.as_ref()])
+}
+
+/// A sender's proof-of-work counter
+pub fn pow_state_address(sender: &Pubkey) -> Pubkey {
+    find(&[b"pow", sender.as_ref()])
+}
+
+/// Prepaid send credit of `owner` in `mint`
+pub fn credit_address(owner: &Pubkey, mint: &Pubkey) -> Pubkey {
+    find(&[b"credit", owner.as_ref(), mint.as_ref()])
+}
+
+/// A subscriber's `PlanSubscription`
+pub fn plan_subscription_address(subscriber: &Pubkey) -> Pubkey {
+    find(&[b"plan_subscription", subscriber.as_ref()])
+}
+
+/// Payment escrow keyed by the caller-chosen `payment_id`
+pub fn escrow_address(payment_id: &[u8; 32]) -> Pubkey {
+    find(&[b"escrow", payment_id])
+}
+
+/// A recipient's block on `sender`
+pub fn block_address(recipient: &Pubkey, sender: &Pubkey) -> Pubkey {
+    find(&[b"block", recipient.as_ref(), sender.as_ref()])
+}
+
+/// Associated token account of `owner` for `mint`
+pub fn token_account_address(owner: &Pubkey, mint: &Pubkey, token_program: &Pubkey) -> Pubkey {
+    get_associated_token_address_with_program_id(owner, mint, token_program)
+}
@@ -0,0 +1,68 @@
+//! Async helpers over the nonblocking `solana-client` RPC client.
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::AccountDeserialize;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::signature::Signature;
+use solana_sdk::signer::Signer;
+use solana_sdk::transaction::Transaction;
+
+use crate::accounts::{decode, MailerState, RecipientClaim};
+use crate::pda::{claim_address, mailer_address};
+use crate::{ClientError, Result};
+
+/// Reads mailer accounts and submits transactions through one RPC client
+pub struct MailerRpc {
+    client: RpcClient,
+}
+
+impl MailerRpc {
+    pub fn new(client: RpcClient) -> Self {
+        Self { client }
+    }
+
+    /// The underlying RPC client
+    pub fn client(&self) -> &RpcClient {
+        &self.client
+    }
+
+    /// Fetch and decode an account, or `None` if it does not exist
+    pub async fn fetch<T: AccountDeserialize>(&self, address: &Pubkey) -> Result<Option<T>> {
+        let response = self
+            .client
+            .get_account_with_commitment(address, self.client.commitment())
+            .await?;
+        let account = response.value.map(|account| decode(&account.data)).transpose()?;
+        Ok(account)
+    }
+
+    /// Global mailer configuration and counters
+    pub async fn mailer_state(&self) -> Result<MailerState> {
+        let address = mailer_address();
+        self.fetch(&address)
+            .await?
+            .ok_or(ClientError::AccountNotFound(address))
+    }
+
+    /// A recipient's claim, or `None` if they have never received a share
+    pub async fn claim(&self, recipient: &Pubkey) -> Result<Option<RecipientClaim>> {
+        self.fetch(&claim_address(recipient)).await
+    }
+
+    /// Sign `instructions` with `payer` as fee payer and send them, waiting for confirmation
+    pub async fn send_instructions(
+        &self,
+        instructions: &[Instruction],
+        payer: &dyn Signer,
+    ) -> Result<Signature> {
+        let blockhash = self.client.get_latest_blockhash().await?;
+        let transaction = Transaction::new_signed_with_payer(
+            instructions,
+            Some(&payer.pubkey()),
+            &[payer],
+            blockhash,
+        );
+        Ok(self.client.send_and_confirm_transaction(&transaction).await?)
+    }
+}
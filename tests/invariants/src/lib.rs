@@ -0,0 +1,370 @@
+//! Executable model of the mailer and mail service accounting.
+//!
+//! `Protocol` replays instructions against plain balances instead of token
+//! accounts, using the programs' own fee, share and claim arithmetic for every
+//! step. The property tests in `tests/` drive it with randomized instruction
+//! sequences and check the protocol's core invariants after each one:
+//!
+//! - priority fees split into owner and recipient shares that sum to the fee
+//! - the mailer vault always covers claims, send credit and the owner's balance
+//! - a claim only shrinks when the same instruction transfers that value out
+//! - setting a delegation charges the delegation fee exactly once
+//! - a delegate only becomes active by accepting a pending delegation
+//!
+//! The model only covers a single plain SPL mint; Token-2022 transfer fees,
+//! escrows and tips are left to the integration tests.
+
+use anchor_lang::prelude::*;
+use mail_service::{delegation_charge, MailServiceError};
+use mailer::admin::MailerError;
+use mailer::claims::{is_claim_expired, ClaimError, ClaimMode, RecipientClaim};
+use mailer::fees::{
+    split_expired, split_shares, standard_fee, ConfigChange, FeeError, FeeSnapshot,
+    DEFAULT_CRANK_BOUNTY_BPS, SEND_FEE,
+};
+
+/// Timestamp the model clock starts at
+pub const GENESIS: i64 = 1_700_000_000;
+
+/// One instruction against the model; users are indexes into `Protocol::users`
+#[derive(Clone, Debug)]
+pub enum Op {
+    SendPriority { user: usize },
+    Send { user: usize },
+    SendUsingClaim { user: usize, priority: bool },
+    ClaimRecipientShare { user: usize },
+    ClaimOwnerShare,
+    ClaimExpiredShares { user: usize },
+    SetClaimMode { user: usize, mode: ClaimMode },
+    SetFee { new_fee: u64 },
+    SetCrankBounty { new_bounty_bps: u16 },
+    SetRollover { new_rollover_bps: u16 },
+    AdvanceTime { seconds: i64 },
+    /// `None` clears the delegation, `Some(None)` delegates to the default key
+    DelegateTo { user: usize, delegate: Option<Option<usize>> },
+    /// The pending delegate of `user`'s delegation accepts it
+    AcceptDelegation { user: usize },
+    RejectDelegation { user: usize },
+    WithdrawBucket,
+}
+
+/// A wallet together with the program accounts derived from it
+#[derive(Clone)]
+pub struct User {
+    pub key: Pubkey,
+    /// Token balance of the user's wallet
+    pub wallet: u64,
+    pub claim: RecipientClaim,
+    pub delegate: Option<Pubkey>,
+    pub pending_delegate: Option<Pubkey>,
+}
+
+#[derive(Clone)]
+pub struct Protocol {
+    pub now: i64,
+    pub send_fee: u64,
+    pub crank_bounty_bps: u16,
+    pub rollover_bps: u16,
+    /// Mailer token account balance
+    pub vault: u64,
+    /// Owner's send bucket
+    pub owner_claimable: u64,
+    /// Tokens paid out to the owner's bucket destination
+    pub owner_wallet: u64,
+    /// Tokens paid out to crankers as expiry bounties
+    pub cranker_wallet: u64,
+    pub users: Vec<User>,
+    pub delegation_fee: u64,
+    /// Mail service token account balance
+    pub service_vault: u64,
+    /// Mail service delegation bucket
+    pub delegation_claimable: u64,
+    /// Tokens paid out of the delegation bucket
+    pub delegation_withdrawn: u64,
+}
+
+impl Protocol {
+    /// Fresh deployment with one user per wallet balance
+    pub fn new(wallets: &[u64], delegation_fee: u64) -> Self {
+        let users = wallets
+            .iter()
+            .map(|&wallet| User {
+                key: Pubkey::new_unique(),
+                wallet,
+                claim: empty_claim(),
+                delegate: None,
+                pending_delegate: None,
+            })
+            .collect();
+        Self {
+            now: GENESIS,
+            send_fee: SEND_FEE,
+            crank_bounty_bps: DEFAULT_CRANK_BOUNTY_BPS,
+            rollover_bps: 0,
+            vault: 0,
+            owner_claimable: 0,
+            owner_wallet: 0,
+            cranker_wallet: 0,
+            users,
+            delegation_fee,
+            service_vault: 0,
+            delegation_claimable: 0,
+            delegation_withdrawn: 0,
+        }
+    }
+
+    /// Apply `op` atomically: on error the state is left untouched, as with a failed transaction
+    pub fn apply(&mut self, op: &Op) -> Result<()> {
+        let mut next = self.clone();
+        next.execute(op)?;
+        *self = next;
+        Ok(())
+    }
+
+    /// Everything the mailer vault owes: claims, send credit and the owner's balance
+    pub fn liabilities(&self) -> u64 {
+        self.users
+            .iter()
+            .map(|user| user.claim.amount + user.claim.send_credit)
+            .sum::<u64>()
+            + self.owner_claimable
+    }
+
+    /// Tokens held anywhere in the model; no instruction mints or burns
+    pub fn total_supply(&self) -> u64 {
+        self.users.iter().map(|user| user.wallet).sum::<u64>()
+            + self.vault
+            + self.owner_wallet
+            + self.cranker_wallet
+            + self.service_vault
+            + self.delegation_withdrawn
+    }
+
+    fn execute(&mut self, op: &Op) -> Result<()> {
+        match *op {
+            Op::SendPriority { user } => {
+                let send_fee = self.send_fee;
+                self.charge_sender(user, send_fee)?;
+                self.record_shares(user, send_fee)
+            }
+            Op::Send { user } => {
+                let owner_fee = standard_fee(self.send_fee)?;
+                self.charge_sender(user, owner_fee)?;
+                self.credit_owner(owner_fee)
+            }
+            Op::SendUsingClaim { user, priority } => {
+                let send_fee = self.send_fee;
+                let fee = if priority {
+                    send_fee
+                } else {
+                    standard_fee(send_fee)?
+                };
+                let claim = &mut self.users[user].claim;
+                let credit_covered = claim.send_credit.min(fee);
+                let netted = if claim.amount > 0 {
+                    claim
+                        .available(self.now)?
+                        .min(fee.checked_sub(credit_covered).ok_or(FeeError::MathOverflow)?)
+                } else {
+                    0
+                };
+                if netted > 0 {
+                    claim.withdraw(netted)?;
+                }
+                self.charge_sender(user, fee.checked_sub(netted).ok_or(FeeError::MathOverflow)?)?;
+                // The netted amount stays in the vault and is re-credited below
+                if priority {
+                    self.record_shares(user, send_fee)
+                } else {
+                    self.credit_owner(fee)
+                }
+            }
+            Op::ClaimRecipientShare { user } => {
+                let now = self.now;
+                let claim = &mut self.users[user].claim;
+                require!(claim.amount > 0, MailerError::NoClaimableAmount);
+                if claim.mode == ClaimMode::Lump {
+                    require!(
+                        !is_claim_expired(claim.timestamp, now),
+                        MailerError::ClaimPeriodExpired
+                    );
+                }
+                let amount = claim.available(now)?;
+                require!(amount > 0, MailerError::NoClaimableAmount);
+                claim.withdraw(amount)?;
+                self.pay_out(amount)?;
+                self.users[user].wallet += amount;
+                Ok(())
+            }
+            Op::ClaimOwnerShare => {
+                let amount = self.owner_claimable;
+                require!(amount > 0, MailerError::NoClaimableAmount);
+                self.owner_claimable = 0;
+                self.pay_out(amount)?;
+                self.owner_wallet += amount;
+                Ok(())
+            }
+            Op::ClaimExpiredShares { user } => {
+                let now = self.now;
+                let claim = &mut self.users[user].claim;
+                require!(claim.amount > 0, MailerError::NoClaimableAmount);
+                require!(
+                    claim.mode == ClaimMode::Lump && is_claim_expired(claim.timestamp, now),
+                    MailerError::ClaimPeriodNotExpired
+                );
+                let (bounty, rolled_over, owner_amount) =
+                    split_expired(claim.amount, self.crank_bounty_bps, self.rollover_bps)?;
+                claim.amount = 0;
+                claim.timestamp = 0;
+                claim.send_credit = claim
+                    .send_credit
+                    .checked_add(rolled_over)
+                    .ok_or(FeeError::MathOverflow)?;
+                self.credit_owner(owner_amount)?;
+                self.pay_out(bounty)?;
+                self.cranker_wallet += bounty;
+                Ok(())
+            }
+            Op::SetClaimMode { user, mode } => {
+                let claim = &mut self.users[user].claim;
+                require!(claim.amount == 0, ClaimError::ClaimNotEmpty);
+                claim.mode = mode;
+                Ok(())
+            }
+            Op::SetFee { new_fee } => {
+                ConfigChange::SendFee { new_fee }.validate()?;
+                self.send_fee = new_fee;
+                Ok(())
+            }
+            Op::SetCrankBounty { new_bounty_bps } => {
+                ConfigChange::CrankBounty { new_bounty_bps }.validate()?;
+                self.crank_bounty_bps = new_bounty_bps;
+                Ok(())
+            }
+            Op::SetRollover { new_rollover_bps } => {
+                ConfigChange::Rollover { new_rollover_bps }.validate()?;
+                self.rollover_bps = new_rollover_bps;
+                Ok(())
+            }
+            Op::AdvanceTime { seconds } => {
+                self.now = self.now.checked_add(seconds).ok_or(FeeError::MathOverflow)?;
+                Ok(())
+            }
+            Op::DelegateTo { user, delegate } => {
+                let delegate = delegate.map(|target| match target {
+                    Some(index) => self.users[index].key,
+                    None => Pubkey::default(),
+                });
+                let fee = delegation_charge(delegate, self.delegation_fee);
+                self.debit_wallet(user, fee)?;
+                self.service_vault += fee;
+                self.delegation_claimable = self
+                    .delegation_claimable
+                    .checked_add(fee)
+                    .ok_or(MailServiceError::MathOverflow)?;
+                self.users[user].delegate = None;
+                self.users[user].pending_delegate = delegate;
+                Ok(())
+            }
+            Op::AcceptDelegation { user } => {
+                let user = &mut self.users[user];
+                require!(user.pending_delegate.is_some(), MailServiceError::NoPendingDelegation);
+                user.delegate = user.pending_delegate.take();
+                Ok(())
+            }
+            Op::RejectDelegation { user } => {
+                // Any user may reject on the delegate's behalf in the model; only the state change matters
+                let user = &mut self.users[user];
+                require!(
+                    user.delegate.is_some() || user.pending_delegate.is_some(),
+                    MailServiceError::NoDelegationToReject
+                );
+                user.delegate = None;
+                user.pending_delegate = None;
+                Ok(())
+            }
+            Op::WithdrawBucket => {
+                let amount = self.delegation_claimable;
+                require!(amount > 0, MailServiceError::NoClaimableAmount);
+                self.delegation_claimable = 0;
+                self.service_vault = self
+                    .service_vault
+                    .checked_sub(amount)
+                    .ok_or(ProgramError::InsufficientFunds)?;
+                self.delegation_withdrawn += amount;
+                Ok(())
+            }
+        }
+    }
+
+    /// Mirror of `charge_sender`: send credit first, the rest from the wallet
+    fn charge_sender(&mut self, user: usize, fee: u64) -> Result<()> {
+        let claim = &mut self.users[user].claim;
+        let from_credit = claim.send_credit.min(fee);
+        claim.send_credit -= from_credit;
+        let transferred = fee.checked_sub(from_credit).ok_or(FeeError::MathOverflow)?;
+        self.debit_wallet(user, transferred)?;
+        self.vault = self.vault.checked_add(transferred).ok_or(FeeError::MathOverflow)?;
+        Ok(())
+    }
+
+    /// Mirror of `record_shares` for a sender's own claim
+    fn record_shares(&mut self, user: usize, total_amount: u64) -> Result<()> {
+        let (owner_amount, recipient_amount) = split_shares(total_amount)?;
+        let now = self.now;
+        let claim = &mut self.users[user].claim;
+        claim.amount = claim
+            .amount
+            .checked_add(recipient_amount)
+            .ok_or(FeeError::MathOverflow)?;
+        if claim.timestamp == 0 {
+            claim.timestamp = now;
+        }
+        claim.fee_snapshot = FeeSnapshot::current(total_amount);
+        self.credit_owner(owner_amount)
+    }
+
+    fn credit_owner(&mut self, amount: u64) -> Result<()> {
+        self.owner_claimable = self
+            .owner_claimable
+            .checked_add(amount)
+            .ok_or(FeeError::MathOverflow)?;
+        Ok(())
+    }
+
+    fn debit_wallet(&mut self, user: usize, amount: u64) -> Result<()> {
+        let wallet = &mut self.users[user].wallet;
+        *wallet = wallet
+            .checked_sub(amount)
+            .ok_or(ProgramError::InsufficientFunds)?;
+        Ok(())
+    }
+
+    /// Transfer out of the mailer vault; the token program rejects overdrafts
+    fn pay_out(&mut self, amount: u64) -> Result<()> {
+        self.vault = self
+            .vault
+            .checked_sub(amount)
+            .ok_or(ProgramError::InsufficientFunds)?;
+        Ok(())
+    }
+}
+
+fn empty_claim() -> RecipientClaim {
+    RecipientClaim {
+        recipient: Pubkey::default(),
+        amount: 0,
+        timestamp: 0,
+        bump: 0,
+        rent_payer: Pubkey::default(),
+        send_credit: 0,
+        fee_snapshot: FeeSnapshot::default(),
+        mint: Pubkey::default(),
+        mode: ClaimMode::Lump,
+        streamed: 0,
+        auto_claim: false,
+        vested: 0,
+        vesting_start: 0,
+        released: 0,
+    }
+}
@@ -0,0 +1,140 @@
+//! Core protocol invariants, checked after every step of randomized instruction sequences.
+
+use invariants::{Op, Protocol};
+use mailer::claims::{ClaimMode, CLAIM_PERIOD};
+use mailer::fees::{split_expired, split_shares, MAX_CRANK_BOUNTY_BPS, SEND_FEE};
+use proptest::prelude::*;
+
+const USERS: usize = 4;
+const DELEGATION_FEE: u64 = 10_000_000;
+
+fn user() -> impl Strategy<Value = usize> {
+    0..USERS
+}
+
+fn op() -> impl Strategy<Value = Op> {
+    prop_oneof![
+        4 => user().prop_map(|user| Op::SendPriority { user }),
+        3 => user().prop_map(|user| Op::Send { user }),
+        3 => (user(), any::<bool>()).prop_map(|(user, priority)| Op::SendUsingClaim { user, priority }),
+        3 => user().prop_map(|user| Op::ClaimRecipientShare { user }),
+        1 => Just(Op::ClaimOwnerShare),
+        3 => user().prop_map(|user| Op::ClaimExpiredShares { user }),
+        1 => (user(), prop_oneof![Just(ClaimMode::Lump), Just(ClaimMode::Streaming)])
+            .prop_map(|(user, mode)| Op::SetClaimMode { user, mode }),
+        1 => prop_oneof![Just(0u64), 1..=SEND_FEE * 10, Just(u64::MAX)]
+            .prop_map(|new_fee| Op::SetFee { new_fee }),
+        1 => (0..=MAX_CRANK_BOUNTY_BPS * 2).prop_map(|new_bounty_bps| Op::SetCrankBounty { new_bounty_bps }),
+        1 => (0..=12_000u16).prop_map(|new_rollover_bps| Op::SetRollover { new_rollover_bps }),
+        3 => (0..=CLAIM_PERIOD).prop_map(|seconds| Op::AdvanceTime { seconds }),
+        2 => (user(), proptest::option::of(proptest::option::of(user())))
+            .prop_map(|(user, delegate)| Op::DelegateTo { user, delegate }),
+        2 => user().prop_map(|user| Op::AcceptDelegation { user }),
+        1 => user().prop_map(|user| Op::RejectDelegation { user }),
+        1 => Just(Op::WithdrawBucket),
+    ]
+}
+
+fn wallets() -> impl Strategy<Value = Vec<u64>> {
+    proptest::collection::vec(0..=50 * SEND_FEE + 2 * DELEGATION_FEE, USERS)
+}
+
+/// Whether `op` is an instruction allowed to shrink `user`'s claim by moving value out of it
+fn transfers_out_of_claim(op: &Op, user: usize) -> bool {
+    matches!(
+        *op,
+        Op::ClaimRecipientShare { user: u }
+            | Op::ClaimExpiredShares { user: u }
+            | Op::SendUsingClaim { user: u, .. } if u == user
+    )
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(512))]
+
+    #[test]
+    fn shares_sum_to_fee(fee in any::<u64>()) {
+        // Fees large enough to overflow the share math are rejected instead of split
+        if let Ok((owner_amount, recipient_amount)) = split_shares(fee) {
+            prop_assert_eq!(owner_amount.checked_add(recipient_amount), Some(fee));
+            prop_assert!(owner_amount <= recipient_amount);
+        }
+    }
+
+    #[test]
+    fn expired_splits_sum_to_claim(
+        amount in any::<u64>(),
+        bounty_bps in 0..=MAX_CRANK_BOUNTY_BPS,
+        rollover_bps in 0..=10_000u16,
+    ) {
+        if let Ok((bounty, rolled_over, owner_amount)) = split_expired(amount, bounty_bps, rollover_bps) {
+            prop_assert_eq!(
+                bounty.checked_add(rolled_over).and_then(|sum| sum.checked_add(owner_amount)),
+                Some(amount)
+            );
+        }
+    }
+
+    #[test]
+    fn invariants_hold_across_instruction_sequences(
+        wallets in wallets(),
+        ops in proptest::collection::vec(op(), 1..200),
+    ) {
+        let mut protocol = Protocol::new(&wallets, DELEGATION_FEE);
+        let supply = protocol.total_supply();
+        let mut delegations_charged = 0u64;
+
+        for op in &ops {
+            let before = protocol.clone();
+            if protocol.apply(op).is_err() {
+                continue;
+            }
+
+            // Vault covers every liability, and tokens are only ever moved, never created
+            prop_assert!(protocol.vault >= protocol.liabilities());
+            prop_assert_eq!(protocol.total_supply(), supply);
+
+            // Claims only decrease when the same instruction transfers that value out
+            for (user, (old, new)) in before.users.iter().zip(&protocol.users).enumerate() {
+                if new.claim.amount < old.claim.amount {
+                    prop_assert!(transfers_out_of_claim(op, user), "{:?} shrank claim {}", op, user);
+                }
+            }
+            if let Op::ClaimRecipientShare { user } = *op {
+                let paid = protocol.users[user].wallet - before.users[user].wallet;
+                prop_assert_eq!(before.users[user].claim.amount - protocol.users[user].claim.amount, paid);
+                prop_assert_eq!(before.vault - protocol.vault, paid);
+            }
+
+            // A delegation is charged exactly once when set, and clearing it is free
+            if let Op::DelegateTo { user, delegate } = *op {
+                let charged = before.users[user].wallet - protocol.users[user].wallet;
+                prop_assert_eq!(protocol.users[user].delegate, None);
+                match protocol.users[user].pending_delegate {
+                    Some(key) if key != anchor_lang::prelude::Pubkey::default() => {
+                        prop_assert!(delegate.is_some());
+                        prop_assert_eq!(charged, DELEGATION_FEE);
+                        delegations_charged += 1;
+                    }
+                    _ => prop_assert_eq!(charged, 0),
+                }
+            }
+            // Accepting is free and activates exactly the delegate that was requested
+            if let Op::AcceptDelegation { user } = *op {
+                prop_assert_eq!(protocol.users[user].wallet, before.users[user].wallet);
+                prop_assert_eq!(protocol.users[user].delegate, before.users[user].pending_delegate);
+                prop_assert_eq!(protocol.users[user].pending_delegate, None);
+            }
+            for (old, new) in before.users.iter().zip(&protocol.users) {
+                if new.delegate.is_some() && new.delegate != old.delegate {
+                    prop_assert!(matches!(op, Op::AcceptDelegation { .. }), "{:?} activated a delegate", op);
+                }
+            }
+            prop_assert_eq!(
+                protocol.delegation_claimable + protocol.delegation_withdrawn,
+                delegations_charged * DELEGATION_FEE
+            );
+            prop_assert!(protocol.service_vault >= protocol.delegation_claimable);
+        }
+    }
+}
@@ -2,6 +2,16 @@ import * as anchor from '@coral-xyz/anchor';
 import { Program } from '@coral-xyz/anchor';
 import { expect } from 'chai';
 import { PublicKey, Keypair, SystemProgram } from '@solana/web3.js';
+import {
+    TOKEN_PROGRAM_ID,
+    ASSOCIATED_TOKEN_PROGRAM_ID,
+    createMint,
+    createAssociatedTokenAccount,
+    mintTo,
+    getAccount,
+    getAssociatedTokenAddressSync
+} from '@solana/spl-token';
+import { createHash } from 'crypto';
 import { MailBoxFactory } from '../target/types/mail_box_factory';
 
 describe('MailBoxFactory', () => {
@@ -15,6 +25,22 @@ describe('MailBoxFactory', () => {
     let factoryPda: PublicKey;
     let factoryBump: number;
 
+    const sha256 = (data: string) => Array.from(createHash('sha256').update(data).digest());
+    const programHash = sha256('program binary');
+    const idlHash = sha256('idl');
+    const metadata = { metadataUri: '', tags: [] };
+
+    const indexAccounts = (deploymentType: string, programId: PublicKey, network: string) => ({
+        deploymentByProgram: PublicKey.findProgramAddressSync(
+            [Buffer.from('deployment_by_program'), programId.toBuffer()],
+            program.programId
+        )[0],
+        deploymentByNetwork: PublicKey.findProgramAddressSync(
+            [Buffer.from('deployment_by_network'), Buffer.from(network), Buffer.from(deploymentType)],
+            program.programId
+        )[0],
+    });
+
     before(async () => {
         // Create keypairs
         owner = Keypair.generate();
@@ -39,7 +65,7 @@ describe('MailBoxFactory', () => {
                 .initialize(version)
                 .accounts({
                     factory: factoryPda,
-                    owner: owner.publicKey,
+                    admin: owner.publicKey,
                     systemProgram: SystemProgram.programId,
                 })
                 .signers([owner])
@@ -50,6 +76,7 @@ describe('MailBoxFactory', () => {
             // Verify factory state
             const factoryAccount = await (program.account as any).factoryState.fetch(factoryPda);
             expect(factoryAccount.owner.toString()).to.equal(owner.publicKey.toString());
+            expect(factoryAccount.admins.map((key: PublicKey) => key.toString())).to.deep.equal([owner.publicKey.toString()]);
             expect(factoryAccount.version).to.equal(version);
             expect(factoryAccount.deploymentCount.toNumber()).to.equal(0);
             expect(factoryAccount.bump).to.equal(factoryBump);
@@ -61,7 +88,7 @@ describe('MailBoxFactory', () => {
                     .initialize('v1.0.1')
                     .accounts({
                         factory: factoryPda,
-                        owner: owner.publicKey,
+                        admin: owner.publicKey,
                         systemProgram: SystemProgram.programId,
                     })
                     .signers([owner])
@@ -89,11 +116,12 @@ describe('MailBoxFactory', () => {
             );
 
             const tx = await (program.methods as any)
-                .registerDeployment(deploymentType, programId, network)
+                .registerDeployment(deploymentType, programId, network, programHash, idlHash, metadata)
                 .accounts({
                     deployment: deploymentPda,
+                    ...indexAccounts(deploymentType, programId, network),
                     factory: factoryPda,
-                    owner: owner.publicKey,
+                    admin: owner.publicKey,
                     systemProgram: SystemProgram.programId,
                 })
                 .signers([owner])
@@ -128,11 +156,12 @@ describe('MailBoxFactory', () => {
             );
 
             const tx = await (program.methods as any)
-                .registerDeployment(deploymentType, programId, network)
+                .registerDeployment(deploymentType, programId, network, programHash, idlHash, metadata)
                 .accounts({
                     deployment: deploymentPda,
+                    ...indexAccounts(deploymentType, programId, network),
                     factory: factoryPda,
-                    owner: owner.publicKey,
+                    admin: owner.publicKey,
                     systemProgram: SystemProgram.programId,
                 })
                 .signers([owner])
@@ -145,7 +174,109 @@ describe('MailBoxFactory', () => {
             expect(factoryAccount.deploymentCount.toNumber()).to.equal(2);
         });
 
-        it('Should fail to register deployment as non-owner', async () => {
+        it('Should look up a deployment by program ID and by network', async () => {
+            const [deploymentPda] = PublicKey.findProgramAddressSync(
+                [Buffer.from('deployment'), new anchor.BN(1).toArrayLike(Buffer, 'le', 8)],
+                program.programId
+            );
+            const registered = await (program.account as any).deploymentInfo.fetch(deploymentPda);
+            const { deploymentByProgram, deploymentByNetwork } =
+                indexAccounts('MailService', registered.programId, 'mainnet-beta');
+
+            for (const index of [deploymentByProgram, deploymentByNetwork]) {
+                const found = await (program.methods as any)
+                    .lookupDeployment()
+                    .accounts({ index, deployment: deploymentPda })
+                    .view();
+                expect(found.programId.toString()).to.equal(registered.programId.toString());
+                expect(found.network).to.equal('mainnet-beta');
+            }
+        });
+
+        it('Should update deployment metadata and reject oversized tags', async () => {
+            const [deploymentPda] = PublicKey.findProgramAddressSync(
+                [Buffer.from('deployment'), new anchor.BN(0).toArrayLike(Buffer, 'le', 8)],
+                program.programId
+            );
+            const accounts = { deployment: deploymentPda, factory: factoryPda, admin: owner.publicKey };
+            const manifest = { metadataUri: 'ar://manifest', tags: ['official', 'v1'] };
+
+            await (program.methods as any)
+                .updateDeploymentMetadata(manifest)
+                .accounts(accounts)
+                .signers([owner])
+                .rpc();
+            const deploymentAccount = await (program.account as any).deploymentInfo.fetch(deploymentPda);
+            expect(deploymentAccount.metadataUri).to.equal(manifest.metadataUri);
+            expect(deploymentAccount.tags).to.deep.equal(manifest.tags);
+
+            try {
+                await (program.methods as any)
+                    .updateDeploymentMetadata({ metadataUri: '', tags: ['a', 'b', 'c', 'd', 'e'] })
+                    .accounts(accounts)
+                    .signers([owner])
+                    .rpc();
+                expect.fail('Should have failed');
+            } catch (error) {
+                expect((error as any).message).to.include('InvalidMetadata');
+            }
+        });
+
+        it('Should attest a deployment only against its registered program hash', async () => {
+            const [deploymentPda] = PublicKey.findProgramAddressSync(
+                [Buffer.from('deployment'), new anchor.BN(0).toArrayLike(Buffer, 'le', 8)],
+                program.programId
+            );
+            const accounts = { deployment: deploymentPda, factory: factoryPda, admin: owner.publicKey };
+
+            try {
+                await (program.methods as any)
+                    .attestDeployment(sha256('tampered binary'))
+                    .accounts(accounts)
+                    .signers([owner])
+                    .rpc();
+                expect.fail('Should have failed');
+            } catch (error) {
+                expect((error as any).message).to.include('HashMismatch');
+            }
+
+            await (program.methods as any)
+                .attestDeployment(programHash)
+                .accounts(accounts)
+                .signers([owner])
+                .rpc();
+            const deploymentAccount = await (program.account as any).deploymentInfo.fetch(deploymentPda);
+            expect(deploymentAccount.attested).to.be.true;
+            expect(deploymentAccount.idlHash).to.deep.equal(idlHash);
+        });
+
+        it('Should deactivate and reactivate a deployment', async () => {
+            const [deploymentPda] = PublicKey.findProgramAddressSync(
+                [Buffer.from('deployment'), new anchor.BN(0).toArrayLike(Buffer, 'le', 8)],
+                program.programId
+            );
+            expect((await (program.account as any).deploymentInfo.fetch(deploymentPda)).active).to.be.true;
+
+            await (program.methods as any)
+                .deactivateDeployment('Superseded by v1.1.0')
+                .accounts({ deployment: deploymentPda, factory: factoryPda, admin: owner.publicKey })
+                .signers([owner])
+                .rpc();
+            let deploymentAccount = await (program.account as any).deploymentInfo.fetch(deploymentPda);
+            expect(deploymentAccount.active).to.be.false;
+            expect(deploymentAccount.deprecatedReason).to.equal('Superseded by v1.1.0');
+
+            await (program.methods as any)
+                .reactivateDeployment()
+                .accounts({ deployment: deploymentPda, factory: factoryPda, admin: owner.publicKey })
+                .signers([owner])
+                .rpc();
+            deploymentAccount = await (program.account as any).deploymentInfo.fetch(deploymentPda);
+            expect(deploymentAccount.active).to.be.true;
+            expect(deploymentAccount.deprecatedReason).to.equal('');
+        });
+
+        it('Should fail to register deployment as non-admin', async () => {
             const deploymentType = 'Mailer';
             const programId = Keypair.generate().publicKey;
             const network = 'testnet';
@@ -160,20 +291,78 @@ describe('MailBoxFactory', () => {
 
             try {
                 await (program.methods as any)
-                    .registerDeployment(deploymentType, programId, network)
+                    .registerDeployment(deploymentType, programId, network, programHash, idlHash, metadata)
                     .accounts({
                         deployment: deploymentPda,
+                        ...indexAccounts(deploymentType, programId, network),
                         factory: factoryPda,
-                        owner: user1.publicKey, // Non-owner trying to register
+                        admin: user1.publicKey, // Non-admin trying to register
                         systemProgram: SystemProgram.programId,
                     })
                     .signers([user1])
                     .rpc();
                 expect.fail('Should have failed');
             } catch (error) {
-                expect((error as any).message).to.include('OnlyOwner');
+                expect((error as any).message).to.include('OnlyAdmin');
             }
         });
+
+        it('Should let anyone register for the fee and admins withdraw it', async () => {
+            const payer = (provider.wallet as any).payer;
+            const usdcMint = await createMint(provider.connection, payer, provider.wallet.publicKey, null, 6);
+            const fee = 5_000_000;
+
+            await (program.methods as any)
+                .setRegistrationFee(usdcMint, new anchor.BN(fee))
+                .accounts({ factory: factoryPda, admin: owner.publicKey })
+                .signers([owner])
+                .rpc();
+
+            const user1Usdc = await createAssociatedTokenAccount(provider.connection, payer, usdcMint, user1.publicKey);
+            await mintTo(provider.connection, payer, usdcMint, user1Usdc, provider.wallet.publicKey, fee);
+            const factoryUsdc = getAssociatedTokenAddressSync(usdcMint, factoryPda, true);
+
+            const factoryAccount = await (program.account as any).factoryState.fetch(factoryPda);
+            const [deploymentPda] = PublicKey.findProgramAddressSync(
+                [Buffer.from('deployment'), factoryAccount.deploymentCount.toArrayLike(Buffer, 'le', 8)],
+                program.programId
+            );
+
+            await (program.methods as any)
+                .registerDeploymentPaid('Mailer', Keypair.generate().publicKey, 'devnet', programHash, idlHash, metadata)
+                .accounts({
+                    deployment: deploymentPda,
+                    factory: factoryPda,
+                    registrant: user1.publicKey,
+                    usdcMint,
+                    registrantUsdcAccount: user1Usdc,
+                    factoryUsdcAccount: factoryUsdc,
+                    tokenProgram: TOKEN_PROGRAM_ID,
+                    associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+                    systemProgram: SystemProgram.programId,
+                })
+                .signers([user1])
+                .rpc();
+
+            const deploymentAccount = await (program.account as any).deploymentInfo.fetch(deploymentPda);
+            expect(deploymentAccount.deployer.toString()).to.equal(user1.publicKey.toString());
+            expect(deploymentAccount.curated).to.be.false;
+            expect(Number((await getAccount(provider.connection, factoryUsdc)).amount)).to.equal(fee);
+
+            const ownerUsdc = await createAssociatedTokenAccount(provider.connection, payer, usdcMint, owner.publicKey);
+            await (program.methods as any)
+                .withdrawRegistrationFees()
+                .accounts({
+                    factory: factoryPda,
+                    admin: owner.publicKey,
+                    factoryUsdcAccount: factoryUsdc,
+                    adminUsdcAccount: ownerUsdc,
+                    tokenProgram: TOKEN_PROGRAM_ID,
+                })
+                .signers([owner])
+                .rpc();
+            expect(Number((await getAccount(provider.connection, ownerUsdc)).amount)).to.equal(fee);
+        });
     });
 
     describe('Address Prediction', () => {
@@ -216,48 +405,86 @@ describe('MailBoxFactory', () => {
     });
 
     describe('Batch Initialization', () => {
-        it('Should emit batch initialization event', async () => {
-            const projectName = 'BatchTest';
-            const version = 'v1.0.0';
-            const usdcMint = Keypair.generate().publicKey;
-            const mailerProgram = Keypair.generate().publicKey;
-            const mailServiceProgram = Keypair.generate().publicKey;
+        // The mailer and mail service state PDAs are singletons initialized by their own
+        // suites, so these tests only exercise the factory's checks before the CPIs
+        const network = 'localnet';
+        const usdcMint = Keypair.generate().publicKey;
+        const [networkConfigPda] = PublicKey.findProgramAddressSync(
+            [Buffer.from('network'), Buffer.from(network)],
+            program.programId
+        );
 
-            const tx = await (program.methods as any)
-                .batchInitializePrograms(projectName, version, usdcMint)
+        const batchAccounts = (admin: PublicKey, mailerProgram: PublicKey, mailServiceProgram: PublicKey) => ({
+            factory: factoryPda,
+            admin,
+            networkConfig: networkConfigPda,
+            usdcMint,
+            mailer: PublicKey.findProgramAddressSync([Buffer.from('mailer')], mailerProgram)[0],
+            mailerStats: PublicKey.findProgramAddressSync([Buffer.from('mailer_stats')], mailerProgram)[0],
+            mailService: PublicKey.findProgramAddressSync([Buffer.from('mail_service')], mailServiceProgram)[0],
+            delegationBucket: PublicKey.findProgramAddressSync(
+                [Buffer.from('bucket'), Buffer.from('delegation')],
+                mailServiceProgram
+            )[0],
+            handleBucket: PublicKey.findProgramAddressSync(
+                [Buffer.from('bucket'), Buffer.from('handle')],
+                mailServiceProgram
+            )[0],
+            mailerProgram,
+            mailServiceProgram,
+            systemProgram: SystemProgram.programId,
+        });
+
+        it('Should store per-network defaults for batch initialization', async () => {
+            await (program.methods as any)
+                .setNetworkConfig(network, usdcMint, new anchor.BN(100_000), new anchor.BN(10_000_000))
                 .accounts({
+                    networkConfig: networkConfigPda,
                     factory: factoryPda,
-                    coordinator: owner.publicKey,
-                    mailerProgram: mailerProgram,
-                    mailServiceProgram: mailServiceProgram,
+                    admin: owner.publicKey,
+                    systemProgram: SystemProgram.programId,
                 })
                 .signers([owner])
                 .rpc();
 
-            console.log('Batch initialization transaction:', tx);
+            const config = await (program.account as any).networkConfig.fetch(networkConfigPda);
+            expect(config.network).to.equal(network);
+            expect(config.usdcMint.toString()).to.equal(usdcMint.toString());
+            expect(config.sendFee.toNumber()).to.equal(100_000);
+            expect(config.delegationFee.toNumber()).to.equal(10_000_000);
         });
 
-        it('Should fail batch initialization as non-owner', async () => {
-            const projectName = 'FailTest';
-            const version = 'v1.0.0';
-            const usdcMint = Keypair.generate().publicKey;
-            const mailerProgram = Keypair.generate().publicKey;
-            const mailServiceProgram = Keypair.generate().publicKey;
-
+        it('Should fail batch initialization as non-admin', async () => {
             try {
                 await (program.methods as any)
-                    .batchInitializePrograms(projectName, version, usdcMint)
-                    .accounts({
-                        factory: factoryPda,
-                        coordinator: user1.publicKey, // Non-owner
-                        mailerProgram: mailerProgram,
-                        mailServiceProgram: mailServiceProgram,
-                    })
+                    .batchInitializePrograms('FailTest', 'v1.0.0', network)
+                    .accounts(batchAccounts(
+                        user1.publicKey, // Non-admin
+                        anchor.workspace.Mailer.programId,
+                        anchor.workspace.MailService.programId
+                    ))
                     .signers([user1])
                     .rpc();
                 expect.fail('Should have failed');
             } catch (error) {
-                expect((error as any).message).to.include('OnlyOwner');
+                expect((error as any).message).to.include('OnlyAdmin');
+            }
+        });
+
+        it('Should refuse to initialize programs other than the mailer and mail service', async () => {
+            try {
+                await (program.methods as any)
+                    .batchInitializePrograms('BatchTest', 'v1.0.0', network)
+                    .accounts(batchAccounts(
+                        owner.publicKey,
+                        Keypair.generate().publicKey,
+                        Keypair.generate().publicKey
+                    ))
+                    .signers([owner])
+                    .rpc();
+                expect.fail('Should have failed');
+            } catch (error) {
+                expect((error as any).message).to.include('InvalidProgramId');
             }
         });
     });
@@ -270,7 +497,7 @@ describe('MailBoxFactory', () => {
                 .updateVersion(newVersion)
                 .accounts({
                     factory: factoryPda,
-                    owner: owner.publicKey,
+                    admin: owner.publicKey,
                 })
                 .signers([owner])
                 .rpc();
@@ -282,45 +509,83 @@ describe('MailBoxFactory', () => {
             expect(factoryAccount.version).to.equal(newVersion);
         });
 
-        it('Should set new owner', async () => {
-            const newOwner = user1.publicKey;
+        it('Should leave a factory already in the current layout unchanged when migrating', async () => {
+            const before = await provider.connection.getAccountInfo(factoryPda);
 
-            const tx = await (program.methods as any)
-                .setOwner(newOwner)
+            await (program.methods as any)
+                .migrateFactory()
                 .accounts({
                     factory: factoryPda,
                     owner: owner.publicKey,
+                    systemProgram: SystemProgram.programId,
                 })
                 .signers([owner])
                 .rpc();
 
-            console.log('Owner update transaction:', tx);
+            const after = await provider.connection.getAccountInfo(factoryPda);
+            expect(after!.data.equals(before!.data)).to.be.true;
+        });
+
+        it('Should add an admin who can manage the factory alongside the first', async () => {
+            await (program.methods as any)
+                .addAdmin(user1.publicKey)
+                .accounts({
+                    factory: factoryPda,
+                    admin: owner.publicKey,
+                })
+                .signers([owner])
+                .rpc();
 
-            // Verify owner was updated
             const factoryAccount = await (program.account as any).factoryState.fetch(factoryPda);
-            expect(factoryAccount.owner.toString()).to.equal(newOwner.toString());
+            expect(factoryAccount.admins.map((key: PublicKey) => key.toString()))
+                .to.deep.equal([owner.publicKey.toString(), user1.publicKey.toString()]);
+        });
+
+        it('Should let any admin remove another but never the last', async () => {
+            await (program.methods as any)
+                .removeAdmin(owner.publicKey)
+                .accounts({
+                    factory: factoryPda,
+                    admin: user1.publicKey,
+                })
+                .signers([user1])
+                .rpc();
+
+            try {
+                await (program.methods as any)
+                    .removeAdmin(user1.publicKey)
+                    .accounts({
+                        factory: factoryPda,
+                        admin: user1.publicKey,
+                    })
+                    .signers([user1])
+                    .rpc();
+                expect.fail('Should have failed');
+            } catch (error) {
+                expect((error as any).message).to.include('LastAdmin');
+            }
         });
 
-        it('Should fail to update version as non-owner', async () => {
+        it('Should fail to update version as a removed admin', async () => {
             try {
                 await (program.methods as any)
                     .updateVersion('v3.0.0')
                     .accounts({
                         factory: factoryPda,
-                        owner: owner.publicKey, // Original owner, but ownership transferred
+                        admin: owner.publicKey, // Original admin, since removed
                     })
                     .signers([owner])
                     .rpc();
                 expect.fail('Should have failed');
             } catch (error) {
-                expect((error as any).message).to.include('OnlyOwner');
+                expect((error as any).message).to.include('OnlyAdmin');
             }
         });
     });
 
     describe('Integration Tests', () => {
         it('Should handle full deployment workflow', async () => {
-            // Register multiple deployments with new owner (user1)
+            // Register multiple deployments with the remaining admin (user1)
             const deployments = [
                 { type: 'Mailer', network: 'localnet' },
                 { type: 'MailService', network: 'localnet' },
@@ -344,11 +609,12 @@ describe('MailBoxFactory', () => {
                 );
 
                 await (program.methods as any)
-                    .registerDeployment(deployment.type, programId, deployment.network)
+                    .registerDeployment(deployment.type, programId, deployment.network, programHash, idlHash, metadata)
                     .accounts({
                         deployment: deploymentPda,
+                        ...indexAccounts(deployment.type, programId, deployment.network),
                         factory: factoryPda,
-                        owner: user1.publicKey, // New owner
+                        admin: user1.publicKey, // Remaining admin
                         systemProgram: SystemProgram.programId,
                     })
                     .signers([user1])
@@ -360,6 +626,66 @@ describe('MailBoxFactory', () => {
             expect(factoryAfter.deploymentCount.toNumber()).to.equal(initialCount + deployments.length);
         });
 
+        it('Should deregister a deployment, refund its rent and tombstone its lookups', async () => {
+            const programId = Keypair.generate().publicKey;
+            const factoryAccount = await (program.account as any).factoryState.fetch(factoryPda);
+            const [deploymentPda] = PublicKey.findProgramAddressSync(
+                [Buffer.from('deployment'), factoryAccount.deploymentCount.toArrayLike(Buffer, 'le', 8)],
+                program.programId
+            );
+            const indexes = indexAccounts('Mailer', programId, 'devnet');
+
+            await (program.methods as any)
+                .registerDeployment('Mailer', programId, 'devnet', programHash, idlHash, metadata)
+                .accounts({
+                    deployment: deploymentPda,
+                    ...indexes,
+                    factory: factoryPda,
+                    admin: user1.publicKey,
+                    systemProgram: SystemProgram.programId,
+                })
+                .signers([user1])
+                .rpc();
+
+            try {
+                await (program.methods as any)
+                    .deregisterDeployment()
+                    .accounts({
+                        deployment: deploymentPda,
+                        deploymentByProgram: null,
+                        deploymentByNetwork: indexes.deploymentByNetwork,
+                        factory: factoryPda,
+                        deployer: user1.publicKey,
+                        authority: user1.publicKey,
+                    })
+                    .signers([user1])
+                    .rpc();
+                expect.fail('Should have failed');
+            } catch (error) {
+                expect((error as any).message).to.include('MissingDeploymentIndex');
+            }
+
+            const balanceBefore = await provider.connection.getBalance(user1.publicKey);
+            await (program.methods as any)
+                .deregisterDeployment()
+                .accounts({
+                    deployment: deploymentPda,
+                    ...indexes,
+                    factory: factoryPda,
+                    deployer: user1.publicKey,
+                    authority: user1.publicKey,
+                })
+                .signers([user1])
+                .rpc();
+
+            expect(await provider.connection.getAccountInfo(deploymentPda)).to.be.null;
+            expect(await provider.connection.getBalance(user1.publicKey)).to.be.greaterThan(balanceBefore);
+            for (const index of [indexes.deploymentByProgram, indexes.deploymentByNetwork]) {
+                const indexAccount = await (program.account as any).deploymentIndex.fetch(index);
+                expect(indexAccount.deployment.toString()).to.equal(PublicKey.default.toString());
+            }
+        });
+
         it('Should verify program PDA derivation consistency', () => {
             const [derivedFactoryPda, derivedBump] = PublicKey.findProgramAddressSync(
                 [Buffer.from('factory')],
@@ -1,16 +1,18 @@
 import * as anchor from '@coral-xyz/anchor';
 import { Program } from '@coral-xyz/anchor';
 import { expect } from 'chai';
-import { PublicKey, Keypair, SystemProgram } from '@solana/web3.js';
+import { PublicKey, Keypair, SystemProgram, Transaction, sendAndConfirmTransaction } from '@solana/web3.js';
 import { 
     TOKEN_PROGRAM_ID, 
     createMint, 
     createAssociatedTokenAccount, 
     mintTo,
-    getAccount 
+    getAccount,
+    getAssociatedTokenAddressSync
 } from '@solana/spl-token';
 import { MailService } from '../target/types/mail_service';
 import { MailServiceClient } from '../app/mail-service-client';
+import { DelegationPermission } from '../app/types';
 
 describe('MailService', () => {
     const provider = anchor.AnchorProvider.env();
@@ -141,9 +143,95 @@ describe('MailService', () => {
             const txSig = await userClient.delegateTo(user2.publicKey);
             expect(txSig).to.be.a('string');
 
-            // Check delegation was set
-            const delegation = await client.getDelegation(user1.publicKey);
+            // Check delegation is pending until the delegate accepts
+            let delegation = await client.getDelegation(user1.publicKey);
+            expect(delegation?.delegate).to.be.null;
+            expect(delegation?.pendingDelegate?.toString()).to.equal(user2.publicKey.toString());
+
+            const user2Client = new MailServiceClient(
+                provider.connection,
+                {
+                    publicKey: user2.publicKey,
+                    signTransaction: async () => { throw new Error('Not implemented'); },
+                    signAllTransactions: async () => { throw new Error('Not implemented'); }
+                } as any,
+                program.programId,
+                usdcMint
+            );
+            await user2Client.acceptDelegation(user1.publicKey);
+
+            delegation = await client.getDelegation(user1.publicKey);
             expect(delegation?.delegate?.toString()).to.equal(user2.publicKey.toString());
+            expect(delegation?.pendingDelegate).to.be.null;
+            expect(delegation?.permissions).to.equal(DelegationPermission.ALL);
+        });
+
+        it('Should record a delegation expiry and reject one in the past', async () => {
+            const user1Client = new MailServiceClient(
+                provider.connection,
+                {
+                    publicKey: user1.publicKey,
+                    signTransaction: async () => { throw new Error('Not implemented'); },
+                    signAllTransactions: async () => { throw new Error('Not implemented'); }
+                } as any,
+                program.programId,
+                usdcMint
+            );
+
+            const expiresAt = Math.floor(Date.now() / 1000) + 30 * 24 * 60 * 60;
+            await user1Client.delegateTo(user2.publicKey, expiresAt);
+            expect((await client.getDelegation(user1.publicKey))?.expiresAt).to.equal(expiresAt);
+
+            try {
+                await user1Client.delegateTo(user2.publicKey, Math.floor(Date.now() / 1000) - 60);
+                expect.fail('Should have thrown an error');
+            } catch (error: any) {
+                expect(error.message).to.include('Delegation expiry must be in the future');
+            }
+        });
+
+        it('Should store scoped permissions and reject unknown flags', async () => {
+            const user1Client = new MailServiceClient(
+                provider.connection,
+                {
+                    publicKey: user1.publicKey,
+                    signTransaction: async () => { throw new Error('Not implemented'); },
+                    signAllTransactions: async () => { throw new Error('Not implemented'); }
+                } as any,
+                program.programId,
+                usdcMint
+            );
+
+            await user1Client.delegateTo(user2.publicKey, null, DelegationPermission.SEND);
+            expect((await client.getDelegation(user1.publicKey))?.permissions).to.equal(DelegationPermission.SEND);
+
+            try {
+                await user1Client.delegateTo(user2.publicKey, null, 1 << 8);
+                expect.fail('Should have thrown an error');
+            } catch (error: any) {
+                expect(error.message).to.include('Unknown delegation permission flags');
+            }
+        });
+
+        it('Should only let the requested delegate accept', async () => {
+            const user1Client = new MailServiceClient(
+                provider.connection,
+                {
+                    publicKey: user1.publicKey,
+                    signTransaction: async () => { throw new Error('Not implemented'); },
+                    signAllTransactions: async () => { throw new Error('Not implemented'); }
+                } as any,
+                program.programId,
+                usdcMint
+            );
+
+            await user1Client.delegateTo(user2.publicKey);
+            try {
+                await user1Client.acceptDelegation(user1.publicKey);
+                expect.fail('Should have thrown an error');
+            } catch (error: any) {
+                expect(error.message).to.include('No pending delegation to accept');
+            }
         });
 
         it('Should clear delegation when setting to null', async () => {
@@ -203,6 +291,104 @@ describe('MailService', () => {
             // Check delegation was cleared
             const delegation = await client.getDelegation(user1.publicKey);
             expect(delegation?.delegate).to.be.null;
+            expect(delegation?.pendingDelegate).to.be.null;
+        });
+    });
+
+    describe('Closing Delegations', () => {
+        it('Should close a cleared delegation only', async () => {
+            const user1Client = new MailServiceClient(
+                provider.connection,
+                {
+                    publicKey: user1.publicKey,
+                    signTransaction: async () => { throw new Error('Not implemented'); },
+                    signAllTransactions: async () => { throw new Error('Not implemented'); }
+                } as any,
+                program.programId,
+                usdcMint
+            );
+
+            await user1Client.delegateTo(user2.publicKey);
+            try {
+                await user1Client.closeDelegation();
+                expect.fail('Should have thrown an error');
+            } catch (error: any) {
+                expect(error.message).to.include('Delegation still has an active or pending delegate');
+            }
+
+            const lamportsBefore = await provider.connection.getBalance(user1.publicKey);
+            await user1Client.delegateTo(null);
+            await user1Client.closeDelegation();
+
+            expect(await client.getDelegation(user1.publicKey)).to.be.null;
+            expect(await provider.connection.getBalance(user1.publicKey)).to.be.greaterThan(lamportsBefore);
+        });
+    });
+
+    describe('Multiple Delegates', () => {
+        const walletClient = (user: Keypair) => new MailServiceClient(
+            provider.connection,
+            {
+                publicKey: user.publicKey,
+                signTransaction: async () => { throw new Error('Not implemented'); },
+                signAllTransactions: async () => { throw new Error('Not implemented'); }
+            } as any,
+            program.programId,
+            usdcMint
+        );
+
+        it('Should authorize several delegates at once and count them', async () => {
+            const operator = Keypair.generate();
+            await walletClient(user1).addDelegate(user2.publicKey, null, DelegationPermission.SEND);
+            await walletClient(user1).addDelegate(operator.publicKey);
+            expect(await client.getDelegateCount(user1.publicKey)).to.equal(2);
+
+            await walletClient(user2).acceptDelegation(user1.publicKey, true);
+            const delegation = await client.getDelegateDelegation(user1.publicKey, user2.publicKey);
+            expect(delegation?.delegate?.toString()).to.equal(user2.publicKey.toString());
+            expect(delegation?.permissions).to.equal(DelegationPermission.SEND);
+
+            // The operator's delegation is independent and still pending
+            const pending = await client.getDelegateDelegation(user1.publicKey, operator.publicKey);
+            expect(pending?.delegate).to.be.null;
+            expect(pending?.pendingDelegate?.toString()).to.equal(operator.publicKey.toString());
+        });
+
+        it('Should let a delegate enumerate its delegators', async () => {
+            const delegators = await client.getDelegators(user2.publicKey);
+            const perDelegate = client.getDelegateDelegationAddress(user1.publicKey, user2.publicKey);
+            expect(delegators.map(d => d.delegation.toString())).to.include(perDelegate.toString());
+            expect(await client.getDelegatorCount(user2.publicKey)).to.equal(delegators.length);
+        });
+
+        it('Should close a removed delegate and decrement the count', async () => {
+            const delegatorCount = await client.getDelegatorCount(user2.publicKey);
+            await walletClient(user1).removeDelegate(user2.publicKey);
+            expect(await client.getDelegateDelegation(user1.publicKey, user2.publicKey)).to.be.null;
+            expect(await client.getDelegateCount(user1.publicKey)).to.equal(1);
+            expect(await client.getDelegatorCount(user2.publicKey)).to.equal(delegatorCount - 1);
+        });
+
+        it('Should index a delegate whose index address was pre-funded', async () => {
+            const payer = (provider.wallet as any).payer;
+            const delegate = Keypair.generate().publicKey;
+
+            // A dust transfer to the unopened index must not block delegating to it
+            await sendAndConfirmTransaction(
+                provider.connection,
+                new Transaction().add(
+                    SystemProgram.transfer({
+                        fromPubkey: payer.publicKey,
+                        toPubkey: client.getDelegateIndexAddress(delegate),
+                        lamports: 1_000,
+                    })
+                ),
+                [payer]
+            );
+            await walletClient(user2).delegateTo(delegate);
+            expect(await client.getDelegatorCount(delegate)).to.equal(1);
+
+            await walletClient(user2).delegateTo(null);
         });
     });
 
@@ -215,6 +401,103 @@ describe('MailService', () => {
             expect(fees.delegationFee).to.equal(newFee * 1_000_000);
         });
 
+        it('Should charge the reduced update fee when replacing a recent delegation', async () => {
+            await client.setUpdateFee(2, 7 * 24 * 60 * 60);
+            const fees = await client.getFees();
+            expect(fees.updateFee).to.equal(2_000_000);
+            expect(fees.updateWindow).to.equal(7 * 24 * 60 * 60);
+
+            const user2Client = new MailServiceClient(
+                provider.connection,
+                {
+                    publicKey: user2.publicKey,
+                    signTransaction: async () => { throw new Error('Not implemented'); },
+                    signAllTransactions: async () => { throw new Error('Not implemented'); }
+                } as any,
+                program.programId,
+                usdcMint
+            );
+            const user2Usdc = getAssociatedTokenAddressSync(usdcMint, user2.publicKey);
+
+            // A fresh delegation pays the full fee, a rotation right after it only the update fee
+            let balanceBefore = Number((await getAccount(provider.connection, user2Usdc)).amount);
+            await user2Client.delegateTo(user1.publicKey);
+            let balanceAfter = Number((await getAccount(provider.connection, user2Usdc)).amount);
+            expect(balanceBefore - balanceAfter).to.equal(fees.delegationFee);
+
+            balanceBefore = balanceAfter;
+            await user2Client.delegateTo(owner.publicKey);
+            balanceAfter = Number((await getAccount(provider.connection, user2Usdc)).amount);
+            expect(balanceBefore - balanceAfter).to.equal(fees.updateFee);
+
+            await user2Client.delegateTo(null);
+        });
+
+        it('Should accrue a share of delegation fees to the delegate', async () => {
+            await client.setDelegateShare(2_000); // 20%
+            expect((await client.getFees()).delegateShareBps).to.equal(2_000);
+
+            const walletClient = (user: Keypair) => new MailServiceClient(
+                provider.connection,
+                {
+                    publicKey: user.publicKey,
+                    signTransaction: async () => { throw new Error('Not implemented'); },
+                    signAllTransactions: async () => { throw new Error('Not implemented'); }
+                } as any,
+                program.programId,
+                usdcMint
+            );
+
+            const claimBefore = await client.getDelegateClaim(user1.publicKey);
+            await walletClient(user2).addDelegate(user1.publicKey);
+            const fees = await client.getFees();
+            expect(await client.getDelegateClaim(user1.publicKey) - claimBefore).to.equal(fees.delegationFee * 0.2);
+
+            const user1Usdc = getAssociatedTokenAddressSync(usdcMint, user1.publicKey);
+            const balanceBefore = Number((await getAccount(provider.connection, user1Usdc)).amount);
+            const claimable = await client.getDelegateClaim(user1.publicKey);
+            await walletClient(user1).claimDelegateShare();
+            const balanceAfter = Number((await getAccount(provider.connection, user1Usdc)).amount);
+            expect(balanceAfter - balanceBefore).to.equal(claimable);
+            expect(await client.getDelegateClaim(user1.publicKey)).to.equal(0);
+
+            await client.setDelegateShare(0);
+        });
+
+        it('Should accrue a delegate share to a pre-funded claim address', async () => {
+            await client.setDelegateShare(2_000); // 20%
+            const payer = (provider.wallet as any).payer;
+            const delegate = Keypair.generate().publicKey;
+            const user2Client = new MailServiceClient(
+                provider.connection,
+                {
+                    publicKey: user2.publicKey,
+                    signTransaction: async () => { throw new Error('Not implemented'); },
+                    signAllTransactions: async () => { throw new Error('Not implemented'); }
+                } as any,
+                program.programId,
+                usdcMint
+            );
+
+            // A dust transfer to the unopened claim must not block paying the delegate
+            await sendAndConfirmTransaction(
+                provider.connection,
+                new Transaction().add(
+                    SystemProgram.transfer({
+                        fromPubkey: payer.publicKey,
+                        toPubkey: client.getDelegateClaimAddress(delegate),
+                        lamports: 1_000,
+                    })
+                ),
+                [payer]
+            );
+            await user2Client.addDelegate(delegate);
+            const fees = await client.getFees();
+            expect(await client.getDelegateClaim(delegate)).to.equal(fees.delegationFee * 0.2);
+
+            await client.setDelegateShare(0);
+        });
+
         it('Should allow owner to withdraw fees', async () => {
             const ownerTokenAccount = await createAssociatedTokenAccount(
                 provider.connection,
@@ -230,6 +513,150 @@ describe('MailService', () => {
             const balanceAfter = await getAccount(provider.connection, ownerTokenAccount);
             expect(Number(balanceAfter.amount) - Number(balanceBefore.amount)).to.equal(5_000_000);
         });
+
+        it('Should track collected and withdrawn fees and refuse to overdraw', async () => {
+            const ledger = await client.getFeeLedger();
+            const delegationBucket = await client.getFeeBucket('delegation');
+            const handleBucket = await client.getFeeBucket('handle');
+            expect(ledger.feesWithdrawn).to.be.at.least(5_000_000);
+            expect(ledger.feesCollected - ledger.feesWithdrawn)
+                .to.equal(delegationBucket!.claimable + handleBucket!.claimable);
+
+            try {
+                await client.withdrawFees(delegationBucket!.claimable / 1_000_000 + 1);
+                expect.fail('Should have thrown an error');
+            } catch (error: any) {
+                expect(error.message).to.include('Withdrawal exceeds the fees accrued to the service');
+            }
+        });
+
+        it('Should accrue delegation fees in a separately withdrawable bucket', async () => {
+            const bucket = await client.getFeeBucket('delegation');
+            expect(bucket).to.not.be.null;
+            expect(bucket!.destination.toString()).to.equal(owner.publicKey.toString());
+            expect(bucket!.claimable).to.be.greaterThan(0);
+
+            const ownerTokenAccount = getAssociatedTokenAddressSync(usdcMint, owner.publicKey);
+            const balanceBefore = await getAccount(provider.connection, ownerTokenAccount);
+
+            await client.withdrawBucket('delegation');
+
+            const balanceAfter = await getAccount(provider.connection, ownerTokenAccount);
+            expect(Number(balanceAfter.amount) - Number(balanceBefore.amount)).to.equal(bucket!.claimable);
+            expect((await client.getFeeBucket('delegation'))!.claimable).to.equal(0);
+        });
+
+        it('Should sweep the whole delegation bucket with withdrawAllFees', async () => {
+            const user2Client = new MailServiceClient(
+                provider.connection,
+                {
+                    publicKey: user2.publicKey,
+                    signTransaction: async () => { throw new Error('Not implemented'); },
+                    signAllTransactions: async () => { throw new Error('Not implemented'); }
+                } as any,
+                program.programId,
+                usdcMint
+            );
+            await user2Client.addDelegate(Keypair.generate().publicKey);
+
+            const claimable = (await client.getFeeBucket('delegation'))!.claimable;
+            const withdrawnBefore = (await client.getFeeLedger()).feesWithdrawn;
+            const ownerTokenAccount = getAssociatedTokenAddressSync(usdcMint, owner.publicKey);
+            const balanceBefore = await getAccount(provider.connection, ownerTokenAccount);
+
+            await client.withdrawAllFees();
+
+            const balanceAfter = await getAccount(provider.connection, ownerTokenAccount);
+            expect(Number(balanceAfter.amount) - Number(balanceBefore.amount)).to.equal(claimable);
+            expect((await client.getFeeBucket('delegation'))!.claimable).to.equal(0);
+            expect((await client.getFeeLedger()).feesWithdrawn - withdrawnBefore).to.equal(claimable);
+        });
+
+        it('Should route the delegation bucket to a new destination (owner only)', async () => {
+            await client.setBucketDestination('delegation', user2.publicKey);
+            const bucket = await client.getFeeBucket('delegation');
+            expect(bucket!.destination.toString()).to.equal(user2.publicKey.toString());
+        });
+    });
+
+    describe('Handles', () => {
+        const userClient = (user: Keypair) => new MailServiceClient(
+            provider.connection,
+            {
+                publicKey: user.publicKey,
+                signTransaction: async () => { throw new Error('Not implemented'); },
+                signAllTransactions: async () => { throw new Error('Not implemented'); }
+            } as any,
+            program.programId,
+            usdcMint
+        );
+
+        it('Should register a handle for a year and charge the registration fee', async () => {
+            const fees = await client.getFees();
+            const user1Usdc = getAssociatedTokenAddressSync(usdcMint, user1.publicKey);
+            const balanceBefore = await getAccount(provider.connection, user1Usdc);
+
+            await userClient(user1).registerHandle('alice');
+
+            const handle = await client.getHandle('alice');
+            expect(handle!.owner.toString()).to.equal(user1.publicKey.toString());
+            expect(handle!.isExpired).to.be.false;
+            expect(handle!.expiresAt).to.be.greaterThan(Date.now() / 1000 + 364 * 24 * 60 * 60);
+
+            const balanceAfter = await getAccount(provider.connection, user1Usdc);
+            expect(Number(balanceBefore.amount) - Number(balanceAfter.amount)).to.equal(fees.registrationFee);
+            expect((await client.getFeeBucket('handle'))!.claimable).to.equal(fees.registrationFee);
+        });
+
+        it('Should reject registering a live handle or an invalid name', async () => {
+            for (const name of ['alice', 'Alice']) {
+                try {
+                    await userClient(user2).registerHandle(name);
+                    expect.fail('Should have thrown an error');
+                } catch (error: any) {
+                    expect(error.message).to.match(/Handle is registered|Handles must be/);
+                }
+            }
+        });
+
+        it('Should extend a renewed handle by another year', async () => {
+            const before = await client.getHandle('alice');
+            await userClient(user1).renewHandle('alice');
+            const after = await client.getHandle('alice');
+            expect(after!.expiresAt - before!.expiresAt).to.equal(365 * 24 * 60 * 60);
+        });
+
+        it('Should resolve a wallet to its primary handle', async () => {
+            expect(await client.getPrimaryHandle(user1.publicKey)).to.be.null;
+
+            await userClient(user1).setPrimaryHandle('alice');
+            expect(await client.getPrimaryHandle(user1.publicKey)).to.equal('alice');
+
+            try {
+                await userClient(user2).setPrimaryHandle('alice');
+                expect.fail('Should have thrown an error');
+            } catch (error: any) {
+                expect(error.message).to.include('Only the handle owner');
+            }
+        });
+
+        it('Should transfer a handle to a new owner (owner only)', async () => {
+            try {
+                await userClient(user2).transferHandle('alice', user2.publicKey);
+                expect.fail('Should have thrown an error');
+            } catch (error: any) {
+                expect(error.message).to.include('Only the handle owner');
+            }
+
+            await userClient(user1).transferHandle('alice', user2.publicKey);
+            const handle = await client.getHandle('alice');
+            expect(handle!.owner.toString()).to.equal(user2.publicKey.toString());
+
+            // The previous owner's reverse record no longer points at the handle
+            const record = await (program.account as any).reverseRecord.fetch(client.getReverseRecordAddress(user1.publicKey));
+            expect(record.name).to.equal('');
+            expect(await client.getPrimaryHandle(user1.publicKey)).to.be.null;
+        });
     });
 
     describe('Validation', () => {
@@ -259,6 +686,69 @@ describe('MailService', () => {
             }
         });
 
+        it('Should reject self-delegation and program-derived delegates', async () => {
+            const user1Client = new MailServiceClient(
+                provider.connection,
+                {
+                    publicKey: user1.publicKey,
+                    signTransaction: async () => { throw new Error('Not implemented'); },
+                    signAllTransactions: async () => { throw new Error('Not implemented'); }
+                } as any,
+                program.programId,
+                usdcMint
+            );
+
+            try {
+                await user1Client.delegateTo(user1.publicKey);
+                expect.fail('Should have thrown an error');
+            } catch (error: any) {
+                expect(error.message).to.include('Cannot delegate to yourself');
+            }
+
+            try {
+                await user1Client.delegateTo(client.getServiceAddress());
+                expect.fail('Should have thrown an error');
+            } catch (error: any) {
+                expect(error.message).to.include('Delegate must be a wallet');
+            }
+        });
+
+        it('Should let the guardian pause delegations but only the owner unpause', async () => {
+            const walletClient = (user: Keypair) => new MailServiceClient(
+                provider.connection,
+                {
+                    publicKey: user.publicKey,
+                    signTransaction: async () => { throw new Error('Not implemented'); },
+                    signAllTransactions: async () => { throw new Error('Not implemented'); }
+                } as any,
+                program.programId,
+                usdcMint
+            );
+
+            await client.setGuardian(user2.publicKey);
+            await walletClient(user2).pause();
+            expect((await client.getPauseState()).paused).to.be.true;
+
+            try {
+                await walletClient(user1).delegateTo(Keypair.generate().publicKey);
+                expect.fail('Should have thrown an error');
+            } catch (error: any) {
+                expect(error.message).to.include('Mail service is paused');
+            }
+            // Revoking a delegation still works while paused
+            await walletClient(user1).delegateTo(null);
+
+            try {
+                await walletClient(user2).unpause();
+                expect.fail('Should have thrown an error');
+            } catch (error: any) {
+                expect(error.message).to.include('Only the owner can perform this action');
+            }
+
+            await client.unpause();
+            expect((await client.getPauseState()).paused).to.be.false;
+        });
+
         it('Should fail fee operations by non-owner', async () => {
             const user1Wallet = { 
                 publicKey: user1.publicKey,
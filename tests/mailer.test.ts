@@ -1,16 +1,30 @@
 import * as anchor from '@coral-xyz/anchor';
 import { Program } from '@coral-xyz/anchor';
 import { expect } from 'chai';
-import { PublicKey, Keypair, SystemProgram } from '@solana/web3.js';
+import { PublicKey, Keypair, SystemProgram, Ed25519Program, SYSVAR_INSTRUCTIONS_PUBKEY, Transaction, sendAndConfirmTransaction } from '@solana/web3.js';
 import { 
     TOKEN_PROGRAM_ID, 
+    ASSOCIATED_TOKEN_PROGRAM_ID,
     createMint, 
     createAssociatedTokenAccount, 
     mintTo,
-    getAccount 
+    approve,
+    getAccount,
+    getAssociatedTokenAddressSync,
+    TOKEN_2022_PROGRAM_ID,
+    ExtensionType,
+    getMintLen,
+    createInitializeTransferFeeConfigInstruction,
+    createInitializeMintInstruction,
+    NATIVE_MINT
 } from '@solana/spl-token';
 import { Mailer } from '../target/types/mailer';
 import { MailerClient } from '../app/mailer-client';
+import { externalAddressFromHex, formatTokenAmount } from '../app/types';
+import { computeMessageId } from '../app/message-id';
+import { computeMessageRoot, messageRootDay, messageRootProof } from '../app/message-roots';
+import { BODY_HASH_THRESHOLD, resolveEventBody } from '../app/message-body';
+import { generateEnvelopeKeyPair, openEnvelope, sealEnvelope } from '../app/sealed-envelope';
 
 describe('Mailer', () => {
     const provider = anchor.AnchorProvider.env();
@@ -57,6 +71,7 @@ describe('Mailer', () => {
             usdcMint,
             owner.publicKey
         );
+        await client.addToken(usdcMint, SEND_FEE);
 
         // Create token accounts and mint tokens
         await setupTokenAccounts();
@@ -106,6 +121,26 @@ describe('Mailer', () => {
             expect(mailerState.sendFee.toNumber()).to.equal(SEND_FEE);
             expect(mailerState.ownerClaimable.toNumber()).to.equal(0);
         });
+
+        it('Should not replace the USDC mint once it accepts fees', async () => {
+            const newMint = await createMint(
+                provider.connection,
+                (provider.wallet as any).payer || provider.wallet,
+                provider.wallet.publicKey,
+                null,
+                6
+            );
+
+            try {
+                await client.updateUsdcMint(newMint);
+                expect.fail('Should have thrown an error');
+            } catch (error: any) {
+                expect(error.message).to.include('UsdcMintInUse');
+            }
+
+            const mailerState = await (program.account as any).mailerState.fetch(client.getMailerAddress());
+            expect(mailerState.usdcMint.toString()).to.equal(usdcMint.toString());
+        });
     });
 
     describe('Priority Mail Sending', () => {
@@ -133,6 +168,9 @@ describe('Mailer', () => {
             
             expect(claimInfo!.amount).to.equal(expectedRecipientAmount);
             expect(claimInfo!.isExpired).to.be.false;
+            expect(claimInfo!.feeSnapshot!.sendFee).to.equal(SEND_FEE);
+            expect(claimInfo!.feeSnapshot!.ownerShare).to.equal(OWNER_SHARE);
+            expect(claimInfo!.feeSnapshot!.recipientShare).to.equal(RECIPIENT_SHARE);
 
             // Check owner claimable increased
             const ownerClaimable = await client.getOwnerClaimable();
@@ -179,6 +217,186 @@ describe('Mailer', () => {
             expect(claimInfo?.amount || 0).to.equal(0);
         });
 
+        it('Should count sends and fees in the aggregate stats', async () => {
+            const userClient = new MailerClient(
+                provider.connection,
+                new anchor.Wallet(user2),
+                program.programId,
+                usdcMint
+            );
+
+            const before = await client.getStats();
+            await userClient.send('Stats', 'Counted as a standard message');
+            await userClient.sendPriority('Stats', 'Counted as a priority message');
+            const after = await client.getStats();
+
+            const fees = await client.getFees();
+            const standardFee = Math.floor((fees.sendFee * OWNER_SHARE) / 100);
+            expect(after.totalMessagesSent - before.totalMessagesSent).to.equal(2);
+            expect(after.totalPriorityMessages - before.totalPriorityMessages).to.equal(1);
+            expect(after.totalFeesCollected - before.totalFeesCollected).to.equal(fees.sendFee + standardFee);
+        });
+
+        it('Should reject subjects and bodies over the owner limits', async () => {
+            const userClient = new MailerClient(
+                provider.connection,
+                new anchor.Wallet(user2),
+                program.programId,
+                usdcMint
+            );
+
+            const defaults = await client.getMessageLimits();
+            expect(defaults.maxSubjectLen).to.equal(200);
+            expect(defaults.maxBodyLen).to.equal(800);
+
+            await client.setMessageLimits(8, 16);
+            try {
+                await userClient.send('Fits', 'Within limits');
+                for (const [subject, body] of [['Too long!', 'Body'], ['Subject', 'x'.repeat(17)]]) {
+                    try {
+                        await userClient.send(subject, body);
+                        expect.fail('Should have thrown an error');
+                    } catch (error: any) {
+                        expect(error.message).to.include('MessageTooLarge');
+                    }
+                }
+            } finally {
+                await client.setMessageLimits(defaults.maxSubjectLen, defaults.maxBodyLen);
+            }
+        });
+
+        it('Should cap how many messages a sender sends per day', async () => {
+            const payer = (provider.wallet as any).payer || provider.wallet;
+            const sender = Keypair.generate();
+            await provider.connection.confirmTransaction(
+                await provider.connection.requestAirdrop(sender.publicKey, anchor.web3.LAMPORTS_PER_SOL)
+            );
+            const senderUsdc = await createAssociatedTokenAccount(provider.connection, payer, usdcMint, sender.publicKey);
+            await mintTo(provider.connection, payer, usdcMint, senderUsdc, payer, 10 * 1_000_000);
+            const senderClient = new MailerClient(provider.connection, new anchor.Wallet(sender), program.programId, usdcMint);
+
+            expect((await client.getMessageLimits()).maxMessagesPerDay).to.equal(0);
+            await client.setRateLimit(2);
+            try {
+                await senderClient.send('First', 'Within the limit');
+                await senderClient.sendPrepared('rate-limit-2');
+                try {
+                    await senderClient.send('Third', 'Over the limit');
+                    expect.fail('Should have failed');
+                } catch (error) {
+                    expect((error as any).message).to.include('RateLimitExceeded');
+                }
+
+                const rateLimit = await (program.account as any).rateLimit.fetch(client.getRateLimitAddress(sender.publicKey));
+                expect(rateLimit.sender.toString()).to.equal(sender.publicKey.toString());
+                expect(rateLimit.count.toNumber()).to.equal(2);
+            } finally {
+                await client.setRateLimit(0);
+            }
+
+            // Lifting the limit lets the sender continue within the same window
+            await senderClient.send('Fourth', 'Limit lifted');
+        });
+
+        it('Should number sent messages in a single global sequence', async () => {
+            const userClient = new MailerClient(
+                provider.connection,
+                new anchor.Wallet(user2),
+                program.programId,
+                usdcMint
+            );
+
+            const events: any[] = [];
+            const listener = program.addEventListener('standardMailSent' as any, (event: any) => events.push(event));
+            const before = await client.getStats();
+            await userClient.send('Seq', 'First');
+            await userClient.send('Seq', 'Second');
+            await new Promise((resolve) => setTimeout(resolve, 1000));
+            await program.removeEventListener(listener);
+
+            const after = await client.getStats();
+            expect(after.nextMessageSeq - before.nextMessageSeq).to.equal(2);
+            expect(events.map((event) => event.messageSeq.toNumber())).to.deep.equal([
+                before.nextMessageSeq,
+                before.nextMessageSeq + 1,
+            ]);
+            expect(events[0].timestamp.toNumber()).to.be.greaterThan(0);
+        });
+
+        it('Should emit long bodies as a hash and length', async () => {
+            const userClient = new MailerClient(
+                provider.connection,
+                new anchor.Wallet(user2),
+                program.programId,
+                usdcMint
+            );
+
+            const events: any[] = [];
+            const listener = program.addEventListener('standardMailSent' as any, (event: any) => events.push(event));
+            const longBody = 'y'.repeat(BODY_HASH_THRESHOLD + 1);
+            await userClient.send('Short', 'Inline body');
+            await userClient.send('Long', longBody);
+            await new Promise((resolve) => setTimeout(resolve, 1000));
+            await program.removeEventListener(listener);
+
+            expect(events).to.have.length(2);
+            expect(events[0].body).to.equal('Inline body');
+            expect(events[0].bodyHash).to.be.null;
+            expect(events[1].body).to.equal('');
+            expect(events[1].bodyLen).to.equal(longBody.length);
+            expect(resolveEventBody(events[1], longBody)).to.equal(longBody);
+        });
+
+        it('Should echo the client reference in the send event', async () => {
+            const userClient = new MailerClient(
+                provider.connection,
+                new anchor.Wallet(user2),
+                program.programId,
+                usdcMint
+            );
+
+            const events: any[] = [];
+            const listener = program.addEventListener('standardMailSent' as any, (event: any) => events.push(event));
+            const clientRef = Uint8Array.from({ length: 16 }, (_, i) => i + 1);
+            await userClient.send('Ref', 'With reference', undefined, undefined, clientRef);
+            await userClient.send('Ref', 'Without reference');
+            await new Promise((resolve) => setTimeout(resolve, 1000));
+            await program.removeEventListener(listener);
+
+            expect(events).to.have.length(2);
+            expect(Buffer.from(events[0].clientRef).equals(Buffer.from(clientRef))).to.be.true;
+            expect(events[1].clientRef).to.be.null;
+
+            try {
+                await userClient.send('Ref', 'Bad length', undefined, undefined, new Uint8Array(8));
+                expect.fail('Should have thrown an error');
+            } catch (error: any) {
+                expect(error.message).to.include('16 bytes');
+            }
+        });
+
+        it('Should emit a message ID derived from the sender, sequence number and slot', async () => {
+            const userClient = new MailerClient(
+                provider.connection,
+                new anchor.Wallet(user2),
+                program.programId,
+                usdcMint
+            );
+
+            const received: { event: any; slot: number }[] = [];
+            const listener = program.addEventListener('standardMailSent' as any, (event: any, slot: number) =>
+                received.push({ event, slot })
+            );
+            await userClient.send('Id', 'Deterministic');
+            await new Promise((resolve) => setTimeout(resolve, 1000));
+            await program.removeEventListener(listener);
+
+            expect(received).to.have.length(1);
+            const { event, slot } = received[0];
+            const expected = computeMessageId(user2.publicKey, BigInt(event.messageSeq.toString()), BigInt(slot));
+            expect(Buffer.from(event.messageId).equals(expected)).to.be.true;
+        });
+
         it('Should send prepared regular mail', async () => {
             const userClient = new MailerClient(
                 provider.connection,
@@ -191,6 +409,172 @@ describe('Mailer', () => {
             const txSig = await userClient.sendPrepared(mailId);
             console.log('Regular prepared mail transaction:', txSig);
         });
+
+        it('Should reject a replayed prepared mail ID when recorded', async () => {
+            const userClient = new MailerClient(
+                provider.connection,
+                new anchor.Wallet(user2),
+                program.programId,
+                usdcMint
+            );
+
+            const mailId = 'replay-protected-789';
+            expect(await userClient.isMailIdRecorded(user2.publicKey, mailId)).to.be.false;
+            await userClient.sendPrepared(mailId, undefined, undefined, true);
+            expect(await userClient.isMailIdRecorded(user2.publicKey, mailId)).to.be.true;
+
+            try {
+                await userClient.sendPriorityPrepared(mailId, undefined, undefined, true);
+                expect.fail('Should have thrown an error');
+            } catch (error: any) {
+                expect(error.message).to.include('DuplicateMailId');
+            }
+        });
+
+        it('Should charge a compressed prepared batch per mail ID', async () => {
+            const userClient = new MailerClient(
+                provider.connection,
+                new anchor.Wallet(user2),
+                program.programId,
+                usdcMint
+            );
+
+            const mailIds = Array.from({ length: 40 }, (_, i) => `batch-prepared-${i}`);
+            const ownerBefore = await client.getOwnerClaimable();
+            await userClient.sendPreparedBatch(mailIds);
+            const ownerAfter = await client.getOwnerClaimable();
+
+            const expectedFee = Math.floor(SEND_FEE * OWNER_SHARE / 100) * mailIds.length;
+            expect(ownerAfter - ownerBefore).to.equal(expectedFee);
+        });
+    });
+
+    describe('Claim Netting', () => {
+        it('Should pay a standard send entirely from the claimable balance', async () => {
+            const userClient = new MailerClient(
+                provider.connection,
+                new anchor.Wallet(user1),
+                program.programId,
+                usdcMint
+            );
+
+            const claimBefore = await userClient.getRecipientClaimable(user1.publicKey);
+            const usdcAccount = getAssociatedTokenAddressSync(usdcMint, user1.publicKey);
+            const balanceBefore = Number((await getAccount(provider.connection, usdcAccount)).amount);
+
+            await userClient.sendUsingClaim('Netted', 'Paid from claim', false);
+
+            const claimAfter = await userClient.getRecipientClaimable(user1.publicKey);
+            const balanceAfter = Number((await getAccount(provider.connection, usdcAccount)).amount);
+            const standardFee = Math.floor((SEND_FEE * OWNER_SHARE) / 100);
+
+            expect(claimBefore!.amount - claimAfter!.amount).to.equal(standardFee);
+            expect(balanceAfter).to.equal(balanceBefore);
+        });
+    });
+
+    describe('Delegated Sending', () => {
+        it('Should reject a delegate send without a delegation', async () => {
+            const delegateClient = new MailerClient(
+                provider.connection,
+                new anchor.Wallet(user2),
+                program.programId,
+                usdcMint
+            );
+            const mailService = anchor.workspace.MailService as Program;
+
+            try {
+                await delegateClient.sendAsDelegator(user1.publicKey, mailService.programId, 'On behalf', 'Body');
+                expect.fail('Should have thrown an error');
+            } catch (error: any) {
+                expect(error.message).to.include('AccountNotInitialized');
+            }
+        });
+
+        it('Should let a delegator approve and revoke the mailer fee allowance', async () => {
+            const delegatorClient = new MailerClient(
+                provider.connection,
+                new anchor.Wallet(user1),
+                program.programId,
+                usdcMint
+            );
+            const standardFee = Math.floor((SEND_FEE * OWNER_SHARE) / 100);
+
+            await delegatorClient.approveFeeAllowance(10 * standardFee);
+            expect(await client.getFeeAllowance(user1.publicKey)).to.equal(10 * standardFee);
+
+            await delegatorClient.revokeFeeAllowance();
+            expect(await client.getFeeAllowance(user1.publicKey)).to.equal(0);
+        });
+
+        it('Should reject a billing allowance without a delegation', async () => {
+            const delegatorClient = new MailerClient(
+                provider.connection,
+                new anchor.Wallet(user1),
+                program.programId,
+                usdcMint
+            );
+            const mailService = anchor.workspace.MailService as Program;
+
+            try {
+                await delegatorClient.openDelegatorBilling(mailService.programId, 10_000, user2.publicKey);
+                expect.fail('Should have thrown an error');
+            } catch (error: any) {
+                expect(error.message).to.include('AccountNotInitialized');
+            }
+            expect(await client.getFeeAllowance(user1.publicKey)).to.equal(0);
+        });
+    });
+
+    describe('CPI Caller Policy', () => {
+        it('Should let only the owner restrict CPI callers', async () => {
+            const userClient = new MailerClient(
+                provider.connection,
+                new anchor.Wallet(user1),
+                program.programId,
+                usdcMint
+            );
+            try {
+                await userClient.setCallerPolicy([], false);
+                expect.fail('Should have thrown an error');
+            } catch (error: any) {
+                expect(error.message).to.include('OnlyOwner');
+            }
+
+            const daoProgram = Keypair.generate().publicKey;
+            await client.setCallerPolicy([daoProgram], false);
+
+            const policy = await (program.account as any).callerPolicy.fetch(client.getCallerPolicyAddress());
+            expect(policy.allowAll).to.be.false;
+            expect(policy.programs[0].toString()).to.equal(daoProgram.toString());
+
+            // Wallet sends are not CPI and are unaffected
+            await userClient.send('Direct', 'Wallet-originated messages are always accepted');
+
+            await client.setCallerPolicy([], true);
+        });
+    });
+
+    describe('Origin Policy', () => {
+        it('Should still accept direct sends with a denylist in place', async () => {
+            const userClient = new MailerClient(
+                provider.connection,
+                new anchor.Wallet(user2),
+                program.programId,
+                usdcMint
+            );
+
+            const noisyProgram = Keypair.generate().publicKey;
+            await userClient.setOriginPolicy([noisyProgram], false);
+
+            const prefs = await (program.account as any).recipientPreferences.fetch(
+                userClient.getPreferencesAddress(user2.publicKey)
+            );
+            expect(prefs.originAllowlist).to.be.false;
+            expect(prefs.originPrograms[0].toString()).to.equal(noisyProgram.toString());
+
+            await userClient.send('Direct', 'Wallet-originated messages are always accepted');
+        });
     });
 
     describe('Claims Management', () => {
@@ -226,6 +610,42 @@ describe('Mailer', () => {
             }
         });
 
+        it('Should unlock a streaming claim gradually instead of all at once', async () => {
+            const payer = (provider.wallet as any).payer || provider.wallet;
+            const streamer = Keypair.generate();
+            await provider.connection.confirmTransaction(
+                await provider.connection.requestAirdrop(streamer.publicKey, anchor.web3.LAMPORTS_PER_SOL)
+            );
+            const streamerUsdc = await createAssociatedTokenAccount(provider.connection, payer, usdcMint, streamer.publicKey);
+            await mintTo(provider.connection, payer, usdcMint, streamerUsdc, payer, 10 * 1_000_000);
+
+            const streamerClient = new MailerClient(provider.connection, new anchor.Wallet(streamer), program.programId, usdcMint);
+            await streamerClient.setClaimMode('streaming');
+            await streamerClient.sendPriority('Streaming', 'Unlocks over 60 days');
+
+            const info = await streamerClient.getRecipientClaimable(streamer.publicKey);
+            expect(info!.mode).to.equal('streaming');
+            expect(info!.amount).to.be.greaterThan(0);
+            expect(info!.available).to.be.lessThan(info!.amount);
+            expect(info!.isExpired).to.be.false;
+
+            // Moments after the deposit almost nothing has unlocked
+            try {
+                await streamerClient.claimRecipientShare();
+                expect.fail('Should have failed');
+            } catch (error) {
+                expect((error as any).message).to.include('NoClaimableAmount');
+            }
+
+            // The mode is fixed while shares are outstanding
+            try {
+                await streamerClient.setClaimMode('lump');
+                expect.fail('Should have failed');
+            } catch (error) {
+                expect((error as any).message).to.include('ClaimNotEmpty');
+            }
+        });
+
         it('Should fail to claim with no claimable amount', async () => {
             try {
                 await client.claimOwnerShare();
@@ -271,6 +691,43 @@ describe('Mailer', () => {
             const fees = await client.getFeesFormatted();
             expect(fees.sendFee).to.include('USDC');
         });
+
+        it('Should simulate config changes without applying them', async () => {
+            const before = await client.getFees();
+
+            const valid = await client.simulateConfigChange({ kind: 'sendFee', value: 200_000 });
+            expect(valid.valid).to.equal(true);
+            expect(valid.oldValue).to.equal(before.sendFee);
+            expect(valid.newValue).to.equal(200_000);
+
+            const rejected = await client.simulateConfigChange({ kind: 'rollover', value: 10_001 });
+            expect(rejected.valid).to.equal(false);
+            expect(rejected.errorCode).to.be.greaterThan(0);
+
+            const discount = await client.simulateConfigChange({ kind: 'nftDiscount', value: 10_001 });
+            expect(discount.valid).to.equal(false);
+
+            const after = await client.getFees();
+            expect(after.sendFee).to.equal(before.sendFee);
+        });
+
+        it('Should reject a send fee whose standard fee rounds to zero', async () => {
+            try {
+                await client.setFee(9);
+                expect.fail('Should have failed');
+            } catch (error) {
+                expect((error as any).message).to.include('InvalidFee');
+            }
+        });
+
+        it('Should reject a zero USD fee', async () => {
+            try {
+                await client.setUsdFee(0);
+                expect.fail('Should have failed');
+            } catch (error) {
+                expect((error as any).message).to.include('InvalidFee');
+            }
+        });
     });
 
     describe('Expired Claims', () => {
@@ -294,16 +751,86 @@ describe('Mailer', () => {
             expect(claimInfo!.expiresAt).to.be.greaterThan(Math.floor(Date.now() / 1000));
         });
 
-        it('Should allow owner to claim expired shares', async () => {
-            // This would typically require time manipulation or mock
-            // For now, just test the function exists
+        it('Should reject cranking a claim that has not expired', async () => {
+            // Cranking is permissionless; any wallet with a USDC account can try
+            const crankerClient = new MailerClient(
+                provider.connection,
+                new anchor.Wallet(user2),
+                program.programId,
+                usdcMint
+            );
+
             try {
-                await client.claimExpiredShares(user1.publicKey);
+                await crankerClient.claimExpiredShares(user1.publicKey);
+                expect.fail('Should have failed');
             } catch (error) {
-                // Expected to fail since claim hasn't expired
                 expect((error as any).message).to.include('ClaimPeriodNotExpired');
             }
         });
+
+        it('Should reject a batch sweep when no claim has expired', async () => {
+            const crankerClient = new MailerClient(
+                provider.connection,
+                new anchor.Wallet(user2),
+                program.programId,
+                usdcMint
+            );
+
+            try {
+                await crankerClient.claimExpiredSharesBatch([user1.publicKey, user2.publicKey]);
+                expect.fail('Should have failed');
+            } catch (error) {
+                expect((error as any).message).to.include('NoClaimableAmount');
+            }
+        });
+
+        it('Should reject a batch sweep whose claim has no expiry bucket after it', async () => {
+            try {
+                await (program.methods as any)
+                    .claimExpiredSharesBatch()
+                    .accounts({
+                        mailer: client.getMailerAddress(),
+                        ownerBucket: client.getOwnerBucketAddress(usdcMint, 'expired'),
+                        mint: usdcMint,
+                        cranker: user2.publicKey,
+                        crankerUsdcAccount: getAssociatedTokenAddressSync(usdcMint, user2.publicKey),
+                        mailerUsdcAccount: getAssociatedTokenAddressSync(usdcMint, client.getMailerAddress(), true),
+                        tokenProgram: TOKEN_PROGRAM_ID,
+                    })
+                    .remainingAccounts([
+                        { pubkey: client.getClaimAddress(user1.publicKey), isSigner: false, isWritable: true },
+                    ])
+                    .signers([user2])
+                    .rpc();
+                expect.fail('Should have failed');
+            } catch (error) {
+                expect((error as any).message).to.include('InvalidClaimAccount');
+            }
+        });
+
+        it('Should reject a claim extension above the cap', async () => {
+            try {
+                await client.extendClaim(user1.publicKey, 31 * 24 * 60 * 60);
+                expect.fail('Should have failed');
+            } catch (error) {
+                expect((error as any).message).to.include('InvalidExtension');
+            }
+        });
+
+        it('Should update crank bounty (owner only)', async () => {
+            await client.setCrankBounty(200);
+            const mailerState = await (program.account as any).mailerState.fetch(client.getMailerAddress());
+            expect(mailerState.crankBountyBps).to.equal(200);
+        });
+
+        it('Should reject crank bounty above the cap', async () => {
+            try {
+                await client.setCrankBounty(1_001);
+                expect.fail('Should have failed');
+            } catch (error) {
+                expect((error as any).message).to.include('InvalidCrankBounty');
+            }
+        });
     });
 
     describe('Fee Calculation Helper', () => {
@@ -337,10 +864,30 @@ describe('Mailer', () => {
             expect(fees.ownerAmount).to.equal(expectedOwnerAmount);
             expect(fees.recipientAmount).to.equal(0);
         });
-    });
 
-    describe('Integration Tests', () => {
-        it('Should handle complete mail workflow', async () => {
+        it('Should match the on-chain fee breakdown', async () => {
+            const breakdown = await client.getFeeBreakdown();
+            const fees = client.calculateFees(breakdown.priorityFee, true);
+
+            expect(breakdown.ownerShare).to.equal(fees.ownerAmount);
+            expect(breakdown.recipientShare).to.equal(fees.recipientAmount);
+            expect(breakdown.standardFee).to.equal(client.calculateFees(breakdown.priorityFee, false).ownerAmount);
+        });
+
+        it('Should report claims through the get_claimable view', async () => {
+            const view = await client.getClaimableView(user1.publicKey);
+            const stored = await client.getRecipientClaimable(user1.publicKey);
+            expect(view.amount).to.equal(stored!.amount);
+            expect(view.sendCredit).to.equal(stored!.sendCredit);
+
+            const empty = await client.getClaimableView(Keypair.generate().publicKey);
+            expect(empty.amount).to.equal(0);
+            expect(empty.expiresAt).to.equal(0);
+        });
+    });
+
+    describe('Integration Tests', () => {
+        it('Should handle complete mail workflow', async () => {
             const userClient = new MailerClient(
                 provider.connection,
                 new anchor.Wallet(user2),
@@ -363,6 +910,121 @@ describe('Mailer', () => {
             expect(afterClaim?.amount || 0).to.equal(0);
         });
 
+        it('Should close a fully claimed account and recreate it on next priority send', async () => {
+            const userClient = new MailerClient(
+                provider.connection,
+                new anchor.Wallet(user2),
+                program.programId,
+                usdcMint
+            );
+
+            const balanceBefore = await provider.connection.getBalance(user2.publicKey);
+            await userClient.closeClaim();
+            const balanceAfter = await provider.connection.getBalance(user2.publicKey);
+            expect(balanceAfter).to.be.greaterThan(balanceBefore);
+
+            const closed = await userClient.getRecipientClaimable(user2.publicKey);
+            expect(closed).to.be.null;
+
+            // Next priority send re-initializes the claim PDA
+            await userClient.sendPriority('Reopen', 'Claim account recreated');
+            const reopened = await userClient.getRecipientClaimable(user2.publicKey);
+            expect(reopened?.amount).to.be.greaterThan(0);
+        });
+
+        it('Should fail to close a claim with an unclaimed amount', async () => {
+            const userClient = new MailerClient(
+                provider.connection,
+                new anchor.Wallet(user2),
+                program.programId,
+                usdcMint
+            );
+
+            try {
+                await userClient.closeClaim();
+                expect.fail('Should have failed');
+            } catch (error) {
+                expect((error as any).message).to.include('ClaimNotEmpty');
+            }
+        });
+
+        it('Should let a zero-SOL wallet accumulate and claim revenue via a relayer', async () => {
+            // Relayer (provider wallet) pays rent and transaction fees; the user only signs
+            const relayer = provider.wallet.publicKey;
+            const gaslessUser = Keypair.generate();
+            const gaslessUsdc = await createAssociatedTokenAccount(
+                provider.connection,
+                (provider.wallet as any).payer || provider.wallet,
+                usdcMint,
+                gaslessUser.publicKey
+            );
+            await mintTo(
+                provider.connection,
+                (provider.wallet as any).payer || provider.wallet,
+                usdcMint,
+                gaslessUsdc,
+                (provider.wallet as any).payer || provider.wallet,
+                SEND_FEE
+            );
+            expect(await provider.connection.getBalance(gaslessUser.publicKey)).to.equal(0);
+
+            const [claimPda] = PublicKey.findProgramAddressSync(
+                [Buffer.from('claim'), gaslessUser.publicKey.toBuffer()],
+                program.programId
+            );
+            const mailerUsdc = getAssociatedTokenAddressSync(usdcMint, client.getMailerAddress(), true);
+
+            await (program.methods as any)
+                .sendPriority('Gasless', 'Relayer funded', null, null, null)
+                .accounts({
+                    recipientClaim: claimPda,
+                    mailer: client.getMailerAddress(),
+                    tokenConfig: client.getTokenConfigAddress(usdcMint),
+                    priceUpdate: null,
+                    ownerBucket: client.getOwnerBucketAddress(usdcMint, 'send'),
+                    mint: usdcMint,
+                    sender: gaslessUser.publicKey,
+                    payer: relayer,
+                    senderUsdcAccount: gaslessUsdc,
+                    mailerUsdcAccount: mailerUsdc,
+                    recipientPreferences: client.getPreferencesAddress(gaslessUser.publicKey),
+                    callerPolicy: client.getCallerPolicyAddress(),
+                    instructions: SYSVAR_INSTRUCTIONS_PUBKEY,
+                    expiryIndex: client.getExpiryIndexAddress(Math.floor(Date.now() / 1000)),
+                    rateLimit: client.getRateLimitAddress(gaslessUser.publicKey),
+                    denylist: client.getDenylistAddress(gaslessUser.publicKey),
+                    tokenProgram: TOKEN_PROGRAM_ID,
+                    associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+                    systemProgram: SystemProgram.programId,
+                })
+                .signers([gaslessUser])
+                .rpc();
+
+            const claim = await (program.account as any).recipientClaim.fetch(claimPda);
+            expect(claim.rentPayer.toString()).to.equal(relayer.toString());
+            expect(claim.amount.toNumber()).to.be.greaterThan(0);
+
+            await (program.methods as any)
+                .claimRecipientShare()
+                .accounts({
+                    recipientClaim: claimPda,
+                    mailer: client.getMailerAddress(),
+                    recipient: gaslessUser.publicKey,
+                    mint: usdcMint,
+                    recipientUsdcAccount: gaslessUsdc,
+                    mailerUsdcAccount: mailerUsdc,
+                    expiryIndex: client.getExpiryIndexAddress(claim.timestamp.toNumber()),
+                    denylist: client.getDenylistAddress(gaslessUser.publicKey),
+                    tokenProgram: TOKEN_PROGRAM_ID,
+                })
+                .signers([gaslessUser])
+                .rpc();
+
+            const usdcAfter = await getAccount(provider.connection, gaslessUsdc);
+            expect(Number(usdcAfter.amount)).to.equal(claim.amount.toNumber());
+            expect(await provider.connection.getBalance(gaslessUser.publicKey)).to.equal(0);
+        });
+
         it('Should verify program address derivation', () => {
             const expectedAddress = client.getMailerAddress();
             const [derivedAddress] = PublicKey.findProgramAddressSync(
@@ -377,4 +1039,1888 @@ describe('Mailer', () => {
             expect(client.getProgramId().toString()).to.equal(program.programId.toString());
         });
     });
-});
\ No newline at end of file
+
+    describe('Expiry Index', () => {
+        it('Should list a new claim in its expiry week and drop it once claimed', async () => {
+            const userClient = new MailerClient(
+                provider.connection,
+                new anchor.Wallet(user2),
+                program.programId,
+                usdcMint
+            );
+
+            const info = await userClient.getRecipientClaimable(user2.publicKey);
+            expect(info?.amount).to.be.greaterThan(0);
+
+            const listed = await client.getExpiringClaims(info!.expiresAt);
+            expect(listed).to.not.be.null;
+            expect(listed!.startsAt).to.be.at.most(info!.expiresAt);
+            expect(listed!.endsAt).to.be.greaterThan(info!.expiresAt);
+            expect(listed!.recipients.map((r) => r.toString())).to.include(user2.publicKey.toString());
+
+            await userClient.claimRecipientShare();
+
+            const afterClaim = await client.getExpiringClaims(info!.expiresAt);
+            expect(afterClaim!.recipients.map((r) => r.toString())).to.not.include(user2.publicKey.toString());
+        });
+
+        it('Should reject an expiry index for the wrong week', async () => {
+            const userClient = new MailerClient(
+                provider.connection,
+                new anchor.Wallet(user2),
+                program.programId,
+                usdcMint
+            );
+            await userClient.sendPriority('Expiry', 'Listed this week');
+
+            const [claimPda] = PublicKey.findProgramAddressSync(
+                [Buffer.from('claim'), user2.publicKey.toBuffer()],
+                program.programId
+            );
+            const claim = await (program.account as any).recipientClaim.fetch(claimPda);
+            const nextWeek = claim.timestamp.toNumber() + 7 * 24 * 60 * 60;
+
+            try {
+                await (program.methods as any)
+                    .claimRecipientShare()
+                    .accounts({
+                        recipientClaim: claimPda,
+                        mailer: client.getMailerAddress(),
+                        recipient: user2.publicKey,
+                        mint: usdcMint,
+                        recipientUsdcAccount: getAssociatedTokenAddressSync(usdcMint, user2.publicKey),
+                        mailerUsdcAccount: getAssociatedTokenAddressSync(usdcMint, client.getMailerAddress(), true),
+                        expiryIndex: client.getExpiryIndexAddress(nextWeek),
+                        denylist: client.getDenylistAddress(user2.publicKey),
+                        tokenProgram: TOKEN_PROGRAM_ID,
+                    })
+                    .signers([user2])
+                    .rpc();
+                expect.fail('Should have failed');
+            } catch (error) {
+                expect((error as any).message).to.include('InvalidExpiryIndex');
+            }
+        });
+    });
+
+    describe('Payment Escrow', () => {
+        const PAYMENT = 1_000_000; // 1 USDC
+
+        it('Should release an escrowed payment when the recipient acknowledges', async () => {
+            const senderClient = new MailerClient(provider.connection, new anchor.Wallet(user1), program.programId, usdcMint);
+            const recipientClient = new MailerClient(provider.connection, new anchor.Wallet(user2), program.programId, usdcMint);
+            const recipientAccount = getAssociatedTokenAddressSync(usdcMint, user2.publicKey);
+
+            const { paymentId } = await senderClient.sendWithPayment(user2.publicKey, PAYMENT, 'paid-mail-1');
+            const escrow = await client.getPaymentEscrow(paymentId);
+            expect(escrow!.amount).to.equal(PAYMENT);
+            expect(escrow!.ackDeadline).to.be.null;
+
+            const before = Number((await getAccount(provider.connection, recipientAccount)).amount);
+            await recipientClient.acknowledgePayment(paymentId);
+            const after = Number((await getAccount(provider.connection, recipientAccount)).amount);
+
+            expect(after - before).to.equal(PAYMENT);
+            expect(await client.getPaymentEscrow(paymentId)).to.be.null;
+        });
+
+        it('Should derive the message ID of a payment-bearing message and key the escrow separately', async () => {
+            const senderClient = new MailerClient(provider.connection, new anchor.Wallet(user1), program.programId, usdcMint);
+
+            const received: { event: any; slot: number }[] = [];
+            const listener = program.addEventListener('paymentMailSent' as any, (event: any, slot: number) =>
+                received.push({ event, slot })
+            );
+            const { paymentId } = await senderClient.sendWithPayment(user2.publicKey, PAYMENT, 'paid-mail-id');
+            await new Promise((resolve) => setTimeout(resolve, 1000));
+            await program.removeEventListener(listener);
+
+            expect(received).to.have.length(1);
+            const { event, slot } = received[0];
+            const expected = computeMessageId(user1.publicKey, BigInt(event.messageSeq.toString()), BigInt(slot));
+            expect(Buffer.from(event.messageId).equals(expected)).to.be.true;
+            expect(Buffer.from(event.paymentId).equals(paymentId)).to.be.true;
+
+            const escrow = await client.getPaymentEscrow(paymentId);
+            expect(escrow!.messageId).to.equal(expected.toString('hex'));
+            expect(escrow!.paymentId).to.equal(paymentId.toString('hex'));
+        });
+
+        it('Should refund an unread payment to the sender once the deadline passes', async () => {
+            const senderClient = new MailerClient(provider.connection, new anchor.Wallet(user1), program.programId, usdcMint);
+            const recipientClient = new MailerClient(provider.connection, new anchor.Wallet(user2), program.programId, usdcMint);
+            const senderAccount = getAssociatedTokenAddressSync(usdcMint, user1.publicKey);
+
+            const deadline = Math.floor(Date.now() / 1000) + 3;
+            const before = Number((await getAccount(provider.connection, senderAccount)).amount);
+            const { paymentId } = await senderClient.sendWithPayment(user2.publicKey, PAYMENT, 'paid-mail-2', deadline);
+
+            try {
+                await client.refundUnread(paymentId);
+                expect.fail('Should have failed');
+            } catch (error) {
+                expect((error as any).message).to.include('AckDeadlineNotReached');
+            }
+            // A payment with a deadline is only ever refunded through refundUnread
+            expect((await client.getPaymentEscrow(paymentId))!.reclaimableAt).to.be.null;
+            try {
+                await senderClient.reclaimPayment(paymentId);
+                expect.fail('Should have failed');
+            } catch (error) {
+                expect((error as any).message).to.include('HasAckDeadline');
+            }
+
+            await new Promise((resolve) => setTimeout(resolve, 5000));
+
+            try {
+                await recipientClient.acknowledgePayment(paymentId);
+                expect.fail('Should have failed');
+            } catch (error) {
+                expect((error as any).message).to.include('AcknowledgementExpired');
+            }
+
+            // Any wallet can crank the refund
+            await client.refundUnread(paymentId);
+            const after = Number((await getAccount(provider.connection, senderAccount)).amount);
+            expect(after).to.equal(before);
+            expect(await client.getPaymentEscrow(paymentId)).to.be.null;
+        }).timeout(20_000);
+
+        it('Should store a recipient address on another chain with the escrow', async () => {
+            const senderClient = new MailerClient(provider.connection, new anchor.Wallet(user1), program.programId, usdcMint);
+            const recipientClient = new MailerClient(provider.connection, new anchor.Wallet(user2), program.programId, usdcMint);
+            const evmRecipient = externalAddressFromHex(1, '0x742d35Cc6634C0532925a3b844Bc454e4438f44e');
+
+            const { paymentId } = await senderClient.sendWithPayment(
+                user2.publicKey, PAYMENT, 'paid-mail-5', undefined, evmRecipient
+            );
+            const escrow = await client.getPaymentEscrow(paymentId);
+            expect(escrow!.recipientExternal).to.deep.equal(evmRecipient);
+
+            await recipientClient.acknowledgePayment(paymentId);
+        });
+
+        it('Should reject an external recipient without a chain ID', async () => {
+            const senderClient = new MailerClient(provider.connection, new anchor.Wallet(user1), program.programId, usdcMint);
+
+            try {
+                await senderClient.send('Cross-chain', 'No chain', undefined, { chainId: 0, address: new Uint8Array(20) });
+                expect.fail('Should have failed');
+            } catch (error) {
+                expect((error as any).message).to.include('InvalidExternalAddress');
+            }
+        });
+
+        it('Should not let the sender reclaim a payment before the timeout', async () => {
+            const senderClient = new MailerClient(provider.connection, new anchor.Wallet(user1), program.programId, usdcMint);
+            const recipientClient = new MailerClient(provider.connection, new anchor.Wallet(user2), program.programId, usdcMint);
+
+            const { paymentId } = await senderClient.sendWithPayment(user2.publicKey, PAYMENT, 'paid-mail-4');
+            const escrow = await client.getPaymentEscrow(paymentId);
+            expect(escrow!.reclaimableAt).to.equal(escrow!.createdAt + 30 * 24 * 60 * 60);
+
+            try {
+                await senderClient.reclaimPayment(paymentId);
+                expect.fail('Should have failed');
+            } catch (error) {
+                expect((error as any).message).to.include('ReclaimTimeoutNotReached');
+            }
+
+            // Only the sender may reclaim
+            try {
+                await recipientClient.reclaimPayment(paymentId);
+                expect.fail('Should have failed');
+            } catch (error) {
+                expect((error as any).message).to.not.include('Should have failed');
+            }
+
+            await recipientClient.acknowledgePayment(paymentId);
+            expect(await client.getPaymentEscrow(paymentId)).to.be.null;
+        });
+
+        it('Should reject a deadline in the past', async () => {
+            const senderClient = new MailerClient(provider.connection, new anchor.Wallet(user1), program.programId, usdcMint);
+            try {
+                await senderClient.sendWithPayment(user2.publicKey, PAYMENT, 'paid-mail-3', Math.floor(Date.now() / 1000) - 60);
+                expect.fail('Should have failed');
+            } catch (error) {
+                expect((error as any).message).to.include('InvalidAckDeadline');
+            }
+        });
+    });
+
+    describe('Multi-Token Fees', () => {
+        let pyusdMint: PublicKey;
+        let pyusdUser: Keypair;
+        let pyusdClient: MailerClient;
+
+        before(async () => {
+            pyusdMint = await createMint(
+                provider.connection,
+                (provider.wallet as any).payer || provider.wallet,
+                provider.wallet.publicKey,
+                null,
+                6
+            );
+            pyusdUser = Keypair.generate();
+            await provider.connection.confirmTransaction(
+                await provider.connection.requestAirdrop(pyusdUser.publicKey, anchor.web3.LAMPORTS_PER_SOL)
+            );
+            const pyusdAccount = await createAssociatedTokenAccount(
+                provider.connection,
+                (provider.wallet as any).payer || provider.wallet,
+                pyusdMint,
+                pyusdUser.publicKey
+            );
+            await mintTo(
+                provider.connection,
+                (provider.wallet as any).payer || provider.wallet,
+                pyusdMint,
+                pyusdAccount,
+                (provider.wallet as any).payer || provider.wallet,
+                10 * 1_000_000
+            );
+            await createAssociatedTokenAccount(
+                provider.connection,
+                (provider.wallet as any).payer || provider.wallet,
+                pyusdMint,
+                client.getMailerAddress(),
+                undefined,
+                undefined,
+                undefined,
+                true
+            );
+            pyusdClient = new MailerClient(
+                provider.connection,
+                new anchor.Wallet(pyusdUser),
+                program.programId,
+                pyusdMint
+            );
+        });
+
+        it('Should reject sends in a mint that was never added', async () => {
+            try {
+                await pyusdClient.sendPriority('Subject', 'Body');
+                expect.fail('Should have failed');
+            } catch (error) {
+                expect((error as any).message).to.match(/AccountNotInitialized|tokenConfig/);
+            }
+        });
+
+        it('Should charge the per-mint fee and track owner share per mint', async () => {
+            const pyusdFee = 200_000;
+            await client.addToken(pyusdMint, pyusdFee);
+
+            const config = await client.getTokenConfig(pyusdMint);
+            expect(config!.enabled).to.be.true;
+            expect(config!.sendFee).to.equal(pyusdFee);
+            expect(config!.decimals).to.equal(6);
+
+            await pyusdClient.sendPriority('Subject', 'Paid in PYUSD');
+
+            const claimInfo = await pyusdClient.getRecipientClaimable(pyusdUser.publicKey);
+            expect(claimInfo!.mint!.toString()).to.equal(pyusdMint.toString());
+            expect(claimInfo!.amount).to.equal(pyusdFee - Math.floor((pyusdFee * OWNER_SHARE) / 100));
+            expect(await client.getOwnerClaimable(pyusdMint)).to.equal(Math.floor((pyusdFee * OWNER_SHARE) / 100));
+        });
+
+        it('Should refuse sends after a mint is removed (owner only)', async () => {
+            const userClient = new MailerClient(
+                provider.connection,
+                new anchor.Wallet(user1),
+                program.programId,
+                usdcMint
+            );
+            try {
+                await userClient.removeToken(pyusdMint);
+                expect.fail('Should have failed');
+            } catch (error) {
+                expect((error as any).message).to.include('OnlyOwner');
+            }
+
+            await client.removeToken(pyusdMint);
+            try {
+                await pyusdClient.send('Subject', 'Body');
+                expect.fail('Should have failed');
+            } catch (error) {
+                expect((error as any).message).to.include('TokenDisabled');
+            }
+
+            // Outstanding claims in a removed mint remain claimable
+            await pyusdClient.claimRecipientShare();
+            const claimInfo = await pyusdClient.getRecipientClaimable(pyusdUser.publicKey);
+            expect(claimInfo!.amount).to.equal(0);
+        });
+
+        it('Should withdraw an owner share bucket to its routed destination', async () => {
+            const ownerPyusdClient = new MailerClient(
+                provider.connection,
+                new anchor.Wallet(owner),
+                program.programId,
+                pyusdMint
+            );
+            const treasuryAccount = await createAssociatedTokenAccount(
+                provider.connection,
+                (provider.wallet as any).payer || provider.wallet,
+                pyusdMint,
+                user2.publicKey
+            );
+
+            await client.setBucketDestination(pyusdMint, 'send', user2.publicKey);
+            const bucket = await client.getOwnerBucket(pyusdMint, 'send');
+            expect(bucket!.destination.toString()).to.equal(user2.publicKey.toString());
+
+            const accrued = await client.getOwnerClaimable(pyusdMint, 'send');
+            expect(accrued).to.be.greaterThan(0);
+            await ownerPyusdClient.claimOwnerShare('send');
+
+            const treasury = await getAccount(provider.connection, treasuryAccount);
+            expect(Number(treasury.amount)).to.equal(accrued);
+            expect(await client.getOwnerClaimable(pyusdMint, 'send')).to.equal(0);
+
+            // The expired-share bucket is untouched and withdrawn independently
+            try {
+                await ownerPyusdClient.claimOwnerShare('expired');
+                expect.fail('Should have failed');
+            } catch (error) {
+                expect((error as any).message).to.include('NoClaimableAmount');
+            }
+        });
+    });
+
+    describe('Tips', () => {
+        const TIP = 250_000; // 0.25 USDC
+
+        it('Should credit a tip to the owner on top of the fee', async () => {
+            const userClient = new MailerClient(provider.connection, new anchor.Wallet(user2), program.programId, usdcMint);
+            const userAccount = getAssociatedTokenAddressSync(usdcMint, user2.publicKey);
+
+            const ownerBefore = await client.getOwnerClaimable();
+            const balanceBefore = Number((await getAccount(provider.connection, userAccount)).amount);
+            await userClient.send('Thanks', 'Tipping the operator', { amount: TIP, target: 'owner' });
+            const balanceAfter = Number((await getAccount(provider.connection, userAccount)).amount);
+
+            const standardFee = Math.floor((SEND_FEE * OWNER_SHARE) / 100);
+            expect(balanceBefore - balanceAfter).to.equal(standardFee + TIP);
+            expect((await client.getOwnerClaimable()) - ownerBefore).to.equal(standardFee + TIP);
+        });
+
+        it('Should credit a tip to the recipient claim', async () => {
+            const userClient = new MailerClient(provider.connection, new anchor.Wallet(user2), program.programId, usdcMint);
+
+            const claimBefore = (await userClient.getRecipientClaimable(user2.publicKey))?.amount ?? 0;
+            await userClient.sendPriorityPrepared('tipped-mail-1', { amount: TIP, target: 'recipient' });
+            const claimAfter = (await userClient.getRecipientClaimable(user2.publicKey))!.amount;
+
+            const recipientShare = SEND_FEE - Math.floor((SEND_FEE * OWNER_SHARE) / 100);
+            expect(claimAfter - claimBefore).to.equal(recipientShare + TIP);
+        });
+    });
+
+    describe('Sender Blocks', () => {
+        it('Should refuse payment mail from a blocked sender until unblocked', async () => {
+            const user1Client = new MailerClient(provider.connection, new anchor.Wallet(user1), program.programId, usdcMint);
+            const user2Client = new MailerClient(provider.connection, new anchor.Wallet(user2), program.programId, usdcMint);
+
+            await user2Client.setSenderBlocked(user1.publicKey, true);
+            expect(await client.isSenderBlocked(user2.publicKey, user1.publicKey)).to.be.true;
+
+            try {
+                await user1Client.sendWithPayment(user2.publicKey, 1_000_000, 'blocked-mail');
+                expect.fail('Should have failed');
+            } catch (error) {
+                expect((error as any).message).to.include('SenderBlocked');
+            }
+
+            await user2Client.setSenderBlocked(user1.publicKey, false);
+            expect(await client.isSenderBlocked(user2.publicKey, user1.publicKey)).to.be.false;
+            await user1Client.sendWithPayment(user2.publicKey, 1_000_000, 'unblocked-mail');
+        });
+
+        it('Should refuse sealed mail relayed by a blocked signer', async () => {
+            const relayerClient = new MailerClient(provider.connection, new anchor.Wallet(user1), program.programId, usdcMint);
+            await client.setSenderBlocked(user1.publicKey, true);
+
+            const envelope = sealEnvelope(user2, owner.publicKey, 'blocked-sealed', generateEnvelopeKeyPair().publicKey);
+            try {
+                await relayerClient.sendSealed(owner.publicKey, 'blocked-sealed', envelope);
+                expect.fail('Should have failed');
+            } catch (error) {
+                expect((error as any).message).to.include('SenderBlocked');
+            } finally {
+                await client.setSenderBlocked(user1.publicKey, false);
+            }
+        });
+    });
+
+    describe('Compliance Denylist', () => {
+        let flagged: Keypair;
+        let flaggedClient: MailerClient;
+
+        before(async () => {
+            const payer = (provider.wallet as any).payer || provider.wallet;
+            flagged = Keypair.generate();
+            await provider.connection.confirmTransaction(
+                await provider.connection.requestAirdrop(flagged.publicKey, anchor.web3.LAMPORTS_PER_SOL)
+            );
+            const flaggedUsdc = await createAssociatedTokenAccount(provider.connection, payer, usdcMint, flagged.publicKey);
+            await mintTo(provider.connection, payer, usdcMint, flaggedUsdc, payer, 10 * 1_000_000);
+            flaggedClient = new MailerClient(provider.connection, new anchor.Wallet(flagged), program.programId, usdcMint);
+
+            // Leave a revenue share to claim once denied
+            await flaggedClient.sendPriority('Before', 'Not yet denied');
+        });
+
+        it('Should only let the owner change the denylist', async () => {
+            const user1Client = new MailerClient(provider.connection, new anchor.Wallet(user1), program.programId, usdcMint);
+            try {
+                await user1Client.setAddressDenied(flagged.publicKey, true);
+                expect.fail('Should have failed');
+            } catch (error) {
+                expect((error as any).message).to.include('OnlyOwner');
+            }
+            expect(await client.isAddressDenied(flagged.publicKey)).to.be.false;
+        });
+
+        it('Should refuse sends, addressed mail and payouts for a denied address', async () => {
+            const user1Client = new MailerClient(provider.connection, new anchor.Wallet(user1), program.programId, usdcMint);
+            const { paymentId } = await user1Client.sendWithPayment(flagged.publicKey, 1_000_000, 'paid-before-denial');
+            await client.setAddressDenied(flagged.publicKey, true);
+            expect(await client.isAddressDenied(flagged.publicKey)).to.be.true;
+
+            try {
+                const attempts: Array<() => Promise<unknown>> = [
+                    () => flaggedClient.send('Denied', 'Should not send'),
+                    () => flaggedClient.claimRecipientShare(),
+                    () => flaggedClient.acknowledgePayment(paymentId),
+                    () => user1Client.sendWithPayment(flagged.publicKey, 1_000_000, 'denied-mail'),
+                ];
+                for (const attempt of attempts) {
+                    try {
+                        await attempt();
+                        expect.fail('Should have failed');
+                    } catch (error) {
+                        expect((error as any).message).to.include('AddressDenied');
+                    }
+                }
+            } finally {
+                await client.setAddressDenied(flagged.publicKey, false);
+            }
+
+            expect(await client.isAddressDenied(flagged.publicKey)).to.be.false;
+            await flaggedClient.claimRecipientShare();
+            await flaggedClient.acknowledgePayment(paymentId);
+        });
+    });
+
+    describe('Fee Exemptions', () => {
+        it('Should let an exempt address send without paying a fee', async () => {
+            const payer = (provider.wallet as any).payer || provider.wallet;
+            const partner = Keypair.generate();
+            await provider.connection.confirmTransaction(
+                await provider.connection.requestAirdrop(partner.publicKey, anchor.web3.LAMPORTS_PER_SOL)
+            );
+            const partnerUsdc = await createAssociatedTokenAccount(provider.connection, payer, usdcMint, partner.publicKey);
+            await mintTo(provider.connection, payer, usdcMint, partnerUsdc, payer, 1_000_000);
+            const partnerClient = new MailerClient(provider.connection, new anchor.Wallet(partner), program.programId, usdcMint);
+
+            await client.setFeeExempt(partner.publicKey, true);
+            expect(await client.isFeeExempt(partner.publicKey)).to.be.true;
+
+            const events: any[] = [];
+            const listener = program.addEventListener('feeExemptSend' as any, (event: any) => events.push(event));
+            partnerClient.useFeeExemption(true);
+            try {
+                const before = await client.getStats();
+                await partnerClient.sendPriority('Support', 'How can we help?');
+                await partnerClient.send('Support', 'Ticket closed');
+                const after = await client.getStats();
+
+                const balance = await getAccount(provider.connection, partnerUsdc);
+                expect(Number(balance.amount)).to.equal(1_000_000);
+                expect(after.totalFeesCollected).to.equal(before.totalFeesCollected);
+                expect(after.totalMessagesSent - before.totalMessagesSent).to.equal(2);
+
+                await new Promise((resolve) => setTimeout(resolve, 1000));
+                expect(events).to.have.length(2);
+                expect(events[0].sender.toString()).to.equal(partner.publicKey.toString());
+            } finally {
+                await program.removeEventListener(listener);
+                await client.setFeeExempt(partner.publicKey, false);
+            }
+
+            expect(await client.isFeeExempt(partner.publicKey)).to.be.false;
+            partnerClient.useFeeExemption(false);
+            await partnerClient.send('Support', 'Paid again');
+            const balance = await getAccount(provider.connection, partnerUsdc);
+            expect(Number(balance.amount)).to.be.lessThan(1_000_000);
+        });
+    });
+
+    describe('Referrals', () => {
+        const REFERRAL_BPS = 2000;
+
+        after(async () => {
+            await client.setReferralShare(0);
+        });
+
+        it('Should pay the referrer a slice of the owner share', async () => {
+            const payer = (provider.wallet as any).payer || provider.wallet;
+            const referrer = Keypair.generate();
+            const sender = Keypair.generate();
+            for (const wallet of [referrer, sender]) {
+                await provider.connection.confirmTransaction(
+                    await provider.connection.requestAirdrop(wallet.publicKey, anchor.web3.LAMPORTS_PER_SOL)
+                );
+            }
+            const senderUsdc = await createAssociatedTokenAccount(provider.connection, payer, usdcMint, sender.publicKey);
+            await mintTo(provider.connection, payer, usdcMint, senderUsdc, payer, 1_000_000);
+            const referrerClient = new MailerClient(provider.connection, new anchor.Wallet(referrer), program.programId, usdcMint);
+            const senderClient = new MailerClient(provider.connection, new anchor.Wallet(sender), program.programId, usdcMint);
+
+            await client.setReferralShare(REFERRAL_BPS);
+            await referrerClient.registerReferralCode('wallet-x');
+            expect((await client.getReferralCodeReferrer('wallet-x'))!.toString()).to.equal(referrer.publicKey.toString());
+
+            await senderClient.useReferralCode('wallet-x');
+            await senderClient.sendPriority('Hello', 'Referred send');
+            await senderClient.send('Hello', 'Referred standard send');
+
+            const ownerShare = (SEND_FEE * 10) / 100;
+            const claim = await client.getRecipientClaimable(referrer.publicKey);
+            expect(claim!.amount).to.equal(2 * ((ownerShare * REFERRAL_BPS) / 10_000));
+        });
+
+        it('Should reject a referral code registered twice or used by its referrer', async () => {
+            try {
+                await client.registerReferralCode('wallet-x');
+                expect.fail('Should have failed');
+            } catch (error) {
+                expect((error as any).message).to.not.include('Should have failed');
+            }
+
+            const user1Client = new MailerClient(provider.connection, new anchor.Wallet(user1), program.programId, usdcMint);
+            await user1Client.registerReferralCode('user1-code');
+            await user1Client.useReferralCode('user1-code');
+            try {
+                await user1Client.sendPriority('Hello', 'Self referral');
+                expect.fail('Should have failed');
+            } catch (error) {
+                expect((error as any).message).to.include('SelfReferral');
+            }
+        });
+    });
+
+    describe('Campaigns', () => {
+        it('Should boost the recipient share of priority sends until the budget runs out', async () => {
+            const payer = (provider.wallet as any).payer || provider.wallet;
+            const sender = Keypair.generate();
+            await provider.connection.confirmTransaction(
+                await provider.connection.requestAirdrop(sender.publicKey, anchor.web3.LAMPORTS_PER_SOL)
+            );
+            const senderUsdc = await createAssociatedTokenAccount(provider.connection, payer, usdcMint, sender.publicKey);
+            await mintTo(provider.connection, payer, usdcMint, senderUsdc, payer, 1_000_000);
+            const senderClient = new MailerClient(provider.connection, new anchor.Wallet(sender), program.programId, usdcMint);
+
+            // A 95% share adds 5% of the fee; the budget covers one and a half boosts
+            const boost = (SEND_FEE * 5) / 100;
+            const now = Math.floor(Date.now() / 1000);
+            await client.createCampaign(1, 95, now - 60, now + 3600, boost * 1.5);
+
+            senderClient.useCampaign(1);
+            await senderClient.sendPriority('Promo', 'First');
+            await senderClient.sendPriority('Promo', 'Second');
+            await senderClient.sendPriority('Promo', 'Third');
+
+            const normalShare = (SEND_FEE * 90) / 100;
+            const claim = await client.getRecipientClaimable(sender.publicKey);
+            expect(claim!.amount).to.equal(3 * normalShare + boost * 1.5);
+            const campaign = await client.getCampaign(1);
+            expect(campaign!.spent).to.equal(campaign!.budget);
+
+            await client.closeCampaign(1);
+            expect(await client.getCampaign(1)).to.be.null;
+        });
+
+        it('Should reject invalid campaign terms', async () => {
+            const now = Math.floor(Date.now() / 1000);
+            try {
+                await client.createCampaign(2, 90, now, now + 3600, 1_000);
+                expect.fail('Should have failed');
+            } catch (error) {
+                expect((error as any).message).to.include('InvalidCampaignShare');
+            }
+            try {
+                await client.createCampaign(2, 95, now, now, 1_000);
+                expect.fail('Should have failed');
+            } catch (error) {
+                expect((error as any).message).to.include('InvalidCampaignWindow');
+            }
+        });
+    });
+
+    describe('Loyalty Points', () => {
+        after(async () => {
+            await client.setPointsRates(0, 0, 0);
+        });
+
+        it('Should accrue points per paid message and redeem them for send credit', async () => {
+            const payer = (provider.wallet as any).payer || provider.wallet;
+            const sender = Keypair.generate();
+            await provider.connection.confirmTransaction(
+                await provider.connection.requestAirdrop(sender.publicKey, anchor.web3.LAMPORTS_PER_SOL)
+            );
+            const senderUsdc = await createAssociatedTokenAccount(provider.connection, payer, usdcMint, sender.publicKey);
+            await mintTo(provider.connection, payer, usdcMint, senderUsdc, payer, 1_000_000);
+            const senderClient = new MailerClient(provider.connection, new anchor.Wallet(sender), program.programId, usdcMint);
+
+            await client.setPointsRates(10, 1, 100);
+            senderClient.useLoyaltyPoints(true);
+            await senderClient.sendPriority('Points', 'Priority');
+            await senderClient.send('Points', 'Standard');
+
+            const earned = await client.getLoyaltyPoints(sender.publicKey);
+            expect(earned!.points).to.equal(11);
+            expect(earned!.lifetimePoints).to.equal(11);
+
+            await senderClient.redeemPoints(10);
+            const claim = await client.getRecipientClaimable(sender.publicKey);
+            expect(claim!.sendCredit).to.equal(1_000);
+            const remaining = await client.getLoyaltyPoints(sender.publicKey);
+            expect(remaining!.points).to.equal(1);
+            expect(remaining!.lifetimePoints).to.equal(11);
+
+            try {
+                await senderClient.redeemPoints(2);
+                expect.fail('Should have failed');
+            } catch (error) {
+                expect((error as any).message).to.include('InsufficientPoints');
+            }
+        });
+    });
+
+    describe('NFT Perks', () => {
+        it('Should reject a holder discount above 100%', async () => {
+            try {
+                await client.setNftPerk(Keypair.generate().publicKey, 10_001);
+                expect.fail('Should have failed');
+            } catch (error) {
+                expect((error as any).message).to.include('InvalidNftDiscount');
+            }
+        });
+
+        it('Should reject an NFT proof while no collection is configured', async () => {
+            const payer = (provider.wallet as any).payer || provider.wallet;
+            const user1Client = new MailerClient(provider.connection, new anchor.Wallet(user1), program.programId, usdcMint);
+            const nftMint = await createMint(provider.connection, payer, payer.publicKey, null, 0);
+            const nftAccount = await createAssociatedTokenAccount(provider.connection, payer, nftMint, user1.publicKey);
+            await mintTo(provider.connection, payer, nftMint, nftAccount, payer, 1);
+
+            await client.setNftPerk(null, 0);
+            user1Client.useNftPerk(nftMint);
+            try {
+                await user1Client.send('Hello', 'Holder send');
+                expect.fail('Should have failed');
+            } catch (error) {
+                expect((error as any).message).to.not.include('Should have failed');
+            }
+            expect(await client.getNextSignedSendNonce(user1.publicKey)).to.equal(nonce);
+        });
+    });
+
+    describe('Receipts', () => {
+        it('Should reject a receipt base URI longer than 110 bytes', async () => {
+            try {
+                await client.setReceiptTree(Keypair.generate().publicKey, 'x'.repeat(111));
+                expect.fail('Should have failed');
+            } catch (error) {
+                expect((error as any).message).to.include('InvalidReceiptUri');
+            }
+        });
+
+        it('Should reject a receipt while no tree is configured', async () => {
+            const user1Client = new MailerClient(provider.connection, new anchor.Wallet(user1), program.programId, usdcMint);
+
+            await client.setReceiptTree(null, 'https://mail.example/receipts/');
+            user1Client.useReceipts(Keypair.generate().publicKey);
+            try {
+                await user1Client.sendPriority('Hello', 'Receipt send');
+                expect.fail('Should have failed');
+            } catch (error) {
+                expect((error as any).message).to.include('ReceiptsDisabled');
+            }
+        });
+    });
+
+    describe('Message Roots', () => {
+        const leaves = [1, 2, 3].map((n) => Buffer.alloc(32, n));
+
+        it('Should anchor a finished day and verify inclusion against it', async () => {
+            await client.publishMessageRoot(1, computeMessageRoot(leaves), leaves.length);
+            const published = await client.getMessageRoot(1);
+            expect(published!.messageCount).to.equal(3);
+
+            await client.verifyInclusion(1, leaves[2], messageRootProof(leaves, 2));
+            try {
+                await client.verifyInclusion(1, Buffer.alloc(32, 9), messageRootProof(leaves, 2));
+                expect.fail('Should have failed');
+            } catch (error) {
+                expect((error as any).message).to.include('InvalidInclusionProof');
+            }
+        });
+
+        it('Should reject roots for unfinished days and from other keys', async () => {
+            try {
+                await client.publishMessageRoot(messageRootDay(Date.now() / 1000), computeMessageRoot(leaves), 3);
+                expect.fail('Should have failed');
+            } catch (error) {
+                expect((error as any).message).to.include('DayNotFinished');
+            }
+
+            const user1Client = new MailerClient(provider.connection, new anchor.Wallet(user1), program.programId, usdcMint);
+            try {
+                await user1Client.publishMessageRoot(2, computeMessageRoot(leaves), 3);
+                expect.fail('Should have failed');
+            } catch (error) {
+                expect((error as any).message).to.include('NotRootPublisher');
+            }
+        });
+    });
+
+    describe('Scheduled Sends', () => {
+        it('Should escrow the standard fee until cancelled before it is due', async () => {
+            const user1Client = new MailerClient(provider.connection, new anchor.Wallet(user1), program.programId, usdcMint);
+            const usdcAccount = getAssociatedTokenAddressSync(usdcMint, user1.publicKey);
+            const balanceBefore = Number((await getAccount(provider.connection, usdcAccount)).amount);
+            const executeAfter = Math.floor(Date.now() / 1000) + 3600;
+
+            await user1Client.scheduleSend('reminder-1', executeAfter);
+            const scheduled = await client.getScheduledMessage(user1.publicKey, 'reminder-1');
+            expect(scheduled!.amount).to.equal(Math.floor((SEND_FEE * OWNER_SHARE) / 100));
+            expect(scheduled!.executeAfter).to.equal(executeAfter);
+
+            try {
+                await client.executeScheduled(user1.publicKey, 'reminder-1');
+                expect.fail('Should have failed');
+            } catch (error) {
+                expect((error as any).message).to.include('NotYetExecutable');
+            }
+
+            await user1Client.cancelScheduled('reminder-1');
+            expect(await client.getScheduledMessage(user1.publicKey, 'reminder-1')).to.be.null;
+            const balanceAfter = Number((await getAccount(provider.connection, usdcAccount)).amount);
+            expect(balanceAfter).to.equal(balanceBefore);
+        });
+
+        it('Should let a relayer sponsor the rent of a zero-SOL sender', async () => {
+            const relayer = provider.wallet.publicKey;
+            const payer = (provider.wallet as any).payer || provider.wallet;
+            const sponsored = Keypair.generate();
+            const sponsoredUsdc = await createAssociatedTokenAccount(provider.connection, payer, usdcMint, sponsored.publicKey);
+            await mintTo(provider.connection, payer, usdcMint, sponsoredUsdc, payer, SEND_FEE);
+            const scheduledPda = client.getScheduledMessageAddress(sponsored.publicKey, 'sponsored-1');
+
+            await (program.methods as any)
+                .scheduleSend('sponsored-1', new anchor.BN(Math.floor(Date.now() / 1000) + 3600))
+                .accounts({
+                    scheduled: scheduledPda,
+                    mailer: client.getMailerAddress(),
+                    tokenConfig: client.getTokenConfigAddress(usdcMint),
+                    priceUpdate: null,
+                    mint: usdcMint,
+                    sender: sponsored.publicKey,
+                    payer: relayer,
+                    senderUsdcAccount: sponsoredUsdc,
+                    mailerUsdcAccount: getAssociatedTokenAddressSync(usdcMint, client.getMailerAddress(), true),
+                    rateLimit: client.getRateLimitAddress(sponsored.publicKey),
+                    denylist: client.getDenylistAddress(sponsored.publicKey),
+                    tokenProgram: TOKEN_PROGRAM_ID,
+                    systemProgram: SystemProgram.programId,
+                })
+                .signers([sponsored])
+                .rpc();
+            const scheduled = await client.getScheduledMessage(sponsored.publicKey, 'sponsored-1');
+            expect(scheduled!.rentPayer.toString()).to.equal(relayer.toString());
+            expect(await provider.connection.getBalance(sponsored.publicKey)).to.equal(0);
+
+            // Rent goes back to the relayer, not the sender
+            await (program.methods as any)
+                .cancelScheduled()
+                .accounts({
+                    scheduled: scheduledPda,
+                    mailer: client.getMailerAddress(),
+                    sender: sponsored.publicKey,
+                    rentPayer: relayer,
+                    mint: usdcMint,
+                    senderUsdcAccount: sponsoredUsdc,
+                    mailerUsdcAccount: getAssociatedTokenAddressSync(usdcMint, client.getMailerAddress(), true),
+                    tokenProgram: TOKEN_PROGRAM_ID,
+                })
+                .signers([sponsored])
+                .rpc();
+            expect(await provider.connection.getBalance(sponsored.publicKey)).to.equal(0);
+            expect(Number((await getAccount(provider.connection, sponsoredUsdc)).amount)).to.equal(SEND_FEE);
+        });
+
+        it('Should reject a send scheduled in the past', async () => {
+            const user1Client = new MailerClient(provider.connection, new anchor.Wallet(user1), program.programId, usdcMint);
+            try {
+                await user1Client.scheduleSend('reminder-2', Math.floor(Date.now() / 1000) - 60);
+                expect.fail('Should have failed');
+            } catch (error) {
+                expect((error as any).message).to.include('InvalidExecuteAfter');
+            }
+        });
+    });
+
+    describe('Auto Claim', () => {
+        let user1Client: MailerClient;
+        let user2Client: MailerClient;
+
+        before(async () => {
+            user1Client = new MailerClient(provider.connection, new anchor.Wallet(user1), program.programId, usdcMint);
+            user2Client = new MailerClient(provider.connection, new anchor.Wallet(user2), program.programId, usdcMint);
+            await client.setAutoClaimer(provider.wallet.publicKey);
+        });
+
+        after(async () => {
+            await client.setAutoClaimer(null);
+            await user1Client.enableAutoClaim(false);
+        });
+
+        it('Should only let the registered auto-claimer pay out an opted-in claim near expiry', async () => {
+            await user1Client.enableAutoClaim(true);
+            await user1Client.sendPriority('Auto', 'Claimed before it expires');
+            const info = await client.getRecipientClaimable(user1.publicKey);
+            expect(info!.autoClaim).to.be.true;
+
+            try {
+                await user2Client.autoClaim(user1.publicKey);
+                expect.fail('Should have failed');
+            } catch (error) {
+                expect((error as any).message).to.include('NotAutoClaimer');
+            }
+
+            // Fresh claims are weeks away from their auto-claim window
+            try {
+                await client.autoClaim(user1.publicKey);
+                expect.fail('Should have failed');
+            } catch (error) {
+                expect((error as any).message).to.include('AutoClaimNotDue');
+            }
+        });
+
+        it('Should not auto-claim for recipients who have not opted in', async () => {
+            await user2Client.sendPriority('Manual', 'Claimed by hand');
+            try {
+                await client.autoClaim(user2.publicKey);
+                expect.fail('Should have failed');
+            } catch (error) {
+                expect((error as any).message).to.include('AutoClaimDisabled');
+            }
+        });
+    });
+
+    describe('Expiry Notifications', () => {
+        const pingEvents = async (signature: string) => {
+            const tx = await provider.connection.getTransaction(signature, {
+                commitment: 'confirmed',
+                maxSupportedTransactionVersion: 0,
+            });
+            const parser = new anchor.EventParser(program.programId, new anchor.BorshCoder(program.idl));
+            return [...parser.parseLogs(tx!.meta!.logMessages!)].filter((e) => e.name === 'claimExpiringSoon');
+        };
+
+        it('Should emit ClaimExpiringSoon only for claims inside the window', async () => {
+            const user1Client = new MailerClient(provider.connection, new anchor.Wallet(user1), program.programId, usdcMint);
+            await user1Client.sendPriority('Ping', 'Expires in 60 days');
+            const info = await client.getRecipientClaimable(user1.publicKey);
+
+            const events = await pingEvents(await client.pingClaims([user1.publicKey], 60));
+            expect(events).to.have.length(1);
+            expect(events[0].data.recipient.toString()).to.equal(user1.publicKey.toString());
+            expect(events[0].data.amount.toNumber()).to.equal(info!.amount);
+            expect(events[0].data.expiresAt.toNumber()).to.equal(info!.expiresAt);
+
+            expect(await pingEvents(await client.pingClaims([user1.publicKey], 1))).to.be.empty;
+        });
+
+        it('Should reject an empty window', async () => {
+            try {
+                await client.pingClaims([user1.publicKey], 0);
+                expect.fail('Should have failed');
+            } catch (error) {
+                expect((error as any).message).to.include('InvalidPingWindow');
+            }
+        });
+    });
+
+    describe('Session Keys', () => {
+        const sessionKey = Keypair.generate();
+        let user1Client: MailerClient;
+        let sessionClient: MailerClient;
+
+        before(async () => {
+            user1Client = new MailerClient(provider.connection, new anchor.Wallet(user1), program.programId, usdcMint);
+            sessionClient = new MailerClient(provider.connection, new anchor.Wallet(sessionKey), program.programId, usdcMint);
+            await provider.connection.confirmTransaction(
+                await provider.connection.requestAirdrop(sessionKey.publicKey, anchor.web3.LAMPORTS_PER_SOL)
+            );
+        });
+
+        it('Should send from the owner within the session spend limit', async () => {
+            const usdcAccount = getAssociatedTokenAddressSync(usdcMint, user1.publicKey);
+            const balanceBefore = Number((await getAccount(provider.connection, usdcAccount)).amount);
+            const standardFee = Math.floor((SEND_FEE * OWNER_SHARE) / 100);
+            await user1Client.createSession(sessionKey.publicKey, Math.floor(Date.now() / 1000) + 3600, standardFee);
+
+            await sessionClient.sendWithSession(user1.publicKey, 'Session', 'Sent with a session key', false);
+            const session = await client.getSession(user1.publicKey, sessionKey.publicKey);
+            expect(session!.spent).to.equal(standardFee);
+            const balanceAfter = Number((await getAccount(provider.connection, usdcAccount)).amount);
+            expect(balanceAfter).to.equal(balanceBefore - standardFee);
+
+            try {
+                await sessionClient.sendWithSession(user1.publicKey, 'Session', 'Over the limit', false);
+                expect.fail('Should have failed');
+            } catch (error) {
+                expect((error as any).message).to.include('SessionSpendExceeded');
+            }
+
+            await user1Client.revokeSession(sessionKey.publicKey);
+            expect(await client.getSession(user1.publicKey, sessionKey.publicKey)).to.be.null;
+            const account = await getAccount(provider.connection, usdcAccount);
+            expect(account.delegate).to.be.null;
+        });
+
+        it('Should reject sessions lasting over 30 days', async () => {
+            try {
+                await user1Client.createSession(
+                    sessionKey.publicKey,
+                    Math.floor(Date.now() / 1000) + 31 * 24 * 60 * 60,
+                    SEND_FEE
+                );
+                expect.fail('Should have failed');
+            } catch (error) {
+                expect((error as any).message).to.include('InvalidSessionExpiry');
+            }
+        });
+    });
+
+    describe('Signed Sends', () => {
+        const signer = Keypair.generate();
+        // Ed25519 signature as laid out in a web3.js Ed25519 program instruction
+        const sign = (message: Buffer) =>
+            Ed25519Program.createInstructionWithPrivateKey({ privateKey: signer.secretKey, message }).data.subarray(48, 112);
+
+        before(async () => {
+            const payer = (provider.wallet as any).payer || provider.wallet;
+            const signerUsdc = await createAssociatedTokenAccount(provider.connection, payer, usdcMint, signer.publicKey);
+            await mintTo(provider.connection, payer, usdcMint, signerUsdc, payer, SEND_FEE);
+            await approve(provider.connection, payer, signerUsdc, client.getMailerAddress(), signer, SEND_FEE);
+        });
+
+        it('Should relay a signed send billed to a zero-SOL signer exactly once', async () => {
+            const expiry = Math.floor(Date.now() / 1000) + 3600;
+            const nonce = await client.getNextSignedSendNonce(signer.publicKey);
+            expect(nonce).to.equal(0);
+            const signature = sign(client.getSignedSendPayload(signer.publicKey, 'signed-1', nonce, expiry));
+
+            await client.relaySignedSend(signer.publicKey, 'signed-1', nonce, expiry, signature);
+            expect(await client.getNextSignedSendNonce(signer.publicKey)).to.equal(1);
+            const signerUsdc = getAssociatedTokenAddressSync(usdcMint, signer.publicKey);
+            const balance = Number((await getAccount(provider.connection, signerUsdc)).amount);
+            expect(balance).to.equal(SEND_FEE - Math.floor((SEND_FEE * OWNER_SHARE) / 100));
+            expect(await provider.connection.getBalance(signer.publicKey)).to.equal(0);
+
+            try {
+                await client.relaySignedSend(signer.publicKey, 'signed-1', nonce, expiry, signature);
+                expect.fail('Should have failed');
+            } catch (error) {
+                expect((error as any).message).to.include('InvalidNonce');
+            }
+        });
+
+        it('Should reject a payload signed by someone else', async () => {
+            const expiry = Math.floor(Date.now() / 1000) + 3600;
+            const nonce = await client.getNextSignedSendNonce(user1.publicKey);
+            try {
+                await client.relaySignedSend(
+                    user1.publicKey,
+                    'forged-1',
+                    nonce,
+                    expiry,
+                    sign(client.getSignedSendPayload(user1.publicKey, 'forged-1', nonce, expiry))
+                );
+                expect.fail('Should have failed');
+            } catch (error) {
+                // The Ed25519 program rejects the signature before the mailer runs
+                expect((error as any).message).to.not.include('Should have failed');
+            }
+        });
+    });
+
+    describe('Prepaid Credit', () => {
+        it('Should pay sends from deposited credit and refund the rest', async () => {
+            const user2Client = new MailerClient(provider.connection, new anchor.Wallet(user2), program.programId, usdcMint);
+            const usdcAccount = getAssociatedTokenAddressSync(usdcMint, user2.publicKey);
+            const standardFee = Math.floor((SEND_FEE * OWNER_SHARE) / 100);
+
+            await user2Client.depositCredit(2 * standardFee);
+            expect(await client.getCreditBalance(user2.publicKey)).to.equal(2 * standardFee);
+
+            user2Client.usePrepaidCredit(true);
+            const balanceBefore = Number((await getAccount(provider.connection, usdcAccount)).amount);
+            await user2Client.send('Prepaid', 'No token transfer needed');
+            const balanceAfter = Number((await getAccount(provider.connection, usdcAccount)).amount);
+            expect(balanceAfter).to.equal(balanceBefore);
+
+            const remaining = await client.getCreditBalance(user2.publicKey);
+            expect(remaining).to.be.lessThan(2 * standardFee);
+            try {
+                await user2Client.withdrawCredit(remaining + 1);
+                expect.fail('Should have failed');
+            } catch (error) {
+                expect((error as any).message).to.include('InsufficientCredit');
+            }
+
+            await user2Client.withdrawCredit(remaining);
+            expect(await client.getCreditBalance(user2.publicKey)).to.equal(0);
+            const refunded = Number((await getAccount(provider.connection, usdcAccount)).amount);
+            expect(refunded).to.equal(balanceAfter + remaining);
+        });
+    });
+
+    describe('Subscription Plans', () => {
+        const PLAN_ID = 1;
+        const PLAN_PRICE = 5_000_000;
+
+        before(async () => {
+            await client.createSubscriptionPlan(PLAN_ID, PLAN_PRICE, 30 * 86400, 2);
+        });
+
+        after(async () => {
+            await client.closeSubscriptionPlan(PLAN_ID);
+        });
+
+        it('Should reject plan creation by non-owner', async () => {
+            const user1Client = new MailerClient(provider.connection, new anchor.Wallet(user1), program.programId, usdcMint);
+            try {
+                await user1Client.createSubscriptionPlan(PLAN_ID + 1, PLAN_PRICE, 86400, 0);
+                expect.fail('Should have failed');
+            } catch (error) {
+                expect((error as any).message).to.include('OnlyOwner');
+            }
+        });
+
+        it('Should waive standard fees up to the plan cap', async () => {
+            const user1Client = new MailerClient(provider.connection, new anchor.Wallet(user1), program.programId, usdcMint);
+            const usdcAccount = getAssociatedTokenAddressSync(usdcMint, user1.publicKey);
+            const standardFee = Math.floor((SEND_FEE * OWNER_SHARE) / 100);
+
+            const bucketBefore = await client.getOwnerClaimable();
+            await user1Client.purchaseSubscription(PLAN_ID);
+            expect((await client.getOwnerClaimable()) - bucketBefore).to.equal(PLAN_PRICE);
+            const subscription = await client.getPlanSubscription(user1.publicKey);
+            expect(subscription!.planId).to.equal(PLAN_ID);
+            expect(subscription!.messageCap).to.equal(2);
+
+            user1Client.useSubscription(true);
+            const balanceBefore = Number((await getAccount(provider.connection, usdcAccount)).amount);
+            await user1Client.send('Subscribed', 'First covered send');
+            await user1Client.send('Subscribed', 'Second covered send');
+            const balanceAfter = Number((await getAccount(provider.connection, usdcAccount)).amount);
+            expect(balanceAfter).to.equal(balanceBefore);
+            expect((await client.getPlanSubscription(user1.publicKey))!.messagesUsed).to.equal(2);
+
+            // The cap is spent, so the next send is charged the standard fee again
+            const ownerBefore = await client.getOwnerClaimable();
+            await user1Client.send('Subscribed', 'Over the cap');
+            expect((await client.getOwnerClaimable()) - ownerBefore).to.equal(standardFee);
+            expect((await client.getPlanSubscription(user1.publicKey))!.messagesUsed).to.equal(2);
+        });
+    });
+
+    describe('Staking Tiers', () => {
+        let staker: Keypair;
+        let stakerClient: MailerClient;
+
+        before(async () => {
+            const payer = (provider.wallet as any).payer || provider.wallet;
+            staker = Keypair.generate();
+            await provider.connection.confirmTransaction(
+                await provider.connection.requestAirdrop(staker.publicKey, anchor.web3.LAMPORTS_PER_SOL)
+            );
+            const stakerUsdc = await createAssociatedTokenAccount(provider.connection, payer, usdcMint, staker.publicKey);
+            await mintTo(provider.connection, payer, usdcMint, stakerUsdc, payer, 10 * 1_000_000);
+
+            stakerClient = new MailerClient(provider.connection, new anchor.Wallet(staker), program.programId, usdcMint);
+            await client.setStakeTiers([
+                { minStake: 1_000_000, discountBps: 1_000 },
+                { minStake: 5_000_000, discountBps: 2_500 },
+            ]);
+        });
+
+        after(async () => {
+            await client.setStakeTiers([]);
+        });
+
+        it('Should reject tiers that do not rise', async () => {
+            try {
+                await client.setStakeTiers([
+                    { minStake: 5_000_000, discountBps: 1_000 },
+                    { minStake: 1_000_000, discountBps: 2_500 },
+                ]);
+                expect.fail('Should have failed');
+            } catch (error) {
+                expect((error as any).message).to.include('InvalidStakeTiers');
+            }
+        });
+
+        it('Should discount priority sends by the tier the stake reaches', async () => {
+            const usdcAccount = getAssociatedTokenAddressSync(usdcMint, staker.publicKey);
+
+            await stakerClient.stake(5_000_000);
+            expect((await client.getStake(staker.publicKey))!.amount).to.equal(5_000_000);
+            expect(await client.getStakeTiers()).to.have.length(2);
+
+            stakerClient.useStake(true);
+            const balanceBefore = Number((await getAccount(provider.connection, usdcAccount)).amount);
+            await stakerClient.sendPriority('Staked', 'A quarter off');
+            const balanceAfter = Number((await getAccount(provider.connection, usdcAccount)).amount);
+            expect(balanceBefore - balanceAfter).to.equal(SEND_FEE * 0.75);
+        });
+
+        it('Should keep the stake locked after a deposit', async () => {
+            try {
+                await stakerClient.unstake();
+                expect.fail('Should have failed');
+            } catch (error) {
+                expect((error as any).message).to.include('StakeLocked');
+            }
+        });
+    });
+
+    describe('Vested Priority Sends', () => {
+        let vester: Keypair;
+        let vesterClient: MailerClient;
+
+        before(async () => {
+            const payer = (provider.wallet as any).payer || provider.wallet;
+            vester = Keypair.generate();
+            await provider.connection.confirmTransaction(
+                await provider.connection.requestAirdrop(vester.publicKey, anchor.web3.LAMPORTS_PER_SOL)
+            );
+            const vesterUsdc = await createAssociatedTokenAccount(provider.connection, payer, usdcMint, vester.publicKey);
+            await mintTo(provider.connection, payer, usdcMint, vesterUsdc, payer, 10 * 1_000_000);
+
+            vesterClient = new MailerClient(provider.connection, new anchor.Wallet(vester), program.programId, usdcMint);
+        });
+
+        it('Should credit a 95% share locked behind the cliff', async () => {
+            const ownerBefore = await client.getOwnerClaimable();
+            await vesterClient.sendPriorityVested('Vested', 'Locked for a better share');
+            expect((await client.getOwnerClaimable()) - ownerBefore).to.equal(SEND_FEE * 0.05);
+
+            const claim = await client.getRecipientClaimable(vester.publicKey);
+            expect(claim!.amount).to.equal(SEND_FEE * 0.95);
+            expect(claim!.vested).to.equal(SEND_FEE * 0.95);
+            expect(claim!.available).to.equal(0);
+
+            try {
+                await vesterClient.claimRecipientShare();
+                expect.fail('Should have failed');
+            } catch (error) {
+                expect((error as any).message).to.include('NoClaimableAmount');
+            }
+        });
+
+        it('Should leave vested shares behind when claiming the rest', async () => {
+            await vesterClient.sendPriority('Unvested', 'Standard share');
+            await vesterClient.claimRecipientShare();
+
+            const claim = await client.getRecipientClaimable(vester.publicKey);
+            expect(claim!.amount).to.equal(SEND_FEE * 0.95);
+            expect(claim!.available).to.equal(0);
+        });
+    });
+
+    describe('Spam Deposits', () => {
+        const DEPOSIT_FEES = 5;
+        let stranger: Keypair;
+        let strangerClient: MailerClient;
+        let user2Client: MailerClient;
+
+        before(async () => {
+            const payer = (provider.wallet as any).payer || provider.wallet;
+            stranger = Keypair.generate();
+            await provider.connection.confirmTransaction(
+                await provider.connection.requestAirdrop(stranger.publicKey, anchor.web3.LAMPORTS_PER_SOL)
+            );
+            const strangerUsdc = await createAssociatedTokenAccount(provider.connection, payer, usdcMint, stranger.publicKey);
+            await mintTo(provider.connection, payer, usdcMint, strangerUsdc, payer, 10 * 1_000_000);
+
+            strangerClient = new MailerClient(provider.connection, new anchor.Wallet(stranger), program.programId, usdcMint);
+            user2Client = new MailerClient(provider.connection, new anchor.Wallet(user2), program.programId, usdcMint);
+            await user2Client.setSpamDeposit(DEPOSIT_FEES);
+        });
+
+        after(async () => {
+            await user2Client.setSpamDeposit(0);
+        });
+
+        it('Should lock a deposit from a stranger and let the recipient confiscate it', async () => {
+            const envelope = sealEnvelope(stranger, user2.publicKey, 'spam-1', generateEnvelopeKeyPair().publicKey);
+
+            expect(await client.isDepositRequired(user2.publicKey, stranger.publicKey)).to.be.true;
+            await strangerClient.sendSealed(user2.publicKey, 'spam-1', envelope);
+            const deposit = await client.getSpamDeposit(user2.publicKey, stranger.publicKey);
+            expect(deposit!.amount).to.equal(SEND_FEE * DEPOSIT_FEES);
+
+            const claimBefore = await client.getRecipientClaimable(user2.publicKey);
+            await user2Client.confiscateSpamDeposit(stranger.publicKey);
+            const claimAfter = await client.getRecipientClaimable(user2.publicKey);
+            expect(claimAfter!.amount - (claimBefore?.amount ?? 0)).to.equal(SEND_FEE * DEPOSIT_FEES);
+            expect(await client.getSpamDeposit(user2.publicKey, stranger.publicKey)).to.be.null;
+        });
+
+        it('Should refund a deposit and waive it for the new contact', async () => {
+            const user1Client = new MailerClient(provider.connection, new anchor.Wallet(user1), program.programId, usdcMint);
+            const user1Usdc = getAssociatedTokenAddressSync(usdcMint, user1.publicKey);
+            const keys = generateEnvelopeKeyPair();
+
+            const balanceBefore = Number((await getAccount(provider.connection, user1Usdc)).amount);
+            await user1Client.sendSealed(user2.publicKey, 'legit-1', sealEnvelope(user1, user2.publicKey, 'legit-1', keys.publicKey));
+            await user2Client.refundSpamDeposit(user1.publicKey);
+            const balanceAfter = Number((await getAccount(provider.connection, user1Usdc)).amount);
+            expect(balanceBefore - balanceAfter).to.equal(Math.floor((SEND_FEE * OWNER_SHARE) / 100));
+
+            expect(await client.isDepositRequired(user2.publicKey, user1.publicKey)).to.be.false;
+            await user1Client.sendSealed(user2.publicKey, 'legit-2', sealEnvelope(user1, user2.publicKey, 'legit-2', keys.publicKey));
+            expect(await client.getSpamDeposit(user2.publicKey, user1.publicKey)).to.be.null;
+        });
+
+        it('Should reject a stranger who withholds the deposit', async () => {
+            const envelope = sealEnvelope(stranger, user2.publicKey, 'spam-2', generateEnvelopeKeyPair().publicKey);
+            try {
+                await (program.methods as any)
+                    .sendSealed(user2.publicKey, 'spam-2', envelope)
+                    .accounts({
+                        send: {
+                            recipientClaim: client.getClaimAddress(stranger.publicKey),
+                            mailer: client.getMailerAddress(),
+                            tokenConfig: client.getTokenConfigAddress(usdcMint),
+                            priceUpdate: null,
+                            ownerBucket: client.getOwnerBucketAddress(usdcMint, 'send'),
+                            mint: usdcMint,
+                            sender: stranger.publicKey,
+                            payer: stranger.publicKey,
+                            senderUsdcAccount: getAssociatedTokenAddressSync(usdcMint, stranger.publicKey),
+                            mailerUsdcAccount: getAssociatedTokenAddressSync(usdcMint, client.getMailerAddress(), true),
+                            recipientPreferences: client.getPreferencesAddress(stranger.publicKey),
+                            callerPolicy: client.getCallerPolicyAddress(),
+                            instructions: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
+                            expiryIndex: client.getExpiryIndexAddress(Math.floor(Date.now() / 1000)),
+                            mailIdRecord: null,
+                            rateLimit: client.getRateLimitAddress(stranger.publicKey),
+                            denylist: client.getDenylistAddress(stranger.publicKey),
+                            inbox: null,
+                            tokenProgram: TOKEN_PROGRAM_ID,
+                            associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+                            systemProgram: SystemProgram.programId,
+                        },
+                        recipientPreferences: client.getPreferencesAddress(user2.publicKey),
+                        block: client.getBlockAddress(user2.publicKey, stranger.publicKey),
+                        recipientDenylist: client.getDenylistAddress(user2.publicKey),
+                        spamPolicy: client.getSpamPolicyAddress(user2.publicKey),
+                        contact: client.getContactAddress(user2.publicKey, stranger.publicKey),
+                        deposit: null,
+                    })
+                    .signers([stranger])
+                    .rpc();
+                expect.fail('Should have failed');
+            } catch (error) {
+                expect((error as any).message).to.include('DepositRequired');
+            }
+        });
+    });
+
+    describe('Inbox Counters', () => {
+        it('Should count deliveries and remember the latest message', async () => {
+            const user1Client = new MailerClient(provider.connection, new anchor.Wallet(user1), program.programId, usdcMint);
+            const before = await client.getInbox(user1.publicKey);
+            await user1Client.send('Inbox', 'Counted');
+            await user1Client.sendPrepared('inbox-mail-1');
+
+            const inbox = await client.getInbox(user1.publicKey);
+            expect(inbox!.receivedCount).to.equal((before?.receivedCount ?? 0) + 2);
+            expect(inbox!.lastSender.toString()).to.equal(user1.publicKey.toString());
+            const stats = await client.getStats();
+            expect(inbox!.lastMessageSeq).to.equal(stats.nextMessageSeq - 1);
+        });
+
+        it('Should count a sealed send in the recipient inbox', async () => {
+            const relayerClient = new MailerClient(provider.connection, new anchor.Wallet(user1), program.programId, usdcMint);
+            const envelope = sealEnvelope(owner, user2.publicKey, 'inbox-sealed', generateEnvelopeKeyPair().publicKey);
+            const before = await client.getInbox(user2.publicKey);
+            await relayerClient.sendSealed(user2.publicKey, 'inbox-sealed', envelope);
+
+            const inbox = await client.getInbox(user2.publicKey);
+            expect(inbox!.receivedCount).to.equal((before?.receivedCount ?? 0) + 1);
+            expect(inbox!.lastSender.toString()).to.equal(user1.publicKey.toString());
+        });
+
+        it('Should create an inbox whose address was pre-funded', async () => {
+            const payer = (provider.wallet as any).payer || provider.wallet;
+            const sender = Keypair.generate();
+            await provider.connection.confirmTransaction(
+                await provider.connection.requestAirdrop(sender.publicKey, anchor.web3.LAMPORTS_PER_SOL)
+            );
+            const senderUsdc = await createAssociatedTokenAccount(provider.connection, payer, usdcMint, sender.publicKey);
+            await mintTo(provider.connection, payer, usdcMint, senderUsdc, payer, 1_000_000);
+            const senderClient = new MailerClient(provider.connection, new anchor.Wallet(sender), program.programId, usdcMint);
+
+            // A dust transfer to the unopened inbox must not block the first delivery
+            await sendAndConfirmTransaction(
+                provider.connection,
+                new Transaction().add(
+                    SystemProgram.transfer({
+                        fromPubkey: payer.publicKey,
+                        toPubkey: client.getInboxAddress(sender.publicKey),
+                        lamports: 1_000,
+                    })
+                ),
+                [payer]
+            );
+            await senderClient.send('Inbox', 'Pre-funded');
+
+            const inbox = await client.getInbox(sender.publicKey);
+            expect(inbox!.receivedCount).to.equal(1);
+        });
+    });
+
+    describe('Read Receipts', () => {
+        it('Should emit a read receipt and mark the read-state bitmap', async () => {
+            const messageId = Buffer.alloc(32, 7);
+            const signature = await client.markRead(messageId, 1029);
+
+            const tx = await provider.connection.getTransaction(signature, {
+                commitment: 'confirmed',
+                maxSupportedTransactionVersion: 0,
+            });
+            const parser = new anchor.EventParser(program.programId, new anchor.BorshCoder(program.idl));
+            const event = [...parser.parseLogs(tx!.meta!.logMessages!)].find((e) => e.name === 'mailRead')!;
+            expect(event.data.reader.toString()).to.equal(owner.publicKey.toString());
+            expect(Buffer.from(event.data.messageId as number[]).equals(messageId)).to.be.true;
+
+            expect(await client.isRead(owner.publicKey, 1029)).to.be.true;
+            expect(await client.isRead(owner.publicKey, 1028)).to.be.false;
+            expect(await client.isRead(user1.publicKey, 1029)).to.be.false;
+        });
+
+        it('Should emit a receipt without a read-state account', async () => {
+            const user2Client = new MailerClient(provider.connection, new anchor.Wallet(user2), program.programId, usdcMint);
+            await user2Client.markRead(Buffer.alloc(32, 8), 3, false);
+
+            const readState = await provider.connection.getAccountInfo(client.getReadStateAddress(user2.publicKey));
+            expect(readState).to.be.null;
+        });
+    });
+
+    describe('Sealed Sends', () => {
+        it('Should record only the relayer as sender and charge the standard fee', async () => {
+            const relayerClient = new MailerClient(provider.connection, new anchor.Wallet(user1), program.programId, usdcMint);
+            const recipientKeys = generateEnvelopeKeyPair();
+            const mailId = 'sealed-mail-1';
+            const envelope = sealEnvelope(user2, owner.publicKey, mailId, recipientKeys.publicKey);
+
+            const ownerBefore = await client.getOwnerClaimable();
+            const signature = await relayerClient.sendSealed(owner.publicKey, mailId, envelope);
+            expect((await client.getOwnerClaimable()) - ownerBefore).to.equal(Math.floor((SEND_FEE * OWNER_SHARE) / 100));
+
+            const tx = await provider.connection.getTransaction(signature, {
+                commitment: 'confirmed',
+                maxSupportedTransactionVersion: 0,
+            });
+            const parser = new anchor.EventParser(program.programId, new anchor.BorshCoder(program.idl));
+            const event = [...parser.parseLogs(tx!.meta!.logMessages!)].find((e) => e.name === 'sealedMailSent')!;
+            expect(event.data.from.toString()).to.equal(user1.publicKey.toString());
+            expect(event.data.to.toString()).to.equal(owner.publicKey.toString());
+
+            const trueSender = openEnvelope(Buffer.from(event.data.envelope as Buffer), owner.publicKey, mailId, recipientKeys);
+            expect(trueSender.toString()).to.equal(user2.publicKey.toString());
+        });
+
+        it('Should reject an empty envelope', async () => {
+            try {
+                await client.sendSealed(user1.publicKey, 'sealed-mail-2', Buffer.alloc(0));
+                expect.fail('Should have failed');
+            } catch (error) {
+                expect((error as any).message).to.include('InvalidEnvelope');
+            }
+        });
+    });
+
+    describe('Proof-of-Work Sends', () => {
+        it('Should refuse proof-of-work sends while disabled', async () => {
+            const userClient = new MailerClient(provider.connection, new anchor.Wallet(user1), program.programId, usdcMint);
+            expect(await userClient.getPowDifficulty()).to.equal(0);
+            try {
+                await userClient.sendWithPow('Subject', 'Body');
+                expect.fail('Should have failed');
+            } catch (error) {
+                expect((error as any).message).to.include('disabled');
+            }
+        });
+
+        it('Should accept a standard send paid with proof-of-work', async () => {
+            const noUsdcUser = Keypair.generate();
+            await provider.connection.confirmTransaction(
+                await provider.connection.requestAirdrop(noUsdcUser.publicKey, anchor.web3.LAMPORTS_PER_SOL)
+            );
+            const powClient = new MailerClient(provider.connection, new anchor.Wallet(noUsdcUser), program.programId, usdcMint);
+
+            try {
+                await powClient.setPowDifficulty(8);
+                expect.fail('Should have failed');
+            } catch (error) {
+                expect((error as any).message).to.include('OnlyOwner');
+            }
+            await client.setPowDifficulty(8);
+
+            await powClient.sendWithPow('Subject', 'Sent without USDC');
+            await powClient.sendWithPow('Subject', 'Sent without USDC');
+            const state = await (program.account as any).powState.fetch(powClient.getPowStateAddress(noUsdcUser.publicKey));
+            expect(state.counter.toNumber()).to.equal(2);
+
+            // A nonce that does not meet the difficulty is rejected
+            try {
+                await (program.methods as any)
+                    .sendWithPow('Subject', 'Unsolved', new anchor.BN(0), null)
+                    .accounts({
+                        mailer: client.getMailerAddress(),
+                        powState: powClient.getPowStateAddress(noUsdcUser.publicKey),
+                        sender: noUsdcUser.publicKey,
+                        payer: noUsdcUser.publicKey,
+                        recipientPreferences: powClient.getPreferencesAddress(noUsdcUser.publicKey),
+                        callerPolicy: powClient.getCallerPolicyAddress(),
+                        instructions: SYSVAR_INSTRUCTIONS_PUBKEY,
+                        rateLimit: powClient.getRateLimitAddress(noUsdcUser.publicKey),
+                        denylist: powClient.getDenylistAddress(noUsdcUser.publicKey),
+                        systemProgram: SystemProgram.programId,
+                    })
+                    .signers([noUsdcUser])
+                    .rpc();
+            } catch (error) {
+                // Nonce 0 solves 8 bits with probability 1/256
+                expect((error as any).message).to.include('InsufficientWork');
+            }
+
+            await client.setPowDifficulty(0);
+        });
+    });
+
+    describe('Fee Display Metadata', () => {
+        it('Should quote fees with the mint\'s display metadata', async () => {
+            const bare = await client.quoteFee(true);
+            expect(bare.amount).to.equal(SEND_FEE);
+            expect(bare.decimals).to.equal(6);
+            expect(bare.symbol).to.equal('');
+
+            await client.setTokenDisplay(usdcMint, 'USDC', '$0.00');
+            const display = await client.getTokenDisplay(usdcMint);
+            expect(display!.symbol).to.equal('USDC');
+            expect(display!.decimals).to.equal(6);
+
+            const standard = await client.quoteFee(false);
+            expect(standard.amount).to.equal(Math.floor((SEND_FEE * OWNER_SHARE) / 100));
+            expect(standard.symbol).to.equal('USDC');
+            expect(formatTokenAmount(standard.amount, standard)).to.equal('$0.01');
+        });
+
+        it('Should reject oversized display metadata and non-owners', async () => {
+            try {
+                await client.setTokenDisplay(usdcMint, 'TOOLONGSYMBOL', '$0.00');
+                expect.fail('Should have failed');
+            } catch (error) {
+                expect((error as any).message).to.include('InvalidDisplayMetadata');
+            }
+
+            const userClient = new MailerClient(provider.connection, new anchor.Wallet(user1), program.programId, usdcMint);
+            try {
+                await userClient.setTokenDisplay(usdcMint, 'FAKE', '0.00');
+                expect.fail('Should have failed');
+            } catch (error) {
+                expect((error as any).message).to.include('OnlyOwner');
+            }
+        });
+    });
+
+    describe('Token-2022 Fees', () => {
+        const TRANSFER_FEE_BPS = 100; // 1%
+
+        it('Should record shares on the net amount received for transfer-fee mints', async () => {
+            const payer = (provider.wallet as any).payer;
+            const mintKeypair = Keypair.generate();
+            const mintLen = getMintLen([ExtensionType.TransferFeeConfig]);
+            const lamports = await provider.connection.getMinimumBalanceForRentExemption(mintLen);
+            await sendAndConfirmTransaction(
+                provider.connection,
+                new Transaction().add(
+                    SystemProgram.createAccount({
+                        fromPubkey: payer.publicKey,
+                        newAccountPubkey: mintKeypair.publicKey,
+                        space: mintLen,
+                        lamports,
+                        programId: TOKEN_2022_PROGRAM_ID,
+                    }),
+                    createInitializeTransferFeeConfigInstruction(
+                        mintKeypair.publicKey,
+                        payer.publicKey,
+                        payer.publicKey,
+                        TRANSFER_FEE_BPS,
+                        BigInt(1_000_000_000),
+                        TOKEN_2022_PROGRAM_ID
+                    ),
+                    createInitializeMintInstruction(mintKeypair.publicKey, 6, payer.publicKey, null, TOKEN_2022_PROGRAM_ID)
+                ),
+                [payer, mintKeypair]
+            );
+            const mint = mintKeypair.publicKey;
+
+            const sender = Keypair.generate();
+            await provider.connection.confirmTransaction(
+                await provider.connection.requestAirdrop(sender.publicKey, anchor.web3.LAMPORTS_PER_SOL)
+            );
+            const senderAccount = await createAssociatedTokenAccount(
+                provider.connection, payer, mint, sender.publicKey, undefined, TOKEN_2022_PROGRAM_ID
+            );
+            await mintTo(provider.connection, payer, mint, senderAccount, payer, 10 * 1_000_000, [], undefined, TOKEN_2022_PROGRAM_ID);
+            await createAssociatedTokenAccount(
+                provider.connection, payer, mint, client.getMailerAddress(), undefined, TOKEN_2022_PROGRAM_ID, undefined, true
+            );
+
+            await client.addToken(mint, SEND_FEE);
+            const senderClient = new MailerClient(
+                provider.connection,
+                new anchor.Wallet(sender),
+                program.programId,
+                mint,
+                TOKEN_2022_PROGRAM_ID
+            );
+            await senderClient.sendPriority('Subject', 'Paid with a transfer-fee mint');
+
+            const received = SEND_FEE - Math.floor((SEND_FEE * TRANSFER_FEE_BPS) / 10_000);
+            const ownerAmount = Math.floor((received * OWNER_SHARE) / 100);
+            const claimInfo = await senderClient.getRecipientClaimable(sender.publicKey);
+            expect(claimInfo!.amount).to.equal(received - ownerAmount);
+            expect(await client.getOwnerClaimable(mint)).to.equal(ownerAmount);
+        });
+    });
+
+    describe('Oracle Pricing', () => {
+        const SOL_USD_FEED = 'ef0d8b6fda2ceba41da15d4095d1da392a0d2f8ed0c6c7bc0f4cfac8c280b56d';
+
+        it('Should default the USD fee to 10 cents and let only the owner change it', async () => {
+            expect(await client.getUsdFee()).to.equal(10);
+
+            const userClient = new MailerClient(
+                provider.connection,
+                new anchor.Wallet(user1),
+                program.programId,
+                usdcMint
+            );
+            try {
+                await userClient.setUsdFee(25);
+                expect.fail('Should have failed');
+            } catch (error) {
+                expect((error as any).message).to.include('OnlyOwner');
+            }
+
+            await client.setUsdFee(25);
+            expect(await client.getUsdFee()).to.equal(25);
+            await client.setUsdFee(10);
+        });
+
+        it('Should require a price update for oracle-priced mints', async () => {
+            const payer = (provider.wallet as any).payer;
+            const mint = await createMint(provider.connection, payer, payer.publicKey, null, 9);
+            const sender = Keypair.generate();
+            await provider.connection.confirmTransaction(
+                await provider.connection.requestAirdrop(sender.publicKey, anchor.web3.LAMPORTS_PER_SOL)
+            );
+            const senderAccount = await createAssociatedTokenAccount(provider.connection, payer, mint, sender.publicKey);
+            await mintTo(provider.connection, payer, mint, senderAccount, payer, 10 * anchor.web3.LAMPORTS_PER_SOL);
+            await createAssociatedTokenAccount(provider.connection, payer, mint, client.getMailerAddress(), undefined, undefined, undefined, true);
+
+            await client.addToken(mint, SEND_FEE);
+            await client.setPriceFeed(mint, '0x' + SOL_USD_FEED);
+            expect((await client.getTokenConfig(mint))!.priceFeedId).to.equal(SOL_USD_FEED);
+
+            const senderClient = new MailerClient(
+                provider.connection,
+                new anchor.Wallet(sender),
+                program.programId,
+                mint
+            );
+            try {
+                await senderClient.send('Subject', 'No price update');
+                expect.fail('Should have failed');
+            } catch (error) {
+                expect((error as any).message).to.include('PriceFeedRequired');
+            }
+
+            // Clearing the feed reverts to the fixed per-mint fee
+            await client.setPriceFeed(mint, null);
+            expect((await client.getTokenConfig(mint))!.priceFeedId).to.be.null;
+            await senderClient.send('Subject', 'Fixed fee');
+            expect(await client.getOwnerClaimable(mint)).to.equal(Math.floor((SEND_FEE * OWNER_SHARE) / 100));
+        });
+    });
+
+    describe('Wrapped SOL Fees', () => {
+        it('Should let a SOL-only wallet pay the fee in wrapped SOL', async () => {
+            const payer = (provider.wallet as any).payer;
+            const WSOL_FEE = 1_000_000; // 0.001 SOL
+            await createAssociatedTokenAccount(provider.connection, payer, NATIVE_MINT, client.getMailerAddress(), undefined, undefined, undefined, true);
+            await client.addToken(NATIVE_MINT, WSOL_FEE);
+
+            const sender = Keypair.generate();
+            await provider.connection.confirmTransaction(
+                await provider.connection.requestAirdrop(sender.publicKey, anchor.web3.LAMPORTS_PER_SOL)
+            );
+            const solClient = new MailerClient(
+                provider.connection,
+                new anchor.Wallet(sender),
+                program.programId,
+                NATIVE_MINT
+            );
+
+            await solClient.sendWithSol('Subject', 'Paid in SOL');
+
+            expect(await client.getOwnerClaimable(NATIVE_MINT)).to.equal(Math.floor((WSOL_FEE * OWNER_SHARE) / 100));
+            // The temporary wSOL account is closed in the same transaction
+            const wsolAccount = getAssociatedTokenAddressSync(NATIVE_MINT, sender.publicKey);
+            expect(await provider.connection.getAccountInfo(wsolAccount)).to.be.null;
+        });
+    });
+
+    describe('Channels', () => {
+        const CHANNEL = 'weekly-digest';
+
+        it('Should create a channel and count its subscribers', async () => {
+            const ownerClient = new MailerClient(provider.connection, new anchor.Wallet(user1), program.programId, usdcMint);
+            const readerClient = new MailerClient(provider.connection, new anchor.Wallet(user2), program.programId, usdcMint);
+
+            await ownerClient.createChannel(CHANNEL);
+            await readerClient.subscribeToChannel(CHANNEL);
+
+            const channel = (await client.getChannel(CHANNEL))!;
+            expect(channel.owner.toString()).to.equal(user1.publicKey.toString());
+            expect(channel.name).to.equal(CHANNEL);
+            expect(channel.subscriberCount).to.equal(1);
+            expect(await client.isSubscribed(CHANNEL, user2.publicKey)).to.be.true;
+
+            try {
+                await readerClient.subscribeToChannel(CHANNEL);
+                expect.fail('Should have failed');
+            } catch (error) {
+                expect((error as any).message).to.not.include('Should have failed');
+            }
+        });
+
+        it('Should credit the recipient share of a post to the channel owner', async () => {
+            const ownerClient = new MailerClient(provider.connection, new anchor.Wallet(user1), program.programId, usdcMint);
+            const posterClient = new MailerClient(provider.connection, new anchor.Wallet(user2), program.programId, usdcMint);
+            const recipientShare = SEND_FEE - Math.floor((SEND_FEE * OWNER_SHARE) / 100);
+
+            const ownerBefore = (await client.getRecipientClaimable(user1.publicKey))?.amount ?? 0;
+            const posterBefore = (await client.getRecipientClaimable(user2.publicKey))?.amount ?? 0;
+            await posterClient.postToChannel(CHANNEL, 'channel-post-1');
+            expect((await client.getRecipientClaimable(user1.publicKey))!.amount - ownerBefore).to.equal(recipientShare);
+            expect((await client.getRecipientClaimable(user2.publicKey))?.amount ?? 0).to.equal(posterBefore);
+
+            // The owner broadcasting to their own channel is credited like a priority send
+            await ownerClient.postToChannel(CHANNEL, 'channel-post-2');
+            expect((await client.getRecipientClaimable(user1.publicKey))!.amount - ownerBefore).to.equal(2 * recipientShare);
+            expect((await client.getChannel(CHANNEL))!.postCount).to.equal(2);
+        });
+
+        it('Should charge subscriptions per epoch and gate readers-only posts', async () => {
+            const PAID_CHANNEL = 'paid-newsletter';
+            const SUBSCRIPTION_FEE = 2_000_000; // 2 USDC per epoch
+            const EPOCH = 30 * 24 * 60 * 60;
+            const ownerClient = new MailerClient(provider.connection, new anchor.Wallet(user1), program.programId, usdcMint);
+            const readerClient = new MailerClient(provider.connection, new anchor.Wallet(user2), program.programId, usdcMint);
+
+            await ownerClient.createChannel(PAID_CHANNEL);
+            await ownerClient.setChannelTerms(PAID_CHANNEL, SUBSCRIPTION_FEE, true);
+            try {
+                await readerClient.setChannelTerms(PAID_CHANNEL, 0, false);
+                expect.fail('Should have failed');
+            } catch (error) {
+                expect((error as any).message).to.include('NotChannelOwner');
+            }
+
+            // Subscribed but unpaid readers cannot post
+            await readerClient.subscribeToChannel(PAID_CHANNEL);
+            expect((await readerClient.getSubscription(PAID_CHANNEL, user2.publicKey))!.active).to.be.false;
+            try {
+                await readerClient.postToChannel(PAID_CHANNEL, 'unpaid-post');
+                expect.fail('Should have failed');
+            } catch (error) {
+                expect((error as any).message).to.include('SubscriptionRequired');
+            }
+
+            await readerClient.paySubscription(PAID_CHANNEL, 2);
+            const subscription = (await readerClient.getSubscription(PAID_CHANNEL, user2.publicKey))!;
+            expect(subscription.active).to.be.true;
+            expect(subscription.paidUntil - subscription.subscribedAt).to.be.closeTo(2 * EPOCH, 10);
+            expect((await client.getChannel(PAID_CHANNEL))!.claimable).to.equal(2 * SUBSCRIPTION_FEE);
+            await readerClient.postToChannel(PAID_CHANNEL, 'paid-post');
+
+            const ownerAccount = getAssociatedTokenAddressSync(usdcMint, user1.publicKey);
+            const balanceBefore = Number((await getAccount(provider.connection, ownerAccount)).amount);
+            await ownerClient.withdrawChannelFees(PAID_CHANNEL);
+            const balanceAfter = Number((await getAccount(provider.connection, ownerAccount)).amount);
+            expect(balanceAfter - balanceBefore).to.equal(2 * SUBSCRIPTION_FEE);
+            expect((await client.getChannel(PAID_CHANNEL))!.claimable).to.equal(0);
+        });
+
+        it('Should reject empty and oversized channel names', async () => {
+            for (const name of ['', 'x'.repeat(33)]) {
+                try {
+                    await client.createChannel(name);
+                    expect.fail('Should have failed');
+                } catch (error) {
+                    expect((error as any).message).to.include('InvalidChannelName');
+                }
+            }
+        });
+
+        it('Should let moderators pin posts, mute senders and remove subscribers', async () => {
+            const ownerClient = new MailerClient(provider.connection, new anchor.Wallet(user1), program.programId, usdcMint);
+            const readerClient = new MailerClient(provider.connection, new anchor.Wallet(user2), program.programId, usdcMint);
+            const moderatorClient = client;
+            const pinned = Buffer.alloc(32, 7);
+
+            try {
+                await moderatorClient.pinPost(CHANNEL, pinned);
+                expect.fail('Should have failed');
+            } catch (error) {
+                expect((error as any).message).to.include('NotModerator');
+            }
+            await ownerClient.setChannelModerator(CHANNEL, owner.publicKey, true);
+            await moderatorClient.pinPost(CHANNEL, pinned);
+            expect((await client.getChannel(CHANNEL))!.pinnedMessage).to.equal(pinned.toString('hex'));
+
+            // Muted senders cannot post until unmuted
+            await moderatorClient.setSenderMuted(CHANNEL, user2.publicKey, true);
+            try {
+                await readerClient.postToChannel(CHANNEL, 'muted-post');
+                expect.fail('Should have failed');
+            } catch (error) {
+                expect((error as any).message).to.include('SenderMuted');
+            }
+            try {
+                await moderatorClient.setSenderMuted(CHANNEL, user1.publicKey, true);
+                expect.fail('Should have failed');
+            } catch (error) {
+                expect((error as any).message).to.include('CannotMuteModerator');
+            }
+            await moderatorClient.setSenderMuted(CHANNEL, user2.publicKey, false);
+            await readerClient.postToChannel(CHANNEL, 'unmuted-post');
+
+            const subscribers = (await client.getChannel(CHANNEL))!.subscriberCount;
+            await moderatorClient.removeSubscriber(CHANNEL, user2.publicKey);
+            expect(await client.isSubscribed(CHANNEL, user2.publicKey)).to.be.false;
+            expect((await client.getChannel(CHANNEL))!.subscriberCount).to.equal(subscribers - 1);
+
+            await ownerClient.setChannelModerator(CHANNEL, owner.publicKey, false);
+            expect((await client.getChannel(CHANNEL))!.moderators).to.be.empty;
+        });
+    });
+
+    describe('Groups', () => {
+        const GROUP = 'dao-core';
+
+        it('Should let only the admin manage members', async () => {
+            const adminClient = new MailerClient(provider.connection, new anchor.Wallet(user1), program.programId, usdcMint);
+            const memberClient = new MailerClient(provider.connection, new anchor.Wallet(user2), program.programId, usdcMint);
+
+            await adminClient.createGroup(GROUP);
+            await adminClient.addGroupMember(GROUP, user1.publicKey);
+            await adminClient.addGroupMember(GROUP, user2.publicKey);
+            await adminClient.addGroupMember(GROUP, owner.publicKey);
+
+            try {
+                await memberClient.removeGroupMember(GROUP, owner.publicKey);
+                expect.fail('Should have failed');
+            } catch (error) {
+                expect((error as any).message).to.include('NotGroupModerator');
+            }
+            try {
+                await adminClient.addGroupMember(GROUP, user2.publicKey);
+                expect.fail('Should have failed');
+            } catch (error) {
+                expect((error as any).message).to.include('AlreadyMember');
+            }
+
+            const group = (await client.getGroup(GROUP))!;
+            expect(group.admin.toString()).to.equal(user1.publicKey.toString());
+            expect(group.members.map((m) => m.toString())).to.deep.equal(
+                [user1, user2, owner].map((k) => k.publicKey.toString())
+            );
+        });
+
+        it('Should split the recipient share equally across member claims', async () => {
+            const memberClient = new MailerClient(provider.connection, new anchor.Wallet(user2), program.programId, usdcMint);
+            const members = [user1.publicKey, user2.publicKey, owner.publicKey];
+            const ownerShare = Math.floor((SEND_FEE * OWNER_SHARE) / 100);
+            const memberShare = Math.floor((SEND_FEE - ownerShare) / members.length);
+            const remainder = SEND_FEE - ownerShare - memberShare * members.length;
+
+            const claimsBefore = await Promise.all(
+                members.map(async (m) => (await client.getRecipientClaimable(m))?.amount ?? 0)
+            );
+            const ownerBefore = await client.getOwnerClaimable();
+            await memberClient.sendToGroup(GROUP, 'group-announcement-1');
+
+            for (const [i, member] of members.entries()) {
+                expect((await client.getRecipientClaimable(member))!.amount - claimsBefore[i]).to.equal(memberShare);
+            }
+            expect((await client.getOwnerClaimable()) - ownerBefore).to.equal(ownerShare + remainder);
+        });
+
+        it('Should open a claim for a member who has none', async () => {
+            const adminClient = new MailerClient(provider.connection, new anchor.Wallet(user1), program.programId, usdcMint);
+            const memberClient = new MailerClient(provider.connection, new anchor.Wallet(user2), program.programId, usdcMint);
+            const newcomer = Keypair.generate();
+            await adminClient.addGroupMember(GROUP, newcomer.publicKey);
+            expect(await client.getRecipientClaimable(newcomer.publicKey)).to.be.null;
+
+            await memberClient.sendToGroup(GROUP, 'group-announcement-newcomer');
+
+            const ownerShare = Math.floor((SEND_FEE * OWNER_SHARE) / 100);
+            const memberShare = Math.floor((SEND_FEE - ownerShare) / 4);
+            expect((await client.getRecipientClaimable(newcomer.publicKey))!.amount).to.equal(memberShare);
+            await adminClient.removeGroupMember(GROUP, newcomer.publicKey);
+        });
+
+        it('Should refuse group sends from non-members', async () => {
+            // A moderator appointed by the admin may remove members
+            const adminClient = new MailerClient(provider.connection, new anchor.Wallet(user1), program.programId, usdcMint);
+            await adminClient.setGroupModerator(GROUP, owner.publicKey, true);
+            expect((await client.getGroup(GROUP))!.moderators.map((m) => m.toString())).to.deep.equal([owner.publicKey.toString()]);
+            await client.removeGroupMember(GROUP, user2.publicKey);
+
+            const formerClient = new MailerClient(provider.connection, new anchor.Wallet(user2), program.programId, usdcMint);
+            try {
+                await formerClient.sendToGroup(GROUP, 'group-announcement-2');
+                expect.fail('Should have failed');
+            } catch (error) {
+                expect((error as any).message).to.include('NotMember');
+            }
+        });
+    });
+});
@@ -1,5 +1,7 @@
 import { expect } from 'chai';
-import { PublicKey, Keypair } from '@solana/web3.js';
+import { utils } from '@coral-xyz/anchor';
+import { createHash } from 'crypto';
+import { PublicKey, Keypair, SystemProgram, Transaction } from '@solana/web3.js';
 import { 
     formatUSDC, 
     parseUSDC, 
@@ -10,8 +12,27 @@ import {
     DelegationInfo,
     DeploymentConfig,
     MailServiceFees,
-    MailerFees
+    MailerFees,
+    formatTokenAmount,
+    externalAddressFromHex,
+    messageLogTreeSize,
+    MAX_EXTERNAL_ADDRESS_LEN
 } from '../app/types';
+import { RpcPool, isTransientRpcError } from '../app/rpc-pool';
+import { MAX_SEALED_ENVELOPE_LEN, generateEnvelopeKeyPair, openEnvelope, sealEnvelope } from '../app/sealed-envelope';
+import { encodePreparedBatch, decodePreparedBatch, MAX_PREPARED_BATCH } from '../app/prepared-batch';
+import { powChallengeHash, leadingZeroBits, solvePow } from '../app/pow';
+import { computeMessageId, computeMessageContentHash, computeMessageLeaf } from '../app/message-id';
+import { computeMessageRoot, messageRootDay, messageRootProof } from '../app/message-roots';
+import { BODY_HASH_THRESHOLD, resolveEventBody } from '../app/message-body';
+import { EVENT_IX_TAG, decodeCpiEvents } from '../app/cpi-events';
+import {
+    encodeTransactionRequestUrl,
+    parseTransactionRequestUrl,
+    parseTransactionRequestAccount,
+    createTransactionRequestResponse,
+    createTransactionRequestQR
+} from '../app/solana-pay';
 
 describe('Types and Utilities', () => {
     describe('USDC Formatting and Parsing', () => {
@@ -99,12 +120,14 @@ describe('Types and Utilities', () => {
             const claimable: ClaimableInfo = {
                 amount: 1000000,
                 expiresAt: Math.floor(Date.now() / 1000) + 3600,
-                isExpired: false
+                isExpired: false,
+                sendCredit: 0
             };
 
             expect(claimable.amount).to.be.a('number');
             expect(claimable.expiresAt).to.be.a('number');
             expect(claimable.isExpired).to.be.a('boolean');
+            expect(claimable.sendCredit).to.be.a('number');
         });
 
         it('Should validate DelegationInfo structure', () => {
@@ -283,4 +306,349 @@ describe('Types and Utilities', () => {
             });
         });
     });
-});
\ No newline at end of file
+    describe('RPC Failover', () => {
+        // Minimal Connection stand-in: each call consumes the next scripted outcome
+        function fakeConnection(endpoint: string, outcomes: Array<'ok' | Error>) {
+            const calls: string[] = [];
+            return {
+                calls,
+                rpcEndpoint: endpoint,
+                getBalance: async () => {
+                    calls.push('getBalance');
+                    const outcome = outcomes.shift() ?? 'ok';
+                    if (outcome !== 'ok') throw outcome;
+                    return 42;
+                },
+            } as any;
+        }
+
+        it('Should fail over to the next endpoint on transient errors', async () => {
+            const primary = fakeConnection('primary', [new Error('503 Service Unavailable')]);
+            const backup = fakeConnection('backup', []);
+            const pool = new RpcPool([primary, backup]);
+
+            expect(await pool.connection().getBalance(Keypair.generate().publicKey)).to.equal(42);
+            expect(primary.calls).to.have.length(1);
+            expect(backup.calls).to.have.length(1);
+            expect(pool.health()[0].consecutiveFailures).to.equal(1);
+        });
+
+        it('Should not fail over on program errors', async () => {
+            const programError = Object.assign(new Error('custom program error: 0x1770'), { logs: [] });
+            const primary = fakeConnection('primary', [programError]);
+            const backup = fakeConnection('backup', []);
+            const pool = new RpcPool([primary, backup]);
+
+            try {
+                await pool.execute((c) => c.getBalance(Keypair.generate().publicKey));
+                expect.fail('Should have thrown');
+            } catch (error) {
+                expect(error).to.equal(programError);
+            }
+            expect(backup.calls).to.have.length(0);
+        });
+
+        it('Should bench unhealthy endpoints and prefer healthy ones', async () => {
+            const now = 0;
+            const primary = fakeConnection('primary', [new Error('request timed out')]);
+            const backup = fakeConnection('backup', []);
+            const pool = new RpcPool([primary, backup], { maxConsecutiveFailures: 1, cooldownMs: 1000, now: () => now });
+
+            await pool.execute((c) => c.getBalance(Keypair.generate().publicKey));
+            expect(pool.health()[0].benchedUntil).to.equal(1000);
+
+            // Benched primary is skipped while the backup stays healthy
+            await pool.execute((c) => c.getBalance(Keypair.generate().publicKey));
+            expect(primary.calls).to.have.length(1);
+            expect(backup.calls).to.have.length(2);
+        });
+
+        it('Should classify transient RPC errors', () => {
+            expect(isTransientRpcError(new Error('fetch failed'))).to.be.true;
+            expect(isTransientRpcError(new Error('429 Too Many Requests'))).to.be.true;
+            expect(isTransientRpcError(new Error('Account does not exist'))).to.be.false;
+        });
+    });
+
+    describe('Token Amount Formatting', () => {
+        it('Should apply a display hint\'s prefix, suffix and precision', () => {
+            const usdc = { decimals: 6, symbol: 'USDC', displayHint: '$0.00' };
+            expect(formatTokenAmount(100_000, usdc)).to.equal('$0.10');
+            expect(formatTokenAmount(1_234_567, { ...usdc, displayHint: '0.000 USDC' })).to.equal('1.235 USDC');
+            expect(formatTokenAmount(2_000_000, { ...usdc, displayHint: '€0' })).to.equal('€2');
+        });
+
+        it('Should fall back to full precision and the symbol without a hint', () => {
+            expect(formatTokenAmount(666_667, { decimals: 9, symbol: 'SOL', displayHint: '' })).to.equal('0.000666667 SOL');
+            expect(formatTokenAmount(10, { decimals: 1, symbol: '', displayHint: '' })).to.equal('1.0');
+        });
+    });
+
+    describe('External Addresses', () => {
+        it('Should parse an EVM address with or without a 0x prefix', () => {
+            const hex = '742d35Cc6634C0532925a3b844Bc454e4438f44e';
+            const address = externalAddressFromHex(1, `0x${hex}`);
+            expect(address.chainId).to.equal(1);
+            expect(address.address.length).to.equal(20);
+            expect(Buffer.from(address.address).toString('hex')).to.equal(hex.toLowerCase());
+            expect(externalAddressFromHex(1, hex)).to.deep.equal(address);
+        });
+
+        it('Should reject malformed or oversized addresses', () => {
+            expect(() => externalAddressFromHex(1, '0x')).to.throw('Invalid address hex');
+            expect(() => externalAddressFromHex(1, '0xabc')).to.throw('Invalid address hex');
+            expect(() => externalAddressFromHex(1, 'zz')).to.throw('Invalid address hex');
+            expect(() => externalAddressFromHex(1, 'ab'.repeat(MAX_EXTERNAL_ADDRESS_LEN + 1))).to.throw('exceeds');
+        });
+    });
+
+    describe('Prepared Batches', () => {
+        const mailIds = Array.from({ length: 100 }, (_, i) => `QmPreparedMailIdentifier${i.toString().padStart(4, '0')}`);
+
+        it('Should round-trip mail IDs through a compressed batch', async () => {
+            const batch = await encodePreparedBatch(mailIds);
+            expect(batch.count).to.equal(mailIds.length);
+            expect(batch.idsHash).to.have.length(32);
+            expect(batch.payload.subarray(0, 4)).to.deep.equal(Buffer.from([0x28, 0xb5, 0x2f, 0xfd]));
+            // Compression is what lets a batch fit in one transaction
+            expect(batch.payload.length).to.be.lessThan(Buffer.from(mailIds.join('\n')).length / 4);
+
+            expect(await decodePreparedBatch(batch)).to.deep.equal(mailIds);
+        });
+
+        it('Should reject a payload that does not match its hash or count', async () => {
+            const batch = await encodePreparedBatch(mailIds);
+            const other = await encodePreparedBatch(mailIds.slice(1));
+
+            try {
+                await decodePreparedBatch({ ...batch, payload: other.payload });
+                expect.fail('Should have thrown');
+            } catch (error) {
+                expect((error as Error).message).to.include('does not match its hash');
+            }
+
+            try {
+                await decodePreparedBatch({ ...batch, count: batch.count + 1 });
+                expect.fail('Should have thrown');
+            } catch (error) {
+                expect((error as Error).message).to.include('declares');
+            }
+        });
+
+        it('Should refuse empty, oversized and newline-containing batches', async () => {
+            for (const ids of [[], new Array(MAX_PREPARED_BATCH + 1).fill('id'), ['a\nb']]) {
+                try {
+                    await encodePreparedBatch(ids);
+                    expect.fail('Should have thrown');
+                } catch (error) {
+                    expect((error as Error).message).to.not.include('Should have thrown');
+                }
+            }
+        });
+    });
+
+    describe('Proof-of-Work', () => {
+        it('Should count leading zero bits across bytes', () => {
+            const hash = Buffer.alloc(32, 0xff);
+            expect(leadingZeroBits(hash)).to.equal(0);
+            hash[0] = 0;
+            hash[1] = 0b0001_0000;
+            expect(leadingZeroBits(hash)).to.equal(11);
+            expect(leadingZeroBits(Buffer.alloc(32))).to.equal(256);
+        });
+
+        it('Should find a nonce bound to the sender, counter and message', () => {
+            const sender = Keypair.generate().publicKey;
+            const nonce = solvePow(sender, 0n, 'Hi', 'Body', 12);
+            expect(leadingZeroBits(powChallengeHash(sender, 0n, 'Hi', 'Body', nonce))).to.be.at.least(12);
+
+            const reused = [
+                powChallengeHash(sender, 1n, 'Hi', 'Body', nonce),
+                powChallengeHash(sender, 0n, 'H', 'iBody', nonce),
+            ];
+            expect(reused.some((hash) => leadingZeroBits(hash) < 12)).to.be.true;
+        });
+
+        it('Should refuse difficulties outside the program cap', () => {
+            const sender = Keypair.generate().publicKey;
+            expect(() => solvePow(sender, 0n, 'Hi', 'Body', 0)).to.throw('difficulty');
+            expect(() => solvePow(sender, 0n, 'Hi', 'Body', 33)).to.throw('difficulty');
+        });
+    });
+
+    describe('Message Bodies', () => {
+        it('Should return bodies emitted in full', () => {
+            expect(resolveEventBody({ body: 'Hello', bodyHash: null, bodyLen: 5 })).to.equal('Hello');
+        });
+
+        it('Should verify hashed bodies against the instruction data', () => {
+            const body = 'x'.repeat(BODY_HASH_THRESHOLD + 1);
+            const event = {
+                body: '',
+                bodyHash: Array.from(createHash('sha256').update(body).digest()),
+                bodyLen: body.length,
+            };
+            expect(resolveEventBody(event)).to.be.null;
+            expect(resolveEventBody(event, body)).to.equal(body);
+            expect(resolveEventBody(event, body.slice(1) + 'y')).to.be.null;
+        });
+    });
+
+    describe('Message IDs', () => {
+        it('Should derive a distinct ID per sender, sequence number and slot', () => {
+            const sender = Keypair.generate().publicKey;
+            const id = computeMessageId(sender, 7n, 1000n);
+            expect(id.length).to.equal(32);
+            expect(computeMessageId(sender, 7n, 1000n).equals(id)).to.be.true;
+
+            const others = [
+                computeMessageId(Keypair.generate().publicKey, 7n, 1000n),
+                computeMessageId(sender, 8n, 1000n),
+                computeMessageId(sender, 7n, 1001n),
+            ];
+            expect(others.every((other) => !other.equals(id))).to.be.true;
+        });
+    });
+
+    describe('Message Log', () => {
+        it('Should derive leaves committing to the ID, parties and content', () => {
+            const from = Keypair.generate().publicKey;
+            const to = Keypair.generate().publicKey;
+            const id = computeMessageId(from, 1n, 1n);
+            const content = computeMessageContentHash('Hi', 'there');
+            const leaf = computeMessageLeaf(id, from, to, content);
+            expect(leaf.length).to.equal(32);
+
+            expect(computeMessageContentHash('Hit', 'here').equals(content)).to.be.false;
+            expect(computeMessageLeaf(id, to, from, content).equals(leaf)).to.be.false;
+        });
+
+        it('Should size trees like the account compression program', () => {
+            expect(messageLogTreeSize(14, 64)).to.equal(31_800);
+            expect(messageLogTreeSize(3, 8)).to.equal(1_304);
+        });
+    });
+
+    describe('Message Roots', () => {
+        const leaves = [1, 2, 3, 4, 5].map((n) => Buffer.alloc(32, n));
+
+        it('Should build proofs that fold into the root', () => {
+            const root = computeMessageRoot(leaves);
+            const fold = (index: number) => {
+                let node = createHash('sha256').update(Buffer.from([0])).update(leaves[index]).digest();
+                for (const sibling of messageRootProof(leaves, index)) {
+                    const [left, right] = Buffer.compare(node, sibling) <= 0 ? [node, sibling] : [sibling, node];
+                    node = createHash('sha256').update(Buffer.from([1])).update(left).update(right).digest();
+                }
+                return node;
+            };
+            for (let i = 0; i < leaves.length; i++) {
+                expect(fold(i).equals(root)).to.be.true;
+            }
+            // The unpaired fifth leaf skips the levels it has no sibling on
+            expect(messageRootProof(leaves, 4)).to.have.length(1);
+            expect(() => messageRootProof(leaves, 5)).to.throw('out of range');
+        });
+
+        it('Should number days from UTC midnight', () => {
+            expect(messageRootDay(86_399)).to.equal(0);
+            expect(messageRootDay(86_400)).to.equal(1);
+        });
+    });
+
+    describe('Sealed Envelopes', () => {
+        const sender = Keypair.generate();
+        const to = Keypair.generate().publicKey;
+        const recipientKeys = generateEnvelopeKeyPair();
+
+        it('should reveal the true sender only to the recipient', () => {
+            const envelope = sealEnvelope(sender, to, 'sealed-mail', recipientKeys.publicKey);
+            expect(envelope.length).to.be.at.most(MAX_SEALED_ENVELOPE_LEN);
+            expect(openEnvelope(envelope, to, 'sealed-mail', recipientKeys).equals(sender.publicKey)).to.be.true;
+
+            expect(() => openEnvelope(envelope, to, 'sealed-mail', generateEnvelopeKeyPair())).to.throw();
+        });
+
+        it('should reject an envelope replayed onto another message', () => {
+            const envelope = sealEnvelope(sender, to, 'sealed-mail', recipientKeys.publicKey);
+            expect(() => openEnvelope(envelope, to, 'other-mail', recipientKeys)).to.throw('signature');
+        });
+    });
+
+    describe('CPI Events', () => {
+        it('Should decode only tagged inner instructions of the program', () => {
+            const programId = Keypair.generate().publicKey;
+            const other = Keypair.generate().publicKey;
+            const tagged = (payload: string) =>
+                utils.bytes.bs58.encode(Buffer.concat([EVENT_IX_TAG, Buffer.from(payload)]));
+            const tx = {
+                transaction: { message: { getAccountKeys: () => ({ get: (i: number) => [other, programId][i] }) } },
+                meta: {
+                    innerInstructions: [{
+                        index: 0,
+                        instructions: [
+                            { programIdIndex: 1, accounts: [], data: tagged('sent') },
+                            { programIdIndex: 0, accounts: [], data: tagged('foreign') },
+                            { programIdIndex: 1, accounts: [], data: utils.bytes.bs58.encode(Buffer.from('plain instruction')) },
+                        ],
+                    }],
+                },
+            } as any;
+            const program = {
+                programId,
+                coder: {
+                    events: {
+                        decode: (data: string) => ({ name: 'standardMailSent', data: Buffer.from(data, 'base64').toString() }),
+                    },
+                },
+            };
+
+            expect(decodeCpiEvents(program, tx)).to.deep.equal([{ name: 'standardMailSent', data: 'sent' }]);
+        });
+    });
+
+    describe('Solana Pay Transaction Requests', () => {
+        it('Should round-trip transaction request URLs', () => {
+            const plain = encodeTransactionRequestUrl('https://mail.example.com/api/pay/claim');
+            expect(plain).to.equal('solana:https://mail.example.com/api/pay/claim');
+            expect(parseTransactionRequestUrl(plain).toString()).to.equal('https://mail.example.com/api/pay/claim');
+
+            // Query parameters are percent-encoded so wallets do not treat them as request fields
+            const withQuery = encodeTransactionRequestUrl('https://mail.example.com/api/pay/send?priority=true');
+            expect(withQuery).to.not.include('?');
+            expect(parseTransactionRequestUrl(withQuery).searchParams.get('priority')).to.equal('true');
+        });
+
+        it('Should reject non-HTTPS links', () => {
+            expect(() => encodeTransactionRequestUrl('http://mail.example.com/api/pay')).to.throw('HTTPS');
+            expect(() => parseTransactionRequestUrl('https://mail.example.com')).to.throw('Not a Solana Pay URL');
+        });
+
+        it('Should read the wallet account from a request body', () => {
+            const account = Keypair.generate().publicKey;
+            expect(parseTransactionRequestAccount({ account: account.toBase58() }).equals(account)).to.be.true;
+            expect(() => parseTransactionRequestAccount({})).to.throw('account');
+        });
+
+        it('Should serialize an unsigned transaction for the wallet', () => {
+            const payer = Keypair.generate().publicKey;
+            const tx = new Transaction({
+                feePayer: payer,
+                blockhash: Keypair.generate().publicKey.toBase58(),
+                lastValidBlockHeight: 0,
+            }).add(SystemProgram.transfer({ fromPubkey: payer, toPubkey: payer, lamports: 1 }));
+
+            const response = createTransactionRequestResponse(tx, 'Claim your revenue share');
+            expect(response.message).to.equal('Claim your revenue share');
+            const decoded = Transaction.from(Buffer.from(response.transaction, 'base64'));
+            expect(decoded.feePayer!.equals(payer)).to.be.true;
+            expect(decoded.instructions).to.have.length(1);
+            expect(createTransactionRequestResponse(tx)).to.not.have.property('message');
+        });
+
+        it('Should render a QR code as SVG', async () => {
+            const svg = await createTransactionRequestQR('solana:https://mail.example.com/api/pay/claim');
+            expect(svg).to.include('<svg');
+        });
+    });
+});